@@ -0,0 +1,137 @@
+//! Compara, con 10 mil cámaras, el recorrido de todas las cámaras contra el índice espacial:
+//! al buscar las cámaras que cubren un incidente, y al calcular las lindantes al cargar las cámaras.
+//!
+//! `cargo bench --bench camera_index`
+
+use std::{
+    collections::HashMap,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rustx::apps::{
+    geo,
+    sist_camaras::{
+        bordering::{add_bordering_cameras, BorderingRules},
+        camera::{Camera, DEFAULT_BORDER_RANGE_METERS},
+        camera_index::CameraGridIndex,
+    },
+};
+
+const CAMERAS: u32 = 10_000;
+const INCIDENTS: usize = 2_000;
+// Las cámaras se reparten en un cuadrado de 20 km de lado alrededor del obelisco, como en una ciudad
+const CITY_CENTER: (f64, f64) = (-34.6037344, -58.3861838);
+const CITY_RADIUS_METERS: f64 = 10_000.0;
+
+fn random_position(rng: &mut StdRng) -> (f64, f64) {
+    let north = geo::destination_point(
+        CITY_CENTER,
+        0.0,
+        rng.gen_range(-CITY_RADIUS_METERS..CITY_RADIUS_METERS),
+    );
+    geo::destination_point(
+        north,
+        90.0,
+        rng.gen_range(-CITY_RADIUS_METERS..CITY_RADIUS_METERS),
+    )
+}
+
+fn create_cameras(rng: &mut StdRng) -> Vec<Camera> {
+    (0..CAMERAS)
+        .map(|id| {
+            let (lat, lon) = random_position(rng);
+            Camera::new(id, lat, lon, rng.gen_range(150.0..300.0))
+        })
+        .collect()
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+fn report(what: &str, scan: Duration, index: Duration) {
+    println!(
+        "{}: recorriendo todas {:?}, con el índice {:?} ({:.0}x)",
+        what,
+        scan,
+        index,
+        scan.as_secs_f64() / index.as_secs_f64()
+    );
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(44);
+    let cameras = create_cameras(&mut rng);
+    let incidents: Vec<(f64, f64)> = (0..INCIDENTS).map(|_| random_position(&mut rng)).collect();
+
+    // Lindantes al cargar: cada cámara contra todas las ya cargadas, o contra las cercanas según el índice
+    let (scan_loaded, scan_time) = time(|| {
+        let mut loaded: HashMap<u32, Camera> = HashMap::new();
+        for camera in &cameras {
+            let mut new_camera = camera.clone();
+            for loaded_camera in loaded.values_mut() {
+                loaded_camera.mutually_add_if_bordering(&mut new_camera, DEFAULT_BORDER_RANGE_METERS);
+            }
+            loaded.insert(new_camera.get_id(), new_camera);
+        }
+        loaded
+    });
+    let (mut index_loaded, index_time) = time(|| {
+        let mut loaded: HashMap<u32, Camera> = HashMap::new();
+        let mut index = CameraGridIndex::default();
+        let rules = BorderingRules::default();
+        for camera in &cameras {
+            let mut new_camera = camera.clone();
+            add_bordering_cameras(&mut loaded, &index, &rules, &mut new_camera);
+            index.insert(&new_camera);
+            loaded.insert(new_camera.get_id(), new_camera);
+        }
+        loaded
+    });
+    report("Lindantes de 10 mil cámaras", scan_time, index_time);
+    for (id, mut camera) in scan_loaded {
+        let mut expected = camera.get_bordering_cams().clone();
+        let mut found = index_loaded
+            .get_mut(&id)
+            .unwrap()
+            .get_bordering_cams()
+            .clone();
+        expected.sort();
+        found.sort();
+        assert_eq!(expected, found, "Lindantes distintas para la cámara {}", id);
+    }
+
+    // Cámaras que cubren cada incidente
+    let cameras_by_id: HashMap<u32, Camera> =
+        cameras.iter().map(|c| (c.get_id(), c.clone())).collect();
+    let index = CameraGridIndex::from_cameras(&cameras_by_id);
+    let (scan_matches, scan_time) = time(|| {
+        incidents
+            .iter()
+            .map(|position| {
+                black_box(&cameras)
+                    .iter()
+                    .filter(|camera| camera.will_register(*position))
+                    .count()
+            })
+            .sum::<usize>()
+    });
+    let (index_matches, index_time) = time(|| {
+        incidents
+            .iter()
+            .map(|position| {
+                black_box(&index)
+                    .candidates_covering(*position)
+                    .iter()
+                    .filter(|id| cameras_by_id[id].will_register(*position))
+                    .count()
+            })
+            .sum::<usize>()
+    });
+    report("Cámaras que cubren 2 mil incidentes", scan_time, index_time);
+    assert_eq!(scan_matches, index_matches);
+}
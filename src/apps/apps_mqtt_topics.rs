@@ -1,13 +1,35 @@
 use std::io::Error;
 
+use crate::apps::{
+    incident_data::{
+        active_incidents::{ActiveIncidentsReply, ActiveIncidentsRequest},
+        incident::Incident,
+    },
+    sist_camaras::{
+        camera::{Camera, CameraSystemId, DEFAULT_CAMERA_SYSTEM_ID},
+        camera_command::{CameraCommandReply, CameraCommandRequest},
+    },
+    sist_dron::dron_current_info::DronCurrentInfo,
+};
+use crate::mqtt::{
+    client::typed_topic::Topic, mqtt_utils::will_message_utils::will_content::WillContent,
+};
+
 #[derive(Debug)]
 pub enum AppsMqttTopics {
     IncidentTopic,
     DronTopic,
     CameraTopic,
     DescTopic,
+    CameraCommandTopic,
+    ActiveIncidentsTopic,
 }
 
+/// Prefijo de los topics donde se responden los comandos de cámaras, seguido del id de quien los pide.
+const CAMERA_COMMAND_REPLIES_PREFIX: &str = "cam/cmd/respuestas/";
+/// Prefijo de los topics donde se responden los pedidos de incidentes activos, seguido del id de quien los pide.
+const ACTIVE_INCIDENTS_REPLIES_PREFIX: &str = "inc/activos/respuestas/";
+
 impl AppsMqttTopics {
     pub fn to_str(&self) -> &str {
         match self {
@@ -15,6 +37,8 @@ impl AppsMqttTopics {
             AppsMqttTopics::DronTopic => "dron",
             AppsMqttTopics::CameraTopic => "cam",
             AppsMqttTopics::DescTopic => "desc",
+            AppsMqttTopics::CameraCommandTopic => "cam/cmd",
+            AppsMqttTopics::ActiveIncidentsTopic => "inc/activos",
         }
     }
 
@@ -24,8 +48,82 @@ impl AppsMqttTopics {
             "dron" => Ok(AppsMqttTopics::DronTopic),
             "cam" => Ok(AppsMqttTopics::CameraTopic),
             "desc" => Ok(AppsMqttTopics::DescTopic),
+            "cam/cmd" => Ok(AppsMqttTopics::CameraCommandTopic),
+            "inc/activos" => Ok(AppsMqttTopics::ActiveIncidentsTopic),
             _ => Err(Error::new(std::io::ErrorKind::InvalidInput, "Error: string inválida para crea un enum AppsMqttTopics."))
 
         }
     }
+
+    /// Topic de los incidentes, con su payload tipado.
+    pub fn incidents() -> Topic<Incident> {
+        Topic::new(Self::IncidentTopic.to_str())
+    }
+
+    /// Topic de la current info de los drones, con su payload tipado.
+    pub fn drones() -> Topic<DronCurrentInfo> {
+        Topic::new(Self::DronTopic.to_str())
+    }
+
+    /// Topic de las cámaras, con su payload tipado.
+    pub fn cameras() -> Topic<Camera> {
+        Topic::new(Self::CameraTopic.to_str())
+    }
+
+    /// Topic de las desconexiones (will messages de las apps), con su payload tipado.
+    pub fn disconnections() -> Topic<WillContent> {
+        Topic::new(Self::DescTopic.to_str())
+    }
+
+    /// Topic de los comandos para administrar las cámaras de sistema cámaras, con su payload tipado.
+    pub fn camera_commands() -> Topic<CameraCommandRequest> {
+        Self::camera_commands_of(DEFAULT_CAMERA_SYSTEM_ID)
+    }
+
+    /// Topic de los comandos para administrar las cámaras del sistema `system_id`: `cam/cmd/<system_id>`,
+    /// o `cam/cmd` para el sistema por defecto.
+    pub fn camera_commands_of(system_id: CameraSystemId) -> Topic<CameraCommandRequest> {
+        if system_id == DEFAULT_CAMERA_SYSTEM_ID {
+            Topic::new(Self::CameraCommandTopic.to_str())
+        } else {
+            Topic::new(&format!("{}/{}", Self::CameraCommandTopic.to_str(), system_id))
+        }
+    }
+
+    /// Devuelve si `topic` es el topic de comandos de cámaras de algún sistema.
+    pub fn is_camera_commands(topic: &str) -> bool {
+        match topic.strip_prefix(Self::CameraCommandTopic.to_str()) {
+            Some("") => true,
+            Some(rest) => rest
+                .strip_prefix('/')
+                .is_some_and(|system_id| system_id.parse::<CameraSystemId>().is_ok()),
+            None => false,
+        }
+    }
+
+    /// Topic donde `client_id` recibe las respuestas a sus comandos de cámaras. Es el `reply_topic` a indicar
+    /// en los pedidos (aunque sistema cámaras responde en el que se indique).
+    pub fn camera_command_replies(client_id: &str) -> Topic<CameraCommandReply> {
+        Topic::new(&format!("{}{}", CAMERA_COMMAND_REPLIES_PREFIX, client_id))
+    }
+
+    /// Devuelve si `topic` es uno de los topics de respuestas a comandos de cámaras.
+    pub fn is_camera_command_replies(topic: &str) -> bool {
+        topic.starts_with(CAMERA_COMMAND_REPLIES_PREFIX)
+    }
+
+    /// Topic de los pedidos de incidentes activos, que responde sistema monitoreo, con su payload tipado.
+    pub fn active_incidents() -> Topic<ActiveIncidentsRequest> {
+        Topic::new(Self::ActiveIncidentsTopic.to_str())
+    }
+
+    /// Topic donde `client_id` recibe las respuestas a sus pedidos de incidentes activos.
+    pub fn active_incidents_replies(client_id: &str) -> Topic<ActiveIncidentsReply> {
+        Topic::new(&format!("{}{}", ACTIVE_INCIDENTS_REPLIES_PREFIX, client_id))
+    }
+
+    /// Devuelve si `topic` es uno de los topics de respuestas a pedidos de incidentes activos.
+    pub fn is_active_incidents_replies(topic: &str) -> bool {
+        topic.starts_with(ACTIVE_INCIDENTS_REPLIES_PREFIX)
+    }
 }
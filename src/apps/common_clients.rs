@@ -11,7 +11,10 @@ use super::apps_mqtt_topics::AppsMqttTopics;
 
 /// Lee el IP del cliente y el puerto en el que el cliente se va a conectar al servidor.
 fn load_ip_and_port() -> Result<(String, u16), Box<Error>> {
-    let argv = std::env::args().collect::<Vec<String>>();
+    // Las opciones (ej `--daemon`) las lee cada app, no son parte de la dirección
+    let argv = std::env::args()
+        .filter(|arg| !arg.starts_with("--"))
+        .collect::<Vec<String>>();
     if argv.len() != 3 {
         return Err(Box::new(std::io::Error::new(
             std::io::ErrorKind::InvalidInput,
@@ -43,6 +46,20 @@ pub fn get_broker_address() -> SocketAddr {
     broker_addr.parse().expect("Dirección no válida")
 }
 
+/// Devuelve si la app se ejecutó con la opción `option` (ej `--daemon`).
+pub fn has_option(option: &str) -> bool {
+    std::env::args().any(|arg| arg == option)
+}
+
+/// Devuelve el valor de la opción `option` si la app se ejecutó con `option=valor` (ej `--system-id=2`).
+pub fn get_option_value(option: &str) -> Option<String> {
+    std::env::args().find_map(|arg| {
+        arg.strip_prefix(option)
+            .and_then(|rest| rest.strip_prefix('='))
+            .map(String::from)
+    })
+}
+
 pub fn get_app_will_topic() -> String {
     let will_topic = AppsMqttTopics::DescTopic.to_str();
     String::from(will_topic)
@@ -0,0 +1,216 @@
+// Cálculos geodésicos sobre posiciones `(latitud, longitud)` en grados. Las distancias son en metros,
+// y los rumbos en grados, medidos desde el norte en sentido horario.
+
+/// Radio medio de la Tierra, en metros.
+pub const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+
+/// Metros que mide un grado de latitud, sobre la esfera de radio `EARTH_RADIUS_METERS`.
+pub const METERS_PER_DEGREE_OF_LATITUDE: f64 = EARTH_RADIUS_METERS * std::f64::consts::PI / 180.0;
+
+// Elipsoide WGS84, usado por Vincenty.
+const WGS84_SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+const WGS84_FLATTENING: f64 = 1.0 / 298.257_223_563;
+const VINCENTY_MAX_ITERATIONS: usize = 200;
+const VINCENTY_TOLERANCE: f64 = 1e-12;
+
+/// Distancia en metros entre `a` y `b`. Es la que usan las apps, con la fórmula de Haversine.
+pub fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    haversine_distance(a, b)
+}
+
+/// Distancia en metros entre `a` y `b` sobre una esfera (fórmula de Haversine).
+/// El error respecto del elipsoide es menor al 0.5%, de sobra para las distancias de las apps.
+pub fn haversine_distance((lat_a, lon_a): (f64, f64), (lat_b, lon_b): (f64, f64)) -> f64 {
+    let (phi_a, phi_b) = (lat_a.to_radians(), lat_b.to_radians());
+    let d_phi = (lat_b - lat_a).to_radians();
+    let d_lambda = (lon_b - lon_a).to_radians();
+
+    let h =
+        (d_phi / 2.0).sin().powi(2) + phi_a.cos() * phi_b.cos() * (d_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * h.sqrt().min(1.0).asin()
+}
+
+/// Distancia en metros entre `a` y `b` sobre el elipsoide WGS84 (fórmula inversa de Vincenty).
+/// Si no converge, lo que puede pasar con puntos casi antípodas, devuelve la de Haversine.
+pub fn vincenty_distance((lat_a, lon_a): (f64, f64), (lat_b, lon_b): (f64, f64)) -> f64 {
+    let f = WGS84_FLATTENING;
+    let a = WGS84_SEMI_MAJOR_AXIS;
+    let b = a * (1.0 - f);
+
+    let l = (lon_b - lon_a).to_radians();
+    let u1 = ((1.0 - f) * lat_a.to_radians().tan()).atan();
+    let u2 = ((1.0 - f) * lat_b.to_radians().tan()).atan();
+    let (sin_u1, cos_u1) = u1.sin_cos();
+    let (sin_u2, cos_u2) = u2.sin_cos();
+
+    let mut lambda = l;
+    for _ in 0..VINCENTY_MAX_ITERATIONS {
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        let sin_sigma = ((cos_u2 * sin_lambda).powi(2)
+            + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2))
+        .sqrt();
+        if sin_sigma == 0.0 {
+            return 0.0; // Son el mismo punto
+        }
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        let cos_sq_alpha = 1.0 - sin_alpha.powi(2);
+        // Sobre el ecuador cos_sq_alpha es 0
+        let cos_2_sigma_m = if cos_sq_alpha == 0.0 {
+            0.0
+        } else {
+            cos_sigma - 2.0 * sin_u1 * sin_u2 / cos_sq_alpha
+        };
+        let c = f / 16.0 * cos_sq_alpha * (4.0 + f * (4.0 - 3.0 * cos_sq_alpha));
+        let previous_lambda = lambda;
+        lambda = l
+            + (1.0 - c)
+                * f
+                * sin_alpha
+                * (sigma
+                    + c * sin_sigma
+                        * (cos_2_sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2_sigma_m.powi(2))));
+
+        if (lambda - previous_lambda).abs() < VINCENTY_TOLERANCE {
+            let u_sq = cos_sq_alpha * (a.powi(2) - b.powi(2)) / b.powi(2);
+            let big_a =
+                1.0 + u_sq / 16384.0 * (4096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)));
+            let big_b = u_sq / 1024.0 * (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+            let delta_sigma = big_b
+                * sin_sigma
+                * (cos_2_sigma_m
+                    + big_b / 4.0
+                        * (cos_sigma * (-1.0 + 2.0 * cos_2_sigma_m.powi(2))
+                            - big_b / 6.0
+                                * cos_2_sigma_m
+                                * (-3.0 + 4.0 * sin_sigma.powi(2))
+                                * (-3.0 + 4.0 * cos_2_sigma_m.powi(2))));
+            return b * big_a * (sigma - delta_sigma);
+        }
+    }
+
+    haversine_distance((lat_a, lon_a), (lat_b, lon_b))
+}
+
+/// Rumbo inicial, en grados en `[0, 360)`, para ir desde `origin` hasta `destination` por el camino más corto.
+pub fn initial_bearing((lat_a, lon_a): (f64, f64), (lat_b, lon_b): (f64, f64)) -> f64 {
+    let (phi_a, phi_b) = (lat_a.to_radians(), lat_b.to_radians());
+    let d_lambda = (lon_b - lon_a).to_radians();
+
+    let y = d_lambda.sin() * phi_b.cos();
+    let x = phi_a.cos() * phi_b.sin() - phi_a.sin() * phi_b.cos() * d_lambda.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// Posición a la que se llega desde `origin` recorriendo `distance_meters` con rumbo inicial `bearing_degrees`.
+pub fn destination_point(
+    (lat, lon): (f64, f64),
+    bearing_degrees: f64,
+    distance_meters: f64,
+) -> (f64, f64) {
+    let phi = lat.to_radians();
+    let lambda = lon.to_radians();
+    let theta = bearing_degrees.to_radians();
+    let delta = distance_meters / EARTH_RADIUS_METERS;
+
+    let dest_phi = (phi.sin() * delta.cos() + phi.cos() * delta.sin() * theta.cos()).asin();
+    let dest_lambda = lambda
+        + (theta.sin() * delta.sin() * phi.cos()).atan2(delta.cos() - phi.sin() * dest_phi.sin());
+
+    // Normaliza la longitud a [-180, 180)
+    let dest_lon = (dest_lambda.to_degrees() + 540.0).rem_euclid(360.0) - 180.0;
+    (dest_phi.to_degrees(), dest_lon)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        destination_point, distance, initial_bearing, vincenty_distance,
+        METERS_PER_DEGREE_OF_LATITUDE,
+    };
+
+    const OBELISCO: (f64, f64) = (-34.6037344, -58.3861838);
+
+    fn assert_close(value: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (value - expected).abs() <= tolerance,
+            "{} no está a menos de {} de {}",
+            value,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_1_un_grado_de_latitud_y_de_longitud_miden_lo_esperado() {
+        assert_close(
+            distance((0.0, 0.0), (1.0, 0.0)),
+            METERS_PER_DEGREE_OF_LATITUDE,
+            1e-6,
+        );
+        // Un grado de longitud se achica con la latitud: en Buenos Aires mide un 82% de lo que mide en el ecuador
+        let one_degree_of_longitude = distance(OBELISCO, (OBELISCO.0, OBELISCO.1 + 1.0));
+        assert_close(
+            one_degree_of_longitude,
+            METERS_PER_DEGREE_OF_LATITUDE * OBELISCO.0.to_radians().cos(),
+            10.0,
+        );
+    }
+
+    #[test]
+    fn test_2_vincenty_da_las_distancias_del_elipsoide() {
+        // Sobre el ecuador, un grado mide lo mismo que el arco del semieje mayor
+        assert_close(vincenty_distance((0.0, 0.0), (0.0, 1.0)), 111_319.491, 0.01);
+        assert_eq!(vincenty_distance(OBELISCO, OBELISCO), 0.0);
+
+        // Para distancias de la ciudad, coincide con Haversine en menos de un 0.5%
+        let plaza_de_mayo = (-34.6083, -58.3712);
+        let haversine = distance(OBELISCO, plaza_de_mayo);
+        assert_close(
+            vincenty_distance(OBELISCO, plaza_de_mayo),
+            haversine,
+            haversine * 0.005,
+        );
+    }
+
+    #[test]
+    fn test_3_el_rumbo_apunta_a_los_puntos_cardinales() {
+        assert_close(
+            initial_bearing(OBELISCO, (OBELISCO.0 + 0.01, OBELISCO.1)),
+            0.0,
+            1e-9,
+        );
+        assert_close(
+            initial_bearing(OBELISCO, (OBELISCO.0, OBELISCO.1 + 0.01)),
+            90.0,
+            0.01,
+        );
+        assert_close(
+            initial_bearing(OBELISCO, (OBELISCO.0 - 0.01, OBELISCO.1)),
+            180.0,
+            1e-9,
+        );
+        assert_close(
+            initial_bearing(OBELISCO, (OBELISCO.0, OBELISCO.1 - 0.01)),
+            270.0,
+            0.01,
+        );
+    }
+
+    #[test]
+    fn test_4_el_punto_de_destino_esta_a_la_distancia_y_con_el_rumbo_pedidos() {
+        for bearing in [0.0, 45.0, 135.0, 200.0, 315.0] {
+            let destination = destination_point(OBELISCO, bearing, 750.0);
+            assert_close(distance(OBELISCO, destination), 750.0, 1e-6);
+            // Diferencia entre rumbos, en (-180, 180], ya que 359.99... está junto a 0
+            let bearing_difference = (initial_bearing(OBELISCO, destination) - bearing + 540.0)
+                .rem_euclid(360.0)
+                - 180.0;
+            assert_close(bearing_difference, 0.0, 1e-6);
+        }
+        // Cruza el antimeridiano normalizando la longitud
+        let (_, lon) = destination_point((0.0, 179.9999), 90.0, 100.0);
+        assert!(lon < -179.0);
+    }
+}
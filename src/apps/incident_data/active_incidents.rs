@@ -0,0 +1,169 @@
+use std::{collections::HashMap, io::Error};
+
+use serde::{Deserialize, Serialize};
+
+use crate::apps::payload_codec::{
+    envelope::PayloadSchema,
+    versioned_payload::{no_legacy_format_error, VersionedPayload},
+};
+
+use super::{incident::Incident, incident_info::IncidentInfo};
+
+/// Incidentes activos, según los publicados en el topic de incidentes: cada incidente se agrega al recibirlo,
+/// y se quita al recibirlo resuelto.
+#[derive(Debug, Default, Clone)]
+pub struct ActiveIncidents {
+    incidents: HashMap<IncidentInfo, Incident>,
+}
+
+impl ActiveIncidents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registra el `incident` recibido: si está resuelto deja de estar activo, si no se guarda (o se actualiza).
+    pub fn update(&mut self, incident: &Incident) {
+        if incident.is_resolved() {
+            self.incidents.remove(&incident.get_info());
+        } else {
+            self.incidents.insert(incident.get_info(), incident.clone());
+        }
+    }
+
+    /// Devuelve si el incidente `info` está activo.
+    pub fn contains(&self, info: &IncidentInfo) -> bool {
+        self.incidents.contains_key(info)
+    }
+
+    /// Devuelve los incidentes activos.
+    pub fn to_vec(&self) -> Vec<Incident> {
+        self.incidents.values().cloned().collect()
+    }
+}
+
+/// Pedido de los incidentes activos, que se publica en el topic de incidentes activos (ie al iniciar sistema
+/// cámaras, para recuperar los incidentes que se publicaron mientras no estaba). La respuesta se publica en
+/// `reply_topic`, con el mismo `correlation_id`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActiveIncidentsRequest {
+    correlation_id: String,
+    reply_topic: String,
+}
+
+impl ActiveIncidentsRequest {
+    pub fn new(correlation_id: &str, reply_topic: &str) -> Self {
+        Self {
+            correlation_id: correlation_id.to_string(),
+            reply_topic: reply_topic.to_string(),
+        }
+    }
+
+    pub fn get_correlation_id(&self) -> &str {
+        &self.correlation_id
+    }
+
+    pub fn get_reply_topic(&self) -> &str {
+        &self.reply_topic
+    }
+}
+
+/// Respuesta a un `ActiveIncidentsRequest`: los incidentes activos al momento de recibir el pedido.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveIncidentsReply {
+    correlation_id: String,
+    incidents: Vec<Incident>,
+}
+
+impl ActiveIncidentsReply {
+    pub fn new(correlation_id: &str, incidents: Vec<Incident>) -> Self {
+        Self {
+            correlation_id: correlation_id.to_string(),
+            incidents,
+        }
+    }
+
+    pub fn get_correlation_id(&self) -> &str {
+        &self.correlation_id
+    }
+
+    pub fn get_incidents(&self) -> &[Incident] {
+        &self.incidents
+    }
+
+    /// Devuelve los incidentes, consumiendo la respuesta.
+    pub fn into_incidents(self) -> Vec<Incident> {
+        self.incidents
+    }
+}
+
+impl VersionedPayload for ActiveIncidentsRequest {
+    const SCHEMA: PayloadSchema = PayloadSchema::ActiveIncidentsRequest;
+
+    fn to_legacy_bytes(&self) -> Result<Vec<u8>, Error> {
+        Err(no_legacy_format_error(Self::SCHEMA))
+    }
+
+    fn from_legacy_bytes(_bytes: &[u8]) -> Result<Self, Error> {
+        Err(no_legacy_format_error(Self::SCHEMA))
+    }
+}
+
+impl VersionedPayload for ActiveIncidentsReply {
+    const SCHEMA: PayloadSchema = PayloadSchema::ActiveIncidentsReply;
+
+    fn to_legacy_bytes(&self) -> Result<Vec<u8>, Error> {
+        Err(no_legacy_format_error(Self::SCHEMA))
+    }
+
+    fn from_legacy_bytes(_bytes: &[u8]) -> Result<Self, Error> {
+        Err(no_legacy_format_error(Self::SCHEMA))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ActiveIncidents, ActiveIncidentsReply, ActiveIncidentsRequest};
+    use crate::{
+        apps::incident_data::{incident::Incident, incident_source::IncidentSource},
+        mqtt::client::typed_topic::MqttPayload,
+    };
+
+    #[test]
+    fn test_1_los_incidentes_resueltos_dejan_de_estar_activos() {
+        let mut active = ActiveIncidents::new();
+        let mut incident =
+            Incident::new(Incident::generate_id(), (-34.6, -58.4), IncidentSource::Manual);
+        let other = Incident::new(Incident::generate_id(), (-34.7, -58.5), IncidentSource::Automated);
+
+        active.update(&incident);
+        active.update(&other);
+        active.update(&incident);
+        assert_eq!(active.to_vec().len(), 2);
+
+        incident.set_resolved();
+        active.update(&incident);
+        assert!(!active.contains(&incident.get_info()));
+        assert!(active.contains(&other.get_info()));
+
+        // Un resuelto que no estaba activo no cambia nada
+        active.update(&incident);
+        assert_eq!(active.to_vec().len(), 1);
+    }
+
+    #[test]
+    fn test_2_pedido_y_respuesta_se_codifican_y_decodifican() {
+        let request = ActiveIncidentsRequest::new("pedido-1", "inc/activos/respuestas/Sistema-Camaras");
+        let decoded = ActiveIncidentsRequest::decode(&request.encode().unwrap()).unwrap();
+        assert_eq!(decoded, request);
+
+        let incident = Incident::new(Incident::generate_id(), (-34.6, -58.4), IncidentSource::Manual);
+        let reply = ActiveIncidentsReply::new("pedido-1", vec![incident.clone()]);
+        let decoded = ActiveIncidentsReply::decode(&reply.encode().unwrap()).unwrap();
+        assert_eq!(decoded.get_correlation_id(), "pedido-1");
+        assert_eq!(decoded.get_incidents().len(), 1);
+        assert_eq!(decoded.get_incidents()[0].get_info(), incident.get_info());
+
+        // Sin envelope no son válidos, ya que no existían antes de él
+        assert!(ActiveIncidentsRequest::decode(b"pedido").is_err());
+    }
+}
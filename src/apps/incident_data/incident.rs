@@ -1,14 +1,22 @@
 use std::io::Error;
 
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::apps::payload_codec::{
+    envelope::{Encoding, PayloadSchema},
+    versioned_payload::{check_legacy_len, narrow_id, unsupported_version_error, VersionedPayload},
+};
+
 use super::incident_info::IncidentInfo;
 use super::incident_state::IncidentState;
 use super::incident_source::IncidentSource;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// Struct que representa un incidente, para ser utilizado por las aplicaciones del sistema de vigilancia (sist de monitoreo, sist central de cámaras, y app de drones).
 /// Posee un id, coordenadas x e y, un estado.
 pub struct Incident {
-    id: u8, // []
+    id: Uuid, // Único entre todas las apps que crean incidentes.
     latitude: f64,
     longitude: f64,
     state: IncidentState,
@@ -16,7 +24,7 @@ pub struct Incident {
 }
 
 impl Incident {
-    pub fn new(id: u8, location: (f64, f64), source: IncidentSource) -> Self {
+    pub fn new(id: Uuid, location: (f64, f64), source: IncidentSource) -> Self {
         Self {
             id,
             latitude: location.0,
@@ -26,6 +34,12 @@ impl Incident {
         }
     }
 
+    /// Genera un id para un incidente nuevo. Es aleatorio, así los sistemas de cámaras y las estaciones de monitoreo
+    /// pueden crear incidentes a la vez sin coordinarse y sin que se repitan los ids.
+    pub fn generate_id() -> Uuid {
+        Uuid::new_v4()
+    }
+
     /// Devuelve coordenadas (x, y) correspondientes a la posición del incidente.
     pub fn get_position(&self) -> (f64, f64) {
         (self.latitude, self.longitude)
@@ -41,25 +55,31 @@ impl Incident {
         self.state = IncidentState::ResolvedIncident;
     }
 
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let mut bytes = vec![self.id];
+    /// Pasa el incidente a bytes con el formato previo al envelope. Devuelve error si el id no entra en un byte.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut bytes = vec![narrow_id(self.id.as_u128(), Self::SCHEMA)?];
         bytes.extend_from_slice(&self.latitude.to_le_bytes());
         bytes.extend_from_slice(&self.longitude.to_le_bytes());
         bytes.push(self.state.to_byte()[0]);
         bytes.push(self.source.to_byte()[0]);
-        bytes
+        Ok(bytes)
     }
 
-    pub fn get_id(&self) -> u8 {
+    pub fn get_id(&self) -> Uuid {
         self.id
     }
 
+    /// Devuelve el comienzo del id, suficiente para mostrarlo en la ui.
+    pub fn get_short_id(&self) -> String {
+        self.id.simple().to_string()[..8].to_string()
+    }
+
     pub fn get_info(&self) -> IncidentInfo {
         IncidentInfo::new(self.id, self.source)
     }
 
     pub fn from_bytes(msg_bytes: Vec<u8>) -> Result<Self, Error> {
-        let id = msg_bytes[0];
+        let id = IncidentInfo::id_from_narrow(msg_bytes[0]);
         let latitude = f64::from_le_bytes([
             msg_bytes[1],
             msg_bytes[2],
@@ -103,27 +123,128 @@ impl Incident {
         &self.source
     }
 }
+
+// id, latitud, longitud, estado y source.
+const INCIDENT_BYTES_LEN: usize = 1 + 8 + 8 + 1 + 1;
+
+/// `Incident` en la versión 1 del envelope, con el id de un byte.
+#[derive(Serialize, Deserialize)]
+struct IncidentV1 {
+    id: u8,
+    latitude: f64,
+    longitude: f64,
+    state: IncidentState,
+    source: IncidentSource,
+}
+
+impl VersionedPayload for Incident {
+    const SCHEMA: PayloadSchema = PayloadSchema::Incident;
+    // La versión 2 pasó el id de un byte a uuid.
+    const CURRENT_VERSION: u8 = 2;
+
+    fn to_legacy_bytes(&self) -> Result<Vec<u8>, Error> {
+        self.to_bytes()
+    }
+
+    fn from_legacy_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        check_legacy_len(bytes, INCIDENT_BYTES_LEN, Self::SCHEMA)?;
+        Self::from_bytes(bytes.to_vec())
+    }
+
+    fn from_older_version(version: u8, body: &[u8], encoding: Encoding) -> Result<Self, Error> {
+        match version {
+            1 => {
+                let v1: IncidentV1 = encoding.deserialize(body)?;
+                Ok(Self {
+                    id: IncidentInfo::id_from_narrow(v1.id),
+                    latitude: v1.latitude,
+                    longitude: v1.longitude,
+                    state: v1.state,
+                    source: v1.source,
+                })
+            }
+            _ => Err(unsupported_version_error(Self::SCHEMA, version)),
+        }
+    }
+
+    fn to_older_version(&self, version: u8, encoding: Encoding) -> Result<Vec<u8>, Error> {
+        match version {
+            1 => encoding.serialize(&IncidentV1 {
+                id: narrow_id(self.id.as_u128(), Self::SCHEMA)?,
+                latitude: self.latitude,
+                longitude: self.longitude,
+                state: self.state.clone(),
+                source: self.source,
+            }),
+            _ => Err(unsupported_version_error(Self::SCHEMA, version)),
+        }
+    }
+}
 // hacer test de los metodos from_bytes y to_bytes
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::apps::payload_codec::{codec::PayloadCodec, envelope::EnvelopeHeader, versioned_payload::decode_payload};
+    use crate::mqtt::client::typed_topic::MqttPayload;
 
     #[test]
     fn test_reverse_to_bytes() {
         let incident = Incident {
-            id: 1,
+            id: IncidentInfo::id_from_narrow(1),
             latitude: 2.0,
             longitude: 2.0,
             state: IncidentState::ActiveIncident,
             source: IncidentSource::Manual,
         };
-        let bytes = incident.to_bytes();
+        let bytes = incident.to_bytes().unwrap();
         let incident_bytes = Incident::from_bytes(bytes).unwrap();
         assert_eq!(incident_bytes.id, incident.id);
         assert_eq!(incident_bytes.latitude, incident.latitude);
         assert_eq!(incident_bytes.longitude, incident.longitude);
         assert_eq!(incident_bytes.state, incident.state);
     }
+
+    #[test]
+    fn test_from_legacy_bytes_de_un_payload_incompleto_da_error() {
+        let incident = Incident::new(IncidentInfo::id_from_narrow(1), (2.0, 2.0), IncidentSource::Manual);
+        let bytes = incident.to_legacy_bytes().unwrap();
+
+        assert_eq!(Incident::from_legacy_bytes(&bytes).unwrap().get_position(), (2.0, 2.0));
+        assert!(Incident::from_legacy_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_se_decodifica_la_version_1_y_no_se_trunca_el_id_al_codificarla() {
+        let incident = Incident::new(IncidentInfo::id_from_narrow(9), (2.0, 3.0), IncidentSource::Automated);
+        let body = incident.to_older_version(1, Encoding::Json).unwrap();
+        let decoded = Incident::from_older_version(1, &body, Encoding::Json).unwrap();
+        assert_eq!((decoded.get_id(), decoded.get_position()), (incident.get_id(), (2.0, 3.0)));
+
+        // Un id generado no entra en la versión 1: configurada como de compatibilidad, se envía en la actual, sin truncarlo.
+        let codec = PayloadCodec::new(Encoding::Bincode);
+        codec.set_compat_version(Some(1)).unwrap();
+        let new_incident = Incident::new(Incident::generate_id(), (2.0, 3.0), IncidentSource::Automated);
+        let bytes = codec.encode(&new_incident).unwrap();
+        let decoded = decode_payload::<Incident>(&bytes).unwrap();
+        assert_eq!((decoded.value.get_id(), decoded.version), (new_incident.get_id(), Incident::CURRENT_VERSION));
+
+        // Un incidente de un id de un byte sí se envía en la versión 1.
+        assert_eq!(decode_payload::<Incident>(&codec.encode(&incident).unwrap()).unwrap().version, 1);
+    }
+
+    #[test]
+    fn test_luego_de_recibir_un_incidente_de_version_1_se_publican_los_nuevos() {
+        let old_incident = Incident::new(IncidentInfo::id_from_narrow(9), (2.0, 3.0), IncidentSource::Automated);
+        let mut old_bytes = EnvelopeHeader::new(Incident::SCHEMA, 1, Encoding::Bincode).to_bytes();
+        old_bytes.extend(old_incident.to_older_version(1, Encoding::Bincode).unwrap());
+        let decoded = <Incident as MqttPayload>::decode(&old_bytes).unwrap();
+        assert_eq!(decoded.get_id(), old_incident.get_id());
+
+        let new_incident = Incident::new(Incident::generate_id(), (4.0, 5.0), IncidentSource::Manual);
+        let bytes = new_incident.encode().unwrap();
+        let received = <Incident as MqttPayload>::decode(&bytes).unwrap();
+        assert_eq!((received.get_id(), received.get_position()), (new_incident.get_id(), (4.0, 5.0)));
+    }
 }
 
@@ -1,33 +1,48 @@
-use std::io::Error;
+use std::io::{Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::apps::payload_codec::{
+    envelope::{Encoding, PayloadSchema},
+    versioned_payload::{check_legacy_len, narrow_id, unsupported_version_error, VersionedPayload},
+};
 
 use super::incident_source::IncidentSource;
 
 /// Este struct se utiliza como clave en hashmaps para identificar a un Incident.
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 pub struct IncidentInfo {
-    inc_id: u8,
+    inc_id: Uuid,
     src: IncidentSource,
 }
 impl IncidentInfo {
-    pub fn new(inc_id: u8, src: IncidentSource) -> Self {
+    pub fn new(inc_id: Uuid, src: IncidentSource) -> Self {
         Self {inc_id, src}
     }
-    pub fn get_inc_id(&self) -> u8 {
+    pub fn get_inc_id(&self) -> Uuid {
         self.inc_id
     }
     pub fn get_src(&self) -> &IncidentSource {
         &self.src
     }
 
-    /// Convierte un struct `IncidentSource` a bytes.
-    pub fn to_bytes(&self) -> Vec<u8> {
+    /// Devuelve el id que corresponde al id de un byte de las versiones anteriores a la 2.
+    pub fn id_from_narrow(inc_id: u8) -> Uuid {
+        Uuid::from_u128(inc_id as u128)
+    }
+
+    /// Convierte un struct `IncidentSource` a bytes, con el formato previo al envelope.
+    /// Devuelve error si el id no entra en un byte.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
         let mut bytes = Vec::new();
-        bytes.extend_from_slice(&[self.inc_id]);
+        bytes.push(narrow_id(self.inc_id.as_u128(), Self::SCHEMA)?);
         bytes.extend_from_slice(&self.src.to_byte());
-        bytes
+        Ok(bytes)
     }
 
-    /// Obtiene un struct `IncidentSource` a partir de bytes.
+    /// Obtiene un struct `IncidentSource` a partir de bytes con el formato previo al envelope,
+    /// en el que el id 0 indica que no hay incidente.
     pub fn from_bytes(bytes: Vec<u8>) -> Result<Option<Self>, Error> {
         let inc_id = u8::from_be_bytes([bytes[0]]);
         if inc_id == 0 {
@@ -36,14 +51,70 @@ impl IncidentInfo {
         let src = IncidentSource::from_byte([bytes[1]])?;
 
         Ok(Some(Self {
-            inc_id,
+            inc_id: Self::id_from_narrow(inc_id),
             src,
         }))
     }   
 }
 
+/// `IncidentInfo` en la versión 1 del envelope, con el id de un byte.
+#[derive(Serialize, Deserialize)]
+pub struct IncidentInfoV1 {
+    inc_id: u8,
+    src: IncidentSource,
+}
+
+impl From<IncidentInfoV1> for IncidentInfo {
+    fn from(v1: IncidentInfoV1) -> Self {
+        Self::new(Self::id_from_narrow(v1.inc_id), v1.src)
+    }
+}
+
+impl TryFrom<&IncidentInfo> for IncidentInfoV1 {
+    type Error = Error;
+
+    fn try_from(inc_info: &IncidentInfo) -> Result<Self, Error> {
+        Ok(Self {
+            inc_id: narrow_id(inc_info.inc_id.as_u128(), IncidentInfo::SCHEMA)?,
+            src: inc_info.src,
+        })
+    }
+}
+
+impl VersionedPayload for IncidentInfo {
+    const SCHEMA: PayloadSchema = PayloadSchema::IncidentInfo;
+    // La versión 2 pasó el id de un byte a uuid.
+    const CURRENT_VERSION: u8 = 2;
+
+    fn to_legacy_bytes(&self) -> Result<Vec<u8>, Error> {
+        self.to_bytes()
+    }
+
+    fn from_legacy_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        check_legacy_len(bytes, 2, Self::SCHEMA)?;
+        Self::from_bytes(bytes.to_vec())?
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "IncidentInfo vacío (id 0)."))
+    }
+
+    fn from_older_version(version: u8, body: &[u8], encoding: Encoding) -> Result<Self, Error> {
+        match version {
+            1 => Ok(encoding.deserialize::<IncidentInfoV1>(body)?.into()),
+            _ => Err(unsupported_version_error(Self::SCHEMA, version)),
+        }
+    }
+
+    fn to_older_version(&self, version: u8, encoding: Encoding) -> Result<Vec<u8>, Error> {
+        match version {
+            1 => encoding.serialize(&IncidentInfoV1::try_from(self)?),
+            _ => Err(unsupported_version_error(Self::SCHEMA, version)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use uuid::Uuid;
+
     use super::{IncidentInfo, IncidentSource};
 
 
@@ -51,8 +122,14 @@ mod test {
     fn test_1_incident_info_to_and_from_bytes_works() {
         // pasada a bytes y reconstruida es igual a la original
         let src = IncidentSource::Manual;
-        let inc_info = IncidentInfo::new(18, src);
+        let inc_info = IncidentInfo::new(IncidentInfo::id_from_narrow(18), src);
         
-        assert_eq!(inc_info, IncidentInfo::from_bytes(inc_info.to_bytes()).unwrap().unwrap());
+        assert_eq!(inc_info, IncidentInfo::from_bytes(inc_info.to_bytes().unwrap()).unwrap().unwrap());
+    }
+
+    #[test]
+    fn test_2_un_id_que_no_entra_en_un_byte_no_se_trunca() {
+        let inc_info = IncidentInfo::new(Uuid::new_v4(), IncidentSource::Automated);
+        assert!(inc_info.to_bytes().is_err());
     }
 }
\ No newline at end of file
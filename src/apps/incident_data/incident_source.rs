@@ -1,9 +1,11 @@
-use std::io::{Error, ErrorKind};
+use std::io::Error;
+
+use serde::{Deserialize, Serialize};
 
 /// Representa el origen en el que se generó el incidente:
 /// puede ser `Manual`, si fue generado manualmente desde la ui de sistema de monitoreo;
 /// o `Automated` si se generó automáticamente mediante inteligencia artificial en sistema cámaras.
-#[derive(Debug, PartialEq, Clone, Copy, Hash, Eq)]
+#[derive(Debug, PartialEq, Clone, Copy, Hash, Eq, Serialize, Deserialize)]
 pub enum IncidentSource {
     Manual,
     Automated,
@@ -21,8 +23,7 @@ impl IncidentSource {
         match u8::from_be_bytes(byte) {
             1 => Ok(IncidentSource::Manual),
             2 => Ok(IncidentSource::Automated),
-            _ => Err(Error::new(
-                ErrorKind::Other,
+            _ => Err(Error::other(
                 "Origen de incidente no válido",
             )),
         }
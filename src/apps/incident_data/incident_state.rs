@@ -1,6 +1,8 @@
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 
-#[derive(Debug, PartialEq, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum IncidentState {
     ActiveIncident,
     ResolvedIncident,
@@ -18,8 +20,7 @@ impl IncidentState {
         match u8::from_be_bytes(byte) {
             1 => Ok(IncidentState::ActiveIncident),
             2 => Ok(IncidentState::ResolvedIncident),
-            _ => Err(Error::new(
-                ErrorKind::Other,
+            _ => Err(Error::other(
                 "Estado de incidente no válido",
             )),
         }
@@ -1,3 +1,4 @@
+pub mod active_incidents;
 pub mod incident;
 pub mod incident_state;
 pub mod incident_source;
@@ -1,14 +1,19 @@
 pub mod apps_mqtt_topics;
 pub mod common_client_errors;
 pub mod common_clients;
+pub mod geo;
 pub mod local_tiles;
+pub mod mqtt_recorder;
 pub mod places;
 pub mod plugins;
 pub mod properties;
+pub mod rx_mqtt;
 pub mod sist_camaras;
 pub mod sist_dron;
 pub mod sist_monitoreo;
 pub mod vendor;
 pub mod windows;
 pub mod incident_data;
-pub mod place_type;
\ No newline at end of file
+pub mod place_type;
+pub mod payload_codec;
+
@@ -0,0 +1,3 @@
+pub mod recorded_publish;
+pub mod recorder;
+pub mod replayer;
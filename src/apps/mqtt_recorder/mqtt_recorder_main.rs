@@ -0,0 +1,125 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Error, ErrorKind},
+    net::SocketAddr,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use rustx::apps::{
+    apps_mqtt_topics::AppsMqttTopics,
+    mqtt_recorder::{recorder::Recorder, replayer::Replayer},
+};
+use rustx::logging::string_logger::StringLogger;
+use rustx::mqtt::client::mqtt_client::MQTTClient;
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+const USAGE: &str = "Uso: mqtt_recorder record <ip> <puerto> <archivo>\n     mqtt_recorder replay <ip> <puerto> <archivo> [velocidad]";
+
+/// Qué hacer, según los argumentos con que se corrió el programa.
+enum Mode {
+    Record { file: String },
+    Replay { file: String, speed: f64 },
+}
+
+fn load_mode_and_broker_address() -> Result<(Mode, SocketAddr), Error> {
+    let argv = std::env::args().collect::<Vec<String>>();
+    if argv.len() < 5 || argv.len() > 6 {
+        return Err(Error::new(ErrorKind::InvalidInput, USAGE));
+    }
+    let broker_addr = format!("{}:{}", argv[2], argv[3])
+        .parse()
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, "Dirección no válida"))?;
+    let file = argv[4].to_string();
+
+    let mode = match (argv[1].as_str(), argv.get(5)) {
+        ("record", None) => Mode::Record { file },
+        ("replay", speed) => {
+            let speed = match speed {
+                Some(speed) => speed.parse::<f64>().map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        "La velocidad proporcionada no es válida",
+                    )
+                })?,
+                None => 1.0,
+            };
+            Mode::Replay { file, speed }
+        }
+        _ => return Err(Error::new(ErrorKind::InvalidInput, USAGE)),
+    };
+    Ok((mode, broker_addr))
+}
+
+/// Graba los publish de todos los topics de las apps en `file`, hasta recibir SIGINT o SIGTERM.
+fn record(file: &str, broker_addr: &SocketAddr, logger: &StringLogger) -> Result<(), Error> {
+    let stop = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(SIGINT, stop.clone())?;
+    signal_hook::flag::register(SIGTERM, stop.clone())?;
+
+    let (mut mqtt_client, publish_msg_rx, _handle) = MQTTClient::mqtt_connect_to_broker(
+        "mqtt-recorder".to_string(),
+        broker_addr,
+        None,
+        logger.clone_ref(),
+    )?;
+    // Como el broker no admite wildcards, se suscribe a cada uno de los topics de las apps.
+    let topics = [
+        AppsMqttTopics::IncidentTopic,
+        AppsMqttTopics::DronTopic,
+        AppsMqttTopics::CameraTopic,
+        AppsMqttTopics::DescTopic,
+    ];
+    mqtt_client.mqtt_subscribe(topics.iter().map(|t| (t.to_str().to_string(), 1)).collect())?;
+    println!("Grabando en {} (Ctrl+C para terminar)...", file);
+
+    let mut recorder = Recorder::new(BufWriter::new(File::create(file)?));
+    let recorded = recorder.record_until_stopped(&publish_msg_rx, &stop)?;
+    println!("Grabados {} publish.", recorded);
+    mqtt_client.mqtt_disconnect()
+}
+
+/// Publica la grabación de `file`, a la velocidad indicada.
+fn replay(
+    file: &str,
+    speed: f64,
+    broker_addr: &SocketAddr,
+    logger: &StringLogger,
+) -> Result<(), Error> {
+    let replayer =
+        Replayer::from_reader(BufReader::new(File::open(file)?), speed, logger.clone_ref())?;
+    let (mut mqtt_client, _publish_msg_rx, _handle) = MQTTClient::mqtt_connect_to_broker(
+        "mqtt-replayer".to_string(),
+        broker_addr,
+        None,
+        logger.clone_ref(),
+    )?;
+    println!(
+        "Reproduciendo {} publish de {} (velocidad {})...",
+        replayer.get_records().len(),
+        file,
+        speed
+    );
+
+    let replayed = replayer.replay(&mut mqtt_client)?;
+    println!("Reproducidos {} publish.", replayed);
+    mqtt_client.mqtt_disconnect()
+}
+
+fn main() -> Result<(), Error> {
+    let (mode, broker_addr) = load_mode_and_broker_address()?;
+    let (mut logger, handle_logger) = StringLogger::create_logger("mqtt-recorder".to_string());
+
+    let res = match mode {
+        Mode::Record { file } => record(&file, &broker_addr, &logger),
+        Mode::Replay { file, speed } => replay(&file, speed, &broker_addr, &logger),
+    };
+    if let Err(e) = &res {
+        println!("Error: {:?}", e);
+    }
+
+    logger.stop_logging();
+    if handle_logger.join().is_err() {
+        println!("Error al esperar al hijo para string logger writer.")
+    }
+    res
+}
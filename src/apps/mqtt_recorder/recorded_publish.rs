@@ -0,0 +1,82 @@
+use std::io::{Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+
+use crate::mqtt::messages::publish_message::PublishMessage;
+
+/// Un publish grabado por el `Recorder`. En el archivo de grabación ocupa una línea, en json.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedPublish {
+    received_at_ms: u64, // Desde el comienzo de la grabación.
+    timestamp: u128,     // El que le puso quien lo publicó.
+    topic: String,
+    qos: u8,
+    payload: String, // En hexa, para que el archivo siga siendo legible.
+}
+
+impl RecordedPublish {
+    pub fn new(publish_msg: &PublishMessage, received_at_ms: u64) -> Self {
+        Self {
+            received_at_ms,
+            timestamp: publish_msg.get_timestamp(),
+            topic: publish_msg.get_topic(),
+            qos: publish_msg.get_qos(),
+            payload: hex::encode(publish_msg.get_payload()),
+        }
+    }
+
+    pub fn get_received_at_ms(&self) -> u64 {
+        self.received_at_ms
+    }
+
+    pub fn get_timestamp(&self) -> u128 {
+        self.timestamp
+    }
+
+    pub fn get_topic(&self) -> &str {
+        &self.topic
+    }
+
+    pub fn get_qos(&self) -> u8 {
+        self.qos
+    }
+
+    pub fn get_payload(&self) -> Result<Vec<u8>, Error> {
+        hex::decode(&self.payload).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Payload grabado inválido: {}.", e),
+            )
+        })
+    }
+
+    /// Devuelve la línea con la que se guarda en el archivo de grabación (sin el salto de línea).
+    pub fn to_line(&self) -> Result<String, Error> {
+        serde_json::to_string(self).map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+    }
+
+    /// Lee un publish grabado de una línea del archivo de grabación.
+    pub fn from_line(line: &str) -> Result<Self, Error> {
+        serde_json::from_str(line).map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RecordedPublish;
+    use crate::mqtt::messages::{publish_flags::PublishFlags, publish_message::PublishMessage};
+
+    #[test]
+    fn test_1_se_guarda_y_se_lee_de_una_linea() {
+        let flags = PublishFlags::new(0, 1, 0).unwrap();
+        let publish_msg = PublishMessage::new(flags, "dron", Some(8), &[1, 2, 255]).unwrap();
+        let recorded = RecordedPublish::new(&publish_msg, 1500);
+
+        let line = recorded.to_line().unwrap();
+        assert!(!line.contains('\n'));
+        let read = RecordedPublish::from_line(&line).unwrap();
+        assert_eq!(read, recorded);
+        assert_eq!(read.get_payload().unwrap(), vec![1, 2, 255]);
+        assert_eq!(read.get_timestamp(), publish_msg.get_timestamp());
+    }
+}
@@ -0,0 +1,66 @@
+use std::{
+    io::{Error, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, RecvTimeoutError},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::mqtt::messages::publish_message::PublishMessage;
+
+use super::recorded_publish::RecordedPublish;
+
+// Cada cuánto se revisa si se pidió terminar la grabación.
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Graba los publish recibidos en `writer`, uno por línea, con el momento en que llegaron.
+#[derive(Debug)]
+pub struct Recorder<W: Write> {
+    writer: W,
+    start: Instant,
+    recorded: usize,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Crea un `Recorder`; los tiempos de los publish grabados se toman desde este momento.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            start: Instant::now(),
+            recorded: 0,
+        }
+    }
+
+    /// Graba el `publish_msg` recibido. Se escribe la línea entera, así la grabación sirve aunque se corte.
+    pub fn record(&mut self, publish_msg: &PublishMessage) -> Result<(), Error> {
+        let received_at_ms = self.start.elapsed().as_millis() as u64;
+        let line = RecordedPublish::new(publish_msg, received_at_ms).to_line()?;
+        writeln!(self.writer, "{}", line)?;
+        self.writer.flush()?;
+        self.recorded += 1;
+        Ok(())
+    }
+
+    /// Graba los publish que lleguen por `publish_msg_rx`, hasta que se cierre o se marque `stop`.
+    /// Devuelve cuántos se grabaron.
+    pub fn record_until_stopped(
+        &mut self,
+        publish_msg_rx: &Receiver<PublishMessage>,
+        stop: &AtomicBool,
+    ) -> Result<usize, Error> {
+        while !stop.load(Ordering::Relaxed) {
+            match publish_msg_rx.recv_timeout(STOP_CHECK_INTERVAL) {
+                Ok(publish_msg) => self.record(&publish_msg)?,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        Ok(self.recorded)
+    }
+
+    /// Devuelve el `writer`, ej para cerrar el archivo.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
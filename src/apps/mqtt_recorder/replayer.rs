@@ -0,0 +1,110 @@
+use std::{
+    io::{BufRead, Error, ErrorKind},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{logging::string_logger::StringLogger, mqtt::client::mqtt_client::MQTTClient};
+
+use super::recorded_publish::RecordedPublish;
+
+/// Vuelve a publicar una grabación hecha por el `Recorder`, respetando el tiempo entre los publish
+/// (escalado según `speed`: 2.0 la reproduce al doble de velocidad).
+#[derive(Debug)]
+pub struct Replayer {
+    records: Vec<RecordedPublish>,
+    speed: f64,
+    logger: StringLogger,
+}
+
+impl Replayer {
+    /// Lee la grabación de `reader`, ignorando las líneas vacías.
+    pub fn from_reader<R: BufRead>(
+        reader: R,
+        speed: f64,
+        logger: StringLogger,
+    ) -> Result<Self, Error> {
+        if speed.is_nan() || speed <= 0.0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "La velocidad debe ser mayor a 0.",
+            ));
+        }
+        let mut records = vec![];
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                records.push(RecordedPublish::from_line(&line)?);
+            }
+        }
+        Ok(Self {
+            records,
+            speed,
+            logger,
+        })
+    }
+
+    pub fn get_records(&self) -> &[RecordedPublish] {
+        &self.records
+    }
+
+    /// Devuelve, para el publish `i`, cuánto después del comienzo de la reproducción debe publicarse.
+    pub fn offset_of(&self, i: usize) -> Duration {
+        let first_ms = self.records.first().map_or(0, |r| r.get_received_at_ms());
+        let elapsed_ms = self.records[i]
+            .get_received_at_ms()
+            .saturating_sub(first_ms);
+        Duration::from_secs_f64(elapsed_ms as f64 / 1000.0 / self.speed)
+    }
+
+    /// Publica la grabación con `mqtt_client`. Los tiempos se miden desde el comienzo, así la espera de
+    /// los acks no va atrasando la reproducción. Devuelve cuántos publish se enviaron.
+    pub fn replay(&self, mqtt_client: &mut MQTTClient) -> Result<usize, Error> {
+        let start = Instant::now();
+        for (i, record) in self.records.iter().enumerate() {
+            if let Some(wait) = self.offset_of(i).checked_sub(start.elapsed()) {
+                thread::sleep(wait);
+            }
+            mqtt_client.mqtt_publish(
+                record.get_topic(),
+                &record.get_payload()?,
+                record.get_qos(),
+            )?;
+            self.logger
+                .log(format!("Replay: publicado {} a {}.", i, record.get_topic()));
+        }
+        Ok(self.records.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::mpsc, time::Duration};
+
+    use super::Replayer;
+    use crate::logging::string_logger::StringLogger;
+
+    const RECORDING: &str = r#"{"received_at_ms":1000,"timestamp":1,"topic":"dron","qos":1,"payload":"01"}
+{"received_at_ms":1500,"timestamp":2,"topic":"inc","qos":1,"payload":"02"}
+
+{"received_at_ms":3000,"timestamp":3,"topic":"dron","qos":0,"payload":"03"}
+"#;
+
+    #[test]
+    fn test_1_los_tiempos_se_escalan_segun_la_velocidad() {
+        let (tx, _rx) = mpsc::channel();
+        let replayer =
+            Replayer::from_reader(RECORDING.as_bytes(), 2.0, StringLogger::new(tx)).unwrap();
+
+        assert_eq!(replayer.get_records().len(), 3);
+        assert_eq!(replayer.offset_of(0), Duration::ZERO);
+        assert_eq!(replayer.offset_of(1), Duration::from_millis(250));
+        assert_eq!(replayer.offset_of(2), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_2_una_velocidad_no_positiva_da_error() {
+        let (tx, _rx) = mpsc::channel();
+        assert!(Replayer::from_reader(RECORDING.as_bytes(), 0.0, StringLogger::new(tx)).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use std::{
+    collections::HashMap,
+    io::Error,
+    sync::{Arc, Mutex, MutexGuard, OnceLock},
+};
+
+use crate::mqtt::client::typed_topic::MqttPayload;
+
+use super::{
+    envelope::{Encoding, PayloadSchema},
+    versioned_payload::{decode_payload, encode_payload, unsupported_version_error, VersionedPayload},
+};
+
+// Variable de entorno con la que elegir la codificación de los payloads que envía la app (ej "json", para debuggear).
+const ENCODING_ENV_VAR: &str = "RUSTX_PAYLOAD_ENCODING";
+// Variable de entorno con la versión máxima en la que la app envía los payloads, mientras sigan desplegadas
+// apps con un formato anterior (ej "0", para las previas al envelope).
+const COMPAT_VERSION_ENV_VAR: &str = "RUSTX_PAYLOAD_COMPAT_VERSION";
+
+static SHARED_CODEC: OnceLock<PayloadCodec> = OnceLock::new();
+
+/// Codifica y decodifica los payloads de las apps, a través del envelope. Se decodifica cualquier versión conocida,
+/// y se envía la actual; salvo que se configure una versión de compatibilidad, para que también entiendan
+/// lo enviado las apps desplegadas con un formato anterior. La versión recibida no cambia la enviada, ya que
+/// un único mensaje viejo haría que se envíe en ella a todas las apps.
+#[derive(Debug, Clone)]
+pub struct PayloadCodec {
+    state: Arc<Mutex<CodecState>>,
+}
+
+#[derive(Debug)]
+struct CodecState {
+    encoding: Encoding,
+    compat_version: Option<u8>, // Versión máxima en la que se envía cada schema.
+    versions: HashMap<PayloadSchema, u8>, // Versión en la que se envía cada schema, si se configuró una en particular.
+}
+
+impl PayloadCodec {
+    pub fn new(encoding: Encoding) -> Self {
+        let state = CodecState {
+            encoding,
+            compat_version: None,
+            versions: HashMap::new(),
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Devuelve el codec de la app, compartido por todo el proceso; es el que usan los `Topic<T>` tipados.
+    /// Codifica con bincode, salvo que se indique otra codificación en la variable de entorno `RUSTX_PAYLOAD_ENCODING`,
+    /// y en la versión actual, salvo que se indique una de compatibilidad en `RUSTX_PAYLOAD_COMPAT_VERSION`.
+    pub fn shared() -> &'static PayloadCodec {
+        SHARED_CODEC.get_or_init(|| {
+            let encoding = std::env::var(ENCODING_ENV_VAR)
+                .ok()
+                .and_then(|name| Encoding::from_name(&name).ok())
+                .unwrap_or(Encoding::Bincode);
+            let codec = PayloadCodec::new(encoding);
+            if let Some(version) = std::env::var(COMPAT_VERSION_ENV_VAR)
+                .ok()
+                .and_then(|version| version.trim().parse::<u8>().ok())
+            {
+                let _ = codec.set_compat_version(Some(version));
+            }
+            codec
+        })
+    }
+
+    pub fn set_encoding(&self, encoding: Encoding) -> Result<(), Error> {
+        self.lock_state()?.encoding = encoding;
+        Ok(())
+    }
+
+    /// Configura la versión máxima en la que se envían los datos de todos los schemas, mientras sigan desplegadas
+    /// apps que sólo entienden hasta ella. Con None se envía la versión actual de cada uno.
+    pub fn set_compat_version(&self, version: Option<u8>) -> Result<(), Error> {
+        self.lock_state()?.compat_version = version;
+        Ok(())
+    }
+
+    /// Configura la versión en la que se envían los datos de tipo `T`, que tiene prioridad sobre la de compatibilidad.
+    /// Devuelve error si es más nueva que la actual.
+    pub fn set_version_for<T: VersionedPayload>(&self, version: u8) -> Result<(), Error> {
+        if version > T::CURRENT_VERSION {
+            return Err(unsupported_version_error(T::SCHEMA, version));
+        }
+        self.lock_state()?.versions.insert(T::SCHEMA, version);
+        Ok(())
+    }
+
+    /// Devuelve la versión en la que se envían los datos de tipo `T`.
+    pub fn get_version_for<T: VersionedPayload>(&self) -> Result<u8, Error> {
+        let state = self.lock_state()?;
+        let version = match (state.versions.get(&T::SCHEMA), state.compat_version) {
+            (Some(version), _) => *version,
+            (None, Some(compat_version)) => compat_version.min(T::CURRENT_VERSION),
+            (None, None) => T::CURRENT_VERSION,
+        };
+        Ok(version)
+    }
+
+    /// Pasa `value` a bytes, en la versión configurada. Si no puede escribirse en ella (ej un id que no entra en
+    /// el formato anterior), se envía en la versión actual, que las apps con el formato anterior descartan,
+    /// en lugar de dejar de enviarlo.
+    pub fn encode<T: VersionedPayload>(&self, value: &T) -> Result<Vec<u8>, Error> {
+        let version = self.get_version_for::<T>()?;
+        let encoding = self.lock_state()?.encoding;
+        match encode_payload(value, version, encoding) {
+            Err(_) if version < T::CURRENT_VERSION => encode_payload(value, T::CURRENT_VERSION, encoding),
+            res => res,
+        }
+    }
+
+    /// Reconstruye el dato a partir de `bytes`, en la versión en que haya llegado.
+    pub fn decode<T: VersionedPayload>(&self, bytes: &[u8]) -> Result<T, Error> {
+        Ok(decode_payload::<T>(bytes)?.value)
+    }
+
+    fn lock_state(&self) -> Result<MutexGuard<'_, CodecState>, Error> {
+        self.state
+            .lock()
+            .map_err(|_| Error::other("Error al tomar lock del codec de payloads."))
+    }
+}
+
+/// Los datos de las apps viajan por los `Topic<T>` tipados con su envelope, usando el codec compartido.
+impl<T: VersionedPayload> MqttPayload for T {
+    fn encode(&self) -> Result<Vec<u8>, Error> {
+        PayloadCodec::shared().encode(self)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        PayloadCodec::shared().decode(bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PayloadCodec;
+    use crate::apps::{
+        incident_data::{incident::Incident, incident_info::IncidentInfo, incident_source::IncidentSource},
+        payload_codec::{envelope::Encoding, versioned_payload::VersionedPayload},
+        sist_camaras::{camera::Camera, camera_fov::CameraFov},
+    };
+    use crate::mqtt::messages::{
+        protocol_version::ProtocolVersion, publish_flags::PublishFlags, publish_message::PublishMessage,
+    };
+
+    #[test]
+    fn test_1_se_codifica_con_envelope_en_bincode_o_json() {
+        let incident = Incident::new(Incident::generate_id(), (-34.6, -58.4), IncidentSource::Automated);
+        for encoding in [Encoding::Bincode, Encoding::Json] {
+            let codec = PayloadCodec::new(encoding);
+            let bytes = codec.encode(&incident).unwrap();
+            let decoded: Incident = codec.decode(&bytes).unwrap();
+            assert_eq!(decoded.get_position(), incident.get_position());
+            assert_eq!(decoded.get_source(), incident.get_source());
+        }
+
+        // El json es legible, para debuggear.
+        let json = PayloadCodec::new(Encoding::Json).encode(&incident).unwrap();
+        assert!(String::from_utf8_lossy(&json).contains("\"latitude\":-34.6"));
+    }
+
+    #[test]
+    fn test_2_recibir_una_version_anterior_no_cambia_la_que_se_envia() {
+        let codec = PayloadCodec::new(Encoding::Bincode);
+
+        // Llega un incidente de una app desplegada antes del envelope.
+        let old_incident = Incident::new(IncidentInfo::id_from_narrow(1), (1.0, 2.0), IncidentSource::Manual);
+        let decoded: Incident = codec.decode(&old_incident.to_legacy_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.get_position(), (1.0, 2.0));
+
+        // Se sigue enviando la versión actual, al resto de las apps.
+        assert_eq!(codec.get_version_for::<Incident>().unwrap(), Incident::CURRENT_VERSION);
+    }
+
+    #[test]
+    fn test_3_se_envia_en_la_version_de_compatibilidad_configurada() {
+        let codec = PayloadCodec::new(Encoding::Bincode);
+        codec.set_compat_version(Some(0)).unwrap();
+        let old_incident = Incident::new(IncidentInfo::id_from_narrow(1), (1.0, 2.0), IncidentSource::Manual);
+        assert_eq!(codec.get_version_for::<Incident>().unwrap(), 0);
+        assert_eq!(codec.encode(&old_incident).unwrap(), old_incident.to_legacy_bytes().unwrap());
+
+        // La versión configurada para un schema tiene prioridad; una más nueva que la actual no se acepta.
+        codec.set_version_for::<Camera>(1).unwrap();
+        assert_eq!(codec.get_version_for::<Camera>().unwrap(), 1);
+        assert!(codec.set_version_for::<Camera>(Camera::CURRENT_VERSION + 1).is_err());
+
+        codec.set_compat_version(None).unwrap();
+        assert_eq!(codec.get_version_for::<Incident>().unwrap(), Incident::CURRENT_VERSION);
+    }
+
+    #[test]
+    fn test_4_una_camara_con_lindantes_en_json_se_envia_completa_en_un_publish() {
+        let mut camera = Camera::new(1, -34.6090, -58.3873, 250.0);
+        camera.set_fov(CameraFov::new(90.0, 60.0, Some(150.0)).unwrap());
+        for id in 1001..1009 {
+            let mut bordering = Camera::new(id, -34.6091, -58.3874, 250.0);
+            camera.mutually_add_bordering(&mut bordering);
+        }
+        let codec = PayloadCodec::new(Encoding::Json);
+        let bytes = codec.encode(&camera).unwrap();
+
+        // Aunque el publish supere los 255 bytes, lleva el payload completo, en ambas versiones.
+        let flags = PublishFlags::new(0, 1, 0).unwrap();
+        let publish = PublishMessage::new(flags, "cam", Some(1), &bytes).unwrap();
+        for version in [ProtocolVersion::V311, ProtocolVersion::V5] {
+            assert!(publish.to_bytes_for(version).len() > 255);
+            let received =
+                PublishMessage::from_bytes_with_version(publish.to_bytes_for(version), version).unwrap();
+            let decoded: Camera = codec.decode(&received.get_payload()).unwrap();
+            assert_eq!(decoded, camera);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::io::{Error, ErrorKind};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+// Bytes con los que empieza todo payload con envelope ("RX"); los payloads de formato previo no lo tienen.
+const ENVELOPE_MAGIC: [u8; 2] = [0x52, 0x58];
+pub const ENVELOPE_HEADER_LEN: usize = ENVELOPE_MAGIC.len() + 3;
+
+/// Tipo de dato que lleva el payload.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum PayloadSchema {
+    Incident = 1,
+    Camera = 2,
+    DronCurrentInfo = 3,
+    IncidentInfo = 4,
+    WillContent = 5,
+    CameraCommandRequest = 6,
+    CameraCommandReply = 7,
+    ActiveIncidentsRequest = 8,
+    ActiveIncidentsReply = 9,
+}
+
+impl PayloadSchema {
+    pub fn to_byte(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_byte(byte: u8) -> Result<Self, Error> {
+        match byte {
+            1 => Ok(PayloadSchema::Incident),
+            2 => Ok(PayloadSchema::Camera),
+            3 => Ok(PayloadSchema::DronCurrentInfo),
+            4 => Ok(PayloadSchema::IncidentInfo),
+            5 => Ok(PayloadSchema::WillContent),
+            6 => Ok(PayloadSchema::CameraCommandRequest),
+            7 => Ok(PayloadSchema::CameraCommandReply),
+            8 => Ok(PayloadSchema::ActiveIncidentsRequest),
+            9 => Ok(PayloadSchema::ActiveIncidentsReply),
+            _ => Err(Error::new(ErrorKind::InvalidData, "Schema de payload desconocido.")),
+        }
+    }
+}
+
+/// Codificación del cuerpo del payload: binaria y compacta, o json para debuggear.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Encoding {
+    Bincode = 0,
+    Json = 1,
+}
+
+impl Encoding {
+    pub fn to_byte(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_byte(byte: u8) -> Result<Self, Error> {
+        match byte {
+            0 => Ok(Encoding::Bincode),
+            1 => Ok(Encoding::Json),
+            _ => Err(Error::new(ErrorKind::InvalidData, "Codificación de payload desconocida.")),
+        }
+    }
+
+    /// Devuelve la codificación de nombre `name` ("bincode" o "json").
+    pub fn from_name(name: &str) -> Result<Self, Error> {
+        match name.trim().to_lowercase().as_str() {
+            "bincode" => Ok(Encoding::Bincode),
+            "json" => Ok(Encoding::Json),
+            _ => Err(Error::new(ErrorKind::InvalidInput, "Codificación de payload desconocida.")),
+        }
+    }
+
+    pub fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, Error> {
+        match self {
+            Encoding::Bincode => bincode::serialize(value)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string())),
+            Encoding::Json => serde_json::to_vec(value)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string())),
+        }
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, Error> {
+        match self {
+            Encoding::Bincode => bincode::deserialize(bytes)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string())),
+            Encoding::Json => serde_json::from_slice(bytes)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string())),
+        }
+    }
+}
+
+/// Header con el que se envían los payloads de las apps: indica qué tipo de dato llevan, en qué versión
+/// de su formato, y con qué codificación. Así quien lo recibe puede decodificar también versiones anteriores.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct EnvelopeHeader {
+    schema: PayloadSchema,
+    version: u8,
+    encoding: Encoding,
+}
+
+impl EnvelopeHeader {
+    pub fn new(schema: PayloadSchema, version: u8, encoding: Encoding) -> Self {
+        Self {
+            schema,
+            version,
+            encoding,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = ENVELOPE_MAGIC.to_vec();
+        bytes.push(self.schema.to_byte());
+        bytes.push(self.version);
+        bytes.push(self.encoding.to_byte());
+        bytes
+    }
+
+    /// Separa el header del cuerpo del payload. Devuelve None si el payload no empieza con un header válido
+    /// (por ejemplo, si es de formato previo al envelope).
+    pub fn split(bytes: &[u8]) -> Option<(Self, &[u8])> {
+        if bytes.len() < ENVELOPE_HEADER_LEN || bytes[..ENVELOPE_MAGIC.len()] != ENVELOPE_MAGIC {
+            return None;
+        }
+        let schema = PayloadSchema::from_byte(bytes[2]).ok()?;
+        let encoding = Encoding::from_byte(bytes[4]).ok()?;
+        let header = Self::new(schema, bytes[3], encoding);
+        Some((header, &bytes[ENVELOPE_HEADER_LEN..]))
+    }
+
+    pub fn get_schema(&self) -> PayloadSchema {
+        self.schema
+    }
+
+    pub fn get_version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn get_encoding(&self) -> Encoding {
+        self.encoding
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Encoding, EnvelopeHeader, PayloadSchema};
+
+    #[test]
+    fn test_1_el_header_se_separa_del_cuerpo() {
+        let header = EnvelopeHeader::new(PayloadSchema::Camera, 3, Encoding::Json);
+        let mut bytes = header.to_bytes();
+        bytes.extend_from_slice(b"{}");
+
+        let (read_header, body) = EnvelopeHeader::split(&bytes).unwrap();
+        assert_eq!(read_header, header);
+        assert_eq!(body, b"{}");
+    }
+
+    #[test]
+    fn test_2_un_payload_sin_header_no_se_confunde_con_uno_con_header() {
+        // Ej: un incidente de formato previo, que empieza con su id.
+        assert!(EnvelopeHeader::split(&[7, 0, 0, 0, 0, 0, 0, 0, 0]).is_none());
+        assert!(EnvelopeHeader::split(&[0x52, 0x58, 99, 1, 0]).is_none());
+        assert!(EnvelopeHeader::split(&[0x52, 0x58]).is_none());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod codec;
+pub mod envelope;
+pub mod versioned_payload;
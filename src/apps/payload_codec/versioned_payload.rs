@@ -0,0 +1,240 @@
+use std::io::{Error, ErrorKind};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::envelope::{Encoding, EnvelopeHeader, PayloadSchema};
+
+/// Versión de los payloads enviados sin envelope, con el formato armado a mano previo a él.
+pub const LEGACY_VERSION: u8 = 0;
+
+/// Dato de las apps que se envía como payload, dentro de un envelope con su schema y versión.
+/// Al agregar un campo, se sube `CURRENT_VERSION` y se implementan `from_older_version` y `to_older_version`,
+/// para seguir entendiéndose con las apps ya desplegadas.
+pub trait VersionedPayload: Serialize + DeserializeOwned {
+    const SCHEMA: PayloadSchema;
+    /// Versión actual del formato; la `LEGACY_VERSION` es la previa al envelope.
+    const CURRENT_VERSION: u8 = 1;
+
+    /// Pasa el dato a bytes con el formato previo al envelope.
+    /// Devuelve error si el dato no entra en ese formato (ej un id que no entra en un byte).
+    fn to_legacy_bytes(&self) -> Result<Vec<u8>, Error>;
+
+    /// Reconstruye el dato a partir de bytes con el formato previo al envelope.
+    /// Devuelve error (sin hacer panic) si los bytes no corresponden a un dato válido.
+    fn from_legacy_bytes(bytes: &[u8]) -> Result<Self, Error>;
+
+    /// Reconstruye el dato a partir del cuerpo de una versión anterior a la actual, y posterior a la previa al envelope.
+    fn from_older_version(version: u8, _body: &[u8], _encoding: Encoding) -> Result<Self, Error> {
+        Err(unsupported_version_error(Self::SCHEMA, version))
+    }
+
+    /// Pasa el dato al cuerpo de una versión anterior a la actual, y posterior a la previa al envelope.
+    fn to_older_version(&self, version: u8, _encoding: Encoding) -> Result<Vec<u8>, Error> {
+        Err(unsupported_version_error(Self::SCHEMA, version))
+    }
+}
+
+/// Dato decodificado, junto con la versión y la codificación con las que llegó
+/// (la codificación es None si llegó con el formato previo al envelope).
+#[derive(Debug, PartialEq)]
+pub struct DecodedPayload<T> {
+    pub value: T,
+    pub version: u8,
+    pub encoding: Option<Encoding>,
+}
+
+/// Pasa `value` a bytes en la `version` indicada: con la `LEGACY_VERSION` usa el formato previo al envelope,
+/// si no agrega el header y codifica el cuerpo con `encoding`.
+pub fn encode_payload<T: VersionedPayload>(value: &T, version: u8, encoding: Encoding) -> Result<Vec<u8>, Error> {
+    if version == LEGACY_VERSION {
+        return value.to_legacy_bytes();
+    }
+    let body = if version == T::CURRENT_VERSION {
+        encoding.serialize(value)?
+    } else if version < T::CURRENT_VERSION {
+        value.to_older_version(version, encoding)?
+    } else {
+        return Err(unsupported_version_error(T::SCHEMA, version));
+    };
+
+    let mut bytes = EnvelopeHeader::new(T::SCHEMA, version, encoding).to_bytes();
+    bytes.extend(body);
+    Ok(bytes)
+}
+
+/// Reconstruye el dato a partir de `bytes`, en cualquier versión conocida: con envelope, o con el formato previo a él.
+/// Devuelve error si el envelope es de otro schema.
+pub fn decode_payload<T: VersionedPayload>(bytes: &[u8]) -> Result<DecodedPayload<T>, Error> {
+    let Some((header, body)) = EnvelopeHeader::split(bytes) else {
+        // Sin header: formato previo al envelope.
+        let value = T::from_legacy_bytes(bytes)?;
+        return Ok(DecodedPayload {
+            value,
+            version: LEGACY_VERSION,
+            encoding: None,
+        });
+    };
+
+    if header.get_schema() != T::SCHEMA {
+        return Err(schema_mismatch_error(T::SCHEMA, header.get_schema()));
+    }
+    let version = header.get_version();
+    let encoding = header.get_encoding();
+    let value = if version == T::CURRENT_VERSION {
+        encoding.deserialize(body)?
+    } else if version > LEGACY_VERSION && version < T::CURRENT_VERSION {
+        T::from_older_version(version, body, encoding)?
+    } else {
+        return Err(unsupported_version_error(T::SCHEMA, version));
+    };
+    Ok(DecodedPayload {
+        value,
+        version,
+        encoding: Some(encoding),
+    })
+}
+
+/// Error para los payloads cuyo envelope indica un schema `received` distinto del `expected`.
+pub fn schema_mismatch_error(expected: PayloadSchema, received: PayloadSchema) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("Se esperaba un payload {:?}, pero se recibió uno {:?}.", expected, received),
+    )
+}
+
+/// Error para las versiones de `schema` que no se sabe decodificar ni codificar.
+pub fn unsupported_version_error(schema: PayloadSchema, version: u8) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("Versión {} no soportada para el payload {:?}.", version, schema),
+    )
+}
+
+/// Error para los payloads de `schema` que no tienen formato previo al envelope, porque se agregaron después de él.
+pub fn no_legacy_format_error(schema: PayloadSchema) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("El payload {:?} no tiene formato previo al envelope.", schema),
+    )
+}
+
+/// Pasa `id` al id de un byte de las versiones anteriores a la 2; devuelve error si no entra en él,
+/// ya que truncarlo podría hacerlo coincidir con el de otro dato.
+pub fn narrow_id(id: u128, schema: PayloadSchema) -> Result<u8, Error> {
+    u8::try_from(id).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("El id {} de {:?} no entra en el formato de una versión anterior.", id, schema),
+        )
+    })
+}
+
+/// Devuelve error si el payload tiene menos de `min_len` bytes; para usar al decodificar
+/// el formato previo al envelope, antes de indexar los bytes.
+pub fn check_legacy_len(bytes: &[u8], min_len: usize, schema: PayloadSchema) -> Result<(), Error> {
+    if bytes.len() < min_len {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Payload inválido para {:?}: se esperaban al menos {} bytes, se recibieron {}.",
+                schema,
+                min_len,
+                bytes.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Error, ErrorKind};
+
+    use serde::{Deserialize, Serialize};
+
+    use super::{decode_payload, encode_payload, VersionedPayload, LEGACY_VERSION};
+    use crate::apps::payload_codec::envelope::{Encoding, PayloadSchema};
+
+    /// Dato de prueba en su versión 2, que agregó `battery` a la versión 1.
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct DronData {
+        id: u8,
+        battery: u8,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct DronDataV1 {
+        id: u8,
+    }
+
+    impl VersionedPayload for DronData {
+        const SCHEMA: PayloadSchema = PayloadSchema::DronCurrentInfo;
+        const CURRENT_VERSION: u8 = 2;
+
+        fn to_legacy_bytes(&self) -> Result<Vec<u8>, Error> {
+            Ok(vec![self.id])
+        }
+
+        fn from_legacy_bytes(bytes: &[u8]) -> Result<Self, Error> {
+            match bytes {
+                [id] => Ok(Self { id: *id, battery: 100 }),
+                _ => Err(Error::new(ErrorKind::InvalidData, "DronData inválido.")),
+            }
+        }
+
+        fn from_older_version(version: u8, body: &[u8], encoding: Encoding) -> Result<Self, Error> {
+            match version {
+                1 => {
+                    let v1: DronDataV1 = encoding.deserialize(body)?;
+                    Ok(Self { id: v1.id, battery: 100 })
+                }
+                _ => Err(Error::new(ErrorKind::InvalidData, "Versión no soportada.")),
+            }
+        }
+
+        fn to_older_version(&self, version: u8, encoding: Encoding) -> Result<Vec<u8>, Error> {
+            match version {
+                1 => encoding.serialize(&DronDataV1 { id: self.id }),
+                _ => Err(Error::new(ErrorKind::InvalidData, "Versión no soportada.")),
+            }
+        }
+    }
+
+    #[test]
+    fn test_1_se_decodifican_todas_las_versiones_conocidas() {
+        let dron = DronData { id: 4, battery: 70 };
+        for encoding in [Encoding::Bincode, Encoding::Json] {
+            let current = decode_payload::<DronData>(&encode_payload(&dron, 2, encoding).unwrap()).unwrap();
+            assert_eq!((current.value, current.version, current.encoding), (DronData { id: 4, battery: 70 }, 2, Some(encoding)));
+
+            // La versión 1 no tenía batería, se completa con el valor por defecto.
+            let older = decode_payload::<DronData>(&encode_payload(&dron, 1, encoding).unwrap()).unwrap();
+            assert_eq!((older.value, older.version), (DronData { id: 4, battery: 100 }, 1));
+        }
+
+        let legacy = decode_payload::<DronData>(&[4]).unwrap();
+        assert_eq!((legacy.value.id, legacy.version, legacy.encoding), (4, LEGACY_VERSION, None));
+    }
+
+    #[test]
+    fn test_2_una_version_mas_nueva_que_la_actual_da_error() {
+        let dron = DronData { id: 4, battery: 70 };
+        assert!(encode_payload(&dron, 3, Encoding::Bincode).is_err());
+
+        // Una app más nueva envía la versión 3: no se la confunde con el formato previo al envelope.
+        let mut bytes = encode_payload(&dron, 2, Encoding::Bincode).unwrap();
+        bytes[3] = 3;
+        assert!(decode_payload::<DronData>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_3_un_payload_con_envelope_de_otro_schema_da_error() {
+        let dron = DronData { id: 4, battery: 70 };
+        let mut bytes = encode_payload(&dron, 2, Encoding::Bincode).unwrap();
+        bytes[2] = PayloadSchema::Camera.to_byte();
+
+        let err = decode_payload::<DronData>(&bytes).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("Camera"));
+    }
+}
@@ -1,13 +1,17 @@
-use crate::mqtt::mqtt_utils::will_message_utils::app_type::AppType;
+use crate::mqtt::mqtt_utils::will_message_utils::{app_type::AppType, will_content::WillContent};
 
-use super::incident_data::incident_source::IncidentSource;
+use super::{
+    incident_data::incident_source::IncidentSource,
+    sist_camaras::camera::{CameraSystemId, DEFAULT_CAMERA_SYSTEM_ID},
+};
 
 /// PlaceType para usar en el vector que utiliza la ui de Sistema monitoreo.
 /// Según este enum se identifican los elementos en dicho vector, ya que el mismo puede tener
 /// elementos con un mismo id pero diferente place type (por ejemplo cámara 1 y dron 1).
+/// Las cámaras se distinguen además por su sistema de cámaras, ya que cada uno numera las suyas.
 #[derive(Debug, PartialEq, Clone)]
 pub enum PlaceType {
-    Camera,
+    Camera(CameraSystemId),
     Dron,
     ManualIncident,
     AutomatedIncident,
@@ -23,12 +27,37 @@ impl PlaceType {
         }
     }
 
-    /// Devuelve un `PlaceType` acorde al `AppType` del `WillContent` recibido.
-    pub fn from_app_type_will_content(app_type: &AppType) -> Self {
-        match app_type {
-            AppType::Cameras => Self::Camera,
+    /// Devuelve un `PlaceType` acorde al `AppType` del `WillContent` recibido. En el de las cámaras, el id es el
+    /// de su sistema (sin id, de las versiones anteriores, es el sistema por defecto).
+    pub fn from_will_content(will_content: &WillContent) -> Self {
+        match will_content.get_app_type_identifier() {
+            AppType::Cameras => {
+                Self::Camera(will_content.get_id().unwrap_or(DEFAULT_CAMERA_SYSTEM_ID))
+            }
             AppType::Dron => Self::Dron,
             AppType::Monitoreo => Self::Mantainance, // Aux: esta rama no tiene sentido, nunca se va a dar. Ver. [].
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::apps::sist_camaras::camera::DEFAULT_CAMERA_SYSTEM_ID;
+    use crate::mqtt::mqtt_utils::will_message_utils::{app_type::AppType, will_content::WillContent};
+
+    use super::PlaceType;
+
+    #[test]
+    fn test_1_el_will_de_un_sistema_de_camaras_identifica_solo_a_sus_camaras() {
+        let will_content = WillContent::new(AppType::Cameras, Some(2));
+        assert_eq!(PlaceType::from_will_content(&will_content), PlaceType::Camera(2));
+        assert_ne!(PlaceType::from_will_content(&will_content), PlaceType::Camera(3));
+
+        // Sin id, es el de un sistema de cámaras de una versión anterior: el sistema por defecto
+        let will_content = WillContent::new(AppType::Cameras, None);
+        assert_eq!(
+            PlaceType::from_will_content(&will_content),
+            PlaceType::Camera(DEFAULT_CAMERA_SYSTEM_ID)
+        );
+    }
+}
@@ -4,7 +4,7 @@ use egui::{Color32, Painter, Response};
 
 use super::places;
 
-/// Creates a built-in `Places` plugin with some predefined places.
+// Creates a built-in `Places` plugin with some predefined places.
 // pub fn places() -> impl Plugin {
 //     Places::new(vec![
 // Place {
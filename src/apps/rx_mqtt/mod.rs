@@ -0,0 +1,2 @@
+pub mod payload_printer;
+pub mod rx_mqtt_args;
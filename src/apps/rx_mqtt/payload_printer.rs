@@ -0,0 +1,133 @@
+use std::io::{Error, ErrorKind};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::apps::{
+    apps_mqtt_topics::AppsMqttTopics,
+    incident_data::{
+        active_incidents::{ActiveIncidentsReply, ActiveIncidentsRequest},
+        incident::Incident,
+    },
+    sist_camaras::{
+        camera::Camera,
+        camera_command::{CameraCommandReply, CameraCommandRequest},
+    },
+    sist_dron::dron_current_info::DronCurrentInfo,
+};
+use crate::mqtt::{
+    client::typed_topic::MqttPayload, mqtt_utils::will_message_utils::will_content::WillContent,
+};
+
+/// Devuelve el `payload` recibido en `topic` como texto para mostrar.
+/// Si `decode` y el topic es de las apps, se muestra su valor decodificado en json; si no, el payload como texto,
+/// o en hexa si no es utf-8.
+pub fn format_payload(topic: &str, payload: &[u8], decode: bool) -> String {
+    if decode {
+        if let Some(decoded) = decode_app_payload(topic, payload) {
+            return match decoded {
+                Ok(json) => json,
+                Err(e) => format!("{} (no se pudo decodificar: {})", format_raw(payload), e),
+            };
+        }
+    }
+    format_raw(payload)
+}
+
+/// Codifica el valor en json `json` como payload del tipo de las apps que corresponde a `topic`.
+/// Devuelve error si el topic no es de las apps, o si el json no es un valor válido de su tipo.
+pub fn encode_app_payload(topic: &str, json: &str) -> Result<Vec<u8>, Error> {
+    if AppsMqttTopics::is_active_incidents_replies(topic) {
+        return from_json::<ActiveIncidentsReply>(json)?.encode();
+    }
+    if AppsMqttTopics::is_camera_command_replies(topic) {
+        return from_json::<CameraCommandReply>(json)?.encode();
+    }
+    if AppsMqttTopics::is_camera_commands(topic) {
+        return from_json::<CameraCommandRequest>(json)?.encode();
+    }
+    match AppsMqttTopics::topic_from_str(topic)? {
+        AppsMqttTopics::IncidentTopic => from_json::<Incident>(json)?.encode(),
+        AppsMqttTopics::DronTopic => from_json::<DronCurrentInfo>(json)?.encode(),
+        AppsMqttTopics::CameraTopic => from_json::<Camera>(json)?.encode(),
+        AppsMqttTopics::DescTopic => from_json::<WillContent>(json)?.encode(),
+        AppsMqttTopics::CameraCommandTopic => from_json::<CameraCommandRequest>(json)?.encode(),
+        AppsMqttTopics::ActiveIncidentsTopic => from_json::<ActiveIncidentsRequest>(json)?.encode(),
+    }
+}
+
+/// Si `topic` es de las apps, decodifica el payload con su tipo y lo devuelve en json.
+fn decode_app_payload(topic: &str, payload: &[u8]) -> Option<Result<String, Error>> {
+    if AppsMqttTopics::is_active_incidents_replies(topic) {
+        return Some(to_pretty_json::<ActiveIncidentsReply>(payload));
+    }
+    if AppsMqttTopics::is_camera_command_replies(topic) {
+        return Some(to_pretty_json::<CameraCommandReply>(payload));
+    }
+    if AppsMqttTopics::is_camera_commands(topic) {
+        return Some(to_pretty_json::<CameraCommandRequest>(payload));
+    }
+    let decoded = match AppsMqttTopics::topic_from_str(topic).ok()? {
+        AppsMqttTopics::IncidentTopic => to_pretty_json::<Incident>(payload),
+        AppsMqttTopics::DronTopic => to_pretty_json::<DronCurrentInfo>(payload),
+        AppsMqttTopics::CameraTopic => to_pretty_json::<Camera>(payload),
+        AppsMqttTopics::DescTopic => to_pretty_json::<WillContent>(payload),
+        AppsMqttTopics::CameraCommandTopic => to_pretty_json::<CameraCommandRequest>(payload),
+        AppsMqttTopics::ActiveIncidentsTopic => to_pretty_json::<ActiveIncidentsRequest>(payload),
+    };
+    Some(decoded)
+}
+
+fn to_pretty_json<T: MqttPayload + Serialize>(payload: &[u8]) -> Result<String, Error> {
+    let value = T::decode(payload)?;
+    serde_json::to_string_pretty(&value).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, Error> {
+    serde_json::from_str(json).map_err(|e| Error::new(ErrorKind::InvalidInput, e))
+}
+
+fn format_raw(payload: &[u8]) -> String {
+    match std::str::from_utf8(payload) {
+        Ok(text) => text.to_string(),
+        Err(_) => format!("0x{}", hex::encode(payload)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{encode_app_payload, format_payload};
+    use crate::apps::incident_data::{incident::Incident, incident_source::IncidentSource};
+    use crate::apps::sist_camaras::camera_command::{CameraCommand, CameraCommandRequest};
+    use crate::mqtt::client::typed_topic::MqttPayload;
+
+    #[test]
+    fn test_1_se_decodifica_un_incidente_y_se_vuelve_a_codificar_desde_su_json() {
+        let incident = Incident::new(Incident::generate_id(), (-34.6, -58.4), IncidentSource::Manual);
+
+        let json = format_payload("inc", &incident.encode().unwrap(), true);
+        assert!(json.contains(&incident.get_id().to_string()));
+
+        let decoded = Incident::decode(&encode_app_payload("inc", &json).unwrap()).unwrap();
+        assert_eq!(decoded.get_id(), incident.get_id());
+        assert!(encode_app_payload("inc", "{}").is_err());
+        assert!(encode_app_payload("otro", &json).is_err());
+    }
+
+    #[test]
+    fn test_2_los_payloads_que_no_son_de_las_apps_se_muestran_como_texto_o_hexa() {
+        assert_eq!(format_payload("otro", b"hola", true), "hola");
+        assert_eq!(format_payload("inc", &[0xff, 0x01], false), "0xff01");
+        assert!(format_payload("inc", &[0xff, 0x01], true).starts_with("0xff01 (no se pudo decodificar"));
+    }
+
+    #[test]
+    fn test_3_los_comandos_de_cualquier_sistema_de_camaras_se_decodifican() {
+        let request = CameraCommandRequest::new("pedido-1", "cam/cmd/respuestas/admin", CameraCommand::List);
+        for topic in ["cam/cmd", "cam/cmd/2"] {
+            let json = format_payload(topic, &request.encode().unwrap(), true);
+            let decoded = CameraCommandRequest::decode(&encode_app_payload(topic, &json).unwrap()).unwrap();
+            assert_eq!(decoded, request);
+        }
+        assert!(encode_app_payload("cam/cmd/otro", "{}").is_err());
+    }
+}
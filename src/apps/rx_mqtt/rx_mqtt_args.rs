@@ -0,0 +1,219 @@
+use std::{
+    io::{Error, ErrorKind},
+    net::SocketAddr,
+};
+
+use crate::mqtt::{
+    client::credentials::Credentials, mqtt_utils::will_message_utils::will_message::WillMessageData,
+};
+
+pub const USAGE: &str = "Uso: rx_mqtt pub  -t <topic> -m <mensaje> [opciones]
+     rx_mqtt sub  -t <topic> [-t <topic> ...] [opciones]
+     rx_mqtt ping [opciones]
+
+Opciones:
+  -h, --host <ip>             ip del broker (127.0.0.1 por defecto)
+  -p, --port <puerto>         puerto del broker (9090 por defecto)
+  -i, --id <client_id>        client id (rx_mqtt-<pid> por defecto)
+  -u, --username <usuario>    usuario, junto con -P
+  -P, --password <contraseña> contraseña, junto con -u
+  -q, --qos <0|1>             qos del publish o de la suscripción (0 por defecto)
+  -r, --retain                publica con el flag de retain
+  -d, --decode                sub: muestra los payloads de las apps decodificados;
+                              pub: el mensaje es el json del tipo del topic, y se envía codificado
+  -c, --count <n>             sub: termina tras recibir n mensajes; ping: cantidad de pings (4 por defecto)
+      --will-topic <topic>    topic del will message, junto con --will-message
+      --will-message <msg>    contenido del will message
+      --will-qos <0|1>        qos del will message (1 por defecto)
+      --will-retain           will message con el flag de retain";
+
+const DEFAULT_HOST: &str = "127.0.0.1";
+const DEFAULT_PORT: &str = "9090";
+const DEFAULT_PINGS: usize = 4;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RxMqttCommand {
+    Pub,
+    Sub,
+    Ping,
+}
+
+/// Argumentos con los que se corre rx_mqtt, ya validados.
+#[derive(Debug)]
+pub struct RxMqttArgs {
+    pub command: RxMqttCommand,
+    pub broker_addr: SocketAddr,
+    pub client_id: String,
+    pub credentials: Credentials,
+    pub topics: Vec<String>,
+    pub qos: u8,
+    pub retain: bool,
+    pub message: Option<String>,
+    pub decode: bool,
+    pub count: Option<usize>,
+    pub will: Option<WillMessageData>,
+}
+
+impl RxMqttArgs {
+    /// Interpreta `args` (sin el nombre del programa). Devuelve error, con el motivo, si no son válidos.
+    pub fn parse(args: &[String]) -> Result<Self, Error> {
+        let command = match args.first().map(|arg| arg.as_str()) {
+            Some("pub") => RxMqttCommand::Pub,
+            Some("sub") => RxMqttCommand::Sub,
+            Some("ping") => RxMqttCommand::Ping,
+            _ => return Err(invalid_input(USAGE)),
+        };
+
+        let mut host = DEFAULT_HOST.to_string();
+        let mut port = DEFAULT_PORT.to_string();
+        let mut client_id = format!("rx_mqtt-{}", std::process::id());
+        let (mut username, mut password) = (None, None);
+        let mut topics = vec![];
+        let mut qos = 0;
+        let mut retain = false;
+        let mut message = None;
+        let mut decode = false;
+        let mut count = None;
+        let (mut will_topic, mut will_message, mut will_qos, mut will_retain) = (None, None, 1, false);
+
+        let mut remaining = args[1..].iter();
+        while let Some(option) = remaining.next() {
+            let mut value = || {
+                remaining
+                    .next()
+                    .cloned()
+                    .ok_or_else(|| invalid_input(&format!("Falta el valor de la opción {}.", option)))
+            };
+            match option.as_str() {
+                "-h" | "--host" => host = value()?,
+                "-p" | "--port" => port = value()?,
+                "-i" | "--id" => client_id = value()?,
+                "-u" | "--username" => username = Some(value()?),
+                "-P" | "--password" => password = Some(value()?),
+                "-t" | "--topic" => topics.push(value()?),
+                "-q" | "--qos" => qos = parse_qos(&value()?)?,
+                "-r" | "--retain" => retain = true,
+                "-m" | "--message" => message = Some(value()?),
+                "-d" | "--decode" => decode = true,
+                "-c" | "--count" => count = Some(parse_count(&value()?)?),
+                "--will-topic" => will_topic = Some(value()?),
+                "--will-message" => will_message = Some(value()?),
+                "--will-qos" => will_qos = parse_qos(&value()?)?,
+                "--will-retain" => will_retain = true,
+                _ => return Err(invalid_input(&format!("Opción desconocida: {}.\n{}", option, USAGE))),
+            }
+        }
+
+        let broker_addr = format!("{}:{}", host, port)
+            .parse()
+            .map_err(|_| invalid_input("Dirección no válida"))?;
+        let credentials = match (username, password) {
+            (Some(username), Some(password)) => Credentials::new(&username, &password),
+            (None, None) => Credentials::default(),
+            _ => return Err(invalid_input("El usuario y la contraseña deben indicarse juntos.")),
+        };
+        let will = match (will_topic, will_message) {
+            (Some(topic), Some(content)) => Some(WillMessageData::new(content, topic, will_qos, will_retain as u8)),
+            (None, None) => None,
+            _ => return Err(invalid_input("El topic y el mensaje del will deben indicarse juntos.")),
+        };
+
+        match command {
+            RxMqttCommand::Pub if topics.len() != 1 || message.is_none() => {
+                return Err(invalid_input("pub requiere un topic (-t) y un mensaje (-m)."))
+            }
+            RxMqttCommand::Sub if topics.is_empty() => {
+                return Err(invalid_input("sub requiere al menos un topic (-t)."))
+            }
+            RxMqttCommand::Ping => count = count.or(Some(DEFAULT_PINGS)),
+            _ => {}
+        }
+
+        Ok(Self {
+            command,
+            broker_addr,
+            client_id,
+            credentials,
+            topics,
+            qos,
+            retain,
+            message,
+            decode,
+            count,
+            will,
+        })
+    }
+}
+
+fn parse_qos(value: &str) -> Result<u8, Error> {
+    match value {
+        "0" => Ok(0),
+        "1" => Ok(1),
+        _ => Err(invalid_input("El qos debe ser 0 o 1.")),
+    }
+}
+
+fn parse_count(value: &str) -> Result<usize, Error> {
+    value
+        .parse::<usize>()
+        .ok()
+        .filter(|count| *count > 0)
+        .ok_or_else(|| invalid_input("La cantidad debe ser un número mayor a 0."))
+}
+
+fn invalid_input(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, msg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RxMqttArgs, RxMqttCommand};
+    use crate::mqtt::client::credentials::Credentials;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_1_pub_con_todas_las_opciones() {
+        let parsed = RxMqttArgs::parse(&args(
+            "pub -h 10.0.0.5 -p 9191 -i operador -u usuario1 -P contraseña1 -t inc -m hola -q 1 -r \
+             --will-topic desc --will-message chau --will-retain",
+        ))
+        .unwrap();
+
+        assert_eq!(parsed.command, RxMqttCommand::Pub);
+        assert_eq!(parsed.broker_addr, "10.0.0.5:9191".parse().unwrap());
+        assert_eq!(parsed.client_id, "operador");
+        assert_eq!(parsed.credentials, Credentials::new("usuario1", "contraseña1"));
+        assert_eq!(parsed.topics, vec!["inc".to_string()]);
+        assert_eq!(parsed.message, Some("hola".to_string()));
+        assert_eq!((parsed.qos, parsed.retain), (1, true));
+        let will = parsed.will.unwrap();
+        assert_eq!((will.get_will_topic(), will.get_will_msg_content()), ("desc".to_string(), "chau".to_string()));
+        assert_eq!((will.get_qos(), will.get_will_retain()), (1, 1));
+    }
+
+    #[test]
+    fn test_2_valores_por_defecto_y_cantidad_de_pings() {
+        let parsed = RxMqttArgs::parse(&args("ping")).unwrap();
+
+        assert_eq!(parsed.broker_addr, "127.0.0.1:9090".parse().unwrap());
+        assert_eq!(parsed.credentials, Credentials::default());
+        assert_eq!(parsed.count, Some(4));
+        assert!(parsed.will.is_none());
+    }
+
+    #[test]
+    fn test_3_argumentos_invalidos_dan_error() {
+        assert!(RxMqttArgs::parse(&args("")).is_err());
+        assert!(RxMqttArgs::parse(&args("pub -t inc")).is_err());
+        assert!(RxMqttArgs::parse(&args("sub -d")).is_err());
+        assert!(RxMqttArgs::parse(&args("sub -t inc -q 2")).is_err());
+        assert!(RxMqttArgs::parse(&args("sub -t inc -u usuario1")).is_err());
+        assert!(RxMqttArgs::parse(&args("sub -t inc --will-topic desc")).is_err());
+        assert!(RxMqttArgs::parse(&args("sub -t inc -c 0")).is_err());
+        assert!(RxMqttArgs::parse(&args("sub -t")).is_err());
+        assert!(RxMqttArgs::parse(&args("sub -t inc --verbose")).is_err());
+    }
+}
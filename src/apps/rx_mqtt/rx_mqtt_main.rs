@@ -0,0 +1,140 @@
+use std::{
+    io::{Error, ErrorKind},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use rustx::apps::rx_mqtt::{
+    payload_printer::{encode_app_payload, format_payload},
+    rx_mqtt_args::{RxMqttArgs, RxMqttCommand, USAGE},
+};
+use rustx::logging::string_logger::StringLogger;
+use rustx::mqtt::{client::mqtt_client::MQTTClient, messages::publish_message::PublishMessage};
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+const PING_INTERVAL: Duration = Duration::from_secs(1);
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Publica el mensaje de los argumentos. Con `decode`, el mensaje es el json del tipo del topic.
+fn publish(args: &RxMqttArgs, mqtt_client: &mut MQTTClient) -> Result<(), Error> {
+    let topic = &args.topics[0];
+    let message = args.message.as_deref().unwrap_or_default();
+    let payload = if args.decode {
+        encode_app_payload(topic, message)?
+    } else {
+        message.as_bytes().to_vec()
+    };
+
+    if args.retain {
+        mqtt_client.mqtt_publish_retained(topic, &payload, args.qos)?;
+    } else {
+        mqtt_client.mqtt_publish(topic, &payload, args.qos)?;
+    }
+    println!("Publicado en {}: {} bytes.", topic, payload.len());
+    Ok(())
+}
+
+/// Se suscribe a los topics de los argumentos y muestra los mensajes recibidos, hasta recibir `count` mensajes,
+/// o SIGINT o SIGTERM.
+fn subscribe(
+    args: &RxMqttArgs,
+    mqtt_client: &mut MQTTClient,
+    publish_msg_rx: &Receiver<PublishMessage>,
+) -> Result<(), Error> {
+    let stop = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(SIGINT, stop.clone())?;
+    signal_hook::flag::register(SIGTERM, stop.clone())?;
+
+    mqtt_client.mqtt_subscribe(args.topics.iter().map(|topic| (topic.to_string(), args.qos)).collect())?;
+    println!("Suscripto a {} (Ctrl+C para terminar)...", args.topics.join(", "));
+
+    let mut received = 0;
+    while !stop.load(Ordering::Relaxed) && args.count.is_none_or(|count| received < count) {
+        match publish_msg_rx.recv_timeout(STOP_CHECK_INTERVAL) {
+            Ok(msg) => {
+                received += 1;
+                let retained = if msg.is_retained() { " (retain)" } else { "" };
+                println!(
+                    "[{}]{} {}",
+                    msg.get_topic(),
+                    retained,
+                    format_payload(&msg.get_topic(), &msg.get_payload(), args.decode)
+                );
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(Error::new(ErrorKind::NotConnected, "Se cerró la conexión con el broker."))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Envía `count` pings al broker, mostrando cuánto tarda cada respuesta.
+/// Devuelve error si no se recibió ninguna.
+fn ping(args: &RxMqttArgs, mqtt_client: &mut MQTTClient) -> Result<(), Error> {
+    let count = args.count.unwrap_or(1);
+    let mut answered = 0;
+    for i in 1..=count {
+        match mqtt_client.mqtt_ping(PING_TIMEOUT) {
+            Ok(rtt) => {
+                answered += 1;
+                println!("pingresp de {}: n={} tiempo={:.2} ms", args.broker_addr, i, rtt.as_secs_f64() * 1000.0);
+            }
+            Err(e) => println!("Sin respuesta de {}: n={} ({})", args.broker_addr, i, e),
+        }
+        if i < count {
+            thread::sleep(PING_INTERVAL);
+        }
+    }
+    println!("{} pings enviados, {} respondidos.", count, answered);
+    if answered == 0 {
+        return Err(Error::new(ErrorKind::TimedOut, "El broker no respondió ningún ping."));
+    }
+    Ok(())
+}
+
+fn run(args: &RxMqttArgs, logger: &StringLogger) -> Result<(), Error> {
+    let (mut mqtt_client, publish_msg_rx, _handle) = MQTTClient::mqtt_connect_to_broker_with_credentials(
+        args.client_id.to_string(),
+        &args.broker_addr,
+        args.will.clone(),
+        args.credentials.clone(),
+        logger.clone_ref(),
+    )?;
+
+    let res = match args.command {
+        RxMqttCommand::Pub => publish(args, &mut mqtt_client),
+        RxMqttCommand::Sub => subscribe(args, &mut mqtt_client, &publish_msg_rx),
+        RxMqttCommand::Ping => ping(args, &mut mqtt_client),
+    };
+    mqtt_client.mqtt_disconnect()?;
+    res
+}
+
+fn main() -> Result<(), Error> {
+    let argv = std::env::args().skip(1).collect::<Vec<String>>();
+    if argv.iter().any(|arg| arg == "--help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    let args = RxMqttArgs::parse(&argv)?;
+    let (mut logger, handle_logger) = StringLogger::create_logger(args.client_id.to_string());
+
+    let res = run(&args, &logger);
+    if let Err(e) = &res {
+        println!("Error: {:?}", e);
+    }
+
+    logger.stop_logging();
+    if handle_logger.join().is_err() {
+        println!("Error al esperar al hijo para string logger writer.")
+    }
+    res
+}
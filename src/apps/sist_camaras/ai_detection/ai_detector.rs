@@ -1,19 +1,15 @@
 use rand::{thread_rng, Rng};
-use reqwest::{
-    blocking::Client,
-    header::{HeaderMap, CONTENT_TYPE},
-};
 use std::{
     error::Error,
-    io::ErrorKind,
-    sync::{mpsc, Arc, Mutex},
+    sync::{mpsc, Arc},
 };
 
 use crate::{
     apps::{
+        geo,
         incident_data::{incident::Incident, incident_source::IncidentSource},
         sist_camaras::{
-            ai_detection::{api_credentials::ApiCredentials, properties::DetectorProperties},
+            ai_detection::{ai_provider::AiProvider, properties::DetectorProperties},
             types::shareable_cameras_type::ShCamerasType,
         },
     },
@@ -27,8 +23,8 @@ use crate::{
 pub struct AutomaticIncidentDetector {
     cameras: ShCamerasType,
     tx: mpsc::Sender<Incident>,
-    last_incident_id: Arc<Mutex<u8>>,
     properties: DetectorProperties,
+    ai_provider: Arc<dyn AiProvider>,
     logger: StringLogger,
 }
 
@@ -37,13 +33,14 @@ impl AutomaticIncidentDetector {
         cameras: ShCamerasType,
         tx: mpsc::Sender<Incident>,
         properties: DetectorProperties,
+        ai_provider: Arc<dyn AiProvider>,
         logger: StringLogger,
     ) -> Self {
         Self {
             cameras,
             tx,
-            last_incident_id: Arc::new(Mutex::new(0)),
             properties,
+            ai_provider,
             logger,
         }
     }
@@ -52,32 +49,17 @@ impl AutomaticIncidentDetector {
         Self {
             cameras: self.cameras.clone(),
             tx: self.tx.clone(),
-            last_incident_id: self.last_incident_id.clone(),
             properties: self.properties.clone(),
+            ai_provider: Arc::clone(&self.ai_provider),
             logger: self.logger.clone_ref(),
         }
     }
 
-    /// Lee la imagen de `image_path`, se la envía al proveedor de ia y analiza su respuesta para concluir si
+    /// Recibe la imagen tomada por la cámara `cam_id`, se la envía al proveedor de ia y analiza su respuesta para concluir si
     /// la imagen contiene o no un incidente. En caso afirmativo, se procesa al incidente.
-    pub fn process_image(&mut self, image: Vec<u8>, cam_id: u8) -> Result<(), Box<dyn Error>> {
-        let api_credentials = ApiCredentials::new(self.properties.get_api_credentials_file_path());
-
-        let (client, headers) = create_client_and_headers(&api_credentials)?;
-
-        println!("DEBUG: Image size: {}", image.len()); // debug
-
+    pub fn process_image(&mut self, image: Vec<u8>, cam_id: u32) -> Result<(), Box<dyn Error>> {
         // Se envía la imagen al proveedor
-        let res = client
-            .post(api_credentials.get_endpoint())
-            .headers(headers)
-            .body(image)
-            .send()?;
-
-        println!("DEBUG: res.status: {}", res.status()); // debug
-        //println!("AUX: res.text: {:?}", res.text()); // debug, aux, ahora borro esta línea
-
-        let res_text = res.text()?;
+        let res_text = self.ai_provider.analyze_image(image)?;
         let incident_probability = self.process_response(&res_text)?;
 
         println!("Detector: Probability: {:?}", incident_probability);
@@ -117,8 +99,7 @@ impl AutomaticIncidentDetector {
             Ok(incident_probability)
         } else {
             self.logger.log(format!("Response raw recibida: {}.", res_json));
-            Err(Box::new(std::io::Error::new(
-                ErrorKind::Other,
+            Err(Box::new(std::io::Error::other(
                 "Error al obtener la incident_probability.",
             )))
         }
@@ -138,8 +119,7 @@ impl AutomaticIncidentDetector {
                 "Response API es error: code {}, message: {}.",
                 error_code, error_msg
             );
-            return Err(Box::new(std::io::Error::new(
-                ErrorKind::Other,
+            return Err(Box::new(std::io::Error::other(
                 displayable_error,
             )));
         }
@@ -173,12 +153,11 @@ impl AutomaticIncidentDetector {
 
     /// Recibe el image_path de la imagen en la que se detectó un incidente, crea el Incident y lo envía internamente para
     /// ser publicado por MQTT.
-    fn process_incident(&mut self, cam_id: u8) -> Result<(), Box<dyn Error>> {
+    fn process_incident(&mut self, cam_id: u32) -> Result<(), Box<dyn Error>> {
         // obtenemos la posición
         let incident_position: (f64, f64) = self.get_incident_position(cam_id)?;
-        // creamos el incidente
-        let inc_id = self.get_next_incident_id()?;
-        let incident = Incident::new(inc_id, incident_position, IncidentSource::Automated);
+        // creamos el incidente, con un id que no se repite aunque haya varios sistemas de cámaras
+        let incident = Incident::new(Incident::generate_id(), incident_position, IncidentSource::Automated);
 
         println!("Detector: Incidente creado! {:?}", incident);
         self.logger
@@ -190,65 +169,31 @@ impl AutomaticIncidentDetector {
 
     /// Genera una ubicación de incidente aleatoria
     /// dentro del rango de la camara que detectó el incidente.
-    fn get_incident_position(&self, camera_id: u8) -> Result<(f64, f64), std::io::Error> {
+    fn get_incident_position(&self, camera_id: u32) -> Result<(f64, f64), std::io::Error> {
         if let Ok(cameras) = self.cameras.lock() {
             if let Some(camera) = cameras.get(&camera_id) {
-                let (x, y) = camera.get_position();
-                let range = camera.get_range_area();
-
                 let mut rng = thread_rng();
 
-                // Genera un desplazamiento aleatorio dentro del rango para x e y
-                let dx = rng.gen_range(0.0..=range);
-                let dy = rng.gen_range(0.0..=range);
+                // Genera un desplazamiento aleatorio dentro del círculo del rango, en metros
+                let distance = camera.get_range() * rng.gen_range(0.0..1.0_f64).sqrt();
+                let bearing = rng.gen_range(0.0..360.0);
 
                 // Calcula las nuevas coordenadas dentro del rango de la cámara
-                let new_x = x + dx as f64;
-                let new_y = y + dy as f64;
-
-                return Ok((new_x, new_y));
+                return Ok(geo::destination_point(camera.get_position(), bearing, distance));
             }
         }
 
-        Err(std::io::Error::new(
-            ErrorKind::Other,
+        Err(std::io::Error::other(
             "Error al obtener la camera del hashmap en get_incident_position.",
         ))
     }
-
-    /// Obtiene el siguiente incident id disponible para utilizar.
-    /// Al ser éste un programa multihilo, es necesario que el manejo de esta variable sea atómico
-    /// para no tener problemas de concurrencia que lleven a ids duplicados.
-    fn get_next_incident_id(&mut self) -> Result<u8, std::io::Error> {
-        if let Ok(mut last) = self.last_incident_id.lock() {
-            *last += 1;
-            return Ok(*last);
-        }
-        Err(std::io::Error::new(
-            ErrorKind::Other,
-            "Detector: Error al tomar el lock",
-        ))
-    }
-}
-
-fn create_client_and_headers(
-    api_credentials: &ApiCredentials,
-) -> Result<(Client, HeaderMap), Box<dyn Error>> {
-    let client = Client::new();
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        "Prediction-Key",
-        api_credentials.get_prediction_key().parse()?,
-    );
-    headers.insert(CONTENT_TYPE, "application/octet-stream".parse()?);
-    Ok((client, headers))
 }
 
 
 #[cfg(test)]
 mod test {
     use std::{collections::HashMap, sync::{mpsc, Arc, Mutex}};
-    use crate::{apps::{incident_data::incident::Incident, sist_camaras::ai_detection::properties::DetectorProperties}, logging::string_logger::StringLogger};
+    use crate::{apps::{incident_data::incident::Incident, sist_camaras::ai_detection::{ai_provider::AzureAiProvider, properties::DetectorProperties}}, logging::string_logger::StringLogger};
     use super::AutomaticIncidentDetector;
 
     // Devuelve un json de prueba, como una str.
@@ -290,7 +235,8 @@ mod test {
         AutomaticIncidentDetector::new(
             Arc::new(Mutex::new(HashMap::new())),
             inc_tx,
-            properties,
+            properties.clone(),
+            Arc::new(AzureAiProvider::new(properties.get_api_credentials_file_path())),
             logger,
         )    
     }
@@ -4,7 +4,7 @@ use std::{
     error::Error,
     ffi::OsStr,
     fs,
-    io::{Error as ioError, ErrorKind},
+    io::Error as ioError,
     path::Path,
     sync::{
         mpsc::{self, Receiver, Sender},
@@ -18,7 +18,9 @@ use crate::{
         incident_data::incident::Incident,
         sist_camaras::{
             ai_detection::{
-                ai_detector::AutomaticIncidentDetector, properties::DetectorProperties,
+                ai_detector::AutomaticIncidentDetector,
+                ai_provider::{AiProvider, AzureAiProvider},
+                properties::DetectorProperties,
             },
             types::shareable_cameras_type::ShCamerasType,
         },
@@ -26,7 +28,8 @@ use crate::{
     logging::string_logger::StringLogger,
 };
 
-const PROPERTIES_FILE: &str = "./src/apps/sist_camaras/ai_detection/properties.txt";
+/// Archivo de configuración del detector, que indica también dónde están los directorios de imágenes de las cámaras.
+pub const PROPERTIES_FILE: &str = "./src/apps/sist_camaras/ai_detection/properties.txt";
 
 #[derive(Debug)]
 /// Se encarga de inicializar todo lo relacionado a directorios, monitorearlos, y threads,
@@ -37,6 +40,7 @@ pub struct AIDetectorManager {
     inc_tx: Sender<Incident>,
     exit_requested: Arc<Mutex<bool>>,
     properties: DetectorProperties,
+    ai_provider: Arc<dyn AiProvider>,
     logger: StringLogger,
 }
 
@@ -49,13 +53,27 @@ impl AIDetectorManager {
         logger: StringLogger,
     ) -> Result<Self, ioError> {
         let properties = DetectorProperties::new(PROPERTIES_FILE)?;
+        let ai_provider = Arc::new(AzureAiProvider::new(properties.get_api_credentials_file_path()));
+        Self::run_with(cameras, inc_tx, exit_rx, properties, ai_provider, logger)
+    }
 
+    /// Como `run`, pero con las `properties` y el proveedor de ia recibidos, en lugar de los del archivo de configuración
+    /// (ie para correr el detector contra un proveedor simulado).
+    pub fn run_with(
+        cameras: ShCamerasType,
+        inc_tx: mpsc::Sender<Incident>,
+        exit_rx: mpsc::Receiver<()>,
+        properties: DetectorProperties,
+        ai_provider: Arc<dyn AiProvider>,
+        logger: StringLogger,
+    ) -> Result<Self, ioError> {
         let er = Arc::new(Mutex::new(false));
         let detector_manager = Self {
             cameras,
             inc_tx,
             exit_requested: er.clone(),
             properties,
+            ai_provider,
             logger,
         };
 
@@ -103,6 +121,7 @@ impl AIDetectorManager {
             self.cameras.clone(),
             self.inc_tx.clone(),
             self.properties.clone(),
+            Arc::clone(&self.ai_provider),
             logger_ai,
         );
 
@@ -169,8 +188,8 @@ impl AIDetectorManager {
         Ok(())
     }
 
-    /// Crea un subdirectorio de `base_dir` de nombre "camera_i" donde `i` es el u8 recibido.
-    fn create_subdir(&self, base_dir: &Path, i: u8) -> Result<(), ioError> {
+    /// Crea un subdirectorio de `base_dir` de nombre "camera_i" donde `i` es el id recibido.
+    fn create_subdir(&self, base_dir: &Path, i: u32) -> Result<(), ioError> {
         // Concatena el nombre del subdir a crear, al dir base
         let subdir = format!("camera_{}", i);
         let new_dir_path = base_dir.join(subdir);
@@ -220,8 +239,7 @@ impl AIDetectorManager {
                 return Ok(());
             }
         }
-        Err(Box::new(ioError::new(
-            ErrorKind::Other,
+        Err(Box::new(ioError::other(
             "Extensión inválida.",
         )))
     }
@@ -265,8 +283,7 @@ fn read_image(image_path: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
 
     println!("DEBUG: Image size en read_image: {}", image_buffer.len()); // debug
     if image_buffer.is_empty() {
-        return Err(Box::new(ioError::new(
-            ErrorKind::Other,
+        return Err(Box::new(ioError::other(
             "La imagen tiene tamaño 0.",
         )));
     }
@@ -276,13 +293,13 @@ fn read_image(image_path: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
 /// Recibe el path de la imagen que se está procesando, obtiene el id
 /// de la cámara que capturó dicha imagen. Es decir la parte que sigue a "camera_"
 /// de su carpeta padre.
-fn extract_camera_id(path: &Path) -> Option<u8> {
+fn extract_camera_id(path: &Path) -> Option<u32> {
     // Obtiene el nombre del directorio padre
     path.parent()
         .and_then(|parent| parent.file_name())
         .and_then(|file_name| file_name.to_str())
         .and_then(|name| {
-            // El nombre del directorio tiene el formato "camera_u8"
+            // El nombre del directorio tiene el formato "camera_id"
             let prefix = "camera_";
             if name.starts_with(prefix) {
                 if let Some(cam_id) = name.strip_prefix(prefix) {
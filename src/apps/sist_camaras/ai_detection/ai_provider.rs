@@ -0,0 +1,62 @@
+use reqwest::{
+    blocking::Client,
+    header::{HeaderMap, CONTENT_TYPE},
+};
+use std::{error::Error, fmt::Debug};
+
+use super::api_credentials::ApiCredentials;
+
+/// Proveedor de inteligencia artificial al que el detector le envía las imágenes de las cámaras.
+/// Devuelve la respuesta en crudo (json con las `predictions`), que el detector interpreta.
+pub trait AiProvider: Debug + Send + Sync {
+    fn analyze_image(&self, image: Vec<u8>) -> Result<String, Box<dyn Error>>;
+}
+
+/// Proveedor que consulta la api de predicción de Azure Custom Vision,
+/// con la key y el endpoint del archivo de credenciales.
+#[derive(Debug)]
+pub struct AzureAiProvider {
+    api_credentials_file_path: String,
+}
+
+impl AzureAiProvider {
+    pub fn new(api_credentials_file_path: String) -> Self {
+        Self {
+            api_credentials_file_path,
+        }
+    }
+}
+
+impl AiProvider for AzureAiProvider {
+    fn analyze_image(&self, image: Vec<u8>) -> Result<String, Box<dyn Error>> {
+        let api_credentials = ApiCredentials::new(self.api_credentials_file_path.to_string());
+
+        let (client, headers) = create_client_and_headers(&api_credentials)?;
+
+        println!("DEBUG: Image size: {}", image.len()); // debug
+
+        // Se envía la imagen al proveedor
+        let res = client
+            .post(api_credentials.get_endpoint())
+            .headers(headers)
+            .body(image)
+            .send()?;
+
+        println!("DEBUG: res.status: {}", res.status()); // debug
+
+        Ok(res.text()?)
+    }
+}
+
+fn create_client_and_headers(
+    api_credentials: &ApiCredentials,
+) -> Result<(Client, HeaderMap), Box<dyn Error>> {
+    let client = Client::new();
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "Prediction-Key",
+        api_credentials.get_prediction_key().parse()?,
+    );
+    headers.insert(CONTENT_TYPE, "application/octet-stream".parse()?);
+    Ok((client, headers))
+}
@@ -1,4 +1,5 @@
 pub mod ai_detector_manager;
 pub mod ai_detector;
+pub mod ai_provider;
 pub mod api_credentials;
 pub mod properties;
\ No newline at end of file
@@ -26,8 +26,7 @@ impl DetectorProperties {
             base_dir = String::from(prop);
         } else {
             println!("No se encontró la propiedad 'base_dir");
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Falta propiedad base_dir.",
             ));
         }
@@ -37,8 +36,7 @@ impl DetectorProperties {
             api_credentials_file_path = String::from(prop);
         } else {
             println!("No se encontró la propiedad 'api_credentials_file_path");
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Falta propiedad api_credentials_file_path.",
             ));
         }
@@ -48,8 +46,7 @@ impl DetectorProperties {
             inc_tag = String::from(prop);
         } else {
             println!("No se encontró la propiedad 'inc_tag");
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Falta propiedad inc_tag.",
             ));
         }
@@ -61,7 +58,7 @@ impl DetectorProperties {
                 .map_err(|_| Error::new(ErrorKind::InvalidInput, "inc_threshold"))?;
         } else {
             println!("No se encontró la propiedad 'inc_threshold");
-            return Err(Error::new(ErrorKind::Other, "Falta propiedad inc_threshold."));
+            return Err(Error::other("Falta propiedad inc_threshold."));
         }
 
         let img_valid_extension1: String;
@@ -69,8 +66,7 @@ impl DetectorProperties {
             img_valid_extension1 = String::from(prop);
         } else {
             println!("No se encontró la propiedad 'img_valid_extension1");
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Falta propiedad img_valid_extension1.",
             ));
         }
@@ -80,8 +76,7 @@ impl DetectorProperties {
             img_valid_extension2 = String::from(prop);
         } else {
             println!("No se encontró la propiedad 'img_valid_extension2");
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Falta propiedad img_valid_extension2.",
             ));
         }
@@ -0,0 +1,470 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Error, ErrorKind},
+};
+
+use crate::apps::{geo, properties::Properties};
+
+use super::{
+    camera::{Camera, DEFAULT_BORDER_RANGE_METERS},
+    camera_index::CameraGridIndex,
+    camera_registry::camera_record::CameraRecord,
+};
+
+/// Archivo de configuración de sistema cámaras, del que se leen la política de lindantes y la de salud de las cámaras.
+pub const SIST_CAMARAS_PROPERTIES_FILE: &str = "./sistema_camaras.properties";
+
+/// Cómo se calculan las cámaras lindantes, y cuáles se activan ante un incidente.
+/// - radius: distancia, en metros, hasta la que dos cámaras son lindantes;
+/// - max_bordering: máxima cantidad de lindantes calculadas de cada cámara (sin contar las fijadas manualmente), o sin límite;
+/// - activation_depth: hasta qué lindantes se activan con la cámara que registra un incidente (1: sus lindantes,
+///   2: también las lindantes de sus lindantes, 0: ninguna).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BorderingPolicy {
+    radius: f64,
+    max_bordering: Option<usize>,
+    activation_depth: u32,
+}
+
+impl Default for BorderingPolicy {
+    fn default() -> Self {
+        Self {
+            radius: DEFAULT_BORDER_RANGE_METERS,
+            max_bordering: None,
+            activation_depth: 1,
+        }
+    }
+}
+
+impl BorderingPolicy {
+    /// Crea una política de lindantes. Devuelve error si el radio no es un número de metros válido.
+    pub fn new(
+        radius: f64,
+        max_bordering: Option<usize>,
+        activation_depth: u32,
+    ) -> Result<Self, Error> {
+        if !radius.is_finite() || radius < 0.0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Radio de lindantes no válido: {}.", radius),
+            ));
+        }
+        Ok(Self {
+            radius,
+            max_bordering,
+            activation_depth,
+        })
+    }
+
+    /// Lee la política del archivo de properties `properties_file`, de las propiedades `bordering-radius-meters`,
+    /// `bordering-max-cameras` y `bordering-activation-depth`. Las que no estén (o el archivo si no existe) toman
+    /// el valor por defecto; `bordering-max-cameras` vacía es sin límite.
+    pub fn from_properties_file(properties_file: &str) -> Result<Self, Error> {
+        let properties = match Properties::new(properties_file) {
+            Ok(properties) => properties,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        let default = Self::default();
+
+        let radius = match properties.get("bordering-radius-meters") {
+            Some(prop) => parse_property(prop, "bordering-radius-meters")?,
+            None => default.radius,
+        };
+        let max_bordering = match properties.get("bordering-max-cameras") {
+            Some(prop) if !prop.is_empty() => Some(parse_property(prop, "bordering-max-cameras")?),
+            _ => default.max_bordering,
+        };
+        let activation_depth = match properties.get("bordering-activation-depth") {
+            Some(prop) => parse_property(prop, "bordering-activation-depth")?,
+            None => default.activation_depth,
+        };
+
+        Self::new(radius, max_bordering, activation_depth)
+    }
+
+    pub fn get_radius(&self) -> f64 {
+        self.radius
+    }
+
+    pub fn get_max_bordering(&self) -> Option<usize> {
+        self.max_bordering
+    }
+
+    pub fn get_activation_depth(&self) -> u32 {
+        self.activation_depth
+    }
+}
+
+/// Parsea el valor `prop` de la propiedad `name`, devolviendo error si no es válido.
+pub(crate) fn parse_property<T: std::str::FromStr>(prop: &str, name: &str) -> Result<T, Error> {
+    prop.parse().map_err(|_| {
+        println!("Error al parsear '{}'", name);
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Valor no válido para la propiedad {}: {}", name, prop),
+        )
+    })
+}
+
+/// Elección manual sobre si dos cámaras son lindantes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorderingPin {
+    /// Son lindantes, sin importar la distancia ni el máximo de lindantes.
+    Pinned,
+    /// No son lindantes, aunque estén cerca.
+    Unpinned,
+    /// Se calcula según la política de lindantes.
+    Automatic,
+}
+
+/// Pares de cámaras fijados o excluidos manualmente como lindantes. Los pares no tienen orden.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BorderingPins {
+    pinned: HashSet<(u32, u32)>,
+    unpinned: HashSet<(u32, u32)>,
+}
+
+impl BorderingPins {
+    /// Arma los pares a partir de lo guardado en los registros de las cámaras no borradas.
+    pub fn from_records(records: &[CameraRecord]) -> Self {
+        let mut pins = Self::default();
+        for record in records.iter().filter(|record| !record.is_deleted()) {
+            for other_id in record.get_pinned_bordering() {
+                pins.set(record.get_id(), *other_id, BorderingPin::Pinned);
+            }
+            for other_id in record.get_unpinned_bordering() {
+                pins.set(record.get_id(), *other_id, BorderingPin::Unpinned);
+            }
+        }
+        pins
+    }
+
+    /// Guarda la elección `pin` para el par de cámaras `id` y `other_id`, reemplazando la anterior.
+    pub fn set(&mut self, id: u32, other_id: u32, pin: BorderingPin) {
+        let pair = pair_of(id, other_id);
+        self.pinned.remove(&pair);
+        self.unpinned.remove(&pair);
+        match pin {
+            BorderingPin::Pinned => {
+                self.pinned.insert(pair);
+            }
+            BorderingPin::Unpinned => {
+                self.unpinned.insert(pair);
+            }
+            BorderingPin::Automatic => {}
+        }
+    }
+
+    /// Devuelve la elección para el par de cámaras `id` y `other_id`.
+    pub fn get(&self, id: u32, other_id: u32) -> BorderingPin {
+        let pair = pair_of(id, other_id);
+        if self.pinned.contains(&pair) {
+            BorderingPin::Pinned
+        } else if self.unpinned.contains(&pair) {
+            BorderingPin::Unpinned
+        } else {
+            BorderingPin::Automatic
+        }
+    }
+
+    /// Devuelve, ordenados, los ids de las cámaras fijadas como lindantes de la cámara `id`.
+    pub fn pinned_of(&self, id: u32) -> Vec<u32> {
+        others_of(&self.pinned, id)
+    }
+
+    /// Devuelve, ordenados, los ids de las cámaras excluidas como lindantes de la cámara `id`.
+    pub fn unpinned_of(&self, id: u32) -> Vec<u32> {
+        others_of(&self.unpinned, id)
+    }
+
+    /// Quita los pares de la cámara `id` (ej al borrarla). Devuelve, ordenados, los ids de las otras cámaras de esos pares.
+    pub fn remove_camera(&mut self, id: u32) -> Vec<u32> {
+        let mut others = self.pinned_of(id);
+        others.extend(self.unpinned_of(id));
+        others.sort();
+        self.pinned.retain(|(a, b)| *a != id && *b != id);
+        self.unpinned.retain(|(a, b)| *a != id && *b != id);
+        others
+    }
+}
+
+fn pair_of(id: u32, other_id: u32) -> (u32, u32) {
+    (id.min(other_id), id.max(other_id))
+}
+
+fn others_of(pairs: &HashSet<(u32, u32)>, id: u32) -> Vec<u32> {
+    let mut others: Vec<u32> = pairs
+        .iter()
+        .filter_map(|(a, b)| match (*a == id, *b == id) {
+            (true, _) => Some(*b),
+            (_, true) => Some(*a),
+            _ => None,
+        })
+        .collect();
+    others.sort();
+    others
+}
+
+/// Política de lindantes junto con las elecciones manuales: todo lo necesario para calcular las lindantes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BorderingRules {
+    policy: BorderingPolicy,
+    pins: BorderingPins,
+}
+
+impl BorderingRules {
+    pub fn new(policy: BorderingPolicy, pins: BorderingPins) -> Self {
+        Self { policy, pins }
+    }
+
+    pub fn get_policy(&self) -> &BorderingPolicy {
+        &self.policy
+    }
+
+    pub fn get_pins(&self) -> &BorderingPins {
+        &self.pins
+    }
+
+    pub fn get_pins_mut(&mut self) -> &mut BorderingPins {
+        &mut self.pins
+    }
+
+    /// Devuelve si la cámara puede sumar una lindante calculada, según el máximo de la política.
+    fn has_room_for_bordering(&self, camera: &Camera) -> bool {
+        let Some(max_bordering) = self.policy.max_bordering else {
+            return true;
+        };
+        let calculated = camera
+            .get_bordering_ids()
+            .iter()
+            .filter(|other_id| self.pins.get(camera.get_id(), **other_id) != BorderingPin::Pinned)
+            .count();
+        calculated < max_bordering
+    }
+
+    /// Devuelve si `camera` y `other`, que no son lindantes entre sí, deben serlo según la política:
+    /// si están en el radio de lindantes y ambas tienen lugar para una más.
+    fn should_border(&self, camera: &Camera, other: &Camera) -> bool {
+        geo::distance(camera.get_position(), other.get_position()) <= self.policy.radius
+            && self.has_room_for_bordering(camera)
+            && self.has_room_for_bordering(other)
+    }
+}
+
+/// Agrega mutuamente como lindantes a `new_camera` y a las cámaras de `cameras` que le correspondan según `rules`:
+/// las fijadas manualmente, y las que están en el radio de lindantes (buscándolas con `index`), de la más cercana
+/// a la más lejana mientras ambas tengan lugar. Las lindantes ya asignadas no se quitan para hacer lugar.
+/// Devuelve los ids de las cámaras que se agregaron como lindantes.
+pub fn add_bordering_cameras(
+    cameras: &mut HashMap<u32, Camera>,
+    index: &CameraGridIndex,
+    rules: &BorderingRules,
+    new_camera: &mut Camera,
+) -> Vec<u32> {
+    let new_id = new_camera.get_id();
+    let mut bordering = vec![];
+    for id in rules.pins.pinned_of(new_id) {
+        if let Some(camera) = cameras.get_mut(&id) {
+            camera.mutually_add_bordering(new_camera);
+            bordering.push(id);
+        }
+    }
+
+    let mut candidates: Vec<(f64, u32)> = index
+        .ids_within(new_camera.get_position(), rules.policy.radius)
+        .into_iter()
+        .filter(|id| *id != new_id && rules.pins.get(new_id, *id) == BorderingPin::Automatic)
+        .filter_map(|id| {
+            let camera = cameras.get(&id)?;
+            Some((
+                geo::distance(camera.get_position(), new_camera.get_position()),
+                id,
+            ))
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+    for (_distance, id) in candidates {
+        if !rules.has_room_for_bordering(new_camera) {
+            break;
+        }
+        if let Some(camera) = cameras.get_mut(&id) {
+            if rules.has_room_for_bordering(camera) {
+                camera.mutually_add_bordering(new_camera);
+                bordering.push(id);
+            }
+        }
+    }
+    bordering
+}
+
+/// Vuelve a decidir si las cámaras `id` y `other_id` de `cameras` son lindantes, según `rules`
+/// (ie luego de cambiar la elección manual para ese par). Devuelve error si alguna no existe.
+pub fn update_bordering_pair(
+    cameras: &mut HashMap<u32, Camera>,
+    rules: &BorderingRules,
+    id: u32,
+    other_id: u32,
+) -> Result<(), Error> {
+    let not_found =
+        |id: u32| Error::new(ErrorKind::NotFound, format!("La cámara {} no existe.", id));
+    let mut camera = cameras.remove(&id).ok_or_else(|| not_found(id))?;
+    let Some(other) = cameras.get_mut(&other_id) else {
+        cameras.insert(id, camera);
+        return Err(not_found(other_id));
+    };
+
+    camera.remove_from_list_if_bordering(other);
+    other.remove_from_list_if_bordering(&mut camera);
+    let should_border = match rules.pins.get(id, other_id) {
+        BorderingPin::Pinned => true,
+        BorderingPin::Unpinned => false,
+        BorderingPin::Automatic => rules.should_border(&camera, other),
+    };
+    if should_border {
+        camera.mutually_add_bordering(other);
+    }
+    cameras.insert(id, camera);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{
+        add_bordering_cameras, update_bordering_pair, BorderingPin, BorderingPins, BorderingPolicy,
+        BorderingRules,
+    };
+    use crate::apps::{
+        geo,
+        sist_camaras::{camera::Camera, camera_index::CameraGridIndex},
+    };
+
+    const OBELISCO: (f64, f64) = (-34.6037344, -58.3861838);
+
+    /// Cámaras alrededor del obelisco, cada `step` metros hacia el este y `step` metros hacia el norte.
+    fn grid_of_cameras(side: u32, step: f64) -> HashMap<u32, Camera> {
+        let mut cameras = HashMap::new();
+        for i in 0..side {
+            for j in 0..side {
+                let id = i * side + j;
+                let north = geo::destination_point(OBELISCO, 0.0, step * i as f64);
+                let (lat, lon) = geo::destination_point(north, 90.0, step * j as f64);
+                cameras.insert(id, Camera::new(id, lat, lon, 250.0));
+            }
+        }
+        cameras
+    }
+
+    fn sorted(mut ids: Vec<u32>) -> Vec<u32> {
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_1_las_lindantes_se_encuentran_con_el_indice() {
+        let mut cameras = grid_of_cameras(3, 400.0);
+        let index = CameraGridIndex::from_cameras(&cameras);
+
+        // Una cámara nueva en el centro: a 400 metros de 4 cámaras, y a unos 565 de las 4 en diagonal
+        let (lat, lon) = cameras.get(&4).unwrap().get_position();
+        let mut new_camera = Camera::new(9, lat, lon, 250.0);
+        let bordering = add_bordering_cameras(
+            &mut cameras,
+            &index,
+            &BorderingRules::default(),
+            &mut new_camera,
+        );
+
+        // La 4 está en la misma posición, y 1, 3, 5 y 7 a 400 metros
+        assert_eq!(sorted(bordering), vec![1, 3, 4, 5, 7]);
+        assert!(cameras.get(&1).unwrap().get_bordering_ids().contains(&9));
+        assert!(!cameras.get(&0).unwrap().get_bordering_ids().contains(&9));
+    }
+
+    #[test]
+    fn test_2_el_radio_y_el_maximo_de_lindantes_se_respetan() {
+        let mut cameras = grid_of_cameras(3, 400.0);
+        let index = CameraGridIndex::from_cameras(&cameras);
+        let (lat, lon) = cameras.get(&4).unwrap().get_position();
+
+        // Con 600 metros también son lindantes las de las diagonales, pero sólo entran las 2 más cercanas
+        let policy = BorderingPolicy::new(600.0, Some(2), 1).unwrap();
+        let rules = BorderingRules::new(policy, BorderingPins::default());
+        let mut new_camera = Camera::new(9, lat, lon, 250.0);
+        let bordering = add_bordering_cameras(&mut cameras, &index, &rules, &mut new_camera);
+        assert_eq!(bordering.len(), 2);
+        assert_eq!(bordering[0], 4);
+    }
+
+    #[test]
+    fn test_3_las_lindantes_fijadas_y_excluidas_manualmente_se_respetan() {
+        let mut cameras = grid_of_cameras(3, 400.0);
+        let index = CameraGridIndex::from_cameras(&cameras);
+        let (lat, lon) = cameras.get(&4).unwrap().get_position();
+
+        // La 0 está lejos pero se fija, y la 1 está cerca pero se excluye
+        let mut pins = BorderingPins::default();
+        pins.set(0, 9, BorderingPin::Pinned);
+        pins.set(9, 1, BorderingPin::Unpinned);
+        let mut rules = BorderingRules::new(BorderingPolicy::default(), pins);
+        let mut new_camera = Camera::new(9, lat, lon, 250.0);
+        let bordering = add_bordering_cameras(&mut cameras, &index, &rules, &mut new_camera);
+        assert_eq!(sorted(bordering), vec![0, 3, 4, 5, 7]);
+        assert_eq!(rules.get_pins().pinned_of(9), vec![0]);
+        assert_eq!(rules.get_pins().unpinned_of(9), vec![1]);
+        cameras.insert(9, new_camera);
+
+        // Al volver la 1 al cálculo automático, pasa a ser lindante
+        rules.get_pins_mut().set(1, 9, BorderingPin::Automatic);
+        update_bordering_pair(&mut cameras, &rules, 9, 1).unwrap();
+        assert!(cameras.get(&1).unwrap().get_bordering_ids().contains(&9));
+        assert!(cameras.get(&9).unwrap().get_bordering_ids().contains(&1));
+
+        // Y al excluir la 0, deja de serlo
+        rules.get_pins_mut().set(0, 9, BorderingPin::Unpinned);
+        update_bordering_pair(&mut cameras, &rules, 0, 9).unwrap();
+        assert!(!cameras.get(&9).unwrap().get_bordering_ids().contains(&0));
+        assert_eq!(rules.get_pins_mut().remove_camera(9), vec![0]);
+        assert!(update_bordering_pair(&mut cameras, &rules, 0, 99).is_err());
+        assert!(cameras.contains_key(&0));
+    }
+
+    #[test]
+    fn test_4_la_politica_se_lee_del_properties_con_valores_por_defecto() {
+        let path =
+            std::env::temp_dir().join(format!("rustx_bordering_{}.properties", std::process::id()));
+        let path_str = path.to_str().unwrap();
+
+        // Sin archivo, o sin las propiedades, se usan los valores por defecto
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            BorderingPolicy::from_properties_file(path_str).unwrap(),
+            BorderingPolicy::default()
+        );
+        std::fs::write(&path, "ip-server-mqtt=127.0.0.1\nbordering-max-cameras=\n").unwrap();
+        assert_eq!(
+            BorderingPolicy::from_properties_file(path_str).unwrap(),
+            BorderingPolicy::default()
+        );
+
+        std::fs::write(
+            &path,
+            "bordering-radius-meters=300\nbordering-max-cameras=4\nbordering-activation-depth=2\n",
+        )
+        .unwrap();
+        assert_eq!(
+            BorderingPolicy::from_properties_file(path_str).unwrap(),
+            BorderingPolicy::new(300.0, Some(4), 2).unwrap()
+        );
+
+        std::fs::write(&path, "bordering-radius-meters=-1\n").unwrap();
+        assert!(BorderingPolicy::from_properties_file(path_str).is_err());
+        std::fs::write(&path, "bordering-activation-depth=mucha\n").unwrap();
+        assert!(BorderingPolicy::from_properties_file(path_str).is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,4 +1,16 @@
-use crate::apps::{incident_data::incident_info::IncidentInfo, sist_camaras::camera_state::CameraState};
+use std::io::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::apps::geo::{self, METERS_PER_DEGREE_OF_LATITUDE};
+use crate::apps::{
+    incident_data::incident_info::IncidentInfo,
+    sist_camaras::{camera_fov::CameraFov, camera_health::CameraHealth, camera_state::CameraState},
+};
+use crate::apps::payload_codec::{
+    envelope::{Encoding, PayloadSchema},
+    versioned_payload::{check_legacy_len, narrow_id, unsupported_version_error, VersionedPayload},
+};
 
 #[derive(Debug, PartialEq)]
 /// Struct que representa el estado de una de las cámaras del sistema central de cámaras.
@@ -6,56 +18,64 @@ use crate::apps::{incident_data::incident_info::IncidentInfo, sist_camaras::came
 /// - id;
 /// - latitud y longitud
 /// - estado;
-/// - rango dentro del cual interesará manejar incidentes, simllar a un radio;
+/// - rango dentro del cual interesará manejar incidentes: el radio, en metros;
+/// - fov: campo de visión (hacia dónde apunta, con qué apertura y hasta qué distancia ve), por defecto en todas las direcciones;
 /// - border_cameras: vector con los ids de sus cámaras lindantes;
 /// - deleted: campo que indica si la Camera ha pasado por un borrado lógico en el sistema central de cámaras;
+/// - system_id: id del sistema de cámaras al que pertenece (varios pueden compartir el broker);
 /// - incs_being_managed: vector con los ids de los incidentes a los que la Camera está prestando atención, esto es, ids de los incidentes que ocasionan que esta Camera esté en estado activo.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Camera {
-    id: u8,
+    id: u32,
     latitude: f64,
     longitude: f64,
     state: CameraState,
-    range: u8,
-    border_cameras: Vec<u8>,
+    range: f64, // en metros
+    fov: CameraFov,
+    border_cameras: Vec<u32>,
     deleted: bool,
+    system_id: CameraSystemId,
+    #[serde(skip)] // No se envía, es propio del sistema central de cámaras.
     incs_being_managed: Vec<IncidentInfo>, // info (id y src) de los incidentes a los que está prestando atención
 }
 
 impl Camera {
-    /// Crea un struct `Camera`.
-    pub fn new(id: u8, latitude: f64, longitude: f64, range: u8) -> Self {
+    /// Crea un struct `Camera`, con su `range` en metros.
+    pub fn new(id: u32, latitude: f64, longitude: f64, range: f64) -> Self {
         Self {
             id,
             latitude,
             longitude,
             state: CameraState::SavingMode,
             range,
+            fov: CameraFov::default(),
             border_cameras: vec![],
             deleted: false,
+            system_id: DEFAULT_CAMERA_SYSTEM_ID,
             incs_being_managed: vec![],
         }
     }
 
-    /// Pasa un struct Camera a bytes.
-    pub fn to_bytes(&self) -> Vec<u8> {
+    /// Pasa un struct Camera a bytes, con el formato previo al envelope.
+    /// Devuelve error si su id o el de alguna lindante no entra en un byte.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
         let mut bytes = vec![];
-        bytes.push(self.id);
+        bytes.push(narrow_id(self.id as u128, Self::SCHEMA)?);
         bytes.extend_from_slice(&self.latitude.to_be_bytes());
         bytes.extend_from_slice(&self.longitude.to_be_bytes());
-        bytes.extend_from_slice(&self.state.to_byte());
-        bytes.extend_from_slice(&self.range.to_be_bytes());
+        bytes.extend_from_slice(&self.state.without_degraded().to_byte());
+        bytes.push(meters_to_legacy_range(self.range));
         bytes.extend_from_slice(&(self.border_cameras.len() as u8).to_be_bytes());
         for camera in &self.border_cameras {
-            bytes.push(*camera);
+            bytes.push(narrow_id(*camera as u128, Self::SCHEMA)?);
         }
         bytes.push(self.deleted as u8);
-        bytes
+        Ok(bytes)
     }
 
-    /// Lee bytes para devolver un struct Camera.
+    /// Lee bytes con el formato previo al envelope para devolver un struct Camera.
     pub fn from_bytes(bytes: &[u8]) -> Self {
-        let id = bytes[0];
+        let id = bytes[0] as u32;
         let latitude = f64::from_be_bytes([
             bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7], bytes[8],
         ]);
@@ -63,11 +83,11 @@ impl Camera {
             bytes[9], bytes[10], bytes[11], bytes[12], bytes[13], bytes[14], bytes[15], bytes[16],
         ]);
         let state = CameraState::from_byte([bytes[17]]);
-        let range = bytes[18];
+        let range = legacy_range_to_meters(bytes[18]);
         let border_cameras_len = bytes[19];
         let mut border_cameras = vec![];
         for i in 0..border_cameras_len {
-            border_cameras.push(bytes[20 + i as usize]);
+            border_cameras.push(bytes[20 + i as usize] as u32);
         }
         let deleted = bytes[20 + border_cameras_len as usize] == 1;
         Self {
@@ -76,8 +96,10 @@ impl Camera {
             longitude,
             state,
             range,
+            fov: CameraFov::default(), // El formato previo al envelope no tiene campo de visión
             border_cameras,
             deleted,
+            system_id: DEFAULT_CAMERA_SYSTEM_ID,
             incs_being_managed: vec![],
         }
     }
@@ -88,14 +110,44 @@ impl Camera {
         println!("Latitude: {}", self.latitude);
         println!("Longitude: {}", self.longitude);
         println!("Estado: {:?}", self.state);
-        println!("Rango de alcance: {}", self.range);
+        println!("Rango de alcance: {} m", self.range);
+        if !self.fov.is_circular() {
+            println!("Rumbo: {}°, ángulo de visión: {}°", self.fov.get_heading(), self.fov.get_angle());
+        }
+        if let Some(max_distance) = self.fov.get_max_distance() {
+            println!("Distancia máxima de visión: {} m", max_distance);
+        }
         println!("Cámaras lindantes: {:?}\n", self.border_cameras);
     }
 
     /// Devuelve si el incidente de coordenadas `(inc_coord_x, inc_coord_y)`
-    /// está en el rango de la cámara `Self`.
+    /// está en el campo de visión de la cámara `Self`: dentro de su rango (o de su distancia máxima de visión),
+    /// y del sector hacia el que apunta.
     pub fn will_register(&self, (latitude, longitude): (f64, f64)) -> bool {
-        self.is_within_range_from_self(latitude, longitude, self.range as f64)
+        if !self.is_within_range_from_self(latitude, longitude, self.get_coverage_distance()) {
+            return false;
+        }
+        let position = self.get_position();
+        // En la posición misma de la cámara no hay rumbo: se considera vista
+        position == (latitude, longitude)
+            || self
+                .fov
+                .contains_bearing(geo::initial_bearing(position, (latitude, longitude)))
+    }
+
+    /// Devuelve hasta qué distancia, en metros, ve la cámara: su rango, o su distancia máxima de visión si es menor.
+    pub fn get_coverage_distance(&self) -> f64 {
+        self.fov.get_distance_for(self.range)
+    }
+
+    /// Devuelve el campo de visión de la cámara.
+    pub fn get_fov(&self) -> CameraFov {
+        self.fov
+    }
+
+    /// Cambia el campo de visión de la cámara.
+    pub fn set_fov(&mut self, fov: CameraFov) {
+        self.fov = fov;
     }
 
     /// Modifica su estado al recibido por parámetro, y se marca un atributo
@@ -104,19 +156,27 @@ impl Camera {
         self.state = new_state;
     }
 
+    /// Cambia la posición y el rango (en metros) de la cámara. Sus lindantes deben recalcularse aparte.
+    pub fn set_position_and_range(&mut self, latitude: f64, longitude: f64, range: f64) {
+        self.latitude = latitude;
+        self.longitude = longitude;
+        self.range = range;
+    }
+
     /// Devuelve un vector con los ids de sus cámaras lindantes.
-    pub fn get_bordering_cams(&mut self) -> &mut Vec<u8> {
+    pub fn get_bordering_cams(&mut self) -> &mut Vec<u32> {
         &mut self.border_cameras
     }
 
     /// Agrega el inc_id a su lista de incidentes a los que le presta atención,
     /// y se cambia el estado a activo. Maneja su marcado.
+    /// Si está degradada, sólo lo agrega a la lista: pasará a activo cuando vuelva a funcionar.
     /// Devuelve si cambió su estado interno (a Activo).
     pub fn append_to_incs_being_managed(&mut self, inc_info: IncidentInfo) -> bool {
         let mut state_has_changed = false;
         self.incs_being_managed.push(inc_info);
         // Si ya estaba en estado activo, la dejo como estaba (para no marcarla como modificada)
-        if self.state != CameraState::Active {
+        if self.state != CameraState::Active && !self.state.is_degraded() {
             self.set_state_to(CameraState::Active);
             state_has_changed = true;
         };
@@ -124,14 +184,14 @@ impl Camera {
     }
 
     /// Elimina el inc_id de su lista de incidentes a los que les presta atención,
-    /// y si ya no le quedan incidentes, se cambia el estado a modo ahorro de energía.
+    /// y si ya no le quedan incidentes, se cambia el estado a modo ahorro de energía (salvo que esté degradada).
     /// Devuelve si cambió su estado interno (a Ahorro de energía).
     pub fn remove_from_incs_being_managed(&mut self, inc_info: IncidentInfo) -> bool {
         let mut state_has_changed = false;
         if let Some(pos_de_inc_info) = self.incs_being_managed.iter().position(|&x| x == inc_info) {
             self.incs_being_managed.remove(pos_de_inc_info);
             // Maneja su lista y se cambia el estado si corresponde
-            if self.incs_being_managed.is_empty() {
+            if self.incs_being_managed.is_empty() && !self.state.is_degraded() {
                 self.set_state_to(CameraState::SavingMode);
                 state_has_changed = true;
             }
@@ -139,8 +199,24 @@ impl Camera {
         state_has_changed
     }
 
+    /// Actualiza el estado según la salud de la cámara: pasa a `Offline` o `Fault` si está degradada, y al volver
+    /// a funcionar, a activo o a ahorro de energía según si hay incidentes a los que presta atención.
+    /// Devuelve si cambió su estado.
+    pub fn set_health(&mut self, health: CameraHealth) -> bool {
+        let new_state = match health {
+            CameraHealth::Offline => CameraState::Offline,
+            CameraHealth::Fault => CameraState::Fault,
+            CameraHealth::Healthy if !self.state.is_degraded() => self.state,
+            CameraHealth::Healthy if self.incs_being_managed.is_empty() => CameraState::SavingMode,
+            CameraHealth::Healthy => CameraState::Active,
+        };
+        let state_has_changed = new_state != self.state;
+        self.set_state_to(new_state);
+        state_has_changed
+    }
+
     /// Función getter utilizada con propósitos de debugging.
-    pub fn get_id_and_incs_for_debug_display(&self) -> (u8, Vec<IncidentInfo>) {
+    pub fn get_id_and_incs_for_debug_display(&self) -> (u32, Vec<IncidentInfo>) {
         (self.id, self.incs_being_managed.to_vec())
     }
 
@@ -154,12 +230,6 @@ impl Camera {
         self.deleted = true;
     }
 
-    /// Devuelve el rango ajustado de la cámara.
-    pub fn get_range_area(&self) -> f64 {
-        0.00135 + 0.0012 * self.range as f64
-        //self.range
-    }
-
     /// Devuelve la latitud de la cámara.
     pub fn get_latitude(&self) -> f64 {
         self.latitude
@@ -176,33 +246,62 @@ impl Camera {
     }
 
     /// Devuelve el id de la cámara.
-    pub fn get_id(&self) -> u8 {
+    pub fn get_id(&self) -> u32 {
         self.id
     }
 
+    /// Devuelve el rango de la cámara, en metros.
+    pub fn get_range(&self) -> f64 {
+        self.range
+    }
+
+    /// Devuelve el id del sistema de cámaras al que pertenece.
+    pub fn get_system_id(&self) -> CameraSystemId {
+        self.system_id
+    }
+
+    /// Asigna la cámara al sistema de cámaras `system_id`.
+    pub fn set_system_id(&mut self, system_id: CameraSystemId) {
+        self.system_id = system_id;
+    }
+
     /// Devuelve el estado en que se encuentra la cámara.
     pub fn get_state(&self) -> CameraState {
         self.state
     }
 
-    // Analiza si se encuentra la cámara recibida por parámetro dentro del border_range, en caso afirmativo:
+    // Analiza si se encuentra la cámara recibida por parámetro a no más de `border_range` metros, en caso afirmativo:
     // tanto self como la cámara recibida por parámetro agregan sus ids mutuamente a la lista de lindantes de la otra.
-    pub fn mutually_add_if_bordering(&mut self, candidate_bordering: &mut Camera) {
-        let const_border_range: f64 = 3.0; // Constante que debe ir en arch de configuración.
-                                           // Se fija si están en rango de lindantes.
+    pub fn mutually_add_if_bordering(&mut self, candidate_bordering: &mut Camera, border_range: f64) {
+        // Se fija si están en rango de lindantes.
         let in_range = self.is_within_range_from_self(
             candidate_bordering.get_latitude(),
             candidate_bordering.get_longitude(),
-            const_border_range,
+            border_range,
         );
 
         // Si sí, se agregan mutuamente como lindantes
         if in_range {
-            self.border_cameras.push(candidate_bordering.get_id());
-            candidate_bordering.border_cameras.push(self.id);
+            self.mutually_add_bordering(candidate_bordering);
+        }
+    }
+
+    /// Agrega mutuamente a self y a `other` como lindantes, sin importar la distancia (ej al fijarlas manualmente).
+    /// Si ya lo eran, no hace nada.
+    pub fn mutually_add_bordering(&mut self, other: &mut Camera) {
+        if !self.border_cameras.contains(&other.get_id()) {
+            self.border_cameras.push(other.get_id());
+        }
+        if !other.border_cameras.contains(&self.id) {
+            other.border_cameras.push(self.id);
         }
     }
 
+    /// Devuelve los ids de sus cámaras lindantes.
+    pub fn get_bordering_ids(&self) -> &[u32] {
+        &self.border_cameras
+    }
+
     pub fn remove_from_list_if_bordering(&mut self, camera_to_delete: &mut Camera) {
         // No necesito recalcular para borrarla; "si es lindante" en este contexto es "si está en la lista".        
         // Busco la pos del id de la camera_to_delete en mi lista de lindantes, y la elimino
@@ -215,54 +314,368 @@ impl Camera {
         }
     }
 
-    /// Calcula si se encuentra las coordenadas pasadas se encuentran dentro del rango pasado
+    /// Calcula si las coordenadas pasadas se encuentran a no más de `range` metros de la cámara.
     fn is_within_range_from_self(&self, latitude: f64, longitude: f64, range: f64) -> bool {
-        let lat_dist = self.latitude - latitude;
-        let long_dist = self.longitude - longitude;
-        let rad = f64::sqrt(lat_dist.powi(2) + long_dist.powi(2));
+        geo::distance(self.get_position(), (latitude, longitude)) <= range
+    }
+}
+
+/// Id de un sistema de cámaras. Cada cámara se identifica por el de su sistema junto con el suyo.
+pub type CameraSystemId = u32;
+
+/// Sistema de las cámaras que no indican otro, ie las de las versiones anteriores a la 6.
+pub const DEFAULT_CAMERA_SYSTEM_ID: CameraSystemId = 0;
+
+/// Distancia, en metros, hasta la que dos cámaras se consideran lindantes si no se configura otra
+/// (ver `BorderingPolicy`).
+pub const DEFAULT_BORDER_RANGE_METERS: f64 = 550.0;
+
+/// Hasta la versión 2, el rango era una cantidad de "cuadras", que cubría un radio de
+/// `0.00135 + 0.0012 * rango` grados. Se pasa a metros sobre la latitud.
+pub(crate) fn legacy_range_to_meters(range: u8) -> f64 {
+    (0.00135 + 0.0012 * range as f64) * METERS_PER_DEGREE_OF_LATITUDE
+}
+
+/// Inversa de `legacy_range_to_meters`, redondeada a la cantidad de cuadras más cercana que entre en un byte.
+fn meters_to_legacy_range(range: f64) -> u8 {
+    let blocks = (range / METERS_PER_DEGREE_OF_LATITUDE - 0.00135) / 0.0012;
+    blocks.round().clamp(0.0, u8::MAX as f64) as u8
+}
 
-        // Se modifica el range de las cámaras, ahora que son latitudes de verdad y no "3 4".
-        let adjusted_range = 0.00135 + 0.0012 * range;
+// id, latitud, longitud, estado, rango, y la cantidad de cámaras lindantes; luego sus ids y el flag de borrado.
+const CAMERA_FIXED_BYTES_LEN: usize = 1 + 8 + 8 + 1 + 1 + 1;
 
-        /*println!(
-            "Dio que la cuenta vale: {}, y adj_range vale: {}. Era rango: {}",
-            rad, adjusted_range, range
-        ); // debug []*/
-        rad <= (adjusted_range)
+/// `Camera` en la versión 3 del envelope, sin campo de visión.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CameraV3 {
+    id: u32,
+    latitude: f64,
+    longitude: f64,
+    state: CameraState,
+    range: f64,
+    border_cameras: Vec<u32>,
+    deleted: bool,
+}
+
+impl From<&Camera> for CameraV3 {
+    fn from(camera: &Camera) -> Self {
+        Self {
+            id: camera.id,
+            latitude: camera.latitude,
+            longitude: camera.longitude,
+            state: camera.state.without_degraded(),
+            range: camera.range,
+            border_cameras: camera.border_cameras.clone(),
+            deleted: camera.deleted,
+        }
     }
 }
 
-#[cfg(test)]
+impl From<CameraV3> for Camera {
+    fn from(v3: CameraV3) -> Self {
+        Self {
+            id: v3.id,
+            latitude: v3.latitude,
+            longitude: v3.longitude,
+            state: v3.state,
+            range: v3.range,
+            fov: CameraFov::default(),
+            border_cameras: v3.border_cameras,
+            deleted: v3.deleted,
+            system_id: DEFAULT_CAMERA_SYSTEM_ID,
+            incs_being_managed: vec![],
+        }
+    }
+}
+
+/// `Camera` en las versiones 4 y 5 del envelope, sin id de sistema. La 4 no tenía los estados degradados.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CameraV5 {
+    id: u32,
+    latitude: f64,
+    longitude: f64,
+    state: CameraState,
+    range: f64,
+    fov: CameraFov,
+    border_cameras: Vec<u32>,
+    deleted: bool,
+}
+
+impl CameraV5 {
+    /// Pasa los estados degradados a `SavingMode`, para enviarla en la versión 4.
+    pub(crate) fn without_degraded(mut self) -> Self {
+        self.state = self.state.without_degraded();
+        self
+    }
+}
+
+impl From<&Camera> for CameraV5 {
+    fn from(camera: &Camera) -> Self {
+        Self {
+            id: camera.id,
+            latitude: camera.latitude,
+            longitude: camera.longitude,
+            state: camera.state,
+            range: camera.range,
+            fov: camera.fov,
+            border_cameras: camera.border_cameras.clone(),
+            deleted: camera.deleted,
+        }
+    }
+}
+
+impl From<CameraV5> for Camera {
+    fn from(v5: CameraV5) -> Self {
+        Self {
+            id: v5.id,
+            latitude: v5.latitude,
+            longitude: v5.longitude,
+            state: v5.state,
+            range: v5.range,
+            fov: v5.fov,
+            border_cameras: v5.border_cameras,
+            deleted: v5.deleted,
+            system_id: DEFAULT_CAMERA_SYSTEM_ID,
+            incs_being_managed: vec![],
+        }
+    }
+}
+
+/// `Camera` en la versión 2 del envelope, con el rango en cuadras.
+#[derive(Serialize, Deserialize)]
+struct CameraV2 {
+    id: u32,
+    latitude: f64,
+    longitude: f64,
+    state: CameraState,
+    range: u8,
+    border_cameras: Vec<u32>,
+    deleted: bool,
+}
 
+/// `Camera` en la versión 1 del envelope, con los ids de un byte.
+#[derive(Serialize, Deserialize)]
+struct CameraV1 {
+    id: u8,
+    latitude: f64,
+    longitude: f64,
+    state: CameraState,
+    range: u8,
+    border_cameras: Vec<u8>,
+    deleted: bool,
+}
+
+impl VersionedPayload for Camera {
+    const SCHEMA: PayloadSchema = PayloadSchema::Camera;
+    // La versión 2 pasó los ids de un byte a u32, la 3 el rango de cuadras a metros, la 4 agregó el campo de visión,
+    // la 5 los estados degradados (`Offline` y `Fault`), que a las anteriores se envían como `SavingMode`,
+    // y la 6 el id del sistema de cámaras.
+    const CURRENT_VERSION: u8 = 6;
+
+    fn to_legacy_bytes(&self) -> Result<Vec<u8>, Error> {
+        self.to_bytes()
+    }
+
+    fn from_legacy_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        check_legacy_len(bytes, CAMERA_FIXED_BYTES_LEN, Self::SCHEMA)?;
+        let border_cameras_len = bytes[CAMERA_FIXED_BYTES_LEN - 1] as usize;
+        check_legacy_len(bytes, CAMERA_FIXED_BYTES_LEN + border_cameras_len + 1, Self::SCHEMA)?;
+        Ok(Self::from_bytes(bytes))
+    }
+
+    fn from_older_version(version: u8, body: &[u8], encoding: Encoding) -> Result<Self, Error> {
+        match version {
+            1 => {
+                let v1: CameraV1 = encoding.deserialize(body)?;
+                Ok(Self {
+                    id: v1.id as u32,
+                    latitude: v1.latitude,
+                    longitude: v1.longitude,
+                    state: v1.state,
+                    range: legacy_range_to_meters(v1.range),
+                    fov: CameraFov::default(),
+                    border_cameras: v1.border_cameras.into_iter().map(u32::from).collect(),
+                    deleted: v1.deleted,
+                    system_id: DEFAULT_CAMERA_SYSTEM_ID,
+                    incs_being_managed: vec![],
+                })
+            }
+            2 => {
+                let v2: CameraV2 = encoding.deserialize(body)?;
+                Ok(Self {
+                    id: v2.id,
+                    latitude: v2.latitude,
+                    longitude: v2.longitude,
+                    state: v2.state,
+                    range: legacy_range_to_meters(v2.range),
+                    fov: CameraFov::default(),
+                    border_cameras: v2.border_cameras,
+                    deleted: v2.deleted,
+                    system_id: DEFAULT_CAMERA_SYSTEM_ID,
+                    incs_being_managed: vec![],
+                })
+            }
+            3 => Ok(encoding.deserialize::<CameraV3>(body)?.into()),
+            // La 4 tiene el mismo formato que la 5, sólo que nunca trae estados degradados
+            4 | 5 => Ok(encoding.deserialize::<CameraV5>(body)?.into()),
+            _ => Err(unsupported_version_error(Self::SCHEMA, version)),
+        }
+    }
+
+    fn to_older_version(&self, version: u8, encoding: Encoding) -> Result<Vec<u8>, Error> {
+        match version {
+            1 => {
+                let border_cameras = self
+                    .border_cameras
+                    .iter()
+                    .map(|id| narrow_id(*id as u128, Self::SCHEMA))
+                    .collect::<Result<Vec<u8>, Error>>()?;
+                encoding.serialize(&CameraV1 {
+                    id: narrow_id(self.id as u128, Self::SCHEMA)?,
+                    latitude: self.latitude,
+                    longitude: self.longitude,
+                    state: self.state.without_degraded(),
+                    range: meters_to_legacy_range(self.range),
+                    border_cameras,
+                    deleted: self.deleted,
+                })
+            }
+            2 => encoding.serialize(&CameraV2 {
+                id: self.id,
+                latitude: self.latitude,
+                longitude: self.longitude,
+                state: self.state.without_degraded(),
+                range: meters_to_legacy_range(self.range),
+                border_cameras: self.border_cameras.clone(),
+                deleted: self.deleted,
+            }),
+            3 => encoding.serialize(&CameraV3::from(self)),
+            4 => encoding.serialize(&CameraV5::from(self).without_degraded()),
+            5 => encoding.serialize(&CameraV5::from(self)),
+            _ => Err(unsupported_version_error(Self::SCHEMA, version)),
+        }
+    }
+}
+
+#[cfg(test)]
 mod test {
-    use super::Camera;
+    use super::{
+        legacy_range_to_meters, meters_to_legacy_range, Camera, CameraV2, DEFAULT_BORDER_RANGE_METERS,
+        DEFAULT_CAMERA_SYSTEM_ID,
+    };
+    use crate::apps::{
+        geo,
+        payload_codec::{envelope::Encoding, versioned_payload::VersionedPayload},
+        incident_data::{incident_info::IncidentInfo, incident_source::IncidentSource},
+        sist_camaras::{camera_fov::CameraFov, camera_health::CameraHealth, camera_state::CameraState},
+    };
+    use crate::mqtt::client::typed_topic::MqttPayload;
 
     #[test]
     fn test_1_camera_to_y_from_bytes() {
-        let camera = Camera::new(12, 3.0, 4.0, 5);
+        // El formato previo al envelope guarda el rango en cuadras
+        let camera = Camera::new(12, 3.0, 4.0, legacy_range_to_meters(5));
 
-        let bytes = camera.to_bytes();
+        let bytes = camera.to_bytes().unwrap();
 
         let camera_reconstruida = Camera::from_bytes(&bytes);
 
         assert_eq!(camera_reconstruida, camera);
     }
 
+    #[test]
+    fn test_1b_from_legacy_bytes_de_un_payload_incompleto_da_error() {
+        let mut camera = Camera::new(12, 3.0, 4.0, legacy_range_to_meters(5));
+        camera.border_cameras = vec![1, 2];
+        let bytes = camera.to_legacy_bytes().unwrap();
+
+        assert_eq!(Camera::from_legacy_bytes(&bytes).unwrap(), camera);
+        assert!(Camera::from_legacy_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Camera::from_legacy_bytes(&bytes[..3]).is_err());
+    }
+
+    #[test]
+    fn test_1c_se_decodifica_la_version_1_y_un_id_que_no_entra_en_ella_da_error() {
+        let mut camera = Camera::new(12, 3.0, 4.0, legacy_range_to_meters(5));
+        camera.border_cameras = vec![1, 2];
+        let body = camera.to_older_version(1, Encoding::Bincode).unwrap();
+        assert_eq!(Camera::from_older_version(1, &body, Encoding::Bincode).unwrap(), camera);
+
+        camera.border_cameras.push(300);
+        assert!(camera.to_older_version(1, Encoding::Bincode).is_err());
+        assert!(camera.to_legacy_bytes().is_err());
+    }
+
+    #[test]
+    fn test_1d_la_version_2_con_rango_en_cuadras_se_pasa_a_metros() {
+        let camera = Camera::new(300, 3.0, 4.0, 250.0);
+        let body = camera.to_older_version(2, Encoding::Bincode).unwrap();
+        let v2: CameraV2 = Encoding::Bincode.deserialize(&body).unwrap();
+        assert_eq!(v2.range, 1); // 250 metros es, redondeado, una cuadra
+
+        let decoded = Camera::from_older_version(2, &body, Encoding::Bincode).unwrap();
+        assert_eq!(decoded.get_id(), 300);
+        assert_eq!(decoded.get_range(), legacy_range_to_meters(1));
+        assert_eq!(meters_to_legacy_range(decoded.get_range()), 1);
+    }
+
+    #[test]
+    fn test_1e_el_campo_de_vision_viaja_en_la_version_actual_y_no_en_la_3() {
+        let mut camera = Camera::new(7, -34.6, -58.4, 250.0);
+        camera.set_fov(CameraFov::new(45.0, 90.0, Some(120.0)).unwrap());
+        let decoded = Camera::decode(&camera.encode().unwrap()).unwrap();
+        assert_eq!(decoded, camera);
+
+        let body = camera.to_older_version(3, Encoding::Json).unwrap();
+        let decoded = Camera::from_older_version(3, &body, Encoding::Json).unwrap();
+        assert_eq!(decoded.get_fov(), CameraFov::default());
+        assert_eq!(decoded.get_range(), 250.0);
+    }
+
+    #[test]
+    fn test_1f_los_estados_degradados_viajan_en_la_version_actual_y_como_ahorro_en_las_anteriores() {
+        let mut camera = Camera::new(7, -34.6, -58.4, 250.0);
+        camera.set_health(CameraHealth::Offline);
+        assert_eq!(Camera::decode(&camera.encode().unwrap()).unwrap(), camera);
+
+        for version in 1..Camera::CURRENT_VERSION {
+            let body = camera.to_older_version(version, Encoding::Bincode).unwrap();
+            let decoded = Camera::from_older_version(version, &body, Encoding::Bincode).unwrap();
+            // Desde la 5 se conocen los estados degradados
+            let expected = if version >= 5 { CameraState::Offline } else { CameraState::SavingMode };
+            assert_eq!(decoded.get_state(), expected, "versión {}", version);
+        }
+        let legacy = Camera::from_legacy_bytes(&camera.to_legacy_bytes().unwrap()).unwrap();
+        assert_eq!(legacy.get_state(), CameraState::SavingMode);
+    }
+
+    #[test]
+    fn test_1g_el_id_de_sistema_viaja_en_la_version_actual_y_no_en_las_anteriores() {
+        let mut camera = Camera::new(7, -34.6, -58.4, 250.0);
+        camera.set_system_id(2);
+        assert_eq!(Camera::decode(&camera.encode().unwrap()).unwrap(), camera);
+
+        let body = camera.to_older_version(5, Encoding::Bincode).unwrap();
+        let decoded = Camera::from_older_version(5, &body, Encoding::Bincode).unwrap();
+        assert_eq!(decoded.get_system_id(), DEFAULT_CAMERA_SYSTEM_ID);
+        assert_eq!(decoded.get_id(), 7);
+    }
+
     #[test]
     fn test_2_camaras_cercanas_son_lindantes() {
         //     Aux: obelisco: lon -58.3861838  lat: -34.6037344
 
         let lat = -34.6037344;
         let lon = -58.3861838;
-        let range = 10;
+        let range = 250.0;
         let incr = 0.0000005;
         let mut cam_1 = Camera::new(1, lat, lon, range);
 
         // Otra cámara, con misma longitud, y latitud apenas incrementada
         let mut cam_2 = Camera::new(2, lat + incr, lon, range);
 
-        cam_1.mutually_add_if_bordering(&mut cam_2);
-        // Aux con estos datos da: Dio que la cuenta vale: 0.0000004999999987376214
+        cam_1.mutually_add_if_bordering(&mut cam_2, DEFAULT_BORDER_RANGE_METERS);
+        // Aux: están a unos 5 cm
 
         // Se han agregado mutuamente, xq sí qentraron dentro del border_range para ser consideradas lindantes
         assert!(cam_1.border_cameras.contains(&cam_2.get_id()));
@@ -270,10 +683,10 @@ mod test {
 
         //
         // Ídem con datos "reales"
-        let mut cam_5: Camera = Camera::new(5, -34.6040, -58.3873, 1); // Aux: cámara 5.
-        let mut cam_6: Camera = Camera::new(6, -34.6039, -58.3837, 1); // Aux: cámara 6.
+        let mut cam_5: Camera = Camera::new(5, -34.6040, -58.3873, 250.0); // Aux: cámara 5.
+        let mut cam_6: Camera = Camera::new(6, -34.6039, -58.3837, 250.0); // Aux: cámara 6, a unos 330 metros.
 
-        cam_5.mutually_add_if_bordering(&mut cam_6);
+        cam_5.mutually_add_if_bordering(&mut cam_6, DEFAULT_BORDER_RANGE_METERS);
 
         // Se han agregado mutuamente, xq sí qentraron dentro del border_range para ser consideradas lindantes
         assert!(cam_5.border_cameras.contains(&cam_6.get_id()));
@@ -282,14 +695,14 @@ mod test {
 
     #[test]
     fn test_3_camaras_lejanas_no_son_lindantes() {
-        // A 5 cuadras de la otra cámara, es decir, afuera de las 4 cuadras de lindantes
+        // A unos 700 metros de la otra cámara, es decir, afuera de los 550 metros de lindantes
         //-58.3950 -34.6044
-        let mut cam_a: Camera = Camera::new(10, -34.6044, -58.3950, 1); // 3 cuadras a la izq de cam 5.
+        let mut cam_a: Camera = Camera::new(10, -34.6044, -58.3950, 250.0);
 
-        // Otra cámara, con misma longitud, y latitud más lejana
-        let mut cam_b: Camera = Camera::new(5, -34.6040, -58.3873, 1); // Aux: cámara 5.
+        // Otra cámara, con latitud casi igual, y longitud más lejana
+        let mut cam_b: Camera = Camera::new(5, -34.6040, -58.3873, 250.0); // Aux: cámara 5.
 
-        cam_b.mutually_add_if_bordering(&mut cam_a);
+        cam_b.mutually_add_if_bordering(&mut cam_a, DEFAULT_BORDER_RANGE_METERS);
 
         // No se han agregado mutuamente, xq no entraron dentro del border_range para ser consideradas lindantes
         assert!(!cam_a.border_cameras.contains(&cam_b.get_id()));
@@ -299,22 +712,22 @@ mod test {
     // #[test]
     // fn test_4_testing_camera_range() {
 
-    //     let camera = Camera::new(5, -34.6040, -58.3873, 3); // Aux: cámara 5.
+    //     let camera = Camera::new(5, -34.6040, -58.3873, 500.0); // Aux: cámara 5.
 
     //     let (lat, lon) = (-34.6042, -58.3897);
-    //     let is_in_range = camera.is_within_range_from_self(lat, lon, camera.range as f64);
+    //     let is_in_range = camera.is_within_range_from_self(lat, lon, camera.range);
 
     //     assert!(is_in_range);
 
     // }
 
     #[test]
-    fn test_4a_una_pos_dentro_de_range_cuadras_esta_dentro_del_rango() {
-        // Rango de 1 cuadra.
-        let camera = Camera::new(5, -34.6040, -58.3873, 1); // Aux: cámara 5.
+    fn test_4a_una_pos_dentro_de_range_metros_esta_dentro_del_rango() {
+        // Rango de 250 metros.
+        let camera = Camera::new(5, -34.6040, -58.3873, 250.0); // Aux: cámara 5.
 
-        let (lat, lon) = (-34.6042, -58.3897); // una cuadra a la izq de la cam 5
-        let is_in_range = camera.is_within_range_from_self(lat, lon, camera.range as f64);
+        let (lat, lon) = (-34.6042, -58.3897); // una cuadra a la izq de la cam 5, a unos 220 metros
+        let is_in_range = camera.is_within_range_from_self(lat, lon, camera.range);
 
         assert!(is_in_range);
         //assert!(false);
@@ -322,12 +735,53 @@ mod test {
 
     #[test]
     fn test_4b_una_pos_mas_lejana_esta_fuera_del_rango() {
-        // Rango de 1 cuadra.
-        let camera = Camera::new(5, -34.6040, -58.3873, 1); // Aux: cámara 5.
+        // Rango de 250 metros.
+        let camera = Camera::new(5, -34.6040, -58.3873, 250.0); // Aux: cámara 5.
 
-        let (lat, lon) = (-34.6042, -58.3902);
-        let is_in_range = camera.is_within_range_from_self(lat, lon, camera.range as f64);
+        let (lat, lon) = (-34.6042, -58.3902); // a unos 265 metros
+        let is_in_range = camera.is_within_range_from_self(lat, lon, camera.range);
 
         assert!(!is_in_range);
     }
+
+    #[test]
+    fn test_5_solo_registra_lo_que_esta_dentro_de_su_campo_de_vision() {
+        // Mirando al este, con 90 grados de apertura, hasta 150 metros aunque su rango sea de 250
+        let mut camera = Camera::new(5, -34.6040, -58.3873, 250.0);
+        camera.set_fov(CameraFov::new(90.0, 90.0, Some(150.0)).unwrap());
+        let position = camera.get_position();
+
+        assert!(camera.will_register(position));
+        assert!(camera.will_register(geo::destination_point(position, 90.0, 100.0)));
+        assert!(camera.will_register(geo::destination_point(position, 130.0, 100.0)));
+        // Fuera del sector
+        assert!(!camera.will_register(geo::destination_point(position, 140.0, 100.0)));
+        assert!(!camera.will_register(geo::destination_point(position, 270.0, 100.0)));
+        // Dentro del sector, pero más lejos que la distancia máxima
+        assert!(!camera.will_register(geo::destination_point(position, 90.0, 200.0)));
+    }
+
+    #[test]
+    fn test_6_degradada_sigue_los_incidentes_y_al_recuperarse_vuelve_al_estado_que_corresponde() {
+        let mut camera = Camera::new(5, -34.6040, -58.3873, 250.0);
+        let inc_info = IncidentInfo::new(IncidentInfo::id_from_narrow(1), IncidentSource::Manual);
+
+        assert!(camera.set_health(CameraHealth::Fault));
+        assert!(!camera.set_health(CameraHealth::Fault));
+        // Degradada, registra el incidente pero no se activa
+        assert!(!camera.append_to_incs_being_managed(inc_info));
+        assert_eq!(camera.get_state(), CameraState::Fault);
+
+        // Al recuperarse, pasa a activo porque tiene un incidente
+        assert!(camera.set_health(CameraHealth::Healthy));
+        assert_eq!(camera.get_state(), CameraState::Active);
+        assert!(!camera.set_health(CameraHealth::Healthy));
+
+        // Si el incidente se resuelve mientras está offline, sigue offline, y al recuperarse vuelve a ahorro
+        camera.set_health(CameraHealth::Offline);
+        assert!(!camera.remove_from_incs_being_managed(inc_info));
+        assert_eq!(camera.get_state(), CameraState::Offline);
+        assert!(camera.set_health(CameraHealth::Healthy));
+        assert_eq!(camera.get_state(), CameraState::SavingMode);
+    }
 }
@@ -0,0 +1,425 @@
+use std::io::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::apps::payload_codec::{
+    envelope::{Encoding, PayloadSchema},
+    versioned_payload::{no_legacy_format_error, unsupported_version_error, VersionedPayload},
+};
+
+use super::{
+    camera::{Camera, CameraV3, CameraV5},
+    camera_fov::CameraFov,
+};
+
+/// Operación del abm de cámaras, pedida por MQTT.
+/// Si no se indica el campo de visión (`fov`), la cámara ve en todas las direcciones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CameraCommand {
+    Add {
+        id: u32,
+        latitude: f64,
+        longitude: f64,
+        range: f64, // en metros
+        #[serde(default)]
+        fov: CameraFov,
+    },
+    Modify {
+        id: u32,
+        latitude: f64,
+        longitude: f64,
+        range: f64, // en metros
+        #[serde(default)]
+        fov: CameraFov,
+    },
+    Delete {
+        id: u32,
+    },
+    List,
+}
+
+/// `CameraCommand` en la versión 1 del envelope, sin campo de visión.
+#[derive(Serialize, Deserialize)]
+enum CameraCommandV1 {
+    Add { id: u32, latitude: f64, longitude: f64, range: f64 },
+    Modify { id: u32, latitude: f64, longitude: f64, range: f64 },
+    Delete { id: u32 },
+    List,
+}
+
+impl From<CameraCommandV1> for CameraCommand {
+    fn from(v1: CameraCommandV1) -> Self {
+        let fov = CameraFov::default();
+        match v1 {
+            CameraCommandV1::Add { id, latitude, longitude, range } => {
+                Self::Add { id, latitude, longitude, range, fov }
+            }
+            CameraCommandV1::Modify { id, latitude, longitude, range } => {
+                Self::Modify { id, latitude, longitude, range, fov }
+            }
+            CameraCommandV1::Delete { id } => Self::Delete { id },
+            CameraCommandV1::List => Self::List,
+        }
+    }
+}
+
+impl From<&CameraCommand> for CameraCommandV1 {
+    fn from(command: &CameraCommand) -> Self {
+        match *command {
+            CameraCommand::Add { id, latitude, longitude, range, .. } => {
+                Self::Add { id, latitude, longitude, range }
+            }
+            CameraCommand::Modify { id, latitude, longitude, range, .. } => {
+                Self::Modify { id, latitude, longitude, range }
+            }
+            CameraCommand::Delete { id } => Self::Delete { id },
+            CameraCommand::List => Self::List,
+        }
+    }
+}
+
+/// Pedido que se publica en el topic de comandos de cámaras. La respuesta se publica en `reply_topic`,
+/// con el mismo `correlation_id`, para que quien pidió sepa a qué pedido corresponde.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraCommandRequest {
+    correlation_id: String,
+    reply_topic: String,
+    command: CameraCommand,
+}
+
+impl CameraCommandRequest {
+    pub fn new(correlation_id: &str, reply_topic: &str, command: CameraCommand) -> Self {
+        Self {
+            correlation_id: correlation_id.to_string(),
+            reply_topic: reply_topic.to_string(),
+            command,
+        }
+    }
+
+    pub fn get_correlation_id(&self) -> &str {
+        &self.correlation_id
+    }
+
+    pub fn get_reply_topic(&self) -> &str {
+        &self.reply_topic
+    }
+
+    pub fn get_command(&self) -> &CameraCommand {
+        &self.command
+    }
+}
+
+/// Cantidad de cámaras que se envían en cada respuesta. Al listar, la respuesta se envía en varias páginas
+/// con el mismo `correlation_id`, para no enviar todas las cámaras en un único mensaje.
+pub const CAMERAS_PER_PAGE: usize = 1;
+
+/// Respuesta a un `CameraCommandRequest`: las cámaras resultantes de la operación (la agregada, modificada o
+/// eliminada, o todas al listar), o la descripción del error. Si se envía en varias páginas, cada una indica
+/// su número (desde 0) y la cantidad total.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraCommandReply {
+    correlation_id: String,
+    result: Result<Vec<Camera>, String>,
+    page: u32,
+    pages: u32,
+}
+
+impl CameraCommandReply {
+    pub fn new(correlation_id: &str, result: Result<Vec<Camera>, Error>) -> Self {
+        Self::single_page(correlation_id.to_string(), result.map_err(|e| e.to_string()))
+    }
+
+    fn single_page(correlation_id: String, result: Result<Vec<Camera>, String>) -> Self {
+        Self {
+            correlation_id,
+            result,
+            page: 0,
+            pages: 1,
+        }
+    }
+
+    /// Divide la respuesta en páginas de a `CAMERAS_PER_PAGE` cámaras, en el orden en que deben enviarse.
+    /// Un error, o una única página, se envía tal cual.
+    pub fn into_pages(self) -> Vec<Self> {
+        let cameras = match &self.result {
+            Ok(cameras) if cameras.len() > CAMERAS_PER_PAGE => cameras,
+            _ => return vec![self],
+        };
+        let chunks: Vec<&[Camera]> = cameras.chunks(CAMERAS_PER_PAGE).collect();
+        let pages = chunks.len() as u32;
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(page, chunk)| Self {
+                correlation_id: self.correlation_id.clone(),
+                result: Ok(chunk.to_vec()),
+                page: page as u32,
+                pages,
+            })
+            .collect()
+    }
+
+    pub fn get_correlation_id(&self) -> &str {
+        &self.correlation_id
+    }
+
+    /// Devuelve el resultado de la operación; si la respuesta tiene varias páginas, sólo las cámaras de ésta.
+    pub fn get_result(&self) -> &Result<Vec<Camera>, String> {
+        &self.result
+    }
+
+    pub fn get_page(&self) -> u32 {
+        self.page
+    }
+
+    pub fn get_pages(&self) -> u32 {
+        self.pages
+    }
+}
+
+/// `CameraCommandRequest` en la versión 1 del envelope, sin campo de visión.
+#[derive(Serialize, Deserialize)]
+struct CameraCommandRequestV1 {
+    correlation_id: String,
+    reply_topic: String,
+    command: CameraCommandV1,
+}
+
+/// `CameraCommandReply` en la versión 1 del envelope, con las cámaras sin campo de visión.
+#[derive(Serialize, Deserialize)]
+struct CameraCommandReplyV1 {
+    correlation_id: String,
+    result: Result<Vec<CameraV3>, String>,
+}
+
+/// `CameraCommandReply` en la versión 2 del envelope, con las cámaras sin id de sistema ni estados degradados.
+#[derive(Serialize, Deserialize)]
+struct CameraCommandReplyV2 {
+    correlation_id: String,
+    result: Result<Vec<CameraV5>, String>,
+}
+
+/// `CameraCommandReply` en la versión 3 del envelope, sin páginas: cada respuesta se entiende como completa.
+#[derive(Serialize, Deserialize)]
+struct CameraCommandReplyV3 {
+    correlation_id: String,
+    result: Result<Vec<Camera>, String>,
+}
+
+impl VersionedPayload for CameraCommandRequest {
+    const SCHEMA: PayloadSchema = PayloadSchema::CameraCommandRequest;
+    // La versión 2 agregó el campo de visión a las altas y modificaciones.
+    const CURRENT_VERSION: u8 = 2;
+
+    fn to_legacy_bytes(&self) -> Result<Vec<u8>, Error> {
+        Err(no_legacy_format_error(Self::SCHEMA))
+    }
+
+    fn from_legacy_bytes(_bytes: &[u8]) -> Result<Self, Error> {
+        Err(no_legacy_format_error(Self::SCHEMA))
+    }
+
+    fn from_older_version(version: u8, body: &[u8], encoding: Encoding) -> Result<Self, Error> {
+        match version {
+            1 => {
+                let v1: CameraCommandRequestV1 = encoding.deserialize(body)?;
+                Ok(Self {
+                    correlation_id: v1.correlation_id,
+                    reply_topic: v1.reply_topic,
+                    command: v1.command.into(),
+                })
+            }
+            _ => Err(unsupported_version_error(Self::SCHEMA, version)),
+        }
+    }
+
+    fn to_older_version(&self, version: u8, encoding: Encoding) -> Result<Vec<u8>, Error> {
+        match version {
+            1 => encoding.serialize(&CameraCommandRequestV1 {
+                correlation_id: self.correlation_id.clone(),
+                reply_topic: self.reply_topic.clone(),
+                command: (&self.command).into(),
+            }),
+            _ => Err(unsupported_version_error(Self::SCHEMA, version)),
+        }
+    }
+}
+
+impl VersionedPayload for CameraCommandReply {
+    const SCHEMA: PayloadSchema = PayloadSchema::CameraCommandReply;
+    // La versión 2 agregó el campo de visión a las cámaras, la 3 su id de sistema, y la 4 las páginas.
+    const CURRENT_VERSION: u8 = 4;
+
+    fn to_legacy_bytes(&self) -> Result<Vec<u8>, Error> {
+        Err(no_legacy_format_error(Self::SCHEMA))
+    }
+
+    fn from_legacy_bytes(_bytes: &[u8]) -> Result<Self, Error> {
+        Err(no_legacy_format_error(Self::SCHEMA))
+    }
+
+    fn from_older_version(version: u8, body: &[u8], encoding: Encoding) -> Result<Self, Error> {
+        match version {
+            1 => {
+                let v1: CameraCommandReplyV1 = encoding.deserialize(body)?;
+                Ok(Self::single_page(
+                    v1.correlation_id,
+                    v1.result
+                        .map(|cameras| cameras.into_iter().map(Camera::from).collect()),
+                ))
+            }
+            2 => {
+                let v2: CameraCommandReplyV2 = encoding.deserialize(body)?;
+                Ok(Self::single_page(
+                    v2.correlation_id,
+                    v2.result
+                        .map(|cameras| cameras.into_iter().map(Camera::from).collect()),
+                ))
+            }
+            3 => {
+                let v3: CameraCommandReplyV3 = encoding.deserialize(body)?;
+                Ok(Self::single_page(v3.correlation_id, v3.result))
+            }
+            _ => Err(unsupported_version_error(Self::SCHEMA, version)),
+        }
+    }
+
+    fn to_older_version(&self, version: u8, encoding: Encoding) -> Result<Vec<u8>, Error> {
+        match version {
+            1 => encoding.serialize(&CameraCommandReplyV1 {
+                correlation_id: self.correlation_id.clone(),
+                result: self
+                    .result
+                    .as_ref()
+                    .map(|cameras| cameras.iter().map(CameraV3::from).collect())
+                    .map_err(|e| e.clone()),
+            }),
+            2 => encoding.serialize(&CameraCommandReplyV2 {
+                correlation_id: self.correlation_id.clone(),
+                result: self
+                    .result
+                    .as_ref()
+                    .map(|cameras| {
+                        cameras
+                            .iter()
+                            .map(|camera| CameraV5::from(camera).without_degraded())
+                            .collect()
+                    })
+                    .map_err(|e| e.clone()),
+            }),
+            3 => encoding.serialize(&CameraCommandReplyV3 {
+                correlation_id: self.correlation_id.clone(),
+                result: self.result.clone(),
+            }),
+            _ => Err(unsupported_version_error(Self::SCHEMA, version)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CameraCommand, CameraCommandReply, CameraCommandRequest, CAMERAS_PER_PAGE};
+    use crate::{
+        apps::{
+            payload_codec::{envelope::Encoding, versioned_payload::VersionedPayload},
+            sist_camaras::{
+                camera::{Camera, DEFAULT_CAMERA_SYSTEM_ID},
+                camera_fov::CameraFov,
+            },
+        },
+        mqtt::client::typed_topic::MqttPayload,
+    };
+
+    #[test]
+    fn test_1_pedido_y_respuesta_se_codifican_y_decodifican() {
+        let request = CameraCommandRequest::new(
+            "pedido-1",
+            "cam/cmd/respuestas/admin",
+            CameraCommand::Modify {
+                id: 7,
+                latitude: -34.6,
+                longitude: -58.4,
+                range: 400.0,
+                fov: CameraFov::new(90.0, 60.0, Some(150.0)).unwrap(),
+            },
+        );
+        let decoded = CameraCommandRequest::decode(&request.encode().unwrap()).unwrap();
+        assert_eq!(decoded, request);
+
+        let reply = CameraCommandReply::new("pedido-1", Ok(vec![Camera::new(7, -34.6, -58.4, 400.0)]));
+        let decoded = CameraCommandReply::decode(&reply.encode().unwrap()).unwrap();
+        assert_eq!(decoded, reply);
+
+        // Sin envelope no son válidos, ya que no existían antes de él
+        assert!(CameraCommandRequest::decode(b"pedido").is_err());
+    }
+
+    #[test]
+    fn test_2_la_version_1_se_entiende_sin_campo_de_vision() {
+        let request = CameraCommandRequest::new(
+            "pedido-2",
+            "cam/cmd/respuestas/admin",
+            CameraCommand::Add {
+                id: 8,
+                latitude: -34.6,
+                longitude: -58.4,
+                range: 250.0,
+                fov: CameraFov::new(90.0, 60.0, None).unwrap(),
+            },
+        );
+        let body = request.to_older_version(1, Encoding::Bincode).unwrap();
+        let decoded = CameraCommandRequest::from_older_version(1, &body, Encoding::Bincode).unwrap();
+        let CameraCommand::Add { fov, .. } = decoded.get_command() else {
+            panic!("Se esperaba un alta");
+        };
+        assert_eq!(*fov, CameraFov::default());
+
+        let mut camera = Camera::new(8, -34.6, -58.4, 250.0);
+        camera.set_fov(CameraFov::new(90.0, 60.0, None).unwrap());
+        let reply = CameraCommandReply::new("pedido-2", Ok(vec![camera]));
+        let body = reply.to_older_version(1, Encoding::Json).unwrap();
+        let decoded = CameraCommandReply::from_older_version(1, &body, Encoding::Json).unwrap();
+        assert_eq!(decoded.get_result().as_ref().unwrap()[0].get_fov(), CameraFov::default());
+    }
+
+    #[test]
+    fn test_3_la_version_2_de_la_respuesta_no_tiene_el_id_de_sistema() {
+        let mut camera = Camera::new(9, -34.6, -58.4, 250.0);
+        camera.set_system_id(3);
+        let reply = CameraCommandReply::new("pedido-3", Ok(vec![camera]));
+        let decoded = CameraCommandReply::decode(&reply.encode().unwrap()).unwrap();
+        assert_eq!(decoded.get_result().as_ref().unwrap()[0].get_system_id(), 3);
+
+        let body = reply.to_older_version(2, Encoding::Bincode).unwrap();
+        let decoded = CameraCommandReply::from_older_version(2, &body, Encoding::Bincode).unwrap();
+        assert_eq!(
+            decoded.get_result().as_ref().unwrap()[0].get_system_id(),
+            DEFAULT_CAMERA_SYSTEM_ID
+        );
+    }
+
+    #[test]
+    fn test_4_el_listado_se_divide_en_paginas_con_el_mismo_correlation_id() {
+        let cameras: Vec<Camera> = (1..=12).map(|id| Camera::new(id, -34.6, -58.4, 250.0)).collect();
+        let pages = CameraCommandReply::new("pedido-4", Ok(cameras.clone())).into_pages();
+        assert_eq!(pages.len(), cameras.len().div_ceil(CAMERAS_PER_PAGE));
+
+        let mut received = vec![];
+        for (i, page) in pages.iter().enumerate() {
+            let decoded = CameraCommandReply::decode(&page.encode().unwrap()).unwrap();
+            assert_eq!(decoded.get_correlation_id(), "pedido-4");
+            assert_eq!((decoded.get_page(), decoded.get_pages()), (i as u32, pages.len() as u32));
+            received.extend(decoded.get_result().clone().unwrap());
+        }
+        assert_eq!(received, cameras);
+
+        // Un error, o una única cámara, va en una sola página.
+        let error = CameraCommandReply::new("pedido-5", Err(std::io::Error::other("no existe")));
+        assert_eq!(error.clone().into_pages(), vec![error]);
+
+        // Las versiones anteriores no tienen páginas: cada respuesta es completa.
+        let body = pages[1].to_older_version(3, Encoding::Bincode).unwrap();
+        let decoded = CameraCommandReply::from_older_version(3, &body, Encoding::Bincode).unwrap();
+        assert_eq!((decoded.get_page(), decoded.get_pages()), (0, 1));
+    }
+}
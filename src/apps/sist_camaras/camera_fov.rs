@@ -0,0 +1,127 @@
+use std::io::{Error, ErrorKind};
+
+use serde::{Deserialize, Serialize};
+
+/// Campo de visión de una cámara: el sector, centrado en `heading` y de apertura `angle` (ambos en grados,
+/// el rumbo medido desde el norte en sentido horario), dentro del cual la cámara ve.
+/// Opcionalmente, `max_distance` limita en metros hasta dónde ve (ej por un edificio), si es menor a su rango.
+/// Por defecto ve en todas las direcciones, hasta su rango.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CameraFov {
+    heading: f64,
+    angle: f64,
+    max_distance: Option<f64>,
+}
+
+impl Default for CameraFov {
+    fn default() -> Self {
+        Self {
+            heading: 0.0,
+            angle: 360.0,
+            max_distance: None,
+        }
+    }
+}
+
+impl CameraFov {
+    /// Crea un campo de visión. Devuelve error si el ángulo no está en `(0, 360]`, o la distancia máxima no es positiva.
+    pub fn new(heading: f64, angle: f64, max_distance: Option<f64>) -> Result<Self, Error> {
+        if angle.is_nan() || angle <= 0.0 || angle > 360.0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Ángulo de visión no válido: {}, debe estar entre 0 y 360.",
+                    angle
+                ),
+            ));
+        }
+        if let Some(distance) =
+            max_distance.filter(|distance| distance.is_nan() || *distance <= 0.0)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Distancia máxima de visión no válida: {}.", distance),
+            ));
+        }
+        if !heading.is_finite() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Rumbo no válido: {}.", heading),
+            ));
+        }
+        Ok(Self {
+            heading: heading.rem_euclid(360.0),
+            angle,
+            max_distance,
+        })
+    }
+
+    /// Devuelve si ve en todas las direcciones.
+    pub fn is_circular(&self) -> bool {
+        self.angle >= 360.0
+    }
+
+    /// Devuelve si el rumbo recibido, en grados, está dentro del sector.
+    pub fn contains_bearing(&self, bearing: f64) -> bool {
+        if self.is_circular() {
+            return true;
+        }
+        // Diferencia con el heading, en (-180, 180]
+        let difference = (bearing - self.heading + 540.0).rem_euclid(360.0) - 180.0;
+        difference.abs() <= self.angle / 2.0
+    }
+
+    /// Devuelve hasta qué distancia, en metros, ve una cámara de rango `range`.
+    pub fn get_distance_for(&self, range: f64) -> f64 {
+        self.max_distance
+            .map_or(range, |max_distance| max_distance.min(range))
+    }
+
+    pub fn get_heading(&self) -> f64 {
+        self.heading
+    }
+
+    pub fn get_angle(&self) -> f64 {
+        self.angle
+    }
+
+    pub fn get_max_distance(&self) -> Option<f64> {
+        self.max_distance
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CameraFov;
+
+    #[test]
+    fn test_1_el_sector_contiene_los_rumbos_dentro_de_su_apertura() {
+        // Mirando al norte, con 90 grados de apertura
+        let fov = CameraFov::new(0.0, 90.0, None).unwrap();
+        assert!(fov.contains_bearing(0.0));
+        assert!(fov.contains_bearing(44.0));
+        assert!(fov.contains_bearing(316.0));
+        assert!(!fov.contains_bearing(46.0));
+        assert!(!fov.contains_bearing(180.0));
+
+        // Por defecto ve en todas las direcciones
+        assert!(CameraFov::default().contains_bearing(180.0));
+    }
+
+    #[test]
+    fn test_2_valores_no_validos_dan_error() {
+        assert!(CameraFov::new(0.0, 0.0, None).is_err());
+        assert!(CameraFov::new(0.0, 361.0, None).is_err());
+        assert!(CameraFov::new(0.0, 90.0, Some(-1.0)).is_err());
+        assert_eq!(
+            CameraFov::new(-90.0, 90.0, None).unwrap().get_heading(),
+            270.0
+        );
+        assert_eq!(
+            CameraFov::new(0.0, 90.0, Some(100.0))
+                .unwrap()
+                .get_distance_for(250.0),
+            100.0
+        );
+    }
+}
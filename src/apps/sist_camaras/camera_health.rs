@@ -0,0 +1,201 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::apps::properties::Properties;
+
+use super::bordering::parse_property;
+
+/// Salud de una cámara, según su directorio de imágenes.
+/// - Healthy: produce imágenes;
+/// - Offline: hace más del tiempo máximo configurado que no produce imágenes;
+/// - Fault: no se puede leer su directorio de imágenes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraHealth {
+    Healthy,
+    Offline,
+    Fault,
+}
+
+/// Cada cuánto se publica el latido de las cámaras, y cuánto puede estar una cámara sin producir imágenes
+/// antes de considerarla offline (o nunca, si no se configura).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraHealthPolicy {
+    heartbeat_interval: Duration,
+    feed_timeout: Option<Duration>,
+}
+
+impl Default for CameraHealthPolicy {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: Duration::from_secs(10),
+            feed_timeout: None,
+        }
+    }
+}
+
+impl CameraHealthPolicy {
+    /// Crea una política de salud. Devuelve error si el intervalo del latido o el tiempo máximo sin imágenes son nulos.
+    pub fn new(
+        heartbeat_interval: Duration,
+        feed_timeout: Option<Duration>,
+    ) -> Result<Self, Error> {
+        if heartbeat_interval.is_zero() || feed_timeout.is_some_and(|timeout| timeout.is_zero()) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "El intervalo del latido y el tiempo máximo sin imágenes deben ser mayores a cero.",
+            ));
+        }
+        Ok(Self {
+            heartbeat_interval,
+            feed_timeout,
+        })
+    }
+
+    /// Lee la política del archivo de properties `properties_file`, de las propiedades `camera-heartbeat-seconds`
+    /// y `camera-feed-timeout-seconds`. Las que no estén (o el archivo si no existe) toman el valor por defecto;
+    /// `camera-feed-timeout-seconds` vacía es no controlar las imágenes.
+    pub fn from_properties_file(properties_file: &str) -> Result<Self, Error> {
+        let properties = match Properties::new(properties_file) {
+            Ok(properties) => properties,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        let default = Self::default();
+
+        let heartbeat_interval = match properties.get("camera-heartbeat-seconds") {
+            Some(prop) => Duration::from_secs(parse_property(prop, "camera-heartbeat-seconds")?),
+            None => default.heartbeat_interval,
+        };
+        let feed_timeout = match properties.get("camera-feed-timeout-seconds") {
+            Some(prop) if !prop.is_empty() => Some(Duration::from_secs(parse_property(
+                prop,
+                "camera-feed-timeout-seconds",
+            )?)),
+            _ => default.feed_timeout,
+        };
+        Self::new(heartbeat_interval, feed_timeout)
+    }
+
+    /// Devuelve cada cuánto se publica el latido de las cámaras.
+    pub fn get_heartbeat_interval(&self) -> Duration {
+        self.heartbeat_interval
+    }
+
+    /// Devuelve cuánto puede estar una cámara sin producir imágenes antes de considerarla offline, si se controla.
+    pub fn get_feed_timeout(&self) -> Option<Duration> {
+        self.feed_timeout
+    }
+}
+
+/// Controla la salud de las cámaras según sus directorios de imágenes, `camera_<id>` dentro de `base_dir`
+/// (los que monitorea el detector automático de incidentes).
+#[derive(Debug, Clone)]
+pub struct FeedMonitor {
+    base_dir: PathBuf,
+    timeout: Duration,
+    started_at: SystemTime,
+}
+
+impl FeedMonitor {
+    /// Crea un `FeedMonitor`. Las cámaras tienen `timeout` desde este momento para producir su primera imagen.
+    pub fn new(base_dir: impl Into<PathBuf>, timeout: Duration) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+            timeout,
+            started_at: SystemTime::now(),
+        }
+    }
+
+    /// Devuelve la salud de la cámara `camera_id`: `Fault` si no se puede leer su directorio, `Offline` si
+    /// hace más de `timeout` que no cambia, y si no `Healthy`.
+    pub fn check(&self, camera_id: u32) -> CameraHealth {
+        let dir = self.base_dir.join(format!("camera_{}", camera_id));
+        let last_activity = match last_activity_in(&dir) {
+            Ok(last_activity) => last_activity.max(self.started_at),
+            Err(_) => return CameraHealth::Fault,
+        };
+        match last_activity.elapsed() {
+            Ok(elapsed) if elapsed > self.timeout => CameraHealth::Offline,
+            _ => CameraHealth::Healthy,
+        }
+    }
+}
+
+/// Devuelve la última modificación del directorio `dir` o de alguno de sus archivos.
+/// El directorio se modifica al crear o borrar archivos en él, pero no si se copian conservando su fecha.
+fn last_activity_in(dir: &Path) -> Result<SystemTime, Error> {
+    let mut last_activity = fs::metadata(dir)?.modified()?;
+    for entry in fs::read_dir(dir)? {
+        let modified = entry?.metadata()?.modified()?;
+        last_activity = last_activity.max(modified);
+    }
+    Ok(last_activity)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, thread, time::Duration};
+
+    use super::{CameraHealth, CameraHealthPolicy, FeedMonitor};
+
+    #[test]
+    fn test_1_la_politica_se_lee_del_properties_con_valores_por_defecto() {
+        let path = std::env::temp_dir().join(format!(
+            "rustx_camera_health_{}.properties",
+            std::process::id()
+        ));
+        let path_str = path.to_str().unwrap();
+
+        let _ = fs::remove_file(&path);
+        assert_eq!(
+            CameraHealthPolicy::from_properties_file(path_str).unwrap(),
+            CameraHealthPolicy::default()
+        );
+        fs::write(&path, "camera-feed-timeout-seconds=\n").unwrap();
+        assert_eq!(
+            CameraHealthPolicy::from_properties_file(path_str).unwrap(),
+            CameraHealthPolicy::default()
+        );
+
+        fs::write(
+            &path,
+            "camera-heartbeat-seconds=5\ncamera-feed-timeout-seconds=60\n",
+        )
+        .unwrap();
+        let policy = CameraHealthPolicy::from_properties_file(path_str).unwrap();
+        assert_eq!(policy.get_heartbeat_interval(), Duration::from_secs(5));
+        assert_eq!(policy.get_feed_timeout(), Some(Duration::from_secs(60)));
+
+        fs::write(&path, "camera-heartbeat-seconds=0\n").unwrap();
+        assert!(CameraHealthPolicy::from_properties_file(path_str).is_err());
+        fs::write(&path, "camera-feed-timeout-seconds=mucho\n").unwrap();
+        assert!(CameraHealthPolicy::from_properties_file(path_str).is_err());
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_2_sin_directorio_es_fault_y_sin_imagenes_nuevas_es_offline() {
+        let base_dir =
+            std::env::temp_dir().join(format!("rustx_camera_feeds_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base_dir);
+        fs::create_dir_all(base_dir.join("camera_1")).unwrap();
+        let monitor = FeedMonitor::new(&base_dir, Duration::from_millis(200));
+
+        assert_eq!(monitor.check(1), CameraHealth::Healthy);
+        assert_eq!(monitor.check(2), CameraHealth::Fault);
+
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!(monitor.check(1), CameraHealth::Offline);
+
+        // Una imagen nueva la vuelve a poner en funcionamiento
+        fs::write(base_dir.join("camera_1").join("img.jpg"), b"jpg").unwrap();
+        assert_eq!(monitor.check(1), CameraHealth::Healthy);
+
+        let _ = fs::remove_dir_all(&base_dir);
+    }
+}
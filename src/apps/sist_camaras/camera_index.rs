@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use crate::apps::geo::{self, METERS_PER_DEGREE_OF_LATITUDE};
+
+use super::camera::Camera;
+
+/// Lado, en metros sobre la latitud, de las celdas de la grilla. Conviene que sea del orden de los rangos de las cámaras.
+pub const DEFAULT_CELL_SIZE_METERS: f64 = 500.0;
+
+type Cell = (i64, i64);
+
+/// Índice espacial de las posiciones de las cámaras: una grilla de celdas de igual tamaño en grados,
+/// cada una con los ids de las cámaras que caen en ella.
+/// Permite buscar las cámaras cercanas a una posición recorriendo sólo las celdas alrededor, en lugar de todas las cámaras.
+#[derive(Debug, Clone)]
+pub struct CameraGridIndex {
+    cell_degrees: f64,
+    cells: HashMap<Cell, Vec<u32>>,
+    positions: HashMap<u32, (f64, f64)>,
+    // Mayor rango de las cámaras indexadas. No se achica al quitar cámaras: sólo se usa como radio de búsqueda.
+    max_range: f64,
+}
+
+impl Default for CameraGridIndex {
+    fn default() -> Self {
+        Self::new(DEFAULT_CELL_SIZE_METERS)
+    }
+}
+
+impl CameraGridIndex {
+    /// Crea un índice vacío, con celdas de `cell_size_meters` de lado.
+    pub fn new(cell_size_meters: f64) -> Self {
+        Self {
+            cell_degrees: cell_size_meters / METERS_PER_DEGREE_OF_LATITUDE,
+            cells: HashMap::new(),
+            positions: HashMap::new(),
+            max_range: 0.0,
+        }
+    }
+
+    /// Crea un índice con las cámaras no borradas de `cameras`.
+    pub fn from_cameras(cameras: &HashMap<u32, Camera>) -> Self {
+        let mut index = Self::default();
+        for camera in cameras.values().filter(|camera| camera.is_not_deleted()) {
+            index.insert(camera);
+        }
+        index
+    }
+
+    /// Agrega la cámara al índice, o actualiza su posición y rango si ya estaba.
+    pub fn insert(&mut self, camera: &Camera) {
+        self.remove(camera.get_id());
+        let position = camera.get_position();
+        self.cells
+            .entry(self.cell_of(position))
+            .or_default()
+            .push(camera.get_id());
+        self.positions.insert(camera.get_id(), position);
+        self.max_range = self.max_range.max(camera.get_range());
+    }
+
+    /// Quita del índice la cámara del id recibido, si estaba.
+    pub fn remove(&mut self, id: u32) {
+        if let Some(position) = self.positions.remove(&id) {
+            let cell = self.cell_of(position);
+            if let Some(ids) = self.cells.get_mut(&cell) {
+                ids.retain(|cam_id| *cam_id != id);
+                if ids.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+
+    /// Devuelve los ids de las cámaras que están a no más de `radius` metros de `position`.
+    pub fn ids_within(&self, position: (f64, f64), radius: f64) -> Vec<u32> {
+        let mut ids = vec![];
+        self.for_each_candidate(position, radius, |id, cam_position| {
+            if geo::distance(position, cam_position) <= radius {
+                ids.push(id);
+            }
+        });
+        ids
+    }
+
+    /// Devuelve los ids de las cámaras que podrían cubrir `position`: las que están a no más del mayor rango indexado.
+    /// Quien llama debe confirmar con el rango de cada una (`Camera::will_register`).
+    pub fn candidates_covering(&self, position: (f64, f64)) -> Vec<u32> {
+        self.ids_within(position, self.max_range)
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Llama a `f` con cada cámara de las celdas que alcanzan el círculo de `radius` metros alrededor de `position`.
+    fn for_each_candidate(
+        &self,
+        position: (f64, f64),
+        radius: f64,
+        mut f: impl FnMut(u32, (f64, f64)),
+    ) {
+        let lat_span = radius / METERS_PER_DEGREE_OF_LATITUDE;
+        // Un grado de longitud se achica con la latitud: se toma el ancho en el borde más cercano al polo
+        let farthest_lat = (position.0.abs() + lat_span).min(90.0);
+        let lon_span = lat_span / farthest_lat.to_radians().cos();
+
+        let (min_row, min_col) = self.cell_of((position.0 - lat_span, position.1 - lon_span));
+        let (max_row, max_col) = self.cell_of((position.0 + lat_span, position.1 + lon_span));
+        let cells_in_range = (max_row - min_row + 1).saturating_mul(max_col - min_col + 1);
+
+        // Cerca de los polos o del antimeridiano, o con radios enormes, conviene recorrer todo
+        let crosses_antimeridian = position.1 - lon_span < -180.0 || position.1 + lon_span >= 180.0;
+        if !lon_span.is_finite()
+            || crosses_antimeridian
+            || cells_in_range as usize > self.cells.len()
+        {
+            for (id, cam_position) in &self.positions {
+                f(*id, *cam_position);
+            }
+            return;
+        }
+
+        for row in min_row..=max_row {
+            for col in min_col..=max_col {
+                for id in self.cells.get(&(row, col)).into_iter().flatten() {
+                    if let Some(cam_position) = self.positions.get(id) {
+                        f(*id, *cam_position);
+                    }
+                }
+            }
+        }
+    }
+
+    fn cell_of(&self, (latitude, longitude): (f64, f64)) -> Cell {
+        (
+            (latitude / self.cell_degrees).floor() as i64,
+            (longitude / self.cell_degrees).floor() as i64,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::CameraGridIndex;
+    use crate::apps::{geo, sist_camaras::camera::Camera};
+
+    const OBELISCO: (f64, f64) = (-34.6037344, -58.3861838);
+
+    /// Cámaras alrededor del obelisco, cada `step` metros hacia el este y `step` metros hacia el norte.
+    fn grid_of_cameras(side: u32, step: f64) -> HashMap<u32, Camera> {
+        let mut cameras = HashMap::new();
+        for i in 0..side {
+            for j in 0..side {
+                let id = i * side + j;
+                let north = geo::destination_point(OBELISCO, 0.0, step * i as f64);
+                let (lat, lon) = geo::destination_point(north, 90.0, step * j as f64);
+                cameras.insert(id, Camera::new(id, lat, lon, 250.0));
+            }
+        }
+        cameras
+    }
+
+    #[test]
+    fn test_1_busca_lo_mismo_que_recorrer_todas_las_camaras() {
+        let cameras = grid_of_cameras(20, 180.0);
+        let index = CameraGridIndex::from_cameras(&cameras);
+        assert_eq!(index.len(), 400);
+
+        for (bearing, distance, radius) in [
+            (0.0, 0.0, 250.0),
+            (45.0, 1000.0, 600.0),
+            (200.0, 3000.0, 50.0),
+        ] {
+            let position = geo::destination_point(OBELISCO, bearing, distance);
+            let mut found = index.ids_within(position, radius);
+            found.sort();
+            let mut expected: Vec<u32> = cameras
+                .values()
+                .filter(|camera| geo::distance(camera.get_position(), position) <= radius)
+                .map(|camera| camera.get_id())
+                .collect();
+            expected.sort();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn test_2_al_mover_o_quitar_una_camara_se_actualiza_el_indice() {
+        let mut cameras = grid_of_cameras(2, 100.0);
+        let mut index = CameraGridIndex::from_cameras(&cameras);
+        assert!(index.candidates_covering(OBELISCO).contains(&0));
+
+        let camera = cameras.get_mut(&0).unwrap();
+        let (lat, lon) = geo::destination_point(OBELISCO, 180.0, 5000.0);
+        camera.set_position_and_range(lat, lon, 250.0);
+        index.insert(camera);
+        assert!(!index.candidates_covering(OBELISCO).contains(&0));
+        assert_eq!(index.ids_within((lat, lon), 1.0), vec![0]);
+
+        index.remove(0);
+        assert!(index.ids_within((lat, lon), 1.0).is_empty());
+        assert_eq!(index.len(), 3);
+    }
+}
@@ -0,0 +1,151 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::apps::sist_camaras::{
+    camera::{legacy_range_to_meters, Camera},
+    camera_fov::CameraFov,
+};
+
+/// Milisegundos desde epoch, con los que se marcan las operaciones sobre el registro.
+pub type RecordTimestamp = u64;
+
+/// Lo que se guarda de una cámara en el registro: su configuración (id, posición, rango y campo de visión),
+/// y cuándo fue creada, modificada por última vez, y borrada (borrado lógico).
+/// También guarda las cámaras fijadas o excluidas manualmente como sus lindantes.
+/// El estado y las lindantes no se guardan: el estado es propio de la ejecución, y las lindantes se recalculan al cargar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredCameraRecord")]
+pub struct CameraRecord {
+    id: u32,
+    latitude: f64,
+    longitude: f64,
+    range_meters: f64,
+    fov: CameraFov,
+    pinned_bordering: Vec<u32>,
+    unpinned_bordering: Vec<u32>,
+    created_at: RecordTimestamp,
+    modified_at: Option<RecordTimestamp>,
+    deleted_at: Option<RecordTimestamp>,
+}
+
+impl CameraRecord {
+    /// Crea el registro de una cámara creada en `timestamp`.
+    pub fn new(camera: &Camera, timestamp: RecordTimestamp) -> Self {
+        Self {
+            id: camera.get_id(),
+            latitude: camera.get_latitude(),
+            longitude: camera.get_longitude(),
+            range_meters: camera.get_range(),
+            fov: camera.get_fov(),
+            pinned_bordering: vec![],
+            unpinned_bordering: vec![],
+            created_at: timestamp,
+            modified_at: None,
+            deleted_at: None,
+        }
+    }
+
+    /// Crea la cámara registrada, en su estado inicial y sin lindantes.
+    pub fn to_camera(&self) -> Camera {
+        let mut camera = Camera::new(self.id, self.latitude, self.longitude, self.range_meters);
+        camera.set_fov(self.fov);
+        camera
+    }
+
+    /// Actualiza la configuración guardada con la de `camera`, marcándola como modificada en `timestamp`.
+    pub fn set_modified(&mut self, camera: &Camera, timestamp: RecordTimestamp) {
+        self.latitude = camera.get_latitude();
+        self.longitude = camera.get_longitude();
+        self.range_meters = camera.get_range();
+        self.fov = camera.get_fov();
+        self.modified_at = Some(timestamp);
+    }
+
+    /// Reemplaza las cámaras fijadas y excluidas manualmente como lindantes.
+    pub fn set_bordering_pins(&mut self, pinned: &[u32], unpinned: &[u32]) {
+        self.pinned_bordering = pinned.to_vec();
+        self.unpinned_bordering = unpinned.to_vec();
+    }
+
+    /// Marca la cámara como borrada en `timestamp`.
+    pub fn set_deleted(&mut self, timestamp: RecordTimestamp) {
+        self.deleted_at = Some(timestamp);
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn get_pinned_bordering(&self) -> &[u32] {
+        &self.pinned_bordering
+    }
+
+    pub fn get_unpinned_bordering(&self) -> &[u32] {
+        &self.unpinned_bordering
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    pub fn get_created_at(&self) -> RecordTimestamp {
+        self.created_at
+    }
+
+    pub fn get_modified_at(&self) -> Option<RecordTimestamp> {
+        self.modified_at
+    }
+
+    pub fn get_deleted_at(&self) -> Option<RecordTimestamp> {
+        self.deleted_at
+    }
+}
+
+/// Registro tal como se lee del archivo: los guardados antes de pasar a metros tienen `range`, en cuadras,
+/// y los guardados antes de agregar el campo de visión o las lindantes manuales no los tienen.
+#[derive(Deserialize)]
+struct StoredCameraRecord {
+    id: u32,
+    latitude: f64,
+    longitude: f64,
+    range_meters: Option<f64>,
+    range: Option<u8>,
+    #[serde(default)]
+    fov: CameraFov,
+    #[serde(default)]
+    pinned_bordering: Vec<u32>,
+    #[serde(default)]
+    unpinned_bordering: Vec<u32>,
+    created_at: RecordTimestamp,
+    modified_at: Option<RecordTimestamp>,
+    deleted_at: Option<RecordTimestamp>,
+}
+
+impl From<StoredCameraRecord> for CameraRecord {
+    fn from(stored: StoredCameraRecord) -> Self {
+        let range_meters = stored
+            .range_meters
+            .unwrap_or_else(|| legacy_range_to_meters(stored.range.unwrap_or(0)));
+        Self {
+            id: stored.id,
+            latitude: stored.latitude,
+            longitude: stored.longitude,
+            range_meters,
+            fov: stored.fov,
+            pinned_bordering: stored.pinned_bordering,
+            unpinned_bordering: stored.unpinned_bordering,
+            created_at: stored.created_at,
+            modified_at: stored.modified_at,
+            deleted_at: stored.deleted_at,
+        }
+    }
+}
+
+/// Devuelve el momento actual, en milisegundos desde epoch.
+pub fn now_as_record_timestamp() -> RecordTimestamp {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as RecordTimestamp)
+        .unwrap_or(0)
+}
@@ -0,0 +1,158 @@
+use std::{
+    fs::{self, File},
+    io::{Error, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{camera_record::CameraRecord, registry::CameraRegistry};
+
+/// Contenido del archivo del registro.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredCameras {
+    cameras: Vec<CameraRecord>,
+}
+
+/// Registro de cámaras guardado como json en un archivo.
+/// Cada `save` escribe un archivo temporal y lo renombra sobre el original, de modo que ante una falla
+/// a mitad de la escritura se conserva el contenido anterior completo.
+#[derive(Debug, Clone)]
+pub struct JsonFileCameraRegistry {
+    path: PathBuf,
+}
+
+impl JsonFileCameraRegistry {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    fn tmp_path(&self) -> PathBuf {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        PathBuf::from(tmp)
+    }
+}
+
+impl CameraRegistry for JsonFileCameraRegistry {
+    fn load(&self) -> Result<Option<Vec<CameraRecord>>, Error> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let stored: StoredCameras = serde_json::from_str(&contents).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Registro de cámaras inválido en {:?}: {}", self.path, e),
+            )
+        })?;
+        Ok(Some(stored.cameras))
+    }
+
+    fn save(&mut self, records: &[CameraRecord]) -> Result<(), Error> {
+        let stored = StoredCameras {
+            cameras: records.to_vec(),
+        };
+        let json = serde_json::to_string_pretty(&stored)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+        let tmp_path = self.tmp_path();
+        let mut tmp_file = File::create(&tmp_path)?;
+        tmp_file.write_all(json.as_bytes())?;
+        tmp_file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf};
+
+    use super::JsonFileCameraRegistry;
+    use crate::apps::sist_camaras::{
+        camera::Camera, camera_fov::CameraFov, camera_registry::registry::CameraRegistry,
+    };
+
+    fn registry_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "rustx_camera_registry_{}_{}.json",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_1_registro_sin_archivo_no_tiene_nada_guardado() {
+        let path = registry_path("vacio");
+        let registry = JsonFileCameraRegistry::new(&path);
+
+        assert_eq!(registry.load().unwrap(), None);
+    }
+
+    #[test]
+    fn test_2_alta_modificacion_y_baja_quedan_guardadas_con_sus_timestamps() {
+        let path = registry_path("abm");
+        let mut registry = JsonFileCameraRegistry::new(&path);
+        registry.save(&[]).unwrap();
+
+        registry
+            .record_created(&Camera::new(1, -34.60, -58.38, 250.0))
+            .unwrap();
+        registry
+            .record_created(&Camera::new(2, -34.61, -58.39, 400.0))
+            .unwrap();
+        let mut modified_camera = Camera::new(1, -34.62, -58.40, 500.0);
+        modified_camera.set_fov(CameraFov::new(180.0, 90.0, None).unwrap());
+        registry.record_modified(&modified_camera).unwrap();
+        registry.record_deleted(2).unwrap();
+
+        // Otra instancia, como al reiniciar, lee lo mismo
+        let records = JsonFileCameraRegistry::new(&path).load().unwrap().unwrap();
+        assert_eq!(records.len(), 2);
+        let modified = records.iter().find(|r| r.get_id() == 1).unwrap();
+        assert_eq!(modified.to_camera().get_position(), (-34.62, -58.40));
+        assert_eq!(modified.to_camera().get_range(), 500.0);
+        assert_eq!(modified.to_camera().get_fov(), modified_camera.get_fov());
+        assert!(modified.get_modified_at().unwrap() >= modified.get_created_at());
+        assert!(!modified.is_deleted());
+        let deleted = records.iter().find(|r| r.get_id() == 2).unwrap();
+        assert!(deleted.get_deleted_at().unwrap() >= deleted.get_created_at());
+
+        // No se puede modificar ni borrar una cámara borrada, y no queda el archivo temporal
+        assert!(registry.record_deleted(2).is_err());
+        assert!(registry
+            .record_modified(&Camera::new(2, -34.61, -58.39, 400.0))
+            .is_err());
+        assert!(!registry.tmp_path().exists());
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_3_un_registro_con_el_rango_en_cuadras_se_carga_en_metros() {
+        let path = registry_path("cuadras");
+        fs::write(
+            &path,
+            r#"{"cameras": [{"id": 1, "latitude": -34.6, "longitude": -58.38, "range": 1,
+                "created_at": 10, "modified_at": null, "deleted_at": null}]}"#,
+        )
+        .unwrap();
+
+        let records = JsonFileCameraRegistry::new(&path).load().unwrap().unwrap();
+        let range = records[0].to_camera().get_range();
+        assert!((range - 283.5).abs() < 1.0, "rango: {}", range);
+        // Tampoco tiene campo de visión: ve en todas las direcciones
+        assert_eq!(records[0].to_camera().get_fov(), CameraFov::default());
+
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod camera_record;
+pub mod json_file_registry;
+pub mod registry;
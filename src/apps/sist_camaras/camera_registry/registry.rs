@@ -0,0 +1,75 @@
+use std::{
+    fmt::Debug,
+    io::{Error, ErrorKind},
+};
+
+use crate::apps::sist_camaras::{bordering::BorderingPins, camera::Camera};
+
+use super::camera_record::{now_as_record_timestamp, CameraRecord};
+
+/// Almacenamiento durable de las cámaras de sistema cámaras, para no perder los cambios hechos con el abm
+/// al reiniciar. Cada implementación decide dónde y cómo se guardan los registros; las operaciones del abm
+/// se implementan a partir de `load` y `save`.
+pub trait CameraRegistry: Debug + Send {
+    /// Devuelve todos los registros guardados, incluidos los de cámaras borradas.
+    /// Devuelve `None` si todavía no se guardó nada (ie primera ejecución).
+    fn load(&self) -> Result<Option<Vec<CameraRecord>>, Error>;
+
+    /// Reemplaza todo lo guardado por `records`.
+    fn save(&mut self, records: &[CameraRecord]) -> Result<(), Error>;
+
+    /// Registra la creación de `camera`. Si ya existía un registro con su id, lo reemplaza.
+    fn record_created(&mut self, camera: &Camera) -> Result<(), Error> {
+        let mut records = self.load()?.unwrap_or_default();
+        records.retain(|record| record.get_id() != camera.get_id());
+        records.push(CameraRecord::new(camera, now_as_record_timestamp()));
+        self.save(&records)
+    }
+
+    /// Registra la modificación de `camera`, que debe existir y no estar borrada.
+    fn record_modified(&mut self, camera: &Camera) -> Result<(), Error> {
+        let mut records = self.load()?.unwrap_or_default();
+        find_active_record(&mut records, camera.get_id())?
+            .set_modified(camera, now_as_record_timestamp());
+        self.save(&records)
+    }
+
+    /// Registra, según `pins`, las cámaras fijadas y excluidas manualmente como lindantes de cada una de las
+    /// cámaras `ids`, que deben existir y no estar borradas. Se guardan todas juntas, o ninguna.
+    fn record_bordering_pins(&mut self, pins: &BorderingPins, ids: &[u32]) -> Result<(), Error> {
+        let mut records = self.load()?.unwrap_or_default();
+        for id in ids {
+            find_active_record(&mut records, *id)?
+                .set_bordering_pins(&pins.pinned_of(*id), &pins.unpinned_of(*id));
+        }
+        self.save(&records)
+    }
+
+    /// Registra el borrado lógico de la cámara de id `id`, que debe existir y no estar borrada.
+    /// Además la quita de las lindantes fijadas o excluidas manualmente de las demás cámaras.
+    fn record_deleted(&mut self, id: u32) -> Result<(), Error> {
+        let mut records = self.load()?.unwrap_or_default();
+        find_active_record(&mut records, id)?.set_deleted(now_as_record_timestamp());
+        for record in records.iter_mut().filter(|record| record.get_id() != id) {
+            let without_id = |others: &[u32]| -> Vec<u32> {
+                others.iter().copied().filter(|other| *other != id).collect()
+            };
+            let pinned = without_id(record.get_pinned_bordering());
+            let unpinned = without_id(record.get_unpinned_bordering());
+            record.set_bordering_pins(&pinned, &unpinned);
+        }
+        self.save(&records)
+    }
+}
+
+fn find_active_record(records: &mut [CameraRecord], id: u32) -> Result<&mut CameraRecord, Error> {
+    records
+        .iter_mut()
+        .find(|record| record.get_id() == id && !record.is_deleted())
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("La cámara {} no está en el registro.", id),
+            )
+        })
+}
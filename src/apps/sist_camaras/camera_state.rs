@@ -1,7 +1,13 @@
-#[derive(Debug, PartialEq, Clone, Copy)]
+use serde::{Deserialize, Serialize};
+
+/// Estado de una cámara. `Offline` y `Fault` son estados degradados: la cámara dejó de producir imágenes,
+/// o no se puede leer su directorio de imágenes, respectivamente (ver `camera_health`).
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum CameraState {
     Active,
     SavingMode,
+    Offline,
+    Fault,
 }
 
 impl CameraState {
@@ -9,6 +15,8 @@ impl CameraState {
         match self {
             CameraState::Active => 1_u8.to_be_bytes(),
             CameraState::SavingMode => 2_u8.to_be_bytes(),
+            CameraState::Offline => 3_u8.to_be_bytes(),
+            CameraState::Fault => 4_u8.to_be_bytes(),
         }
     }
 
@@ -16,7 +24,23 @@ impl CameraState {
         match u8::from_be_bytes(bytes) {
             1 => CameraState::Active,
             2 => CameraState::SavingMode,
+            3 => CameraState::Offline,
+            4 => CameraState::Fault,
             _ => panic!("Estado de cámara no válido"),
         }
     }
+
+    /// Devuelve si es un estado degradado (`Offline` o `Fault`).
+    pub fn is_degraded(&self) -> bool {
+        matches!(self, CameraState::Offline | CameraState::Fault)
+    }
+
+    /// Estado a enviar en los formatos que no conocen los estados degradados: éstos se envían como `SavingMode`.
+    pub(crate) fn without_degraded(self) -> Self {
+        if self.is_degraded() {
+            CameraState::SavingMode
+        } else {
+            self
+        }
+    }
 }
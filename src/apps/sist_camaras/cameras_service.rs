@@ -0,0 +1,760 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Error, ErrorKind},
+    sync::{mpsc::Sender, Arc, Mutex, MutexGuard},
+};
+
+use crate::logging::string_logger::StringLogger;
+use crate::mqtt::client::typed_topic::MqttPayload;
+
+use super::{
+    bordering::{add_bordering_cameras, update_bordering_pair, BorderingPin},
+    camera::{Camera, CameraSystemId, DEFAULT_CAMERA_SYSTEM_ID},
+    camera_command::{CameraCommand, CameraCommandReply, CameraCommandRequest},
+    camera_fov::CameraFov,
+    camera_health::FeedMonitor,
+    camera_registry::registry::CameraRegistry,
+    sistema_camaras_logic::{CamerasLogic, ShCamerasLogic},
+    types::shareable_cameras_type::ShCamerasType,
+};
+
+/// `CamerasService` compartido entre el abm por consola y el hilo que atiende los comandos recibidos por MQTT.
+pub type ShCamerasService = Arc<Mutex<CamerasService>>;
+
+/// Operaciones de administración de las cámaras (alta, baja, modificación y listado), independientes de
+/// desde dónde se pidan. Cada cambio se guarda primero en el registro, sin tener tomados los locks de las
+/// cámaras ni de la lógica, y sólo si se pudo guardar se aplica en memoria: si falla el registro, se devuelve
+/// su error y las cámaras quedan como estaban. Las cámaras que cambian se envían por `camera_tx` para que
+/// sistema cámaras las publique.
+#[derive(Debug)]
+pub struct CamerasService {
+    cameras: ShCamerasType,
+    logic: ShCamerasLogic,
+    registry: Box<dyn CameraRegistry>,
+    camera_tx: Sender<Vec<u8>>,
+    system_id: CameraSystemId,
+    logger: StringLogger,
+}
+
+impl CamerasService {
+    /// Crea un `CamerasService`. Con `logic` se reevalúan los incidentes activos al modificar una cámara.
+    pub fn new(
+        cameras: ShCamerasType,
+        logic: ShCamerasLogic,
+        registry: Box<dyn CameraRegistry>,
+        camera_tx: Sender<Vec<u8>>,
+        logger: StringLogger,
+    ) -> Self {
+        Self {
+            cameras,
+            logic,
+            registry,
+            camera_tx,
+            system_id: DEFAULT_CAMERA_SYSTEM_ID,
+            logger,
+        }
+    }
+
+    /// Indica el sistema de cámaras al que pertenecen las cámaras que se agreguen. Por defecto, el de
+    /// `DEFAULT_CAMERA_SYSTEM_ID`.
+    pub fn set_system_id(&mut self, system_id: CameraSystemId) {
+        self.system_id = system_id;
+    }
+
+    /// Agrega la cámara recibida al sistema de cámaras del service, manejando sus lindantes, y la envía para publicarla.
+    /// Devuelve error si ya existe una cámara con su id, o si no se pudo guardar el alta en el registro.
+    pub fn add_camera(&mut self, mut new_camera: Camera) -> Result<Camera, Error> {
+        new_camera.set_system_id(self.system_id);
+        if lock_cameras(&self.cameras)?.contains_key(&new_camera.get_id()) {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("Ya existe la cámara {}.", new_camera.get_id()),
+            ));
+        }
+        // Guarda el alta en el registro (sólo este service agrega cámaras, así que el id sigue libre al aplicarla)
+        self.registry
+            .record_created(&new_camera)
+            .map_err(|e| self.registry_error("alta", e))?;
+
+        // Primero la lógica (que tiene el índice) y luego las cámaras
+        let mut logic = lock_logic(&self.logic)?;
+        let mut cams = lock_cameras(&self.cameras)?;
+        // Agrega la nueva cámara como lindante de las cercanas y viceversa, terminando la creación
+        add_bordering_cameras(
+            &mut cams,
+            logic.get_index(),
+            logic.get_bordering_rules(),
+            &mut new_camera,
+        );
+        logic.get_index_mut().insert(&new_camera);
+        // Envía la nueva cámara por tx, para ser publicada por el otro hilo
+        self.send_camera_bytes(&new_camera);
+        cams.insert(new_camera.get_id(), new_camera.clone());
+        Ok(new_camera)
+    }
+
+    /// Elimina (borrado lógico) la cámara del id recibido, quitándola de las lindantes de las demás, y la envía para publicarla.
+    /// Devuelve error si la cámara no existe, o si no se pudo guardar la baja en el registro.
+    pub fn delete_camera(&mut self, id: u32) -> Result<Camera, Error> {
+        if !self.camera_exists(id) {
+            return Err(camera_not_found_error(id));
+        }
+        // Guarda la baja en el registro, que también olvida las lindantes fijadas o excluidas que la involucraban
+        self.registry
+            .record_deleted(id)
+            .map_err(|e| self.registry_error("baja", e))?;
+
+        let mut logic = lock_logic(&self.logic)?;
+        let mut cams = lock_cameras(&self.cameras)?;
+        let Some(mut camera_to_delete) = cams.remove(&id) else {
+            return Err(camera_not_found_error(id));
+        };
+        camera_to_delete.delete_camera();
+        logic.get_index_mut().remove(id);
+        logic.get_bordering_rules_mut().get_pins_mut().remove_camera(id);
+
+        // Elimina la cámara a eliminar de la lista de lindantes de sus lindantes, terminando la eliminación
+        for bordering_id in camera_to_delete.get_bordering_cams().clone() {
+            if let Some(camera) = cams.get_mut(&bordering_id) {
+                camera.remove_from_list_if_bordering(&mut camera_to_delete);
+            }
+        }
+
+        // Envía por el tx la cámara a eliminar para que se publique desde el otro hilo
+        // (con eso es suficiente. Si bien se les eliminó una lindante, no es necesario publicar el cambio
+        // de las demás ya que eso solo es relevante para sistema camaras)
+        self.send_camera_bytes(&camera_to_delete);
+        Ok(camera_to_delete)
+    }
+
+    /// Modifica la posición, el rango y el campo de visión de la cámara del id recibido. Recalcula sus lindantes y las de las cámaras
+    /// afectadas, y reevalúa los incidentes activos, ya que la cámara puede haber empezado o dejado de cubrir alguno.
+    /// Envía por tx todas las cámaras que cambiaron, para que se publiquen desde el otro hilo.
+    /// Devuelve error si la cámara no existe, o si no se pudo guardar la modificación en el registro.
+    pub fn modify_camera(
+        &mut self,
+        id: u32,
+        latitude: f64,
+        longitude: f64,
+        range: f64,
+        fov: CameraFov,
+    ) -> Result<Camera, Error> {
+        // Guarda la modificación en el registro, a partir de una copia de la cámara ya modificada
+        let mut modified = lock_cameras(&self.cameras)?
+            .get(&id)
+            .filter(|camera| camera.is_not_deleted())
+            .cloned()
+            .ok_or_else(|| camera_not_found_error(id))?;
+        modified.set_position_and_range(latitude, longitude, range);
+        modified.set_fov(fov);
+        self.registry
+            .record_modified(&modified)
+            .map_err(|e| self.registry_error("modificación", e))?;
+
+        // Primero la lógica y luego las cámaras, en el mismo orden en que los toma el hilo que procesa incidentes
+        let mut logic = lock_logic(&self.logic)?;
+        let mut cams = lock_cameras(&self.cameras)?;
+        let Some(mut camera_to_modify) = cams.remove(&id) else {
+            return Err(camera_not_found_error(id));
+        };
+
+        // Se quita de las lindantes de sus lindantes, se mueve, y se recalculan sus lindantes desde cero
+        let bordering_before: HashSet<u32> = camera_to_modify.get_bordering_cams().drain(..).collect();
+        for bordering_id in &bordering_before {
+            if let Some(camera) = cams.get_mut(bordering_id) {
+                camera.remove_from_list_if_bordering(&mut camera_to_modify);
+            }
+        }
+        camera_to_modify.set_position_and_range(latitude, longitude, range);
+        camera_to_modify.set_fov(fov);
+        logic.get_index_mut().insert(&camera_to_modify);
+        let bordering_now: HashSet<u32> = add_bordering_cameras(
+            &mut cams,
+            logic.get_index(),
+            logic.get_bordering_rules(),
+            &mut camera_to_modify,
+        )
+        .into_iter()
+        .collect();
+        cams.insert(id, camera_to_modify);
+
+        // Cambiaron la cámara modificada, las que ganaron o perdieron una lindante, y las que cambiaron de estado
+        let mut changed: HashSet<u32> = HashSet::from([id]);
+        changed.extend(bordering_before.symmetric_difference(&bordering_now));
+        changed.extend(logic.reevaluate_active_incidents(&mut cams));
+
+        for cam_id in &changed {
+            if let Some(camera) = cams.get(cam_id) {
+                self.send_camera_bytes(camera);
+            }
+        }
+        cams.get(&id)
+            .cloned()
+            .ok_or_else(|| camera_not_found_error(id))
+    }
+
+    /// Fija (`Pinned`) o excluye (`Unpinned`) a las cámaras `id` y `other_id` como lindantes entre sí, o las vuelve
+    /// al cálculo según la política de lindantes (`Automatic`). Guarda la elección en el registro, y reevalúa los
+    /// incidentes activos ya que cambian las lindantes que se activan. Envía por tx las cámaras que cambiaron.
+    /// Devuelve ambas cámaras, o error si alguna no existe o si no se pudo guardar la elección en el registro.
+    pub fn set_bordering_pin(
+        &mut self,
+        id: u32,
+        other_id: u32,
+        pin: BorderingPin,
+    ) -> Result<Vec<Camera>, Error> {
+        if id == other_id {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Una cámara no puede ser lindante de sí misma.",
+            ));
+        }
+        for cam_id in [id, other_id] {
+            if !self.camera_exists(cam_id) {
+                return Err(camera_not_found_error(cam_id));
+            }
+        }
+        // Guarda en el registro las elecciones de ambas cámaras, a partir de una copia con la nueva elección
+        let mut pins = lock_logic(&self.logic)?.get_bordering_rules().get_pins().clone();
+        pins.set(id, other_id, pin);
+        self.registry
+            .record_bordering_pins(&pins, &[id, other_id])
+            .map_err(|e| self.registry_error("elección de lindantes", e))?;
+
+        let mut logic = lock_logic(&self.logic)?;
+        let mut cams = lock_cameras(&self.cameras)?;
+        logic.get_bordering_rules_mut().get_pins_mut().set(id, other_id, pin);
+        update_bordering_pair(&mut cams, logic.get_bordering_rules(), id, other_id)?;
+
+        let mut changed: HashSet<u32> = HashSet::from([id, other_id]);
+        changed.extend(logic.reevaluate_active_incidents(&mut cams));
+        for cam_id in &changed {
+            if let Some(camera) = cams.get(cam_id) {
+                self.send_camera_bytes(camera);
+            }
+        }
+        Ok([id, other_id]
+            .iter()
+            .filter_map(|cam_id| cams.get(cam_id).cloned())
+            .collect())
+    }
+
+    /// Devuelve las cámaras no borradas, ordenadas por id.
+    pub fn list_cameras(&self) -> Result<Vec<Camera>, Error> {
+        let mut cameras: Vec<Camera> = lock_cameras(&self.cameras)?
+            .values()
+            .filter(|camera| camera.is_not_deleted())
+            .cloned()
+            .collect();
+        cameras.sort_by_key(|camera| camera.get_id());
+        Ok(cameras)
+    }
+
+    /// Devuelve si existe la cámara del id recibido (y no está borrada).
+    pub fn camera_exists(&self, id: u32) -> bool {
+        lock_cameras(&self.cameras)
+            .map(|cams| cams.get(&id).is_some_and(|camera| camera.is_not_deleted()))
+            .unwrap_or(false)
+    }
+
+    /// Ejecuta `command`, devolviendo las cámaras resultantes: la agregada, eliminada o modificada, o todas al listar.
+    pub fn execute(&mut self, command: &CameraCommand) -> Result<Vec<Camera>, Error> {
+        match *command {
+            CameraCommand::Add {
+                id,
+                latitude,
+                longitude,
+                range,
+                fov,
+            } => {
+                let mut camera = Camera::new(id, latitude, longitude, range);
+                camera.set_fov(fov);
+                Ok(vec![self.add_camera(camera)?])
+            }
+            CameraCommand::Modify {
+                id,
+                latitude,
+                longitude,
+                range,
+                fov,
+            } => Ok(vec![self.modify_camera(id, latitude, longitude, range, fov)?]),
+            CameraCommand::Delete { id } => Ok(vec![self.delete_camera(id)?]),
+            CameraCommand::List => self.list_cameras(),
+        }
+    }
+
+    /// Ejecuta el comando del pedido recibido por MQTT, y devuelve la respuesta a publicar en su reply topic.
+    pub fn handle_request(&mut self, request: &CameraCommandRequest) -> CameraCommandReply {
+        self.logger
+            .log(format!("Sistema-Camaras: recibido comando: {:?}", request));
+        let result = self.execute(request.get_command());
+        if let Err(e) = &result {
+            self.logger.log(format!(
+                "Sistema-Camaras: error al ejecutar el comando: {:?}.",
+                e
+            ));
+        }
+        CameraCommandReply::new(request.get_correlation_id(), result)
+    }
+
+    /// Actualiza el estado de las cámaras no borradas según su salud, controlada por `monitor`.
+    /// No envía las que cambiaron: se envían todas con el latido (`send_all_cameras`). Devuelve sus ids.
+    pub fn update_cameras_health(&self, monitor: &FeedMonitor) -> Result<Vec<u32>, Error> {
+        let mut cams = lock_cameras(&self.cameras)?;
+        let mut changed = vec![];
+        for (id, camera) in cams.iter_mut().filter(|(_, camera)| camera.is_not_deleted()) {
+            let health = monitor.check(*id);
+            if camera.set_health(health) {
+                self.logger.log(format!(
+                    "Sistema-Camaras: la cámara {} pasa a {:?} ({:?}).",
+                    id,
+                    camera.get_state(),
+                    health
+                ));
+                changed.push(*id);
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Recorre las cámaras y envía cada una por el channel, para que quien lea del rx haga el publish.
+    pub fn send_all_cameras(&self) {
+        match lock_cameras(&self.cameras) {
+            Ok(cams) => {
+                for camera in cams.values() {
+                    self.send_camera_bytes(camera);
+                }
+            }
+            Err(e) => self.logger.log(format!("Sistema-Camaras: {:?}.", e)),
+        }
+    }
+
+    /// Codifica la cámara recibida y la envía por el channel, para que quien la reciba por rx haga el publish.
+    /// Además logguea la operación. Devuelve si se la pudo enviar.
+    fn send_camera_bytes(&self, camera: &Camera) -> bool {
+        self.logger
+            .log(format!("Sistema-Camaras: envío cámara: {:?}", camera));
+
+        let camera_bytes = match camera.encode() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.logger.log(format!(
+                    "Sistema-Camaras: error al codificar la cámara: {:?}.",
+                    e
+                ));
+                return false;
+            }
+        };
+        if self.camera_tx.send(camera_bytes).is_err() {
+            self.logger
+                .log("Sistema-Camaras: error al enviar cámara por tx.".to_string());
+            return false;
+        }
+        true
+    }
+
+    /// Informa que no se pudo guardar en el registro la operación `operation`, que no se aplica en memoria.
+    /// Devuelve el error, para devolverlo a quien la pidió.
+    fn registry_error(&self, operation: &str, e: Error) -> Error {
+        println!(
+            "Error al guardar la {} en el registro de cámaras: {:?}.",
+            operation, e
+        );
+        self.logger.log(format!(
+            "Sistema-Camaras: error al guardar la {} en el registro de cámaras: {:?}.",
+            operation, e
+        ));
+        e
+    }
+}
+
+fn lock_cameras(cameras: &ShCamerasType) -> Result<MutexGuard<'_, HashMap<u32, Camera>>, Error> {
+    cameras
+        .lock()
+        .map_err(|_| Error::other("Error al tomar lock de cámaras."))
+}
+
+fn lock_logic(logic: &ShCamerasLogic) -> Result<MutexGuard<'_, CamerasLogic>, Error> {
+    logic.lock().map_err(|_| {
+        Error::other(
+            "Error al tomar lock de la lógica de cámaras.",
+        )
+    })
+}
+
+fn camera_not_found_error(id: u32) -> Error {
+    Error::new(ErrorKind::NotFound, format!("La cámara {} no existe.", id))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::{HashMap, HashSet},
+        sync::{
+            mpsc::{self, Receiver},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    use crate::{
+        apps::{
+            incident_data::{incident::Incident, incident_source::IncidentSource},
+            geo,
+            sist_camaras::{
+                bordering::{BorderingPin, BorderingPins, BorderingPolicy, BorderingRules},
+                camera::Camera,
+                camera_command::{CameraCommand, CameraCommandRequest},
+                camera_fov::CameraFov,
+                camera_health::FeedMonitor,
+                camera_registry::{
+                    json_file_registry::JsonFileCameraRegistry, registry::CameraRegistry,
+                },
+                camera_state::CameraState,
+                manage_stored_cameras::load_cameras,
+                sistema_camaras_logic::CamerasLogic,
+            },
+        },
+        logging::string_logger::StringLogger,
+        mqtt::client::typed_topic::MqttPayload,
+    };
+
+    use super::CamerasService;
+
+    /// Registro en un archivo temporal, vacío.
+    fn create_registry(name: &str) -> JsonFileCameraRegistry {
+        let path = std::env::temp_dir().join(format!(
+            "rustx_cameras_service_{}_{}.json",
+            name,
+            std::process::id()
+        ));
+        let mut registry = JsonFileCameraRegistry::new(path);
+        registry.save(&[]).unwrap();
+        registry
+    }
+
+    /// Crea el service, y devuelve también el rx por el que se reciben las cámaras a publicar.
+    fn create_service(registry: JsonFileCameraRegistry) -> (CamerasService, Receiver<Vec<u8>>) {
+        let (camera_tx, camera_rx) = mpsc::channel();
+        let cameras = Arc::new(Mutex::new(HashMap::new()));
+        let (string_logger_tx, _string_logger_rx) = mpsc::channel();
+        let logger_for_testing = StringLogger::new(string_logger_tx);
+        let logic = CamerasLogic::new(
+            cameras.clone(),
+            camera_tx.clone(),
+            logger_for_testing.clone_ref(),
+        );
+
+        let service = CamerasService::new(
+            cameras,
+            Arc::new(Mutex::new(logic)),
+            Box::new(registry),
+            camera_tx,
+            logger_for_testing,
+        );
+        (service, camera_rx)
+    }
+
+    /// Devuelve los ids de las cámaras enviadas para publicar hasta el momento.
+    fn sent_camera_ids(camera_rx: &Receiver<Vec<u8>>) -> HashSet<u32> {
+        camera_rx
+            .try_iter()
+            .map(|bytes| Camera::decode(&bytes).unwrap().get_id())
+            .collect()
+    }
+
+    fn state_of(service: &CamerasService, id: u32) -> CameraState {
+        service
+            .cameras
+            .lock()
+            .unwrap()
+            .get(&id)
+            .unwrap()
+            .get_state()
+    }
+
+    #[test]
+    fn test_1_alta_de_camara_la_agrega_a_cameras() {
+        let (mut service, _camera_rx) = create_service(create_registry("alta"));
+
+        // Se agrega la cámara
+        let new_camera_id = 1;
+        service
+            .add_camera(Camera::new(new_camera_id, -34.0, -58.0, 500.0))
+            .unwrap();
+
+        // La cámara nueva se ha agregado a cameras, y no se puede volver a agregar
+        assert!(service.camera_exists(new_camera_id));
+        assert!(service
+            .add_camera(Camera::new(new_camera_id, -34.0, -58.0, 500.0))
+            .is_err());
+    }
+
+    #[test]
+    fn test_2_baja_de_camara_la_elimina_de_cameras() {
+        let (mut service, _camera_rx) = create_service(create_registry("baja"));
+
+        // Se agrega la cámara, y se la elimina
+        let camera_to_remove_id = 1;
+        service
+            .add_camera(Camera::new(camera_to_remove_id, -34.0, -58.0, 500.0))
+            .unwrap();
+        service.delete_camera(camera_to_remove_id).unwrap();
+
+        assert!(!service
+            .cameras
+            .lock()
+            .unwrap()
+            .contains_key(&camera_to_remove_id));
+        assert!(service.delete_camera(camera_to_remove_id).is_err());
+    }
+
+    #[test]
+    fn test_3_altas_y_bajas_se_guardan_en_el_registro() {
+        let registry = create_registry("registro");
+        let stored = registry.clone();
+        let (mut service, _camera_rx) = create_service(registry);
+
+        service.add_camera(Camera::new(1, -34.0, -58.0, 500.0)).unwrap();
+        service.add_camera(Camera::new(2, -34.1, -58.1, 500.0)).unwrap();
+        service.delete_camera(2).unwrap();
+
+        // Lo guardado sobrevive al service, como al reiniciar
+        let records = stored.load().unwrap().unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().any(|r| r.get_id() == 1 && !r.is_deleted()));
+        assert!(records.iter().any(|r| r.get_id() == 2 && r.is_deleted()));
+
+        let _ = std::fs::remove_file(stored.get_path());
+    }
+
+    #[test]
+    fn test_4_modificar_camara_recalcula_lindantes_y_reevalua_incidentes() {
+        let registry = create_registry("modificar");
+        let stored = registry.clone();
+        let (mut service, camera_rx) = create_service(registry);
+        service.add_camera(Camera::new(1, -34.0, -58.0, 250.0)).unwrap();
+        service.add_camera(Camera::new(2, -34.1, -58.1, 250.0)).unwrap();
+
+        // Un incidente junto a la cámara 1, que está lejos de la 2
+        let incident = Incident::new(
+            Incident::generate_id(),
+            (-34.0, -58.0),
+            IncidentSource::Manual,
+        );
+        service
+            .logic
+            .lock()
+            .unwrap()
+            .manage_incident(incident)
+            .unwrap();
+        assert_eq!(state_of(&service, 1), CameraState::Active);
+        assert_eq!(state_of(&service, 2), CameraState::SavingMode);
+        let _ = sent_camera_ids(&camera_rx);
+
+        // La 2 se mueve junto a la 1: son lindantes, y pasa a cubrir el incidente
+        service.modify_camera(2, -34.001, -58.0, 250.0, CameraFov::default()).unwrap();
+        assert_eq!(state_of(&service, 2), CameraState::Active);
+        assert_eq!(
+            service
+                .cameras
+                .lock()
+                .unwrap()
+                .get_mut(&1)
+                .unwrap()
+                .get_bordering_cams(),
+            &vec![2]
+        );
+        assert_eq!(sent_camera_ids(&camera_rx), HashSet::from([1, 2]));
+
+        // La 1 se aleja: deja de ser lindante de la 2 y de cubrir el incidente, que sigue cubriendo la 2
+        service.modify_camera(1, -34.1, -58.1, 250.0, CameraFov::default()).unwrap();
+        assert_eq!(state_of(&service, 1), CameraState::SavingMode);
+        assert_eq!(state_of(&service, 2), CameraState::Active);
+        assert!(service
+            .cameras
+            .lock()
+            .unwrap()
+            .get_mut(&2)
+            .unwrap()
+            .get_bordering_cams()
+            .is_empty());
+        assert_eq!(sent_camera_ids(&camera_rx), HashSet::from([1, 2]));
+
+        // La modificación queda en el registro
+        let records = stored.load().unwrap().unwrap();
+        let modified = records.iter().find(|r| r.get_id() == 1).unwrap();
+        assert_eq!(modified.to_camera().get_position(), (-34.1, -58.1));
+        assert!(modified.get_modified_at().is_some());
+
+        let _ = std::fs::remove_file(stored.get_path());
+    }
+
+    #[test]
+    fn test_5_pedido_por_mqtt_se_responde_con_su_correlation_id() {
+        let (mut service, _camera_rx) = create_service(create_registry("pedidos"));
+        let add = CameraCommand::Add {
+            id: 3,
+            latitude: -34.0,
+            longitude: -58.0,
+            range: 250.0,
+            fov: CameraFov::default(),
+        };
+
+        let reply =
+            service.handle_request(&CameraCommandRequest::new("a", "respuestas", add.clone()));
+        assert_eq!(reply.get_correlation_id(), "a");
+        assert_eq!(reply.get_result().as_ref().unwrap()[0].get_id(), 3);
+
+        // El error viaja en la respuesta
+        let reply = service.handle_request(&CameraCommandRequest::new("b", "respuestas", add));
+        assert_eq!(reply.get_correlation_id(), "b");
+        assert!(reply.get_result().is_err());
+
+        let reply = service.handle_request(&CameraCommandRequest::new(
+            "c",
+            "respuestas",
+            CameraCommand::List,
+        ));
+        assert_eq!(reply.get_result().as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_6_lindantes_fijadas_y_excluidas_se_guardan_en_el_registro() {
+        let registry = create_registry("lindantes");
+        let mut stored = registry.clone();
+        let (mut service, camera_rx) = create_service(registry);
+        service.add_camera(Camera::new(1, -34.0, -58.0, 250.0)).unwrap();
+        service.add_camera(Camera::new(2, -34.1, -58.1, 250.0)).unwrap();
+        service.add_camera(Camera::new(3, -34.2, -58.2, 250.0)).unwrap();
+        let _ = sent_camera_ids(&camera_rx);
+
+        // Están lejos, pero se fijan como lindantes
+        service.set_bordering_pin(1, 2, BorderingPin::Pinned).unwrap();
+        service.set_bordering_pin(3, 1, BorderingPin::Pinned).unwrap();
+        assert_eq!(sent_camera_ids(&camera_rx), HashSet::from([1, 2, 3]));
+        assert!(service.set_bordering_pin(1, 1, BorderingPin::Pinned).is_err());
+        assert!(service.set_bordering_pin(1, 99, BorderingPin::Pinned).is_err());
+
+        // Al reiniciar se cargan como lindantes
+        let (mut cameras, bordering) =
+            load_cameras(&mut stored, "no_existe.properties", BorderingPolicy::default()).unwrap();
+        assert_eq!(cameras.get_mut(&1).unwrap().get_bordering_cams(), &vec![2, 3]);
+        assert_eq!(bordering.get_pins().pinned_of(1), vec![2, 3]);
+
+        // Se excluye la 2, y al borrar la 3 se olvida lo elegido para ella
+        service.set_bordering_pin(2, 1, BorderingPin::Unpinned).unwrap();
+        service.delete_camera(3).unwrap();
+        let (mut cameras, bordering) =
+            load_cameras(&mut stored, "no_existe.properties", BorderingPolicy::default()).unwrap();
+        assert!(cameras.get_mut(&1).unwrap().get_bordering_cams().is_empty());
+        assert_eq!(bordering.get_pins().unpinned_of(1), vec![2]);
+        assert!(bordering.get_pins().pinned_of(1).is_empty());
+
+        let _ = std::fs::remove_file(stored.get_path());
+    }
+
+    #[test]
+    fn test_7_la_profundidad_de_activacion_activa_lindantes_de_lindantes() {
+        let (mut service, _camera_rx) = create_service(create_registry("profundidad"));
+        let policy = BorderingPolicy::new(450.0, None, 2).unwrap();
+        service
+            .logic
+            .lock()
+            .unwrap()
+            .set_bordering_rules(BorderingRules::new(policy, BorderingPins::default()));
+
+        // Cuatro cámaras en línea, cada 400 metros hacia el este: cada una es lindante de las de al lado
+        let mut position = (-34.6037344, -58.3861838);
+        for id in 1..=4 {
+            service
+                .add_camera(Camera::new(id, position.0, position.1, 100.0))
+                .unwrap();
+            position = geo::destination_point(position, 90.0, 400.0);
+        }
+
+        // Un incidente que sólo registra la 1 activa a la 2 (su lindante) y a la 3 (lindante de la 2)
+        let incident = Incident::new(
+            Incident::generate_id(),
+            (-34.6037344, -58.3861838),
+            IncidentSource::Manual,
+        );
+        service
+            .logic
+            .lock()
+            .unwrap()
+            .manage_incident(incident)
+            .unwrap();
+        for (id, expected) in [
+            (1, CameraState::Active),
+            (2, CameraState::Active),
+            (3, CameraState::Active),
+            (4, CameraState::SavingMode),
+        ] {
+            assert_eq!(state_of(&service, id), expected, "cámara {}", id);
+        }
+    }
+
+    #[test]
+    fn test_8_sin_directorio_de_imagenes_la_camara_falla_y_al_volver_retoma_sus_incidentes() {
+        let (mut service, _camera_rx) = create_service(create_registry("salud"));
+        let feeds_dir =
+            std::env::temp_dir().join(format!("rustx_cameras_service_feeds_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&feeds_dir);
+        std::fs::create_dir_all(feeds_dir.join("camera_1")).unwrap();
+        let monitor = FeedMonitor::new(&feeds_dir, Duration::from_secs(60));
+
+        service.add_camera(Camera::new(1, -34.6037344, -58.3861838, 100.0)).unwrap();
+        service.add_camera(Camera::new(2, -34.6040, -58.3873, 100.0)).unwrap();
+        let incident = Incident::new(
+            Incident::generate_id(),
+            (-34.6037344, -58.3861838),
+            IncidentSource::Manual,
+        );
+        service.logic.lock().unwrap().manage_incident(incident).unwrap();
+        assert_eq!(state_of(&service, 2), CameraState::Active);
+
+        // La 2 no tiene directorio de imágenes
+        assert_eq!(service.update_cameras_health(&monitor).unwrap(), vec![2]);
+        assert_eq!(state_of(&service, 1), CameraState::Active);
+        assert_eq!(state_of(&service, 2), CameraState::Fault);
+
+        // Al aparecer, vuelve a prestar atención al incidente
+        std::fs::create_dir_all(feeds_dir.join("camera_2")).unwrap();
+        assert_eq!(service.update_cameras_health(&monitor).unwrap(), vec![2]);
+        assert_eq!(state_of(&service, 2), CameraState::Active);
+        assert!(service.update_cameras_health(&monitor).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&feeds_dir);
+    }
+
+    #[test]
+    fn test_9_si_falla_el_registro_se_devuelve_el_error_y_las_camaras_quedan_como_estaban() {
+        let dir = std::env::temp_dir()
+            .join(format!("rustx_cameras_service_registro_roto_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut registry = JsonFileCameraRegistry::new(dir.join("cameras.json"));
+        registry.save(&[]).unwrap();
+        let (mut service, camera_rx) = create_service(registry);
+        service.add_camera(Camera::new(1, -34.0, -58.0, 250.0)).unwrap();
+        service.add_camera(Camera::new(2, -34.001, -58.0, 250.0)).unwrap();
+        let _ = sent_camera_ids(&camera_rx);
+        let before = service.list_cameras().unwrap();
+
+        // Sin su directorio, el registro no puede guardar nada
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(service.add_camera(Camera::new(3, -34.0, -58.0, 250.0)).is_err());
+        assert!(service.delete_camera(2).is_err());
+        assert!(service
+            .modify_camera(1, -34.1, -58.1, 250.0, CameraFov::default())
+            .is_err());
+        assert!(service.set_bordering_pin(1, 2, BorderingPin::Unpinned).is_err());
+
+        // Nada de eso se aplicó en memoria ni se envió a publicar
+        assert!(!service.camera_exists(3));
+        assert_eq!(service.list_cameras().unwrap(), before);
+        let logic = service.logic.lock().unwrap();
+        assert_eq!(logic.get_bordering_rules().get_pins(), &BorderingPins::default());
+        assert!(sent_camera_ids(&camera_rx).is_empty());
+    }
+}
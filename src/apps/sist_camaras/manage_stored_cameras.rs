@@ -1,40 +1,200 @@
-use std::{collections::HashMap, fs, sync::{Arc, Mutex}};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Error, ErrorKind},
+    sync::{Arc, Mutex},
+};
 
-use super::camera::Camera;
+use super::{
+    bordering::{
+        add_bordering_cameras, BorderingPins, BorderingPolicy, BorderingRules,
+        SIST_CAMARAS_PROPERTIES_FILE,
+    },
+    camera::Camera,
+    camera_fov::CameraFov,
+    camera_index::CameraGridIndex,
+    camera_registry::{
+        camera_record::{now_as_record_timestamp, CameraRecord},
+        json_file_registry::JsonFileCameraRegistry,
+        registry::CameraRegistry,
+    },
+    types::shareable_cameras_type::ShCamerasType,
+};
+
+/// Archivo con las cámaras iniciales, que se importa al registro la primera vez.
+pub const CAMERAS_PROPERTIES_FILE: &str = "./cameras.properties";
+/// Archivo donde se guarda el registro de cámaras, con los cambios hechos desde el abm.
+pub const CAMERAS_REGISTRY_FILE: &str = "./cameras.json";
+
+/// Devuelve el registro de cámaras que usa sistema cámaras por defecto.
+pub fn default_camera_registry() -> JsonFileCameraRegistry {
+    JsonFileCameraRegistry::new(CAMERAS_REGISTRY_FILE)
+}
 
 /// Crea el hashmap de cámaras bien inicializado envuelto en un arc mutex, listo para ser usado
-/// por sistema cámaras y sus módulos.
-pub fn create_cameras() -> Arc<Mutex<HashMap<u8, Camera>>> {
-    let cameras: HashMap<u8, Camera> = read_cameras_from_file("./cameras.properties");
-    Arc::new(Mutex::new(cameras))
+/// por sistema cámaras y sus módulos. Las cámaras se cargan del registro por defecto, con la política de lindantes
+/// de `sistema_camaras.properties`. Devuelve también esa política junto con las lindantes elegidas manualmente.
+/// Devuelve error si no pueden leerse la política o el registro.
+pub fn create_cameras() -> Result<(ShCamerasType, BorderingRules), Error> {
+    let policy = BorderingPolicy::from_properties_file(SIST_CAMARAS_PROPERTIES_FILE)?;
+    let mut registry = default_camera_registry();
+    let (cameras, bordering) = load_cameras(&mut registry, CAMERAS_PROPERTIES_FILE, policy)?;
+    Ok((Arc::new(Mutex::new(cameras)), bordering))
 }
 
-/// Lee las cámaras desde el archivo `filename`, las parsea y las crea, configurando también cuáles
-/// son lindantes entre sí. Devuelve un hashmap con el id de cada cámara como clave y la cámara como valor.
-fn read_cameras_from_file(filename: &str) -> HashMap<u8, Camera> {
-    let mut cameras: HashMap<u8, Camera> = HashMap::new();
-    let contents = fs::read_to_string(filename).expect("Error al leer el archivo de properties");
+/// Carga las cámaras no borradas de `registry`, configurando cuáles son lindantes entre sí según `policy` y
+/// las lindantes fijadas o excluidas manualmente que tenga guardadas.
+/// Si el registro todavía no tiene nada guardado, importa las cámaras de `properties_file` y las guarda en él.
+/// Devuelve un hashmap con el id de cada cámara como clave y la cámara como valor, y las reglas de lindantes usadas.
+pub fn load_cameras(
+    registry: &mut dyn CameraRegistry,
+    properties_file: &str,
+    policy: BorderingPolicy,
+) -> Result<(HashMap<u32, Camera>, BorderingRules), Error> {
+    let records = match registry.load()? {
+        Some(records) => records,
+        None => {
+            let timestamp = now_as_record_timestamp();
+            let records: Vec<CameraRecord> = read_cameras_from_file(properties_file)?
+                .iter()
+                .map(|camera| CameraRecord::new(camera, timestamp))
+                .collect();
+            registry.save(&records)?;
+            records
+        }
+    };
+
+    let bordering = BorderingRules::new(policy, BorderingPins::from_records(&records));
+    let mut cameras: HashMap<u32, Camera> = HashMap::new();
+    let mut index = CameraGridIndex::default();
+    for record in records.iter().filter(|record| !record.is_deleted()) {
+        let mut new_camera = record.to_camera();
+
+        // Agrega la nueva cámara como lindante de las ya cargadas que estén cerca y viceversa, terminando la creación
+        add_bordering_cameras(&mut cameras, &index, &bordering, &mut new_camera);
+        index.insert(&new_camera);
+
+        // Guarda la nueva cámara
+        cameras.insert(new_camera.get_id(), new_camera);
+    }
+
+    Ok((cameras, bordering))
+}
+
+/// Lee las cámaras desde el archivo `filename` y las parsea. Si el archivo no existe, no hay cámaras.
+fn read_cameras_from_file(filename: &str) -> Result<Vec<Camera>, Error> {
+    let mut cameras = vec![];
+    let contents = match fs::read_to_string(filename) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(cameras),
+        Err(e) => return Err(e),
+    };
 
     for line in contents.lines() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        // ID:LATITUD:LONGITUD:RANGO, opcionalmente seguido de :RUMBO:ANGULO, y opcionalmente de :DISTANCIA_MAX
         let parts: Vec<&str> = line.split(':').collect();
-        if parts.len() == 4 {
+        if matches!(parts.len(), 4 | 6 | 7) {
             // Lee los atributos a cargar a la nueva cámara
-            let id: u8 = parts[0].trim().parse().expect("Id no válido");
-            let latitude = parts[1].trim().parse().expect("Latitud no válida");
-            let longitude = parts[2].trim().parse().expect("Longitud no válida");
-            let range = parts[3].trim().parse().expect("Rango no válido"); // []
+            let id: u32 = parse_field(parts[0], "Id no válido")?;
+            let latitude = parse_field(parts[1], "Latitud no válida")?;
+            let longitude = parse_field(parts[2], "Longitud no válida")?;
+            let range = parse_field(parts[3], "Rango no válido")?;
 
-            let mut new_camera = Camera::new(id, latitude, longitude, range);
-
-            // Recorre las cámaras ya existentes, agregando la nueva cámara como lindante de la que corresponda y viceversa, terminando la creación
-            for camera in cameras.values_mut() {
-                camera.mutually_add_if_bordering(&mut new_camera);
+            let mut camera = Camera::new(id, latitude, longitude, range);
+            if parts.len() > 4 {
+                let heading = parse_field(parts[4], "Rumbo no válido")?;
+                let angle = parse_field(parts[5], "Ángulo de visión no válido")?;
+                let max_distance = match parts.get(6) {
+                    Some(field) => Some(parse_field(field, "Distancia máxima no válida")?),
+                    None => None,
+                };
+                camera.set_fov(CameraFov::new(heading, angle, max_distance)?);
             }
-
-            // Guarda la nueva cámara
-            cameras.insert(id, new_camera);
+            cameras.push(camera);
         }
     }
 
-    cameras
+    Ok(cameras)
+}
+
+fn parse_field<T: std::str::FromStr>(field: &str, error_msg: &str) -> Result<T, Error> {
+    field
+        .trim()
+        .parse()
+        .map_err(|_| Error::new(ErrorKind::InvalidData, format!("{}: {}", error_msg, field)))
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::{load_cameras, read_cameras_from_file, BorderingPolicy};
+    use crate::apps::sist_camaras::{camera::Camera, camera_fov::CameraFov};
+    use crate::apps::sist_camaras::camera_registry::{
+        json_file_registry::JsonFileCameraRegistry, registry::CameraRegistry,
+    };
+
+    #[test]
+    fn test_1_primera_carga_importa_el_properties_y_luego_se_usa_el_registro() {
+        let dir = std::env::temp_dir().join(format!("rustx_stored_cameras_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let properties_file = dir.join("cameras.properties");
+        fs::write(
+            &properties_file,
+            "# Formato: ID:LATITUD:LONGITUD:RANGO\n1:-34.6090:-58.3873:250\n2:-34.6095:-58.3878:250\n3:-34.7000:-58.5000:250\n",
+        )
+        .unwrap();
+        let properties_file = properties_file.to_str().unwrap();
+        let mut registry = JsonFileCameraRegistry::new(dir.join("cameras.json"));
+        let _ = fs::remove_file(registry.get_path());
+
+        let mut cameras = load_cameras(&mut registry, properties_file, BorderingPolicy::default())
+            .unwrap()
+            .0;
+        assert_eq!(cameras.len(), 3);
+        assert_eq!(cameras.get_mut(&1).unwrap().get_bordering_cams(), &vec![2]);
+
+        // Cambios hechos con el abm: se borra la 2 y se agrega la 4, lindante de la 3
+        registry.record_deleted(2).unwrap();
+        registry
+            .record_created(&Camera::new(4, -34.7005, -58.5005, 250.0))
+            .unwrap();
+
+        // Al reiniciar, se cargan del registro (no del properties) y se recalculan las lindantes
+        let mut cameras = load_cameras(&mut registry, properties_file, BorderingPolicy::default())
+            .unwrap()
+            .0;
+        assert_eq!(cameras.len(), 3);
+        assert!(!cameras.contains_key(&2));
+        assert!(cameras.get_mut(&1).unwrap().get_bordering_cams().is_empty());
+        assert_eq!(cameras.get_mut(&3).unwrap().get_bordering_cams(), &vec![4]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_2_el_properties_admite_rumbo_angulo_y_distancia_maxima() {
+        let path = std::env::temp_dir().join(format!("rustx_cameras_fov_{}.properties", std::process::id()));
+        fs::write(
+            &path,
+            "# Formato: ID:LATITUD:LONGITUD:RANGO[:RUMBO:ANGULO[:DISTANCIA_MAX]]\n1:-34.6:-58.38:250\n2:-34.6:-58.38:250:90:60\n3:-34.6:-58.38:250:270:120:100\n",
+        )
+        .unwrap();
+
+        let cameras = read_cameras_from_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(cameras.len(), 3);
+        assert_eq!(cameras[0].get_fov(), CameraFov::default());
+        assert_eq!(cameras[1].get_fov(), CameraFov::new(90.0, 60.0, None).unwrap());
+        assert_eq!(cameras[2].get_fov(), CameraFov::new(270.0, 120.0, Some(100.0)).unwrap());
+        assert_eq!(cameras[2].get_coverage_distance(), 100.0);
+
+        // Un ángulo fuera de rango es un error, como cualquier campo no válido
+        fs::write(&path, "1:-34.6:-58.38:250:90:400\n").unwrap();
+        assert!(read_cameras_from_file(path.to_str().unwrap()).is_err());
+
+        let _ = fs::remove_file(&path);
+    }
 }
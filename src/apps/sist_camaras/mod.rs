@@ -1,7 +1,14 @@
 pub mod ai_detection;
+pub mod bordering;
 pub mod camara_errors;
+pub mod camera_registry;
 pub mod camera;
+pub mod camera_command;
+pub mod camera_fov;
+pub mod camera_health;
+pub mod camera_index;
 pub mod camera_state;
+pub mod cameras_service;
 pub mod manage_stored_cameras;
 pub mod sist_cams_mqtt_properties;
 pub mod sistema_camaras;
@@ -1,4 +1,4 @@
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 
 use crate::apps::properties::Properties;
 
@@ -16,8 +16,7 @@ impl SistCamsMQTTProperties {
             ip = prop.to_owned();
         } else {
             println!("No se encontró la propiedad 'ip-server-mqtt");
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Falta propiedad sist cams mqtt.",
             ));
         }
@@ -28,15 +27,13 @@ impl SistCamsMQTTProperties {
                 port = parsed_port;
             } else {
                 println!("Error al parsear 'port-server-mqtt");
-                return Err(Error::new(
-                    ErrorKind::Other,
+                return Err(Error::other(
                     "Error en propiedades sist cams mqtt.",
                 ));
             }
         } else {
             println!("No se encontró la propiedad 'port-server-mqtt");
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Falta propiedad sist cams mqtt.",
             ));
         }
@@ -47,15 +44,13 @@ impl SistCamsMQTTProperties {
                 publish_interval = parsed_interval;
             } else {
                 println!("Error al parsear 'publish-interval-mqtt");
-                return Err(Error::new(
-                    ErrorKind::Other,
+                return Err(Error::other(
                     "Error en propiedades sist cams mqtt.",
                 ));
             }
         } else {
             println!("No se encontró la propiedad 'publish-interval-mqtt");
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Falta propiedad sist cams mqtt.",
             ));
         }
@@ -1,25 +1,42 @@
 use crate::apps::{
     apps_mqtt_topics::AppsMqttTopics,
     common_clients::{exit_when_asked, there_are_no_more_publish_msgs},
-    incident_data::incident::Incident,
+    incident_data::{
+        active_incidents::{ActiveIncidentsReply, ActiveIncidentsRequest},
+        incident::Incident,
+    },
     sist_camaras::{
-        ai_detection::ai_detector_manager::AIDetectorManager, camera::Camera,
-        sistema_camaras_abm::ABMCameras, sistema_camaras_logic::CamerasLogic,
-        types::shareable_cameras_type::ShCamerasType,
+        ai_detection::{
+            ai_detector_manager::{AIDetectorManager, PROPERTIES_FILE},
+            ai_provider::AiProvider,
+            properties::DetectorProperties,
+        },
+        bordering::{BorderingRules, SIST_CAMARAS_PROPERTIES_FILE},
+        camera::{Camera, CameraSystemId, DEFAULT_CAMERA_SYSTEM_ID},
+        camera_health::{CameraHealthPolicy, FeedMonitor},
+        camera_command::{CameraCommandReply, CameraCommandRequest},
+        camera_registry::registry::CameraRegistry,
+        cameras_service::{CamerasService, ShCamerasService},
+        sistema_camaras_abm::ABMCameras,
+        sistema_camaras_logic::{CamerasLogic, ShCamerasLogic},
     },
 };
+use crate::apps::properties::Properties;
 use crate::logging::string_logger::StringLogger;
-use crate::mqtt::{client::mqtt_client::MQTTClient, messages::publish_message::PublishMessage};
+use crate::mqtt::client::{mqtt_client::MQTTClient, typed_topic::Topic};
+
+use uuid::Uuid;
 
 use std::collections::HashMap;
 use std::{
     fs,
     io::{self, ErrorKind},
     sync::{
-        mpsc::{self, Receiver, Sender},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
         Arc, Mutex,
     },
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
 use super::types::channels_type::create_channels;
@@ -28,7 +45,12 @@ use super::types::channels_type::create_channels;
 /// proveer un abm por consola, y ejecutar un detector automático de incidentes.
 #[derive(Debug)]
 pub struct SistemaCamaras {
-    cameras: Arc<Mutex<HashMap<u8, Camera>>>,
+    cameras: Arc<Mutex<HashMap<u32, Camera>>>,
+    bordering: BorderingRules,
+    health: CameraHealthPolicy,
+    system_id: CameraSystemId,
+    // Cuánto esperar los incidentes activos al iniciar, para reconstruir los que se manejan (None es no pedirlos).
+    reconciliation_timeout: Option<Duration>,
     qos: u8,
     logger: StringLogger,
 }
@@ -47,18 +69,85 @@ fn leer_qos_desde_archivo(ruta_archivo: &str) -> Result<u8, io::Error> {
     })?;
     Ok(valor_qos)
 }
+
+/// Lee el id del sistema de cámaras de la propiedad `camera-system-id` del archivo `properties_file`.
+/// Si no está (o no existe el archivo), es el sistema por defecto.
+pub fn read_camera_system_id(properties_file: &str) -> Result<CameraSystemId, io::Error> {
+    let properties = match Properties::new(properties_file) {
+        Ok(properties) => properties,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(DEFAULT_CAMERA_SYSTEM_ID),
+        Err(e) => return Err(e),
+    };
+    match properties.get("camera-system-id") {
+        Some(prop) if !prop.is_empty() => prop.parse().map_err(|_| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Id de sistema de cámaras no válido: {}", prop),
+            )
+        }),
+        _ => Ok(DEFAULT_CAMERA_SYSTEM_ID),
+    }
+}
+
+/// Lee de la propiedad `incident-reconciliation-seconds` del archivo `properties_file` cuánto esperar los incidentes
+/// activos al iniciar. Si no está, está vacía (o no existe el archivo), no se piden.
+pub fn read_reconciliation_timeout(properties_file: &str) -> Result<Option<Duration>, io::Error> {
+    let properties = match Properties::new(properties_file) {
+        Ok(properties) => properties,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    match properties.get("incident-reconciliation-seconds") {
+        Some(prop) if !prop.is_empty() => match prop.parse::<u64>() {
+            Ok(seconds) if seconds > 0 => Ok(Some(Duration::from_secs(seconds))),
+            _ => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Tiempo de espera de incidentes activos no válido: {}", prop),
+            )),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Client id del sistema de cámaras `system_id`. Cada sistema necesita uno propio para compartir el broker,
+/// y con él recibe las respuestas a sus pedidos.
+pub fn camera_system_client_id(system_id: CameraSystemId) -> String {
+    if system_id == DEFAULT_CAMERA_SYSTEM_ID {
+        String::from("Sistema-Camaras")
+    } else {
+        format!("Sistema-Camaras-{}", system_id)
+    }
+}
 impl SistemaCamaras {
-    /// Crea un Sistema Cámaras.
+    /// Crea un Sistema Cámaras, con la política de lindantes por defecto y sin lindantes elegidas manualmente.
     pub fn new(
-        cameras: Arc<Mutex<HashMap<u8, Camera>>>,
+        cameras: Arc<Mutex<HashMap<u32, Camera>>>,
+        logger: StringLogger,
+    ) -> Self {
+        Self::new_with_bordering(cameras, BorderingRules::default(), logger)
+    }
+
+    /// Crea un Sistema Cámaras que calcula las lindantes, y decide cuáles activar, según `bordering`
+    /// (ie las reglas con las que se cargaron las cámaras).
+    pub fn new_with_bordering(
+        cameras: Arc<Mutex<HashMap<u32, Camera>>>,
+        bordering: BorderingRules,
         logger: StringLogger,
     ) -> Self {
         println!("Sistema de Cámaras\n");
         let qos =
             leer_qos_desde_archivo("src/apps/sist_camaras/qos_sistema_camaras.properties").unwrap();
+        let health = CameraHealthPolicy::from_properties_file(SIST_CAMARAS_PROPERTIES_FILE)
+            .expect("Error al leer la política de salud de las cámaras");
+        let reconciliation_timeout = read_reconciliation_timeout(SIST_CAMARAS_PROPERTIES_FILE)
+            .expect("Error al leer la espera de incidentes activos");
 
         let sistema_camaras: SistemaCamaras = Self {
             cameras,
+            bordering,
+            health,
+            system_id: DEFAULT_CAMERA_SYSTEM_ID,
+            reconciliation_timeout,
             qos,
             logger,
         };
@@ -66,37 +155,180 @@ impl SistemaCamaras {
         sistema_camaras
     }
 
-    /// Inicializa las partes internas del Sistema Cámaras.
-    pub fn spawn_threads(
+    /// Reemplaza la política de salud de las cámaras leída de `sistema_camaras.properties` (ie para
+    /// controlar sus imágenes en tests). Debe llamarse antes de lanzar los hilos.
+    pub fn set_health_policy(&mut self, health: CameraHealthPolicy) {
+        self.health = health;
+    }
+
+    /// Reemplaza cuánto se esperan los incidentes activos al iniciar, leído de `sistema_camaras.properties`
+    /// (None es no pedirlos). Debe llamarse antes de lanzar los hilos.
+    pub fn set_reconciliation_timeout(&mut self, reconciliation_timeout: Option<Duration>) {
+        self.reconciliation_timeout = reconciliation_timeout;
+    }
+
+    /// Indica el id de este sistema de cámaras, para distinguir sus cámaras y su topic de comandos de los de otros
+    /// sistemas que compartan el broker. Asigna a él las cámaras ya cargadas. Debe llamarse antes de lanzar los hilos.
+    pub fn set_system_id(&mut self, system_id: CameraSystemId) {
+        self.system_id = system_id;
+        match self.cameras.lock() {
+            Ok(mut cams) => cams
+                .values_mut()
+                .for_each(|camera| camera.set_system_id(system_id)),
+            Err(_) => self
+                .logger
+                .log("Sistema-Camaras: error al tomar lock de cámaras.".to_string()),
+        }
+    }
+
+    /// Inicializa las partes internas del Sistema Cámaras, con el abm por consola. Los cambios que se hagan
+    /// desde el abm o por MQTT se guardan en `registry`.
+    pub fn spawn_threads(&mut self, mqtt_client: MQTTClient, registry: Box<dyn CameraRegistry>) -> Vec<JoinHandle<()>> {
+        let run_detector = self.default_detector();
+        let feeds_dir = default_feeds_dir();
+        let (children, _exit_tx) = self.spawn_threads_with(mqtt_client, registry, (run_detector, feeds_dir), true);
+        children
+    }
+
+    /// Inicializa las partes internas del Sistema Cámaras sin el abm por consola, para correrlo como servicio:
+    /// las cámaras se administran sólo por MQTT. Devuelve también el tx por el que pedir la salida.
+    pub fn spawn_threads_as_daemon(
         &mut self,
-        publish_msg_rx: Receiver<PublishMessage>,
         mqtt_client: MQTTClient,
-    ) -> Vec<JoinHandle<()>> {
-        let mut children: Vec<JoinHandle<()>> = vec![];
+        registry: Box<dyn CameraRegistry>,
+    ) -> (Vec<JoinHandle<()>>, Sender<bool>) {
+        let run_detector = self.default_detector();
+        let feeds_dir = default_feeds_dir();
+        self.spawn_threads_with(mqtt_client, registry, (run_detector, feeds_dir), false)
+    }
+
+    /// Inicializa las partes internas del Sistema Cámaras, sin el abm por consola, y con el detector usando
+    /// las `detector_properties` y el `ai_provider` recibidos (ie para embeberlo en tests).
+    /// Devuelve también el tx por el que pedir la salida, como lo hace el abm.
+    pub fn spawn_threads_without_abm(
+        &mut self,
+        mqtt_client: MQTTClient,
+        registry: Box<dyn CameraRegistry>,
+        detector_properties: DetectorProperties,
+        ai_provider: Arc<dyn AiProvider>,
+    ) -> (Vec<JoinHandle<()>>, Sender<bool>) {
+        let cameras_ref = Arc::clone(&self.cameras);
+        let logger_ai = self.logger.clone_ref();
+        let feeds_dir = detector_properties.get_base_dir().to_string();
+        let run_detector = move |tx, exit_detector_rx| {
+            AIDetectorManager::run_with(cameras_ref, tx, exit_detector_rx, detector_properties, ai_provider, logger_ai)
+        };
+        self.spawn_threads_with(mqtt_client, registry, (run_detector, feeds_dir), false)
+    }
+
+    /// Detector automático de incidentes (ai), con la configuración y el proveedor por defecto.
+    fn default_detector(
+        &self,
+    ) -> impl FnOnce(Sender<Incident>, Receiver<()>) -> Result<AIDetectorManager, io::Error> + Send + 'static {
+        let cameras_ref = Arc::clone(&self.cameras);
+        let logger_ai = self.logger.clone_ref();
+        move |tx, exit_detector_rx| AIDetectorManager::run(cameras_ref, tx, exit_detector_rx, logger_ai)
+    }
+
+    /// Crea el service de cámaras y lanza todos los hilos. Con `with_menu` se ejecuta el abm por consola, que
+    /// publica las cámaras al iniciar; si no, se publican acá. `feeds_dir` es el directorio de imágenes de las
+    /// cámaras del detector, con el que se controla su salud. Devuelve los handles y el tx para pedir la salida.
+    fn spawn_threads_with<F>(
+        &mut self,
+        mqtt_client: MQTTClient,
+        registry: Box<dyn CameraRegistry>,
+        (run_detector, feeds_dir): (F, String),
+        with_menu: bool,
+    ) -> (Vec<JoinHandle<()>>, Sender<bool>)
+    where
+        F: FnOnce(Sender<Incident>, Receiver<()>) -> Result<AIDetectorManager, io::Error> + Send + 'static,
+    {
+        let (
+            cameras_tx,
+            cameras_rx,
+            exit_tx,
+            exit_rx,
+            exit_detector_tx,
+            exit_detector_rx,
+            exit_heartbeat_tx,
+            exit_heartbeat_rx,
+        ) = create_channels();
+
+        // Lógica de incidentes, compartida con el service para reevaluarlos al modificar cámaras
+        let logic = self.create_shared_logic(cameras_tx.clone());
+        let mut service = CamerasService::new(
+            self.cameras.clone(),
+            logic.clone(),
+            registry,
+            cameras_tx,
+            self.logger.clone_ref(),
+        );
+        service.set_system_id(self.system_id);
+        let service: ShCamerasService = Arc::new(Mutex::new(service));
+
+        let mut children = vec![];
+        if with_menu {
+            children.push(self.spawn_abm_cameras_thread(service.clone(), exit_tx.clone()));
+        } else if let Ok(service) = service.lock() {
+            // Envía las cámaras iniciales para que se publiquen, como lo hace el abm al iniciar
+            service.send_all_cameras();
+        }
 
         let mqtt_sh = Arc::new(Mutex::new(mqtt_client));
-        let (cameras_tx, cameras_rx, exit_tx, exit_rx, exit_detector_tx, exit_detector_rx) = create_channels();
+        children.extend(self.spawn_common_threads(
+            mqtt_sh.clone(),
+            (logic, cameras_rx),
+            (exit_rx, exit_detector_tx, exit_detector_rx, exit_heartbeat_tx),
+            run_detector,
+        ));
+        children.push(self.spawn_heartbeat_thread(service.clone(), feeds_dir, exit_heartbeat_rx));
+        children.push(self.spawn_camera_commands_thread(mqtt_sh, service));
+        (children, exit_tx)
+    }
 
-        // Recibe las cámaras que envía el abm y las publica por MQTT
-        children.push(self.spawn_publish_to_topic_thread(mqtt_sh.clone(), cameras_rx));
+    /// Lanza los hilos que no dependen de cómo se inicia el sistema: publicación de cámaras, exit,
+    /// detector (ejecutado con `run_detector`), publicación de incidentes y suscripción.
+    fn spawn_common_threads<F>(
+        &mut self,
+        mqtt_sh: Arc<Mutex<MQTTClient>>,
+        (logic, cameras_rx): (ShCamerasLogic, Receiver<Vec<u8>>),
+        (exit_rx, exit_detector_tx, exit_detector_rx, exit_heartbeat_tx): (
+            Receiver<bool>,
+            Sender<()>,
+            Receiver<()>,
+            Sender<()>,
+        ),
+        run_detector: F,
+    ) -> Vec<JoinHandle<()>>
+    where
+        F: FnOnce(Sender<Incident>, Receiver<()>) -> Result<AIDetectorManager, io::Error> + Send + 'static,
+    {
+        let mut children: Vec<JoinHandle<()>> = vec![];
 
-        // ABM
-        children.push(self.spawn_abm_cameras_thread(&self.cameras, cameras_tx.clone(), exit_tx));
+        // Recibe las cámaras que envía el service y las publica por MQTT
+        children.push(self.spawn_publish_to_topic_thread(mqtt_sh.clone(), cameras_rx));
 
         // Exit, cuando lo solicita el abm
-        children.push(spawn_exit_when_asked_thread(mqtt_sh.clone(), exit_rx, exit_detector_tx));
+        children.push(spawn_exit_when_asked_thread(mqtt_sh.clone(), exit_rx, (exit_detector_tx, exit_heartbeat_tx)));
 
         // Incident detector (ai)
         let (inc_tx, inc_rx) = mpsc::channel::<Incident>();
-        children.push(self.spawn_ai_detector_thread(inc_tx, exit_detector_rx)); // conexión con proveedor intelig artificial
+        children.push(self.spawn_ai_detector_thread(inc_tx, exit_detector_rx, run_detector)); // conexión con proveedor intelig artificial
         children.push(self.spawn_recv_and_publish_inc_thread(inc_rx, mqtt_sh.clone())); // recibe inc y publica
 
         // Suscribe y recibe mensajes por MQTT
-        children.push(self.spawn_subscribe_to_topics_thread(mqtt_sh.clone(), publish_msg_rx, cameras_tx));
+        children.push(self.spawn_subscribe_to_topics_thread(mqtt_sh.clone(), logic));
 
         children
     }
 
+    /// Crea la lógica que procesa los incidentes, que envía por `cameras_tx` las cámaras que cambian.
+    fn create_shared_logic(&self, cameras_tx: Sender<Vec<u8>>) -> ShCamerasLogic {
+        let mut logic = CamerasLogic::new(self.cameras.clone(), cameras_tx, self.logger.clone_ref());
+        logic.set_bordering_rules(self.bordering.clone());
+        Arc::new(Mutex::new(logic))
+    }
+
     /// Hilo que publica las cámaras.
     fn spawn_publish_to_topic_thread(
         &self,
@@ -113,30 +345,133 @@ impl SistemaCamaras {
         })
     }
 
-    /// Envía todas las cámaras por tx para que la parte que las reciba las publique por MQTT.
-    /// Y lanza el hilo encargado de ejecutar el abm.
-    fn spawn_abm_cameras_thread(
+    /// Hilo del latido: cada intervalo de la política de salud actualiza el estado de las cámaras según sus
+    /// imágenes en `feeds_dir` (si se controlan), y envía todas para publicarlas, de modo que quien las recibe
+    /// sepa que siguen funcionando. Termina al recibir por `exit_heartbeat_rx`.
+    fn spawn_heartbeat_thread(
         &self,
-        cameras: &Arc<Mutex<HashMap<u8, Camera>>>,
-        cameras_tx: Sender<Vec<u8>>,
-        exit_tx: Sender<bool>,
+        service: ShCamerasService,
+        feeds_dir: String,
+        exit_heartbeat_rx: Receiver<()>,
     ) -> JoinHandle<()> {
-        // Lanza el hilo para el abm
-        let cameras_c = cameras.clone();
+        let interval = self.health.get_heartbeat_interval();
+        let monitor = self
+            .health
+            .get_feed_timeout()
+            .map(|timeout| FeedMonitor::new(feeds_dir, timeout));
+        let logger = self.logger.clone_ref();
+        thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = exit_heartbeat_rx.recv_timeout(interval) {
+                let Ok(service) = service.lock() else {
+                    logger.log("Sistema-Camaras: error al tomar lock del service de cámaras.".to_string());
+                    continue;
+                };
+                if let Some(monitor) = &monitor {
+                    if let Err(e) = service.update_cameras_health(monitor) {
+                        logger.log(format!("Sistema-Camaras: error al controlar la salud de las cámaras: {:?}.", e));
+                    }
+                }
+                service.send_all_cameras();
+            }
+        })
+    }
+
+    /// Lanza el hilo encargado de ejecutar el abm, que opera sobre las cámaras a través de `service`.
+    fn spawn_abm_cameras_thread(&self, service: ShCamerasService, exit_tx: Sender<bool>) -> JoinHandle<()> {
         let logger_c = self.logger.clone_ref();
         thread::spawn(move || {
             // Ejecuta el abm
-            let mut abm_cameras = ABMCameras::new(cameras_c, cameras_tx, exit_tx, logger_c);
+            let mut abm_cameras = ABMCameras::new(service, exit_tx, logger_c);
             abm_cameras.run();
         })
     }
 
+    /// Hilo que atiende los comandos de cámaras recibidos por MQTT: los ejecuta con `service`, y publica
+    /// la respuesta en el reply topic de cada pedido.
+    fn spawn_camera_commands_thread(
+        &self,
+        mqtt_client: Arc<Mutex<MQTTClient>>,
+        service: ShCamerasService,
+    ) -> JoinHandle<()> {
+        let self_clone = self.clone_ref();
+        thread::spawn(move || {
+            if let Some(requests_rx) = self_clone.subscribe_to_camera_commands(&mqtt_client) {
+                self_clone.receive_camera_commands(requests_rx, mqtt_client, service);
+            }
+        })
+    }
+
+    /// Se suscribe al topic de comandos de cámaras, y devuelve el rx por el que se recibirán ya decodificados.
+    fn subscribe_to_camera_commands(
+        &self,
+        mqtt_client: &Arc<Mutex<MQTTClient>>,
+    ) -> Option<Receiver<Result<CameraCommandRequest, io::Error>>> {
+        let topic = AppsMqttTopics::camera_commands_of(self.system_id);
+        if let Ok(mut mqtt_client_lock) = mqtt_client.lock() {
+            match mqtt_client_lock.mqtt_subscribe_typed(&topic, self.qos) {
+                Ok(requests_rx) => {
+                    self.logger
+                        .log(format!("Subscripto a topic: {:?}", topic.get_name()));
+                    return Some(requests_rx);
+                }
+                Err(e) => {
+                    self.logger.log(format!("Error al subscribirse: {:?}", e));
+                }
+            };
+        }
+        None
+    }
+
+    /// Ejecuta cada comando recibido y publica su respuesta. El service se suelta antes de publicar, ya que
+    /// las cámaras que cambian se publican desde otro hilo, que también usa el cliente MQTT.
+    fn receive_camera_commands(
+        &self,
+        rx: Receiver<Result<CameraCommandRequest, io::Error>>,
+        mqtt_client: Arc<Mutex<MQTTClient>>,
+        service: ShCamerasService,
+    ) {
+        for decoded in rx {
+            let request = match decoded {
+                Ok(request) => request,
+                Err(e) => {
+                    self.logger.log(format!("Error al decodificar comando de cámaras: {:?}.", e));
+                    continue;
+                }
+            };
+            if request.get_reply_topic().is_empty() {
+                self.logger
+                    .log(format!("Comando de cámaras sin reply topic, se descarta: {:?}", request));
+                continue;
+            }
+
+            let reply = match service.lock() {
+                Ok(mut service) => service.handle_request(&request),
+                Err(_) => CameraCommandReply::new(
+                    request.get_correlation_id(),
+                    Err(io::Error::other("Error al tomar lock del service de cámaras.")),
+                ),
+            };
+            let reply_topic = Topic::<CameraCommandReply>::new(request.get_reply_topic());
+            // El listado se envía de a una cámara por mensaje, todos con el mismo correlation id.
+            for page in reply.into_pages() {
+                if let Ok(mut mqtt_client_lock) = mqtt_client.lock() {
+                    match mqtt_client_lock.mqtt_publish_typed(&reply_topic, &page, self.qos) {
+                        Ok(publish_msg) => self.logger.log(format!("Enviada respuesta: {:?}", publish_msg)),
+                        Err(e) => self.logger.log(format!("Error al publicar respuesta: {:?}", e)),
+                    }
+                }
+            }
+        }
+    }
+
     /// Pone en ejecución el módulo de detección automática de incidentes.
-    fn spawn_ai_detector_thread(&self, tx: Sender<Incident>, exit_detector_rx: Receiver<()>) -> JoinHandle<()> {
-        let cameras_ref = Arc::clone(&self.cameras);
+    fn spawn_ai_detector_thread<F>(&self, tx: Sender<Incident>, exit_detector_rx: Receiver<()>, run_detector: F) -> JoinHandle<()>
+    where
+        F: FnOnce(Sender<Incident>, Receiver<()>) -> Result<AIDetectorManager, io::Error> + Send + 'static,
+    {
         let logger_ai = self.logger.clone_ref();
         thread::spawn(move || {
-            if let Err(e) = AIDetectorManager::run(cameras_ref, tx, exit_detector_rx, logger_ai.clone_ref()){
+            if let Err(e) = run_detector(tx, exit_detector_rx) {
                 logger_ai.log(format!("Error al ejecutar el detector en Sistema Cámaras: {:?}.", e));
             }
         })
@@ -153,9 +488,9 @@ impl SistemaCamaras {
         thread::spawn(move || {
             for inc in rx {
                 if let Ok(mut mqtt_client_lock) = mqtt_client.lock() {
-                    let res_publish = mqtt_client_lock.mqtt_publish(
-                        AppsMqttTopics::IncidentTopic.to_str(),
-                        &inc.to_bytes(),
+                    let res_publish = mqtt_client_lock.mqtt_publish_typed(
+                        &AppsMqttTopics::incidents(),
+                        &inc,
                         qos,
                     );
                     match res_publish {
@@ -173,20 +508,93 @@ impl SistemaCamaras {
         })
     }
 
-    fn subscribe_to_topics(&self, mqtt_client: Arc<Mutex<MQTTClient>>, topics: Vec<(String, u8)>) {
-        let topics_log = topics.to_vec();
+    /// Se suscribe al topic de incidentes, y devuelve el rx por el que se recibirán ya decodificados.
+    fn subscribe_to_incidents(
+        &self,
+        mqtt_client: Arc<Mutex<MQTTClient>>,
+    ) -> Option<Receiver<Result<Incident, io::Error>>> {
+        let topic = AppsMqttTopics::incidents();
         if let Ok(mut mqtt_client_lock) = mqtt_client.lock() {
-            let res_subscribe = mqtt_client_lock.mqtt_subscribe(topics);
+            let res_subscribe = mqtt_client_lock.mqtt_subscribe_typed(&topic, self.qos);
             match res_subscribe {
-                Ok(_) => {
+                Ok(incidents_rx) => {
                     self.logger
-                        .log(format!("Subscripto a topic: {:?}", topics_log));
+                        .log(format!("Subscripto a topic: {:?}", topic.get_name()));
+                    return Some(incidents_rx);
                 }
                 Err(e) => {
                     self.logger.log(format!("Error al subscribirse: {:?}", e));
                 }
             };
         }
+        None
+    }
+
+    /// Pide los incidentes activos y reconstruye con ellos los que maneja `logic`, que reactiva y envía para publicar
+    /// las cámaras que les dan seguimiento; los que maneja y ya no están activos se resuelven.
+    /// Si no llegan antes de `timeout`, sigue sólo con los que reciba desde ahora.
+    fn reconcile_active_incidents(
+        &self,
+        mqtt_client: &Arc<Mutex<MQTTClient>>,
+        timeout: Duration,
+        logic: &ShCamerasLogic,
+    ) {
+        let Some(reply) = self.request_active_incidents(mqtt_client, timeout) else {
+            self.logger
+                .log("No se recibieron los incidentes activos, se sigue sin reconciliar.".to_string());
+            return;
+        };
+        let res = match logic.lock() {
+            Ok(mut logic) => logic.reconcile_active_incidents(reply.into_incidents()),
+            Err(_) => Err(io::Error::other("Error al tomar lock de la lógica de cámaras.")),
+        };
+        if let Err(e) = res {
+            self.logger.log(format!("Error al reconciliar los incidentes activos: {:?}.", e));
+        }
+    }
+
+    /// Publica un pedido de incidentes activos, y espera hasta `timeout` la respuesta con su mismo correlation id.
+    fn request_active_incidents(
+        &self,
+        mqtt_client: &Arc<Mutex<MQTTClient>>,
+        timeout: Duration,
+    ) -> Option<ActiveIncidentsReply> {
+        let client_id = camera_system_client_id(self.system_id);
+        let reply_topic = AppsMqttTopics::active_incidents_replies(&client_id);
+        let request = ActiveIncidentsRequest::new(&Uuid::new_v4().to_string(), reply_topic.get_name());
+
+        let replies_rx = match mqtt_client.lock() {
+            Ok(mut mqtt_client_lock) => {
+                let res = mqtt_client_lock
+                    .mqtt_subscribe_typed(&reply_topic, self.qos)
+                    .and_then(|replies_rx| {
+                        mqtt_client_lock.mqtt_publish_typed(&AppsMqttTopics::active_incidents(), &request, self.qos)?;
+                        Ok(replies_rx)
+                    });
+                match res {
+                    Ok(replies_rx) => replies_rx,
+                    Err(e) => {
+                        self.logger.log(format!("Error al pedir los incidentes activos: {:?}", e));
+                        return None;
+                    }
+                }
+            }
+            Err(_) => return None,
+        };
+        self.logger.log(format!("Pedidos los incidentes activos: {:?}", request));
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match replies_rx.recv_timeout(remaining) {
+                Ok(Ok(reply)) if reply.get_correlation_id() == request.get_correlation_id() => return Some(reply),
+                Ok(Ok(_)) => {} // Respuesta a un pedido anterior
+                Ok(Err(e)) => self
+                    .logger
+                    .log(format!("Error al decodificar incidentes activos: {:?}.", e)),
+                Err(_) => return None,
+            }
+        }
     }
 
     /// Utiliza la librería MQTT para hacer publish,
@@ -216,37 +624,39 @@ impl SistemaCamaras {
     fn spawn_subscribe_to_topics_thread(
         &mut self,
         mqtt_client: Arc<Mutex<MQTTClient>>,
-        msg_rx: Receiver<PublishMessage>,
-        cameras_tx: Sender<Vec<u8>>,
+        logic: ShCamerasLogic,
     ) -> JoinHandle<()> {
-        let mut cameras_cloned = self.cameras.clone();
         let mut self_clone = self.clone_ref();
-        let topic = AppsMqttTopics::IncidentTopic.to_str();
         thread::spawn(move || {
-            self_clone.subscribe_to_topics(mqtt_client.clone(), vec![(String::from(topic), self_clone.qos)]);
-            self_clone.receive_messages_from_subscribed_topics(msg_rx, &mut cameras_cloned, cameras_tx);
+            if let Some(incidents_rx) = self_clone.subscribe_to_incidents(mqtt_client.clone()) {
+                // Ya suscripto, para que los incidentes que lleguen mientras tanto se procesen luego de los activos
+                if let Some(timeout) = self_clone.reconciliation_timeout {
+                    self_clone.reconcile_active_incidents(&mqtt_client, timeout, &logic);
+                }
+                self_clone.receive_messages_from_subscribed_topics(incidents_rx, logic);
+            }
         })
     }
 
-    /// Recibe mensajes de los topics a los que se ha suscrito, y delega el procesamiento a `CamerasLogic`.
+    /// Recibe los incidentes del topic al que se ha suscrito, y delega el procesamiento a `CamerasLogic`.
     fn receive_messages_from_subscribed_topics(
         &mut self,
-        rx: Receiver<PublishMessage>,
-        cameras: &mut ShCamerasType,
-        cameras_tx: Sender<Vec<u8>>,
+        rx: Receiver<Result<Incident, io::Error>>,
+        logic: ShCamerasLogic,
     ) {
-        let mut logic = CamerasLogic::new(
-            cameras.clone(),
-            cameras_tx.clone(),
-            self.logger.clone_ref(),
-        );
-
-        for msg in rx {
-            if let Ok(incident) = Incident::from_bytes(msg.get_payload()) {
-                self.logger.log(format!("Inc recibido: {:?}", incident));
-                if let Err(e) = logic.manage_incident(incident) {
-                    self.logger.log(format!("Error al procesar incidente: {:?}.", e));
+        for decoded in rx {
+            match decoded {
+                Ok(incident) => {
+                    self.logger.log(format!("Inc recibido: {:?}", incident));
+                    let res = match logic.lock() {
+                        Ok(mut logic) => logic.manage_incident(incident),
+                        Err(_) => Err(io::Error::other("Error al tomar lock de la lógica de cámaras.")),
+                    };
+                    if let Err(e) = res {
+                        self.logger.log(format!("Error al procesar incidente: {:?}.", e));
+                    }
                 }
+                Err(e) => self.logger.log(format!("Error al decodificar incidente: {:?}.", e)),
             }
         }
 
@@ -256,16 +666,28 @@ impl SistemaCamaras {
     fn clone_ref(&self) -> Self {
         Self {
             cameras: self.cameras.clone(),
+            bordering: self.bordering.clone(),
+            health: self.health.clone(),
+            system_id: self.system_id,
+            reconciliation_timeout: self.reconciliation_timeout,
             qos: self.qos,
             logger: self.logger.clone_ref(),
         }
     }
 }
 
+/// Directorio de imágenes de las cámaras del detector por defecto, o vacío si no se puede leer su configuración
+/// (en cuyo caso, si se controlan las imágenes, las cámaras quedarán en `Fault`).
+fn default_feeds_dir() -> String {
+    DetectorProperties::new(PROPERTIES_FILE)
+        .map(|properties| properties.get_base_dir().to_string())
+        .unwrap_or_default()
+}
+
 fn spawn_exit_when_asked_thread(
     mqtt_client_sh: Arc<Mutex<MQTTClient>>,
     exit_rx: Receiver<bool>,
-    exit_detector_tx: Sender<()>,
+    (exit_detector_tx, exit_heartbeat_tx): (Sender<()>, Sender<()>),
 ) -> JoinHandle<()> {
     thread::spawn(move || {
         exit_when_asked(mqtt_client_sh, exit_rx);
@@ -274,6 +696,9 @@ fn spawn_exit_when_asked_thread(
             //logger.log(format!("Error al enviar por exit_detector_tx: {:?}.", e)); // podría recibir un logger quizás
             println!("Error al enviar por exit_detector_tx: {:?}.", e);
         }
+        if let Err(e) = exit_heartbeat_tx.send(()) {
+            println!("Error al enviar por exit_heartbeat_tx: {:?}.", e);
+        }
         println!("Hilo exit: Listo.");
     })
 }
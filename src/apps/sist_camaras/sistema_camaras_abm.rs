@@ -1,39 +1,34 @@
 use std::{
-    collections::HashMap, io::{stdin, stdout, Error, Write}, sync::{
-        mpsc::Sender,
-        Arc, Mutex,
-    }
+    io::{stdin, stdout, Error, Write}, str::FromStr, sync::{mpsc::Sender, MutexGuard}
 };
 
 use crate::logging::string_logger::StringLogger;
 
+use super::bordering::BorderingPin;
 use super::camera::Camera;
+use super::camera_fov::CameraFov;
+use super::cameras_service::{CamerasService, ShCamerasService};
 
+/// Menú por consola para administrar las cámaras. Las operaciones las realiza el `CamerasService`,
+/// el mismo que atiende los comandos recibidos por MQTT.
 pub struct ABMCameras {
-    cameras: Arc<Mutex<HashMap<u8, Camera>>>,
-    camera_tx: Sender<Vec<u8>>,
+    service: ShCamerasService,
     exit_tx: Sender<bool>,
     logger: StringLogger,
 }
 
 impl ABMCameras {
-    /// Crea un struct `ABMCameras`.
-    pub fn new(
-        cameras: Arc<Mutex<HashMap<u8, Camera>>>,
-        camera_tx: Sender<Vec<u8>>,
-        exit_tx: Sender<bool>,
-        logger: StringLogger,
-    ) -> Self {
+    /// Crea un struct `ABMCameras` que opera sobre las cámaras de `service`.
+    pub fn new(service: ShCamerasService, exit_tx: Sender<bool>, logger: StringLogger) -> Self {
         ABMCameras {
-            cameras,
-            camera_tx,
+            service,
             exit_tx,
             logger,
         }
     }
 
     /// Pone en funcionamiento el menú del abm para cámaras.
-    /// Como cameras es un arc, quien haya llamado a esta función podrá ver reflejados los cambios.
+    /// Como las cámaras del service son un arc, quien las comparta podrá ver reflejados los cambios.
     pub fn run(&mut self) {
         // Publica cámaras al inicio
         self.send_cameras_from_file_to_publish();
@@ -48,7 +43,9 @@ impl ABMCameras {
                 }
                 "2" => self.show_cameras_abm(),
                 "3" => self.delete_camera_abm(),
-                "4" => {
+                "4" => self.modify_camera_abm(),
+                "5" => self.pin_bordering_abm(),
+                "6" => {
                     self.exit_program_abm();
                     break;
                 }
@@ -66,34 +63,78 @@ impl ABMCameras {
         1. Agregar cámara
         2. Mostrar cámaras
         3. Eliminar cámara
-        4. Salir
+        4. Modificar cámara
+        5. Fijar o excluir lindantes
+        6. Salir
         Ingrese una opción:"
         );
     }
 
     /// Opción Crear cámara, del abm. Crea una cámara con el input proporcionado.
-    /// Procesa la cámara y la envía entre hilos para que sistema cámaras pueda publicarla.
+    /// El service la procesa y la envía entre hilos para que sistema cámaras pueda publicarla.
     fn create_camera_abm(&mut self) {
-        if let Ok(camera) = self.create_camera(){
-            self.process_and_send_camera(camera);
+        if let Ok(camera) = self.create_camera() {
+            let result = self.lock_service().and_then(|mut service| service.add_camera(camera));
+            self.print_result(result.map(|_| ()), "Cámara agregada con éxito.");
         }
     }
 
     /// Crea una cámara con el input proporcionado, y la devuelve.
     fn create_camera(&self) -> Result<Camera, Error> {
 
-        let id = self.read_input_and_parse_to_u8("el ID")?;
+        let id = self.read_input_and_parse::<u32>("el ID")?;
         let latitude = self.read_input_and_parse_to_f64("la latitud")?;
         let longitude = self.read_input_and_parse_to_f64("la longitud")?;
-        let range = self.read_input_and_parse_to_u8("el rango")?;
+        let range = self.read_input_and_parse_to_f64("el rango, en metros")?;
+        let fov = self.read_fov();
+
+        let mut camera = Camera::new(id, latitude, longitude, range);
+        camera.set_fov(fov);
+        Ok(camera)
+    }
+
+    /// Lee el campo de visión de la cámara. Sin ángulo (Enter), la cámara ve en todas las direcciones.
+    /// En caso de valores inválidos muestra el error y repregunta.
+    fn read_fov(&self) -> CameraFov {
+        loop {
+            let Some(angle) = self.read_optional_f64("el ángulo de visión, en grados") else {
+                return CameraFov::default();
+            };
+            let heading = self
+                .read_optional_f64("el rumbo hacia el que mira, en grados desde el norte")
+                .unwrap_or(0.0);
+            let max_distance = self.read_optional_f64("la distancia máxima que ve, en metros");
+
+            match CameraFov::new(heading, angle, max_distance) {
+                Ok(fov) => return fov,
+                Err(e) => println!("{} Intente nuevamente.", e),
+            }
+        }
+    }
 
-        Ok(Camera::new(id, latitude, longitude, range))
+    /// Lee el input de teclado y lo devuelve parseado a f64, o None si se presiona Enter sin ingresar nada.
+    /// En caso de input inválido repregunta hasta obtener un input válido.
+    fn read_optional_f64(&self, pm_name: &str) -> Option<f64> {
+        let mut input = self.get_input_abm(Some(
+            format!("Ingrese {} de la cámara (Enter para omitir): ", pm_name).as_str(),
+        ));
+        loop {
+            if input.is_empty() {
+                return None;
+            }
+            if let Ok(value) = input.parse() {
+                return Some(value);
+            }
+            input = self.get_input_abm(Some(
+                format!("Error, intente nuevamente. Ingrese {} de la cámara (Enter para omitir): ", pm_name).as_str(),
+            ));
+        }
     }
 
-    /// Lee el input de teclado y devuelve el valor parseado a u8.
+    /// Lee el input de teclado y devuelve el valor parseado a `T` (un entero, ej u32 para el id).
     /// No debería fallar porque en caso de input inválido repregunta hasta obtener un input válido, pero devuelve un result.
-    fn read_input_and_parse_to_u8(&self, pm_name: &str) -> Result<u8, Error> {
-        let mut res: Result<u8, _> = self
+    fn read_input_and_parse<T: FromStr>(&self, pm_name: &str) -> Result<T, Error> {
+        let mut res: Result<T, _> = self
             .get_input_abm(Some(format!("Ingrese {} de la cámara: ", pm_name).as_str()))
             .parse();
 
@@ -107,7 +148,7 @@ impl ABMCameras {
             return Ok(value);
         }
 
-        Err(Error::new(std::io::ErrorKind::InvalidInput, "Error al parsear u8 (no debería darse)."))
+        Err(Error::new(std::io::ErrorKind::InvalidInput, "Error al parsear el número (no debería darse)."))
     }
 
     /// Lee el input de teclado y devuelve el valor parseado a f64.
@@ -143,183 +184,110 @@ impl ABMCameras {
         input.trim().to_string()
     }
 
-    /// Procesa una nueva cámara (la inserta en el hashmap de cameras, maneja las lindantes), y la envía por un
-    /// channel para que desde el rx el sistema cámaras le pueda hacer publish. Además, logguea la operación.
-    fn process_and_send_camera(&mut self, new_camera: Camera) {
-        match self.cameras.lock() {
-            Ok(mut cams) => {
-                // Recorre las cámaras ya existentes, agregando la nueva cámara como lindante de la que corresponda y viceversa, terminando la creación
-                for camera in cams.values_mut() {
-                    camera.mutually_add_if_bordering(&mut new_camera.clone());
-                }
-                self.logger.log(format!("Sistema-Camaras: envió cámara: {:?}", new_camera));
-                // Envía la nueva cámara por tx, para ser publicada por el otro hilo
-                if self.camera_tx.send(new_camera.to_bytes()).is_err() {
-                    println!("Error al enviar cámara por tx desde hilo abm.");
-                }
-                // Guarda la nueva cámara
-                cams.insert(new_camera.get_id(), new_camera);
-                println!("Cámara agregada con éxito.\n");
-            }
-            Err(e) => println!("Error tomando lock en agregar cámara abm, {:?}.\n", e),
-        }
-    }
-
     /// Opción Mostrar cámaras del abm. Lista todas las cámaras existentes.
     fn show_cameras_abm(&self) {
         // Mostramos todas las cámaras
         println!("Cámaras registradas:\n");
-        match self.cameras.lock() {
-            Ok(cams) => {
-                for camera in (*cams).values() {
-                    // Si no está marcada borrada, mostrarla
-                    if camera.is_not_deleted() {
-                        camera.display();
-                    };
+        match self.lock_service().and_then(|service| service.list_cameras()) {
+            Ok(cameras) => {
+                for camera in cameras.iter() {
+                    camera.display();
                 }
             }
-            Err(_) => println!("Error al tomar lock de cámaras."),
+            Err(e) => println!("Error al listar las cámaras: {}.", e),
         }
     }
 
     /// Opción Eliminar cámara, del abm.
-    /// Elimina la cámara indicada, manejando sus lindantes, y la envía por tx para que rx haga publish.
-    fn delete_camera_abm(&self) {
-        if let Ok(id) = self.read_input_and_parse_to_u8("el ID") {
-            self.delete_camera(id);
+    /// El service elimina la cámara indicada, manejando sus lindantes, y la envía por tx para que rx haga publish.
+    fn delete_camera_abm(&mut self) {
+        if let Ok(id) = self.read_input_and_parse::<u32>("el ID") {
+            let result = self.lock_service().and_then(|mut service| service.delete_camera(id));
+            self.print_result(result.map(|_| ()), "Cámara eliminada con éxito.");
         }
     }
 
-    /// Elimina a la cámara del id recibido.
-    fn delete_camera(&self, id: u8) {
-        match self.cameras.lock() {
-            Ok(mut cams) => {
-                if let Some(mut camera_to_delete) = cams.remove(&id) {
-                    if camera_to_delete.is_not_deleted() {
-                        camera_to_delete.delete_camera();
-
-                        // Recorre las cámaras ya existentes, eliminando la cámara a eliminar como lindante de la que corresponda, terminando la eliminación
-                        for camera in cams.values_mut() {
-                            camera.remove_from_list_if_bordering(&mut camera_to_delete);
-                        }
-
-                        // Envía por el tx la cámara a eliminar para que se publique desde el otro hilo
-                        // (con eso es suficiente. Si bien se les eliminó una lindante, no es necesario publicar el cambio
-                        // de las demás ya que eso solo es relevante para sistema camaras)
-                        if self.camera_tx.send(camera_to_delete.to_bytes()).is_err() {
-                            println!("Error al enviar cámara por tx desde hilo abm.");
-                        } else {
-                            println!("Cámara eliminada con éxito.\n");
-                        }
-                    };
-                } else {
-                    println!("La cámara no existe.\n");
-                }
+    /// Opción Modificar cámara, del abm. Pide la nueva posición, el nuevo rango y el nuevo campo de visión de la cámara indicada.
+    fn modify_camera_abm(&mut self) {
+        if let Ok(id) = self.read_input_and_parse::<u32>("el ID") {
+            let exists = self.lock_service().is_ok_and(|service| service.camera_exists(id));
+            if !exists {
+                println!("La cámara no existe.\n");
+                return;
             }
-            Err(e) => println!("Error tomando lock baja abm, {:?}.\n", e),
-        };
-    }
 
-    /// Opción Salir, del abm.
-    fn exit_program_abm(&self) {
-        match self.exit_tx.send(true) {
-            Ok(_) => println!("Saliendo del programa."),
-            Err(e) => println!("Error al intentar salir: {:?}", e),
+            let (Ok(latitude), Ok(longitude), Ok(range)) = (
+                self.read_input_and_parse_to_f64("la nueva latitud"),
+                self.read_input_and_parse_to_f64("la nueva longitud"),
+                self.read_input_and_parse_to_f64("el nuevo rango, en metros"),
+            ) else {
+                return;
+            };
+            let fov = self.read_fov();
+            let result = self
+                .lock_service()
+                .and_then(|mut service| service.modify_camera(id, latitude, longitude, range, fov));
+            self.print_result(result.map(|_| ()), "Cámara modificada con éxito.");
         }
     }
 
-    /// Recorre las cámaras y envía cada una por el channel, para que quien lea del rx haga el publish.
-    fn send_cameras_from_file_to_publish(&self) {
-        match self.cameras.lock() {
-            Ok(cams) => {
-                for camera in (*cams).values() {
-                    println!("Iniciando, enviando cámara: {:?}", camera);
-                    self.send_camera_bytes(camera, &self.camera_tx);
-                }
+    /// Opción Fijar o excluir lindantes, del abm. Pide dos cámaras, y si fijarlas como lindantes entre sí,
+    /// excluirlas, o volver al cálculo automático según la distancia. La elección se conserva al reiniciar.
+    fn pin_bordering_abm(&mut self) {
+        let (Ok(id), Ok(other_id)) = (
+            self.read_input_and_parse::<u32>("el ID"),
+            self.read_input_and_parse::<u32>("el ID de la otra"),
+        ) else {
+            return;
+        };
+        let pin = loop {
+            let input = self.get_input_abm(Some(
+                "Ingrese 1 para fijarlas como lindantes, 2 para excluirlas, o 3 para calcularlo automáticamente: ",
+            ));
+            match &*input {
+                "1" => break BorderingPin::Pinned,
+                "2" => break BorderingPin::Unpinned,
+                "3" => break BorderingPin::Automatic,
+                _ => println!("Opción no válida. Intente nuevamente."),
             }
-            Err(_) => println!("Error al tomar lock de cámaras."),
-        }
+        };
+        let result = self
+            .lock_service()
+            .and_then(|mut service| service.set_bordering_pin(id, other_id, pin));
+        self.print_result(result.map(|_| ()), "Lindantes actualizadas con éxito.");
     }
 
-    /// Envía la cámara recibida, por el channel, para que quien la reciba por rx haga el publish.
-    /// Además logguea la operación.
-    fn send_camera_bytes(&self, camera: &Camera, camera_tx: &Sender<Vec<u8>>) {
-        self.logger
-            .log(format!("Sistema-Camaras: envío cámara: {:?}", camera));
-
-        if camera_tx.send(camera.to_bytes()).is_err() {
-            println!("Error al enviar cámara por tx desde hilo abm.");
-            self.logger
-                .log("Sistema-Camaras: error al enviar cámara por tx desde hilo abm.".to_string());
+    /// Muestra el mensaje de éxito, o el error de la operación.
+    fn print_result(&self, result: Result<(), Error>, success_msg: &str) {
+        match result {
+            Ok(()) => println!("{}\n", success_msg),
+            Err(e) => {
+                println!("{}\n", e);
+                self.logger
+                    .log(format!("Sistema-Camaras: error en operación del abm: {:?}.", e));
+            }
         }
     }
-}
 
-#[cfg(test)]
-mod test {
-    use std::{
-        collections::HashMap,
-        sync::{mpsc, Arc, Mutex},
-    };
-
-    use crate::{apps::sist_camaras::camera::Camera, logging::string_logger::StringLogger};
-
-    use super::ABMCameras;
-
-    fn create_abm() -> ABMCameras {
-        // Unos tx irrelevantes, para pasar al new de abm
-        // (es necesario conservar las variables de rx en el test de todas formas, para que no se cierre el channel antes del assert)
-        let (camera_tx, _camera_rx) = mpsc::channel();
-        let (exit_tx, _exit_rx) = mpsc::channel();
-
-        // Se crea el abm con su cameras
-        let cameras = Arc::new(Mutex::new(HashMap::new()));
-        // Se crea el logger
-        //let (logger, logger_handle) = StringLogger::create_logger(String::from("Sistema-Cámaras")); // se usa con esto
-        let (string_logger_tx, _string_logger_rx) = mpsc::channel(); // pero para testing, con esto.
-        let logger_for_testing = StringLogger::new(string_logger_tx);
-        
-        ABMCameras::new(cameras.clone(), camera_tx, exit_tx, logger_for_testing)
+    /// Opción Salir, del abm.
+    fn exit_program_abm(&self) {
+        match self.exit_tx.send(true) {
+            Ok(_) => println!("Saliendo del programa."),
+            Err(e) => println!("Error al intentar salir: {:?}", e),
+        }
     }
 
-    #[test]
-    fn test_1_abm_alta_de_camara_la_agrega_a_cameras() {
-        
-        let mut abm = create_abm();
-
-        // Se agrega la cámara
-        let new_camera_id = 1;
-        let camera = Camera::new(new_camera_id, -34.0, -58.0, 5);
-        abm.process_and_send_camera(camera);
-
-        // Se busca la cámara recién agregada
-        let mut is_new_cam_stored = false;
-        if let Ok(cams) = abm.cameras.lock() {
-            is_new_cam_stored = cams.contains_key(&new_camera_id);
+    /// Envía las cámaras cargadas al iniciar, para que quien lea del rx haga el publish.
+    fn send_cameras_from_file_to_publish(&self) {
+        match self.lock_service() {
+            Ok(service) => service.send_all_cameras(),
+            Err(e) => println!("{}", e),
         }
-        // La cámara nueva se ha agregado a cameras
-        assert!(is_new_cam_stored);
     }
 
-    #[test]
-    fn test_2_abm_baja_de_camara_la_elimina_de_cameras() {
-        
-        let mut abm = create_abm();
-
-        // Se agrega la cámara
-        let camera_to_remove_id = 1;
-        let camera = Camera::new(camera_to_remove_id, -34.0, -58.0, 5);
-        abm.process_and_send_camera(camera);
-
-        // Ahora se la elimina
-        abm.delete_camera(camera_to_remove_id);
-
-        // Se busca la cámara recién eliminada
-        let mut is_cam_to_remove_stored = false;
-        if let Ok(cams) = abm.cameras.lock() {
-            is_cam_to_remove_stored = cams.contains_key(&camera_to_remove_id);
-        }
-        // La cámara nueva se ha agregado a cameras
-        assert!(!is_cam_to_remove_stored);
+    fn lock_service(&self) -> Result<MutexGuard<'_, CamerasService>, Error> {
+        self.service
+            .lock()
+            .map_err(|_| Error::other("Error al tomar lock del service de cámaras."))
     }
 }
@@ -1,19 +1,34 @@
 use std::{
-    collections::HashMap,
-    io::{Error, ErrorKind},
-    sync::{mpsc::Sender, MutexGuard},
+    collections::{HashMap, HashSet},
+    io::Error,
+    sync::{mpsc::Sender, Arc, Mutex},
 };
 
-use crate::{apps::incident_data::incident::Incident, logging::string_logger::StringLogger};
+use crate::{
+    apps::incident_data::{incident::Incident, incident_info::IncidentInfo},
+    logging::string_logger::StringLogger,
+};
+use crate::mqtt::client::typed_topic::MqttPayload;
 
 use crate::apps::sist_camaras::{
+    bordering::BorderingRules,
     camera::Camera,
+    camera_index::CameraGridIndex,
+    camera_state::CameraState,
     types::{hashmap_incs_type::HashmapIncsType, shareable_cameras_type::ShCamerasType},
 };
 
+/// `CamerasLogic` compartida entre el hilo que recibe incidentes y el abm.
+/// Quien tome ambos locks debe tomar primero el de la lógica y luego el de las cámaras.
+pub type ShCamerasLogic = Arc<Mutex<CamerasLogic>>;
+
 #[derive(Debug)]
 pub struct CamerasLogic {
     cameras: ShCamerasType,
+    // Índice de las posiciones de las cámaras. Quien agrega, mueve o borra cámaras debe actualizarlo, con ambos locks tomados.
+    index: CameraGridIndex,
+    // Cómo se calculan las lindantes y cuáles se activan. Se actualiza junto con el índice.
+    bordering: BorderingRules,
     incs_being_managed: HashmapIncsType,
     cameras_tx: Sender<Vec<u8>>,
     logger: StringLogger,
@@ -21,15 +36,50 @@ pub struct CamerasLogic {
 
 impl CamerasLogic {
     /// Crea un struct CamerasLogic con las cámaras pasadas como parámetro e incidentes manejándose vacíos.
+    /// Indexa las posiciones de las cámaras.
     pub fn new(cameras: ShCamerasType, cameras_tx: Sender<Vec<u8>>, logger: StringLogger) -> Self {
+        let index = match cameras.lock() {
+            Ok(cams) => CameraGridIndex::from_cameras(&cams),
+            Err(_) => {
+                logger.log("Sistema-Camaras: error al tomar lock de cámaras para indexarlas.".to_string());
+                CameraGridIndex::default()
+            }
+        };
         Self {
             cameras,
+            index,
+            bordering: BorderingRules::default(),
             incs_being_managed: HashMap::new(),
             cameras_tx,
             logger,
         }
     }
 
+    /// Devuelve el índice de las posiciones de las cámaras.
+    pub fn get_index(&self) -> &CameraGridIndex {
+        &self.index
+    }
+
+    /// Devuelve el índice de las posiciones de las cámaras, para actualizarlo al agregar, mover o borrar cámaras.
+    pub fn get_index_mut(&mut self) -> &mut CameraGridIndex {
+        &mut self.index
+    }
+
+    /// Reemplaza la política de lindantes y las elecciones manuales. Por defecto se usan las de `BorderingRules::default`.
+    pub fn set_bordering_rules(&mut self, bordering: BorderingRules) {
+        self.bordering = bordering;
+    }
+
+    /// Devuelve la política de lindantes y las elecciones manuales.
+    pub fn get_bordering_rules(&self) -> &BorderingRules {
+        &self.bordering
+    }
+
+    /// Devuelve la política de lindantes y las elecciones manuales, para fijar o excluir lindantes.
+    pub fn get_bordering_rules_mut(&mut self) -> &mut BorderingRules {
+        &mut self.bordering
+    }
+
     /// Procesa un Incidente recibido.
     pub fn manage_incident(&mut self, incident: Incident) -> Result<(), Error>{
         // Proceso los incidentes
@@ -40,6 +90,52 @@ impl CamerasLogic {
         }
     }
 
+    /// Reconstruye los incidentes que se manejan a partir de `incidents`, los activos según otro sistema (ie al reiniciarse,
+    /// ya que los incidentes manejados sólo están en memoria). Los que todavía no se conocen se procesan como recibidos por
+    /// primera vez, por lo que las cámaras que les dan seguimiento vuelven a activarse y se envían para publicarse.
+    /// Los que se manejan pero no están entre los activos se resolvieron mientras no se los recibía, por lo que se procesan
+    /// como resueltos, y las cámaras que vuelven a ahorro de energía también se envían para publicarse.
+    /// Devuelve cuántos incidentes se recuperaron.
+    pub fn reconcile_active_incidents(&mut self, incidents: Vec<Incident>) -> Result<usize, Error> {
+        let active: HashSet<IncidentInfo> = incidents
+            .iter()
+            .filter(|incident| !incident.is_resolved())
+            .map(|incident| incident.get_info())
+            .collect();
+        let mut recovered = 0;
+        for incident in incidents {
+            if incident.is_resolved() || self.incs_being_managed.contains_key(&incident.get_info()) {
+                continue;
+            }
+            self.process_first_time_incident(incident)?;
+            recovered += 1;
+        }
+
+        let missing: Vec<(IncidentInfo, (f64, f64))> = self
+            .incs_being_managed
+            .iter()
+            .filter(|(inc_info, _)| !active.contains(inc_info))
+            .map(|(inc_info, (inc_position, _cams))| (*inc_info, *inc_position))
+            .collect();
+        for (inc_info, inc_position) in &missing {
+            let mut resolved = Incident::new(inc_info.get_inc_id(), *inc_position, *inc_info.get_src());
+            resolved.set_resolved();
+            self.process_known_incident(resolved)?;
+        }
+
+        self.logger.log(format!(
+            "Reconciliados los incidentes activos, se recuperaron {} incidentes y se resolvieron {}.",
+            recovered,
+            missing.len()
+        ));
+        Ok(recovered)
+    }
+
+    /// Devuelve si el incidente `inc_info` se está manejando.
+    pub fn is_managing(&self, inc_info: &IncidentInfo) -> bool {
+        self.incs_being_managed.contains_key(inc_info)
+    }
+
     // Aux: (condición "hasta que" del enunciado).
     /// Procesa un incidente cuando un incidente con ese mismo id ya fue recibido anteriormente.
     /// Si su estado es resuelto, vuelve el estado de la/s cámara/s que lo atendían, a ahorro de energía.
@@ -50,7 +146,7 @@ impl CamerasLogic {
                 inc.get_id()
             ));
             // Busco la/s cámara/s que atendían este incidente
-            if let Some(cams_managing_inc) = self.incs_being_managed.get(&inc.get_info()) {
+            if let Some((_inc_position, cams_managing_inc)) = self.incs_being_managed.get(&inc.get_info()) {
                 // sé que existe, por el if de más arriba
 
                 // Cambio el estado de las cámaras que lo manejaban, otra vez a ahorro de energía
@@ -62,8 +158,7 @@ impl CamerasLogic {
                                 self.stop_paying_attention_to(&inc, cam_to_update);
                             }
                         }
-                        Err(_) => return Err(Error::new(
-                            ErrorKind::Other,
+                        Err(_) => return Err(Error::other(
                             "Error al tomar lock en process_first_time_incident.",
                         ))
                     };
@@ -108,7 +203,7 @@ impl CamerasLogic {
                         inc.get_info()
                     ));
                     let cameras_that_follow_inc =
-                        self.get_id_of_cams_that_will_change_state_to_active(&mut cams, &inc);
+                        self.get_id_of_cams_that_will_change_state_to_active(&mut cams, inc.get_position());
 
                     // El vector tiene los ids de todas las cámaras que deben cambiar a activo
                     for cam_id in &cameras_that_follow_inc {
@@ -118,11 +213,10 @@ impl CamerasLogic {
                     }
                     // Y se guarda las cámaras que le dan seguimiento al incidente, para luego poder encontrarlas fácilmente sin recorrer
                     self.incs_being_managed
-                        .insert(inc.get_info(), cameras_that_follow_inc);
+                        .insert(inc.get_info(), (inc.get_position(), cameras_that_follow_inc));
                 }
                 Err(_) => {
-                    return Err(Error::new(
-                        ErrorKind::Other,
+                    return Err(Error::other(
                         "Error al tomar lock en process_first_time_incident.",
                     ))
                 }
@@ -131,31 +225,89 @@ impl CamerasLogic {
         Ok(())
     }
 
-    /// Devuelve un vector de u8 con los ids de todas las cámaras que darán seguimiento al incidente `inc`.
+    /// Vuelve a calcular qué cámaras dan seguimiento a cada incidente activo (ie luego de que se modificara la posición
+    /// o el rango de alguna cámara). Las cámaras que ahora lo cubren empiezan a prestarle atención, y las que dejaron de
+    /// cubrirlo dejan de hacerlo.
+    /// Devuelve los ids de las cámaras que cambiaron de estado. No las envía: de eso se encarga quien modificó las cámaras,
+    /// junto con las demás que cambió.
+    pub fn reevaluate_active_incidents(&mut self, cams: &mut HashMap<u32, Camera>) -> Vec<u32> {
+        let states_before: HashMap<u32, CameraState> =
+            cams.iter().map(|(id, camera)| (*id, camera.get_state())).collect();
+
+        let new_followers: Vec<_> = self
+            .incs_being_managed
+            .iter()
+            .map(|(inc_info, (inc_position, _))| {
+                (*inc_info, self.get_id_of_cams_that_will_change_state_to_active(cams, *inc_position))
+            })
+            .collect();
+
+        for (inc_info, new_cams) in new_followers {
+            if let Some((_inc_position, cams_following_inc)) = self.incs_being_managed.get_mut(&inc_info) {
+                for cam_id in cams_following_inc.iter() {
+                    if let Some(camera) = cams.get_mut(cam_id) {
+                        camera.remove_from_incs_being_managed(inc_info);
+                    }
+                }
+                for cam_id in &new_cams {
+                    if let Some(camera) = cams.get_mut(cam_id) {
+                        camera.append_to_incs_being_managed(inc_info);
+                    }
+                }
+                *cams_following_inc = new_cams;
+            }
+        }
+
+        let changed: Vec<u32> = cams
+            .iter()
+            .filter(|(id, camera)| states_before.get(id) != Some(&camera.get_state()))
+            .map(|(id, _)| *id)
+            .collect();
+        self.logger.log(format!(
+            "Reevaluados los incidentes activos, cambian de estado las cámaras: {:?}",
+            changed
+        ));
+        changed
+    }
+
+    /// Devuelve un vector con los ids de todas las cámaras que darán seguimiento al incidente en `inc_position`:
+    /// las que lo registran, y sus lindantes hasta la profundidad de activación de la política de lindantes.
     fn get_id_of_cams_that_will_change_state_to_active(
         &self,
-        cams: &mut MutexGuard<'_, HashMap<u8, Camera>>,
-        inc: &Incident,
-    ) -> Vec<u8> {
+        cams: &mut HashMap<u32, Camera>,
+        inc_position: (f64, f64),
+    ) -> Vec<u32> {
         let mut cameras_that_follow_inc = vec![];
+        let mut already_following = HashSet::new();
 
-        // Recorremos cada una de las cámaras, para ver si el inc está en su rango
-        for (cam_id, camera) in cams.iter_mut() {
-            if camera.will_register(inc.get_position()) {
+        // Recorremos las cámaras cercanas según el índice, para ver si el inc está en su rango
+        for cam_id in self.index.candidates_covering(inc_position) {
+            let Some(camera) = cams.get(&cam_id) else {
+                continue;
+            };
+            if camera.will_register(inc_position) && already_following.insert(cam_id) {
                 self.logger
                     .log(format!("En rango de cam: {}, cambiando a Activo.", cam_id));
+                cameras_that_follow_inc.push(cam_id);
+            }
+        }
 
-                // Si sí, se agrega ella
-                cameras_that_follow_inc.push(*cam_id);
-                // y sus lindantes
-                for bordering_cam_id in camera.get_bordering_cams() {
-                    cameras_that_follow_inc.push(*bordering_cam_id);
+        // Luego sus lindantes, y las lindantes de éstas, tantos niveles como indique la profundidad de activación
+        let mut last_level = cameras_that_follow_inc.clone();
+        for _ in 0..self.bordering.get_policy().get_activation_depth() {
+            let mut next_level = vec![];
+            for cam_id in &last_level {
+                let Some(camera) = cams.get(cam_id) else {
+                    continue;
+                };
+                for bordering_cam_id in camera.get_bordering_ids() {
+                    if already_following.insert(*bordering_cam_id) {
+                        next_level.push(*bordering_cam_id);
+                    }
                 }
-
-                let info = camera.get_id_and_incs_for_debug_display();
-                self.logger
-                    .log(format!(" la cám queda: cam id y lista de incs: {:?}", info));
             }
+            cameras_that_follow_inc.extend(&next_level);
+            last_level = next_level;
         }
         cameras_that_follow_inc
     }
@@ -181,10 +333,102 @@ impl CamerasLogic {
         self.logger
             .log(format!("Sistema-Camaras: envío cámara: {:?}", camera));
 
-        if cameras_tx.send(camera.to_bytes()).is_err() {
+        let camera_bytes = match camera.encode() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.logger
+                    .log(format!("Sistema-Camaras: error al codificar la cámara: {:?}.", e));
+                return;
+            }
+        };
+        if cameras_tx.send(camera_bytes).is_err() {
             println!("Error al enviar cámara por tx desde hilo abm.");
             self.logger
                 .log("Sistema-Camaras: error al enviar cámara por tx desde hilo abm.".to_string());
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashMap,
+        sync::{mpsc, Arc, Mutex},
+    };
+
+    use super::CamerasLogic;
+    use crate::{
+        apps::{
+            incident_data::{incident::Incident, incident_source::IncidentSource},
+            sist_camaras::{camera::Camera, camera_state::CameraState},
+        },
+        logging::string_logger::StringLogger,
+        mqtt::client::typed_topic::MqttPayload,
+    };
+
+    #[test]
+    fn test_1_al_reconciliar_se_recuperan_los_incidentes_activos_y_luego_se_resuelven() {
+        let mut cameras = HashMap::new();
+        cameras.insert(1, Camera::new(1, -34.6090, -58.3873, 250.0));
+        cameras.insert(2, Camera::new(2, -34.7000, -58.5000, 250.0));
+        let cameras = Arc::new(Mutex::new(cameras));
+        let (cameras_tx, cameras_rx) = mpsc::channel();
+        let (logger_tx, _logger_rx) = mpsc::channel();
+        let mut logic = CamerasLogic::new(cameras.clone(), cameras_tx, StringLogger::new(logger_tx));
+
+        let mut incident = Incident::new(Incident::generate_id(), (-34.6091, -58.3874), IncidentSource::Manual);
+        let mut resolved = Incident::new(Incident::generate_id(), (-34.7001, -58.5001), IncidentSource::Manual);
+        resolved.set_resolved();
+
+        // Los resueltos no se recuperan, y los ya conocidos no se vuelven a procesar
+        assert_eq!(logic.reconcile_active_incidents(vec![incident.clone(), resolved]).unwrap(), 1);
+        assert_eq!(logic.reconcile_active_incidents(vec![incident.clone()]).unwrap(), 0);
+        assert!(logic.is_managing(&incident.get_info()));
+        assert_eq!(cameras.lock().unwrap().get(&1).unwrap().get_state(), CameraState::Active);
+        assert_eq!(cameras.lock().unwrap().get(&2).unwrap().get_state(), CameraState::SavingMode);
+        let sent: Vec<u32> = cameras_rx
+            .try_iter()
+            .map(|bytes| Camera::decode(&bytes).unwrap().get_id())
+            .collect();
+        assert_eq!(sent, vec![1]);
+
+        // El resuelto que llega luego ya no es de un incidente desconocido
+        incident.set_resolved();
+        logic.manage_incident(incident.clone()).unwrap();
+        assert!(!logic.is_managing(&incident.get_info()));
+        assert_eq!(cameras.lock().unwrap().get(&1).unwrap().get_state(), CameraState::SavingMode);
+    }
+
+    #[test]
+    fn test_2_al_reconciliar_se_resuelven_los_incidentes_manejados_que_ya_no_estan_activos() {
+        let mut cameras = HashMap::new();
+        cameras.insert(1, Camera::new(1, -34.6090, -58.3873, 250.0));
+        cameras.insert(2, Camera::new(2, -34.7000, -58.5000, 250.0));
+        let cameras = Arc::new(Mutex::new(cameras));
+        let (cameras_tx, cameras_rx) = mpsc::channel();
+        let (logger_tx, _logger_rx) = mpsc::channel();
+        let mut logic = CamerasLogic::new(cameras.clone(), cameras_tx, StringLogger::new(logger_tx));
+
+        // Se manejaban dos incidentes, uno en el rango de cada cámara
+        let resolved_meanwhile = Incident::new(Incident::generate_id(), (-34.6091, -58.3874), IncidentSource::Manual);
+        let still_active = Incident::new(Incident::generate_id(), (-34.7001, -58.5001), IncidentSource::Automated);
+        logic.manage_incident(resolved_meanwhile.clone()).unwrap();
+        logic.manage_incident(still_active.clone()).unwrap();
+        let _ = cameras_rx.try_iter().count();
+
+        // Según la respuesta, sólo sigue activo uno: el otro se resolvió mientras no se lo recibía
+        assert_eq!(logic.reconcile_active_incidents(vec![still_active.clone()]).unwrap(), 0);
+        assert!(!logic.is_managing(&resolved_meanwhile.get_info()));
+        assert!(logic.is_managing(&still_active.get_info()));
+        assert_eq!(cameras.lock().unwrap().get(&1).unwrap().get_state(), CameraState::SavingMode);
+        assert_eq!(cameras.lock().unwrap().get(&2).unwrap().get_state(), CameraState::Active);
+
+        // La cámara que volvió a ahorro de energía se envía para publicarse
+        let sent: Vec<(u32, CameraState)> = cameras_rx
+            .try_iter()
+            .map(|bytes| Camera::decode(&bytes).unwrap())
+            .map(|camera| (camera.get_id(), camera.get_state()))
+            .collect();
+        assert_eq!(sent, vec![(1, CameraState::SavingMode)]);
+    }
+}
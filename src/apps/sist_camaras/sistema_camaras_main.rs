@@ -1,44 +1,127 @@
-use std::io::Error;
+use std::io::{Error, ErrorKind};
+use std::sync::mpsc::Sender;
+
+use signal_hook::{consts::{SIGINT, SIGTERM}, iterator::Signals};
 
 use rustx::logging::string_logger::StringLogger;
 use rustx::mqtt::mqtt_utils::will_message_utils::will_message::WillMessageData;
 use rustx::mqtt::mqtt_utils::will_message_utils::{app_type::AppType, will_content::WillContent};
 use rustx::{
     apps::{
-        common_clients::{get_app_will_topic, get_broker_address, join_all_threads},
-        sist_camaras::{manage_stored_cameras::create_cameras, sistema_camaras::SistemaCamaras},
+        common_clients::{
+            get_app_will_topic, get_broker_address, get_option_value, has_option, join_all_threads,
+        },
+        sist_camaras::{
+            bordering::{BorderingRules, SIST_CAMARAS_PROPERTIES_FILE},
+            camera::CameraSystemId,
+            manage_stored_cameras::{create_cameras, default_camera_registry},
+            sistema_camaras::{camera_system_client_id, read_camera_system_id, SistemaCamaras},
+            types::shareable_cameras_type::ShCamerasType,
+        },
     },
-    mqtt::client::mqtt_client::MQTTClient,
+    mqtt::client::{mqtt_client::MQTTClient, reconnect_policy::ReconnectPolicy},
 };
 
-fn get_formatted_app_id() -> String {
-    String::from("Sistema-Camaras")
+/// El will lleva el id del sistema, para que al desconectarse se quiten sólo sus cámaras.
+fn get_app_will_msg_content(system_id: CameraSystemId) -> WillContent {
+    WillContent::new(AppType::Cameras, Some(system_id))
+}
+
+/// Opción para correr sin el abm por consola: las cámaras se administran por MQTT (topic `cam/cmd`),
+/// y se sale con SIGINT/SIGTERM.
+const DAEMON_OPTION: &str = "--daemon";
+
+/// Opción para indicar el id del sistema de cámaras (`--system-id=2`), en lugar del de `sistema_camaras.properties`.
+/// Permite correr varios sistemas de cámaras con el mismo broker.
+const SYSTEM_ID_OPTION: &str = "--system-id";
+
+/// Devuelve el id del sistema de cámaras: el de la opción `--system-id`, o el de `sistema_camaras.properties`.
+fn get_system_id() -> Result<CameraSystemId, Error> {
+    match get_option_value(SYSTEM_ID_OPTION) {
+        Some(value) => value.parse().map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Id de sistema de cámaras no válido: {}", value),
+            )
+        }),
+        None => read_camera_system_id(SIST_CAMARAS_PROPERTIES_FILE),
+    }
 }
 
-fn get_app_will_msg_content() -> WillContent {
-    WillContent::new(AppType::Cameras, None)
+/// Lanza un hilo que, al recibir SIGINT o SIGTERM, pide la salida por `exit_tx` (como la opción Salir del abm).
+fn exit_on_signals(exit_tx: Sender<bool>, logger: StringLogger) -> Result<(), Error> {
+    let mut signals = Signals::new([SIGINT, SIGTERM])?;
+    std::thread::spawn(move || {
+        if let Some(signal) = signals.forever().next() {
+            logger.log(format!("Recibida señal {}, saliendo.", signal));
+            if let Err(e) = exit_tx.send(true) {
+                println!("Error al intentar salir: {:?}", e);
+            }
+        }
+    });
+    Ok(())
 }
 
 fn main() -> Result<(), Error> {
-    let broker_addr = get_broker_address();
-    let cameras = create_cameras();
+    let as_daemon = has_option(DAEMON_OPTION);
+    let system_id = get_system_id()?;
 
     // Se crean y configuran ambos extremos del string logger
-    let (mut logger, handle_logger) = StringLogger::create_logger(get_formatted_app_id());
+    let (mut logger, handle_logger) = StringLogger::create_logger(camera_system_client_id(system_id));
+
+    match create_cameras() {
+        Ok((cameras, bordering)) => {
+            if let Err(e) = run(cameras, bordering, system_id, as_daemon, &logger) {
+                println!("Error al ejecutar sistema cámaras: {:?}", e);
+                logger.log(format!("Error al ejecutar sistema cámaras: {:?}", e));
+            }
+        }
+        Err(e) => {
+            println!("Error al cargar las cámaras: {:?}", e);
+            logger.log(format!("Error al cargar las cámaras: {:?}", e));
+        }
+    }
+
+    logger.stop_logging();
 
+    // Se espera al hijo para el logger
+    if handle_logger.join().is_err() {
+        println!("Error al esperar al hijo para string logger writer.")
+    }
+    
+    Ok(())
+}
+
+/// Conecta sistema cámaras al broker y lo ejecuta con las `cameras` cargadas, hasta que se pida salir.
+fn run(
+    cameras: ShCamerasType,
+    bordering: BorderingRules,
+    system_id: CameraSystemId,
+    as_daemon: bool,
+    logger: &StringLogger,
+) -> Result<(), Error> {
+    let broker_addr = get_broker_address();
     let qos = 1; // []
-    let client_id = get_formatted_app_id();
-    let will_msg_content = get_app_will_msg_content();
+    let client_id = camera_system_client_id(system_id);
+    let will_msg_content = get_app_will_msg_content(system_id);
     let will_msg_data =
         WillMessageData::new(will_msg_content.to_str(), get_app_will_topic(), qos, 1);
 
-    match MQTTClient::mqtt_connect_to_broker(client_id, &broker_addr, Some(will_msg_data), logger.clone_ref()) {
-        Ok((mqtt_client, publish_msg_rx, handle)) => {
+    match MQTTClient::mqtt_connect_to_broker_with_reconnect(client_id, &broker_addr, Some(will_msg_data), ReconnectPolicy::default(), logger.clone_ref()) {
+        Ok((mqtt_client, _publish_msg_rx, _connection_event_rx, handle)) => {
             println!("Conectado al broker MQTT.");
             logger.log("Conectado al broker MQTT".to_string());
 
-            let mut sistema_camaras = SistemaCamaras::new(cameras, logger.clone_ref());
-            let mut handles = sistema_camaras.spawn_threads(publish_msg_rx, mqtt_client);
+            let mut sistema_camaras = SistemaCamaras::new_with_bordering(cameras, bordering, logger.clone_ref());
+            sistema_camaras.set_system_id(system_id);
+            let registry = Box::new(default_camera_registry());
+            let mut handles = if as_daemon {
+                let (handles, exit_tx) = sistema_camaras.spawn_threads_as_daemon(mqtt_client, registry);
+                exit_on_signals(exit_tx, logger.clone_ref())?;
+                handles
+            } else {
+                sistema_camaras.spawn_threads(mqtt_client, registry)
+            };
 
             handles.push(handle);
             join_all_threads(handles);
@@ -46,12 +129,5 @@ fn main() -> Result<(), Error> {
         Err(e) => println!("Error al conectar al broker MQTT: {:?}", e),
     }
 
-    logger.stop_logging();
-
-    // Se espera al hijo para el logger
-    if handle_logger.join().is_err() {
-        println!("Error al esperar al hijo para string logger writer.")
-    }
-    
     Ok(())
 }
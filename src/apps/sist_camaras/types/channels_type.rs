@@ -7,6 +7,8 @@ type Channels = (
     Receiver<bool>,
     Sender<()>,
     Receiver<()>,
+    Sender<()>,
+    Receiver<()>,
 );
 
 /// Función que crea y devuelve extremos de channels para Sistema Cámaras.
@@ -17,5 +19,16 @@ pub fn create_channels() -> Channels {
     let (exit_tx, exit_rx) = mpsc::channel::<bool>();
     // Hilo de Exit cuando recibe aviso, lo propaga por tx hacia el Detector para que él corte su loop
     let (exit_detector_tx, exit_detector_rx) = mpsc::channel::<()>();
-    (cameras_tx, cameras_rx, exit_tx, exit_rx, exit_detector_tx, exit_detector_rx)
+    // Y también hacia el hilo del latido de las cámaras, para que deje de publicarlas
+    let (exit_heartbeat_tx, exit_heartbeat_rx) = mpsc::channel::<()>();
+    (
+        cameras_tx,
+        cameras_rx,
+        exit_tx,
+        exit_rx,
+        exit_detector_tx,
+        exit_detector_rx,
+        exit_heartbeat_tx,
+        exit_heartbeat_rx,
+    )
 }
\ No newline at end of file
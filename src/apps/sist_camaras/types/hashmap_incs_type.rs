@@ -2,4 +2,4 @@ use std::collections::HashMap;
 
 use crate::apps::incident_data::incident_info::IncidentInfo;
 
-pub type HashmapIncsType = HashMap<IncidentInfo, Vec<u8>>;
\ No newline at end of file
+pub type HashmapIncsType = HashMap<IncidentInfo, ((f64, f64), Vec<u32>)>; // (inc_info, ( (inc_pos), ids de las cámaras que lo siguen ))
\ No newline at end of file
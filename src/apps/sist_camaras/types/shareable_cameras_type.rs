@@ -2,4 +2,4 @@ use std::{collections::HashMap, sync::{Arc, Mutex}};
 
 use super::super::camera::Camera;
 
-pub type ShCamerasType = Arc<Mutex<HashMap<u8, Camera>>>;
\ No newline at end of file
+pub type ShCamerasType = Arc<Mutex<HashMap<u32, Camera>>>;
\ No newline at end of file
@@ -1,6 +1,6 @@
-use std::{io::Error, sync::mpsc::{self, Sender}, thread::sleep, time::Duration};
+use std::{io::Error, sync::mpsc::{self, Sender}, time::Duration};
 
-use crate::{apps::sist_dron::calculations::{calculate_direction, calculate_distance}, logging::string_logger::StringLogger};
+use crate::{apps::{geo, sist_dron::dron_flying_info::direction_from_bearing}, logging::string_logger::StringLogger, time::clock::ShClock};
 
 use super::{data::Data, dron_current_info::DronCurrentInfo, dron_state::DronState, sist_dron_properties::SistDronProperties};
 
@@ -10,18 +10,19 @@ pub struct BatteryManager {
     dron_properties: SistDronProperties,
     logger: StringLogger,
     ci_tx: Sender<DronCurrentInfo>,
-    process_inc_tx: mpsc::Sender<()>
+    process_inc_tx: mpsc::Sender<()>,
+    clock: ShClock,
 }
 
 impl BatteryManager {
 
-    pub fn new(current_data: Data, dron_properties: SistDronProperties, logger: StringLogger, ci_tx: Sender<DronCurrentInfo>, process_inc_tx: mpsc::Sender<()>) -> Self {
-        Self { current_data, dron_properties, logger, ci_tx, process_inc_tx }
+    pub fn new(current_data: Data, dron_properties: SistDronProperties, logger: StringLogger, ci_tx: Sender<DronCurrentInfo>, process_inc_tx: mpsc::Sender<()>, clock: ShClock) -> Self {
+        Self { current_data, dron_properties, logger, ci_tx, process_inc_tx, clock }
     }
 
     pub fn run(&mut self) {
         loop {
-            sleep(Duration::from_secs(5));
+            self.clock.sleep(Duration::from_secs(5));
             
             //Actualizar batería
             if let Err(e) = self.decrement_and_check_battery_lvl(){
@@ -54,7 +55,7 @@ impl BatteryManager {
             let maintanence_position = self.dron_properties.get_mantainance_position();
             self.fly_to_mantainance(maintanence_position, true)?;
 
-            sleep(Duration::from_secs(3));
+            self.clock.sleep(Duration::from_secs(3));
             self.recharge_battery()?;
             self.logger.log("Recargando batería al 100%.".to_string());
 
@@ -74,25 +75,26 @@ impl BatteryManager {
         flag_maintanance: bool,
     ) -> Result<(), Error> {
         let origin = self.current_data.get_current_position()?;
-        let dir = calculate_direction(origin, destination);
+        let bearing = geo::initial_bearing(origin, destination);
         println!("Fly_to: volando"); // se puede borrar
         self.logger.log(format!(
-            "Fly_to: dir: {:?}, vel: {}",
-            dir,
+            "Fly_to: rumbo: {:.1}°, vel: {} km/h",
+            bearing,
             self.dron_properties.get_speed()
         ));
 
         // self.current_data.set_state(DronState::Flying, flag_maintanance)?; // diferencia en caso mantenimiento
-        self.current_data.set_flying_info_values(dir, self.dron_properties.get_speed(), flag_maintanance)?;
+        self.current_data.set_flying_info_values(direction_from_bearing(bearing), self.dron_properties.get_speed(), flag_maintanance)?;
 
         let mut current_pos = origin;
-        let threshold = 0.001; // Define un umbral adecuado para tu aplicación
-        while calculate_distance(current_pos, destination) > threshold {
-            current_pos = self.current_data.increment_current_position_in(dir, flag_maintanance)?;
+        // Avanza de a un paso, hasta quedar a menos de un paso del destino
+        let step_length = self.dron_properties.get_flight_step_length();
+        while geo::distance(current_pos, destination) > step_length {
+            let bearing = geo::initial_bearing(current_pos, destination);
+            current_pos = self.current_data.increment_current_position_in(bearing, step_length, flag_maintanance)?;
 
             // Simular el vuelo, el dron se desplaza
-            let a = 4/5; // aux
-            sleep(Duration::from_secs(a));
+            self.clock.sleep(self.dron_properties.get_flight_step_duration());
             self.logger.log(format!(
                 "   incrementada la posición actual: {:?}",
                 self.current_data.get_current_position()
@@ -16,12 +16,11 @@ impl Data {
     }
 
     /// Toma lock y obtiene el id del dron.
-    pub fn get_id(&self) -> Result<u8, Error> {
+    pub fn get_id(&self) -> Result<u32, Error> {
         if let Ok(ci) = self.current_info.lock() {
             return Ok(ci.get_id());
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de current info.",
         ))
     }
@@ -31,8 +30,7 @@ impl Data {
         if let Ok(ci) = self.current_info.lock() {
             return Ok(ci.get_state());
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de current info.",
         ))
     }
@@ -54,8 +52,7 @@ impl Data {
                 ));
             };
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de current info.",
         ))
     }
@@ -91,8 +88,7 @@ impl Data {
             ci.unset_flying_info();
             return Ok(());
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de current info.",
         ))
     }
@@ -102,8 +98,7 @@ impl Data {
             ci.set_flying_info(info);
             return Ok(());
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de current info.",
         ))
     }
@@ -113,8 +108,7 @@ impl Data {
         if let Ok(ci) = self.current_info.lock() {
             return Ok(ci.get_battery_lvl());
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de current info.",
         ))
     }
@@ -124,8 +118,7 @@ impl Data {
         if let Ok(mut ci) = self.current_info.lock() {
             Ok(ci.decrement_and_check_battery_lvl(min_battery))
         } else {
-            Err(Error::new(
-                ErrorKind::Other,
+            Err(Error::other(
                 "Error al tomar lock de current info.",
             ))
         }
@@ -137,8 +130,7 @@ impl Data {
             ci.set_battery_lvl(new_battery_level);
             Ok(())
         } else {
-            Err(Error::new(
-                ErrorKind::Other,
+            Err(Error::other(
                 "Error al tomar lock de current info.",
             ))
         }
@@ -151,8 +143,7 @@ impl Data {
             ci.set_inc_id_to_resolve(inc_info);
             return Ok(());
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de current info.",
         ))
     }
@@ -162,8 +153,7 @@ impl Data {
             ci.unset_inc_id_to_resolve();
             return Ok(());
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de current info.",
         ))
     }
@@ -172,8 +162,7 @@ impl Data {
         if let Ok(ci) = self.current_info.lock() {
             return Ok(ci.get_inc_id_to_resolve());
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de current info.",
         ))
     }
@@ -183,17 +172,17 @@ impl Data {
         if let Ok(ci) = self.current_info.lock() {
             return Ok(ci.get_current_position());
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de current info.",
         ))
     }
-    /// Toma lock, incrementa la `current_position` en la dirección recibida, y la devuelve actualizada.
+    /// Toma lock, avanza la `current_position` `distance` metros con el rumbo recibido, y la devuelve actualizada.
     /// El flag de mantenimiento indica si quien llama a esta función es el módulo encargado del mantenimiento,
     /// y se utiliza para otorgar permisos.
     pub fn increment_current_position_in(
         &self,
-        dir: (f64, f64),
+        bearing: f64,
+        distance: f64,
         flag_maintanance: bool,
     ) -> Result<(f64, f64), Error> {
         if let Ok(mut ci) = self.current_info.lock() {
@@ -201,7 +190,7 @@ impl Data {
             let is_not_maintainance_set =
                 ci.get_state() != DronState::Mantainance && !flag_maintanance;
             if is_mantainance_set || is_not_maintainance_set {
-                Ok(ci.increment_current_position_in(dir))
+                Ok(ci.increment_current_position_in(bearing, distance))
             } else {
                 Err(Error::new(
                     ErrorKind::InvalidData,
@@ -209,8 +198,7 @@ impl Data {
                 ))
             }
         } else {
-            Err(Error::new(
-                ErrorKind::Other,
+            Err(Error::other(
                 "Error al tomar lock de current info.",
             ))
         }
@@ -221,8 +209,7 @@ impl Data {
             ci.set_current_position(new_position);
             return Ok(());
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de current info.",
         ))
     }
@@ -238,8 +225,7 @@ impl Data {
         if let Ok(ci) = self.current_info.lock() {
             return Ok(ci.clone());
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de current info.",
         ))
     }
@@ -249,8 +235,7 @@ impl Data {
         if let Ok(ci) = self.current_info.lock() {
             return Ok(ci.get_distance_to(destination));
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de current info.",
         ))
     }
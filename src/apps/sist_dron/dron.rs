@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap, fs, io::{self, Error, ErrorKind}, sync::{mpsc, Arc, Mutex}, thread::{self, JoinHandle}
+    collections::HashMap, fmt::Debug, fs, io::{self, Error, ErrorKind}, sync::{mpsc, Arc, Mutex}, thread::{self, JoinHandle}
 };
 
 use std::sync::mpsc::Receiver as MpscReceiver;
@@ -9,17 +9,19 @@ use crate::apps::{
     sist_dron::dron_state::DronState,
 };
 use crate::apps::{
-    common_clients::there_are_no_more_publish_msgs, incident_data::incident_info::IncidentInfo,
+    common_clients::there_are_no_more_publish_msgs,
+    incident_data::{incident::Incident, incident_info::IncidentInfo},
 };
 use crate::logging::string_logger::StringLogger;
-use crate::mqtt::{client::mqtt_client::MQTTClient, messages::publish_message::PublishMessage};
+use crate::time::clock::{ShClock, SystemClock};
+use crate::mqtt::client::{mqtt_client::MQTTClient, typed_topic::{MqttPayload, Topic}};
 
 use super::{
     battery_manager::BatteryManager, data::Data, dron_current_info::DronCurrentInfo,
     dron_logic::DronLogic, sist_dron_properties::SistDronProperties,
 };
 
-type DistancesType = Arc<Mutex<HashMap<IncidentInfo, ((f64, f64), Vec<(u8, f64)>)>>>; // (inc_info, ( (inc_pos),(dron_id, distance_to_incident)) )
+type DistancesType = Arc<Mutex<HashMap<IncidentInfo, ((f64, f64), Vec<(u32, f64)>)>>>; // (inc_info, ( (inc_pos),(dron_id, distance_to_incident)) )
 
 /// Struct que representa a cada uno de los drones del sistema de vigilancia.
 /// Posee componentes para manejar su lógica de procesamiento de incidentes, y gestionar su batería y
@@ -37,12 +39,18 @@ pub struct Dron {
 
     drone_distances_by_inc: DistancesType,
     qos: u8,
+    clock: ShClock,
 }
 
 impl Dron {
     /// Crea un Dron. Dron se inicia con batería al 100%, desde la posición del range_center, con estado activo.
-    pub fn new(id: u8, lat: f64, lon: f64, logger: StringLogger) -> Result<Self, Error> {
-        let dron = Self::new_internal(id, lat, lon, logger)?;
+    pub fn new(id: u32, lat: f64, lon: f64, logger: StringLogger) -> Result<Self, Error> {
+        Self::new_with_clock(id, lat, lon, SystemClock::shared(), logger)
+    }
+
+    /// Como `new`, pero las esperas y el vuelo del dron se miden con `clock` (ie un reloj simulado en los tests).
+    pub fn new_with_clock(id: u32, lat: f64, lon: f64, clock: ShClock, logger: StringLogger) -> Result<Self, Error> {
+        let dron = Self::new_internal(id, lat, lon, clock, logger)?;
         dron.logger.log(format!("Dron: Iniciado dron {:?}", id));
 
         Ok(dron)
@@ -60,7 +68,6 @@ impl Dron {
     pub fn spawn_threads(
         &mut self,
         mqtt_client: MQTTClient,
-        mqtt_rx: MpscReceiver<PublishMessage>,
     ) -> Result<Vec<JoinHandle<()>>, Error> {
         let mut children: Vec<JoinHandle<()>> = vec![];
         let mqtt_client_sh = Arc::new(Mutex::new(mqtt_client));
@@ -74,7 +81,7 @@ impl Dron {
         children.push(self.spawn_for_update_battery(ci_tx.clone(), process_inc_tx.clone()));
 
         children.push(self.spawn_recv_ci_and_publish(ci_rx, mqtt_client_sh.clone()));
-        self.subscribe_to_topics(mqtt_client_sh.clone(), mqtt_rx, ci_tx, process_inc_tx, process_inc_rx)?;
+        self.subscribe_to_topics(mqtt_client_sh.clone(), ci_tx, process_inc_tx, process_inc_rx)?;
 
         Ok(children)
     }
@@ -88,7 +95,8 @@ impl Dron {
                 self_clone.dron_properties,
                 self_clone.logger,
                 ci_tx,
-                process_inc_tx
+                process_inc_tx,
+                self_clone.clock,
             );
             battery_manager.run();
         })
@@ -101,6 +109,7 @@ impl Dron {
             logger: self.logger.clone_ref(),
             drone_distances_by_inc: Arc::clone(&self.drone_distances_by_inc),
             qos: self.qos,
+            clock: self.clock.clone(),
         }
     }
 
@@ -131,9 +140,8 @@ impl Dron {
     ) -> Result<(), Error> {
         if let Ok(mut mqtt_client_lock) = mqtt_client.lock() {
             let topic = AppsMqttTopics::DronTopic.to_str();
-            println!("[DEBUG TEMA ACK]: Por hacer publish:");
-            mqtt_client_lock.mqtt_publish(topic, &ci.to_bytes(), self.qos)?;
-            println!("[DEBUG TEMA ACK]: hecho el publish:");
+            // No se espera el ack, para no atrasarse durante el vuelo; el retransmitter reenvía si hace falta.
+            mqtt_client_lock.mqtt_publish_pipelined(topic, &ci.encode()?, self.qos)?;
         };
         Ok(())
     }
@@ -142,30 +150,33 @@ impl Dron {
     fn subscribe_to_topics(
         &mut self,
         mqtt_client: Arc<Mutex<MQTTClient>>,
-        mqtt_rx: MpscReceiver<PublishMessage>,
         ci_tx: mpsc::Sender<DronCurrentInfo>,
         process_inc_tx: mpsc::Sender<()>,
         process_inc_rx: mpsc::Receiver<()>,
     ) -> Result<(), Error> {
-        self.subscribe_to_topic(&mqtt_client, AppsMqttTopics::IncidentTopic.to_str())?;
-        self.subscribe_to_topic(&mqtt_client, AppsMqttTopics::DronTopic.to_str())?;
-        self.receive_messages_from_subscribed_topics(mqtt_rx, ci_tx, process_inc_tx, process_inc_rx);
+        let incidents_rx = self.subscribe_to_topic(&mqtt_client, &AppsMqttTopics::incidents())?;
+        let drones_rx = self.subscribe_to_topic(&mqtt_client, &AppsMqttTopics::drones())?;
+        self.receive_messages_from_subscribed_topics(incidents_rx, drones_rx, ci_tx, process_inc_tx, process_inc_rx);
 
         Ok(())
     }
 
-    /// Se suscribe al topic recibido.
-    fn subscribe_to_topic(
+    /// Se suscribe al topic recibido, y devuelve el rx por el que se recibirán sus mensajes ya decodificados.
+    fn subscribe_to_topic<T>(
         &self,
         mqtt_client: &Arc<Mutex<MQTTClient>>,
-        topic: &str,
-    ) -> Result<(), Error> {
-        if let Ok(mut mqtt_client) = mqtt_client.lock() {
-            mqtt_client.mqtt_subscribe(vec![((String::from(topic)), self.qos)])?;
-            self.logger
-                .log(format!("Dron: Suscripto a topic: {}", topic));
-        }
-        Ok(())
+        topic: &Topic<T>,
+    ) -> Result<MpscReceiver<Result<T, Error>>, Error>
+    where
+        T: MqttPayload + Send + 'static,
+    {
+        let mut mqtt_client = mqtt_client
+            .lock()
+            .map_err(|_| Error::other("Error al tomar lock del mqtt_client."))?;
+        let rx = mqtt_client.mqtt_subscribe_typed(topic, self.qos)?;
+        self.logger
+            .log(format!("Dron: Suscripto a topic: {}", topic.get_name()));
+        Ok(rx)
     }
 
     /// Recibe mensajes de los topics a los que se ha suscrito: inc y dron.
@@ -173,12 +184,13 @@ impl Dron {
     /// Lanza un hilo por cada mensaje recibido, para procesarlo, y espera a sus hijos.
     fn receive_messages_from_subscribed_topics(
         &mut self,
-        mqtt_rx: MpscReceiver<PublishMessage>,
+        incidents_rx: MpscReceiver<Result<Incident, Error>>,
+        drones_rx: MpscReceiver<Result<DronCurrentInfo, Error>>,
         ci_tx: mpsc::Sender<DronCurrentInfo>,
         process_inc_tx: mpsc::Sender<()>,
         process_inc_rx: mpsc::Receiver<()>,
     ) {
-        // Módulo encargado de la lógica del dron al recibir PublishMessage'self_clone.
+        // Módulo encargado de la lógica del dron al recibir los mensajes.
         let self_clone = self.clone_ref();
         let dron_logic = DronLogic::new(
             self_clone.data,
@@ -186,10 +198,9 @@ impl Dron {
             self_clone.logger,
             self_clone.drone_distances_by_inc.clone(),
             ci_tx,
+            self_clone.clock,
         );
 
-        //let (process_inc_tx, process_inc_rx) = mpsc::channel::<()>();
-
         // Hilo para controlar el vuelo del dron para ir a los incidentes [] aux: hilo nuevo
         let mut logic_clone = dron_logic.clone_ref();
         let logger_c = self.logger.clone_ref();
@@ -202,38 +213,56 @@ impl Dron {
             }
         });
 
-        // Recibe de mqtt
-        let mut children = vec![];
-        for publish_msg in mqtt_rx {
-            self.logger
-                .log(format!("Dron: Recibo mensaje Publish: {:?}", publish_msg));
-
-            // Lanza un hilo para procesar el mensaje, y luego lo espera correctamente
-            let handle_thread =
-                self.spawn_process_recvd_msg_thread(publish_msg, dron_logic.clone_ref(), process_inc_tx.clone());
-            children.push(handle_thread);
-        }
-        there_are_no_more_publish_msgs(&self.logger);
-
-        join_all_threads(children);
+        // Recibe de mqtt, un hilo por topic
+        let receivers = vec![
+            self.spawn_receive_thread(incidents_rx, dron_logic.clone_ref(), move |logic, inc| {
+                logic.process_incident(inc, process_inc_tx.clone())
+            }),
+            self.spawn_receive_thread(drones_rx, dron_logic, |logic, ci| {
+                logic.process_current_info(ci)
+            }),
+        ];
+        join_all_threads(receivers);
     }
 
-    /// Delega el procesamiento del `PublishMessage` recibido, al módulo `DronLogic`.
-    fn spawn_process_recvd_msg_thread(
+    /// Recibe los mensajes de un topic al que se ha suscrito, y lanza un hilo por cada uno, que delega su
+    /// procesamiento al módulo `DronLogic` llamando a `process`. Al no haber más mensajes, espera a sus hijos.
+    fn spawn_receive_thread<T, F>(
         &self,
-        msg: PublishMessage,
+        rx: MpscReceiver<Result<T, Error>>,
         dron_logic: DronLogic,
-        process_inc_tx: mpsc::Sender<()>,
-    ) -> JoinHandle<()> {
-        let mut logic_clone = dron_logic.clone_ref();
-        let logger_c = self.logger.clone_ref();
+        process: F,
+    ) -> JoinHandle<()>
+    where
+        T: Debug + Send + 'static,
+        F: Fn(&mut DronLogic, T) -> Result<(), Error> + Clone + Send + 'static,
+    {
+        let logger = self.logger.clone_ref();
         thread::spawn(move || {
-            if let Err(e) = logic_clone.process_recvd_msg(msg, process_inc_tx.clone()) {
-                logger_c.log(format!(
-                    "Error al procesar mensage recibido, process_rcvd_msg: {:?}.",
-                    e
-                ));
+            let mut children = vec![];
+            for decoded in rx {
+                match decoded {
+                    Ok(value) => {
+                        logger.log(format!("Dron: Recibo mensaje Publish: {:?}", value));
+                        // Lanza un hilo para procesar el mensaje, y luego lo espera correctamente
+                        let mut logic_clone = dron_logic.clone_ref();
+                        let logger_c = logger.clone_ref();
+                        let process = process.clone();
+                        children.push(thread::spawn(move || {
+                            if let Err(e) = process(&mut logic_clone, value) {
+                                logger_c.log(format!(
+                                    "Error al procesar mensage recibido, process_rcvd_msg: {:?}.",
+                                    e
+                                ));
+                            }
+                        }));
+                    }
+                    Err(e) => logger.log(format!("Dron: Error al decodificar mensaje recibido: {:?}", e)),
+                }
             }
+            there_are_no_more_publish_msgs(&logger);
+
+            join_all_threads(children);
         })
     }
 
@@ -255,9 +284,10 @@ impl Dron {
     /// Dron se inicia con batería al 100%, desde la posición del range_center, con estado activo.
     /// Función utilizada para testear, no necesita broker address.
     fn new_internal(
-        id: u8,
+        id: u32,
         initial_lat: f64,
         initial_lon: f64,
+        clock: ShClock,
         logger: StringLogger,
     ) -> Result<Self, Error> {
         let qos = Dron::leer_qos_desde_archivo("src/apps/sist_dron/qos_dron.properties")?;
@@ -288,6 +318,7 @@ impl Dron {
             logger,
             drone_distances_by_inc: drone_distances_by_incident,
             qos,
+            clock,
         };
 
         Ok(dron)
@@ -295,12 +326,13 @@ impl Dron {
 }
 
 #[cfg(test)]
-
 mod test {
     use super::Dron;
-    use crate::apps::sist_dron::calculations::calculate_direction;
+    use crate::apps::geo;
+    use crate::apps::sist_dron::dron_flying_info::direction_from_bearing;
     use crate::apps::sist_dron::dron_state::DronState;
     use crate::logging::string_logger::StringLogger;
+    use crate::time::clock::SystemClock;
     use std::sync::mpsc;
 
     fn create_dron_4() -> Dron {
@@ -311,7 +343,7 @@ mod test {
         let lat = -34.60282;
         let lon = -58.38730;
 
-        Dron::new_internal(4, lat, lon, logger).unwrap()
+        Dron::new_internal(4, lat, lon, SystemClock::shared(), logger).unwrap()
     }
 
     #[test]
@@ -337,38 +369,40 @@ mod test {
     }
 
     #[test]
-    fn test_3a_calculate_direction_da_la_direccion_esperada() {
-        // Dados destino y origen
+    fn test_3a_la_direccion_del_rumbo_es_la_esperada() {
+        // Dados destino y origen, sobre el ecuador
         let origin = (0.0, 0.0); // desde el (0,0)
-        let destination = (4.0, -3.0);
-        let hip = 5.0; // hipotenusa da 5;
+        let destination = (0.0, -3.0); // hacia el oeste
 
-        let dir = calculate_direction(origin, destination);
+        let dir = direction_from_bearing(geo::initial_bearing(origin, destination));
 
-        // La dirección calculada es la esperada
-        let expected_dir = (4.0 / hip, -3.0 / hip);
-        assert_eq!(dir, expected_dir);
-        // En "hip" cantidad de pasos, se llega a la posición de destino
-        assert_eq!(origin.0 + dir.0 * hip, destination.0);
-        assert_eq!(origin.1 + dir.1 * hip, destination.1);
+        // La dirección calculada es la esperada: sin componente lat, y lon negativa
+        assert!(dir.0.abs() < 1e-9);
+        assert!((dir.1 + 1.0).abs() < 1e-9);
     }
 
     #[test]
-    fn test_3b_calculate_direction_da_la_direccion_esperada() {
+    fn test_3b_el_dron_llega_al_destino_avanzando_de_a_un_paso() {
         let dron = create_dron_4();
-
-        // Dados destino y origen
-        let origin = dron.data.get_current_position().unwrap(); // desde (incident_position, candidate_dron) que no es el (0,0)
-        let destination = (origin.0 + 4.0, origin.1 - 3.0);
-        let hip = 5.0; // hipotenusa da 5;
-
-        let dir = calculate_direction(origin, destination);
-
-        // La dirección calculada es la esperada
-        let expected_dir = (4.0 / hip, -3.0 / hip);
-        assert_eq!(dir, expected_dir);
-        // En "hip" cantidad de pasos, se llega a la posición de destino
-        assert_eq!(origin.0 + dir.0 * hip, destination.0);
-        assert_eq!(origin.1 + dir.1 * hip, destination.1);
+        let step_length = dron.dron_properties.get_flight_step_length();
+
+        // Dados destino y origen, a 1000 metros
+        let origin = dron.data.get_current_position().unwrap();
+        let destination = geo::destination_point(origin, 130.0, 1000.0);
+
+        // Cada paso lo acerca un paso al destino
+        let mut current_pos = origin;
+        let mut steps = 0;
+        while geo::distance(current_pos, destination) > step_length {
+            let bearing = geo::initial_bearing(current_pos, destination);
+            current_pos = dron
+                .data
+                .increment_current_position_in(bearing, step_length, false)
+                .unwrap();
+            steps += 1;
+        }
+        let expected_steps = 1000.0 / step_length;
+        assert!(steps as f64 >= expected_steps - 1.0 && steps as f64 <= expected_steps);
+        assert!(geo::distance(current_pos, destination) <= step_length);
     }
 }
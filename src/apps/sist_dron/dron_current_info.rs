@@ -1,14 +1,23 @@
 use std::io::{Error, ErrorKind};
 
-use crate::apps::incident_data::incident_info::IncidentInfo;
+use crate::apps::incident_data::incident_info::{IncidentInfo, IncidentInfoV1};
+
+use serde::{Deserialize, Serialize};
+
+use crate::apps::payload_codec::{
+    envelope::{Encoding, PayloadSchema},
+    versioned_payload::{check_legacy_len, narrow_id, unsupported_version_error, VersionedPayload},
+};
+
+use crate::apps::geo;
 
 use super::dron_flying_info::DronFlyingInfo;
 use super::dron_state::DronState;
 
 /// Struct que contiene los campos que identifican al Dron (el id) y que pueden modificarse durante su funcionamiento.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct DronCurrentInfo {
-    id: u8,
+    id: u32,
     // Posición actual
     latitude: f64,
     longitude: f64,
@@ -22,7 +31,7 @@ pub struct DronCurrentInfo {
 impl DronCurrentInfo {
     /// Inicia con los parámetros recibidos; con ningún incidente en resolución y sin flying_info
     /// (es decir, inicia con estos dos últimos atributos en None).
-    pub fn new(id: u8, latitude: f64, longitude: f64, battery_lvl: u8, state: DronState) -> Self {
+    pub fn new(id: u32, latitude: f64, longitude: f64, battery_lvl: u8, state: DronState) -> Self {
         DronCurrentInfo {
             id,
             latitude,
@@ -34,10 +43,11 @@ impl DronCurrentInfo {
         }
     }
 
-    /// Pasa un struct `DronCurrentInfo` a bytes.
-    pub fn to_bytes(&self) -> Vec<u8> {
+    /// Pasa un struct `DronCurrentInfo` a bytes, con el formato previo al envelope.
+    /// Devuelve error si su id o el del incidente no entra en un byte.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
         let mut bytes = vec![];
-        bytes.extend_from_slice(&self.id.to_be_bytes());
+        bytes.push(narrow_id(self.id as u128, Self::SCHEMA)?);
         bytes.extend_from_slice(&self.latitude.to_be_bytes());
         bytes.extend_from_slice(&self.longitude.to_be_bytes());
         //println!("BYTES ID LAT Y LONG, ENCODEANDO: {:?}", bytes); //aux [] debug
@@ -48,7 +58,7 @@ impl DronCurrentInfo {
         // El info del incidente que se está resolviendo:
         let mut inc_info_to_send: Vec<u8> = vec![0,0];
         if let Some(inc_info) = &self.inc_info_to_resolve {
-            inc_info_to_send = inc_info.to_bytes();
+            inc_info_to_send = inc_info.to_bytes()?;
         }
         bytes.extend_from_slice(&inc_info_to_send);
         
@@ -60,15 +70,15 @@ impl DronCurrentInfo {
         } else {
             bytes.extend_from_slice(&0_u8.to_be_bytes()); // avisa que No se enviará más bytes
         }
-        bytes
+        Ok(bytes)
     }
 
-    /// Obtiene un struct `DronCurrentInfo` a partir de bytes.
+    /// Obtiene un struct `DronCurrentInfo` a partir de bytes con el formato previo al envelope.
     pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
         let mut idx = 0;
         let b_size: usize = 1;
 
-        let id = u8::from_be_bytes([bytes[idx]]);
+        let id = u8::from_be_bytes([bytes[idx]]) as u32;
         idx += b_size;
 
         let latitude = f64::from_be_bytes([
@@ -144,7 +154,7 @@ impl DronCurrentInfo {
 
     // Getters
     /// Devuelve el id.
-    pub fn get_id(&self) -> u8 {
+    pub fn get_id(&self) -> u32 {
         self.id
     }
     /// Devuelve latitud y longitud en las que dron se encuentra actualmente.
@@ -190,12 +200,10 @@ impl DronCurrentInfo {
         self.longitude = new_position.1;
     }
 
-    /// Incrementa la posición actual en la dirección recibida, y devuelve la nueva posición actual.
-    pub fn increment_current_position_in(&mut self, dir: (f64, f64)) -> (f64, f64) {
-        // La dirección es un vector unitario, pero para poder sumarlo a la lat y long y que tenga sentido
-        // hay que escalarla.
-        self.latitude += dir.0 / 10000.0;
-        self.longitude += dir.1 / 10000.0;
+    /// Avanza la posición actual `distance` metros con el rumbo recibido (en grados), y devuelve la nueva posición actual.
+    pub fn increment_current_position_in(&mut self, bearing: f64, distance: f64) -> (f64, f64) {
+        let new_position = geo::destination_point(self.get_current_position(), bearing, distance);
+        self.set_current_position(new_position);
 
         self.get_current_position()
     }
@@ -218,11 +226,9 @@ impl DronCurrentInfo {
         self.flying_info = None;
     }
 
+    /// Devuelve la distancia, en metros, desde la posición actual hasta `destination`.
     pub fn get_distance_to(&self, destination: (f64, f64)) -> f64 {
-        let origin = self.get_current_position();
-        let lat_dist = destination.0 - origin.0;
-        let lon_dist = destination.1 - origin.1;
-        f64::sqrt(lat_dist.powi(2) + lon_dist.powi(2))
+        geo::distance(self.get_current_position(), destination)
     }
 
     /// Decrementa la batería, y chequea y devuelve si la batería está por debajo del mínimo.
@@ -246,9 +252,80 @@ impl DronCurrentInfo {
     }
 }
 
+// id, latitud, longitud, batería, estado, info del incidente y si hay flying info.
+const DRON_CURRENT_INFO_MIN_LEN: usize = 1 + 8 + 8 + 1 + 1 + 2 + 1;
+// dirección (lat, lon) y velocidad.
+const DRON_FLYING_INFO_LEN: usize = 8 + 8 + 8;
+
+/// `DronCurrentInfo` en la versión 1 del envelope, con los ids de un byte.
+#[derive(Serialize, Deserialize)]
+struct DronCurrentInfoV1 {
+    id: u8,
+    latitude: f64,
+    longitude: f64,
+    battery_lvl: u8,
+    state: DronState,
+    inc_info_to_resolve: Option<IncidentInfoV1>,
+    flying_info: Option<DronFlyingInfo>,
+}
+
+impl VersionedPayload for DronCurrentInfo {
+    const SCHEMA: PayloadSchema = PayloadSchema::DronCurrentInfo;
+    // La versión 2 pasó el id de un byte a u32, y el del incidente a uuid.
+    const CURRENT_VERSION: u8 = 2;
+
+    fn to_legacy_bytes(&self) -> Result<Vec<u8>, Error> {
+        self.to_bytes()
+    }
+
+    fn from_legacy_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        check_legacy_len(bytes, DRON_CURRENT_INFO_MIN_LEN, Self::SCHEMA)?;
+        if bytes[DRON_CURRENT_INFO_MIN_LEN - 1] == 1 {
+            check_legacy_len(bytes, DRON_CURRENT_INFO_MIN_LEN + DRON_FLYING_INFO_LEN, Self::SCHEMA)?;
+        }
+        Self::from_bytes(bytes.to_vec())
+    }
+
+    fn from_older_version(version: u8, body: &[u8], encoding: Encoding) -> Result<Self, Error> {
+        match version {
+            1 => {
+                let v1: DronCurrentInfoV1 = encoding.deserialize(body)?;
+                Ok(Self {
+                    id: v1.id as u32,
+                    latitude: v1.latitude,
+                    longitude: v1.longitude,
+                    battery_lvl: v1.battery_lvl,
+                    state: v1.state,
+                    inc_info_to_resolve: v1.inc_info_to_resolve.map(IncidentInfo::from),
+                    flying_info: v1.flying_info,
+                })
+            }
+            _ => Err(unsupported_version_error(Self::SCHEMA, version)),
+        }
+    }
+
+    fn to_older_version(&self, version: u8, encoding: Encoding) -> Result<Vec<u8>, Error> {
+        match version {
+            1 => encoding.serialize(&DronCurrentInfoV1 {
+                id: narrow_id(self.id as u128, Self::SCHEMA)?,
+                latitude: self.latitude,
+                longitude: self.longitude,
+                battery_lvl: self.battery_lvl,
+                state: self.state,
+                inc_info_to_resolve: self.inc_info_to_resolve.as_ref().map(IncidentInfoV1::try_from).transpose()?,
+                flying_info: self.flying_info.clone(),
+            }),
+            _ => Err(unsupported_version_error(Self::SCHEMA, version)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::apps::sist_dron::{dron_current_info::DronCurrentInfo, dron_state::DronState};
+    use crate::apps::sist_dron::{
+        dron_current_info::DronCurrentInfo, dron_flying_info::DronFlyingInfo, dron_state::DronState,
+    };
+    use crate::apps::payload_codec::{envelope::Encoding, versioned_payload::VersionedPayload};
     use crate::apps::incident_data::{incident_info::IncidentInfo, incident_source::IncidentSource};
 
     #[test]
@@ -263,7 +340,7 @@ mod test {
             flying_info: None,
         };
 
-        let bytes = dron.to_bytes();
+        let bytes = dron.to_bytes().unwrap();
         let reconstructed_dron = DronCurrentInfo::from_bytes(bytes);
 
         assert_eq!(reconstructed_dron.unwrap(), dron);
@@ -277,13 +354,35 @@ mod test {
             longitude: -58.0,
             battery_lvl: 100,
             state: DronState::ExpectingToRecvIncident,
-            inc_info_to_resolve: Some(IncidentInfo::new(18, IncidentSource::Manual)),
+            inc_info_to_resolve: Some(IncidentInfo::new(IncidentInfo::id_from_narrow(18), IncidentSource::Manual)),
             flying_info: None,
         };
 
-        let bytes = dron.to_bytes();
+        let bytes = dron.to_bytes().unwrap();
         let reconstructed_dron = DronCurrentInfo::from_bytes(bytes);
 
         assert_eq!(reconstructed_dron.unwrap(), dron);
     }
+
+    #[test]
+    fn test_2_from_legacy_bytes_de_un_payload_incompleto_da_error() {
+        let mut dron = DronCurrentInfo::new(1, -34.0, -58.0, 100, DronState::Flying);
+        dron.set_flying_info(DronFlyingInfo::new((0.5, 0.5), 20.0));
+        let bytes = dron.to_legacy_bytes().unwrap();
+
+        assert_eq!(DronCurrentInfo::from_legacy_bytes(&bytes).unwrap(), dron);
+        // Falta parte de la flying info.
+        assert!(DronCurrentInfo::from_legacy_bytes(&bytes[..bytes.len() - 8]).is_err());
+    }
+
+    #[test]
+    fn test_3_se_decodifica_la_version_1_con_el_incidente_que_resuelve() {
+        let mut dron = DronCurrentInfo::new(7, -34.0, -58.0, 80, DronState::ManagingIncident);
+        dron.set_inc_id_to_resolve(IncidentInfo::new(IncidentInfo::id_from_narrow(3), IncidentSource::Automated));
+        let body = dron.to_older_version(1, Encoding::Json).unwrap();
+        assert_eq!(DronCurrentInfo::from_older_version(1, &body, Encoding::Json).unwrap(), dron);
+
+        let dron_nuevo = DronCurrentInfo::new(1000, -34.0, -58.0, 80, DronState::Flying);
+        assert!(dron_nuevo.to_older_version(1, Encoding::Json).is_err());
+    }
 }
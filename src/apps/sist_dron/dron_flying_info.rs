@@ -1,10 +1,18 @@
 use std::io::Error;
 
+use serde::{Deserialize, Serialize};
+
 /// Dirección y velocidad con las que vuela el dron.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct DronFlyingInfo {
     direction: (f64, f64), // vector unitario de dirección al volar, con componentes lat y lon
-    speed: f64,            // velocidad de desplazamiento al volar
+    speed: f64,            // velocidad de desplazamiento al volar, en km/h
+}
+
+/// Devuelve el vector unitario, con componentes lat y lon, que corresponde al rumbo recibido en grados.
+pub fn direction_from_bearing(bearing: f64) -> (f64, f64) {
+    let (sin, cos) = bearing.to_radians().sin_cos();
+    (cos, sin)
 }
 
 impl DronFlyingInfo {
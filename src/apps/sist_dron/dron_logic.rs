@@ -1,23 +1,23 @@
 use std::{
     collections::{HashMap, VecDeque},
     io::{Error, ErrorKind},
-    sync::{mpsc::{self, Sender}, Arc, Mutex}, thread::{self, sleep}, time::Duration,
+    sync::{mpsc::{self, Sender}, Arc, Mutex}, time::Duration,
 };
 
 use crate::{
     apps::{
-        apps_mqtt_topics::AppsMqttTopics,
         incident_data::{
             incident::Incident, incident_info::IncidentInfo, incident_state::IncidentState,
-        }, sist_dron::calculations::{calculate_direction, calculate_distance},
+        },
+        geo,
     },
     logging::string_logger::StringLogger,
-    mqtt::messages::publish_message::PublishMessage,
+    time::clock::ShClock,
 };
 
 use super::{
-    data::Data, dron_current_info::DronCurrentInfo, dron_state::DronState,
-    sist_dron_properties::SistDronProperties,
+    data::Data, dron_current_info::DronCurrentInfo, dron_flying_info::direction_from_bearing,
+    dron_state::DronState, sist_dron_properties::SistDronProperties,
 };
 
 /// Componente encargado de manejar la lógica de procesamiento de incidentes de cada Dron.
@@ -29,9 +29,14 @@ pub struct DronLogic {
     drone_distances_by_incident: DistancesType, // ya es arc mutex.
     ci_tx: Sender<DronCurrentInfo>,
     active_incs: Arc<Mutex<VecDeque<(IncidentInfo, Incident, u8)>>>, // el u8 es un contador de cuántos drones recibí que ya están yendo hacia ese inc.
+    clock: ShClock, // Con él se esperan las respuestas de los demás drones y se simula el vuelo.
 }
 
-type DistancesType = Arc<Mutex<HashMap<IncidentInfo, ((f64, f64), Vec<(u8, f64)>)>>>; // (inc_info, ( (inc_pos),(dron_id, distance_to_incident)) )
+/// Tiempo que espera cada dron, desde que avisa que responde a un incidente, para recibir las distancias de los
+/// demás drones antes de decidir si es de los dos más cercanos.
+pub const DISTANCES_WAIT: Duration = Duration::from_millis(3500);
+
+type DistancesType = Arc<Mutex<HashMap<IncidentInfo, ((f64, f64), Vec<(u32, f64)>)>>>; // (inc_info, ( (inc_pos),(dron_id, distance_to_incident)) )
 
 impl DronLogic {
     /// Crea un DronLogic.
@@ -41,6 +46,7 @@ impl DronLogic {
         logger: StringLogger,
         distances: DistancesType,
         ci_tx: Sender<DronCurrentInfo>,
+        clock: ShClock,
     ) -> Self {
         Self {
             current_data,
@@ -49,6 +55,7 @@ impl DronLogic {
             drone_distances_by_incident: distances,
             ci_tx,
             active_incs: Arc::new(Mutex::new(VecDeque::new())),
+            clock,
         }
     }
 
@@ -60,51 +67,36 @@ impl DronLogic {
             drone_distances_by_incident: self.drone_distances_by_incident.clone(),
             ci_tx: self.ci_tx.clone(),
             active_incs: self.active_incs.clone(),
+            clock: self.clock.clone(),
         }
     }
 
-    /// Recibe un mensaje de los topics a los que se suscribió, y lo procesa.
-    pub fn process_recvd_msg(
-        &mut self,
-        msg: PublishMessage,
-        process_inc_tx: mpsc::Sender<()>,
-    ) -> Result<(), Error> {
-        let topic = msg.get_topic();
-        let enum_topic = AppsMqttTopics::topic_from_str(topic.as_str())?;
-        match enum_topic {
-            AppsMqttTopics::IncidentTopic => self.process_valid_inc(msg.get_payload(), process_inc_tx),
-            AppsMqttTopics::DronTopic => {
-                let received_ci = DronCurrentInfo::from_bytes(msg.get_payload())?;
-                let not_myself = self.current_data.get_id()? != received_ci.get_id();
-                let recvd_dron_is_not_flying = received_ci.get_state() != DronState::Flying;
-                let recvd_dron_is_not_managing_incident =
-                    received_ci.get_state() != DronState::ManagingIncident;
-
-                let recvd_dron_is_analyzing_if_should_move = received_ci.get_state() == DronState::RespondingToIncident;
-                let recvd_dron_must_move = received_ci.get_state() == DronState::MustRespondToIncident;
-                
-                // Si la current_info recibida es de mi propio publish, no me interesa compararme conmigo mismo.
-                // Si el current_info recibida es de un dron que está volando, tampoco me interesa, esos publish serán para sistema de moniteo.
-                // Si el current_info recibida es de un dron que está en la ubicación de un incidente, tampoco me interesa, esos publish serán para sistema de moniteo.
-                if not_myself {
-                  
-                  if recvd_dron_is_not_flying && recvd_dron_is_not_managing_incident {
-                    if recvd_dron_is_analyzing_if_should_move {
-                        self.process_valid_dron(received_ci)?;
-                    }
+    /// Recibe una current info del topic de drones, y la procesa.
+    pub fn process_current_info(&mut self, received_ci: DronCurrentInfo) -> Result<(), Error> {
+        let not_myself = self.current_data.get_id()? != received_ci.get_id();
+        let recvd_dron_is_not_flying = received_ci.get_state() != DronState::Flying;
+        let recvd_dron_is_not_managing_incident =
+            received_ci.get_state() != DronState::ManagingIncident;
+
+        let recvd_dron_is_analyzing_if_should_move = received_ci.get_state() == DronState::RespondingToIncident;
+        let recvd_dron_must_move = received_ci.get_state() == DronState::MustRespondToIncident;
+        
+        // Si la current_info recibida es de mi propio publish, no me interesa compararme conmigo mismo.
+        // Si el current_info recibida es de un dron que está volando, tampoco me interesa, esos publish serán para sistema de moniteo.
+        // Si el current_info recibida es de un dron que está en la ubicación de un incidente, tampoco me interesa, esos publish serán para sistema de moniteo.
+        if not_myself {
+          
+          if recvd_dron_is_not_flying && recvd_dron_is_not_managing_incident {
+            if recvd_dron_is_analyzing_if_should_move {
+                self.process_valid_dron(received_ci)?;
+            }
 
-                  } else if recvd_dron_must_move {
-                    self.remove_from_active_incs_if_two_drones_already_flying(received_ci)?;
-                  }                                
+          } else if recvd_dron_must_move {
+            self.remove_from_active_incs_if_two_drones_already_flying(received_ci)?;
+          }                                
 
-                }
-                Ok(())
-            }
-            _ => Err(Error::new(
-                std::io::ErrorKind::InvalidData,
-                "Topic no conocido",
-            )),
         }
+        Ok(())
     }
 
     pub fn listen_for_and_process_new_active_incident(&mut self, rx: mpsc::Receiver<()>) -> Result<(), Error> {        
@@ -127,14 +119,12 @@ impl DronLogic {
         Ok(())        
     }
 
-    /// Recibe un incidente, analiza si está o no resuelto y actúa acorde.
-    fn process_valid_inc(
+    /// Recibe un incidente del topic de incidentes, analiza si está o no resuelto y actúa acorde.
+    pub fn process_incident(
         &mut self,
-        payload: Vec<u8>,
+        inc: Incident,
         process_inc_tx: mpsc::Sender<()>,
     ) -> Result<(), Error> {
-        let inc = Incident::from_bytes(payload)?;
-
         match *inc.get_state() {
             IncidentState::ActiveIncident => {
                 // Encolo el inc activo recibido
@@ -193,8 +183,7 @@ impl DronLogic {
             queue.push_back((inc.get_info(), inc.clone(), 0));
             return Ok(());
         } 
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de active_incs.",
         ))
 
@@ -206,8 +195,7 @@ impl DronLogic {
         if let Ok(mut queue) = self.active_incs.lock(){
             return Ok(queue.pop_front());
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de active_incs.",
         ))
     }
@@ -219,8 +207,7 @@ impl DronLogic {
             }
             return Ok(());
         } 
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de active_incs.",
         ))        
     }
@@ -244,14 +231,12 @@ impl DronLogic {
                 }
                 return Ok(());
             }
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Error al tomar lock de active_incs.",
             ));
         }
 
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error current_info recibido con estado e inc_info inválidos.",
         ))        
     }
@@ -286,8 +271,8 @@ impl DronLogic {
     ) -> Result<bool, Error> {
         let mut should_move = false;
 
-        //eSTE THREAD ES NECESARI. NO QUITAR
-        thread::sleep(Duration::from_millis(3500)); // Aux Probando
+        // Espera a recibir las distancias de los demás drones
+        self.clock.sleep(DISTANCES_WAIT);
         if let Ok(mut distances) = self.drone_distances_by_incident.lock() {
             if let Some((_incident_position, candidate_drones)) =
                 distances.get_mut(&incident.get_info())
@@ -296,7 +281,7 @@ impl DronLogic {
                 candidate_drones.sort_by(|a, b| a.1.total_cmp(&b.1));
 
                 // Seleccionar los primeros dos elementos después de ordenar
-                let closest_two_drones: Vec<u8> =
+                let closest_two_drones: Vec<u32> =
                     candidate_drones.iter().take(2).map(|&(id, _)| id).collect();
 
                 // Si el id del dron actual está en la lista de los dos más cercanos, entonces se mueve
@@ -394,18 +379,10 @@ impl DronLogic {
         Ok(())
     }
 
-    /// Calcula si se encuentra las coordenadas pasadas se encuentran dentro de su rango.
+    /// Calcula si las coordenadas pasadas se encuentran a no más de `range` metros del centro de su rango.
     fn is_within_range_from_self(&self, latitude: f64, longitude: f64, range: f64) -> bool {
-        let (center_lat, center_lon) = self.dron_properties.get_range_center_position();
-        let lat_dist = center_lat - latitude;
-        let long_dist = center_lon - longitude;
-        let rad = f64::sqrt(lat_dist.powi(2) + long_dist.powi(2));
-
-        // Ajuste para aprox dos manzanas en diagonal
-        let adjusted_range = range / 1000.0; // hay que modificar el range de las cámaras, ahora que son latitudes de verdad y no "3 4".
-                                             //println!("Dio que la cuenta vale: {}, y adj_range vale: {}. Era rango: {}", rad, adjusted_range, range); // debug []
-
-        rad <= (adjusted_range)
+        let center = self.dron_properties.get_range_center_position();
+        geo::distance(center, (latitude, longitude)) <= range
     }
 
     /// Analiza si el incidente que se resolvió fue el que el dron self estaba atendiendo.
@@ -455,27 +432,28 @@ impl DronLogic {
         destination: (f64, f64),
     ) -> Result<(), Error> {
         let origin = self.current_data.get_current_position()?;
-        let dir = calculate_direction(origin, destination);
+        let bearing = geo::initial_bearing(origin, destination);
         println!("Fly_to: volando"); // se puede borrar
         self.logger.log(format!(
-            "Fly_to: dir: {:?}, vel: {}",
-            dir,
+            "Fly_to: rumbo: {:.1}°, vel: {} km/h",
+            bearing,
             self.dron_properties.get_speed()
         ));
 
         self.current_data.set_state(DronState::Flying, false)?;
         self.current_data
-            .set_flying_info_values(dir, self.dron_properties.get_speed(), false)?;
+            .set_flying_info_values(direction_from_bearing(bearing), self.dron_properties.get_speed(), false)?;
         let mut current_pos = origin;
-        let threshold = 0.001; //
-        while calculate_distance(current_pos, destination) > threshold {
+        // Avanza de a un paso, hasta quedar a menos de un paso del destino
+        let step_length = self.dron_properties.get_flight_step_length();
+        while geo::distance(current_pos, destination) > step_length {
+            let bearing = geo::initial_bearing(current_pos, destination);
             current_pos = self
                 .current_data
-                .increment_current_position_in(dir, false)?;
+                .increment_current_position_in(bearing, step_length, false)?;
 
             // Simula el vuelo, el dron se desplaza
-            let a = 4/5; // aux
-            sleep(Duration::from_secs(a));
+            self.clock.sleep(self.dron_properties.get_flight_step_duration());
             self.logger.log(format!(
                 "   incrementada la posición actual: {:?}",
                 self.current_data.get_current_position()
@@ -508,13 +486,16 @@ impl DronLogic {
         Ok(())
     }
 
+    /// Agrega el incidente al hashmap de distancias, si no estaba. Si ya estaba, se conservan
+    /// las distancias de los drones candidatos recibidas hasta el momento.
     fn add_incident_to_hashmap(&self, inc: &Incident) -> Result<(), Error> {
         if let Ok(mut distances) = self.drone_distances_by_incident.lock() {
-            distances.insert(inc.get_info(), (inc.get_position(), Vec::new()));
+            distances
+                .entry(inc.get_info())
+                .or_insert_with(|| (inc.get_position(), Vec::new()));
             return Ok(());
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de drone_distances_by_incident.",
         ))
     }
@@ -524,8 +505,7 @@ impl DronLogic {
             distances.remove(&inc.get_info());
             return Ok(());
         }
-        Err(Error::new(
-            ErrorKind::Other,
+        Err(Error::other(
             "Error al tomar lock de drone_distances_by_incident.",
         ))
     }
@@ -540,3 +520,67 @@ impl DronLogic {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashMap,
+        sync::{mpsc, Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use super::DronLogic;
+    use crate::{
+        apps::{
+            geo,
+            sist_dron::{
+                data::Data, dron_current_info::DronCurrentInfo, dron_state::DronState,
+                sist_dron_properties::SistDronProperties,
+            },
+        },
+        logging::string_logger::StringLogger,
+        time::simulated_clock::SimulatedClock,
+    };
+
+    #[test]
+    fn test_1_el_vuelo_tarda_segun_la_velocidad_en_el_reloj_simulado() {
+        let origin = (-34.6090, -58.3873);
+        let properties = SistDronProperties::new("src/apps/sist_dron/sistema_dron.properties").unwrap();
+        let clock = Arc::new(SimulatedClock::new());
+        let (ci_tx, ci_rx) = mpsc::channel();
+        let (logger_tx, _logger_rx) = mpsc::channel();
+        let mut logic = DronLogic::new(
+            Data::new(DronCurrentInfo::new(1, origin.0, origin.1, 100, DronState::RespondingToIncident)),
+            properties,
+            StringLogger::new(logger_tx),
+            Arc::new(Mutex::new(HashMap::new())),
+            ci_tx,
+            clock.clone(),
+        );
+
+        // Un vuelo de 1000 metros
+        let destination = geo::destination_point(origin, 45.0, 1000.0);
+        let flight = thread::spawn(move || logic.fly_to(destination));
+
+        // Cada paso espera su duración en el reloj simulado, y recién entonces publica la nueva posición
+        let step = properties.get_flight_step_duration();
+        let mut steps = 0;
+        while clock.wait_for_sleeping_threads(1, Duration::from_millis(500)) {
+            clock.advance(step);
+            let ci = ci_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert_eq!(ci.get_state(), DronState::Flying);
+            steps += 1;
+        }
+        flight.join().unwrap().unwrap();
+        let ci = ci_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(ci.get_state(), DronState::ManagingIncident);
+        assert_eq!(ci.get_current_position(), destination);
+
+        // A la velocidad configurada, se tarda lo que se tarda en recorrer los 1000 metros, salvo el último paso (parcial)
+        let flight_seconds = 1000.0 / (properties.get_speed() / 3.6);
+        let elapsed = clock.elapsed().as_secs_f64();
+        assert_eq!(clock.elapsed(), step * steps);
+        assert!(elapsed <= flight_seconds && elapsed > flight_seconds - step.as_secs_f64());
+    }
+}
@@ -5,15 +5,15 @@ use rustx::apps::{
     sist_dron::{dron::Dron, utils::get_id_lat_long_and_broker_address},
 };
 use rustx::logging::string_logger::StringLogger;
-use rustx::mqtt::client::mqtt_client::MQTTClient;
+use rustx::mqtt::client::{mqtt_client::MQTTClient, reconnect_policy::ReconnectPolicy};
 use rustx::mqtt::mqtt_utils::will_message_utils::will_message::WillMessageData;
 use rustx::mqtt::mqtt_utils::will_message_utils::{app_type::AppType, will_content::WillContent};
 
-fn get_formatted_app_id(id: u8) -> String {
+fn get_formatted_app_id(id: u32) -> String {
     format!("dron-{}", id)
 }
 
-fn get_app_will_msg_content(id: u8) -> WillContent {
+fn get_app_will_msg_content(id: u32) -> WillContent {
     WillContent::new(AppType::Dron, Some(id))
 }
 
@@ -29,14 +29,14 @@ fn main() -> Result<(), Error> {
     let will_msg_content = get_app_will_msg_content(id);
     let will_msg_data = WillMessageData::new(will_msg_content.to_str(), get_app_will_topic(), qos, 1);
     
-    match MQTTClient::mqtt_connect_to_broker(client_id, &broker_addr, Some(will_msg_data), logger.clone_ref()) {
-        Ok((mqtt_client, publish_msg_rx, handle)) => {            
+    match MQTTClient::mqtt_connect_to_broker_with_reconnect(client_id, &broker_addr, Some(will_msg_data), ReconnectPolicy::default(), logger.clone_ref()) {
+        Ok((mqtt_client, _publish_msg_rx, _connection_event_rx, handle)) => {
             println!("Conectado al broker MQTT.");
             logger.log("Conectado al broker MQTT".to_string());
 
             let mut dron = Dron::new(id, lat, lon, logger.clone_ref())?;
 
-            let mut handles = dron.spawn_threads(mqtt_client, publish_msg_rx)?;
+            let mut handles = dron.spawn_threads(mqtt_client)?;
             handles.push(handle);
             join_all_threads(handles);
         }
@@ -1,6 +1,8 @@
 use std::io::{Error, ErrorKind};
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum DronState {
     ExpectingToRecvIncident,
     RespondingToIncident, // analizando si se va a mover (se evalúa la condición de los dos más cercanos)
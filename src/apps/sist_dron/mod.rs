@@ -1,5 +1,4 @@
 pub mod battery_manager;
-pub mod calculations;
 pub mod data;
 pub mod dron;
 pub mod dron_current_info;
@@ -1,12 +1,18 @@
-use std::io::{Error, ErrorKind};
+use std::{
+    io::{Error, ErrorKind},
+    time::Duration,
+};
 
 use super::super::properties::Properties;
 
+/// Segundos de vuelo que se simulan en cada paso del desplazamiento del dron.
+pub const FLIGHT_STEP_SECONDS: f64 = 4.0;
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct SistDronProperties {
     max_battery_lvl: u8,
     min_operational_battery_lvl: u8,
-    range: u8,
+    range: f64, // Radio, en metros, alrededor del range center, dentro del cual atiende incidentes.
     stay_at_inc_time: u8, // Tiempo a permanencer en la ubicación del incidente, desde la llegada, en segundos.
     // Range center, porque un dron se mueve, al terminar de atender incidente vuelve a este range center
     range_center_lat: f64, // Aux: #ToDo: Capaz es mejor tener una Posicion, para no tener mil f64s sueltos []
@@ -31,8 +37,7 @@ impl SistDronProperties {
                 .map_err(|_| Error::new(ErrorKind::InvalidInput, "max_battery_lvl"))?;
         } else {
             println!("No se encontró la propiedad 'max_battery_lvl");
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Falta propiedad max_battery_lvl.",
             ));
         }
@@ -44,17 +49,17 @@ impl SistDronProperties {
                 .map_err(|_| Error::new(ErrorKind::InvalidInput, "min_operational_battery_lvl"))?;
         } else {
             println!("No se encontró la propiedad 'min_operational_battery_lvl");
-            return Err(Error::new(ErrorKind::Other, "Falta propiedad sist dron."));
+            return Err(Error::other("Falta propiedad sist dron."));
         }
 
-        let range: u8;
+        let range: f64;
         if let Some(prop) = global_properties.get("range") {
             range = prop
                 .parse()
                 .map_err(|_| Error::new(ErrorKind::InvalidInput, "range"))?;
         } else {
             println!("No se encontró la propiedad 'range");
-            return Err(Error::new(ErrorKind::Other, "Falta propiedad sist dron."));
+            return Err(Error::other("Falta propiedad sist dron."));
         }
 
         let stay_at_inc_time: u8;
@@ -64,7 +69,7 @@ impl SistDronProperties {
                 .map_err(|_| Error::new(ErrorKind::InvalidInput, "stay_at_inc_time"))?;
         } else {
             println!("No se encontró la propiedad 'stay_at_inc_time");
-            return Err(Error::new(ErrorKind::Other, "Falta propiedad sist dron."));
+            return Err(Error::other("Falta propiedad sist dron."));
         }
 
         //
@@ -75,7 +80,7 @@ impl SistDronProperties {
                 .map_err(|_| Error::new(ErrorKind::InvalidInput, "range_center_lat"))?;
         } else {
             println!("No se encontró la propiedad 'range_center_lat");
-            return Err(Error::new(ErrorKind::Other, "Falta propiedad sist dron."));
+            return Err(Error::other("Falta propiedad sist dron."));
         }
 
         let range_center_lon: f64;
@@ -85,7 +90,7 @@ impl SistDronProperties {
                 .map_err(|_| Error::new(ErrorKind::InvalidInput, "range_center_lon"))?;
         } else {
             println!("No se encontró la propiedad 'range_center_lon");
-            return Err(Error::new(ErrorKind::Other, "Falta propiedad sist dron."));
+            return Err(Error::other("Falta propiedad sist dron."));
         }
 
         let mantainance_lat: f64;
@@ -95,7 +100,7 @@ impl SistDronProperties {
                 .map_err(|_| Error::new(ErrorKind::InvalidInput, "mantainance_lat"))?;
         } else {
             println!("No se encontró la propiedad 'mantainance_lat");
-            return Err(Error::new(ErrorKind::Other, "Falta propiedad sist dron."));
+            return Err(Error::other("Falta propiedad sist dron."));
         }
 
         let mantainance_lon: f64;
@@ -105,7 +110,7 @@ impl SistDronProperties {
                 .map_err(|_| Error::new(ErrorKind::InvalidInput, "mantainance_lon"))?;
         } else {
             println!("No se encontró la propiedad 'mantainance_lon");
-            return Err(Error::new(ErrorKind::Other, "Falta propiedad sist dron."));
+            return Err(Error::other("Falta propiedad sist dron."));
         }
 
         let speed: f64;
@@ -115,7 +120,14 @@ impl SistDronProperties {
                 .map_err(|_| Error::new(ErrorKind::InvalidInput, "speed"))?;
         } else {
             println!("No se encontró la propiedad 'speed");
-            return Err(Error::new(ErrorKind::Other, "Falta propiedad sist dron."));
+            return Err(Error::other("Falta propiedad sist dron."));
+        }
+        // Con velocidad nula o negativa el dron no avanzaría nunca hacia su destino.
+        if speed.is_nan() || speed <= 0.0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("La velocidad del dron debe ser positiva: {}", speed),
+            ));
         }
 
         Ok(Self {
@@ -144,9 +156,9 @@ impl SistDronProperties {
         self.min_operational_battery_lvl
     }
 
-    /// Devuelve el rango, utilizado para evaluar si atender o no incidentes
+    /// Devuelve el rango en metros, utilizado para evaluar si atender o no incidentes
     pub fn get_range(&self) -> f64 {
-        self.range as f64
+        self.range
     }
 
     /// Devuelve latitud y longitud del lugar de Mantenimiento, al que irá para recargar su batería
@@ -154,11 +166,21 @@ impl SistDronProperties {
         (self.mantainance_lat, self.mantainance_lon)
     }
 
-    /// Devuelve la velocidad de vuelo del dron
+    /// Devuelve la velocidad de vuelo del dron, en km/h
     pub fn get_speed(&self) -> f64 {
         self.speed
     }
 
+    /// Devuelve los metros que recorre el dron en cada paso de su vuelo, según su velocidad.
+    pub fn get_flight_step_length(&self) -> f64 {
+        self.speed / 3.6 * FLIGHT_STEP_SECONDS
+    }
+
+    /// Devuelve cuánto tarda el dron en recorrer cada paso de su vuelo.
+    pub fn get_flight_step_duration(&self) -> Duration {
+        Duration::from_secs_f64(FLIGHT_STEP_SECONDS)
+    }
+
     pub fn set_range_center_position(&mut self, lat_inicial: f64, lon_inicial: f64) {
         self.range_center_lat = lat_inicial;
         self.range_center_lon = lon_inicial;
@@ -167,3 +189,29 @@ impl SistDronProperties {
         self.max_battery_lvl
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{fs, io::ErrorKind};
+
+    use super::SistDronProperties;
+
+    const PROPERTIES: &str = "max_battery_lvl=100\nmin_operational_battery_lvl=20\nrange=6700\nstay_at_inc_time=200\nrange_center_lat=-34.6090\nrange_center_lon=-58.3873\nmantainance_lat=-34.6037\nmantainance_lon=-58.3816\n";
+
+    #[test]
+    fn test_1_una_velocidad_no_positiva_da_error() {
+        let path = std::env::temp_dir().join(format!("rustx_dron_speed_{}.properties", std::process::id()));
+        let path_str = path.to_str().unwrap();
+
+        fs::write(&path, format!("{}speed=10.0\n", PROPERTIES)).unwrap();
+        assert_eq!(SistDronProperties::new(path_str).unwrap().get_speed(), 10.0);
+
+        for speed in ["0", "-5.0"] {
+            fs::write(&path, format!("{}speed={}\n", PROPERTIES, speed)).unwrap();
+            let err = SistDronProperties::new(path_str).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+        }
+
+        let _ = fs::remove_file(&path);
+    }
+}
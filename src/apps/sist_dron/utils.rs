@@ -8,7 +8,7 @@ use std::{
 
 /// Lee y devuelve, de los argumentos ingresados al correr el programa,
 /// el id del dron, y la IP y el puerto del servidor al que el cliente se va a conectar.
-fn load_id_lat_long_ip_and_port() -> Result<(u8, f64, f64, String, u16), Error> {
+fn load_id_lat_long_ip_and_port() -> Result<(u32, f64, f64, String, u16), Error> {
     let argv = std::env::args().collect::<Vec<String>>();
     if argv.len() != 6 {
         return Err(Error::new(
@@ -18,7 +18,7 @@ fn load_id_lat_long_ip_and_port() -> Result<(u8, f64, f64, String, u16), Error>
     }
 
     let id = argv[1]
-        .parse::<u8>()
+        .parse::<u32>()
         .map_err(|_| Error::new(ErrorKind::InvalidInput, "El id proporcionado no es válido"))?;
     let latitud = argv[2]
         .parse::<f64>()
@@ -41,7 +41,7 @@ fn load_id_lat_long_ip_and_port() -> Result<(u8, f64, f64, String, u16), Error>
 /// Construye y devuelve la broker_address necesaria para conectarse al servidor mqtt,
 /// a partir de los argumentos recibidos de id, latitud, longitud, ip y puerto.
 /// También devuelve la latitud y longitud.
-pub fn get_id_lat_long_and_broker_address() -> Result<(u8, f64, f64, SocketAddr), Error> {
+pub fn get_id_lat_long_and_broker_address() -> Result<(u32, f64, f64, SocketAddr), Error> {
     let (id, latitud, longitud, ip, puerto) = load_id_lat_long_ip_and_port()?;
     let addr: String = format!("{}:{}", ip, puerto);
     let broker_addr = addr
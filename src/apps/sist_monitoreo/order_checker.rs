@@ -1,78 +1,58 @@
-use std::{collections::HashMap, io::Error};
+use std::collections::HashMap;
 
-use crate::{
-    apps::{
-        apps_mqtt_topics::AppsMqttTopics, sist_camaras::camera::Camera,
-        sist_dron::dron_current_info::DronCurrentInfo,
-    },
-    mqtt::messages::publish_message::PublishMessage,
+use crate::apps::{
+    sist_camaras::camera::{Camera, CameraSystemId},
+    sist_dron::dron_current_info::DronCurrentInfo,
 };
 
+/// Emisor de los mensajes cuyo orden se verifica.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Sender {
+    Dron(u32),
+    Camera(CameraSystemId, u32), // Los ids de las cámaras son únicos sólo dentro de su sistema.
+}
+
 /// Componente encargado de mantener el campo relacionado con el timestamp del último mensaje recibido,
 /// y responder si un dado mensaje es o no más nuevo que el último registrado.
 #[derive(Debug)]
 pub struct OrderChecker {
-    timestamp_by_topic: HashMap<(String, u8), u128>, // ((Topic, id), timestamp)
+    timestamp_by_sender: HashMap<Sender, u128>, // (emisor, timestamp)
 }
 impl OrderChecker {
     /// Crea e inicializa un `OrderChecker`.
     pub fn new() -> Self {
         Self {
-            timestamp_by_topic: HashMap::new(),
+            timestamp_by_sender: HashMap::new(),
         }
     }
 
-    /// Verifica y devuelve si el timestamp del `publish_msg` recibido es más nuevo que el último procesado.
-    pub fn is_newest(&mut self, publish_msg: &PublishMessage) -> Result<bool, Error> {
-        let msg_topic = publish_msg.get_topic();
-        let payload = publish_msg.get_payload();
-        let recvd_timestamp = publish_msg.get_timestamp();
+    /// Verifica y devuelve si la current info del dron, publicada con `timestamp`, es más nueva que la última procesada de ese dron.
+    pub fn is_newest_dron(&mut self, current_info: &DronCurrentInfo, timestamp: u128) -> bool {
+        self.update_timestamp_if_newest(Sender::Dron(current_info.get_id()), timestamp)
+    }
 
-        match AppsMqttTopics::topic_from_str(&msg_topic)? {
-            AppsMqttTopics::DronTopic => {
-                let current_info = DronCurrentInfo::from_bytes(payload)?;
-                let id: u8 = current_info.get_id();
-                self.update_timestamp_if_newest(msg_topic, id, recvd_timestamp)
-            }
-            AppsMqttTopics::CameraTopic => {
-                let camera = Camera::from_bytes(&payload);
-                let id: u8 = camera.get_id();
-                self.update_timestamp_if_newest(msg_topic, id, recvd_timestamp)
-            }
-            _ => Ok(true),
-        }
+    /// Verifica y devuelve si la cámara, publicada con `timestamp`, es más nueva que la última procesada de esa cámara.
+    pub fn is_newest_camera(&mut self, camera: &Camera, timestamp: u128) -> bool {
+        self.update_timestamp_if_newest(Sender::Camera(camera.get_system_id(), camera.get_id()), timestamp)
     }
 
-    /// Si el timestamp recibido es más nuevo que el almacenado para ese topic y id ('emisor'), entonces actualiza el
+    /// Si el timestamp recibido es más nuevo que el almacenado para ese emisor, entonces actualiza el
     /// almacenado con el nuevo. y devuelve true. Caso contrario devuelve false.
-    fn update_timestamp_if_newest(
-        &mut self,
-        msg_topic: String,
-        id: u8,
-        rcvd_timestamp: u128,
-    ) -> Result<bool, Error> {
-        // Genera la clave a partir del topic y el id
-        let key = (msg_topic, id);
-        // Intenta obtener el último timestamp para la clave dada, o lo inserta si no existe
-        if let Some(last_timestamp) = self.timestamp_by_topic.get_mut(&key) {
+    fn update_timestamp_if_newest(&mut self, sender: Sender, rcvd_timestamp: u128) -> bool {
+        // Intenta obtener el último timestamp para el emisor dado, o lo inserta si no existe
+        if let Some(last_timestamp) = self.timestamp_by_sender.get_mut(&sender) {
             // Ya se había recibido mensajes de ese emisor
-            /*println!(
-                "Timestamp rec: {}, guardado: {}, para ID: {}",
-                rcvd_timestamp, *last_timestamp, id
-            );*/
-
             // Si el timestamp recibido es más nuevo, actualiza el valor y devuelve true
             if rcvd_timestamp > *last_timestamp {
-                println!("Se actualiza el timestamp");
                 *last_timestamp = rcvd_timestamp;
-                return Ok(true);
+                return true;
             }
-            // Si el mensaje recibido para un mismo ID es más viejo, devuelve false
-            Ok(false)
+            // Si el mensaje recibido para un mismo emisor es más viejo, devuelve false
+            false
         } else {
             // No se encontró, por lo que es el primer mensaje de ese emisor, por lo tanto es el más nuevo
-            self.timestamp_by_topic.insert(key, rcvd_timestamp);
-            Ok(true)
+            self.timestamp_by_sender.insert(sender, rcvd_timestamp);
+            true
         }
     }
 }
@@ -82,3 +62,35 @@ impl Default for OrderChecker {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::OrderChecker;
+    use crate::apps::sist_camaras::camera::Camera;
+
+    fn camera_of_system(system_id: u32, id: u32) -> Camera {
+        let mut camera = Camera::new(id, -34.6, -58.4, 250.0);
+        camera.set_system_id(system_id);
+        camera
+    }
+
+    #[test]
+    fn test_1_se_descarta_la_camara_mas_vieja_que_la_ultima_recibida() {
+        let mut order_checker = OrderChecker::new();
+        let camera = camera_of_system(1, 7);
+
+        assert!(order_checker.is_newest_camera(&camera, 20));
+        assert!(!order_checker.is_newest_camera(&camera, 10));
+        assert!(order_checker.is_newest_camera(&camera, 30));
+    }
+
+    #[test]
+    fn test_2_camaras_de_igual_id_en_distintos_sistemas_no_se_pisan() {
+        let mut order_checker = OrderChecker::new();
+
+        assert!(order_checker.is_newest_camera(&camera_of_system(1, 7), 20));
+        // La cámara 7 del sistema 2 es otra cámara, aunque llegue con un timestamp anterior.
+        assert!(order_checker.is_newest_camera(&camera_of_system(2, 7), 10));
+        assert!(!order_checker.is_newest_camera(&camera_of_system(2, 7), 5));
+    }
+}
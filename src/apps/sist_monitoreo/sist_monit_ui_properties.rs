@@ -1,4 +1,4 @@
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 
 use crate::apps::properties::Properties;
 
@@ -16,8 +16,7 @@ impl SistMonitUIProperties {
             ui_name = ui_name_prop.to_owned();
         } else {
             println!("No se encontró la propiedad 'ui_name");
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Falta propiedad sist cams ui.",
             ));
         }
@@ -27,8 +26,7 @@ impl SistMonitUIProperties {
             ui_cam_img_file = ui_cam_img_file_prop.to_owned();
         } else {
             println!("No se encontró la propiedad 'ui_cam_img_file");
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Falta propiedad sist cams ui.",
             ));
         }
@@ -38,8 +36,7 @@ impl SistMonitUIProperties {
             ui_dron_img_file = ui_dron_img_file_prop.to_owned();
         } else {
             println!("No se encontró la propiedad 'ui_dron_img_file");
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Falta propiedad sist cams ui.",
             ));
         }
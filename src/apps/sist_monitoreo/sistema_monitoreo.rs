@@ -1,10 +1,15 @@
 use std::{
+    fmt::Debug,
     io::{self, ErrorKind},
-    sync::{mpsc, Arc, Mutex},
+    sync::{mpsc, Arc, Mutex, MutexGuard},
     thread::{self, JoinHandle},
 };
 
-use crate::mqtt::{client::mqtt_client::MQTTClient, messages::publish_message::PublishMessage};
+use crate::mqtt::client::{
+    connection_event::ConnectionEvent,
+    mqtt_client::MQTTClient,
+    typed_topic::{MqttPayload, TimestampedReceiver, Topic},
+};
 use crossbeam_channel::{unbounded, Receiver as CrossbeamReceiver, Sender as CrossbeamSender};
 use std::sync::mpsc::{Receiver as MpscReceiver, Sender as MpscSender};
 
@@ -12,8 +17,14 @@ use crate::{
     apps::{
         apps_mqtt_topics::AppsMqttTopics,
         common_clients::{exit_when_asked, there_are_no_more_publish_msgs},
-        incident_data::incident::Incident,
-        sist_monitoreo::{order_checker::OrderChecker, ui_sistema_monitoreo::UISistemaMonitoreo},
+        incident_data::{
+            active_incidents::{ActiveIncidents, ActiveIncidentsReply, ActiveIncidentsRequest},
+            incident::Incident,
+        },
+        sist_monitoreo::{
+            order_checker::OrderChecker,
+            ui_sistema_monitoreo::{UIMessage, UISistemaMonitoreo},
+        },
     },
     logging::string_logger::StringLogger,
 };
@@ -25,10 +36,10 @@ use std::io::Error;
 /// sobre Cámaras, Drones, e Incidentes creados por el Sistema Cámaras, y mostrarla en una interfaz gráfica.
 #[derive(Debug)]
 pub struct SistemaMonitoreo {
-    incidents: Arc<Mutex<Vec<Incident>>>,
+    // Incidentes activos, publicados por éste o por otros sistemas. Se envían a quien los pida (ie sistema cámaras al reiniciarse).
+    incidents: Arc<Mutex<ActiveIncidents>>,
     qos: u8,
     logger: StringLogger,
-    topics: Vec<(String, u8)>,
 }
 
 fn leer_qos_desde_archivo(ruta_archivo: &str) -> Result<u8, io::Error> {
@@ -55,17 +66,10 @@ impl SistemaMonitoreo {
             leer_qos_desde_archivo("src/apps/sist_monitoreo/qos_sistema_monitoreo.properties")
                 .unwrap_or(0);
         println!("valor de QoS: {}", qos);
-        let topics = vec![
-            (AppsMqttTopics::CameraTopic.to_str().to_string(), qos),
-            (AppsMqttTopics::DronTopic.to_str().to_string(), qos),
-            (AppsMqttTopics::IncidentTopic.to_str().to_string(), qos),
-            (AppsMqttTopics::DescTopic.to_str().to_string(), qos),
-        ];
         let sistema_monitoreo: SistemaMonitoreo = Self {
-            incidents: Arc::new(Mutex::new(Vec::new())), // []
+            incidents: Arc::new(Mutex::new(ActiveIncidents::new())),
             qos,
             logger,
-            topics,
         };
 
         sistema_monitoreo
@@ -74,7 +78,7 @@ impl SistemaMonitoreo {
     /// Lanza las partes internas del sistema monitoreo y las inicializa.
     pub fn spawn_threads(
         &self,
-        publish_message_rx: MpscReceiver<PublishMessage>,
+        connection_event_rx: MpscReceiver<ConnectionEvent>,
         mqtt_client: MQTTClient,
     ) -> Vec<JoinHandle<()>> {
         let (incident_tx, incident_rx) = mpsc::channel::<Incident>();
@@ -82,7 +86,7 @@ impl SistemaMonitoreo {
 
         let mut children: Vec<JoinHandle<()>> = vec![];
         let mqtt_client_sh = Arc::new(Mutex::new(mqtt_client));
-        let (egui_tx, egui_rx) = unbounded::<PublishMessage>();
+        let (egui_tx, egui_rx) = unbounded::<UIMessage>();
 
         // Exit, cuando ui lo solicite
         children.push(self.spawn_exit_thread(mqtt_client_sh.clone(), exit_rx));
@@ -91,14 +95,16 @@ impl SistemaMonitoreo {
         children.push(self.spawn_publish_incs_thread(mqtt_client_sh.clone(), incident_rx));
 
         // Recibe msgs por MQTT y los envía para mostrarse en la ui
-        children.push(self.spawn_subscribe_to_topics_thread(
-            mqtt_client_sh.clone(),
-            publish_message_rx,
-            egui_tx,
-        ));
+        match self.spawn_subscribe_to_topics_threads(&mqtt_client_sh, egui_tx) {
+            Ok(handles) => children.extend(handles),
+            Err(e) => self.logger.log(format!(
+                "Error al suscribirse a los topics de MQTT: {:?}.",
+                e
+            )),
+        }
 
         // UI
-        self.spawn_ui_thread(incident_tx, egui_rx, exit_tx);
+        self.spawn_ui_thread(incident_tx, egui_rx, connection_event_rx, exit_tx);
 
         children
     }
@@ -110,7 +116,8 @@ impl SistemaMonitoreo {
     fn spawn_ui_thread(
         &self,
         incident_tx: MpscSender<Incident>,
-        publish_message_rx: CrossbeamReceiver<PublishMessage>,
+        ui_message_rx: CrossbeamReceiver<UIMessage>,
+        connection_event_rx: MpscReceiver<ConnectionEvent>,
         exit_tx: MpscSender<bool>,
     ) {
         if let Err(e) = eframe::run_native(
@@ -120,7 +127,8 @@ impl SistemaMonitoreo {
                 Box::new(UISistemaMonitoreo::new(
                     cc.egui_ctx.clone(),
                     incident_tx,
-                    publish_message_rx,
+                    ui_message_rx,
+                    connection_event_rx,
                     exit_tx,
                 ))
             }),
@@ -152,83 +160,117 @@ impl SistemaMonitoreo {
             incidents: self.incidents.clone(),
             qos: self.qos,
             logger: self.logger.clone_ref(),
-            topics: self.topics.clone(),
         }
     }
 
-    /// Se suscribe a los topics y queda recibiendo PublishMessages de esos topics.
-    /// Delega el procesamiento de cada mensaje recibido por MQTT a otra parte del Sistema Cámaras, enviándolo por un channel.
-    fn spawn_subscribe_to_topics_thread(
+    /// Se suscribe a los topics de interés, y lanza un hilo por topic que recibe sus mensajes ya decodificados.
+    /// Los que deben mostrarse en la ui se le envían por `egui_tx`.
+    fn spawn_subscribe_to_topics_threads(
         &self,
-        mqtt_client: Arc<Mutex<MQTTClient>>,
-        mqtt_rx: MpscReceiver<PublishMessage>,
-        egui_tx: CrossbeamSender<PublishMessage>,
-    ) -> JoinHandle<()> {
-        let mut self_clone = self.clone_ref();
-        thread::spawn(move || {
-            if let Err(e) = self_clone.subscribe_and_receive_msgs(&mqtt_client, mqtt_rx, egui_tx) {
-                self_clone.logger.log(format!(
-                    "Error en hilo para suscribir y recibir mensajes de MQTT: {:?}.",
-                    e
-                ));
-            }
-        })
+        mqtt_client: &Arc<Mutex<MQTTClient>>,
+        egui_tx: CrossbeamSender<UIMessage>,
+    ) -> Result<Vec<JoinHandle<()>>, Error> {
+        let cameras_rx = self.subscribe_with_timestamp(mqtt_client, &AppsMqttTopics::cameras())?;
+        let drones_rx = self.subscribe_with_timestamp(mqtt_client, &AppsMqttTopics::drones())?;
+        let incidents_rx = self.subscribe(mqtt_client, &AppsMqttTopics::incidents())?;
+        let disconnections_rx = self.subscribe(mqtt_client, &AppsMqttTopics::disconnections())?;
+        let requests_rx = self.subscribe(mqtt_client, &AppsMqttTopics::active_incidents())?;
+
+        // Cámaras y drones se envían a la ui sólo si son más nuevos que los últimos recibidos de su emisor.
+        let mut cameras_order_checker = OrderChecker::new();
+        let cameras_egui_tx = egui_tx.clone();
+        let mut drones_order_checker = OrderChecker::new();
+        let drones_egui_tx = egui_tx.clone();
+        let incidents_egui_tx = egui_tx.clone();
+        let mqtt_client = mqtt_client.clone();
+
+        Ok(vec![
+            self.spawn_receive_thread(cameras_rx, move |self_clone, (camera, timestamp)| {
+                if cameras_order_checker.is_newest_camera(&camera, timestamp) {
+                    self_clone.send_message_to_ui(UIMessage::Camera(camera), &cameras_egui_tx);
+                }
+            }),
+            self.spawn_receive_thread(drones_rx, move |self_clone, (current_info, timestamp)| {
+                if drones_order_checker.is_newest_dron(&current_info, timestamp) {
+                    self_clone.send_message_to_ui(UIMessage::Dron(current_info), &drones_egui_tx);
+                }
+            }),
+            self.spawn_receive_thread(incidents_rx, move |self_clone, incident| {
+                self_clone.update_active_incidents(&incident);
+                self_clone.send_message_to_ui(UIMessage::Incident(incident), &incidents_egui_tx);
+            }),
+            self.spawn_receive_thread(disconnections_rx, move |self_clone, will_content| {
+                self_clone.send_message_to_ui(UIMessage::Disconnection(will_content), &egui_tx);
+            }),
+            // Los pedidos de incidentes activos se responden acá, sin pasar por la ui.
+            self.spawn_receive_thread(requests_rx, move |self_clone, request| {
+                self_clone.answer_active_incidents_request(request, &mqtt_client);
+            }),
+        ])
     }
 
-    /// Se suscribe a los topics de interés y permanece escuchando mensajes recibidos de los mismos.
-    fn subscribe_and_receive_msgs(
-        &mut self,
+    /// Utiliza la librería MQTT para subscribirse al `topic`, y devuelve el rx por el que se recibirán sus mensajes ya decodificados.
+    fn subscribe<T>(
+        &self,
         mqtt_client: &Arc<Mutex<MQTTClient>>,
-        mqtt_rx: MpscReceiver<PublishMessage>,
-        egui_tx: CrossbeamSender<PublishMessage>,
-    ) -> Result<(), Error> {
-        self.subscribe_to_topics(mqtt_client)?;
-        self.logger.log(format!("Suscripto a {:?}", &self.topics));
-        self.receive_messages_from_subscribed_topics(mqtt_rx, egui_tx);
-        Ok(())
+        topic: &Topic<T>,
+    ) -> Result<MpscReceiver<Result<T, Error>>, Error>
+    where
+        T: MqttPayload + Send + 'static,
+    {
+        let rx = self.lock_mqtt_client(mqtt_client)?.mqtt_subscribe_typed(topic, self.qos)?;
+        self.logger.log(format!("Suscripto a {:?}", topic.get_name()));
+        Ok(rx)
     }
 
-    /// Utiliza la librería MQTT para subscribirse a los topics.
-    fn subscribe_to_topics(&self, mqtt_client: &Arc<Mutex<MQTTClient>>) -> Result<(), Error> {
-        if let Ok(mut mqtt_client) = mqtt_client.lock() {
-            mqtt_client.mqtt_subscribe(self.topics.clone())?;
-            Ok(())
-        } else {
-            Err(Error::new(
-                ErrorKind::Other,
-                "Error al obtener el lock del mqtt_client",
-            ))
-        }
+    /// Como `subscribe`, pero cada mensaje se recibe junto con el timestamp con el que se publicó.
+    fn subscribe_with_timestamp<T>(
+        &self,
+        mqtt_client: &Arc<Mutex<MQTTClient>>,
+        topic: &Topic<T>,
+    ) -> Result<TimestampedReceiver<T>, Error>
+    where
+        T: MqttPayload + Send + 'static,
+    {
+        let rx = self
+            .lock_mqtt_client(mqtt_client)?
+            .mqtt_subscribe_typed_with_timestamp(topic, self.qos)?;
+        self.logger.log(format!("Suscripto a {:?}", topic.get_name()));
+        Ok(rx)
     }
 
-    /// Si el mensaje publish recibido por MQTT es más nuevo que el último procesado, entonces
-    /// envía a otra parte del sistema de monitoreo, para ser procesado.
-    fn receive_messages_from_subscribed_topics(
-        &mut self,
-        mqtt_rx: MpscReceiver<PublishMessage>,
-        egui_tx: CrossbeamSender<PublishMessage>,
-    ) {
-        let mut time_order_checker = OrderChecker::new();
-
-        for pub_msg in mqtt_rx {
-            self.logger.log(format!("Publish recibido: {:?}", pub_msg));
-            // Chequeo el timestamp del publish_msg, si es nuevo, lo mando a la ui
-            // Uso un match, no quiero retornar si fue error xq cortaría el loop, solo lo loggueo
-            match time_order_checker.is_newest(&pub_msg) {
-                Ok(true) => self.send_publish_message_to_ui(pub_msg, egui_tx.clone()),
-                Ok(false) => {}, // No se lo procesa porque no es el más nuevo
-                Err(e) => self.logger.log(format!("Error en OrderChecker: {:?}", e)),                
-            }
-        }
+    fn lock_mqtt_client<'a>(
+        &self,
+        mqtt_client: &'a Arc<Mutex<MQTTClient>>,
+    ) -> Result<MutexGuard<'a, MQTTClient>, Error> {
+        mqtt_client
+            .lock()
+            .map_err(|_| Error::other("Error al obtener el lock del mqtt_client"))
+    }
 
-        there_are_no_more_publish_msgs(&self.logger);
+    /// Hilo que procesa con `process` cada mensaje recibido por `rx`, hasta que ya no haya más.
+    /// Los que no pudieron decodificarse sólo se logguean, para no cortar el loop.
+    fn spawn_receive_thread<T, F>(&self, rx: MpscReceiver<Result<T, Error>>, mut process: F) -> JoinHandle<()>
+    where
+        T: Debug + Send + 'static,
+        F: FnMut(&Self, T) + Send + 'static,
+    {
+        let self_clone = self.clone_ref();
+        thread::spawn(move || {
+            for decoded in rx {
+                match decoded {
+                    Ok(value) => {
+                        self_clone.logger.log(format!("Publish recibido: {:?}", value));
+                        process(&self_clone, value);
+                    }
+                    Err(e) => self_clone.logger.log(format!("Error al decodificar mensaje recibido: {:?}", e)),
+                }
+            }
+            there_are_no_more_publish_msgs(&self_clone.logger);
+        })
     }
 
-    fn send_publish_message_to_ui(
-        &self,
-        msg: PublishMessage,
-        egui_tx: CrossbeamSender<PublishMessage>,
-    ) {
+    fn send_message_to_ui(&self, msg: UIMessage, egui_tx: &CrossbeamSender<UIMessage>) {
         let res_send = egui_tx.send(msg);
         match res_send {
             Ok(_) => println!("Enviado mensaje a la UI"),
@@ -247,16 +289,61 @@ impl SistemaMonitoreo {
         })
     }
 
+    /// Registra el `incident` recibido o publicado en los incidentes activos.
+    fn update_active_incidents(&self, incident: &Incident) {
+        match self.incidents.lock() {
+            Ok(mut incidents) => incidents.update(incident),
+            Err(_) => self
+                .logger
+                .log("Error al tomar lock de los incidentes activos.".to_string()),
+        }
+    }
+
+    /// Responde el pedido de incidentes activos `request`, publicándolos en su reply topic.
+    fn answer_active_incidents_request(
+        &self,
+        request: ActiveIncidentsRequest,
+        mqtt_client: &Arc<Mutex<MQTTClient>>,
+    ) {
+        if request.get_reply_topic().is_empty() {
+            self.logger
+                .log(format!("Pedido de incidentes activos sin reply topic, se descarta: {:?}", request));
+            return;
+        }
+
+        let incidents = match self.incidents.lock() {
+            Ok(incidents) => incidents.to_vec(),
+            Err(_) => {
+                self.logger
+                    .log("Error al tomar lock de los incidentes activos.".to_string());
+                return;
+            }
+        };
+        let reply = ActiveIncidentsReply::new(request.get_correlation_id(), incidents);
+        let reply_topic = Topic::<ActiveIncidentsReply>::new(request.get_reply_topic());
+        if let Ok(mut mqtt_client) = mqtt_client.lock() {
+            match mqtt_client.mqtt_publish_typed(&reply_topic, &reply, self.get_qos()) {
+                Ok(publish_msg) => self
+                    .logger
+                    .log(format!("Enviados incidentes activos: {:?}", publish_msg)),
+                Err(e) => self
+                    .logger
+                    .log(format!("Error al enviar incidentes activos: {:?}", e)),
+            }
+        }
+    }
+
     /// Utiliza la librería MQTT para publicar el `incident` al topic de incidentes.
     fn publish_incident(&self, incident: Incident, mqtt_client: &Arc<Mutex<MQTTClient>>) {
         println!("Publicando incidente...");
         self.logger.log("Publicando incidente...".to_string());
+        self.update_active_incidents(&incident);
 
         // Hago el publish
         if let Ok(mut mqtt_client) = mqtt_client.lock() {
-            let res_publish = mqtt_client.mqtt_publish(
-                AppsMqttTopics::IncidentTopic.to_str(),
-                &incident.to_bytes(),
+            let res_publish = mqtt_client.mqtt_publish_typed(
+                &AppsMqttTopics::incidents(),
+                &incident,
                 self.get_qos(),
             );
             match res_publish {
@@ -5,7 +5,7 @@ use rustx::apps::{
     sist_monitoreo::sistema_monitoreo::SistemaMonitoreo,
 };
 use rustx::logging::string_logger::StringLogger;
-use rustx::mqtt::client::mqtt_client::MQTTClient;
+use rustx::mqtt::client::{mqtt_client::MQTTClient, reconnect_policy::ReconnectPolicy};
 
 fn get_formatted_app_id() -> String {
     String::from("Sistema-Monitoreo")
@@ -19,12 +19,12 @@ fn main() -> Result<(), Error> {
 
     let client_id = get_formatted_app_id();
     let sistema_monitoreo = SistemaMonitoreo::new(logger.clone_ref());
-    match MQTTClient::mqtt_connect_to_broker(client_id, &broker_addr, None, logger.clone_ref()) {
-        Ok((mqtt_client, publish_message_rx, handle)) => {
+    match MQTTClient::mqtt_connect_to_broker_with_reconnect(client_id, &broker_addr, None, ReconnectPolicy::default(), logger.clone_ref()) {
+        Ok((mqtt_client, _publish_message_rx, connection_event_rx, handle)) => {
             println!("Conectado al broker MQTT.");
             logger.log("Conectado al broker MQTT".to_string());
 
-            let mut handles = sistema_monitoreo.spawn_threads(publish_message_rx, mqtt_client);
+            let mut handles = sistema_monitoreo.spawn_threads(connection_event_rx, mqtt_client);
 
             handles.push(handle);
             join_all_threads(handles);
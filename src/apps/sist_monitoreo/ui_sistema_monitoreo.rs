@@ -1,8 +1,7 @@
 use std::collections::HashMap;
-use std::str::{from_utf8, Utf8Error};
+use std::str::Utf8Error;
 use std::time::{Duration, Instant};
 
-use crate::apps::apps_mqtt_topics::AppsMqttTopics;
 use crate::apps::incident_data::incident_state::IncidentState;
 use crate::apps::incident_data::{
     incident::Incident, incident_info::IncidentInfo, incident_source::IncidentSource,
@@ -11,11 +10,12 @@ use crate::apps::place_type::PlaceType;
 use crate::apps::sist_camaras::camera_state::CameraState;
 use crate::apps::sist_dron::dron_current_info::DronCurrentInfo;
 use crate::apps::sist_dron::dron_state::DronState;
-use crate::mqtt::messages::publish_message::PublishMessage;
+use crate::mqtt::client::connection_event::ConnectionEvent;
 
-use crate::apps::sist_camaras::camera::Camera;
+use crate::apps::sist_camaras::camera::{Camera, DEFAULT_CAMERA_SYSTEM_ID};
 use crate::apps::vendor::{
-    HttpOptions, Map, MapMemory, Place, Places, Position, Style, Tiles, TilesManager,
+    HttpOptions, Map, MapMemory, Place, PlaceCoverage, Places, Position, Style, Tiles,
+    TilesManager,
 };
 use crate::apps::{places, plugins::ImagesPluginData};
 use crate::mqtt::mqtt_utils::will_message_utils::app_type::AppType;
@@ -23,7 +23,16 @@ use crate::mqtt::mqtt_utils::will_message_utils::will_content::WillContent;
 use crossbeam_channel::{unbounded, Receiver as CrossbeamReceiver, Sender as CrossbeamSender};
 use egui::Color32;
 use egui::Context;
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{Receiver, Sender};
+
+/// Mensaje recibido por MQTT, ya decodificado, que se muestra en la ui.
+#[derive(Debug)]
+pub enum UIMessage {
+    Camera(Camera),
+    Dron(DronCurrentInfo),
+    Incident(Incident),
+    Disconnection(WillContent),
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Provider {
@@ -125,9 +134,10 @@ pub struct UISistemaMonitoreo {
     latitude: String,
     longitude: String,
     publish_incident_tx: Sender<Incident>,
-    publish_message_rx: CrossbeamReceiver<PublishMessage>,
+    ui_message_rx: CrossbeamReceiver<UIMessage>,
+    connection_event_rx: Receiver<ConnectionEvent>,
+    broker_status: Option<String>, // None si hay conexión con el broker.
     places: Places,
-    last_incident_id: u8,
     exit_tx: Sender<bool>,
     incidents_to_resolve: Vec<IncidentWithDrones>, // posicion 0  --> (inc_id_to_resolve, drones(dron1, dron2)) // posicion 1 --> (inc_id_to_resolve 2, drones(dron1, dron2))
     hashmap_incidents: HashMap<IncidentInfo, Incident>, //
@@ -141,7 +151,8 @@ impl UISistemaMonitoreo {
     pub fn new(
         egui_ctx: Context,
         tx: Sender<Incident>,
-        publish_message_rx: CrossbeamReceiver<PublishMessage>,
+        ui_message_rx: CrossbeamReceiver<UIMessage>,
+        connection_event_rx: Receiver<ConnectionEvent>,
         exit_tx: Sender<bool>,
     ) -> Self {
         egui_extras::install_image_loaders(&egui_ctx);
@@ -160,9 +171,10 @@ impl UISistemaMonitoreo {
             latitude: String::new(),
             longitude: String::new(),
             publish_incident_tx: tx,
-            publish_message_rx,
+            ui_message_rx,
+            connection_event_rx,
+            broker_status: None,
             places,
-            last_incident_id: 0,
             exit_tx,
             incidents_to_resolve: Vec::new(),
             hashmap_incidents: HashMap::new(),
@@ -196,6 +208,7 @@ impl UISistemaMonitoreo {
             style,
             id: 0,
             place_type: PlaceType::Mantainance,
+            coverage: None,
         }
     }
 
@@ -212,145 +225,173 @@ impl UISistemaMonitoreo {
                 ..Default::default()
             },
             CameraState::SavingMode => Style::default(),
+            // Degradadas: gris si no produce imágenes, rojo si falla
+            CameraState::Offline => Style {
+                symbol_color: Color32::from_gray(120),
+                symbol_background: Color32::from_gray(60),
+                ..Default::default()
+            },
+            CameraState::Fault => Style {
+                symbol_color: Color32::from_rgb(255, 0, 0), // Color rojo
+                label_color: Color32::from_rgb(255, 120, 120),
+                ..Default::default()
+            },
         }
     }
 
     fn create_camera_place(camera: &Camera, style: Style) -> Place {
         let camera_id = camera.get_id();
+        let system_id = camera.get_system_id();
         let (latitude, longitude) = (camera.get_latitude(), camera.get_longitude());
+        let state = camera.get_state();
+
+        // Las cámaras de otros sistemas que el por defecto se muestran con el id de su sistema
+        let name = if system_id == DEFAULT_CAMERA_SYSTEM_ID {
+            format!("Camera {}", camera_id)
+        } else {
+            format!("Camera {}-{}", system_id, camera_id)
+        };
+        // Una cámara degradada no ve: se indica su estado, y no se dibuja su campo de visión
+        let (label, coverage) = if state.is_degraded() {
+            (format!("{} ({:?})", name, state), None)
+        } else {
+            let coverage = PlaceCoverage {
+                radius: camera.get_coverage_distance(),
+                heading: camera.get_fov().get_heading(),
+                angle: camera.get_fov().get_angle(),
+            };
+            (name, Some(coverage))
+        };
 
         Place {
             position: Position::from_lon_lat(longitude, latitude),
-            label: format!("Camera {}", camera_id),
+            label,
             symbol: '📷',
             style,
-            id: camera_id,
-            place_type: PlaceType::Camera,
+            id: camera_id.into(),
+            place_type: PlaceType::Camera(system_id),
+            coverage,
         }
     }
 
     fn update_camera_on_map(&mut self, camera: Camera) {
         let camera_id = camera.get_id();
+        let place_type = PlaceType::Camera(camera.get_system_id());
 
         if camera.is_not_deleted() {
-            self.places.remove_place(camera_id, PlaceType::Camera);
+            self.places.remove_place(camera_id.into(), place_type);
 
             let style = Self::create_camera_style(camera.get_state());
             let camera_ui = Self::create_camera_place(&camera, style);
             self.places.add_place(camera_ui);
         } else {
-            self.places.remove_place(camera_id, PlaceType::Camera);
+            self.places.remove_place(camera_id.into(), place_type);
         }
     }
 
     /// Se encarga de procesar y agregar o eliminar una cámara recibida al mapa.
-    fn handle_camera_message(&mut self, publish_message: PublishMessage) {
-        let camera = Camera::from_bytes(&publish_message.get_payload());
+    fn handle_camera_message(&mut self, camera: Camera) {
         println!(
             "UI: recibida cámara: {:?}, estado: {:?}",
             camera,
             camera.get_state()
         );
-
         self.update_camera_on_map(camera);
     }
 
     /// Se encarga de procesar y agregar un dron recibido al mapa.
-    fn handle_drone_message(&mut self, msg: PublishMessage) {
-        if let Ok(dron) = DronCurrentInfo::from_bytes(msg.get_payload()) {
-            /*println!(
-                "UI: recibido dron: {:?}, estado: {:?}",
-                dron,
-                dron.get_state()
-            );*/
-            // Si ya existía el dron, se lo elimina, porque que me llegue nuevamente significa que se está moviendo.
-            let dron_id = dron.get_id();
-            self.places.remove_place(dron_id, PlaceType::Dron);
-
-            if dron.get_state() == DronState::ManagingIncident {
-                // Llegó a la posición del inc.
-                if let Some(inc_info) = dron.get_inc_id_to_resolve() {
-                    // Busca el incidente en el vector.
-                    let incident_index = self
-                        .incidents_to_resolve
-                        .iter()
-                        .position(|incident| incident.incident_info == inc_info);
-                    //.position(|incident| incident.incident_info.get_inc_id() == inc_id); // <--pre refactor decía esto
-
-                    match incident_index {
-                        Some(index) => {
-                            // Si el incidente ya existe, agrega el dron al vector de drones del incidente.
-                            self.incidents_to_resolve[index].drones.push(dron.clone());
-                        }
-                        None => {
-                            // Si no tengo guardado el inc_id_to_res, crea una nueva posicion con el dron respectivo.
-                            self.incidents_to_resolve.push(IncidentWithDrones {
-                                incident_info: inc_info,
-                                drones: vec![dron.clone()],
-                            });
-                        }
+    fn handle_drone_message(&mut self, dron: DronCurrentInfo) {
+        /*println!(
+            "UI: recibido dron: {:?}, estado: {:?}",
+            dron,
+            dron.get_state()
+        );*/
+        // Si ya existía el dron, se lo elimina, porque que me llegue nuevamente significa que se está moviendo.
+        let dron_id = dron.get_id();
+        self.places.remove_place(dron_id.into(), PlaceType::Dron);
+
+        if dron.get_state() == DronState::ManagingIncident {
+            // Llegó a la posición del inc.
+            if let Some(inc_info) = dron.get_inc_id_to_resolve() {
+                // Busca el incidente en el vector.
+                let incident_index = self
+                    .incidents_to_resolve
+                    .iter()
+                    .position(|incident| incident.incident_info == inc_info);
+                //.position(|incident| incident.incident_info.get_inc_id() == inc_id); // <--pre refactor decía esto
+
+                match incident_index {
+                    Some(index) => {
+                        // Si el incidente ya existe, agrega el dron al vector de drones del incidente.
+                        self.incidents_to_resolve[index].drones.push(dron.clone());
+                    }
+                    None => {
+                        // Si no tengo guardado el inc_id_to_res, crea una nueva posicion con el dron respectivo.
+                        self.incidents_to_resolve.push(IncidentWithDrones {
+                            incident_info: inc_info,
+                            drones: vec![dron.clone()],
+                        });
                     }
                 }
             }
+        }
 
-            for incident in self.incidents_to_resolve.iter() {
-                if incident.drones.len() == 2 {
-                    let inc_info = &incident.incident_info;
-                    if let Some(mut incident) = self.hashmap_incidents.remove(inc_info) {
-                        incident.set_resolved();
-                        // Obtengo el source del incidente, para pasarle un place_type acorde al remove_place
-                        // y lo remuevo de la lista de places a mostrar en el mapa.
-                        let place_type = PlaceType::from_inc_source(incident.get_source());
-                        self.places.remove_place(inc_info.get_inc_id(), place_type);
-
-                        self.send_incident_for_publish(incident);
-                    }
+        for incident in self.incidents_to_resolve.iter() {
+            if incident.drones.len() == 2 {
+                let inc_info = &incident.incident_info;
+                if let Some(mut incident) = self.hashmap_incidents.remove(inc_info) {
+                    incident.set_resolved();
+                    // Obtengo el source del incidente, para pasarle un place_type acorde al remove_place
+                    // y lo remuevo de la lista de places a mostrar en el mapa.
+                    let place_type = PlaceType::from_inc_source(incident.get_source());
+                    self.places.remove_place(inc_info.get_inc_id().as_u128(), place_type);
+
+                    self.send_incident_for_publish(incident);
                 }
             }
+        }
 
-            // Crea lo necesario para dibujar al dron
-            let (lat, lon) = dron.get_current_position();
-            let dron_pos = Position::from_lon_lat(lon, lat);
-
-            // Se crea el label a mostrar por pantalla, según si está o no volando.
-            let dron_label;
-            if let Some((dir, speed)) = dron.get_flying_info() {
-                let (dir_lat, dir_lon) = dir;
-                // El dron está volando.
-                dron_label = format!(
-                    "Dron {}\n   dir: ({:.2}, {:.2})\n   vel: {} km/h",
-                    dron_id, dir_lat, dir_lon, speed
-                );
-            } else {
-                dron_label = format!("Dron {}", dron_id);
-            }
+        // Crea lo necesario para dibujar al dron
+        let (lat, lon) = dron.get_current_position();
+        let dron_pos = Position::from_lon_lat(lon, lat);
+
+        // Se crea el label a mostrar por pantalla, según si está o no volando.
+        let dron_label;
+        if let Some((dir, speed)) = dron.get_flying_info() {
+            let (dir_lat, dir_lon) = dir;
+            // El dron está volando.
+            dron_label = format!(
+                "Dron {}\n   dir: ({:.2}, {:.2})\n   vel: {} km/h",
+                dron_id, dir_lat, dir_lon, speed
+            );
+        } else {
+            dron_label = format!("Dron {}", dron_id);
+        }
 
-            // Se crea el place y se lo agrega al mapa.
-            let dron_ui = Place {
-                position: dron_pos,
-                label: dron_label,
-                symbol: '🚁',
-                style: Style::default(),
-                id: dron.get_id(),
-                place_type: PlaceType::Dron, // Para luego buscarlo en el places.
-            };
+        // Se crea el place y se lo agrega al mapa.
+        let dron_ui = Place {
+            position: dron_pos,
+            label: dron_label,
+            symbol: '🚁',
+            style: Style::default(),
+            id: dron.get_id().into(),
+            place_type: PlaceType::Dron, // Para luego buscarlo en el places.
+            coverage: None,
+        };
 
-            self.places.add_place(dron_ui);
-        }
+        self.places.add_place(dron_ui);
         //let _ = self.repaint_tx.send(true);
         //let _ = self.repaint_tx.send(true);
     }
 
-    /// Recibe un PublishMessage de topic Inc, y procesa el incidente recibido
+    /// Procesa el incidente recibido por el topic de incidentes
     /// (se lo guarda para continuar procesándolo, y lo muestra en la ui).
-    fn handle_incident_message(&mut self, msg: PublishMessage) {
-        if let Ok(inc) = Incident::from_bytes(msg.get_payload()) {
-            // Agregamos el incidente (add_incident) solamente si él no fue creado por sist monitoreo.
-            if *inc.get_source() == IncidentSource::Automated
-                && *inc.get_state() == IncidentState::ActiveIncident
-            {
-                self.add_incident(&inc);
-            }
+    fn handle_incident_message(&mut self, inc: Incident) {
+        // Agregamos el incidente (add_incident) solamente si él no fue creado por sist monitoreo.
+        if *inc.get_source() == IncidentSource::Automated
+            && *inc.get_state() == IncidentState::ActiveIncident
+        {
+            self.add_incident(&inc);
         }
     }
 
@@ -368,11 +409,12 @@ impl UISistemaMonitoreo {
         let (lat, lon) = incident.get_position();
         Place {
             position: Position::from_lon_lat(lon, lat),
-            label: format!("Incident {}", incident.get_id()),
+            label: format!("Incident {}", incident.get_short_id()),
             symbol: '⚠',
             style: custom_style.clone(),
-            id: incident.get_id(),
+            id: incident.get_id().as_u128(),
             place_type,
+            coverage: None,
         }
     }
 
@@ -382,28 +424,10 @@ impl UISistemaMonitoreo {
         self.hashmap_incidents.insert(inc_info, inc_to_store);
     }
 
-    fn get_next_incident_id(&mut self) -> u8 {
-        self.last_incident_id += 1;
-        self.last_incident_id
-    }
-
-    fn handle_disconnection_message(
-        &mut self,
-        publish_message: PublishMessage,
-    ) -> Result<(), Utf8Error> {
-        let will_content_res = WillContent::will_content_from_string(from_utf8(&publish_message.get_payload())?);
-        
-        if let Ok(will_content) = will_content_res {
-            self.process_will_content(will_content)?;
-        }
-
-        Ok(())
-    }
-
     fn process_will_content(&mut self, will_content: WillContent) -> Result<(), Utf8Error> {
         let app_type = will_content.get_app_type_identifier();
-        let id_option = will_content.get_id(); // es un option porque solo dron tiene id en este contexto.
-        let place_type = PlaceType::from_app_type_will_content(&app_type);
+        let id_option = will_content.get_id(); // el id del dron, o el del sistema de cámaras (las versiones anteriores no lo tienen).
+        let place_type = PlaceType::from_will_content(&will_content);
 
         match app_type {
             AppType::Cameras => self.handle_camera_disconnection(place_type),
@@ -414,42 +438,33 @@ impl UISistemaMonitoreo {
     }
 
     fn handle_camera_disconnection(&mut self, place_type: PlaceType) {
-        // Se eliminan Todas las cámaras
+        // Se eliminan todas las cámaras del sistema de cámaras que se desconectó (el place type incluye su id)
         self.places.remove_places(place_type)
     }
 
-    fn handle_drone_disconnection(&mut self, id_option: Option<u8>, place_type: PlaceType) {
+    fn handle_drone_disconnection(&mut self, id_option: Option<u32>, place_type: PlaceType) {
         if let Some(id) = id_option {
             // Se elimina el dron de id indicado, porque el mismo se desconectó.
-            self.places.remove_place(id, place_type)
+            self.places.remove_place(id.into(), place_type)
         }
     }
 
     fn handle_mqtt_messages(&mut self, ctx: &egui::Context) {
         egui::CentralPanel::default().show(ctx, |_ui| {
-            if let Ok(publish_message) = self.publish_message_rx.try_recv() {
-                self.route_message(publish_message);
+            if let Ok(ui_message) = self.ui_message_rx.try_recv() {
+                self.route_message(ui_message);
             }
         });
     }
 
-    fn route_message(&mut self, publish_message: PublishMessage) {
-        let topic_str = publish_message.get_topic_name();
-        if let Ok(topic) = AppsMqttTopics::topic_from_str(&topic_str) {
-            match topic {
-                AppsMqttTopics::CameraTopic => {
-                    self.handle_camera_message(publish_message)
-                },
-                AppsMqttTopics::DronTopic => {
-                    self.handle_drone_message(publish_message)
-                },
-                AppsMqttTopics::IncidentTopic => {
-                    self.handle_incident_message(publish_message)
-                },
-                AppsMqttTopics::DescTopic => {
-                    println!("Recibido mensaje de desconexión.");
-                    let _ = self.handle_disconnection_message(publish_message);
-                },
+    fn route_message(&mut self, ui_message: UIMessage) {
+        match ui_message {
+            UIMessage::Camera(camera) => self.handle_camera_message(camera),
+            UIMessage::Dron(dron) => self.handle_drone_message(dron),
+            UIMessage::Incident(incident) => self.handle_incident_message(incident),
+            UIMessage::Disconnection(will_content) => {
+                println!("Recibido mensaje de desconexión.");
+                let _ = self.process_will_content(will_content);
             }
         }
     }
@@ -498,10 +513,34 @@ impl UISistemaMonitoreo {
             egui::menu::bar(ui, |ui| {
                 self.incident_menu(ui);
                 self.exit_menu(ui, ctx);
+                self.broker_status_label(ui);
             });
         });
     }
 
+    /// Muestra en el panel superior si no hay conexión con el broker.
+    fn broker_status_label(&self, ui: &mut egui::Ui) {
+        if let Some(status) = &self.broker_status {
+            ui.colored_label(Color32::RED, status);
+        }
+    }
+
+    /// Actualiza el estado de la conexión con el broker según los eventos recibidos del cliente mqtt.
+    fn handle_connection_events(&mut self) {
+        while let Ok(event) = self.connection_event_rx.try_recv() {
+            self.broker_status = match event {
+                ConnectionEvent::Disconnected => Some("Broker offline".to_string()),
+                ConnectionEvent::Reconnecting { attempt } => {
+                    Some(format!("Broker offline (reintento {})", attempt))
+                }
+                ConnectionEvent::Reconnected => None,
+                ConnectionEvent::GaveUp => {
+                    Some("Broker offline: se agotaron los reintentos".to_string())
+                }
+            };
+        }
+    }
+
     fn incident_menu(&mut self, ui: &mut egui::Ui) {
         ui.menu_button("Incidente", |ui| {
             if !self.incident_dialog_open && ui.button("Alta Incidente").clicked() {
@@ -555,8 +594,9 @@ impl UISistemaMonitoreo {
     }
 
     fn handle_successful_parse(&mut self, location: (f64, f64)) {
+        // El id es único aunque haya varias estaciones de monitoreo creando incidentes.
         let incident = Incident::new(
-            self.get_next_incident_id(),
+            Incident::generate_id(),
             location,
             IncidentSource::Manual,
         );
@@ -657,6 +697,7 @@ impl eframe::App for UISistemaMonitoreo {
         self.request_repaint_after(150, ctx);
         self.draw_ui_wrapper(ctx);
         self.handle_mqtt_messages(ctx);
+        self.handle_connection_events();
         self.setup_map(ctx);
         self.setup_top_menu(ctx);
         self.check_if_window_is_closed(ctx);
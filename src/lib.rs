@@ -3,3 +3,5 @@ pub mod apps;
 pub mod mqtt;
 
 pub mod logging;
+
+pub mod time;
@@ -0,0 +1,165 @@
+use std::{
+    io::{Error, ErrorKind},
+    net::SocketAddr,
+};
+
+use crate::apps::properties::Properties;
+use crate::mqtt::server::server_properties::trim_quotes;
+
+const DEFAULT_BRIDGE_CLIENT_ID: &str = "bridge";
+
+/// Propiedades del modo bridge del servidor, leídas del mismo archivo que las del servidor.
+/// El bridge se activa únicamente si está presente la propiedad `bridge_address`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BridgeProperties {
+    remote_addr: SocketAddr,
+    client_id: String,
+    topics_out: Vec<String>, // Topics locales que se reenvían al broker remoto.
+    topics_in: Vec<String>,  // Topics remotos que se reenvían al broker local.
+    out_prefix: String,      // Prefijo que se agrega al topic al reenviar hacia el remoto.
+    in_prefix: String,       // Prefijo que se agrega al topic al reenviar hacia el local.
+}
+
+impl BridgeProperties {
+    /// Lee las propiedades del bridge. Devuelve Ok(None) si no se configuró un bridge.
+    pub fn from_properties(global_properties: &Properties) -> Result<Option<Self>, Error> {
+        let remote_addr: SocketAddr;
+        if let Some(prop) = global_properties.get("bridge_address") {
+            remote_addr = trim_quotes(prop)
+                .parse()
+                .map_err(|_| Error::new(ErrorKind::InvalidInput, "bridge_address"))?;
+        } else {
+            return Ok(None);
+        }
+
+        let mut client_id = DEFAULT_BRIDGE_CLIENT_ID.to_string();
+        if let Some(prop) = global_properties.get("bridge_client_id") {
+            client_id = trim_quotes(prop);
+        }
+
+        let topics_out = parse_topic_list(global_properties.get("bridge_topics_out"));
+        let topics_in = parse_topic_list(global_properties.get("bridge_topics_in"));
+
+        let mut out_prefix = String::new();
+        if let Some(prop) = global_properties.get("bridge_out_prefix") {
+            out_prefix = trim_quotes(prop);
+        }
+
+        let mut in_prefix = String::new();
+        if let Some(prop) = global_properties.get("bridge_in_prefix") {
+            in_prefix = trim_quotes(prop);
+        }
+
+        Ok(Some(Self {
+            remote_addr,
+            client_id,
+            topics_out,
+            topics_in,
+            out_prefix,
+            in_prefix,
+        }))
+    }
+
+    /// Crea las propiedades directamente, sin leerlas de un archivo.
+    pub fn new(
+        remote_addr: SocketAddr,
+        client_id: String,
+        topics_out: Vec<String>,
+        topics_in: Vec<String>,
+        out_prefix: String,
+        in_prefix: String,
+    ) -> Self {
+        Self {
+            remote_addr,
+            client_id,
+            topics_out,
+            topics_in,
+            out_prefix,
+            in_prefix,
+        }
+    }
+
+    /// Devuelve la dirección del broker remoto.
+    pub fn get_remote_addr(&self) -> SocketAddr {
+        self.remote_addr
+    }
+
+    /// Devuelve el client_id con el que el bridge se conecta al broker remoto.
+    pub fn get_client_id(&self) -> String {
+        self.client_id.to_string()
+    }
+
+    /// Devuelve los topics locales que se reenvían al remoto.
+    pub fn get_topics_out(&self) -> &Vec<String> {
+        &self.topics_out
+    }
+
+    /// Devuelve los topics remotos que se reenvían al local.
+    pub fn get_topics_in(&self) -> &Vec<String> {
+        &self.topics_in
+    }
+
+    /// Devuelve el topic con el que se publica en el remoto un mensaje del topic local `topic`.
+    pub fn map_out_topic(&self, topic: &str) -> String {
+        format!("{}{}", self.out_prefix, topic)
+    }
+
+    /// Devuelve el topic con el que se publica en el local un mensaje del topic remoto `topic`.
+    pub fn map_in_topic(&self, topic: &str) -> String {
+        format!("{}{}", self.in_prefix, topic)
+    }
+}
+
+/// Lee una lista de topics separados por coma (ie `cam,inc`).
+fn parse_topic_list(prop: Option<&String>) -> Vec<String> {
+    match prop {
+        Some(value) => trim_quotes(value)
+            .split(',')
+            .map(|topic| topic.trim().to_string())
+            .filter(|topic| !topic.is_empty())
+            .collect(),
+        None => vec![],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::apps::properties::Properties;
+
+    use super::BridgeProperties;
+
+    #[test]
+    fn test_1_sin_bridge_address_no_hay_bridge() {
+        let path = "test_bridge_properties_1.properties";
+        fs::write(path, "ip=\"127.0.0.1\"\nport=\"9090\"\n").unwrap();
+        let props = Properties::new(path);
+        let _ = fs::remove_file(path);
+
+        let bridge = BridgeProperties::from_properties(&props.unwrap()).unwrap();
+        assert!(bridge.is_none());
+    }
+
+    #[test]
+    fn test_2_se_leen_topics_y_prefijos() {
+        let path = "test_bridge_properties_2.properties";
+        fs::write(
+            path,
+            "bridge_address=\"127.0.0.1:9091\"\nbridge_topics_out=\"cam, inc\"\nbridge_out_prefix=\"distrito1/\"\n",
+        )
+        .unwrap();
+        let props = Properties::new(path);
+        let _ = fs::remove_file(path);
+
+        let bridge = BridgeProperties::from_properties(&props.unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(bridge.get_remote_addr().port(), 9091);
+        assert_eq!(bridge.get_client_id(), "bridge");
+        assert_eq!(bridge.get_topics_out(), &vec!["cam".to_string(), "inc".to_string()]);
+        assert!(bridge.get_topics_in().is_empty());
+        assert_eq!(bridge.map_out_topic("cam"), "distrito1/cam");
+        assert_eq!(bridge.map_in_topic("inc"), "inc");
+    }
+}
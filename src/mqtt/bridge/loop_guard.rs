@@ -0,0 +1,81 @@
+use crate::mqtt::messages::{mqtt_properties::MqttProperties, publish_message::PublishMessage};
+
+/// User property con la que el bridge marca los mensajes que reenvía; su valor es el client id del bridge.
+pub const BRIDGE_USER_PROPERTY: &str = "bridge";
+
+/// Extremo del bridge en el que se publica o del que se recibe un mensaje.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BridgeSide {
+    Local,
+    Remote,
+}
+
+/// Evita que un mensaje reenviado por el bridge vuelva a reenviarse en sentido contrario.
+/// Cada mensaje que el bridge reenvía lleva una user property con su id; cuando el bridge recibe uno
+/// con esa marca, se trata de un eco y se descarta. Los demás se reenvían aunque repitan topic y payload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoopGuard {
+    bridge_id: String,
+}
+
+impl LoopGuard {
+    pub fn new(bridge_id: &str) -> Self {
+        Self {
+            bridge_id: bridge_id.to_string(),
+        }
+    }
+
+    /// Devuelve las propiedades con las que reenviar `msg`: sus user properties, más la marca del bridge.
+    pub fn mark(&self, msg: &PublishMessage) -> MqttProperties {
+        let mut properties = MqttProperties::new();
+        if let Some(msg_properties) = msg.get_properties() {
+            properties.user_properties = msg_properties.user_properties.clone();
+        }
+        properties.add_user_property(BRIDGE_USER_PROPERTY, &self.bridge_id);
+        properties
+    }
+
+    /// Devuelve si el mensaje recibido fue reenviado por este mismo bridge. Los marcados por otros bridges
+    /// no son ecos, y se reenvían.
+    pub fn is_echo(&self, msg: &PublishMessage) -> bool {
+        msg.get_properties().is_some_and(|properties| {
+            properties
+                .user_properties
+                .iter()
+                .any(|(name, value)| name == BRIDGE_USER_PROPERTY && *value == self.bridge_id)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LoopGuard;
+    use crate::mqtt::messages::{
+        mqtt_properties::MqttProperties, publish_flags::PublishFlags, publish_message::PublishMessage,
+    };
+
+    fn publish_with(properties: MqttProperties) -> PublishMessage {
+        let mut msg = PublishMessage::new(PublishFlags::new(0, 1, 0).unwrap(), "inc", Some(1), &[1, 2, 3]).unwrap();
+        msg.set_mqtt5_properties(properties);
+        msg
+    }
+
+    #[test]
+    fn test_1_solo_es_eco_un_mensaje_marcado_por_el_mismo_bridge() {
+        let guard = LoopGuard::new("bridge-1");
+        let mut original_properties = MqttProperties::new();
+        original_properties.add_user_property("origen", "sistema_camaras");
+        let original = publish_with(original_properties);
+
+        // Un mensaje sin marca no es eco, aunque repita topic y payload de uno reenviado.
+        assert!(!guard.is_echo(&original));
+
+        let forwarded = publish_with(guard.mark(&original));
+        assert!(guard.is_echo(&forwarded));
+        let properties = forwarded.get_properties().unwrap();
+        assert_eq!(properties.get_user_property("origen").unwrap(), "sistema_camaras");
+
+        // El reenviado por otro bridge sí se reenvía.
+        assert!(!LoopGuard::new("bridge-2").is_echo(&forwarded));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod bridge_properties;
+pub mod loop_guard;
+pub mod mqtt_bridge;
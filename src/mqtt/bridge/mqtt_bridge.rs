@@ -0,0 +1,369 @@
+use std::{
+    io::Error,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::logging::string_logger::StringLogger;
+use crate::mqtt::client::mqtt_client::MQTTClient;
+use crate::mqtt::messages::{mqtt_properties::MqttProperties, publish_message::PublishMessage};
+
+use super::{
+    bridge_properties::BridgeProperties,
+    loop_guard::{BridgeSide, LoopGuard},
+};
+
+const MIN_RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_INTERVAL: Duration = Duration::from_secs(30);
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const BRIDGE_QOS: u8 = 1;
+
+type ShareableClient = Arc<Mutex<MQTTClient>>;
+
+/// Bridge entre el broker local y un broker remoto. Se conecta a ambos como un `MQTTClient`,
+/// y reenvía los topics configurados en cada sentido, reconectándose si alguna de las conexiones se cae.
+#[derive(Debug)]
+pub struct MQTTBridge {
+    properties: BridgeProperties,
+    local_addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    session: Arc<Mutex<Option<(ShareableClient, ShareableClient)>>>, // (local, remoto) de la sesión actual.
+    logger: StringLogger,
+}
+
+impl MQTTBridge {
+    pub fn new(properties: BridgeProperties, local_addr: SocketAddr, logger: StringLogger) -> Self {
+        Self {
+            properties,
+            local_addr,
+            stopped: Arc::new(AtomicBool::new(false)),
+            session: Arc::new(Mutex::new(None)),
+            logger,
+        }
+    }
+
+    /// Lanza el hilo del bridge, que se mantiene reconectando hasta que se llame a `stop`.
+    pub fn spawn(&self) -> JoinHandle<()> {
+        let self_clone = self.clone_ref();
+        thread::spawn(move || self_clone.run())
+    }
+
+    /// Detiene el bridge, cerrando las conexiones de la sesión actual.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.disconnect_session();
+    }
+
+    fn run(&self) {
+        let mut reconnect_interval = MIN_RECONNECT_INTERVAL;
+        while !self.is_stopped() {
+            match self.run_session() {
+                // La sesión llegó a establecerse, se reinicia el intervalo de reconexión.
+                Ok(()) => reconnect_interval = MIN_RECONNECT_INTERVAL,
+                Err(e) => {
+                    self.logger.log(format!("Bridge: error en la sesión: {:?}.", e));
+                }
+            }
+            if self.is_stopped() {
+                break;
+            }
+            self.logger.log(format!("Bridge: reconectando en {:?}.", reconnect_interval));
+            self.sleep_unless_stopped(reconnect_interval);
+            reconnect_interval = (reconnect_interval * 2).min(MAX_RECONNECT_INTERVAL);
+        }
+        self.logger.log("Bridge: detenido.".to_string());
+    }
+
+    /// Se conecta a ambos brokers, y reenvía mensajes hasta que alguna de las dos conexiones se cierre.
+    /// Las conexiones son MQTT 5, para poder marcar los mensajes reenviados (ver `LoopGuard`).
+    fn run_session(&self) -> Result<(), Error> {
+        let local_client_id = format!("{}-local", self.properties.get_client_id());
+        let (local, local_rx, local_handle) = MQTTClient::mqtt_connect_to_broker_v5(
+            local_client_id,
+            &self.local_addr,
+            None,
+            MqttProperties::new(),
+            self.logger.clone_ref(),
+        )?;
+        let local = Arc::new(Mutex::new(local));
+
+        let remote_res = MQTTClient::mqtt_connect_to_broker_v5(
+            self.properties.get_client_id(),
+            &self.properties.get_remote_addr(),
+            None,
+            MqttProperties::new(),
+            self.logger.clone_ref(),
+        );
+        let (remote, remote_rx, remote_handle) = match remote_res {
+            Ok(remote_conn) => remote_conn,
+            Err(e) => {
+                disconnect(&local);
+                let _ = local_handle.join();
+                return Err(e);
+            }
+        };
+        let remote = Arc::new(Mutex::new(remote));
+
+        if let Ok(mut session) = self.session.lock() {
+            *session = Some((local.clone(), remote.clone()));
+        }
+        // Por si se llamó a stop mientras se establecía la sesión.
+        if self.is_stopped() {
+            self.disconnect_session();
+        }
+
+        self.logger.log("Bridge: conectado a ambos brokers.".to_string());
+        let handles = self.forward_until_disconnection(&local, local_rx, &remote, remote_rx);
+
+        self.disconnect_session();
+        for h in handles.into_iter().chain([local_handle, remote_handle]) {
+            if h.join().is_err() {
+                self.logger.log("Bridge: error al esperar hilo.".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Se suscribe en cada extremo y lanza un hilo de reenvío por sentido.
+    /// Retorna cuando alguno de los dos hilos termina.
+    fn forward_until_disconnection(
+        &self,
+        local: &ShareableClient,
+        local_rx: Receiver<PublishMessage>,
+        remote: &ShareableClient,
+        remote_rx: Receiver<PublishMessage>,
+    ) -> Vec<JoinHandle<()>> {
+        subscribe(local, self.properties.get_topics_out(), &self.logger);
+        subscribe(remote, self.properties.get_topics_in(), &self.logger);
+
+        let guard = LoopGuard::new(&self.properties.get_client_id());
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+
+        let out_link = ForwardLink {
+            from: BridgeSide::Local,
+            to_client: remote.clone(),
+            guard: guard.clone(),
+            properties: self.properties.clone(),
+            logger: self.logger.clone_ref(),
+        };
+        let in_link = ForwardLink {
+            from: BridgeSide::Remote,
+            to_client: local.clone(),
+            guard,
+            properties: self.properties.clone(),
+            logger: self.logger.clone_ref(),
+        };
+
+        let handles = vec![
+            out_link.spawn(local_rx, done_tx.clone()),
+            in_link.spawn(remote_rx, done_tx),
+        ];
+
+        // Basta con que termine un sentido para dar por terminada la sesión.
+        let _ = done_rx.recv();
+        handles
+    }
+
+    fn disconnect_session(&self) {
+        if let Ok(mut session) = self.session.lock() {
+            if let Some((local, remote)) = session.take() {
+                disconnect(&local);
+                disconnect(&remote);
+            }
+        }
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    fn sleep_unless_stopped(&self, interval: Duration) {
+        let mut slept = Duration::ZERO;
+        while slept < interval && !self.is_stopped() {
+            thread::sleep(STOP_POLL_INTERVAL);
+            slept += STOP_POLL_INTERVAL;
+        }
+    }
+
+    fn clone_ref(&self) -> Self {
+        Self {
+            properties: self.properties.clone(),
+            local_addr: self.local_addr,
+            stopped: self.stopped.clone(),
+            session: self.session.clone(),
+            logger: self.logger.clone_ref(),
+        }
+    }
+}
+
+/// Un sentido de reenvío del bridge: recibe de un extremo y publica en el otro.
+struct ForwardLink {
+    from: BridgeSide,
+    to_client: ShareableClient,
+    guard: LoopGuard,
+    properties: BridgeProperties,
+    logger: StringLogger,
+}
+
+impl ForwardLink {
+    fn spawn(self, rx: Receiver<PublishMessage>, done_tx: Sender<()>) -> JoinHandle<()> {
+        thread::spawn(move || {
+            for msg in rx {
+                if let Err(e) = self.forward(msg) {
+                    self.logger.log(format!("Bridge: error al reenviar: {:?}.", e));
+                    break;
+                }
+            }
+            let _ = done_tx.send(());
+        })
+    }
+
+    fn forward(&self, msg: PublishMessage) -> Result<(), Error> {
+        if self.guard.is_echo(&msg) {
+            return Ok(());
+        }
+
+        let topic = msg.get_topic();
+        let mapped_topic = match self.from {
+            BridgeSide::Local => self.properties.map_out_topic(&topic),
+            BridgeSide::Remote => self.properties.map_in_topic(&topic),
+        };
+
+        if let Ok(mut client) = self.to_client.lock() {
+            client.mqtt_publish_with_properties(&mapped_topic, &msg.get_payload(), BRIDGE_QOS, self.guard.mark(&msg))?;
+        }
+        Ok(())
+    }
+}
+
+fn subscribe(client: &ShareableClient, topics: &[String], logger: &StringLogger) {
+    if topics.is_empty() {
+        return;
+    }
+    let topics = topics
+        .iter()
+        .map(|topic| (topic.to_string(), BRIDGE_QOS))
+        .collect();
+    if let Ok(mut client) = client.lock() {
+        if let Err(e) = client.mqtt_subscribe(topics) {
+            logger.log(format!("Bridge: error al suscribirse: {:?}.", e));
+        }
+    }
+}
+
+fn disconnect(client: &ShareableClient) {
+    if let Ok(mut client) = client.lock() {
+        let _ = client.mqtt_disconnect();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::mpsc, thread, time::Duration};
+
+    use crate::logging::string_logger::StringLogger;
+    use crate::mqtt::client::mqtt_client::MQTTClient;
+    use crate::mqtt::server::mqtt_server::MQTTServer;
+
+    use super::{BridgeProperties, MQTTBridge};
+
+    #[test]
+    fn test_1_el_bridge_reenvia_un_topic_local_al_remoto_con_prefijo() {
+        let (tx, _rx) = mpsc::channel::<String>();
+        let logger = StringLogger::new(tx);
+
+        let (local_server, local_addr) = MQTTServer::start_on_ephemeral_port(logger.clone_ref()).unwrap();
+        let (remote_server, remote_addr) = MQTTServer::start_on_ephemeral_port(logger.clone_ref()).unwrap();
+
+        let bridge_properties = BridgeProperties::new(
+            remote_addr,
+            "bridge-test".to_string(),
+            vec!["cam".to_string()],
+            vec![],
+            "distrito1/".to_string(),
+            String::new(),
+        );
+        let bridge = MQTTBridge::new(bridge_properties, local_addr, logger.clone_ref());
+        let bridge_h = bridge.spawn();
+
+        // Suscriptor en el remoto, al topic con prefijo.
+        let (mut subscriber, sub_rx, sub_h) =
+            MQTTClient::mqtt_connect_to_broker("sub-remoto".to_string(), &remote_addr, None, logger.clone_ref()).unwrap();
+        subscriber.mqtt_subscribe(vec![("distrito1/cam".to_string(), 1)]).unwrap();
+        thread::sleep(Duration::from_millis(500)); // Que el bridge termine de suscribirse.
+
+        // Publicador en el local. Se publica dos veces lo mismo: no es un eco, y debe llegar las dos veces.
+        let (mut publisher, _pub_rx, pub_h) =
+            MQTTClient::mqtt_connect_to_broker("pub-local".to_string(), &local_addr, None, logger.clone_ref()).unwrap();
+        for _ in 0..2 {
+            publisher.mqtt_publish("cam", &[7, 8, 9], 1).unwrap();
+        }
+
+        for _ in 0..2 {
+            let received = sub_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+            assert_eq!(received.get_topic(), "distrito1/cam");
+            assert_eq!(received.get_payload(), vec![7, 8, 9]);
+        }
+
+        let _ = publisher.mqtt_disconnect();
+        let _ = subscriber.mqtt_disconnect();
+        bridge.stop();
+        for h in [pub_h, sub_h, bridge_h] {
+            h.join().unwrap();
+        }
+        local_server.shutdown().unwrap();
+        remote_server.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_2_un_topic_reenviado_en_ambos_sentidos_no_vuelve_como_eco() {
+        let (tx, _rx) = mpsc::channel::<String>();
+        let logger = StringLogger::new(tx);
+
+        let (_local_server, local_addr) = MQTTServer::start_on_ephemeral_port(logger.clone_ref()).unwrap();
+        let (_remote_server, remote_addr) = MQTTServer::start_on_ephemeral_port(logger.clone_ref()).unwrap();
+
+        // Sin prefijos, "inc" se reenvía en ambos sentidos: sin la marca del bridge, volvería al local.
+        let bridge_properties = BridgeProperties::new(
+            remote_addr,
+            "bridge-eco".to_string(),
+            vec!["inc".to_string()],
+            vec!["inc".to_string()],
+            String::new(),
+            String::new(),
+        );
+        let bridge = MQTTBridge::new(bridge_properties, local_addr, logger.clone_ref());
+        let bridge_h = bridge.spawn();
+
+        let (mut local_client, local_rx, local_h) =
+            MQTTClient::mqtt_connect_to_broker("cliente-local".to_string(), &local_addr, None, logger.clone_ref())
+                .unwrap();
+        local_client.mqtt_subscribe(vec![("inc".to_string(), 1)]).unwrap();
+        let (mut remote_client, remote_rx, remote_h) =
+            MQTTClient::mqtt_connect_to_broker("cliente-remoto".to_string(), &remote_addr, None, logger.clone_ref())
+                .unwrap();
+        remote_client.mqtt_subscribe(vec![("inc".to_string(), 1)]).unwrap();
+        thread::sleep(Duration::from_millis(500)); // Que el bridge termine de suscribirse.
+
+        local_client.mqtt_publish("inc", &[1], 1).unwrap();
+
+        assert_eq!(remote_rx.recv_timeout(Duration::from_secs(10)).unwrap().get_payload(), vec![1]);
+        assert_eq!(local_rx.recv_timeout(Duration::from_secs(10)).unwrap().get_payload(), vec![1]);
+        // Ni el local lo recibe de vuelta, ni el remoto lo recibe dos veces.
+        assert!(local_rx.recv_timeout(Duration::from_millis(1000)).is_err());
+        assert!(remote_rx.recv_timeout(Duration::from_millis(200)).is_err());
+
+        let _ = local_client.mqtt_disconnect();
+        let _ = remote_client.mqtt_disconnect();
+        bridge.stop();
+        for h in [local_h, remote_h, bridge_h] {
+            h.join().unwrap();
+        }
+    }
+}
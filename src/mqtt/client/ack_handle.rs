@@ -0,0 +1,50 @@
+use std::{
+    io::{Error, ErrorKind},
+    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
+};
+
+/// Devuelto al enviar un mensaje sin esperar su ack, permite consultar o esperar el resultado más tarde.
+/// El resultado es Ok cuando llegó el ack, o error si se agotaron las retransmisiones.
+#[derive(Debug)]
+pub struct AckHandle {
+    packet_id: Option<u16>,
+    result_rx: Receiver<Result<(), Error>>,
+}
+
+impl AckHandle {
+    /// Crea un handle pendiente, y el extremo por el que se informará su resultado.
+    pub fn new(packet_id: Option<u16>) -> (Self, Sender<Result<(), Error>>) {
+        let (result_tx, result_rx) = channel();
+        (Self { packet_id, result_rx }, result_tx)
+    }
+
+    /// Crea un handle ya resuelto, para los mensajes que no esperan ack (ej publish con qos 0).
+    pub fn resolved(packet_id: Option<u16>) -> Self {
+        let (handle, result_tx) = Self::new(packet_id);
+        let _ = result_tx.send(Ok(()));
+        handle
+    }
+
+    pub fn get_packet_id(&self) -> Option<u16> {
+        self.packet_id
+    }
+
+    /// Bloquea hasta conocer el resultado del envío.
+    pub fn wait(self) -> Result<(), Error> {
+        self.result_rx.recv().unwrap_or_else(|_| Err(Self::lost_result_error()))
+    }
+
+    /// Devuelve el resultado del envío si ya se conoce, sin bloquear.
+    /// El resultado se devuelve una única vez.
+    pub fn try_result(&self) -> Option<Result<(), Error>> {
+        match self.result_rx.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(Self::lost_result_error())),
+        }
+    }
+
+    fn lost_result_error() -> Error {
+        Error::new(ErrorKind::NotConnected, "Se cerró la conexión antes de recibir el ack.")
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind},
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use tokio::{
+    io::AsyncWriteExt,
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpStream,
+    },
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+    time::timeout,
+};
+
+use crate::logging::string_logger::StringLogger;
+use crate::mqtt::client::{
+    credentials::Credentials,
+    in_flight_window::{ACK_WAITING_INTERVAL, AMOUNT_OF_RETRIES},
+    incoming_messages::IncomingMessages,
+    mqtt_client_connector::MqttClientConnector,
+    mqtt_client_msg_creator::MessageCreator,
+};
+use crate::mqtt::messages::{
+    mqtt_properties::MqttProperties, packet_type::PacketType, protocol_version::ProtocolVersion,
+    puback_message::PubAckMessage, publish_message::PublishMessage, suback_message::SubAckMessage,
+    unsuback_message::Unsuback,
+};
+use crate::mqtt::mqtt_utils::{
+    async_utils::{read_message_from_async_stream, write_message_to_async_stream},
+    fixed_header::FixedHeader,
+    will_message_utils::will_message::WillMessageData,
+};
+
+// Tiempo a esperar el connack; el mismo que espera el connector sincrónico, contando sus retransmisiones.
+const CONNACK_WAITING_INTERVAL: Duration = Duration::from_secs(6);
+
+type SharedWriter = Arc<tokio::sync::Mutex<OwnedWriteHalf>>;
+// Por cada packet_id que espera su ack, el extremo por el que avisar que llegó.
+type PendingAcks = Arc<Mutex<HashMap<u16, oneshot::Sender<()>>>>;
+
+/// Versión asincrónica (sobre tokio) de `MQTTClient`. Usa los mismos mensajes y el mismo `MessageCreator`
+/// que el cliente sincrónico, que sigue disponible para las apps existentes.
+/// Sus métodos toman `&self`, así que pueden enviarse varios mensajes a la vez sin esperar el ack de cada uno.
+/// Los mensajes publicados a los topics a los que se suscriba se reciben por el `IncomingMessages` devuelto al conectarse.
+#[derive(Debug)]
+pub struct AsyncMqttClient {
+    writer: SharedWriter, // Compartido con la tarea lectora, que envía los pubacks.
+    msg_creator: Arc<Mutex<MessageCreator>>,
+    pending_acks: PendingAcks, // Compartido con la tarea lectora, que avisa al llegar los acks.
+    reader_task: JoinHandle<()>,
+    logger: StringLogger,
+}
+
+impl AsyncMqttClient {
+    /// Se conecta al servidor en `addr`. Devuelve el cliente al que solicitarle los demás métodos, y el
+    /// `IncomingMessages` por el que recibir los PublishMessages que se publiquen a los topics a los que nos suscribamos.
+    pub async fn connect(
+        client_id: String,
+        addr: &SocketAddr,
+        will: Option<WillMessageData>,
+        logger: StringLogger,
+    ) -> Result<(Self, IncomingMessages), Error> {
+        Self::connect_with(client_id, addr, will, None, logger).await
+    }
+
+    /// Como `connect`, pero la conexión es MQTT 5, enviando las `properties` en el connect.
+    pub async fn connect_v5(
+        client_id: String,
+        addr: &SocketAddr,
+        will: Option<WillMessageData>,
+        properties: MqttProperties,
+        logger: StringLogger,
+    ) -> Result<(Self, IncomingMessages), Error> {
+        Self::connect_with(client_id, addr, will, Some(properties), logger).await
+    }
+
+    async fn connect_with(
+        client_id: String,
+        addr: &SocketAddr,
+        will: Option<WillMessageData>,
+        properties: Option<MqttProperties>,
+        logger: StringLogger,
+    ) -> Result<(Self, IncomingMessages), Error> {
+        let is_v5 = properties.is_some();
+        let mut stream = TcpStream::connect(addr).await?;
+
+        let mut connect_msg =
+            MqttClientConnector::create_connect_msg(client_id.to_string(), will, &Credentials::default(), properties);
+        logger.log("Mqtt: Enviando connect msg.".to_string());
+        write_message_to_async_stream(&connect_msg.to_bytes(), &mut stream).await?;
+        let connack_bytes = timeout(CONNACK_WAITING_INTERVAL, read_message_from_async_stream(&mut stream))
+            .await
+            .map_err(|_| Error::new(ErrorKind::TimedOut, "No se recibió el connack a tiempo."))??
+            .ok_or_else(|| {
+                Error::new(ErrorKind::NotConnected, "Se cerró la conexión antes de recibir el connack.")
+            })?;
+        let connack_properties = MqttClientConnector::analyze_connack(&connack_bytes)?;
+        logger.log("Mqtt: connack recibido.".to_string());
+
+        // Inicializa sus partes internas
+        let msg_creator = if is_v5 {
+            let topic_alias_maximum = connack_properties
+                .and_then(|props| props.topic_alias_maximum)
+                .unwrap_or(0);
+            MessageCreator::new_v5(topic_alias_maximum, &client_id)
+        } else {
+            MessageCreator::new()
+        };
+        let (reader, writer) = stream.into_split();
+        let writer = Arc::new(tokio::sync::Mutex::new(writer));
+        let pending_acks: PendingAcks = Arc::new(Mutex::new(HashMap::new()));
+        let (publish_msg_tx, publish_msg_rx) = mpsc::unbounded_channel::<PublishMessage>();
+
+        let listener = AsyncListener {
+            writer: writer.clone(),
+            pending_acks: pending_acks.clone(),
+            publish_msg_tx,
+            protocol_version: msg_creator.get_protocol_version(),
+            logger: logger.clone_ref(),
+        };
+        let reader_task = tokio::spawn(listener.read_from_server(reader));
+
+        let client = Self {
+            writer,
+            msg_creator: Arc::new(Mutex::new(msg_creator)),
+            pending_acks,
+            reader_task,
+            logger,
+        };
+        Ok((client, IncomingMessages::new(publish_msg_rx)))
+    }
+
+    /// Publica `payload` al `topic`. Con qos 1, se completa al recibir el puback, retransmitiendo si no llega a tiempo.
+    /// Devuelve el mensaje enviado, con su topic completo aunque se haya enviado con topic alias.
+    pub async fn publish(&self, topic: &str, payload: &[u8], qos: u8) -> Result<PublishMessage, Error> {
+        let mut msg = self.lock_msg_creator()?.create_publish_msg(topic, payload, qos)?;
+        match msg.get_packet_id() {
+            Some(packet_id) if msg.get_qos() > 0 => {
+                let mut dup_msg = msg.clone();
+                dup_msg.set_dup();
+                self.send_and_wait_for_ack(packet_id, msg.to_bytes(), dup_msg.to_bytes())
+                    .await?;
+            }
+            _ => self.send(&msg.to_bytes()).await?,
+        }
+        self.logger.log(format!("-----------------\n Mqtt: publish enviado: \n   {:?}", msg));
+
+        msg.set_topic(topic);
+        Ok(msg)
+    }
+
+    /// Se suscribe a los `topics`, cada uno con su qos. Se completa al recibir el suback.
+    pub async fn subscribe(&self, topics: Vec<(String, u8)>) -> Result<(), Error> {
+        let msg = self.lock_msg_creator()?.create_subscribe_msg(topics)?;
+        let bytes = msg.to_bytes();
+        self.send_and_wait_for_ack(msg.get_packet_id(), bytes.to_vec(), bytes)
+            .await?;
+        self.logger.log(format!("-----------------\n Mqtt: subscribe enviado: \n   {:?}", msg));
+        Ok(())
+    }
+
+    /// Se desuscribe de los `topics`. Se completa al recibir el unsuback.
+    pub async fn unsubscribe(&self, topics: Vec<String>) -> Result<(), Error> {
+        let mut msg = self.lock_msg_creator()?.create_unsubscribe_msg(topics)?;
+        let bytes = msg.to_bytes();
+        self.send_and_wait_for_ack(msg.get_packet_id(), bytes.to_vec(), bytes)
+            .await?;
+        self.logger.log(format!("-----------------\n Mqtt: unsubscribe enviado: \n   {:?}", msg));
+        Ok(())
+    }
+
+    /// Termina de manera voluntaria la conexión con el server. Al hacerlo, termina también el `IncomingMessages`.
+    pub async fn disconnect(self) -> Result<(), Error> {
+        let msg = self.lock_msg_creator()?.create_disconnect_msg()?;
+        self.send(&msg.to_bytes()).await?;
+        self.writer.lock().await.shutdown().await?;
+        self.logger.log("Mqtt: Conexión cerrada.".to_string());
+        Ok(())
+    }
+
+    /// Devuelve la versión del protocolo con la que se conectó el cliente.
+    pub fn get_protocol_version(&self) -> ProtocolVersion {
+        self.lock_msg_creator()
+            .map(|creator| creator.get_protocol_version())
+            .unwrap_or_default()
+    }
+
+    /// Envía `msg_bytes` y espera el ack de `packet_id`. Si no llega a tiempo, envía `resend_bytes`,
+    /// hasta agotar los reintentos. Al terminar, libera el packet_id.
+    async fn send_and_wait_for_ack(
+        &self,
+        packet_id: u16,
+        msg_bytes: Vec<u8>,
+        resend_bytes: Vec<u8>,
+    ) -> Result<(), Error> {
+        // Se registra antes de enviarlo, para no perder un ack que llegue enseguida.
+        let (ack_tx, mut ack_rx) = oneshot::channel();
+        self.lock_pending_acks()?.insert(packet_id, ack_tx);
+
+        let result = self
+            .retransmit_until_ack(packet_id, &mut ack_rx, msg_bytes, resend_bytes)
+            .await;
+        if let Ok(mut pending_acks) = self.pending_acks.lock() {
+            pending_acks.remove(&packet_id);
+        }
+        if let Ok(msg_creator) = self.msg_creator.lock() {
+            msg_creator.release_packet_id(packet_id);
+        }
+        result
+    }
+
+    async fn retransmit_until_ack(
+        &self,
+        packet_id: u16,
+        ack_rx: &mut oneshot::Receiver<()>,
+        msg_bytes: Vec<u8>,
+        resend_bytes: Vec<u8>,
+    ) -> Result<(), Error> {
+        self.send(&msg_bytes).await?;
+        for _ in 0..AMOUNT_OF_RETRIES {
+            match timeout(ACK_WAITING_INTERVAL, &mut *ack_rx).await {
+                Ok(ack) => return ack.map_err(|_| Self::connection_closed_error()),
+                Err(_) => {
+                    self.logger.log(format!("Mqtt: Retransmitiendo {}...", packet_id));
+                    self.send(&resend_bytes).await?;
+                }
+            }
+        }
+        match timeout(ACK_WAITING_INTERVAL, ack_rx).await {
+            Ok(ack) => ack.map_err(|_| Self::connection_closed_error()),
+            Err(_) => Err(Error::other(
+                "MAXRETRIES, se retransmitió sin éxito.",
+            )),
+        }
+    }
+
+    async fn send(&self, msg_bytes: &[u8]) -> Result<(), Error> {
+        let mut writer = self.writer.lock().await;
+        write_message_to_async_stream(msg_bytes, &mut *writer).await
+    }
+
+    fn connection_closed_error() -> Error {
+        Error::new(ErrorKind::NotConnected, "Se cerró la conexión antes de recibir el ack.")
+    }
+
+    fn lock_msg_creator(&self) -> Result<MutexGuard<'_, MessageCreator>, Error> {
+        self.msg_creator
+            .lock()
+            .map_err(|_| Error::other("Error al tomar lock del msg creator."))
+    }
+
+    fn lock_pending_acks(&self) -> Result<MutexGuard<'_, HashMap<u16, oneshot::Sender<()>>>, Error> {
+        self.pending_acks
+            .lock()
+            .map_err(|_| Error::other("Error al tomar lock de los acks pendientes."))
+    }
+}
+
+impl Drop for AsyncMqttClient {
+    /// Deja de leer del servidor, lo que termina el `IncomingMessages`.
+    fn drop(&mut self) {
+        self.reader_task.abort();
+    }
+}
+
+/// Equivalente asincrónico de `MQTTClientListener`: lee los mensajes del servidor, envía los publish recibidos
+/// al `IncomingMessages`, y avisa de los acks que llegan a quien los espera.
+struct AsyncListener {
+    writer: SharedWriter,
+    pending_acks: PendingAcks,
+    publish_msg_tx: mpsc::UnboundedSender<PublishMessage>,
+    protocol_version: ProtocolVersion,
+    logger: StringLogger,
+}
+
+impl AsyncListener {
+    /// Lee del servidor hasta que se cierre la conexión. Al terminar, quienes esperaban un ack reciben error.
+    async fn read_from_server(self, mut reader: OwnedReadHalf) {
+        loop {
+            let msg_bytes = match read_message_from_async_stream(&mut reader).await {
+                Ok(Some(msg_bytes)) => msg_bytes,
+                Ok(None) => {
+                    self.logger.log("Mqtt: Se cerró la conexión con server.".to_string());
+                    break;
+                }
+                Err(e) => {
+                    self.logger.log(format!("Error al leer del stream con server: {:?}.", e));
+                    break;
+                }
+            };
+            if let Err(e) = self.handle_message(msg_bytes).await {
+                self.logger.log(format!("Error al procesar mensaje del server: {:?}.", e));
+            }
+        }
+
+        if let Ok(mut pending_acks) = self.pending_acks.lock() {
+            pending_acks.clear();
+        }
+    }
+
+    /// Analiza el tipo del mensaje leído, y lo procesa acorde a él.
+    async fn handle_message(&self, msg_bytes: Vec<u8>) -> Result<(), Error> {
+        let fixed_header = FixedHeader::from_bytes(msg_bytes[..FixedHeader::fixed_header_len()].to_vec());
+        match fixed_header.get_message_type() {
+            PacketType::Publish => self.handle_publish(msg_bytes).await?,
+            PacketType::Puback => {
+                let msg = PubAckMessage::msg_from_bytes(msg_bytes)?;
+                self.ack_received(msg.get_packet_id());
+            }
+            PacketType::Suback => {
+                let msg = SubAckMessage::from_bytes_with_version(msg_bytes, self.protocol_version)?;
+                self.ack_received(msg.get_packet_id());
+            }
+            PacketType::Unsuback => {
+                let msg = Unsuback::from_bytes_with_version(&msg_bytes, self.protocol_version)?;
+                self.ack_received(msg.get_packet_id());
+            }
+            other => {
+                return Err(Error::other(
+                    format!("Tipo desconocido: {:?}.", other),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_publish(&self, msg_bytes: Vec<u8>) -> Result<(), Error> {
+        let msg = PublishMessage::from_bytes_with_version(msg_bytes, self.protocol_version)?;
+        if let Some(packet_id) = msg.get_packet_id() {
+            let ack = PubAckMessage::new(packet_id, 0);
+            let mut writer = self.writer.lock().await;
+            write_message_to_async_stream(&ack.to_bytes(), &mut *writer).await?;
+        }
+        // Si ya nadie lee los mensajes, no es un error.
+        let _ = self.publish_msg_tx.send(msg);
+        Ok(())
+    }
+
+    /// Avisa a quien espera el ack de `packet_id` que llegó. Si nadie lo espera (ej llegó tarde), se ignora.
+    fn ack_received(&self, packet_id: u16) {
+        let ack_tx = self
+            .pending_acks
+            .lock()
+            .ok()
+            .and_then(|mut pending_acks| pending_acks.remove(&packet_id));
+        if let Some(ack_tx) = ack_tx {
+            let _ = ack_tx.send(());
+        }
+    }
+}
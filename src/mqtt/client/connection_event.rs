@@ -0,0 +1,13 @@
+/// Cambios en el estado de la conexión de un `MQTTClient` con reconexión automática,
+/// informados a la app (por ejemplo, para mostrar que el broker está offline).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionEvent {
+    /// Se perdió la conexión con el broker; los publish con qos >= 1 se almacenan hasta reconectar.
+    Disconnected,
+    /// Se está por realizar el intento de reconexión número `attempt`.
+    Reconnecting { attempt: u32 },
+    /// Se reconectó, se renovaron las suscripciones y se enviaron los publish almacenados.
+    Reconnected,
+    /// Se agotaron los intentos de reconexión.
+    GaveUp,
+}
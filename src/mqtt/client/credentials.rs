@@ -0,0 +1,30 @@
+/// Usuario y contraseña con los que el cliente se autentica en el connect.
+/// Por defecto son los del usuario que usan las apps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Credentials {
+    username: String,
+    password: String,
+}
+
+impl Credentials {
+    pub fn new(username: &str, password: &str) -> Self {
+        Self {
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    pub fn get_username(&self) -> String {
+        self.username.to_string()
+    }
+
+    pub fn get_password(&self) -> String {
+        self.password.to_string()
+    }
+}
+
+impl Default for Credentials {
+    fn default() -> Self {
+        Self::new("usuario0", "rustx123")
+    }
+}
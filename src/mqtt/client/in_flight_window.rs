@@ -0,0 +1,204 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind},
+    sync::{mpsc::Sender, Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::mqtt::mqtt_utils::packet_id_allocator::PacketIdAllocator;
+
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 20;
+// Tiempo a esperar el ack antes de retransmitir.
+pub const ACK_WAITING_INTERVAL: Duration = Duration::from_millis(1000);
+// Cant de veces que se retransmite, hasta que se desista y se informe error.
+pub const AMOUNT_OF_RETRIES: u8 = 5;
+
+/// Mensaje enviado cuyo ack todavía no llegó.
+#[derive(Debug)]
+struct InFlightMsg {
+    resend_bytes: Vec<u8>, // Con el flag dup, si es publish.
+    deadline: Instant,
+    remaining_retries: u8,
+    result_tx: Sender<Result<(), Error>>,
+}
+
+/// Ventana de mensajes enviados que esperan su ack, identificados por packet_id.
+/// Admite hasta `max_in_flight` mensajes a la vez, y lleva un timer de retransmisión para cada uno.
+/// Al terminar de esperar un mensaje (por su ack o por agotar los reintentos), libera su packet_id.
+#[derive(Debug)]
+pub struct InFlightWindow {
+    max_in_flight: usize,
+    msgs: HashMap<u16, InFlightMsg>,
+    closed: bool, // Ya no se recibirán acks.
+    packet_ids: Arc<Mutex<PacketIdAllocator>>, // Compartido con el MessageCreator.
+}
+
+impl InFlightWindow {
+    pub fn new(max_in_flight: usize, packet_ids: Arc<Mutex<PacketIdAllocator>>) -> Self {
+        Self {
+            max_in_flight: max_in_flight.max(1),
+            msgs: HashMap::new(),
+            closed: false,
+            packet_ids,
+        }
+    }
+
+    pub fn set_max_in_flight(&mut self, max_in_flight: usize) {
+        self.max_in_flight = max_in_flight.max(1);
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.msgs.len() >= self.max_in_flight
+    }
+
+    pub fn len(&self) -> usize {
+        self.msgs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.msgs.is_empty()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Agrega un mensaje recién enviado. Si no llega su ack, se retransmitirá `resend_bytes`.
+    pub fn insert(
+        &mut self,
+        packet_id: u16,
+        resend_bytes: Vec<u8>,
+        result_tx: Sender<Result<(), Error>>,
+        now: Instant,
+    ) {
+        let msg = InFlightMsg {
+            resend_bytes,
+            deadline: now + ACK_WAITING_INTERVAL,
+            remaining_retries: AMOUNT_OF_RETRIES,
+            result_tx,
+        };
+        self.msgs.insert(packet_id, msg);
+    }
+
+    /// Quita el mensaje sin informar resultado ni liberar su packet_id (ej si no se pudo enviar,
+    /// y se lo almacena para reenviarlo).
+    pub fn remove(&mut self, packet_id: u16) {
+        self.msgs.remove(&packet_id);
+    }
+
+    /// Llegó el ack del mensaje con ese `packet_id`: lo quita de la ventana e informa el éxito.
+    /// Devuelve si el mensaje estaba en la ventana.
+    pub fn ack(&mut self, packet_id: u16) -> bool {
+        if let Some(msg) = self.msgs.remove(&packet_id) {
+            self.release(packet_id);
+            // Si nadie espera el resultado, no es un error.
+            let _ = msg.result_tx.send(Ok(()));
+            return true;
+        }
+        false
+    }
+
+    /// Devuelve los mensajes cuyo ack no llegó a tiempo, para retransmitirlos.
+    /// Los que ya agotaron sus retransmisiones se quitan de la ventana, informando el error.
+    pub fn take_due_for_retransmission(&mut self, now: Instant) -> Vec<(u16, Vec<u8>)> {
+        let mut to_resend = vec![];
+        let mut given_up = vec![];
+        for (packet_id, msg) in self.msgs.iter_mut() {
+            if msg.deadline > now {
+                continue;
+            }
+            if msg.remaining_retries == 0 {
+                given_up.push(*packet_id);
+            } else {
+                msg.remaining_retries -= 1;
+                msg.deadline = now + ACK_WAITING_INTERVAL;
+                to_resend.push((*packet_id, msg.resend_bytes.to_vec()));
+            }
+        }
+
+        for packet_id in given_up {
+            if let Some(msg) = self.msgs.remove(&packet_id) {
+                self.release(packet_id);
+                let _ = msg.result_tx.send(Err(Error::other(
+                    "MAXRETRIES, se retransmitió sin éxito.",
+                )));
+            }
+        }
+        to_resend
+    }
+
+    /// Ya no llegarán acks: se informa error a todos los mensajes pendientes.
+    pub fn close(&mut self) {
+        self.closed = true;
+        for (packet_id, msg) in std::mem::take(&mut self.msgs) {
+            self.release(packet_id);
+            let _ = msg.result_tx.send(Err(Error::new(
+                ErrorKind::NotConnected,
+                "Se cerró la conexión antes de recibir el ack.",
+            )));
+        }
+    }
+
+    fn release(&self, packet_id: u16) {
+        if let Ok(mut packet_ids) = self.packet_ids.lock() {
+            packet_ids.release(packet_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use super::{InFlightWindow, AMOUNT_OF_RETRIES};
+    use crate::mqtt::{client::ack_handle::AckHandle, mqtt_utils::packet_id_allocator::PacketIdAllocator};
+
+    fn create_window(max_in_flight: usize) -> (InFlightWindow, Arc<Mutex<PacketIdAllocator>>) {
+        let packet_ids = Arc::new(Mutex::new(PacketIdAllocator::new()));
+        (InFlightWindow::new(max_in_flight, packet_ids.clone()), packet_ids)
+    }
+
+    #[test]
+    fn test_1_el_ack_libera_el_lugar_y_resuelve_el_handle() {
+        let (mut window, packet_ids) = create_window(2);
+        let now = Instant::now();
+        let id_1 = packet_ids.lock().unwrap().allocate().unwrap();
+        let id_2 = packet_ids.lock().unwrap().allocate().unwrap();
+        let (handle_1, tx_1) = AckHandle::new(Some(id_1));
+        let (handle_2, tx_2) = AckHandle::new(Some(id_2));
+        window.insert(id_1, vec![1], tx_1, now);
+        window.insert(id_2, vec![2], tx_2, now);
+        assert!(window.is_full());
+
+        // Los acks pueden llegar en cualquier orden.
+        assert!(window.ack(id_2));
+        assert!(!window.is_full());
+        assert!(handle_2.wait().is_ok());
+        assert!(handle_1.try_result().is_none());
+        assert!(!window.ack(id_2));
+        // Sólo se liberó el id del mensaje cuyo ack llegó.
+        assert!(!packet_ids.lock().unwrap().is_in_use(id_2));
+        assert!(packet_ids.lock().unwrap().is_in_use(id_1));
+    }
+
+    #[test]
+    fn test_2_se_retransmite_al_vencer_el_timer_hasta_agotar_los_reintentos() {
+        let (mut window, _) = create_window(20);
+        let mut now = Instant::now();
+        let (handle, tx) = AckHandle::new(Some(7));
+        window.insert(7, vec![7], tx, now);
+        assert!(window.take_due_for_retransmission(now).is_empty());
+
+        for _ in 0..AMOUNT_OF_RETRIES {
+            now += Duration::from_millis(1000);
+            assert_eq!(window.take_due_for_retransmission(now), vec![(7, vec![7])]);
+        }
+        now += Duration::from_millis(1000);
+        assert!(window.take_due_for_retransmission(now).is_empty());
+        assert!(window.is_empty());
+        assert!(handle.wait().is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::Stream;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::mqtt::messages::publish_message::PublishMessage;
+
+/// Mensajes publicados a los topics a los que se suscribió un `AsyncMqttClient`.
+/// Es un `Stream`, que termina al cerrarse la conexión con el servidor.
+#[derive(Debug)]
+pub struct IncomingMessages {
+    publish_msg_rx: UnboundedReceiver<PublishMessage>,
+}
+
+impl IncomingMessages {
+    pub fn new(publish_msg_rx: UnboundedReceiver<PublishMessage>) -> Self {
+        Self { publish_msg_rx }
+    }
+
+    /// Espera el próximo mensaje. Devuelve None si se cerró la conexión.
+    pub async fn recv(&mut self) -> Option<PublishMessage> {
+        self.publish_msg_rx.recv().await
+    }
+}
+
+impl Stream for IncomingMessages {
+    type Item = PublishMessage;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.publish_msg_rx.poll_recv(cx)
+    }
+}
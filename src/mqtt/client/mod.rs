@@ -3,4 +3,15 @@ pub mod mqtt_client_listener;
 pub mod mqtt_client_connector;
 pub mod mqtt_client_msg_creator;
 pub mod ack_message;
-pub mod mqtt_client_retransmitter;
\ No newline at end of file
+pub mod mqtt_client_retransmitter;
+pub mod connection_event;
+pub mod mqtt_client_reconnector;
+pub mod offline_buffer;
+pub mod reconnect_policy;
+pub mod ack_handle;
+pub mod in_flight_window;
+pub mod async_mqtt_client;
+pub mod incoming_messages;
+pub mod topic_router;
+pub mod typed_topic;
+pub mod credentials;
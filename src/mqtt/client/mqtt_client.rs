@@ -1,25 +1,44 @@
 use crate::logging::string_logger::StringLogger;
 use crate::mqtt::client::{
+    ack_handle::AckHandle,
+    connection_event::ConnectionEvent,
+    credentials::Credentials,
     mqtt_client_listener::MQTTClientListener, mqtt_client_retransmitter::Retransmitter,
     mqtt_client_connector::MqttClientConnector,
     mqtt_client_msg_creator::MessageCreator,
+    mqtt_client_reconnector::{ConnectionParams, Reconnector},
+    reconnect_policy::ReconnectPolicy,
+    topic_router::TopicRouter,
+    typed_topic::{MqttPayload, TimestampedReceiver, Topic},
+};
+use crate::mqtt::messages::{
+    mqtt_properties::MqttProperties, ping_message::PingMessage, protocol_version::ProtocolVersion,
+    publish_message::PublishMessage,
 };
-use crate::mqtt::messages::publish_message::PublishMessage;
 use crate::mqtt::mqtt_utils::will_message_utils::will_message::WillMessageData;
-use std::net::TcpStream;
+use crate::mqtt::stream_type::StreamType;
+use crate::time::clock::{ShClock, SystemClock};
 use std::{
-    io::Error,
+    io::{Error, ErrorKind},
     net::SocketAddr,
-    sync::mpsc::{self, Receiver},
+    sync::{
+        mpsc::{self, Receiver},
+        Arc, Mutex, MutexGuard,
+    },
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
-pub type ClientStreamType = TcpStream; // Aux: que solo lo use el cliente por ahora, para hacer refactor más fácil.
+pub type ClientStreamType = StreamType;
+type ReconnectingClientInfo = (MQTTClient, Receiver<PublishMessage>, Receiver<ConnectionEvent>, JoinHandle<()>);
 
 #[derive(Debug)]
 pub struct MQTTClient {
-    msg_creator: MessageCreator,
+    msg_creator: Arc<Mutex<MessageCreator>>, // Compartido con el Reconnector.
     retransmitter: Retransmitter,
+    reconnector: Option<Arc<Reconnector>>, // Presente sólo si se pidió reconexión automática.
+    router: Arc<Mutex<TopicRouter>>, // Compartido con el listener, que reparte los publish recibidos.
+    pingresp_rx: Receiver<()>, // Por él el listener avisa que llegó un pingresp.
     logger: StringLogger,
 }
 
@@ -33,28 +52,189 @@ impl MQTTClient {
         will: Option<WillMessageData>,
         logger: StringLogger,
     ) -> Result<(Self, Receiver<PublishMessage>, JoinHandle<()>), Error> {
+        Self::mqtt_connect_to_broker_with_credentials(client_id, addr, will, Credentials::default(), logger)
+    }
+
+    /// Como `mqtt_connect_to_broker`, pero autenticándose con el usuario y contraseña de `credentials`.
+    pub fn mqtt_connect_to_broker_with_credentials(
+        client_id: String,
+        addr: &SocketAddr,
+        will: Option<WillMessageData>,
+        credentials: Credentials,
+        logger: StringLogger,
+    ) -> Result<(Self, Receiver<PublishMessage>, JoinHandle<()>), Error> {
+        let (client, publish_msg_rx, _, handle) = Self::connect(client_id, addr, will, credentials, None, None, logger)?;
+        Ok((client, publish_msg_rx, handle))
+    }
+
+    /// Como `mqtt_connect_to_broker`, pero si se pierde la conexión con el broker se reconecta automáticamente
+    /// según la `policy`, renovando las suscripciones. Mientras no hay conexión, los publish con qos >= 1 se almacenan
+    /// y se envían al reconectar. Devuelve además un rx por el que se informan los cambios en el estado de la conexión.
+    pub fn mqtt_connect_to_broker_with_reconnect(
+        client_id: String,
+        addr: &SocketAddr,
+        will: Option<WillMessageData>,
+        policy: ReconnectPolicy,
+        logger: StringLogger,
+    ) -> Result<ReconnectingClientInfo, Error> {
+        Self::connect(client_id, addr, will, Credentials::default(), None, Some(policy), logger)
+    }
+
+    /// Como `mqtt_connect_to_broker`, pero la conexión es MQTT 5, enviando las `properties` en el connect
+    /// (por ejemplo el session expiry interval).
+    pub fn mqtt_connect_to_broker_v5(
+        client_id: String,
+        addr: &SocketAddr,
+        will: Option<WillMessageData>,
+        properties: MqttProperties,
+        logger: StringLogger,
+    ) -> Result<(Self, Receiver<PublishMessage>, JoinHandle<()>), Error> {
+        let (client, publish_msg_rx, _, handle) =
+            Self::connect(client_id, addr, will, Credentials::default(), Some(properties), None, logger)?;
+        Ok((client, publish_msg_rx, handle))
+    }
+
+    /// Como `mqtt_connect_to_broker`, pero sobre un `stream` ya establecido con el servidor (ie en memoria, obtenido
+    /// con `MQTTServer::connect_in_memory`), y midiendo los plazos de retransmisión con `clock`.
+    /// No admite reconexión automática. Pensado para tests que corren broker y clientes en el mismo proceso.
+    pub fn mqtt_connect_over_stream(
+        client_id: String,
+        stream: ClientStreamType,
+        will: Option<WillMessageData>,
+        clock: ShClock,
+        logger: StringLogger,
+    ) -> Result<(Self, Receiver<PublishMessage>, JoinHandle<()>), Error> {
+        let (stream, connack_properties) = MqttClientConnector::mqtt_connect_over_stream(
+            stream,
+            client_id.to_string(),
+            will,
+            &Credentials::default(),
+            None,
+            logger.clone_ref(),
+        )?;
+        let (client, publish_msg_rx, _, handle) =
+            Self::start(&client_id, stream, connack_properties, false, None, clock, logger)?;
+        Ok((client, publish_msg_rx, handle))
+    }
+
+    fn connect(
+        client_id: String,
+        addr: &SocketAddr,
+        will: Option<WillMessageData>,
+        credentials: Credentials,
+        properties: Option<MqttProperties>,
+        policy: Option<ReconnectPolicy>,
+        logger: StringLogger,
+    ) -> Result<ReconnectingClientInfo, Error> {
+        let is_v5 = properties.is_some();
+        let params = ConnectionParams {
+            client_id,
+            addr: *addr,
+            will,
+            credentials,
+            properties,
+        };
         // Efectúa la conexión al server
-        let stream = MqttClientConnector::mqtt_connect_to_broker(client_id, addr, will, logger.clone_ref())?;
+        let (stream, connack_properties) = MqttClientConnector::mqtt_connect_to_broker(
+            params.client_id.to_string(),
+            addr,
+            params.will.clone(),
+            &params.credentials,
+            params.properties.clone(),
+            logger.clone_ref(),
+        )?;
+        let client_id = params.client_id.to_string();
+        let reconnection = policy.map(|policy| (params, policy));
+        Self::start(&client_id, stream, connack_properties, is_v5, reconnection, SystemClock::shared(), logger)
+    }
+
+    /// Inicializa las partes internas del cliente `client_id`, ya conectado al server por `stream`, y lanza el hilo
+    /// que lee de él. Si se recibe `reconnection`, se reconecta con esos parámetros y política al perder la conexión.
+    fn start(
+        client_id: &str,
+        stream: ClientStreamType,
+        connack_properties: Option<MqttProperties>,
+        is_v5: bool,
+        reconnection: Option<(ConnectionParams, ReconnectPolicy)>,
+        clock: ShClock,
+        logger: StringLogger,
+    ) -> Result<ReconnectingClientInfo, Error> {
         // Inicializa sus partes internas
-        let writer = MessageCreator::new();
+        let writer = if is_v5 {
+            let topic_alias_maximum = connack_properties
+                .and_then(|props| props.topic_alias_maximum)
+                .unwrap_or(0);
+            MessageCreator::new_v5(topic_alias_maximum, client_id)
+        } else {
+            MessageCreator::new()
+        };
+        let protocol_version = writer.get_protocol_version();
+        let packet_ids = writer.get_packet_id_allocator();
+        let msg_creator = Arc::new(Mutex::new(writer));
+        let shared_stream = Arc::new(Mutex::new(stream.try_clone()?));
         let (publish_msg_tx, publish_msg_rx) = mpsc::channel::<PublishMessage>();
-        let (retransmitter, ack_tx) = Retransmitter::new(stream.try_clone()?, logger.clone_ref());
-        let mut listener = MQTTClientListener::new(stream.try_clone()?, publish_msg_tx, ack_tx);
-        
+        let (events_tx, events_rx) = mpsc::channel::<ConnectionEvent>();
+        let (retransmitter, ack_tx) = Retransmitter::new(shared_stream.clone(), packet_ids.clone(), clock, logger.clone_ref());
+        let router = Arc::new(Mutex::new(TopicRouter::new(publish_msg_tx)));
+        let (pingresp_tx, pingresp_rx) = mpsc::channel::<()>();
+        let mut listener = MQTTClientListener::new(
+            stream.try_clone()?,
+            router.clone(),
+            ack_tx,
+            pingresp_tx,
+            protocol_version,
+            packet_ids,
+        );
+        let reconnector = reconnection.map(|(params, policy)| {
+            Arc::new(Reconnector::new(
+                params,
+                policy,
+                shared_stream,
+                msg_creator.clone(),
+                events_tx,
+                logger.clone_ref(),
+            ))
+        });
+
         let logger_c = logger.clone_ref();
+        let reconnector_c = reconnector.clone();
+        let router_c = router.clone();
         let mqtt_client = MQTTClient {
-            msg_creator: writer,
+            msg_creator,
             retransmitter,
+            reconnector,
+            router,
+            pingresp_rx,
             logger,
         };
 
         let listener_handle = thread::spawn(move || {
-            if let Err(e) = listener.read_from_server(){
-                logger_c.log(format!("Error al leer, en read_from_server: {:?}", e));
+            loop {
+                if let Err(e) = listener.read_from_server() {
+                    logger_c.log(format!("Error al leer, en read_from_server: {:?}", e));
+                }
+                // Se cortó la conexión: si corresponde, se reconecta y se sigue leyendo de la nueva.
+                let Some(reconnector) = &reconnector_c else {
+                    break;
+                };
+                if reconnector.is_closed() {
+                    break;
+                }
+                match reconnector.reconnect() {
+                    Ok((new_stream, replayed_ids)) => listener.update_stream(new_stream, replayed_ids),
+                    Err(e) => {
+                        logger_c.log(format!("Error al reconectar: {:?}", e));
+                        break;
+                    }
+                }
+            }
+            // Ya no se leerá del servidor: terminan los rx de los publish recibidos.
+            if let Ok(mut router) = router_c.lock() {
+                router.close();
             }
         });
 
-        Ok((mqtt_client, publish_msg_rx, listener_handle))
+        Ok((mqtt_client, publish_msg_rx, events_rx, listener_handle))
     }
 
     /// Función de la librería de MQTTClient para realizar un publish.
@@ -65,22 +245,143 @@ impl MQTTClient {
         qos: u8,
     ) -> Result<PublishMessage, Error> {
         // Esto solamente crea y devuelve el mensaje
-        let msg = self.msg_creator.create_publish_msg(topic, payload, qos)?;
+        let msg = self.lock_msg_creator()?.create_publish_msg(topic, payload, qos)?;
+        let (msg, _) = self.send_publish(msg, topic, true)?;
+        Ok(msg)
+    }
+
+    /// Como `mqtt_publish`, pero con el flag de retain.
+    pub fn mqtt_publish_retained(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        qos: u8,
+    ) -> Result<PublishMessage, Error> {
+        let msg = self
+            .lock_msg_creator()?
+            .create_publish_msg_with_retain(topic, payload, qos, true)?;
+        let (msg, _) = self.send_publish(msg, topic, true)?;
+        Ok(msg)
+    }
+
+    /// Como `mqtt_publish`, pero no espera el ack: sólo bloquea si ya hay `max_in_flight` mensajes esperando
+    /// el suyo (ver `set_max_in_flight`). Las retransmisiones se hacen en segundo plano.
+    /// Devuelve también un `AckHandle` con el que esperar o consultar si llegó el ack.
+    pub fn mqtt_publish_pipelined(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        qos: u8,
+    ) -> Result<(PublishMessage, AckHandle), Error> {
+        let msg = self.lock_msg_creator()?.create_publish_msg(topic, payload, qos)?;
+        self.send_publish(msg, topic, false)
+    }
+
+    /// Establece cuántos publish con qos 1 pueden estar esperando su ack a la vez, al usar `mqtt_publish_pipelined`.
+    pub fn set_max_in_flight(&mut self, max_in_flight: usize) -> Result<(), Error> {
+        self.retransmitter.set_max_in_flight(max_in_flight)
+    }
+
+    /// Función de la librería de MQTTClient para realizar un publish con propiedades de MQTT 5
+    /// (user properties, message expiry interval, response topic, correlation data).
+    /// Devuelve error si la conexión no es MQTT 5.
+    pub fn mqtt_publish_with_properties(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        qos: u8,
+        properties: MqttProperties,
+    ) -> Result<PublishMessage, Error> {
+        let msg = self
+            .lock_msg_creator()?
+            .create_publish_msg_with_properties(topic, payload, qos, properties)?;
+        let (msg, _) = self.send_publish(msg, topic, true)?;
+        Ok(msg)
+    }
+
+    /// Responde a la `request` recibida (MQTT 5): publica `payload` a su response topic,
+    /// con sus mismos correlation data. Devuelve error si la request no tenía response topic.
+    pub fn mqtt_respond_to(
+        &mut self,
+        request: &PublishMessage,
+        payload: &[u8],
+        qos: u8,
+    ) -> Result<PublishMessage, Error> {
+        let response_topic = request.get_response_topic().ok_or_else(|| {
+            Error::new(ErrorKind::InvalidInput, "El mensaje recibido no tiene response topic.")
+        })?;
+        let mut properties = MqttProperties::new();
+        properties.correlation_data = request.get_correlation_data();
+        self.mqtt_publish_with_properties(&response_topic, payload, qos, properties)
+    }
+
+    /// Devuelve la versión del protocolo con la que se conectó el cliente.
+    pub fn get_protocol_version(&self) -> ProtocolVersion {
+        self.lock_msg_creator()
+            .map(|creator| creator.get_protocol_version())
+            .unwrap_or_default()
+    }
+
+    /// Envía el publish `msg` y devuelve el mensaje enviado, con su topic completo aunque se haya enviado con topic alias,
+    /// y el `AckHandle` de su ack. Si `wait_for_ack`, espera el ack antes de volver.
+    /// Con reconexión automática, si no hay conexión lo almacena para enviarlo al reconectar (y el handle queda resuelto).
+    fn send_publish(
+        &mut self,
+        mut msg: PublishMessage,
+        topic: &str,
+        wait_for_ack: bool,
+    ) -> Result<(PublishMessage, AckHandle), Error> {
+        if let Some(reconnector) = &self.reconnector {
+            if reconnector.buffer_if_offline(&msg, topic)? {
+                self.logger.log(format!("Mqtt: sin conexión, publish almacenado: {:?}", msg.get_packet_id()));
+                msg.set_topic(topic);
+                return Ok((msg.clone(), AckHandle::resolved(msg.get_packet_id())));
+            }
+        }
+
         // Se lo paso al retransmitter y que él se encargue de mandarlo, y retransmitirlo si es necesario
-        self.retransmitter.send_and_retransmit(&msg)?;
+        let send_result = if wait_for_ack {
+            self.retransmitter
+                .send_and_retransmit(&msg)
+                .map(|_| AckHandle::resolved(msg.get_packet_id()))
+        } else {
+            self.retransmitter.send_without_waiting(&msg)
+        };
+        let ack_handle = match (send_result, &self.reconnector) {
+            (Ok(ack_handle), _) => ack_handle,
+            (Err(_), Some(reconnector)) => {
+                reconnector.buffer_after_failure(&msg, topic)?;
+                AckHandle::resolved(msg.get_packet_id())
+            }
+            (Err(e), None) => {
+                if let Some(packet_id) = msg.get_packet_id() {
+                    self.lock_msg_creator()?.release_packet_id(packet_id);
+                }
+                return Err(e);
+            }
+        };
 
         //println!("-----------------\n Mqtt: publish enviado: \n   {:?}", msg);
         self.logger.log(format!("-----------------\n Mqtt: publish enviado: \n   {:?}", msg));
 
-        Ok(msg)
+        msg.set_topic(topic);
+        Ok((msg, ack_handle))
     }
 
     /// Función de la librería de MQTTClient para realizar un subscribe.
     pub fn mqtt_subscribe(&mut self, topics: Vec<(String, u8)>) -> Result<(), Error> {
+        // Con reconexión automática, la suscripción se renueva al reconectar.
+        if let Some(reconnector) = &self.reconnector {
+            reconnector.add_subscriptions(&topics);
+        }
         // Esto solamente crea y devuelve el mensaje
-        let msg = self.msg_creator.create_subscribe_msg(topics)?;
+        let msg = self.lock_msg_creator()?.create_subscribe_msg(topics)?;
         // Se lo paso al retransmitter y que él se encargue de mandarlo, y retransmitirlo si es necesario
-        self.retransmitter.send_and_retransmit(&msg)?;
+        if let Err(e) = self.retransmitter.send_and_retransmit(&msg) {
+            if self.reconnector.is_none() {
+                return Err(e);
+            }
+        }
         
         println!("-----------------\n Mqtt: subscribe enviado: \n   {:?}", msg);
         self.logger.log(format!("-----------------\n Mqtt: subscribe enviado: \n   {:?}", msg));
@@ -88,10 +389,107 @@ impl MQTTClient {
         Ok(())
     }
 
+    /// Se suscribe al `topic`, y sus mensajes pasan a recibirse por el rx devuelto, en lugar de por el rx general.
+    pub fn mqtt_subscribe_with_channel(&mut self, topic: &str, qos: u8) -> Result<Receiver<PublishMessage>, Error> {
+        let rx = self.lock_router()?.add_channel(topic);
+        self.subscribe_to_routed_topic(topic, qos)?;
+        Ok(rx)
+    }
+
+    /// Se suscribe al `topic`, y sus mensajes pasan a procesarse con `handler`. El handler se ejecuta
+    /// en el hilo que lee del servidor, por lo que no debería bloquear.
+    pub fn mqtt_subscribe_with_handler<F>(&mut self, topic: &str, qos: u8, handler: F) -> Result<(), Error>
+    where
+        F: FnMut(PublishMessage) + Send + 'static,
+    {
+        self.lock_router()?.add_handler(topic, Box::new(handler));
+        self.subscribe_to_routed_topic(topic, qos)
+    }
+
+    /// Se suscribe al `topic`, y devuelve un rx por el que se reciben sus payloads ya decodificados,
+    /// o el error si alguno no pudo decodificarse.
+    pub fn mqtt_subscribe_typed<T>(&mut self, topic: &Topic<T>, qos: u8) -> Result<Receiver<Result<T, Error>>, Error>
+    where
+        T: MqttPayload + Send + 'static,
+    {
+        let (decoded_tx, decoded_rx) = mpsc::channel();
+        self.mqtt_subscribe_with_handler(topic.get_name(), qos, move |msg| {
+            // Si ya nadie recibe los valores, no es un error.
+            let _ = decoded_tx.send(T::decode(&msg.get_payload()));
+        })?;
+        Ok(decoded_rx)
+    }
+
+    /// Como `mqtt_subscribe_typed`, pero cada payload decodificado se recibe junto con el timestamp con el que
+    /// lo publicó su emisor (ie para descartar los que llegan desordenados).
+    pub fn mqtt_subscribe_typed_with_timestamp<T>(
+        &mut self,
+        topic: &Topic<T>,
+        qos: u8,
+    ) -> Result<TimestampedReceiver<T>, Error>
+    where
+        T: MqttPayload + Send + 'static,
+    {
+        let (decoded_tx, decoded_rx) = mpsc::channel();
+        self.mqtt_subscribe_with_handler(topic.get_name(), qos, move |msg| {
+            let decoded = T::decode(&msg.get_payload()).map(|value| (value, msg.get_timestamp()));
+            // Si ya nadie recibe los valores, no es un error.
+            let _ = decoded_tx.send(decoded);
+        })?;
+        Ok(decoded_rx)
+    }
+
+    /// Publica `value` al `topic`, codificado como payload.
+    pub fn mqtt_publish_typed<T: MqttPayload>(
+        &mut self,
+        topic: &Topic<T>,
+        value: &T,
+        qos: u8,
+    ) -> Result<PublishMessage, Error> {
+        self.mqtt_publish(topic.get_name(), &value.encode()?, qos)
+    }
+
+    /// Se suscribe al `topic` ya registrado en el router; si falla, quita su ruta.
+    fn subscribe_to_routed_topic(&mut self, topic: &str, qos: u8) -> Result<(), Error> {
+        if let Err(e) = self.mqtt_subscribe(vec![(topic.to_string(), qos)]) {
+            self.lock_router()?.remove_route(topic);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Envía un pingreq al servidor y espera su pingresp, como mucho `timeout`.
+    /// Devuelve cuánto tardó en llegar la respuesta.
+    pub fn mqtt_ping(&mut self, timeout: Duration) -> Result<Duration, Error> {
+        // Se descartan los pingresp de pings anteriores que llegaron tarde.
+        while self.pingresp_rx.try_recv().is_ok() {}
+        let start = Instant::now();
+        self.retransmitter.send_without_waiting(&PingMessage::new_pingreq())?;
+        self.pingresp_rx
+            .recv_timeout(timeout)
+            .map_err(|_| Error::new(ErrorKind::TimedOut, "No se recibió el pingresp a tiempo."))?;
+        Ok(start.elapsed())
+    }
+
     /// Función de la librería de MQTTClient para terminar de manera voluntaria la conexión con el server.
     pub fn mqtt_disconnect(&mut self) -> Result<(), Error> {
-        let msg = self.msg_creator.create_disconnect_msg()?;
+        if let Some(reconnector) = &self.reconnector {
+            reconnector.close();
+        }
+        let msg = self.lock_msg_creator()?.create_disconnect_msg()?;
         self.retransmitter.send_and_shutdown_stream(msg)?;
         Ok(())
     }
+
+    fn lock_router(&self) -> Result<MutexGuard<'_, TopicRouter>, Error> {
+        self.router
+            .lock()
+            .map_err(|_| Error::other("Error al tomar lock del router."))
+    }
+
+    fn lock_msg_creator(&self) -> Result<MutexGuard<'_, MessageCreator>, Error> {
+        self.msg_creator
+            .lock()
+            .map_err(|_| Error::other("Error al tomar lock del msg creator."))
+    }
 }
@@ -6,7 +6,8 @@ use std::time::Duration;
 use crate::logging::string_logger::StringLogger;
 use crate::mqtt::messages::{
     connack_message::ConnackMessage, connect_message::ConnectMessage,
-    connect_return_code::ConnectReturnCode, packet_type::PacketType,
+    connect_return_code::ConnectReturnCode, mqtt_properties::MqttProperties,
+    packet_type::PacketType,
 };
 use crate::mqtt::mqtt_utils::fixed_header::FixedHeader;
 use crate::mqtt::mqtt_utils::utils::{
@@ -14,28 +15,63 @@ use crate::mqtt::mqtt_utils::utils::{
 };
 use crate::mqtt::mqtt_utils::will_message_utils::will_message::WillMessageData;
 
+use super::credentials::Credentials;
 use super::mqtt_client::ClientStreamType;
 
 pub struct MqttClientConnector {
     stream: ClientStreamType,
     logger: StringLogger,
+    connack_properties: Option<MqttProperties>,
 }
 
 impl MqttClientConnector {
+    /// Se conecta al servidor, autenticándose con `credentials`. Si se reciben `properties`, la conexión es MQTT 5
+    /// y se envían en el connect. Devuelve el stream, y las propiedades del connack recibido (sólo en MQTT 5).
     pub fn mqtt_connect_to_broker(
         client_id: String,
         addr: &SocketAddr,
         will: Option<WillMessageData>,
+        credentials: &Credentials,
+        properties: Option<MqttProperties>,
         logger: StringLogger,
-    ) -> Result<ClientStreamType, Error> {
+    ) -> Result<(ClientStreamType, Option<MqttProperties>), Error> {
         // Intenta conectar al servidor MQTT
         let stream = TcpStream::connect(addr)
-            .map_err(|_| io::Error::new(io::ErrorKind::Other, "Error para establecer conexión con servidor."))?;
+            .map_err(|_| io::Error::other("Error para establecer conexión con servidor."))?;
+        Self::mqtt_connect_over_stream(stream.into(), client_id, will, credentials, properties, logger)
+    }
+
+    /// Como `mqtt_connect_to_broker`, pero sobre un `stream` ya establecido con el servidor (ie en memoria).
+    pub fn mqtt_connect_over_stream(
+        stream: ClientStreamType,
+        client_id: String,
+        will: Option<WillMessageData>,
+        credentials: &Credentials,
+        properties: Option<MqttProperties>,
+        logger: StringLogger,
+    ) -> Result<(ClientStreamType, Option<MqttProperties>), Error> {
         let mut connector = Self {
             stream: stream.try_clone()?, // obs: como no devuelvo Self, esta copia del stream se dropea al salir de esta función y no molesta.
             logger,
+            connack_properties: None,
         };
 
+        let mut msg = Self::create_connect_msg(client_id, will, credentials, properties);
+        connector.logger.log("Mqtt: Enviando connect msg.".to_string());
+        connector.send_and_retransmit(&mut msg)?;
+        connector.logger.log("Mqtt: connack recibido.".to_string());
+
+        Ok((stream, connector.connack_properties))
+    }
+
+    /// Crea el mensaje connect, con el will message si lo hay, y el usuario y contraseña de `credentials`.
+    /// Si se reciben `properties`, el connect es de MQTT 5. Lo usan tanto el cliente sincrónico como el asincrónico.
+    pub fn create_connect_msg(
+        client_id: String,
+        will: Option<WillMessageData>,
+        credentials: &Credentials,
+        properties: Option<MqttProperties>,
+    ) -> ConnectMessage {
         // Aux: sintaxis es let (a, b) = if condicion { (a_si_true, b_si_true) } else { (a_si_false, b_si_false) };
         let (will_msg_content, will_topic, will_qos, _will_retain) = if let Some(will) = will {
             (
@@ -53,16 +89,29 @@ impl MqttClientConnector {
             client_id,
             will_topic,
             will_msg_content,
-            Some("usuario0".to_string()),
-            Some("rustx123".to_string()),
+            Some(credentials.get_username()),
+            Some(credentials.get_password()),
             will_qos,
         );
+        if let Some(properties) = properties {
+            msg.set_mqtt5_properties(properties);
+        }
+        msg
+    }
 
-        connector.logger.log("Mqtt: Enviando connect msg.".to_string());
-        connector.send_and_retransmit(&mut msg)?;
-        connector.logger.log("Mqtt: connack recibido.".to_string());
-
-        Ok(stream)
+    /// Analiza el connack recibido en `connack_bytes`: devuelve error si la conexión no fue aceptada por el servidor,
+    /// y si lo fue, sus propiedades (sólo en MQTT 5).
+    pub fn analyze_connack(connack_bytes: &[u8]) -> Result<Option<MqttProperties>, Error> {
+        let msg = ConnackMessage::from_bytes(connack_bytes)?;
+        println!("   Mensaje conn ack completo recibido: {:?}", msg);
+        if msg.get_connect_return_code() == ConnectReturnCode::ConnectionAccepted {
+            Ok(msg.get_properties().cloned())
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "La conexión no fue aceptada.",
+            ))
+        }
     }
     
     /// Envía el mensaje `msg` recibido una vez, espera por el ack, y si es necesario lo retransmite una cierta
@@ -103,8 +152,7 @@ impl MqttClientConnector {
 
         if !received_ack {
             // Ya salí del while, retransmití muchas veces y nunca recibí el ack, desisto.
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "MAXRETRIES, se retransmitió el connect sin éxito.",
             ));
         }
@@ -150,7 +198,7 @@ impl MqttClientConnector {
                 } else {
                     // Éste es un error real
                     println!("Error al leer: {:?}", e);
-                    Err(Error::new(ErrorKind::Other, "Error al leer."))
+                    Err(Error::other("Error al leer."))
                 }
             }
         }
@@ -171,16 +219,7 @@ impl MqttClientConnector {
             &fixed_header_buf,
         )?;
         // Entonces tengo el mensaje completo
-        let msg = ConnackMessage::from_bytes(&recvd_bytes)?; //
-        println!("   Mensaje conn ack completo recibido: {:?}", msg);
-        let ret = msg.get_connect_return_code();
-        if ret == ConnectReturnCode::ConnectionAccepted {
-            Ok(())
-        } else {
-            Err(Error::new(
-                ErrorKind::InvalidData,
-                "La conexión no fue aceptada.",
-            ))
-        }
+        self.connack_properties = Self::analyze_connack(&recvd_bytes)?;
+        Ok(())
     }
 }
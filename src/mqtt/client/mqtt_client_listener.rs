@@ -1,41 +1,74 @@
-use std::sync::mpsc::Sender;
+use std::sync::{mpsc::Sender, Arc, Mutex};
 
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 
 use crate::mqtt::messages::{
-    packet_type::PacketType, puback_message::PubAckMessage, publish_message::PublishMessage,
-    suback_message::SubAckMessage,
+    packet_type::PacketType, protocol_version::ProtocolVersion, puback_message::PubAckMessage,
+    publish_message::PublishMessage, suback_message::SubAckMessage,
 };
 
 use crate::mqtt::client::ack_message::ACKMessage;
 use crate::mqtt::mqtt_utils::fixed_header::FixedHeader;
+use crate::mqtt::mqtt_utils::packet_id_allocator::PacketIdAllocator;
 use crate::mqtt::mqtt_utils::utils::{
     get_fixed_header_from_stream, get_whole_message_in_bytes_from_stream, is_disconnect_msg,
     send_puback, shutdown,
 };
 
 use super::mqtt_client::ClientStreamType;
+use super::topic_router::TopicRouter;
 
 #[derive(Debug)]
 pub struct MQTTClientListener {
     stream: ClientStreamType,
-    client_tx: Sender<PublishMessage>,
+    router: Arc<Mutex<TopicRouter>>, // Compartido con el MQTTClient, que registra las rutas.
     ack_tx: Sender<ACKMessage>,
+    pingresp_tx: Sender<()>,
+    protocol_version: ProtocolVersion,
+    ignored_ack_ids: Vec<u16>, // Acks de mensajes reenviados al reconectar, que nadie espera.
+    packet_ids: Arc<Mutex<PacketIdAllocator>>, // Para liberar los ids de los mensajes reenviados.
 }
 
 impl MQTTClientListener {
     pub fn new(
         stream: ClientStreamType,
-        client_tx: Sender<PublishMessage>,
+        router: Arc<Mutex<TopicRouter>>,
         ack_tx: Sender<ACKMessage>,
+        pingresp_tx: Sender<()>,
+        protocol_version: ProtocolVersion,
+        packet_ids: Arc<Mutex<PacketIdAllocator>>,
     ) -> Self {
         MQTTClientListener {
             stream,
-            client_tx,
+            router,
             ack_tx,
+            pingresp_tx,
+            protocol_version,
+            ignored_ack_ids: vec![],
+            packet_ids,
         }
     }
 
+    /// Pasa a leer del `stream` de la nueva conexión, tras una reconexión.
+    /// Los acks de `replayed_ids` no se informan, ya que corresponden a mensajes reenviados al reconectar.
+    pub fn update_stream(&mut self, stream: ClientStreamType, replayed_ids: Vec<u16>) {
+        self.stream = stream;
+        self.ignored_ack_ids = replayed_ids;
+    }
+
+    /// Devuelve si el ack de `packet_id` corresponde a un mensaje reenviado al reconectar, y deja de esperarlo
+    /// liberando su id.
+    fn is_ignored_ack(&mut self, packet_id: u16) -> bool {
+        if let Some(pos) = self.ignored_ack_ids.iter().position(|id| *id == packet_id) {
+            self.ignored_ack_ids.remove(pos);
+            if let Ok(mut packet_ids) = self.packet_ids.lock() {
+                packet_ids.release(packet_id);
+            }
+            return true;
+        }
+        false
+    }
+
     /// Función que ejecutará un hilo de MQTTClient, dedicado exclusivamente a la lectura.
     pub fn read_from_server(&mut self) -> Result<(), Error> {
         let mut fixed_header_info: ([u8; 2], FixedHeader);
@@ -58,7 +91,10 @@ impl MQTTClientListener {
                     println!("Se cerró la conexión con server.");
                     break;
                 }
-                Err(_) => todo!(),
+                Err(e) => {
+                    println!("Error al leer del stream con server: {:?}.", e);
+                    return Err(e);
+                }
             }
         }
 
@@ -80,12 +116,13 @@ impl MQTTClientListener {
             PacketType::Publish => self.handle_publish(msg_bytes)?,
             PacketType::Puback => self.handle_puback(msg_bytes)?,
             PacketType::Suback => self.handle_suback(msg_bytes)?,
+            PacketType::Pingresp => self.handle_pingresp(),
             _ => {
                 println!(
                     "   ERROR: tipo desconocido: recibido: \n   {:?}",
                     fixed_header
                 );
-                return Err(Error::new(ErrorKind::Other, "Tipo desconocido."));
+                return Err(Error::other("Tipo desconocido."));
             }
         };
 
@@ -94,18 +131,26 @@ impl MQTTClientListener {
 
     fn handle_publish(&mut self, msg_bytes: Vec<u8>) -> Result<(), Error> {
         println!("Mqtt cliente leyendo: RECIBO MENSAJE TIPO PUBLISH");
-        let msg = PublishMessage::from_bytes(msg_bytes)?;
+        let msg = PublishMessage::from_bytes_with_version(msg_bytes, self.protocol_version)?;
         send_puback(&msg, &mut self.stream)?;
-        // Envía PublishMessage a la app
-        match self.client_tx.send(msg) {
-            Ok(_) => println!("Mqtt cliente leyendo: se envía por tx exitosamente."),
-            Err(_) => println!("Mqtt cliente leyendo: error al enviar por tx."),
+        // Envía PublishMessage a la app, por la ruta de su topic
+        let destination = self
+            .router
+            .lock()
+            .map_err(|_| Error::other("Error al tomar lock del router."))?
+            .destination_of(&msg);
+        match destination.map(|destination| destination.deliver(msg)) {
+            Some(true) => println!("Mqtt cliente leyendo: se envía por tx exitosamente."),
+            _ => println!("Mqtt cliente leyendo: error al enviar por tx."),
         };
         Ok(())
     }
 
-    fn handle_puback(&self, msg_bytes: Vec<u8>) -> Result<(), Error> {
+    fn handle_puback(&mut self, msg_bytes: Vec<u8>) -> Result<(), Error> {
         let msg = PubAckMessage::msg_from_bytes(msg_bytes)?;
+        if self.is_ignored_ack(msg.get_packet_id()) {
+            return Ok(());
+        }
         // Avisa que llegó el ack
         match self.ack_tx.send(ACKMessage::PubAck(msg)) {
             Ok(_) => println!("PubAck enviado por tx exitosamente."),
@@ -114,8 +159,11 @@ impl MQTTClientListener {
         Ok(())
     }
 
-    fn handle_suback(&self, msg_bytes: Vec<u8>) -> Result<(), Error> {
-        let msg = SubAckMessage::from_bytes(msg_bytes)?;
+    fn handle_suback(&mut self, msg_bytes: Vec<u8>) -> Result<(), Error> {
+        let msg = SubAckMessage::from_bytes_with_version(msg_bytes, self.protocol_version)?;
+        if self.is_ignored_ack(msg.get_packet_id()) {
+            return Ok(());
+        }
         // Avisa que llegó el ack
         match self.ack_tx.send(ACKMessage::SubAck(msg)) {
             Ok(_) => println!("SubAck enviado por tx exitosamente."),
@@ -123,6 +171,11 @@ impl MQTTClientListener {
         }
         Ok(())
     }
+
+    fn handle_pingresp(&mut self) {
+        // Avisa que llegó el pingresp; si nadie lo espera, no es un error.
+        let _ = self.pingresp_tx.send(());
+    }
 }
 
 /*impl Clone for MQTTClientListener {
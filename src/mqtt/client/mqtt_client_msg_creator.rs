@@ -1,19 +1,45 @@
 use crate::mqtt::messages::{
-    disconnect_message::DisconnectMessage, publish_flags::PublishFlags,
-    publish_message::PublishMessage, subscribe_message::SubscribeMessage,
+    disconnect_message::DisconnectMessage, mqtt_properties::MqttProperties,
+    protocol_version::ProtocolVersion, publish_flags::PublishFlags,
+    publish_message::{PublishMessage, APP_ID_USER_PROPERTY}, reason_code::ReasonCode,
+    subscribe_message::SubscribeMessage,
+    unsubscribe_message::UnsubscribeMessage,
 };
 
-use std::io::Error;
+use crate::mqtt::mqtt_utils::packet_id_allocator::PacketIdAllocator;
+
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::sync::{Arc, Mutex};
 
 #[derive(Debug)]
 pub struct MessageCreator {
-    available_packet_id: u16,
+    packet_ids: Arc<Mutex<PacketIdAllocator>>, // Compartido con quienes liberan los ids al llegar los acks.
+    protocol_version: ProtocolVersion,
+    topic_alias_maximum: u16,             // MQTT 5: informado por el servidor en el connack.
+    topic_aliases: HashMap<String, u16>,  // MQTT 5: alias ya asignados a cada topic en esta conexión.
+    app_id: String,                       // MQTT 5: se envía en cada publish como user property.
 }
 
 impl MessageCreator {
     pub fn new() -> MessageCreator {
         MessageCreator {
-            available_packet_id: 0,
+            packet_ids: Arc::new(Mutex::new(PacketIdAllocator::new())),
+            protocol_version: ProtocolVersion::V311,
+            topic_alias_maximum: 0,
+            topic_aliases: HashMap::new(),
+            app_id: String::new(),
+        }
+    }
+
+    /// Crea un MessageCreator para una conexión MQTT 5 del cliente `app_id`, en la que el servidor acepta hasta
+    /// `topic_alias_maximum` topic aliases. Sus publish llevan `app_id` como user property.
+    pub fn new_v5(topic_alias_maximum: u16, app_id: &str) -> MessageCreator {
+        MessageCreator {
+            protocol_version: ProtocolVersion::V5,
+            topic_alias_maximum,
+            app_id: app_id.to_string(),
+            ..Self::new()
         }
     }
 
@@ -24,39 +50,153 @@ impl MessageCreator {
         payload: &[u8],
         qos: u8,
     ) -> Result<PublishMessage, Error> {
-        let packet_id = self.generate_packet_id();
+        self.create_publish_msg_with_retain(topic, payload, qos, false)
+    }
+
+    /// Como `create_publish_msg`, pero con el flag de retain según `retain`.
+    pub fn create_publish_msg_with_retain(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        qos: u8,
+        retain: bool,
+    ) -> Result<PublishMessage, Error> {
         // Creo un msj publish
-        let flags = PublishFlags::new(0, qos, 0)?;
-        let publish_msg = PublishMessage::new(flags, topic, Some(packet_id), payload)?;
+        let mut publish_msg = self.create_publish_msg_with_new_id(topic, payload, qos, retain)?;
+        if self.protocol_version.is_v5() {
+            self.set_properties_to(&mut publish_msg, MqttProperties::new());
+        }
+
+        Ok(publish_msg)
+    }
+
+    /// Crea y devuelve el PublishMessage de MQTT 5, con las propiedades recibidas.
+    /// Devuelve error si la conexión no es MQTT 5.
+    pub fn create_publish_msg_with_properties(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        qos: u8,
+        properties: MqttProperties,
+    ) -> Result<PublishMessage, Error> {
+        if !self.protocol_version.is_v5() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Las propiedades sólo pueden enviarse en una conexión MQTT 5.",
+            ));
+        }
+        let mut publish_msg = self.create_publish_msg_with_new_id(topic, payload, qos, false)?;
+        self.set_properties_to(&mut publish_msg, properties);
 
         Ok(publish_msg)
     }
 
+    /// Crea el PublishMessage con un packet_id libre (con qos 0 no lleva packet_id). Si falla, el id se libera.
+    fn create_publish_msg_with_new_id(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        qos: u8,
+        retain: bool,
+    ) -> Result<PublishMessage, Error> {
+        let packet_id = if qos > 0 { Some(self.generate_packet_id()?) } else { None };
+        let msg_res = PublishFlags::new(0, qos, retain as u8)
+            .and_then(|flags| PublishMessage::new(flags, topic, packet_id, payload));
+        if let (Err(_), Some(packet_id)) = (&msg_res, packet_id) {
+            self.release_packet_id(packet_id);
+        }
+        msg_res
+    }
+
+    /// Setea las propiedades al mensaje, usando un topic alias si el servidor lo permite:
+    /// la primera vez que se publica a un topic se le asigna uno, y las siguientes se envía sólo el alias.
+    /// Agrega el app id como user property, salvo que ya se haya indicado uno.
+    fn set_properties_to(&mut self, publish_msg: &mut PublishMessage, mut properties: MqttProperties) {
+        if properties.get_user_property(APP_ID_USER_PROPERTY).is_none() {
+            properties.add_user_property(APP_ID_USER_PROPERTY, &self.app_id);
+        }
+        let topic = publish_msg.get_topic();
+        if let Some(alias) = self.topic_aliases.get(&topic) {
+            properties.topic_alias = Some(*alias);
+            publish_msg.set_topic("");
+        } else if (self.topic_aliases.len() as u16) < self.topic_alias_maximum {
+            let alias = self.topic_aliases.len() as u16 + 1;
+            self.topic_aliases.insert(topic, alias);
+            properties.topic_alias = Some(alias);
+        }
+        publish_msg.set_mqtt5_properties(properties);
+    }
+
     /// Recibe un vector de topics a los cuales cliente desea suscribirse.
     /// Crea y devuelve el SubscribeMessage.
     pub fn create_subscribe_msg(
         &mut self,
         topics_to_subscribe: Vec<(String, u8)>,
     ) -> Result<SubscribeMessage, Error> {
-        let packet_id = self.generate_packet_id();
+        let packet_id = self.generate_packet_id()?;
         // Construyo subscribe
-        let subscribe_msg = SubscribeMessage::new(packet_id, topics_to_subscribe);        
+        let mut subscribe_msg = SubscribeMessage::new(packet_id, topics_to_subscribe);
+        if self.protocol_version.is_v5() {
+            subscribe_msg.set_mqtt5_properties(MqttProperties::new());
+        }
 
         Ok(subscribe_msg)
     }
 
+    /// Recibe un vector de topics de los cuales el cliente desea desuscribirse.
+    /// Crea y devuelve el UnsubscribeMessage.
+    pub fn create_unsubscribe_msg(&mut self, topics: Vec<String>) -> Result<UnsubscribeMessage, Error> {
+        let packet_id = self.generate_packet_id()?;
+        let mut unsubscribe_msg = UnsubscribeMessage::new(packet_id, topics);
+        if self.protocol_version.is_v5() {
+            unsubscribe_msg.set_mqtt5_properties(MqttProperties::new());
+        }
+
+        Ok(unsubscribe_msg)
+    }
+
     /// Crea y devuelve un DisconnectMessage.
     pub fn create_disconnect_msg(&mut self) -> Result<DisconnectMessage, Error> {
-        let msg = DisconnectMessage::new();
+        let msg = match self.protocol_version {
+            ProtocolVersion::V5 => DisconnectMessage::new_v5(ReasonCode::Success, MqttProperties::new()),
+            ProtocolVersion::V311 => DisconnectMessage::new(),
+        };
         Ok(msg)
     }
 
+    /// Olvida los topic aliases asignados, que sólo valían para la conexión anterior,
+    /// y guarda el máximo informado por el servidor en la nueva conexión.
+    pub fn reset_topic_aliases(&mut self, topic_alias_maximum: u16) {
+        self.topic_aliases.clear();
+        if self.protocol_version.is_v5() {
+            self.topic_alias_maximum = topic_alias_maximum;
+        }
+    }
+
+    /// Devuelve la versión del protocolo de la conexión.
+    pub fn get_protocol_version(&self) -> ProtocolVersion {
+        self.protocol_version
+    }
+
+    /// Devuelve el allocator de packet_ids, para compartirlo con quienes deben liberar los ids.
+    pub fn get_packet_id_allocator(&self) -> Arc<Mutex<PacketIdAllocator>> {
+        self.packet_ids.clone()
+    }
+
+    /// Libera el `packet_id` de un mensaje que ya no espera su ack.
+    pub fn release_packet_id(&self, packet_id: u16) {
+        if let Ok(mut packet_ids) = self.packet_ids.lock() {
+            packet_ids.release(packet_id);
+        }
+    }
+
     /// Devuelve el packet_id a usar para el siguiente mensaje enviado.
-    /// Incrementa en 1 el atributo correspondiente, debido a la llamada anterior, y devuelve el valor a ser usado
-    /// en el envío para el cual fue llamada esta función.
-    fn generate_packet_id(&mut self) -> u16 {
-        self.available_packet_id += 1;
-        self.available_packet_id
+    /// El id queda en uso hasta que se lo libere, al recibir el ack del mensaje.
+    fn generate_packet_id(&mut self) -> Result<u16, Error> {
+        self.packet_ids
+            .lock()
+            .map_err(|_| Error::other("Error al tomar lock del allocator de packet_ids."))?
+            .allocate()
     }
 }
 
@@ -65,3 +205,39 @@ impl Default for MessageCreator {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::MessageCreator;
+    use crate::mqtt::messages::protocol_version::ProtocolVersion;
+
+    #[test]
+    fn test_1_en_mqtt5_el_segundo_publish_a_un_topic_usa_solo_el_topic_alias() {
+        let mut creator = MessageCreator::new_v5(1, "Sistema-Camaras");
+        let first = creator.create_publish_msg("cam", &[1], 1).unwrap();
+        let second = creator.create_publish_msg("cam", &[2], 1).unwrap();
+        let other = creator.create_publish_msg("inc", &[3], 1).unwrap();
+
+        assert_eq!((first.get_topic().as_str(), first.get_topic_alias()), ("cam", Some(1)));
+        assert_eq!((second.get_topic().as_str(), second.get_topic_alias()), ("", Some(1)));
+        // Se alcanzó el máximo de alias permitido por el servidor.
+        assert_eq!((other.get_topic().as_str(), other.get_topic_alias()), ("inc", None));
+        assert_eq!(other.get_protocol_version(), ProtocolVersion::V5);
+        // Todos llevan el app id del cliente.
+        for msg in [first, second, other] {
+            assert_eq!(msg.get_app_id(), Some("Sistema-Camaras"));
+        }
+    }
+
+    #[test]
+    fn test_2_en_mqtt311_no_se_pueden_enviar_propiedades() {
+        let mut creator = MessageCreator::new();
+        let msg = creator.create_publish_msg("cam", &[1], 1).unwrap();
+
+        assert_eq!(msg.get_protocol_version(), ProtocolVersion::V311);
+        assert_eq!(msg.get_app_id(), None);
+        assert!(creator
+            .create_publish_msg_with_properties("cam", &[1], 1, Default::default())
+            .is_err());
+    }
+}
@@ -0,0 +1,219 @@
+use std::{
+    io::{Error, ErrorKind},
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc, Mutex, MutexGuard,
+    },
+    thread,
+};
+
+use crate::logging::string_logger::StringLogger;
+use crate::mqtt::messages::{mqtt_properties::MqttProperties, publish_message::PublishMessage};
+use crate::mqtt::mqtt_utils::{
+    utils::write_message_to_stream, will_message_utils::will_message::WillMessageData,
+};
+
+use super::{
+    connection_event::ConnectionEvent, credentials::Credentials, mqtt_client::ClientStreamType,
+    mqtt_client_connector::MqttClientConnector, mqtt_client_msg_creator::MessageCreator,
+    offline_buffer::OfflineBuffer, reconnect_policy::ReconnectPolicy,
+};
+
+/// Datos con los que se realizó la conexión, para repetirla al reconectar.
+#[derive(Debug, Clone)]
+pub struct ConnectionParams {
+    pub client_id: String,
+    pub addr: SocketAddr,
+    pub will: Option<WillMessageData>,
+    pub credentials: Credentials,
+    pub properties: Option<MqttProperties>,
+}
+
+/// Parte interna de `MQTTClient` encargada de la reconexión automática: al perderse la conexión,
+/// reintenta el connect según la `ReconnectPolicy`, renueva las suscripciones y envía los publish almacenados.
+/// Es compartida entre el `MQTTClient` y el hilo que lee del servidor.
+#[derive(Debug)]
+pub struct Reconnector {
+    params: ConnectionParams,
+    policy: ReconnectPolicy,
+    stream: Arc<Mutex<ClientStreamType>>, // Compartido con el Retransmitter.
+    msg_creator: Arc<Mutex<MessageCreator>>,
+    subscriptions: Mutex<Vec<(String, u8)>>,
+    offline_buffer: Mutex<OfflineBuffer>,
+    closed: AtomicBool,
+    events_tx: Mutex<Sender<ConnectionEvent>>,
+    logger: StringLogger,
+}
+
+impl Reconnector {
+    pub fn new(
+        params: ConnectionParams,
+        policy: ReconnectPolicy,
+        stream: Arc<Mutex<ClientStreamType>>,
+        msg_creator: Arc<Mutex<MessageCreator>>,
+        events_tx: Sender<ConnectionEvent>,
+        logger: StringLogger,
+    ) -> Self {
+        let offline_buffer = OfflineBuffer::new(policy.max_buffered_publishes);
+        Self {
+            params,
+            policy,
+            stream,
+            msg_creator,
+            subscriptions: Mutex::new(vec![]),
+            offline_buffer: Mutex::new(offline_buffer),
+            closed: AtomicBool::new(false),
+            events_tx: Mutex::new(events_tx),
+            logger,
+        }
+    }
+
+    /// Registra las suscripciones realizadas, para renovarlas al reconectar.
+    pub fn add_subscriptions(&self, topics: &[(String, u8)]) {
+        if let Ok(mut subscriptions) = self.subscriptions.lock() {
+            subscriptions.extend(topics.iter().cloned());
+        }
+    }
+
+    /// Indica que la conexión se cerró voluntariamente, por lo que no debe reconectarse.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Si no hay conexión, almacena el `msg` para enviarlo al reconectar y devuelve true.
+    /// Devuelve error si no hay conexión y el mensaje es qos 0, ya que esos no se almacenan.
+    pub fn buffer_if_offline(&self, msg: &PublishMessage, topic: &str) -> Result<bool, Error> {
+        let mut buffer = self.lock_buffer()?;
+        if buffer.is_online() {
+            return Ok(false);
+        }
+        self.buffer(&mut buffer, msg, topic)?;
+        Ok(true)
+    }
+
+    /// Almacena el `msg` que no pudo enviarse, para enviarlo al reconectar.
+    pub fn buffer_after_failure(&self, msg: &PublishMessage, topic: &str) -> Result<(), Error> {
+        let mut buffer = self.lock_buffer()?;
+        buffer.set_online(false);
+        self.buffer(&mut buffer, msg, topic)
+    }
+
+    fn buffer(&self, buffer: &mut OfflineBuffer, msg: &PublishMessage, topic: &str) -> Result<(), Error> {
+        if msg.get_qos() == 0 {
+            return Err(Error::new(
+                ErrorKind::NotConnected,
+                "Sin conexión con el broker, se descarta el publish con qos 0.",
+            ));
+        }
+        // El topic alias sólo valía para la conexión anterior.
+        let mut msg = msg.clone();
+        msg.set_topic(topic);
+        msg.remove_topic_alias();
+        if let Some(dropped) = buffer.push(msg) {
+            self.logger.log(format!(
+                "Mqtt: buffer offline lleno, se descarta el publish más antiguo: {:?}.",
+                dropped.get_packet_id()
+            ));
+            if let (Some(packet_id), Ok(creator)) = (dropped.get_packet_id(), self.msg_creator.lock()) {
+                creator.release_packet_id(packet_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reintenta la conexión según la política, hasta lograrlo o agotar los intentos.
+    /// Devuelve el nuevo stream, y los packet_id de los mensajes reenviados al restaurar la sesión
+    /// (sus acks no los espera nadie).
+    pub fn reconnect(&self) -> Result<(ClientStreamType, Vec<u16>), Error> {
+        if let Ok(mut buffer) = self.offline_buffer.lock() {
+            buffer.set_online(false);
+        }
+        self.send_event(ConnectionEvent::Disconnected);
+        self.logger.log("Mqtt: se perdió la conexión con el broker.".to_string());
+
+        let mut attempt = 1;
+        while self.policy.allows_attempt(attempt) && !self.is_closed() {
+            thread::sleep(self.policy.backoff_for(attempt));
+            self.send_event(ConnectionEvent::Reconnecting { attempt });
+            let connection = MqttClientConnector::mqtt_connect_to_broker(
+                self.params.client_id.to_string(),
+                &self.params.addr,
+                self.params.will.clone(),
+                &self.params.credentials,
+                self.params.properties.clone(),
+                self.logger.clone_ref(),
+            );
+            match connection {
+                Ok((stream, connack_properties)) => {
+                    let replayed_ids = self.restore_session(&stream, connack_properties)?;
+                    self.send_event(ConnectionEvent::Reconnected);
+                    self.logger.log(format!("Mqtt: reconectado en el intento {}.", attempt));
+                    return Ok((stream, replayed_ids));
+                }
+                Err(e) => self.logger.log(format!("Mqtt: falló el intento de reconexión {}: {:?}.", attempt, e)),
+            }
+            attempt += 1;
+        }
+
+        self.send_event(ConnectionEvent::GaveUp);
+        Err(Error::new(ErrorKind::NotConnected, "Se agotaron los intentos de reconexión."))
+    }
+
+    /// Pasa a usar el nuevo `stream`, renueva las suscripciones y envía los publish almacenados.
+    fn restore_session(
+        &self,
+        stream: &ClientStreamType,
+        connack_properties: Option<MqttProperties>,
+    ) -> Result<Vec<u16>, Error> {
+        let mut new_stream = stream.try_clone()?;
+        let mut replayed_ids = vec![];
+        // El buffer se mantiene tomado hasta terminar, para que los publish nuevos salgan después de los almacenados.
+        let mut buffer = self.lock_buffer()?;
+        {
+            let mut creator = self
+                .msg_creator
+                .lock()
+                .map_err(|_| Error::other("Error al tomar lock del msg creator."))?;
+            let topic_alias_maximum = connack_properties.and_then(|props| props.topic_alias_maximum);
+            creator.reset_topic_aliases(topic_alias_maximum.unwrap_or(0));
+
+            let topics = self.subscriptions.lock().map(|s| s.clone()).unwrap_or_default();
+            if !topics.is_empty() {
+                let subscribe_msg = creator.create_subscribe_msg(topics)?;
+                write_message_to_stream(&subscribe_msg.to_bytes(), &mut new_stream)?;
+                replayed_ids.push(subscribe_msg.get_packet_id());
+            }
+        }
+        for msg in buffer.drain() {
+            write_message_to_stream(&msg.to_bytes(), &mut new_stream)?;
+            if let Some(packet_id) = msg.get_packet_id() {
+                replayed_ids.push(packet_id);
+            }
+        }
+
+        if let Ok(mut shared_stream) = self.stream.lock() {
+            *shared_stream = new_stream;
+        }
+        buffer.set_online(true);
+        Ok(replayed_ids)
+    }
+
+    fn lock_buffer(&self) -> Result<MutexGuard<'_, OfflineBuffer>, Error> {
+        self.offline_buffer
+            .lock()
+            .map_err(|_| Error::other("Error al tomar lock del buffer offline."))
+    }
+
+    fn send_event(&self, event: ConnectionEvent) {
+        // Si la app no escucha los eventos, no es un error.
+        if let Ok(events_tx) = self.events_tx.lock() {
+            let _ = events_tx.send(event);
+        }
+    }
+}
@@ -1,31 +1,52 @@
-use std::{io::{Error, ErrorKind}, net::Shutdown, sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender}, time::Duration};
+use std::{io::{Error, ErrorKind}, net::Shutdown, sync::{mpsc::{channel, Receiver, RecvTimeoutError, Sender}, Arc, Condvar, Mutex, MutexGuard}, thread, time::Duration};
 
-use crate::{logging::string_logger::StringLogger, mqtt::{messages::{disconnect_message::DisconnectMessage, message::Message, packet_type::PacketType, publish_message::PublishMessage}, mqtt_utils::utils::write_message_to_stream}};
+use crate::{logging::string_logger::StringLogger, time::clock::ShClock, mqtt::{messages::{disconnect_message::DisconnectMessage, message::Message, packet_type::PacketType, publish_message::PublishMessage}, mqtt_utils::{packet_id_allocator::PacketIdAllocator, utils::write_message_to_stream}}};
 
-use super::{ack_message::ACKMessage, mqtt_client::ClientStreamType};
+use super::{ack_handle::AckHandle, ack_message::ACKMessage, in_flight_window::{InFlightWindow, DEFAULT_MAX_IN_FLIGHT}, mqtt_client::ClientStreamType};
+
+// Cada cuánto el hilo de acks revisa los timers de retransmisión.
+const RETRANSMISSION_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+type ShareableWindow = Arc<(Mutex<InFlightWindow>, Condvar)>;
 
 /// Parte interna de `MQTTClient` encargada de manejar los ack y las retransmisiones.
-/// Conserva el extramo receptor de un channel (`ack_rx`).
+/// Los mensajes que esperan ack se guardan en una ventana (`InFlightWindow`) de tamaño configurable,
+/// así pueden enviarse varios sin esperar el ack de cada uno. Un hilo recibe los acks del listener
+/// y retransmite, con el flag dup, los mensajes cuyo ack no llegó a tiempo.
 #[derive(Debug)]
 pub struct Retransmitter {
-    ack_rx: Receiver<ACKMessage>,
-    stream: ClientStreamType,
+    window: ShareableWindow, // Compartida con el hilo de acks.
+    stream: Arc<Mutex<ClientStreamType>>, // Compartido con el Reconnector, que lo reemplaza al reconectar.
+    clock: ShClock, // Con él se miden los plazos de retransmisión.
     logger: StringLogger,
 }
 
 impl Retransmitter {
-    /// Crea y devuelve un Retransmitter, encargado del envío y las retransmisiones, y el extremo de envío de un channel.
-    pub fn new(stream: ClientStreamType, logger: StringLogger) -> (Self, Sender<ACKMessage>) {
+    /// Crea y devuelve un Retransmitter, encargado del envío y las retransmisiones, y el extremo de envío de un channel
+    /// por el que informarle los acks. Lanza el hilo que los recibe, que termina al cerrarse ese channel.
+    /// Al terminar de esperar el ack de un mensaje, libera su packet_id en `packet_ids`.
+    /// Los plazos de retransmisión se miden con `clock`.
+    pub fn new(stream: Arc<Mutex<ClientStreamType>>, packet_ids: Arc<Mutex<PacketIdAllocator>>, clock: ShClock, logger: StringLogger) -> (Self, Sender<ACKMessage>) {
         let (ack_tx, ack_rx) = channel::<ACKMessage>();
-        (Self { ack_rx , stream , logger }, ack_tx)
+        let window = Arc::new((Mutex::new(InFlightWindow::new(DEFAULT_MAX_IN_FLIGHT, packet_ids)), Condvar::new()));
+        let retransmitter = Self { window, stream, clock, logger };
+        retransmitter.spawn_ack_thread(ack_rx);
+        (retransmitter, ack_tx)
+    }
+
+    /// Establece cuántos mensajes pueden estar esperando su ack a la vez.
+    pub fn set_max_in_flight(&mut self, max_in_flight: usize) -> Result<(), Error> {
+        let (window, window_changed) = &*self.window;
+        lock_window(window)?.set_max_in_flight(max_in_flight);
+        window_changed.notify_all();
+        Ok(())
     }
-    
+
     /// Envía el mensaje `msg` recibido una vez, espera por el ack, y si es necesario lo retransmite una cierta
     /// cantidad de veces.
     pub fn send_and_retransmit<T: Message>(&mut self, msg: &T) -> Result<(), Error> {
-        self.logger.log("Mqtt: Enviando msg.".to_string());
-        self.send_msg(msg.to_bytes())?;
-        if let Err(e) = self.wait_for_ack_and_retransmit(msg) {
+        let ack_handle = self.send_without_waiting(msg)?;
+        if let Err(e) = ack_handle.wait() {
             println!("Error al esperar ack: {:?}", e);
             self.logger.log(format!("Error al esperar ack: {:?}", e));
         };
@@ -33,127 +54,155 @@ impl Retransmitter {
         Ok(())
     }
 
-    /// Espera por el ack y si no lo recibe retransmite, teniendo en cuenta el tipo de paquete,
-    /// para el publish considera su nivel de qos.
-    fn wait_for_ack_and_retransmit<T: Message>(&mut self, msg: &T) -> Result<(), Error> {
-        match msg.get_type() {
-            // Si es publish, ver el qos
-            PacketType::Publish => {
-                if let Some(pub_msg) = msg.as_any().downcast_ref::<PublishMessage>() {
-                    let qos = pub_msg.get_qos();
-                    if qos == 1 {
-                        return self.wait_and_retransmit(pub_msg);
-                    } else {
-                        return Ok(());
-                    }
-                }
+    /// Envía el mensaje `msg` sin esperar su ack; el hilo de acks se encarga de retransmitirlo si es necesario.
+    /// Sólo bloquea si la ventana de mensajes sin ack está llena, hasta que se libere un lugar.
+    /// Devuelve un `AckHandle` con el que esperar o consultar el resultado.
+    pub fn send_without_waiting<T: Message>(&mut self, msg: &T) -> Result<AckHandle, Error> {
+        self.logger.log("Mqtt: Enviando msg.".to_string());
+        let packet_id = match (Self::needs_ack(msg), msg.get_packet_id()) {
+            (true, Some(packet_id)) => packet_id,
+            (true, None) => {
+                return Err(Error::other(
+                    "No se pudo obtener el packet id del mensaje.",
+                ))
             }
-            PacketType::Subscribe => {
-                return self.wait_and_retransmit(msg);
+            (false, packet_id) => {
+                self.send_msg(msg.to_bytes())?;
+                return Ok(AckHandle::resolved(packet_id));
             }
-            _ => {}
-        }
+        };
 
-        Ok(())
+        // Se agrega a la ventana antes de enviarlo, para no perder un ack que llegue enseguida.
+        let (ack_handle, result_tx) = AckHandle::new(Some(packet_id));
+        self.add_to_window(packet_id, Self::bytes_for_resend(msg), result_tx)?;
+        if let Err(e) = self.send_msg(msg.to_bytes()) {
+            self.remove_from_window(packet_id)?;
+            return Err(e);
+        }
+        Ok(ack_handle)
     }
 
-    /// Espera a recibir el ack para el packet_id del mensaje `msg`, si no lo recibe, retransmite.
-    fn wait_and_retransmit<T: Message>(&mut self, msg: &T) -> Result<(), Error> {
-        let packet_id = msg.get_packet_id();
-        // Espero la primera vez, para el publish que hicimos arriba. Si se recibió ack, no hay que hacer nada más.
-        let mut received_ack = self.has_ack_arrived(packet_id)?;
-        if received_ack {
-            return Ok(());
+    /// Devuelve si se debe esperar el ack del mensaje, teniendo en cuenta el tipo de paquete;
+    /// para el publish considera su nivel de qos.
+    fn needs_ack<T: Message>(msg: &T) -> bool {
+        match msg.get_type() {
+            PacketType::Publish => msg
+                .as_any()
+                .downcast_ref::<PublishMessage>()
+                .is_some_and(|pub_msg| pub_msg.get_qos() == 1),
+            PacketType::Subscribe => true,
+            _ => false,
         }
+    }
 
-        // No recibí ack, entonces tengo que continuar retransmitiendo, hasta un máx de veces.
-        const AMOUNT_OF_RETRIES: u8 = 5; // cant de veces que va a reintentar, hasta que desista y dé error.
-        let mut remaining_retries = AMOUNT_OF_RETRIES;
-
-        while !received_ack && remaining_retries > 0 {
-            // Lo vuelvo a enviar, y a verificar si llega el ack.
-            
-            self.send_msg(msg.to_bytes())?;
-            received_ack = self.has_ack_arrived(packet_id)?;
-            self.logger.log("Mqtt: Retransmitiendo...".to_string());
-
-            remaining_retries -= 1;
+    /// Devuelve los bytes a enviar al retransmitir: si es publish, se marca con el flag dup.
+    fn bytes_for_resend<T: Message>(msg: &T) -> Vec<u8> {
+        if let Some(pub_msg) = msg.as_any().downcast_ref::<PublishMessage>() {
+            let mut dup_msg = pub_msg.clone();
+            dup_msg.set_dup();
+            return dup_msg.to_bytes();
         }
+        msg.to_bytes()
+    }
 
-        if !received_ack {
-            // Ya salí del while, retransmití muchas veces y nunca recibí el ack, desisto.
-            return Err(Error::new(
-                ErrorKind::Other,
-                "MAXRETRIES, se retransmitió sin éxito.",
-            ));
+    /// Espera a que haya lugar en la ventana, y agrega el mensaje.
+    fn add_to_window(&self, packet_id: u16, resend_bytes: Vec<u8>, result_tx: Sender<Result<(), Error>>) -> Result<(), Error> {
+        let (window, window_changed) = &*self.window;
+        let mut window = window_changed
+            .wait_while(lock_window(window)?, |window| window.is_full() && !window.is_closed())
+            .map_err(|_| Error::other("Error al esperar lugar en la ventana."))?;
+        if window.is_closed() {
+            return Err(Error::new(ErrorKind::NotConnected, "Ya no se reciben acks del servidor."));
         }
-
+        window.insert(packet_id, resend_bytes, result_tx, self.clock.now());
         Ok(())
     }
 
-    /// Espera a que MQTTListener le informe por este rx que llegó el ack. En ese caso devuelve ok.
-    /// Si eso no ocurre, debe retransmitir el mensaje original (el msg cuyo ack está esperando)
-    /// hasta que llegue su ack o bien se llegue a una cantidad máxima de intentos definida como constante.
-    /// Devuelve si recibió el ack.
-    fn has_ack_arrived(&self, packet_id: Option<u16>) -> Result<bool, Error> {
-        // Extrae el packet_id
-        if let Some(packet_id) = packet_id {
-            self.start_waiting_and_check_for_ack(packet_id)
-        } else {
-                Err(Error::new(
-                ErrorKind::Other,
-                "No se pudo obtener el packet id del mensaje publish",
-            ))
-        }
+    fn remove_from_window(&self, packet_id: u16) -> Result<(), Error> {
+        let (window, window_changed) = &*self.window;
+        lock_window(window)?.remove(packet_id);
+        window_changed.notify_all();
+        Ok(())
     }
 
-    /// Espera por el ack como máximo un cierto tiempo,
-    /// si no se cerró la conexión con listener, devuelve Ok de si llega el ack.
-    fn start_waiting_and_check_for_ack(&self, packet_id: u16) -> Result<bool, Error> {
-        // Leo esperando un cierto tiempo, si en el período [0, ese tiempo) no me llega el ack, lo quiero retransmitir.
-        const ACK_WAITING_INTERVAL: u64 = 1000;
-        match self.ack_rx.recv_timeout(Duration::from_millis(ACK_WAITING_INTERVAL)){
-            Ok(ack_message) => {
-                // Se recibió el ack
-                if let Some(packet_identifier) = ack_message.get_packet_id() {
-                    if packet_id == packet_identifier {
-                        println!("   llegó el ack {:?}", ack_message); 
-                        return Ok(true);
+    /// Lanza el hilo que recibe del MQTTListener los acks por `ack_rx`, y retransmite los mensajes cuyo ack
+    /// no llegó a tiempo. Al cerrarse el channel, informa error a los mensajes que quedaron esperando y termina.
+    fn spawn_ack_thread(&self, ack_rx: Receiver<ACKMessage>) {
+        let window = self.window.clone();
+        let stream = self.stream.clone();
+        let clock = self.clock.clone();
+        let logger = self.logger.clone_ref();
+        thread::spawn(move || {
+            let (window, window_changed) = &*window;
+            loop {
+                let ack = ack_rx.recv_timeout(RETRANSMISSION_CHECK_INTERVAL);
+                let Ok(mut window_lock) = lock_window(window) else { break };
+                match ack {
+                    Ok(ack_message) => {
+                        if let Some(packet_id) = ack_message.get_packet_id() {
+                            if window_lock.ack(packet_id) {
+                                println!("   llegó el ack {:?}", ack_message);
+                            }
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => {
+                        // Se cerró el channel, ya no llegarán acks.
+                        window_lock.close();
+                        window_changed.notify_all();
+                        break;
                     }
                 }
-            },
-            Err(e) => {
-                match e {
-                    RecvTimeoutError::Timeout => {
-                        // Se cumplió el tiempo y el ack No se recibió.
-                        return Ok(false);
-
-                    },
-                    RecvTimeoutError::Disconnected => {
-                        // Se cerró el channel. Termina el programa.
-                        // Ver.
-                    },
+                let to_resend = window_lock.take_due_for_retransmission(clock.now());
+                drop(window_lock);
+                window_changed.notify_all();
+
+                for (packet_id, bytes) in to_resend {
+                    logger.log(format!("Mqtt: Retransmitiendo {}...", packet_id));
+                    if let Err(e) = send_to_stream(&stream, &bytes) {
+                        logger.log(format!("Mqtt: error al retransmitir {}: {:?}.", packet_id, e));
+                    }
                 }
-            },
-        }
-        Ok(false)
+            }
+        });
     }
 
     /// Función para ser usada por `MQTTClient`, cuando el `Retransmitter` haya determinado que el `msg` debe
     /// enviarse por el stream a server.
     fn send_msg(&mut self, bytes_msg: Vec<u8>) -> Result<(), Error> {
-        write_message_to_stream(&bytes_msg, &mut self.stream)?;
-        Ok(())
+        send_to_stream(&self.stream, &bytes_msg)
     }
-    
+
+    fn lock_stream(&self) -> Result<MutexGuard<'_, ClientStreamType>, Error> {
+        lock_stream(&self.stream)
+    }
+
     /// Envía el mensaje disconnect recibido por parámetro y cierra la conexión.
     pub fn send_and_shutdown_stream(&mut self, msg: DisconnectMessage) -> Result<(), Error> {
         self.send_msg(msg.to_bytes())?;
         // Cerramos la conexión con el servidor
-        self.stream.shutdown(Shutdown::Both)?;
+        self.lock_stream()?.shutdown(Shutdown::Both)?;
         self.logger.log("Mqtt: Conexión cerrada.".to_string());
 
         Ok(())
     }
 
-}
\ No newline at end of file
+}
+
+fn send_to_stream(stream: &Mutex<ClientStreamType>, bytes_msg: &[u8]) -> Result<(), Error> {
+    let mut stream = lock_stream(stream)?;
+    write_message_to_stream(bytes_msg, &mut stream)?;
+    Ok(())
+}
+
+fn lock_stream(stream: &Mutex<ClientStreamType>) -> Result<MutexGuard<'_, ClientStreamType>, Error> {
+    stream
+        .lock()
+        .map_err(|_| Error::other("Error al tomar lock del stream."))
+}
+
+fn lock_window(window: &Mutex<InFlightWindow>) -> Result<MutexGuard<'_, InFlightWindow>, Error> {
+    window
+        .lock()
+        .map_err(|_| Error::other("Error al tomar lock de la ventana de mensajes sin ack."))
+}
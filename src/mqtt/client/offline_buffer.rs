@@ -0,0 +1,76 @@
+use std::collections::VecDeque;
+
+use crate::mqtt::messages::publish_message::PublishMessage;
+
+/// Publish con qos >= 1 que no pudieron enviarse por no haber conexión con el broker.
+/// Tiene capacidad acotada: si se llena, se descarta el mensaje más antiguo.
+#[derive(Debug)]
+pub struct OfflineBuffer {
+    online: bool,
+    capacity: usize,
+    msgs: VecDeque<PublishMessage>,
+}
+
+impl OfflineBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            online: true,
+            capacity,
+            msgs: VecDeque::new(),
+        }
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.online
+    }
+
+    pub fn set_online(&mut self, online: bool) {
+        self.online = online;
+    }
+
+    /// Almacena el mensaje. Si se superó la capacidad, devuelve el mensaje más antiguo, que se descarta.
+    pub fn push(&mut self, msg: PublishMessage) -> Option<PublishMessage> {
+        self.msgs.push_back(msg);
+        if self.msgs.len() > self.capacity {
+            return self.msgs.pop_front();
+        }
+        None
+    }
+
+    /// Quita y devuelve los mensajes almacenados, en el orden en que se publicaron.
+    pub fn drain(&mut self) -> Vec<PublishMessage> {
+        self.msgs.drain(..).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.msgs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.msgs.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::OfflineBuffer;
+    use crate::mqtt::messages::{publish_flags::PublishFlags, publish_message::PublishMessage};
+
+    fn create_msg(payload: u8) -> PublishMessage {
+        let flags = PublishFlags::new(0, 1, 0).unwrap();
+        PublishMessage::new(flags, "dron", Some(payload as u16), &[payload]).unwrap()
+    }
+
+    #[test]
+    fn test_1_al_superar_la_capacidad_se_descarta_el_mas_antiguo() {
+        let mut buffer = OfflineBuffer::new(2);
+        assert!(buffer.push(create_msg(1)).is_none());
+        assert!(buffer.push(create_msg(2)).is_none());
+        let dropped = buffer.push(create_msg(3)).unwrap();
+
+        assert_eq!(dropped.get_payload(), vec![1]);
+        let payloads: Vec<Vec<u8>> = buffer.drain().iter().map(|msg| msg.get_payload()).collect();
+        assert_eq!(payloads, vec![vec![2], vec![3]]);
+        assert!(buffer.is_empty());
+    }
+}
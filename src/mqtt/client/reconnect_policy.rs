@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+/// Política de reconexión automática de un `MQTTClient`.
+/// Entre intentos se espera un backoff exponencial (duplicándose desde `initial_backoff` hasta `max_backoff`),
+/// al que se le resta al azar hasta una fracción `jitter` para que no reconecten todos los clientes a la vez.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub jitter: f64,                   // Entre 0 y 1.
+    pub max_attempts: Option<u32>,     // None: reintenta indefinidamente.
+    pub max_buffered_publishes: usize, // Publish con qos >= 1 que se almacenan mientras no hay conexión.
+}
+
+impl ReconnectPolicy {
+    /// Devuelve cuánto esperar antes del intento de reconexión número `attempt` (comenzando en 1).
+    pub fn backoff_for(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff);
+        let jitter = self.jitter.clamp(0.0, 1.0) * rand::random::<f64>();
+        backoff.mul_f64(1.0 - jitter)
+    }
+
+    /// Devuelve si corresponde realizar el intento de reconexión número `attempt`.
+    pub fn allows_attempt(&self, attempt: u32) -> bool {
+        self.max_attempts.is_none_or(|max| attempt <= max)
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            jitter: 0.2,
+            max_attempts: None,
+            max_buffered_publishes: 100,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::ReconnectPolicy;
+
+    #[test]
+    fn test_1_el_backoff_crece_exponencialmente_hasta_el_maximo() {
+        let policy = ReconnectPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(1000),
+            jitter: 0.0,
+            ..Default::default()
+        };
+
+        assert_eq!(policy.backoff_for(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff_for(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff_for(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff_for(5), Duration::from_millis(1000));
+        assert_eq!(policy.backoff_for(100), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_2_el_jitter_solo_resta_hasta_la_fraccion_indicada() {
+        let policy = ReconnectPolicy {
+            initial_backoff: Duration::from_millis(1000),
+            jitter: 0.5,
+            max_attempts: Some(3),
+            ..Default::default()
+        };
+
+        for _ in 0..50 {
+            let backoff = policy.backoff_for(1);
+            assert!(backoff <= Duration::from_millis(1000) && backoff >= Duration::from_millis(500));
+        }
+        assert!(policy.allows_attempt(3));
+        assert!(!policy.allows_attempt(4));
+    }
+}
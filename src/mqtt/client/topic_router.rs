@@ -0,0 +1,148 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+};
+
+use crate::mqtt::messages::publish_message::PublishMessage;
+
+/// Función que procesa los mensajes recibidos de un topic. Se ejecuta en el hilo que lee del servidor.
+pub type TopicHandler = Box<dyn FnMut(PublishMessage) + Send>;
+
+enum Route {
+    Channel(Sender<PublishMessage>),
+    Handler(Arc<Mutex<TopicHandler>>),
+}
+
+/// Reparte los publish recibidos según su topic: a un channel o a un handler propio del topic, si se registró uno,
+/// o si no al channel por defecto (el rx devuelto al conectarse).
+/// Como el broker no soporta wildcards, los topics se comparan por igualdad.
+pub struct TopicRouter {
+    routes: HashMap<String, Route>,
+    default_tx: Option<Sender<PublishMessage>>, // None una vez cerrado.
+}
+
+impl TopicRouter {
+    pub fn new(default_tx: Sender<PublishMessage>) -> Self {
+        Self {
+            routes: HashMap::new(),
+            default_tx: Some(default_tx),
+        }
+    }
+
+    /// Los mensajes de `topic` pasan a enviarse por un channel propio, cuyo rx se devuelve.
+    /// Reemplaza la ruta que tuviera el topic.
+    pub fn add_channel(&mut self, topic: &str) -> Receiver<PublishMessage> {
+        let (tx, rx) = mpsc::channel();
+        self.routes.insert(topic.to_string(), Route::Channel(tx));
+        rx
+    }
+
+    /// Los mensajes de `topic` pasan a procesarse con `handler`. Reemplaza la ruta que tuviera el topic.
+    pub fn add_handler(&mut self, topic: &str, handler: TopicHandler) {
+        self.routes
+            .insert(topic.to_string(), Route::Handler(Arc::new(Mutex::new(handler))));
+    }
+
+    /// Los mensajes de `topic` vuelven a enviarse por el channel por defecto.
+    pub fn remove_route(&mut self, topic: &str) {
+        self.routes.remove(topic);
+    }
+
+    /// Ya no llegarán mensajes: se sueltan los channels y handlers, así terminan quienes reciben por ellos.
+    pub fn close(&mut self) {
+        self.routes.clear();
+        self.default_tx = None;
+    }
+
+    /// Devuelve a dónde debe enviarse el `msg`, o None si el router está cerrado. Se separa de `deliver` para no llamar al handler
+    /// con el lock del router tomado (así el handler puede, por ejemplo, registrar otra ruta).
+    pub fn destination_of(&self, msg: &PublishMessage) -> Option<Destination> {
+        match self.routes.get(&msg.get_topic()) {
+            Some(Route::Channel(tx)) => Some(Destination::Channel(tx.clone())),
+            Some(Route::Handler(handler)) => Some(Destination::Handler(handler.clone())),
+            None => self.default_tx.clone().map(Destination::Channel),
+        }
+    }
+}
+
+impl fmt::Debug for TopicRouter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TopicRouter")
+            .field("topics", &self.routes.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Destino de un mensaje, según el `TopicRouter`.
+pub enum Destination {
+    Channel(Sender<PublishMessage>),
+    Handler(Arc<Mutex<TopicHandler>>),
+}
+
+impl Destination {
+    /// Entrega el `msg`. Devuelve false si no se pudo, porque ya nadie recibe por el channel.
+    pub fn deliver(self, msg: PublishMessage) -> bool {
+        match self {
+            Destination::Channel(tx) => tx.send(msg).is_ok(),
+            Destination::Handler(handler) => match handler.lock() {
+                Ok(mut handler) => {
+                    handler(msg);
+                    true
+                }
+                Err(_) => false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc;
+
+    use super::TopicRouter;
+    use crate::mqtt::messages::{publish_flags::PublishFlags, publish_message::PublishMessage};
+
+    fn publish_to(topic: &str, payload: u8) -> PublishMessage {
+        PublishMessage::new(PublishFlags::new(0, 1, 0).unwrap(), topic, Some(1), &[payload]).unwrap()
+    }
+
+    #[test]
+    fn test_1_cada_topic_va_a_su_ruta_y_el_resto_al_channel_por_defecto() {
+        let (default_tx, default_rx) = mpsc::channel();
+        let mut router = TopicRouter::new(default_tx);
+        let inc_rx = router.add_channel("inc");
+        let (handled_tx, handled_rx) = mpsc::channel();
+        router.add_handler("dron", Box::new(move |msg| handled_tx.send(msg.get_payload()).unwrap()));
+
+        for msg in [publish_to("inc", 1), publish_to("dron", 2), publish_to("cam", 3)] {
+            assert!(router.destination_of(&msg).unwrap().deliver(msg));
+        }
+
+        assert_eq!(inc_rx.try_recv().unwrap().get_payload(), vec![1]);
+        assert_eq!(handled_rx.try_recv().unwrap(), vec![2]);
+        assert_eq!(default_rx.try_recv().unwrap().get_payload(), vec![3]);
+
+        // Sin su ruta, el topic vuelve al channel por defecto.
+        router.remove_route("inc");
+        let msg = publish_to("inc", 4);
+        assert!(router.destination_of(&msg).unwrap().deliver(msg));
+        assert_eq!(default_rx.try_recv().unwrap().get_payload(), vec![4]);
+    }
+
+    #[test]
+    fn test_2_al_cerrarse_terminan_quienes_reciben() {
+        let (default_tx, default_rx) = mpsc::channel();
+        let mut router = TopicRouter::new(default_tx);
+        let inc_rx = router.add_channel("inc");
+
+        router.close();
+
+        assert!(router.destination_of(&publish_to("cam", 1)).is_none());
+        assert!(default_rx.recv().is_err());
+        assert!(inc_rx.recv().is_err());
+    }
+}
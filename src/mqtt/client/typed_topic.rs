@@ -0,0 +1,46 @@
+use std::{fmt, io::Error, marker::PhantomData, sync::mpsc::Receiver};
+
+/// Tipo que viaja como payload de los publish de un topic: sabe pasarse a bytes y reconstruirse a partir de ellos.
+pub trait MqttPayload: Sized {
+    fn encode(&self) -> Result<Vec<u8>, Error>;
+
+    /// Reconstruye el valor a partir del payload recibido. Devuelve error (sin hacer panic)
+    /// si los bytes no corresponden a un valor válido.
+    fn decode(bytes: &[u8]) -> Result<Self, Error>;
+}
+
+/// Rx por el que se reciben los valores de un topic junto con el timestamp con el que se publicaron
+/// (ver `MQTTClient::mqtt_subscribe_typed_with_timestamp`).
+pub type TimestampedReceiver<T> = Receiver<Result<(T, u128), Error>>;
+
+/// Topic cuyos mensajes llevan como payload valores de tipo `T`.
+/// Permite suscribirse recibiendo directamente los valores decodificados (ver `MQTTClient::mqtt_subscribe_typed`).
+pub struct Topic<T> {
+    name: String,
+    payload_type: PhantomData<fn() -> T>,
+}
+
+impl<T> Topic<T> {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            payload_type: PhantomData,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<T> Clone for Topic<T> {
+    fn clone(&self) -> Self {
+        Self::new(&self.name)
+    }
+}
+
+impl<T> fmt::Debug for Topic<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Topic").field("name", &self.name).finish()
+    }
+}
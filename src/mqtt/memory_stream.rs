@@ -0,0 +1,219 @@
+use std::{
+    collections::VecDeque,
+    io::{Error, ErrorKind, Read, Write},
+    net::Shutdown,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// Bytes escritos por un extremo y aún no leídos por el otro.
+#[derive(Debug, Default)]
+struct Pipe {
+    buf: VecDeque<u8>,
+    closed: bool,
+}
+
+type SharedPipe = Arc<(Mutex<Pipe>, Condvar)>;
+
+/// Un extremo de la conexión, compartido por todas las copias (`try_clone`) del stream.
+/// Al dropearse la última copia se cierra la conexión, como con un socket.
+#[derive(Debug)]
+struct Endpoint {
+    incoming: SharedPipe,
+    outgoing: SharedPipe,
+    read_timeout: Mutex<Option<Duration>>,
+}
+
+impl Drop for Endpoint {
+    fn drop(&mut self) {
+        close(&self.incoming);
+        close(&self.outgoing);
+    }
+}
+
+/// Stream en memoria, que se comporta como un `TcpStream`: lo que se escribe en un extremo se lee en el otro.
+/// Permite conectar clientes y servidor en el mismo proceso sin usar la red (ie para tests).
+#[derive(Debug)]
+pub struct MemoryStream {
+    endpoint: Arc<Endpoint>,
+}
+
+impl MemoryStream {
+    /// Crea los dos extremos de una conexión.
+    pub fn pair() -> (Self, Self) {
+        let a_to_b: SharedPipe = Arc::new((Mutex::new(Pipe::default()), Condvar::new()));
+        let b_to_a: SharedPipe = Arc::new((Mutex::new(Pipe::default()), Condvar::new()));
+        let a = Self::new(b_to_a.clone(), a_to_b.clone());
+        let b = Self::new(a_to_b, b_to_a);
+        (a, b)
+    }
+
+    fn new(incoming: SharedPipe, outgoing: SharedPipe) -> Self {
+        Self {
+            endpoint: Arc::new(Endpoint {
+                incoming,
+                outgoing,
+                read_timeout: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// Devuelve otra referencia al mismo extremo.
+    pub fn try_clone(&self) -> Result<Self, Error> {
+        Ok(Self {
+            endpoint: Arc::clone(&self.endpoint),
+        })
+    }
+
+    /// Cierra la lectura, la escritura, o ambas. El otro extremo lee fin de stream.
+    pub fn shutdown(&self, how: Shutdown) -> Result<(), Error> {
+        if how != Shutdown::Write {
+            close(&self.endpoint.incoming);
+        }
+        if how != Shutdown::Read {
+            close(&self.endpoint.outgoing);
+        }
+        Ok(())
+    }
+
+    /// Establece cuánto puede bloquear un read. Con `None`, bloquea hasta que haya datos o se cierre el stream.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        if timeout == Some(Duration::ZERO) {
+            return Err(Error::new(ErrorKind::InvalidInput, "El timeout no puede ser cero."));
+        }
+        *self
+            .endpoint
+            .read_timeout
+            .lock()
+            .map_err(|_| Error::other("Error al tomar lock del timeout."))? = timeout;
+        Ok(())
+    }
+
+    fn get_read_timeout(&self) -> Option<Duration> {
+        self.endpoint.read_timeout.lock().ok().and_then(|timeout| *timeout)
+    }
+}
+
+impl Read for MemoryStream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let (pipe, data_available) = &*self.endpoint.incoming;
+        let deadline = self.get_read_timeout().map(|timeout| Instant::now() + timeout);
+        let mut pipe = lock_pipe(pipe)?;
+        while pipe.buf.is_empty() && !pipe.closed {
+            pipe = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(Error::new(ErrorKind::WouldBlock, "Se agotó el tiempo de lectura."));
+                    }
+                    data_available
+                        .wait_timeout(pipe, deadline - now)
+                        .map_err(|_| lock_error())?
+                        .0
+                }
+                None => data_available.wait(pipe).map_err(|_| lock_error())?,
+            };
+        }
+
+        // Si está cerrado y no quedan datos, es fin de stream.
+        let n = buf.len().min(pipe.buf.len());
+        for (dst, src) in buf.iter_mut().zip(pipe.buf.drain(..n)) {
+            *dst = src;
+        }
+        Ok(n)
+    }
+}
+
+impl Write for MemoryStream {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        let (pipe, data_available) = &*self.endpoint.outgoing;
+        let mut pipe = lock_pipe(pipe)?;
+        if pipe.closed {
+            return Err(Error::new(ErrorKind::BrokenPipe, "La conexión está cerrada."));
+        }
+        pipe.buf.extend(buf);
+        data_available.notify_all();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+fn close(pipe: &SharedPipe) {
+    let (pipe, data_available) = &**pipe;
+    if let Ok(mut pipe) = pipe.lock() {
+        pipe.closed = true;
+    }
+    data_available.notify_all();
+}
+
+fn lock_pipe(pipe: &Mutex<Pipe>) -> Result<MutexGuard<'_, Pipe>, Error> {
+    pipe.lock().map_err(|_| lock_error())
+}
+
+fn lock_error() -> Error {
+    Error::other("Error al tomar lock del stream en memoria.")
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{ErrorKind, Read, Write},
+        net::Shutdown,
+        thread,
+        time::Duration,
+    };
+
+    use super::MemoryStream;
+
+    #[test]
+    fn test_1_lo_escrito_en_un_extremo_se_lee_en_el_otro() {
+        let (mut a, mut b) = MemoryStream::pair();
+        a.write_all(b"hola").unwrap();
+        b.write_all(b"chau").unwrap();
+
+        let mut buf = [0; 4];
+        b.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hola");
+        a.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"chau");
+
+        // Un read bloqueado se despierta al llegar datos
+        let mut b_clone = b.try_clone().unwrap();
+        let reader = thread::spawn(move || {
+            let mut buf = [0; 3];
+            b_clone.read_exact(&mut buf).unwrap();
+            buf
+        });
+        thread::sleep(Duration::from_millis(20));
+        a.write_all(b"abc").unwrap();
+        assert_eq!(&reader.join().unwrap(), b"abc");
+    }
+
+    #[test]
+    fn test_2_timeout_de_lectura_y_cierre() {
+        let (a, mut b) = MemoryStream::pair();
+        b.set_read_timeout(Some(Duration::from_millis(10))).unwrap();
+        let mut buf = [0; 1];
+        assert_eq!(b.read(&mut buf).unwrap_err().kind(), ErrorKind::WouldBlock);
+
+        // Al cerrar un extremo, el otro lee fin de stream y ya no puede escribir
+        a.shutdown(Shutdown::Both).unwrap();
+        assert_eq!(b.read(&mut buf).unwrap(), 0);
+        assert_eq!(b.write(b"x").unwrap_err().kind(), ErrorKind::BrokenPipe);
+
+        // Lo mismo al dropear la última copia de un extremo
+        let (c, mut d) = MemoryStream::pair();
+        let c_clone = c.try_clone().unwrap();
+        drop(c);
+        d.set_read_timeout(Some(Duration::from_millis(10))).unwrap();
+        assert_eq!(d.read(&mut buf).unwrap_err().kind(), ErrorKind::WouldBlock);
+        drop(c_clone);
+        assert_eq!(d.read(&mut buf).unwrap(), 0);
+    }
+}
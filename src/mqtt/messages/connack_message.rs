@@ -1,8 +1,9 @@
-use std::io::Error;
+use std::io::{Error, ErrorKind};
 
 use crate::mqtt::messages::{
     connack_fixed_header::FixedHeader, connack_session_present::SessionPresent,
     connack_variable_header::VariableHeader, connect_return_code::ConnectReturnCode,
+    mqtt_properties::MqttProperties, reason_code::ReasonCode,
 };
 
 #[derive(Debug)]
@@ -26,6 +27,7 @@ impl ConnackMessage {
         let variable_header = VariableHeader {
             connect_acknowledge_flags,
             connect_return_code: return_code,
+            properties: None,
         };
 
         ConnackMessage {
@@ -34,6 +36,12 @@ impl ConnackMessage {
         }
     }
 
+    /// Convierte el mensaje en un connack de MQTT 5, con las propiedades recibidas.
+    pub fn set_mqtt5_properties(&mut self, properties: MqttProperties) {
+        self.fixed_header.remaining_length = 2 + properties.to_bytes().len() as u8;
+        self.variable_header.properties = Some(properties);
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         // Fixed Header
         let message_type = self.fixed_header.message_type;
@@ -41,27 +49,46 @@ impl ConnackMessage {
 
         // Variable Header
         let connect_acknowledge_flags = self.variable_header.connect_acknowledge_flags;
-        let connect_return_code = self.variable_header.connect_return_code.to_byte()[0];
+        // En MQTT 5 se envía el reason code equivalente, seguido de las propiedades.
+        let connect_return_code = match self.variable_header.properties {
+            Some(_) => self.variable_header.connect_return_code.to_reason_code().to_byte(),
+            None => self.variable_header.connect_return_code.to_byte()[0],
+        };
 
-        let bytes = vec![
+        let mut bytes = vec![
             message_type,
             remaining_length,
             connect_acknowledge_flags,
             connect_return_code,
         ];
+        if let Some(properties) = &self.variable_header.properties {
+            bytes.extend(properties.to_bytes());
+        }
 
         bytes
     }
 
+    /// Parsea los bytes recibidos. Si la remaining length es mayor a 2, es un connack de MQTT 5 con propiedades.
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < 4 {
+            return Err(Error::new(ErrorKind::InvalidData, "No hay suficientes bytes para un connack válido."));
+        }
         let fixed_header = FixedHeader {
             message_type: bytes[0],
             remaining_length: bytes[1],
         };
 
+        let (connect_return_code, properties) = if fixed_header.remaining_length > 2 {
+            let (properties, _) = MqttProperties::from_bytes(&bytes[4..])?;
+            (ConnectReturnCode::from_reason_code(ReasonCode::from_byte(bytes[3])?), Some(properties))
+        } else {
+            (ConnectReturnCode::from_byte([bytes[3]])?, None)
+        };
+
         let variable_header = VariableHeader {
             connect_acknowledge_flags: bytes[2],
-            connect_return_code: ConnectReturnCode::from_byte([bytes[3]])?,
+            connect_return_code,
+            properties,
         };
 
         // un if message_type != de (2<<4) {dar error}
@@ -75,10 +102,14 @@ impl ConnackMessage {
     pub fn get_connect_return_code(&self) -> ConnectReturnCode {
         self.variable_header.connect_return_code.clone()
     }
+
+    /// Devuelve las propiedades del connack, si es de MQTT 5.
+    pub fn get_properties(&self) -> Option<&MqttProperties> {
+        self.variable_header.properties.as_ref()
+    }
 }
 
 #[cfg(test)]
-
 mod tests {
     use super::*;
 
@@ -123,4 +154,22 @@ mod tests {
             ConnectReturnCode::ConnectionAccepted
         );
     }
+
+    #[test]
+    fn test_connack_mqtt5_envia_reason_code_y_propiedades() {
+        let mut connack_packet = ConnackMessage::new(
+            SessionPresent::NotPresentInLastSession,
+            ConnectReturnCode::NotAuthorized,
+        );
+        let mut properties = MqttProperties::new();
+        properties.topic_alias_maximum = Some(10);
+        connack_packet.set_mqtt5_properties(properties.clone());
+
+        let bytes = connack_packet.to_bytes();
+        assert_eq!(bytes[3], ReasonCode::NotAuthorized.to_byte());
+
+        let connack_packet = ConnackMessage::from_bytes(&bytes).unwrap();
+        assert_eq!(connack_packet.get_connect_return_code(), ConnectReturnCode::NotAuthorized);
+        assert_eq!(connack_packet.get_properties(), Some(&properties));
+    }
 }
@@ -1,7 +1,8 @@
-use super::connect_return_code::ConnectReturnCode;
+use super::{connect_return_code::ConnectReturnCode, mqtt_properties::MqttProperties};
 
 #[derive(Debug)]
 pub struct VariableHeader {
     pub connect_acknowledge_flags: u8, // byte 3 --> 0000_000X (X = 1 if session present)
-    pub connect_return_code: ConnectReturnCode, // byte 4
+    pub connect_return_code: ConnectReturnCode, // byte 4; en MQTT 5 se envía como reason code.
+    pub properties: Option<MqttProperties>, // Sólo en MQTT 5, a continuación del byte 4.
 }
@@ -1,5 +1,5 @@
 #[derive(Debug, PartialEq)]
 pub struct FixedHeader {
-    pub message_type: u8,     // byte 1
-    pub remaining_length: u8, // byte 2
+    pub message_type: u8,        // byte 1
+    pub remaining_length: usize, // desde el byte 2, de uno a cuatro bytes
 }
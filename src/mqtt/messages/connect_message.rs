@@ -1,7 +1,13 @@
+use std::io::{Error, ErrorKind};
+
 use crate::mqtt::{messages::{
     connect_fixed_header::FixedHeader, connect_flags::ConnectFlags, connect_payload::Payload,
-    connect_variable_header::VariableHeader,
-}, mqtt_utils::will_message_utils::will_message::WillMessageData};
+    connect_variable_header::VariableHeader, mqtt_properties::MqttProperties,
+    protocol_version::ProtocolVersion,
+}, mqtt_utils::{
+    fixed_header::{decode_remaining_length, encode_remaining_length},
+    will_message_utils::will_message::WillMessageData,
+}};
 
 #[derive(Debug)]
 pub struct ConnectMessage {
@@ -36,6 +42,7 @@ impl ConnectMessage {
                 clean_session: true,
                 reserved: false,
             },
+            properties: None,
         };
 
         let payload = Payload {
@@ -58,8 +65,20 @@ impl ConnectMessage {
         connect_message
     }
 
-    fn calculate_remaining_length(&self) -> u8 {
-        let variable_header_length = 5 + 1 + 1;
+    /// Convierte el mensaje en un connect de MQTT 5, con las propiedades recibidas.
+    pub fn set_mqtt5_properties(&mut self, properties: MqttProperties) {
+        self.variable_header.protocol_level = ProtocolVersion::V5.to_level();
+        self.variable_header.properties = Some(properties);
+        self.fixed_header.remaining_length = self.calculate_remaining_length();
+    }
+
+    fn calculate_remaining_length(&self) -> usize {
+        let properties_length = self
+            .variable_header
+            .properties
+            .as_ref()
+            .map_or(0, |props| props.to_bytes().len());
+        let variable_header_length = 5 + 1 + 1 + properties_length;
         let length_string_u8 = 1;
         let payload_length = length_string_u8
             + self.payload.client_id.len()
@@ -84,7 +103,7 @@ impl ConnectMessage {
                 .as_ref()
                 .map_or(0, |s| s.len() + length_string_u8);
 
-        (variable_header_length + payload_length) as u8
+        variable_header_length + payload_length
     }
 
     /// Pasa un ConnectMessage a bytes.
@@ -94,7 +113,7 @@ impl ConnectMessage {
         // Fixed Header
         bytes.push(self.fixed_header.message_type);
         self.fixed_header.remaining_length = self.calculate_remaining_length();
-        bytes.push(self.fixed_header.remaining_length);
+        bytes.extend(encode_remaining_length(self.fixed_header.remaining_length));
 
         // Variable Header
         let protocol_name_len: u8 = self.variable_header.protocol_name.len() as u8;
@@ -103,6 +122,9 @@ impl ConnectMessage {
         bytes.push(self.variable_header.protocol_level);
         let connect_flags = self.variable_header.connect_flags.to_byte();
         bytes.push(connect_flags);
+        if let Some(properties) = &self.variable_header.properties {
+            bytes.extend(properties.to_bytes());
+        }
 
         // Payload
         bytes.push(self.payload.client_id.len() as u8);
@@ -128,25 +150,45 @@ impl ConnectMessage {
     }
 
     /// Parsea los bytes recibidos y devuelve un struct ConnectMessage.
-    pub fn from_bytes(bytes: &[u8]) -> Self {
+    /// Si el `protocol_level` es el de MQTT 5, lee también las propiedades.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let (remaining_length, header_len) = decode_remaining_length(bytes)?;
+        // A partir del fixed header, el variable header ocupa 7 bytes más las propiedades.
+        let var_header = bytes
+            .get(header_len..header_len + 7)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "No hay suficientes bytes para un connect válido."))?;
         let fixed_header = FixedHeader {
             message_type: bytes[0],
-            remaining_length: bytes[1],
+            remaining_length,
+        };
+
+        let protocol_level = var_header[5];
+        let (properties, properties_len) = if protocol_level == ProtocolVersion::V5.to_level() {
+            let (properties, len) = MqttProperties::from_bytes(&bytes[header_len + 7..])?;
+            (Some(properties), len)
+        } else {
+            (None, 0)
         };
 
         let variable_header = VariableHeader {
-            // el byte 2 es el protocol_name_len, debería valer siempre 4 que es la len de "MQTT". []
-            protocol_name: [bytes[3], bytes[4], bytes[5], bytes[6]],
-            protocol_level: bytes[7],
-            connect_flags: ConnectFlags::from_byte(bytes[8]),
+            // el byte 0 es el protocol_name_len, debería valer siempre 4 que es la len de "MQTT". []
+            protocol_name: [var_header[1], var_header[2], var_header[3], var_header[4]],
+            protocol_level,
+            connect_flags: ConnectFlags::from_byte(var_header[6]),
+            properties,
         };
 
-        // Indice donde comienza el payload (son 2 bytes de fixed header y 7 bytes de var header)
-        let payload_start_index = 9;
+        // Indice donde comienza el payload (son los bytes de fixed header y 7 bytes de var header, más las propiedades)
+        let payload_start_index = header_len + 7 + properties_len;
 
         // Calcular la longitud del payload
-        let variable_header_len: usize = 7; // (esto podría ser un método del variable header) // es payload_start_index - 2:
-        let payload_length = fixed_header.remaining_length as usize - variable_header_len; // Total - 7 bytes del variable header
+        let variable_header_len: usize = 7 + properties_len; // (esto podría ser un método del variable header) // es payload_start_index - header_len:
+        let payload_length = fixed_header.remaining_length
+            .checked_sub(variable_header_len)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Remaining length inválida en connect."))?; // Total - bytes del variable header
+        if bytes.len() < payload_start_index + payload_length {
+            return Err(Error::new(ErrorKind::InvalidData, "Connect incompleto."));
+        }
                                                                                            // Extraer el payload del mensaje
         let payload_bytes = &bytes[payload_start_index..payload_start_index + payload_length];
 
@@ -157,11 +199,11 @@ impl ConnectMessage {
         // algo del estilo if message_type != 1 {return error tipo incorrecto al crear ConnectMessage },
         // va a cambiar la firma, lo dejo así ahora y dsp lo refactorizo []
         // Construir y retornar el mensaje ConnectMessage completo
-        ConnectMessage {
+        Ok(ConnectMessage {
             fixed_header,
             variable_header,
             payload,
-        }
+        })
     }
 
     /// Parsea los bytes correspondientes al payload, a un struct payload con sus campos.
@@ -250,6 +292,16 @@ impl ConnectMessage {
         self.payload.password.as_ref()
     }
 
+    /// Devuelve la versión del protocolo solicitada por el cliente, o error si no está soportada.
+    pub fn get_protocol_version(&self) -> Result<ProtocolVersion, Error> {
+        ProtocolVersion::from_level(self.variable_header.protocol_level)
+    }
+
+    /// Devuelve las propiedades del connect, si es de MQTT 5.
+    pub fn get_properties(&self) -> Option<&MqttProperties> {
+        self.variable_header.properties.as_ref()
+    }
+
     /// Devuelve el campo client_id del mensaje.
     pub fn get_client_id(&self) -> Option<&String> {
         Some(&self.payload.client_id)
@@ -299,7 +351,7 @@ mod tests {
         let bytes = connect_message.to_bytes();
 
         // Convertimos los bytes a un nuevo mensaje
-        let new_connect_message = ConnectMessage::from_bytes(&bytes);
+        let new_connect_message = ConnectMessage::from_bytes(&bytes).unwrap();
 
         // Comprobamos que los mensajes son iguales
         assert!(connect_message.fixed_header == new_connect_message.fixed_header);
@@ -313,7 +365,7 @@ mod tests {
         let bytes = connect_message.to_bytes();
 
         // Convertimos los bytes a un nuevo mensaje
-        let new_connect_message = ConnectMessage::from_bytes(&bytes);
+        let new_connect_message = ConnectMessage::from_bytes(&bytes).unwrap();
 
         // Comprobamos que los mensajes son iguales
         assert_eq!(
@@ -331,7 +383,7 @@ mod tests {
         let bytes = connect_message.to_bytes();
 
         // Convertimos los bytes a un nuevo mensaje
-        let new_connect_message = ConnectMessage::from_bytes(&bytes);
+        let new_connect_message = ConnectMessage::from_bytes(&bytes).unwrap();
 
         // Comprobamos que los mensajes son iguales
         assert_eq!(connect_message.payload, new_connect_message.payload);
@@ -356,7 +408,7 @@ mod tests {
         let bytes = connect_message.to_bytes();
 
         // Convertimos los bytes a un nuevo mensaje
-        let new_connect_message = ConnectMessage::from_bytes(&bytes);
+        let new_connect_message = ConnectMessage::from_bytes(&bytes).unwrap();
 
         // La función get_user obtiene el user del mensaje luego de convertirlo a mensaje desde bytes
         assert_eq!(new_connect_message.get_user().unwrap(), "test_user");
@@ -378,9 +430,44 @@ mod tests {
         let bytes = connect_message.to_bytes();
 
         // Convertimos los bytes a un nuevo mensaje
-        let new_connect_message = ConnectMessage::from_bytes(&bytes);
+        let new_connect_message = ConnectMessage::from_bytes(&bytes).unwrap();
 
         // Comprobamos que los mensajes son iguales
         assert_eq!(connect_message.payload, new_connect_message.payload);
     }
+
+    #[test]
+    fn test_connect_mqtt5_se_reconstruye_con_sus_propiedades() {
+        let mut connect_message = create_connect_message();
+        let mut properties = MqttProperties::new();
+        properties.session_expiry_interval = Some(60);
+        properties.topic_alias_maximum = Some(10);
+        connect_message.set_mqtt5_properties(properties.clone());
+
+        let bytes = connect_message.to_bytes();
+        let new_connect_message = ConnectMessage::from_bytes(&bytes).unwrap();
+
+        assert_eq!(new_connect_message.get_protocol_version().unwrap(), ProtocolVersion::V5);
+        assert_eq!(new_connect_message.get_properties(), Some(&properties));
+        assert_eq!(connect_message.payload, new_connect_message.payload);
+    }
+
+    #[test]
+    fn test_connect_de_mas_de_255_bytes_se_reconstruye() {
+        let mut connect_message = ConnectMessage::new(
+            "test_client".to_string(),
+            Some("test/will".to_string()),
+            Some("w".repeat(200)),
+            Some("u".repeat(100)),
+            Some("test_password".to_string()),
+            1,
+        );
+
+        let bytes = connect_message.to_bytes();
+        assert!(bytes.len() > 255);
+        let new_connect_message = ConnectMessage::from_bytes(&bytes).unwrap();
+
+        assert_eq!(connect_message.fixed_header, new_connect_message.fixed_header);
+        assert_eq!(connect_message.payload, new_connect_message.payload);
+    }
 }
@@ -1,5 +1,7 @@
 use std::io::{Error, ErrorKind};
 
+use super::reason_code::ReasonCode;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConnectReturnCode {
     ConnectionAccepted = 0x00,
@@ -38,4 +40,30 @@ impl ConnectReturnCode {
             )),
         }
     }
+
+    /// Devuelve el reason code de MQTT 5 equivalente, para el connack de una conexión MQTT 5.
+    pub fn to_reason_code(&self) -> ReasonCode {
+        match self {
+            ConnectReturnCode::ConnectionAccepted => ReasonCode::Success,
+            ConnectReturnCode::ProtocolError => ReasonCode::UnsupportedProtocolVersion,
+            ConnectReturnCode::IdentifierRejected => ReasonCode::ClientIdentifierNotValid,
+            ConnectReturnCode::ServerUnavailable => ReasonCode::ServerUnavailable,
+            ConnectReturnCode::BadUsernameOrPassword => ReasonCode::BadUsernameOrPassword,
+            ConnectReturnCode::NotAuthorized => ReasonCode::NotAuthorized,
+            ConnectReturnCode::UnspecifiedError => ReasonCode::UnspecifiedError,
+        }
+    }
+
+    /// Devuelve el return code equivalente al reason code de MQTT 5 recibido en un connack.
+    pub fn from_reason_code(reason_code: ReasonCode) -> Self {
+        match reason_code {
+            ReasonCode::Success => ConnectReturnCode::ConnectionAccepted,
+            ReasonCode::UnsupportedProtocolVersion => ConnectReturnCode::ProtocolError,
+            ReasonCode::ClientIdentifierNotValid => ConnectReturnCode::IdentifierRejected,
+            ReasonCode::ServerUnavailable => ConnectReturnCode::ServerUnavailable,
+            ReasonCode::BadUsernameOrPassword => ConnectReturnCode::BadUsernameOrPassword,
+            ReasonCode::NotAuthorized => ConnectReturnCode::NotAuthorized,
+            _ => ConnectReturnCode::UnspecifiedError,
+        }
+    }
 }
@@ -1,8 +1,9 @@
-use crate::mqtt::messages::connect_flags::ConnectFlags;
+use crate::mqtt::messages::{connect_flags::ConnectFlags, mqtt_properties::MqttProperties};
 
 #[derive(Debug, PartialEq)]
 pub struct VariableHeader {
     pub protocol_name: [u8; 4],      // bytes 1-4
-    pub protocol_level: u8,          // byte 6
+    pub protocol_level: u8,          // byte 6: 4 para MQTT 3.1.1, 5 para MQTT 5.
     pub connect_flags: ConnectFlags, // byte 7
+    pub properties: Option<MqttProperties>, // Sólo si protocol_level es 5.
 }
@@ -1,8 +1,15 @@
-use crate::mqtt::messages::disconnect_fixed_header::FixedHeader;
+use std::io::Error;
 
+use crate::mqtt::messages::{
+    disconnect_fixed_header::FixedHeader, mqtt_properties::MqttProperties, reason_code::ReasonCode,
+};
+
+/// En MQTT 3.1.1 el disconnect es un único byte. En MQTT 5 lleva además un reason code y propiedades.
 #[derive(Debug, PartialEq)]
 pub struct DisconnectMessage {
     fixed_header: FixedHeader,
+    reason_code: Option<ReasonCode>, // Sólo en MQTT 5.
+    properties: Option<MqttProperties>, // Sólo en MQTT 5.
 }
 
 impl DisconnectMessage {
@@ -13,11 +20,34 @@ impl DisconnectMessage {
             remaining_length: 0,
         };
 
-        DisconnectMessage { fixed_header }
+        DisconnectMessage {
+            fixed_header,
+            reason_code: None,
+            properties: None,
+        }
+    }
+
+    /// Crea un disconnect de MQTT 5, con el motivo de la desconexión.
+    pub fn new_v5(reason_code: ReasonCode, properties: MqttProperties) -> DisconnectMessage {
+        let mut msg = Self::new();
+        msg.fixed_header.remaining_length = (1 + properties.to_bytes().len()) as u8;
+        msg.reason_code = Some(reason_code);
+        msg.properties = Some(properties);
+        msg
+    }
+
+    pub fn get_reason_code(&self) -> Option<ReasonCode> {
+        self.reason_code
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
-        vec![self.fixed_header.message_type << 4 | self.fixed_header.reserved]
+        let mut bytes = vec![self.fixed_header.message_type << 4 | self.fixed_header.reserved];
+        if let (Some(reason_code), Some(properties)) = (self.reason_code, &self.properties) {
+            bytes.push(self.fixed_header.remaining_length);
+            bytes.push(reason_code.to_byte());
+            bytes.extend(properties.to_bytes());
+        }
+        bytes
     }
 
     pub fn from_bytes(bytes: &[u8]) -> DisconnectMessage {
@@ -27,9 +57,23 @@ impl DisconnectMessage {
             remaining_length: 0,
         };
 
-        DisconnectMessage { fixed_header }
+        DisconnectMessage {
+            fixed_header,
+            reason_code: None,
+            properties: None,
+        }
     }
 
+    /// Reconstruye un disconnect de MQTT 5 (si `bytes` tiene sólo el primer byte, es uno de 3.1.1).
+    pub fn from_bytes_v5(bytes: &[u8]) -> Result<DisconnectMessage, Error> {
+        let mut msg = Self::from_bytes(bytes);
+        if bytes.len() > 2 {
+            msg.fixed_header.remaining_length = bytes[1];
+            msg.reason_code = Some(ReasonCode::from_byte(bytes[2])?);
+            msg.properties = Some(MqttProperties::from_bytes(&bytes[3..])?.0);
+        }
+        Ok(msg)
+    }
 }
 
 impl Default for DisconnectMessage {
@@ -41,6 +85,7 @@ impl Default for DisconnectMessage {
 #[cfg(test)]
 mod test {
     use super::DisconnectMessage;
+    use crate::mqtt::messages::{mqtt_properties::MqttProperties, reason_code::ReasonCode};
 
     #[test]
     fn test_disconnect_msg_to_and_from_bytes_works() {
@@ -49,6 +94,17 @@ mod test {
 
         assert_eq!(reconstructed_msg, original_msg)
     }
+
+    #[test]
+    fn test_disconnect_msg_mqtt5_to_and_from_bytes_works() {
+        let mut properties = MqttProperties::new();
+        properties.reason_string = Some("Cerrando servidor".to_string());
+        let original_msg = DisconnectMessage::new_v5(ReasonCode::ServerShuttingDown, properties);
+        let reconstructed_msg = DisconnectMessage::from_bytes_v5(&original_msg.to_bytes()).unwrap();
+
+        assert_eq!(reconstructed_msg, original_msg);
+        assert_eq!(reconstructed_msg.get_reason_code(), Some(ReasonCode::ServerShuttingDown));
+    }
 }
 
 // CHEQUEAR MAS ADELANTE
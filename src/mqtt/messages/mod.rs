@@ -11,13 +11,17 @@ pub mod connect_variable_header;
 pub mod disconnect_fixed_header;
 pub mod disconnect_message;
 pub mod message_type;
+pub mod mqtt_properties;
 pub mod packet_type;
+pub mod ping_message;
+pub mod protocol_version;
 pub mod puback_message;
 pub mod publish_fixed_header;
 pub mod publish_flags;
 pub mod publish_message;
 pub mod publish_payload;
 pub mod publish_variable_header;
+pub mod reason_code;
 pub mod suback_message;
 pub mod subscribe_flags;
 pub mod subscribe_message;
@@ -0,0 +1,238 @@
+use std::io::{Error, ErrorKind};
+
+// Identificadores de las propiedades de MQTT 5 soportadas.
+const MESSAGE_EXPIRY_INTERVAL: u8 = 0x02;
+const RESPONSE_TOPIC: u8 = 0x08;
+const CORRELATION_DATA: u8 = 0x09;
+const SESSION_EXPIRY_INTERVAL: u8 = 0x11;
+const REASON_STRING: u8 = 0x1F;
+const TOPIC_ALIAS_MAXIMUM: u8 = 0x22;
+const TOPIC_ALIAS: u8 = 0x23;
+const USER_PROPERTY: u8 = 0x26;
+
+/// Propiedades de MQTT 5. Se envían en el variable header de cada mensaje, sólo en conexiones MQTT 5,
+/// precedidas por su longitud codificada como variable byte integer.
+/// Cada tipo de mensaje usa únicamente las propiedades que le corresponden; el resto queda en None.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MqttProperties {
+    pub message_expiry_interval: Option<u32>, // Segundos, publish.
+    pub response_topic: Option<String>,       // Publish, para request/response.
+    pub correlation_data: Option<Vec<u8>>,    // Publish, para request/response.
+    pub session_expiry_interval: Option<u32>, // Segundos, connect.
+    pub reason_string: Option<String>,        // Acks y disconnect.
+    pub topic_alias_maximum: Option<u16>,     // Connect y connack.
+    pub topic_alias: Option<u16>,             // Publish.
+    pub user_properties: Vec<(String, String)>,
+}
+
+impl MqttProperties {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Agrega una user property.
+    pub fn add_user_property(&mut self, name: &str, value: &str) {
+        self.user_properties.push((name.to_string(), value.to_string()));
+    }
+
+    /// Devuelve el valor de la primera user property de nombre `name`, si existe.
+    pub fn get_user_property(&self, name: &str) -> Option<&String> {
+        self.user_properties
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Quita y devuelve la primera user property de nombre `name`, si existe.
+    pub fn take_user_property(&mut self, name: &str) -> Option<String> {
+        let pos = self.user_properties.iter().position(|(n, _)| n == name)?;
+        Some(self.user_properties.remove(pos).1)
+    }
+
+    /// Pasa las propiedades a bytes: primero la longitud como variable byte integer, y luego cada propiedad.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut props = vec![];
+        if let Some(value) = self.message_expiry_interval {
+            props.push(MESSAGE_EXPIRY_INTERVAL);
+            props.extend(value.to_be_bytes());
+        }
+        if let Some(value) = &self.response_topic {
+            props.push(RESPONSE_TOPIC);
+            push_binary(&mut props, value.as_bytes());
+        }
+        if let Some(value) = &self.correlation_data {
+            props.push(CORRELATION_DATA);
+            push_binary(&mut props, value);
+        }
+        if let Some(value) = self.session_expiry_interval {
+            props.push(SESSION_EXPIRY_INTERVAL);
+            props.extend(value.to_be_bytes());
+        }
+        if let Some(value) = &self.reason_string {
+            props.push(REASON_STRING);
+            push_binary(&mut props, value.as_bytes());
+        }
+        if let Some(value) = self.topic_alias_maximum {
+            props.push(TOPIC_ALIAS_MAXIMUM);
+            props.extend(value.to_be_bytes());
+        }
+        if let Some(value) = self.topic_alias {
+            props.push(TOPIC_ALIAS);
+            props.extend(value.to_be_bytes());
+        }
+        for (name, value) in &self.user_properties {
+            props.push(USER_PROPERTY);
+            push_binary(&mut props, name.as_bytes());
+            push_binary(&mut props, value.as_bytes());
+        }
+
+        let mut bytes = encode_variable_byte_integer(props.len() as u32);
+        bytes.extend(props);
+        bytes
+    }
+
+    /// Lee las propiedades desde el inicio de `bytes`.
+    /// Devuelve las propiedades y la cantidad de bytes leídos (incluyendo la longitud).
+    pub fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), Error> {
+        let (props_len, len_size) = decode_variable_byte_integer(bytes)?;
+        let end = len_size + props_len as usize;
+        if bytes.len() < end {
+            return Err(Error::new(ErrorKind::InvalidData, "Propiedades incompletas."));
+        }
+
+        let mut properties = Self::new();
+        let mut idx = len_size;
+        while idx < end {
+            let id = bytes[idx];
+            idx += 1;
+            match id {
+                MESSAGE_EXPIRY_INTERVAL => properties.message_expiry_interval = Some(read_u32(bytes, &mut idx)?),
+                RESPONSE_TOPIC => properties.response_topic = Some(read_string(bytes, &mut idx)?),
+                CORRELATION_DATA => properties.correlation_data = Some(read_binary(bytes, &mut idx)?),
+                SESSION_EXPIRY_INTERVAL => properties.session_expiry_interval = Some(read_u32(bytes, &mut idx)?),
+                REASON_STRING => properties.reason_string = Some(read_string(bytes, &mut idx)?),
+                TOPIC_ALIAS_MAXIMUM => properties.topic_alias_maximum = Some(read_u16(bytes, &mut idx)?),
+                TOPIC_ALIAS => properties.topic_alias = Some(read_u16(bytes, &mut idx)?),
+                USER_PROPERTY => {
+                    let name = read_string(bytes, &mut idx)?;
+                    let value = read_string(bytes, &mut idx)?;
+                    properties.user_properties.push((name, value));
+                }
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Propiedad no soportada: {:#x}.", id),
+                    ))
+                }
+            }
+        }
+        if idx != end {
+            return Err(Error::new(ErrorKind::InvalidData, "Longitud de propiedades inválida."));
+        }
+
+        Ok((properties, end))
+    }
+}
+
+/// Codifica `value` como variable byte integer: 7 bits por byte, con el bit más significativo
+/// indicando si sigue otro byte.
+pub fn encode_variable_byte_integer(mut value: u32) -> Vec<u8> {
+    let mut bytes = vec![];
+    loop {
+        let mut byte = (value % 128) as u8;
+        value /= 128;
+        if value > 0 {
+            byte |= 0x80;
+        }
+        bytes.push(byte);
+        if value == 0 {
+            return bytes;
+        }
+    }
+}
+
+/// Decodifica un variable byte integer desde el inicio de `bytes`. Devuelve el valor y la cantidad de bytes leídos.
+pub fn decode_variable_byte_integer(bytes: &[u8]) -> Result<(u32, usize), Error> {
+    let mut value: u32 = 0;
+    let mut multiplier: u32 = 1;
+    for (i, byte) in bytes.iter().take(4).enumerate() {
+        value += (*byte & 0x7F) as u32 * multiplier;
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+        multiplier *= 128;
+    }
+    Err(Error::new(ErrorKind::InvalidData, "Variable byte integer inválido."))
+}
+
+fn push_binary(bytes: &mut Vec<u8>, data: &[u8]) {
+    bytes.extend((data.len() as u16).to_be_bytes());
+    bytes.extend(data);
+}
+
+fn take<'a>(bytes: &'a [u8], idx: &mut usize, len: usize) -> Result<&'a [u8], Error> {
+    let slice = bytes
+        .get(*idx..*idx + len)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Propiedad incompleta."))?;
+    *idx += len;
+    Ok(slice)
+}
+
+fn read_u16(bytes: &[u8], idx: &mut usize) -> Result<u16, Error> {
+    let b = take(bytes, idx, 2)?;
+    Ok(u16::from_be_bytes([b[0], b[1]]))
+}
+
+fn read_u32(bytes: &[u8], idx: &mut usize) -> Result<u32, Error> {
+    let b = take(bytes, idx, 4)?;
+    Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_binary(bytes: &[u8], idx: &mut usize) -> Result<Vec<u8>, Error> {
+    let len = read_u16(bytes, idx)? as usize;
+    Ok(take(bytes, idx, len)?.to_vec())
+}
+
+fn read_string(bytes: &[u8], idx: &mut usize) -> Result<String, Error> {
+    String::from_utf8(read_binary(bytes, idx)?)
+        .map_err(|_| Error::new(ErrorKind::InvalidData, "Propiedad no es UTF-8 válido."))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode_variable_byte_integer, encode_variable_byte_integer, MqttProperties};
+
+    #[test]
+    fn test_1_variable_byte_integer_se_codifica_y_decodifica() {
+        for value in [0, 127, 128, 16_383, 16_384, 2_097_151, 268_435_455] {
+            let bytes = encode_variable_byte_integer(value);
+            assert_eq!(decode_variable_byte_integer(&bytes).unwrap(), (value, bytes.len()));
+        }
+        assert_eq!(encode_variable_byte_integer(321), vec![0xC1, 0x02]);
+    }
+
+    #[test]
+    fn test_2_propiedades_se_pasan_a_bytes_y_se_reconstruyen() {
+        let mut props = MqttProperties::new();
+        props.message_expiry_interval = Some(30);
+        props.response_topic = Some("resp/dron-1".to_string());
+        props.correlation_data = Some(vec![1, 2, 3]);
+        props.topic_alias = Some(4);
+        props.add_user_property("app_id", "sistema_camaras");
+
+        let mut bytes = props.to_bytes();
+        let props_len = bytes.len();
+        bytes.extend([9, 9]); // Lo que sigue a las propiedades no se lee.
+
+        let (reconstructed, read) = MqttProperties::from_bytes(&bytes).unwrap();
+        assert_eq!(reconstructed, props);
+        assert_eq!(read, props_len);
+        assert_eq!(reconstructed.get_user_property("app_id").unwrap(), "sistema_camaras");
+    }
+
+    #[test]
+    fn test_3_propiedades_vacias_ocupan_un_byte() {
+        assert_eq!(MqttProperties::new().to_bytes(), vec![0]);
+        assert_eq!(MqttProperties::from_bytes(&[0]).unwrap(), (MqttProperties::new(), 1));
+    }
+}
@@ -0,0 +1,68 @@
+use std::io::{Error, ErrorKind};
+
+use super::{message::Message, packet_type::PacketType};
+
+/// Mensajes pingreq (del cliente al servidor) y pingresp (la respuesta del servidor).
+/// Ambos son sólo el fixed header, sin variable header ni payload.
+#[derive(Debug, PartialEq)]
+pub struct PingMessage {
+    packet_type: PacketType,
+}
+
+impl PingMessage {
+    pub fn new_pingreq() -> Self {
+        PingMessage { packet_type: PacketType::Pingreq }
+    }
+
+    pub fn new_pingresp() -> Self {
+        PingMessage { packet_type: PacketType::Pingresp }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        vec![(self.packet_type as u8) << 4, 0]
+    }
+
+    /// Reconstruye el mensaje; devuelve error si `bytes` no es un pingreq ni un pingresp.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let packet_type = bytes
+            .first()
+            .map(|first_byte| PacketType::from(first_byte >> 4))
+            .filter(|packet_type| matches!(packet_type, PacketType::Pingreq | PacketType::Pingresp))
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "El mensaje no es un pingreq ni un pingresp."))?;
+        Ok(PingMessage { packet_type })
+    }
+}
+
+impl Message for PingMessage {
+    fn get_packet_id(&self) -> Option<u16> {
+        None
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn get_type(&self) -> PacketType {
+        self.packet_type
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PingMessage;
+
+    #[test]
+    fn test_1_pingreq_y_pingresp_to_and_from_bytes() {
+        let pingreq = PingMessage::new_pingreq();
+        let pingresp = PingMessage::new_pingresp();
+
+        assert_eq!(pingreq.to_bytes(), vec![0xC0, 0]);
+        assert_eq!(PingMessage::from_bytes(&pingreq.to_bytes()).unwrap(), pingreq);
+        assert_eq!(PingMessage::from_bytes(&pingresp.to_bytes()).unwrap(), pingresp);
+        assert!(PingMessage::from_bytes(&[0x30, 0]).is_err());
+    }
+}
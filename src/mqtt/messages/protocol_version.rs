@@ -0,0 +1,35 @@
+use std::io::{Error, ErrorKind};
+
+/// Versión del protocolo MQTT negociada en el connect, mediante el campo `protocol_level`.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum ProtocolVersion {
+    #[default]
+    V311,
+    V5,
+}
+
+impl ProtocolVersion {
+    /// Devuelve el `protocol_level` que se envía en el connect para esta versión.
+    pub fn to_level(&self) -> u8 {
+        match self {
+            ProtocolVersion::V311 => 4,
+            ProtocolVersion::V5 => 5,
+        }
+    }
+
+    /// Devuelve la versión correspondiente al `protocol_level` recibido, o error si no está soportada.
+    pub fn from_level(level: u8) -> Result<Self, Error> {
+        match level {
+            4 => Ok(ProtocolVersion::V311),
+            5 => Ok(ProtocolVersion::V5),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Versión de protocolo no soportada: {}.", level),
+            )),
+        }
+    }
+
+    pub fn is_v5(&self) -> bool {
+        *self == ProtocolVersion::V5
+    }
+}
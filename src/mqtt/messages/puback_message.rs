@@ -1,7 +1,10 @@
 use std::{
-    io::{Error, ErrorKind},
+    io::Error,
     mem::size_of,
 };
+
+use super::mqtt_properties::MqttProperties;
+
 #[derive(Debug, PartialEq)]
 pub struct PubAckMessage {
     // Fixed header
@@ -9,6 +12,7 @@ pub struct PubAckMessage {
     // Variable header
     packet_id: u16,
     puback_reason_code: u8,
+    properties: Option<MqttProperties>, // Sólo en MQTT 5; en ese caso el reason code se envía siempre.
     // El PubAck no lleva payload.
 }
 
@@ -18,9 +22,15 @@ impl PubAckMessage {
             tipo: 4,
             packet_id,
             puback_reason_code,
+            properties: None,
         }
     }
 
+    /// Convierte el mensaje en un puback de MQTT 5, con las propiedades recibidas.
+    pub fn set_mqtt5_properties(&mut self, properties: MqttProperties) {
+        self.properties = Some(properties);
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut msg_bytes: Vec<u8> = vec![];
 
@@ -33,9 +43,12 @@ impl PubAckMessage {
         let rem_len: u8 = self.remaining_length();
         msg_bytes.extend(rem_len.to_be_bytes());
 
-        // Variable header: packet_id y reason code
+        // Variable header: packet_id, reason code y, en MQTT 5, propiedades
         msg_bytes.extend(self.packet_id.to_be_bytes());
-        if self.puback_reason_code != 0 {
+        if let Some(properties) = &self.properties {
+            msg_bytes.extend(self.puback_reason_code.to_be_bytes());
+            msg_bytes.extend(properties.to_bytes());
+        } else if self.puback_reason_code != 0 {
             msg_bytes.extend(self.puback_reason_code.to_be_bytes());
         }
 
@@ -47,7 +60,9 @@ impl PubAckMessage {
     fn remaining_length(&self) -> u8 {
         let mut rem_len: u8 = 0;
         rem_len += 2; // tam de u16 packet_id
-        if self.puback_reason_code != 0 {
+        if let Some(properties) = &self.properties {
+            rem_len += 1 + properties.to_bytes().len() as u8;
+        } else if self.puback_reason_code != 0 {
             rem_len += 1;
         } // Si es 0, significa success y no se envía, else sí se envía.
         rem_len
@@ -71,23 +86,33 @@ impl PubAckMessage {
         let packet_id = u16::from_be_bytes(
             msg_bytes[idx..idx + size_of_u16]
                 .try_into()
-                .map_err(|_| Error::new(ErrorKind::Other, "Error leyendo bytes puback msg."))?,
+                .map_err(|_| Error::other("Error leyendo bytes puback msg."))?,
         ); // forma 1
-           // Leo, si corresponde, u8 de reason code
+        idx += size_of_u16;
+        // Leo, si corresponde, u8 de reason code
         let mut puback_reason_code: u8 = 0;
-        if remaining_len == 3 {
-            puback_reason_code = (&msg_bytes[0..size_of_u8])[0];
+        if remaining_len >= 3 {
+            puback_reason_code = *msg_bytes
+                .get(idx)
+                .ok_or_else(|| Error::other("Error leyendo bytes puback msg."))?;
+            idx += size_of_u8;
+        }
+        // Si hay más, es un puback de MQTT 5 con propiedades
+        let mut properties = None;
+        if remaining_len >= 4 {
+            properties = Some(MqttProperties::from_bytes(&msg_bytes[idx..])?.0);
         }
 
         // Chequeo tipo correcto
         if tipo != 4 {
-            return Err(Error::new(ErrorKind::Other, "Tipo incorrecto."));
+            return Err(Error::other("Tipo incorrecto."));
         }
 
         Ok(PubAckMessage {
             tipo,
             packet_id,
             puback_reason_code,
+            properties,
         })
     }
 
@@ -98,11 +123,17 @@ impl PubAckMessage {
     pub fn get_packet_id(&self) -> u16 {
         self.packet_id
     }
+
+    /// Devuelve las propiedades del puback, si es de MQTT 5.
+    pub fn get_properties(&self) -> Option<&MqttProperties> {
+        self.properties.as_ref()
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::PubAckMessage;
+    use crate::mqtt::messages::{mqtt_properties::MqttProperties, reason_code::ReasonCode};
 
     #[test]
     fn test_1a_puback_msg_caso_success_tiene_rem_len_acorde() {
@@ -127,4 +158,17 @@ mod test {
 
         assert_eq!(msg_reconstruido.unwrap(), msg);
     }
+
+    #[test]
+    fn test_3_puback_msg_mqtt5_envia_reason_code_y_propiedades() {
+        let mut msg = PubAckMessage::new(1, ReasonCode::NotAuthorized.to_byte());
+        let mut properties = MqttProperties::new();
+        properties.reason_string = Some("acl".to_string());
+        msg.set_mqtt5_properties(properties);
+
+        let msg_reconstruido = PubAckMessage::msg_from_bytes(msg.to_bytes()).unwrap();
+
+        assert_eq!(msg_reconstruido.get_reason_code(), ReasonCode::NotAuthorized.to_byte());
+        assert_eq!(msg_reconstruido, msg);
+    }
 }
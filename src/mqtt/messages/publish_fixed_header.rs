@@ -2,6 +2,6 @@ use crate::mqtt::messages::publish_flags::PublishFlags;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct FixedHeader {
-    pub flags: PublishFlags,  // byte 1, incluye también al msg_type.
-    pub remaining_length: u8, // byte 2
+    pub flags: PublishFlags,     // byte 1, incluye también al msg_type.
+    pub remaining_length: usize, // desde el byte 2, de uno a cuatro bytes
 }
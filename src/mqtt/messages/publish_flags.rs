@@ -1,4 +1,4 @@
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 
 #[derive(Debug, PartialEq, Clone)]
 
@@ -20,7 +20,7 @@ impl PublishFlags {
     /// Devuelve un struct PublishFlags creado, que contiene el byte de flags.
     pub fn new(dup: u8, qos: u8, retain: u8) -> Result<Self, Error> {
         if dup > 1 || qos > 2 || retain > 1 {
-            return Err(Error::new(ErrorKind::Other, "Flags para publish inválidos"));
+            return Err(Error::other("Flags para publish inválidos"));
         }
         Ok(PublishFlags { msg_type: 3, dup, qos, retain })
     }
@@ -49,7 +49,7 @@ impl PublishFlags {
         let msg_type = (byte_de_flags & 0b1111_0000) >> 4;
 
         if msg_type != 3 {
-            return Err(Error::new(ErrorKind::Other, "Flags para publish leídos con tipo inválido."));
+            return Err(Error::other("Flags para publish leídos con tipo inválido."));
         }
 
         Ok(PublishFlags { msg_type, dup, qos, retain })
@@ -59,6 +59,21 @@ impl PublishFlags {
     pub fn get_qos(&self) -> u8 {
         self.qos
     }
+
+    /// Devuelve el flag de retain.
+    pub fn get_retain(&self) -> u8 {
+        self.retain
+    }
+
+    /// Devuelve el flag de dup.
+    pub fn get_dup(&self) -> u8 {
+        self.dup
+    }
+
+    /// Marca que el mensaje es una retransmisión.
+    pub fn set_dup(&mut self) {
+        self.dup = 1;
+    }
 }
 
 #[cfg(test)]
@@ -18,13 +18,22 @@ type TdesEde3Cbc = Cbc<TdesEde3, Pkcs7>;
 const KEY: [u8; 24] = [0x01; 24]; // Esto es solo un ejemplo, usa claves seguras en producción
 const IV: [u8; 8] = [0x02; 8];
 
+use crate::mqtt::messages::mqtt_properties::MqttProperties;
+use crate::mqtt::messages::protocol_version::ProtocolVersion;
 use crate::mqtt::messages::publish_fixed_header::FixedHeader;
 use crate::mqtt::messages::publish_flags::PublishFlags;
 use crate::mqtt::messages::publish_payload::Payload;
 use crate::mqtt::messages::publish_variable_header::VariableHeader;
+use crate::mqtt::mqtt_utils::fixed_header::{
+    check_remaining_length, decode_remaining_length, encode_remaining_length,
+};
 
 type TimestampType = u128;
 const  TIMESTAMP_LENGHT: usize = 16;
+// En MQTT 5 el timestamp no se envía al final del mensaje, sino como user property con este nombre.
+pub const TIMESTAMP_USER_PROPERTY: &str = "timestamp";
+// En MQTT 5 cada publish lleva el client id de la app que lo publica, como user property con este nombre.
+pub const APP_ID_USER_PROPERTY: &str = "app_id";
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct PublishMessage {
@@ -32,6 +41,7 @@ pub struct PublishMessage {
     variable_header: VariableHeader,
     payload: Payload,
     timestamp: TimestampType,
+    properties: Option<MqttProperties>, // Sólo si el mensaje es de MQTT 5.
 }
 
 impl<'a> PublishMessage {
@@ -132,15 +142,37 @@ impl<'a> PublishMessage {
             variable_header,
             payload,
             timestamp,
+            properties: None,
         };
 
         publish_message.fixed_header.remaining_length =
             publish_message.calculate_remaining_length_2();
+        // El timestamp de MQTT 5 ocupa más que el de 3.1.1, así el mensaje puede enviarse con ambas versiones.
+        check_remaining_length(publish_message.calculate_remaining_length_v5().0)?;
 
         Ok(publish_message)
     }
 
-    fn calculate_remaining_length_2(&self) -> u8 {
+    /// Convierte el mensaje en un publish de MQTT 5, con las propiedades recibidas.
+    pub fn set_mqtt5_properties(&mut self, properties: MqttProperties) {
+        self.properties = Some(properties);
+        self.fixed_header.remaining_length = self.calculate_remaining_length_v5().0;
+    }
+
+    /// Devuelve las propiedades del mensaje, si es de MQTT 5.
+    pub fn get_properties(&self) -> Option<&MqttProperties> {
+        self.properties.as_ref()
+    }
+
+    /// Devuelve la versión del protocolo con la que se creó o recibió el mensaje.
+    pub fn get_protocol_version(&self) -> ProtocolVersion {
+        match self.properties {
+            Some(_) => ProtocolVersion::V5,
+            None => ProtocolVersion::V311,
+        }
+    }
+
+    fn calculate_remaining_length_2(&self) -> usize {
         //aux: remaining length = variable header + payload
         //aux: variable header = topic_name + packet_identifier
         let rem_len_in_two_bytes = 2;
@@ -152,26 +184,46 @@ impl<'a> PublishMessage {
         let payload_length = self.payload.content.len();
         let timestamp_length = TIMESTAMP_LENGHT; // tamaño de u128
 
-        (rem_len_in_two_bytes
+        rem_len_in_two_bytes
             + topic_name_length
             + packet_identifier_length
             + payload_length
-            + timestamp_length) as u8
+            + timestamp_length
+    }
+
+    /// Calcula la remaining length del mensaje en MQTT 5, cuyas propiedades incluyen al timestamp.
+    /// Devuelve también los bytes de las propiedades, para no volver a calcularlos al enviarlo.
+    fn calculate_remaining_length_v5(&self) -> (usize, Vec<u8>) {
+        let mut properties = self.properties.clone().unwrap_or_default();
+        properties.add_user_property(TIMESTAMP_USER_PROPERTY, &self.timestamp.to_string());
+        let properties_bytes = properties.to_bytes();
+
+        let remaining_length = 2
+            + self.variable_header.topic_name.len()
+            + 2 * self.variable_header.packet_identifier.is_some() as usize
+            + properties_bytes.len()
+            + self.payload.content.len();
+        (remaining_length, properties_bytes)
     }
 
     pub fn get_packet_id(&self) -> Option<u16> {
         self.variable_header.packet_identifier
     }
 
-    ///Devuelve: Vector de bytes segun MQTT:
-    /// 1er byte: meesage type y flags
-    /// 2do byte: remaining_length
-    /// 3er byte: topic_name_length_msb
-    /// 4to byte: topic_name_length_lsb
-    /// 5to byte: topic_name
-    /// 6to byte: packet_identifier_msb opcional
-    /// 7mo byte: packet_identifier_lsb opcional
-    /// 8vo byte: payload
+    /// Reemplaza el packet_id, ej para enviarle el mensaje a cada suscriptor con un id propio de su sesión.
+    pub fn set_packet_id(&mut self, packet_id: u16) {
+        self.variable_header.packet_identifier = Some(packet_id);
+    }
+
+    // Devuelve: Vector de bytes segun MQTT:
+    // 1er byte: meesage type y flags
+    // 2do byte: remaining_length
+    // 3er byte: topic_name_length_msb
+    // 4to byte: topic_name_length_lsb
+    // 5to byte: topic_name
+    // 6to byte: packet_identifier_msb opcional
+    // 7mo byte: packet_identifier_lsb opcional
+    // 8vo byte: payload
     // pub fn to_bytes(&self) -> Vec<u8> {
     //     let mut bytes = Vec::new();
 
@@ -205,24 +257,31 @@ impl<'a> PublishMessage {
     //     bytes
     // }
 
+    /// Pasa el mensaje a bytes, según la versión del protocolo del mensaje.
     pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_for(self.get_protocol_version())
+    }
+
+    /// Pasa el mensaje a bytes para una conexión con la versión de protocolo `version`.
+    /// Permite reenviar un mensaje recibido de un cliente MQTT 3.1.1 a uno MQTT 5, y viceversa.
+    pub fn to_bytes_for(&self, version: ProtocolVersion) -> Vec<u8> {
+        match version {
+            ProtocolVersion::V311 => self.to_bytes_v311(),
+            ProtocolVersion::V5 => self.to_bytes_v5(),
+        }
+    }
+
+    fn to_bytes_v311(&self) -> Vec<u8> {
         let mut bytes = Vec::new();
 
         let first_byte = self.fixed_header.flags.to_flags_byte();
         bytes.push(first_byte);
 
-        let topic_name_length = self.variable_header.topic_name.len() as u8;
-        let remaining_length = 2
-            + topic_name_length
-            + 2 * self.variable_header.packet_identifier.is_some() as u8
-            + self.payload.content.len() as u8
-            + TIMESTAMP_LENGHT as u8; // tamaño del timestamp
-        bytes.push(remaining_length);
-
-        let topic_name_length_msb = ((topic_name_length as u16 >> 8) & 0xFF) as u8;
-        let topic_name_length_lsb = topic_name_length;
-        bytes.push(topic_name_length_msb);
-        bytes.push(topic_name_length_lsb);
+        // La remaining length incluye el tamaño del timestamp, y se envía como variable byte integer.
+        bytes.extend(encode_remaining_length(self.calculate_remaining_length_2()));
+
+        let topic_name_length = self.variable_header.topic_name.len() as u16;
+        bytes.extend(topic_name_length.to_be_bytes());
         bytes.extend_from_slice(self.variable_header.topic_name.as_bytes());
         if let Some(packet_identifier) = self.variable_header.packet_identifier {
             bytes.push((packet_identifier >> 8) as u8);
@@ -237,44 +296,97 @@ impl<'a> PublishMessage {
         bytes
     }
 
-    pub fn from_bytes(bytes: Vec<u8>) -> Result<PublishMessage, std::io::Error> {
-        if bytes.len() < 13 {
-            // Mínimo 5 bytes + 8 bytes de timestamp
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "No hay suficientes bytes para un mensaje válido",
-            ));
+    // MQTT 5: luego del topic y el packet_identifier van las propiedades, y el timestamp viaja en ellas.
+    fn to_bytes_v5(&self) -> Vec<u8> {
+        let (remaining_length, properties_bytes) = self.calculate_remaining_length_v5();
+
+        let mut bytes = vec![self.fixed_header.flags.to_flags_byte()];
+        bytes.extend(encode_remaining_length(remaining_length));
+
+        bytes.extend((self.variable_header.topic_name.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(self.variable_header.topic_name.as_bytes());
+        if let Some(packet_identifier) = self.variable_header.packet_identifier {
+            bytes.extend(packet_identifier.to_be_bytes());
+        }
+        bytes.extend(properties_bytes);
+        bytes.extend_from_slice(&self.payload.content);
+
+        bytes
+    }
+
+    /// Parsea los bytes de un publish recibido por una conexión con la versión de protocolo `version`.
+    pub fn from_bytes_with_version(bytes: Vec<u8>, version: ProtocolVersion) -> Result<PublishMessage, Error> {
+        match version {
+            ProtocolVersion::V311 => Self::from_bytes(bytes),
+            ProtocolVersion::V5 => Self::from_bytes_v5(bytes),
+        }
+    }
+
+    fn from_bytes_v5(bytes: Vec<u8>) -> Result<PublishMessage, Error> {
+        let invalid = || Error::new(ErrorKind::InvalidData, "No hay suficientes bytes para un mensaje válido");
+        let flags = PublishFlags::from_flags_byte(*bytes.first().ok_or_else(invalid)?)?;
+        let (remaining_length, header_len) = decode_remaining_length(&bytes)?;
+        let (topic_name, mut idx) = read_topic_name(&bytes, header_len)?;
+
+        // En MQTT 5 el packet_identifier se determina por el qos, ya que a continuación siguen las propiedades.
+        let mut packet_identifier = None;
+        if flags.is_qos_greater_than_0() {
+            let id_bytes = bytes.get(idx..idx + 2).ok_or_else(invalid)?;
+            packet_identifier = Some(u16::from_be_bytes([id_bytes[0], id_bytes[1]]));
+            idx += 2;
         }
 
-        let first_byte = bytes[0];
-        let flags = PublishFlags::from_flags_byte(first_byte)?;
-        let remaining_length = bytes[1];
-
-        let topic_name_length = ((bytes[2] as usize) << 8) | (bytes[3] as usize);
-        let topic_name = match String::from_utf8(bytes[4..4 + topic_name_length].to_vec()) {
-            Ok(v) => v,
-            Err(_) => {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    "El nombre del tema no es válido UTF-8",
-                ))
-            }
+        let (mut properties, properties_len) = MqttProperties::from_bytes(bytes.get(idx..).ok_or_else(invalid)?)?;
+        idx += properties_len;
+
+        let timestamp = match properties.take_user_property(TIMESTAMP_USER_PROPERTY) {
+            Some(timestamp) => timestamp
+                .parse()
+                .map_err(|_| Error::new(ErrorKind::InvalidData, "Timestamp inválido."))?,
+            None => now_as_timestamp(),
         };
 
+        Ok(Self {
+            fixed_header: FixedHeader {
+                flags,
+                remaining_length,
+            },
+            variable_header: VariableHeader {
+                topic_name,
+                packet_identifier,
+            },
+            payload: Payload {
+                content: bytes.get(idx..).ok_or_else(invalid)?.to_vec(),
+            },
+            timestamp,
+            properties: Some(properties),
+        })
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<PublishMessage, std::io::Error> {
+        let invalid = || Error::new(ErrorKind::InvalidData, "No hay suficientes bytes para un mensaje válido");
+        let flags = PublishFlags::from_flags_byte(*bytes.first().ok_or_else(invalid)?)?;
+        let (remaining_length, header_len) = decode_remaining_length(&bytes)?;
+        let (topic_name, mut payload_start) = read_topic_name(&bytes, header_len)?;
+
+        // El packet_identifier sólo está presente si el qos es mayor a 0.
         let mut packet_identifier = None;
-        if remaining_length > (topic_name_length + 2) as u8 {
-            packet_identifier = Some(
-                ((bytes[4 + topic_name_length] as u16) << 8)
-                    | (bytes[5 + topic_name_length] as u16),
-            );
+        if flags.is_qos_greater_than_0() {
+            let id_bytes = bytes.get(payload_start..payload_start + 2).ok_or_else(invalid)?;
+            packet_identifier = Some(u16::from_be_bytes([id_bytes[0], id_bytes[1]]));
+            payload_start += 2;
         }
 
-        let payload_start = 4 + topic_name_length + 2 * packet_identifier.is_some() as usize;
-        let payload_end = bytes.len() - TIMESTAMP_LENGHT;
+        // Al final del mensaje viaja el timestamp.
+        let payload_end = bytes
+            .len()
+            .checked_sub(TIMESTAMP_LENGHT)
+            .filter(|end| *end >= payload_start)
+            .ok_or_else(invalid)?;
         let payload_content = bytes[payload_start..payload_end].to_vec();
 
         // Cambiar el u128 en caso de que se cambie el tipo de dato del TIMESTAMP
-        let timestamp = u128::from_be_bytes(bytes[payload_end..].try_into().unwrap());
+        let timestamp = u128::from_be_bytes(bytes[payload_end..].try_into().map_err(|_| invalid())?);
 
         Ok(Self {
             fixed_header: FixedHeader {
@@ -289,6 +401,7 @@ impl<'a> PublishMessage {
                 content: payload_content,
             },
             timestamp,
+            properties: None,
         })
     }
 
@@ -356,6 +469,21 @@ impl<'a> PublishMessage {
         self.fixed_header.flags.get_qos()
     }
 
+    /// Devuelve si el mensaje fue publicado con el flag de retain.
+    pub fn is_retained(&self) -> bool {
+        self.fixed_header.flags.get_retain() == 1
+    }
+
+    /// Devuelve si el mensaje es una retransmisión (flag dup).
+    pub fn is_dup(&self) -> bool {
+        self.fixed_header.flags.get_dup() == 1
+    }
+
+    /// Marca al mensaje como retransmisión, con el flag dup.
+    pub fn set_dup(&mut self) {
+        self.fixed_header.flags.set_dup();
+    }
+
     pub fn get_topic_name(&self) -> String {
         self.variable_header.topic_name.to_string()
     }
@@ -363,6 +491,70 @@ impl<'a> PublishMessage {
     pub fn get_timestamp(&self) -> TimestampType {
         self.timestamp
     }
+
+    /// Reemplaza el topic del mensaje. Usado al resolver un topic alias de MQTT 5.
+    pub fn set_topic(&mut self, topic: &str) {
+        self.variable_header.topic_name = topic.to_string();
+    }
+
+    /// Devuelve el id de la app que publicó el mensaje, si es de MQTT 5 y lo indica.
+    pub fn get_app_id(&self) -> Option<&str> {
+        self.properties
+            .as_ref()
+            .and_then(|props| props.get_user_property(APP_ID_USER_PROPERTY))
+            .map(|app_id| app_id.as_str())
+    }
+
+    /// Devuelve el topic alias del mensaje, si es de MQTT 5 y tiene uno.
+    pub fn get_topic_alias(&self) -> Option<u16> {
+        self.properties.as_ref().and_then(|props| props.topic_alias)
+    }
+
+    /// Quita el topic alias, que sólo tiene sentido en la conexión por la que se recibió el mensaje.
+    pub fn remove_topic_alias(&mut self) {
+        if let Some(props) = self.properties.as_mut() {
+            props.topic_alias = None;
+        }
+    }
+
+    /// Devuelve el response topic (MQTT 5) al que debe enviarse la respuesta a este mensaje, si lo tiene.
+    pub fn get_response_topic(&self) -> Option<String> {
+        self.properties.as_ref().and_then(|props| props.response_topic.clone())
+    }
+
+    /// Devuelve los correlation data (MQTT 5) que identifican a la request, si los tiene.
+    pub fn get_correlation_data(&self) -> Option<Vec<u8>> {
+        self.properties.as_ref().and_then(|props| props.correlation_data.clone())
+    }
+
+    /// Devuelve si pasó el message expiry interval (MQTT 5) del mensaje, medido desde su timestamp.
+    pub fn is_expired(&self) -> bool {
+        if let Some(expiry) = self.properties.as_ref().and_then(|props| props.message_expiry_interval) {
+            let elapsed_secs = now_as_timestamp().saturating_sub(self.timestamp) / 1_000_000_000;
+            return elapsed_secs >= expiry as u128;
+        }
+        false
+    }
+}
+
+/// Lee el nombre del tema, precedido por su longitud en dos bytes, a partir de `idx`.
+/// Devuelve el tema y el índice siguiente a él.
+fn read_topic_name(bytes: &[u8], idx: usize) -> Result<(String, usize), Error> {
+    let invalid = || Error::new(ErrorKind::InvalidData, "No hay suficientes bytes para un mensaje válido");
+    let len_bytes = bytes.get(idx..idx + 2).ok_or_else(invalid)?;
+    let topic_name_length = u16::from_be_bytes([len_bytes[0], len_bytes[1]]) as usize;
+    let topic_start = idx + 2;
+    let topic_bytes = bytes.get(topic_start..topic_start + topic_name_length).ok_or_else(invalid)?;
+    let topic_name = String::from_utf8(topic_bytes.to_vec())
+        .map_err(|_| Error::new(ErrorKind::InvalidData, "El nombre del tema no es válido UTF-8"))?;
+    Ok((topic_name, topic_start + topic_name_length))
+}
+
+fn now_as_timestamp() -> TimestampType {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
 }
 
 use super::packet_type::PacketType;
@@ -492,6 +684,85 @@ mod tests {
     //     assert_eq!(recovered_message, original_message);
     // }
 
+    #[test]
+    fn test_publish_311_con_qos_0_se_reconstruye_sin_packet_identifier() {
+        let publish_message =
+            PublishMessage::new(PublishFlags::new(0, 0, 1).unwrap(), "test/topic", None, b"hola").unwrap();
+
+        let deserialized_message = PublishMessage::from_bytes(publish_message.to_bytes()).unwrap();
+
+        assert_eq!(deserialized_message, publish_message);
+        assert_eq!(deserialized_message.get_packet_id(), None);
+        assert_eq!(deserialized_message.get_payload(), b"hola".to_vec());
+    }
+
+    #[test]
+    fn test_publish_mqtt5_se_reconstruye_con_propiedades_y_timestamp() {
+        let mut publish_message = create_test_publish_message().unwrap();
+        let mut properties = MqttProperties::new();
+        properties.response_topic = Some("resp/test".to_string());
+        properties.correlation_data = Some(vec![7, 7]);
+        properties.add_user_property("app_id", "test");
+        publish_message.set_mqtt5_properties(properties);
+
+        let bytes = publish_message.to_bytes();
+        let deserialized_message = PublishMessage::from_bytes_with_version(bytes, ProtocolVersion::V5).unwrap();
+
+        assert_eq!(deserialized_message, publish_message);
+        assert_eq!(deserialized_message.get_response_topic().unwrap(), "resp/test");
+        assert_eq!(deserialized_message.get_payload(), b"Hello, world!".to_vec());
+    }
+
+    #[test]
+    fn test_publish_311_se_reenvia_a_mqtt5_y_vuelve_a_311_sin_perder_datos() {
+        let publish_message = create_test_publish_message().unwrap();
+
+        let v5_bytes = publish_message.to_bytes_for(ProtocolVersion::V5);
+        let v5_message = PublishMessage::from_bytes_with_version(v5_bytes, ProtocolVersion::V5).unwrap();
+        assert_eq!(v5_message.get_timestamp(), publish_message.get_timestamp());
+
+        let v311_bytes = v5_message.to_bytes_for(ProtocolVersion::V311);
+        assert_eq!(v311_bytes, publish_message.to_bytes());
+    }
+
+    #[test]
+    fn test_publish_de_mas_de_255_bytes_se_reconstruye_en_ambas_versiones() {
+        let content = vec![b'x'; 1000];
+        let publish_message =
+            PublishMessage::new(PublishFlags::new(0, 1, 0).unwrap(), "test/topic", Some(42), &content).unwrap();
+
+        let v311_bytes = publish_message.to_bytes();
+        assert_eq!(decode_remaining_length(&v311_bytes).unwrap(), (v311_bytes.len() - 3, 3));
+        let v311_message = PublishMessage::from_bytes(v311_bytes).unwrap();
+        assert_eq!(v311_message, publish_message);
+        assert_eq!(v311_message.get_payload(), content);
+
+        let v5_bytes = publish_message.to_bytes_for(ProtocolVersion::V5);
+        let v5_message = PublishMessage::from_bytes_with_version(v5_bytes, ProtocolVersion::V5).unwrap();
+        assert_eq!(v5_message.get_payload(), content);
+        assert_eq!(v5_message.get_timestamp(), publish_message.get_timestamp());
+    }
+
+    #[test]
+    fn test_publish_truncado_da_error() {
+        let bytes = create_test_publish_message().unwrap().to_bytes_for(ProtocolVersion::V5);
+        for len in [1, 3, 8, 14] {
+            assert!(PublishMessage::from_bytes(bytes[..len].to_vec()).is_err());
+            assert!(PublishMessage::from_bytes_with_version(bytes[..len].to_vec(), ProtocolVersion::V5).is_err());
+        }
+    }
+
+    #[test]
+    fn test_publish_con_message_expiry_vencido_expira() {
+        let mut publish_message = create_test_publish_message().unwrap();
+        assert!(!publish_message.is_expired());
+
+        let mut properties = MqttProperties::new();
+        properties.message_expiry_interval = Some(0);
+        publish_message.set_mqtt5_properties(properties);
+        assert!(publish_message.is_expired());
+    }
+
     #[test]
     /// Testeo de la funcion encriptar
     fn test_encrypt() {
@@ -510,4 +781,16 @@ mod tests {
 
         assert_eq!(content.to_vec(), decrypted_content);
     }
+
+    #[test]
+    /// Testeo de que el flag dup de una retransmisión se conserve al pasar a bytes
+    fn test_set_dup() {
+        let mut publish_message = create_test_publish_message().unwrap();
+        assert!(!publish_message.is_dup());
+        publish_message.set_dup();
+
+        let deserialized_message = PublishMessage::from_bytes(publish_message.to_bytes()).unwrap();
+        assert!(deserialized_message.is_dup());
+        assert_eq!(deserialized_message.get_packet_id(), Some(42));
+    }
 }
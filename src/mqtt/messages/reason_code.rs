@@ -0,0 +1,47 @@
+use std::io::{Error, ErrorKind};
+
+/// Reason codes de MQTT 5, enviados en connack, puback, suback y disconnect
+/// a los clientes que se conectaron con esa versión del protocolo.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ReasonCode {
+    Success = 0x00,
+    NoSubscriptionExisted = 0x11,
+    UnspecifiedError = 0x80,
+    MalformedPacket = 0x81,
+    ProtocolError = 0x82,
+    UnsupportedProtocolVersion = 0x84,
+    ClientIdentifierNotValid = 0x85,
+    BadUsernameOrPassword = 0x86,
+    NotAuthorized = 0x87,
+    ServerUnavailable = 0x88,
+    ServerShuttingDown = 0x8B,
+    SessionTakenOver = 0x8E,
+    TopicNameInvalid = 0x90,
+    TopicAliasInvalid = 0x94,
+}
+
+impl ReasonCode {
+    pub fn to_byte(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_byte(byte: u8) -> Result<Self, Error> {
+        match byte {
+            0x00 => Ok(ReasonCode::Success),
+            0x11 => Ok(ReasonCode::NoSubscriptionExisted),
+            0x80 => Ok(ReasonCode::UnspecifiedError),
+            0x81 => Ok(ReasonCode::MalformedPacket),
+            0x82 => Ok(ReasonCode::ProtocolError),
+            0x84 => Ok(ReasonCode::UnsupportedProtocolVersion),
+            0x85 => Ok(ReasonCode::ClientIdentifierNotValid),
+            0x86 => Ok(ReasonCode::BadUsernameOrPassword),
+            0x87 => Ok(ReasonCode::NotAuthorized),
+            0x88 => Ok(ReasonCode::ServerUnavailable),
+            0x8B => Ok(ReasonCode::ServerShuttingDown),
+            0x8E => Ok(ReasonCode::SessionTakenOver),
+            0x90 => Ok(ReasonCode::TopicNameInvalid),
+            0x94 => Ok(ReasonCode::TopicAliasInvalid),
+            _ => Err(Error::new(ErrorKind::InvalidData, "Reason code no válido.")),
+        }
+    }
+}
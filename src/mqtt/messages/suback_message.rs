@@ -1,9 +1,13 @@
 use std::{
-    io::{Error, ErrorKind},
+    io::Error,
     mem::size_of,
 };
 
-use crate::mqtt::messages::subscribe_return_code::SubscribeReturnCode;
+use crate::mqtt::messages::{
+    mqtt_properties::MqttProperties, protocol_version::ProtocolVersion,
+    subscribe_return_code::SubscribeReturnCode,
+};
+use crate::mqtt::mqtt_utils::fixed_header::{decode_remaining_length, encode_remaining_length};
 
 #[derive(Debug, PartialEq)]
 pub struct SubAckMessage {
@@ -11,6 +15,7 @@ pub struct SubAckMessage {
     reserved_flags: u8, // fixed header: 4 bits menos significativos del primer byte, 0
     packet_identifier: u16, // Variable header: 2 bytes
     return_codes: Vec<SubscribeReturnCode>, // Payload: 2 bytes cada uno, corresponde a cada topic_filter recibido.
+    properties: Option<MqttProperties>, // Variable header, sólo en MQTT 5: a continuación del packet identifier.
 }
 
 impl SubAckMessage {
@@ -20,12 +25,21 @@ impl SubAckMessage {
             reserved_flags: 0,
             packet_identifier: packet_id,
             return_codes,
+            properties: None,
         }
     }
 
-    fn remaining_length(&self) -> u8 {
+    /// Convierte el mensaje en un suback de MQTT 5, con las propiedades recibidas.
+    pub fn set_mqtt5_properties(&mut self, properties: MqttProperties) {
+        self.properties = Some(properties);
+    }
+
+    fn remaining_length(&self) -> usize {
         // Calculo la rem_len
-        let mut rem_len: u8 = 2; // 2 bytes de packet identifier
+        let mut rem_len: usize = 2; // 2 bytes de packet identifier
+        if let Some(properties) = &self.properties {
+            rem_len += properties.to_bytes().len();
+        }
         for _return_code in &self.return_codes {
             rem_len += 2; // 2 bytes para enviar la longitud de cada return_code
         }
@@ -40,12 +54,16 @@ impl SubAckMessage {
         byte_de_tipo |= self.reserved_flags;
         msg_bytes.extend(byte_de_tipo.to_be_bytes());
 
-        // Calculo y envío la remaining length, un byte
+        // Calculo y envío la remaining length, como variable byte integer
         let rem_len = self.remaining_length();
-        msg_bytes.extend(rem_len.to_be_bytes());
+        msg_bytes.extend(encode_remaining_length(rem_len));
 
         // Variable header. Envío el packet identifier, 2 bytes
         msg_bytes.extend(self.packet_identifier.to_be_bytes());
+        // Y en MQTT 5, las propiedades
+        if let Some(properties) = &self.properties {
+            msg_bytes.extend(properties.to_bytes());
+        }
 
         // Payload. Envío el vector de los returned_codes, elemento a elemento:
         for returned_code in &self.return_codes {
@@ -58,29 +76,42 @@ impl SubAckMessage {
     /// Recibe bytes, y los interpreta.
     /// Devuelve un struct SubAckMessage con los valores recibidos e interpretados.
     pub fn from_bytes(msg_bytes: Vec<u8>) -> Result<SubAckMessage, Error> {
+        Self::from_bytes_with_version(msg_bytes, ProtocolVersion::V311)
+    }
+
+    /// Como `from_bytes`, para un suback recibido por una conexión con la versión de protocolo `version`.
+    pub fn from_bytes_with_version(msg_bytes: Vec<u8>, version: ProtocolVersion) -> Result<SubAckMessage, Error> {
         let size_of_u8 = size_of::<u8>();
         // Leo u8 byte de tipo y reserved flags
         let byte_de_tipo_y_flags = (&msg_bytes[0..size_of_u8])[0];
         let tipo = byte_de_tipo_y_flags >> 4;
         let reserved_flags = byte_de_tipo_y_flags & 0b0000_1111;
 
-        // Leo u8 remaining length
-        let rem_len = (&msg_bytes[size_of_u8..2 * size_of_u8])[0];
-        let mut idx = 2 * size_of_u8;
+        // Leo la remaining length, un variable byte integer
+        let (rem_len, mut idx) = decode_remaining_length(&msg_bytes)?;
 
         // Variable header. Leo u16 packet_id
         let size_of_u16 = size_of::<u16>();
         let packet_id = u16::from_be_bytes(
             msg_bytes[idx..idx + size_of_u16]
                 .try_into()
-                .map_err(|_| Error::new(ErrorKind::Other, "Error leyendo bytes subs msg."))?,
+                .map_err(|_| Error::other("Error leyendo bytes subs msg."))?,
         ); // forma 1
            //let packet_id = u16::from_be_bytes([msg_bytes[idx], msg_bytes[idx+size_of_u8]]); // forma 2
         idx += size_of_u16;
+        let mut rem_len_leida: usize = 2;
+
+        // En MQTT 5, leo las propiedades
+        let mut properties = None;
+        if version.is_v5() {
+            let (props, props_len) = MqttProperties::from_bytes(&msg_bytes[idx..])?;
+            properties = Some(props);
+            idx += props_len;
+            rem_len_leida += props_len;
+        }
 
         // Payload. Leo cada elemento del vector
         // Siendo que mqtt no envía la longitud del vector, utilizamos la remaining length
-        let mut rem_len_leida: u8 = 2;
         let mut ret_codes: Vec<SubscribeReturnCode> = vec![];
         while rem_len_leida < rem_len {
             // Leo el u16
@@ -96,7 +127,7 @@ impl SubAckMessage {
 
         // Chequeo tipo correcto
         if tipo != 9 {
-            return Err(Error::new(ErrorKind::Other, "Tipo incorrecto."));
+            return Err(Error::other("Tipo incorrecto."));
         }
 
         let struct_interpretado = SubAckMessage {
@@ -104,6 +135,7 @@ impl SubAckMessage {
             reserved_flags,
             packet_identifier: packet_id,
             return_codes: ret_codes,
+            properties,
         };
 
         Ok(struct_interpretado)
@@ -112,11 +144,16 @@ impl SubAckMessage {
     pub fn get_packet_id(&self) -> u16 {
         self.packet_identifier
     }
+
+    pub fn get_return_codes(&self) -> &Vec<SubscribeReturnCode> {
+        &self.return_codes
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::mqtt::messages::{
+        mqtt_properties::MqttProperties, protocol_version::ProtocolVersion,
         suback_message::SubAckMessage, subscribe_return_code::SubscribeReturnCode,
     };
 
@@ -155,4 +192,18 @@ mod test {
         let msg_reconstruido = SubAckMessage::from_bytes(bytes_msg);
         assert_eq!(msg_reconstruido.unwrap(), suback_msg);
     }
+
+    #[test]
+    fn test_4_suback_msg_mqtt5_se_interpreta_con_sus_propiedades() {
+        let return_codes = vec![SubscribeReturnCode::QoS1, SubscribeReturnCode::NotAuthorized];
+        let mut suback_msg = SubAckMessage::new(1, return_codes);
+        let mut properties = MqttProperties::new();
+        properties.reason_string = Some("acl".to_string());
+        suback_msg.set_mqtt5_properties(properties);
+
+        let bytes_msg = suback_msg.to_bytes();
+
+        let msg_reconstruido = SubAckMessage::from_bytes_with_version(bytes_msg, ProtocolVersion::V5);
+        assert_eq!(msg_reconstruido.unwrap(), suback_msg);
+    }
 }
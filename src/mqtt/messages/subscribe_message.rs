@@ -1,8 +1,11 @@
 use std::{
-    io::{Error, ErrorKind},
+    io::Error,
     mem::size_of,
     str::from_utf8,
 };
+
+use super::{mqtt_properties::MqttProperties, protocol_version::ProtocolVersion};
+use crate::mqtt::mqtt_utils::fixed_header::{decode_remaining_length, encode_remaining_length};
 /* [] Siendo que el variable header igualmente es diferente para cada tipo de mensaje,
  * no veo ganancia en crear un subscribe_variable_header.rs, xq no se va a poder poner comportamiento ahí
  * (en este caso incluso sería medio trivial, mandar un u16 y listo).
@@ -19,6 +22,7 @@ pub struct SubscribeMessage {
     reserved_flags: u8, // fixed header: 4 bytes infs de primer byte; para subscribe siempre es 2 (por protocolo mqtt)
     packet_identifier: u16, // Variable header: 2 bytes
     topic_filters: Vec<(String, u8)>, // Payload: vector de elementos "(topic, qos)"
+    properties: Option<MqttProperties>, // Variable header, sólo en MQTT 5: a continuación del packet identifier.
 }
 
 impl SubscribeMessage {
@@ -27,16 +31,25 @@ impl SubscribeMessage {
             message_type: 8,
             reserved_flags: 2,
             packet_identifier: packet_id,
-            topic_filters: topics // Convertimos cada tema en una tupla con QoS 1
+            topic_filters: topics, // Convertimos cada tema en una tupla con QoS 1
+            properties: None,
         }
     }
 
-    fn remaining_length(&self) -> u8 {
+    /// Convierte el mensaje en un subscribe de MQTT 5, con las propiedades recibidas.
+    pub fn set_mqtt5_properties(&mut self, properties: MqttProperties) {
+        self.properties = Some(properties);
+    }
+
+    fn remaining_length(&self) -> usize {
         // Calculo la rem_len
-        let mut rem_len: u8 = 2; // 2 bytes de packet identifier
+        let mut rem_len: usize = 2; // 2 bytes de packet identifier
+        if let Some(properties) = &self.properties {
+            rem_len += properties.to_bytes().len();
+        }
         for (filter, _qos) in &self.topic_filters {
             rem_len += 2; // 2 bytes para enviar la longitud de cada filter
-            rem_len += filter.len() as u8; // la longitud de cada filter
+            rem_len += filter.len(); // la longitud de cada filter
             rem_len += 1; // 1 byte para qos que es un u8
         }
         rem_len
@@ -50,12 +63,16 @@ impl SubscribeMessage {
         byte_de_tipo |= self.reserved_flags;
         msg_bytes.extend(byte_de_tipo.to_be_bytes());
 
-        // Calculo y envío la remaining length, un byte
+        // Calculo y envío la remaining length, como variable byte integer
         let rem_len = self.remaining_length();
-        msg_bytes.extend(rem_len.to_be_bytes());
+        msg_bytes.extend(encode_remaining_length(rem_len));
 
         // Variable header. Envío el packet identifier, 2 bytes
         msg_bytes.extend(self.packet_identifier.to_be_bytes());
+        // Y en MQTT 5, las propiedades
+        if let Some(properties) = &self.properties {
+            msg_bytes.extend(properties.to_bytes());
+        }
 
         // Payload. Envío el vector de los topic_filters, elemento a elemento:
         // cada longitud (de la string) y elemento del vector topic_filters
@@ -72,30 +89,43 @@ impl SubscribeMessage {
     /// Recibe bytes, y los interpreta.
     /// Devuelve un struct SubscribeMessage con los valores recibidos e interpretados.
     pub fn from_bytes(msg_bytes: Vec<u8>) -> Result<SubscribeMessage, Error> {
+        Self::from_bytes_with_version(msg_bytes, ProtocolVersion::V311)
+    }
+
+    /// Como `from_bytes`, para un subscribe recibido por una conexión con la versión de protocolo `version`.
+    pub fn from_bytes_with_version(msg_bytes: Vec<u8>, version: ProtocolVersion) -> Result<SubscribeMessage, Error> {
         let size_of_u8 = size_of::<u8>();
         // Leo u8 byte de tipo y reserved flags
         let byte_de_tipo_y_flags = (&msg_bytes[0..size_of_u8])[0];
         let tipo = byte_de_tipo_y_flags >> 4;
         let reserved_flags = byte_de_tipo_y_flags & 0b0000_1111;
 
-        // Leo u8 remaining length
-        let rem_len = (&msg_bytes[size_of_u8..2 * size_of_u8])[0];
-        let mut idx = 2 * size_of_u8;
+        // Leo la remaining length, un variable byte integer
+        let (rem_len, mut idx) = decode_remaining_length(&msg_bytes)?;
 
         // Variable header. Leo u16 packet_id
         let size_of_u16 = size_of::<u16>();
         let packet_id = u16::from_be_bytes(
             msg_bytes[idx..idx + size_of_u16]
                 .try_into()
-                .map_err(|_| Error::new(ErrorKind::Other, "Error leyendo bytes subs msg."))?,
+                .map_err(|_| Error::other("Error leyendo bytes subs msg."))?,
         ); // forma 1
            //let packet_id = u16::from_be_bytes([msg_bytes[idx], msg_bytes[idx+size_of_u8]]); // forma 2
         idx += size_of_u16;
+        let mut rem_len_leida: usize = 2;
+
+        // En MQTT 5, leo las propiedades
+        let mut properties = None;
+        if version.is_v5() {
+            let (props, props_len) = MqttProperties::from_bytes(&msg_bytes[idx..])?;
+            properties = Some(props);
+            idx += props_len;
+            rem_len_leida += props_len;
+        }
 
         // Payload. Leo cada elemento del vector: primero la len de la string en u16
         // y luego el elemento, que será una tupla (String, u8)
         // Siendo que mqtt no envía la longitud del vector, utilizamos la remaining length
-        let mut rem_len_leida: u8 = 2;
         let mut topics: Vec<(String, u8)> = vec![];
         while rem_len_leida < rem_len {
             // Leo la string len
@@ -113,7 +143,7 @@ impl SubscribeMessage {
             let elemento = (String::from(string_leida), elem_qos);
             topics.push(elemento);
             // Avanzo la rem_len_leida para saber cuándo termino de leer todos los elementos
-            rem_len_leida += 2 + elem_string_len as u8 + 1;
+            rem_len_leida += 2 + elem_string_len as usize + 1;
         }
 
         let struct_interpretado = SubscribeMessage {
@@ -121,6 +151,7 @@ impl SubscribeMessage {
             reserved_flags,
             packet_identifier: packet_id,
             topic_filters: topics,
+            properties,
         };
         println!(
             "Creo struct interpretado desde bytes: {:?}",
@@ -137,6 +168,11 @@ impl SubscribeMessage {
     pub fn get_packet_id(&self) -> u16 {
         self.packet_identifier
     }
+
+    /// Devuelve las propiedades del subscribe, si es de MQTT 5.
+    pub fn get_properties(&self) -> Option<&MqttProperties> {
+        self.properties.as_ref()
+    }
 }
 
 use crate::mqtt::messages::message::Message;
@@ -162,7 +198,10 @@ impl Message for SubscribeMessage {
 
 #[cfg(test)]
 mod test {
-    use crate::mqtt::messages::subscribe_message::SubscribeMessage;
+    use crate::mqtt::messages::{
+        mqtt_properties::MqttProperties, protocol_version::ProtocolVersion,
+        subscribe_message::SubscribeMessage,
+    };
 
     #[test]
     fn test_1_subscribe_msg_se_crea_con_tipo_y_flag_adecuados() {
@@ -200,4 +239,17 @@ mod test {
         let msg_reconstruido = SubscribeMessage::from_bytes(bytes_msg);
         assert_eq!(msg_reconstruido.unwrap(), subscribe_msg);
     }
+
+    #[test]
+    fn test_4_subscribe_msg_mqtt5_se_interpreta_con_sus_propiedades() {
+        let mut subscribe_msg = SubscribeMessage::new(1, vec![(String::from("topic1"), 1)]);
+        let mut properties = MqttProperties::new();
+        properties.add_user_property("app_id", "dron");
+        subscribe_msg.set_mqtt5_properties(properties);
+
+        let bytes_msg = subscribe_msg.to_bytes();
+
+        let msg_reconstruido = SubscribeMessage::from_bytes_with_version(bytes_msg, ProtocolVersion::V5);
+        assert_eq!(msg_reconstruido.unwrap(), subscribe_msg);
+    }
 }
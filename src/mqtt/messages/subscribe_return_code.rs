@@ -1,4 +1,4 @@
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 // El copy y clone son usados para enviarlo as u16.
@@ -7,6 +7,7 @@ pub enum SubscribeReturnCode {
     QoS1 = 0x01,
     QoS2 = 0x02,
     Failure = 0x80,
+    NotAuthorized = 0x87, // Sólo MQTT 5: en lugar de Failure, cuando la acl no permite la suscripción.
 }
 impl SubscribeReturnCode {
     /// Recibe un número u16 y 'lo convierte' a (devuelve) la variante del enum correspondiente.
@@ -17,8 +18,8 @@ impl SubscribeReturnCode {
             0x01 => Ok(SubscribeReturnCode::QoS1),
             0x02 => Ok(SubscribeReturnCode::QoS2),
             0x80 => Ok(SubscribeReturnCode::Failure),
-            _ => Err(Error::new(
-                ErrorKind::Other,
+            0x87 => Ok(SubscribeReturnCode::NotAuthorized),
+            _ => Err(Error::other(
                 "Error, subscribe returned code inválido.",
             )),
         }
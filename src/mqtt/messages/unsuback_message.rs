@@ -1,11 +1,14 @@
+use std::io::{Error, ErrorKind};
+
 use crate::mqtt::messages::{
+    mqtt_properties::MqttProperties, protocol_version::ProtocolVersion, reason_code::ReasonCode,
     unsuback_fixed_header::FixedHeader, unsuback_variable_header::VariableHeader,
 };
 
 pub struct Unsuback {
     fixed_header: FixedHeader,
     variable_header: VariableHeader,
-    //No tiene payload
+    reason_codes: Vec<ReasonCode>, // Payload, sólo en MQTT 5 (en 3.1.1 no tiene payload).
 }
 
 impl Unsuback {
@@ -13,6 +16,7 @@ impl Unsuback {
         let variable_header = VariableHeader {
             packet_type_identifier_msb,
             packet_type_identifier_lsb,
+            properties: None,
         };
 
         let fixed_header = FixedHeader {
@@ -24,16 +28,57 @@ impl Unsuback {
         Unsuback {
             fixed_header,
             variable_header,
+            reason_codes: vec![],
         }
     }
 
+    /// Convierte el mensaje en un unsuback de MQTT 5, con las propiedades y un reason code por cada topic.
+    pub fn set_mqtt5_properties(&mut self, properties: MqttProperties, reason_codes: Vec<ReasonCode>) {
+        self.fixed_header.remaining_length = (2 + properties.to_bytes().len() + reason_codes.len()) as u8;
+        self.variable_header.properties = Some(properties);
+        self.reason_codes = reason_codes;
+    }
+
+    pub fn get_reason_codes(&self) -> &Vec<ReasonCode> {
+        &self.reason_codes
+    }
+
+    pub fn get_packet_id(&self) -> u16 {
+        u16::from_be_bytes([
+            self.variable_header.packet_type_identifier_msb,
+            self.variable_header.packet_type_identifier_lsb,
+        ])
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
-        vec![
+        let mut bytes = vec![
             self.fixed_header.message_type << 4 | self.fixed_header.reserved,
             self.fixed_header.remaining_length,
             self.variable_header.packet_type_identifier_msb,
             self.variable_header.packet_type_identifier_lsb,
-        ]
+        ];
+        if let Some(properties) = &self.variable_header.properties {
+            bytes.extend(properties.to_bytes());
+            bytes.extend(self.reason_codes.iter().map(|code| code.to_byte()));
+        }
+        bytes
+    }
+
+    /// Como `from_bytes`, para un unsuback recibido por una conexión con la versión de protocolo `version`.
+    pub fn from_bytes_with_version(bytes: &[u8], version: ProtocolVersion) -> Result<Unsuback, Error> {
+        if bytes.len() < 4 {
+            return Err(Error::new(ErrorKind::InvalidData, "Unsuback incompleto."));
+        }
+        let mut unsuback = Self::from_bytes(bytes);
+        if version.is_v5() {
+            let (properties, props_len) = MqttProperties::from_bytes(&bytes[4..])?;
+            unsuback.reason_codes = bytes[4 + props_len..]
+                .iter()
+                .map(|byte| ReasonCode::from_byte(*byte))
+                .collect::<Result<Vec<ReasonCode>, Error>>()?;
+            unsuback.variable_header.properties = Some(properties);
+        }
+        Ok(unsuback)
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Unsuback {
@@ -46,11 +91,13 @@ impl Unsuback {
         let variable_header = VariableHeader {
             packet_type_identifier_msb: bytes[2],
             packet_type_identifier_lsb: bytes[3],
+            properties: None,
         };
 
         Unsuback {
             fixed_header,
             variable_header,
+            reason_codes: vec![],
         }
     }
 }
@@ -79,4 +126,22 @@ mod tests {
         assert_eq!(unsuback.variable_header.packet_type_identifier_msb, 0x00);
         assert_eq!(unsuback.variable_header.packet_type_identifier_lsb, 0x01);
     }
+
+    #[test]
+    fn test_mqtt5_to_bytes_and_from_bytes() {
+        let mut unsuback = Unsuback::new(0x00, 0x01);
+        unsuback.set_mqtt5_properties(
+            MqttProperties::new(),
+            vec![ReasonCode::Success, ReasonCode::NotAuthorized],
+        );
+        let bytes = unsuback.to_bytes();
+        assert_eq!(bytes, vec![0b1011_0000, 0x05, 0x00, 0x01, 0x00, 0x00, 0x87]);
+
+        let unsuback = Unsuback::from_bytes_with_version(&bytes, ProtocolVersion::V5).unwrap();
+        assert_eq!(unsuback.variable_header.properties, Some(MqttProperties::new()));
+        assert_eq!(
+            unsuback.get_reason_codes(),
+            &vec![ReasonCode::Success, ReasonCode::NotAuthorized]
+        );
+    }
 }
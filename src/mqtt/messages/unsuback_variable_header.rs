@@ -1,4 +1,7 @@
+use super::mqtt_properties::MqttProperties;
+
 pub struct VariableHeader {
     pub packet_type_identifier_msb: u8, //1er byte
     pub packet_type_identifier_lsb: u8, //2do byte
+    pub properties: Option<MqttProperties>, // Sólo en MQTT 5.
 }
@@ -1,7 +1,9 @@
 use crate::mqtt::messages::{
+    mqtt_properties::MqttProperties, protocol_version::ProtocolVersion,
     unsubscribe_fixed_header::FixedHeader, unsubscribe_payload::Payload,
     unsubscribe_variable_header::VariableHeader,
 };
+use crate::mqtt::mqtt_utils::fixed_header::{decode_remaining_length, encode_remaining_length};
 
 // UNSUBSCRIBE MESSAGE
 #[derive(Debug)]
@@ -14,7 +16,10 @@ pub struct UnsubscribeMessage {
 impl UnsubscribeMessage {
     // Crea un nuevo mensaje UNSUBSCRIBE
     pub fn new(packet_identifier: u16, topics: Vec<String>) -> UnsubscribeMessage {
-        let variable_header = VariableHeader { packet_identifier };
+        let variable_header = VariableHeader {
+            packet_identifier,
+            properties: None,
+        };
 
         let payload = Payload { topics };
 
@@ -35,9 +40,20 @@ impl UnsubscribeMessage {
         unsubscribe_message
     }
 
+    /// Convierte el mensaje en un unsubscribe de MQTT 5, con las propiedades recibidas.
+    pub fn set_mqtt5_properties(&mut self, properties: MqttProperties) {
+        self.variable_header.properties = Some(properties);
+        self.fixed_header.remaining_length = self.calculate_remaining_length();
+    }
+
     // Calcula el tamaño del remaining = variable header + payload
     pub fn calculate_remaining_length(&self) -> usize {
-        let packet_identifier_length = 2;
+        let packet_identifier_length = 2
+            + self
+                .variable_header
+                .properties
+                .as_ref()
+                .map_or(0, |props| props.to_bytes().len());
         //let topics_length = self.payload.topics.iter().map(|topic| topic.len() + self.payload.topics.len()).sum::<usize>();
         let topics_length = self
             .payload
@@ -55,11 +71,14 @@ impl UnsubscribeMessage {
         let combined = (self.fixed_header.message_type << 4) | self.fixed_header.reserved;
         bytes.push(combined);
         self.fixed_header.remaining_length = self.calculate_remaining_length();
-        bytes.push(self.fixed_header.remaining_length as u8);
+        bytes.extend(encode_remaining_length(self.fixed_header.remaining_length));
 
         // Variable Header
         bytes.push((self.variable_header.packet_identifier >> 8) as u8); // MSB
         bytes.push((self.variable_header.packet_identifier & 0xFF) as u8); // LSB
+        if let Some(properties) = &self.variable_header.properties {
+            bytes.extend(properties.to_bytes());
+        }
 
         // Payload
         for topic in &self.payload.topics {
@@ -71,29 +90,52 @@ impl UnsubscribeMessage {
     }
 
     pub fn from_bytes(bytes: Vec<u8>) -> Result<UnsubscribeMessage, std::io::Error> {
-        if bytes.len() < 4 {
-            return Err(std::io::Error::new(
+        Self::from_bytes_with_version(bytes, ProtocolVersion::V311)
+    }
+
+    /// Como `from_bytes`, para un unsubscribe recibido por una conexión con la versión de protocolo `version`.
+    pub fn from_bytes_with_version(
+        bytes: Vec<u8>,
+        version: ProtocolVersion,
+    ) -> Result<UnsubscribeMessage, std::io::Error> {
+        let invalid = || {
+            std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 "No hay suficientes bytes para un mensaje válido",
-            )); // No hay suficientes bytes para un mensaje válido
-        }
+            )
+        };
 
         // Fixed Header
-        let first_byte = bytes[0];
+        let first_byte = *bytes.first().ok_or_else(invalid)?;
         let message_type = first_byte >> 4; // message_type se extrae de los bits 4 a 7
         let reserved = first_byte & 0x0F; // reserved se extrae de los bits 0 a 3
-        let remaining_length = bytes[1];
+        let (remaining_length, header_len) = decode_remaining_length(&bytes)?;
 
         // Variable Header
-        let packet_identifier = ((bytes[2] as u16) << 8) | (bytes[3] as u16);
+        let id_bytes = bytes.get(header_len..header_len + 2).ok_or_else(invalid)?;
+        let packet_identifier = u16::from_be_bytes([id_bytes[0], id_bytes[1]]);
+
+        let mut index = header_len + 2;
+        let mut properties = None;
+        if version.is_v5() {
+            let (props, props_len) = MqttProperties::from_bytes(&bytes[index..])?;
+            properties = Some(props);
+            index += props_len;
+        }
 
         // Payload
         let mut topics = Vec::new();
-        let mut index = 4;
         while index < bytes.len() {
             let topic_length = bytes[index] as usize;
-            let topic =
-                String::from_utf8(bytes[index + 1..index + 1 + topic_length].to_vec()).unwrap();
+            let topic_bytes = bytes
+                .get(index + 1..index + 1 + topic_length)
+                .ok_or_else(invalid)?;
+            let topic = String::from_utf8(topic_bytes.to_vec()).map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "El nombre del tema no es válido UTF-8",
+                )
+            })?;
             topics.push(topic);
             index += 1 + topic_length;
         }
@@ -102,12 +144,24 @@ impl UnsubscribeMessage {
             fixed_header: FixedHeader {
                 message_type,
                 reserved,
-                remaining_length: remaining_length as usize,
+                remaining_length,
+            },
+            variable_header: VariableHeader {
+                packet_identifier,
+                properties,
             },
-            variable_header: VariableHeader { packet_identifier },
             payload: Payload { topics },
         })
     }
+
+    pub fn get_packet_id(&self) -> u16 {
+        self.variable_header.packet_identifier
+    }
+
+    /// Devuelve los topics de los que el cliente se desuscribe.
+    pub fn get_topics(&self) -> &Vec<String> {
+        &self.payload.topics
+    }
 }
 
 #[cfg(test)]
@@ -220,4 +274,45 @@ mod test {
             new_unsubscribe_message.payload.topics
         );
     }
+
+    //Testea que un unsubscribe de MQTT 5 se reconstruya con sus propiedades
+    #[test]
+    fn test_unsubscribe_message_mqtt5_to_bytes_and_back() {
+        let topics = vec!["topic1".to_string()];
+        let mut unsubscribe_message = UnsubscribeMessage::new(13, topics);
+        let mut properties = MqttProperties::new();
+        properties.add_user_property("app_id", "dron");
+        unsubscribe_message.set_mqtt5_properties(properties.clone());
+
+        let bytes = unsubscribe_message.to_bytes();
+        let new_unsubscribe_message =
+            UnsubscribeMessage::from_bytes_with_version(bytes, ProtocolVersion::V5).unwrap();
+
+        assert_eq!(new_unsubscribe_message.variable_header.properties, Some(properties));
+        assert_eq!(new_unsubscribe_message.payload.topics, vec!["topic1".to_string()]);
+    }
+
+    //Testea que un unsubscribe truncado o con un tema inválido dé error, en vez de hacer panic
+    #[test]
+    fn test_unsubscribe_message_malformado_da_error() {
+        let mut unsubscribe_message = UnsubscribeMessage::new(13, vec!["topic1".to_string()]);
+        let bytes = unsubscribe_message.to_bytes();
+
+        for len in 1..bytes.len() {
+            for version in [ProtocolVersion::V311, ProtocolVersion::V5] {
+                if let Err(e) = UnsubscribeMessage::from_bytes_with_version(bytes[..len].to_vec(), version) {
+                    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+                }
+            }
+        }
+        // El tema dice medir 10 bytes pero sólo hay 6.
+        let mut truncated = bytes.clone();
+        truncated[4] = 10;
+        assert!(UnsubscribeMessage::from_bytes(truncated).is_err());
+
+        let mut invalid_utf8 = bytes;
+        invalid_utf8[5] = 0xFF;
+        let err = UnsubscribeMessage::from_bytes(invalid_utf8).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
 }
@@ -1,4 +1,7 @@
+use super::mqtt_properties::MqttProperties;
+
 #[derive(Debug)]
 pub struct VariableHeader {
     pub packet_identifier: u16,
+    pub properties: Option<MqttProperties>, // Sólo en MQTT 5.
 }
@@ -1,4 +1,6 @@
+pub mod bridge;
 pub mod client;
+pub mod memory_stream;
 pub mod messages;
 pub mod mqtt_utils;
 pub mod server;
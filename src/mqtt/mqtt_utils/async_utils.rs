@@ -0,0 +1,60 @@
+use std::io::{Error, ErrorKind};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::fixed_header::{decode_remaining_length, FixedHeader, MAX_FIXED_HEADER_LEN};
+
+/// Versión asíncrona de `get_fixed_header_from_stream` seguida de `get_whole_message_in_bytes_from_stream`:
+/// lee del `stream` un mensaje completo, y devuelve sus bytes. Devuelve None si se cerró la conexión.
+pub async fn read_message_from_async_stream<R: AsyncRead + Unpin>(
+    stream: &mut R,
+) -> Result<Option<Vec<u8>>, Error> {
+    let mut fixed_header_buf = [0u8; FixedHeader::fixed_header_len()];
+    match stream.read_exact(&mut fixed_header_buf).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let read_error = |_| Error::new(ErrorKind::InvalidData, "Se leyó menos de lo esperado");
+
+    let mut msg_bytes = fixed_header_buf.to_vec();
+    // Mientras el último byte leído indique que la remaining length continúa, se lee el siguiente.
+    while msg_bytes.len() < MAX_FIXED_HEADER_LEN && msg_bytes.last().is_some_and(|byte| byte & 0x80 != 0) {
+        msg_bytes.push(stream.read_u8().await.map_err(read_error)?);
+    }
+    let (rem_len, header_len) = decode_remaining_length(&msg_bytes)?;
+
+    msg_bytes.resize(header_len + rem_len, 0);
+    stream.read_exact(&mut msg_bytes[header_len..]).await.map_err(read_error)?;
+    Ok(Some(msg_bytes))
+}
+
+/// Versión asíncrona de `write_message_to_stream`.
+pub async fn write_message_to_async_stream<W: AsyncWrite + Unpin>(
+    msg_bytes: &[u8],
+    stream: &mut W,
+) -> Result<(), Error> {
+    stream.write_all(msg_bytes).await?;
+    stream.flush().await
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_message_from_async_stream, write_message_to_async_stream};
+    use crate::mqtt::messages::puback_message::PubAckMessage;
+
+    #[tokio::test]
+    async fn test_1_se_leen_los_mensajes_completos_y_none_al_cerrarse() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let first = PubAckMessage::new(1, 0).to_bytes();
+        let second = PubAckMessage::new(2, 0).to_bytes();
+        write_message_to_async_stream(&[first.clone(), second.clone()].concat(), &mut client)
+            .await
+            .unwrap();
+        drop(client);
+
+        assert_eq!(read_message_from_async_stream(&mut server).await.unwrap(), Some(first));
+        assert_eq!(read_message_from_async_stream(&mut server).await.unwrap(), Some(second));
+        assert_eq!(read_message_from_async_stream(&mut server).await.unwrap(), None);
+    }
+}
@@ -1,9 +1,20 @@
-use crate::mqtt::messages::packet_type::PacketType;
+use std::io::{Error, ErrorKind};
+
+use crate::mqtt::messages::{
+    mqtt_properties::{decode_variable_byte_integer, encode_variable_byte_integer},
+    packet_type::PacketType,
+};
+
+/// Máxima remaining length que admite MQTT: la mayor representable con un variable byte integer de cuatro bytes.
+pub const MAX_REMAINING_LENGTH: usize = 268_435_455;
+/// Máxima longitud del fixed header: el byte de tipo más los cuatro de la remaining length.
+pub const MAX_FIXED_HEADER_LEN: usize = 5;
 
 /// Struct que contiene los primeros dos bytes de cualquier tipo de mensaje del protocolo MQTT.
 /// El byte 1 contiene el tipo de mensaje en sus 4 bits más significativos,
 /// y ceros o posiblemente flags (dependiendo del tipo de mensaje) en sus 4 bits menos significativos.
-/// El byte 2 contiene la `remaining_length` que es la longitud de la porción restante del mensaje.
+/// El byte 2 contiene la `remaining_length` que es la longitud de la porción restante del mensaje,
+/// o su primer byte si no entra en uno (ver `decode_remaining_length`).
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub struct FixedHeader {
     message_type_byte: u8, // byte 1, el tipo está en los 4 MSBits.
@@ -37,11 +48,59 @@ impl FixedHeader {
         PacketType::from(self.get_message_type_byte())
     }
 
-    pub const fn get_rem_len(&self) -> usize {
-        self.remaining_length as usize
-    }
-
     pub fn is_not_null(&self) -> bool {
         !((self.message_type_byte == 0) & (self.remaining_length == 0))
     }
 }
+
+/// Codifica la `remaining_length` como la envía MQTT en el fixed header, como variable byte integer
+/// de uno a cuatro bytes. Quien arma el mensaje debe verificar que no supere `MAX_REMAINING_LENGTH`.
+pub fn encode_remaining_length(remaining_length: usize) -> Vec<u8> {
+    encode_variable_byte_integer(remaining_length as u32)
+}
+
+/// Verifica que un mensaje con `remaining_length` pueda enviarse, ie que su longitud sea representable en el fixed header.
+pub fn check_remaining_length(remaining_length: usize) -> Result<(), Error> {
+    if remaining_length > MAX_REMAINING_LENGTH {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("El mensaje es demasiado largo: {} bytes.", remaining_length),
+        ));
+    }
+    Ok(())
+}
+
+/// Decodifica la remaining length de los bytes de un mensaje, que comienza en su segundo byte.
+/// Devuelve la remaining length, y la longitud del fixed header (ie el índice donde comienza el variable header).
+pub fn decode_remaining_length(msg_bytes: &[u8]) -> Result<(usize, usize), Error> {
+    let len_bytes = msg_bytes
+        .get(1..)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Falta la remaining length."))?;
+    let (remaining_length, len_size) = decode_variable_byte_integer(len_bytes)?;
+    Ok((remaining_length as usize, 1 + len_size))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode_remaining_length, encode_remaining_length, MAX_REMAINING_LENGTH};
+
+    #[test]
+    fn test_1_la_remaining_length_ocupa_mas_de_un_byte_a_partir_de_128() {
+        assert_eq!(encode_remaining_length(127), vec![127]);
+        assert_eq!(encode_remaining_length(300), vec![0xAC, 0x02]);
+        assert_eq!(encode_remaining_length(MAX_REMAINING_LENGTH).len(), 4);
+
+        for len in [0, 127, 128, 255, 300, 16_384, MAX_REMAINING_LENGTH] {
+            let mut msg_bytes = vec![0x30];
+            msg_bytes.extend(encode_remaining_length(len));
+            assert_eq!(decode_remaining_length(&msg_bytes).unwrap(), (len, msg_bytes.len()));
+        }
+    }
+
+    #[test]
+    fn test_2_una_remaining_length_incompleta_da_error() {
+        assert!(decode_remaining_length(&[0x30]).is_err());
+        assert!(decode_remaining_length(&[0x30, 0x80]).is_err());
+        assert!(decode_remaining_length(&[0x30, 0xFF, 0xFF, 0xFF, 0xFF]).is_err());
+    }
+}
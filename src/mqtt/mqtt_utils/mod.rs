@@ -1,4 +1,6 @@
 pub mod utils;
 pub mod broker_errors;
 pub mod fixed_header;
-pub mod will_message_utils;
\ No newline at end of file
+pub mod will_message_utils;
+pub mod packet_id_allocator;
+pub mod async_utils;
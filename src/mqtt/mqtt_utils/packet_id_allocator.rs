@@ -0,0 +1,98 @@
+use std::{
+    collections::HashSet,
+    io::Error,
+};
+
+/// Asigna los packet_id de una sesión: nunca devuelve 0 (no es válido en MQTT), al llegar a 65535 vuelve a empezar,
+/// y saltea los ids que siguen en uso (mensajes cuyo ack todavía no llegó).
+#[derive(Debug, Default)]
+pub struct PacketIdAllocator {
+    last_packet_id: u16,
+    in_use: HashSet<u16>,
+}
+
+impl PacketIdAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Devuelve un packet_id libre, y lo marca como en uso hasta que se lo libere con `release`.
+    /// Devuelve error si ya están en uso todos los ids.
+    pub fn allocate(&mut self) -> Result<u16, Error> {
+        if self.in_use.len() >= u16::MAX as usize {
+            return Err(Error::other(
+                "No hay packet_id disponibles, todos los ids están en uso.",
+            ));
+        }
+        loop {
+            self.last_packet_id = self.last_packet_id.checked_add(1).unwrap_or(1);
+            if self.in_use.insert(self.last_packet_id) {
+                return Ok(self.last_packet_id);
+            }
+        }
+    }
+
+    /// Libera el `packet_id`, que ya puede volver a asignarse.
+    pub fn release(&mut self, packet_id: u16) {
+        self.in_use.remove(&packet_id);
+    }
+
+    /// Libera todos los ids, ej al comenzar una nueva conexión.
+    pub fn release_all(&mut self) {
+        self.in_use.clear();
+    }
+
+    pub fn is_in_use(&self, packet_id: u16) -> bool {
+        self.in_use.contains(&packet_id)
+    }
+
+    pub fn in_use_count(&self) -> usize {
+        self.in_use.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PacketIdAllocator;
+
+    #[test]
+    fn test_1_al_llegar_a_65535_vuelve_a_empezar_salteando_el_0() {
+        let mut allocator = PacketIdAllocator::new();
+        for expected in 1..=u16::MAX {
+            let packet_id = allocator.allocate().unwrap();
+            assert_eq!(packet_id, expected);
+            allocator.release(packet_id);
+        }
+        assert_eq!(allocator.allocate().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_2_se_saltean_los_ids_en_uso() {
+        let mut allocator = PacketIdAllocator::new();
+        let first = allocator.allocate().unwrap();
+        for _ in 1..u16::MAX {
+            let packet_id = allocator.allocate().unwrap();
+            allocator.release(packet_id);
+        }
+
+        // Dio la vuelta: el 1 sigue en uso, así que se saltea.
+        assert!(allocator.is_in_use(first));
+        assert_eq!(allocator.allocate().unwrap(), 2);
+        allocator.release(first);
+        assert!(!allocator.is_in_use(first));
+    }
+
+    #[test]
+    fn test_3_si_todos_los_ids_estan_en_uso_da_error() {
+        let mut allocator = PacketIdAllocator::new();
+        for _ in 0..u16::MAX {
+            allocator.allocate().unwrap();
+        }
+        assert!(allocator.allocate().is_err());
+
+        allocator.release(300);
+        assert_eq!(allocator.allocate().unwrap(), 300);
+        allocator.release_all();
+        assert_eq!(allocator.in_use_count(), 0);
+    }
+}
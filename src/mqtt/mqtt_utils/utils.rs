@@ -1,13 +1,15 @@
 use std::{
     io::{Error, ErrorKind, Read, Write},
-    net::{Shutdown, TcpStream},
+    net::Shutdown,
 };
 
 use crate::mqtt::messages::{
     packet_type::PacketType, puback_message::PubAckMessage, publish_message::PublishMessage,
 };
-use crate::mqtt::mqtt_utils::fixed_header::FixedHeader;
-type StreamType = TcpStream;
+use crate::mqtt::mqtt_utils::fixed_header::{
+    decode_remaining_length, FixedHeader, MAX_FIXED_HEADER_LEN,
+};
+use crate::mqtt::stream_type::StreamType;
 
 // Este archivo contiene funciones que utilizan para hacer read y write desde el stream
 // tanto el message_broker_server como el mqtt_client.
@@ -32,7 +34,7 @@ pub fn get_fixed_header_from_stream(
     stream: &mut StreamType,
 ) -> Result<Option<([u8; 2], FixedHeader)>, Error> {
     const FIXED_HEADER_LEN: usize = FixedHeader::fixed_header_len();
-    let res: Result<Vec<u8>, Error> = stream.bytes().take(FIXED_HEADER_LEN).collect();
+    let res = read_up_to(stream, FIXED_HEADER_LEN);
     match res {
         Ok(b) if b.len() == 2 => {
             // He leído bytes de un fixed_header, tengo que ver de qué tipo es.
@@ -52,20 +54,30 @@ pub fn get_fixed_header_from_stream(
 
 /// Una vez leídos los dos bytes del fixed header de un mensaje desde el stream,
 /// lee los siguientes `remaining length` bytes indicados en el fixed header.
+/// Si la remaining length no entra en un byte, lee antes el resto de sus bytes (es un variable byte integer).
 /// Concatena ambos grupos de bytes leídos para conformar los bytes totales del mensaje leído.
 /// (Podría hacer fixed_header.to_bytes(), se aprovecha que ya se leyó fixed_header_bytes).
 pub fn get_whole_message_in_bytes_from_stream(
-    fixed_header: &FixedHeader,
+    _fixed_header: &FixedHeader,
     stream: &mut StreamType,
     fixed_header_bytes: &[u8; 2],
 ) -> Result<Vec<u8>, Error> {
+    let mut buf = fixed_header_bytes.to_vec();
+    // Mientras el último byte leído indique que la remaining length continúa, se lee el siguiente.
+    while buf.len() < MAX_FIXED_HEADER_LEN && buf.last().is_some_and(|byte| byte & 0x80 != 0) {
+        let byte = read_up_to(stream, 1)?;
+        if byte.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "Se leyó menos de lo esperado"));
+        }
+        buf.extend(byte);
+    }
+    let (msg_rem_len, _) = decode_remaining_length(&buf)?;
+
     // Siendo que ya hemos leído fixed_header, sabemos que el resto del mensaje está disponible para ser leído.
-    let msg_rem_len: usize = fixed_header.get_rem_len();
-    let rem_buf: Result<Vec<u8>, Error> = stream.bytes().take(msg_rem_len).collect();
+    let rem_buf = read_up_to(stream, msg_rem_len);
     //println!("obteniendo mensaje completo");
     match rem_buf {
         Ok(b) if b.len() == msg_rem_len => {
-            let mut buf = fixed_header_bytes.to_vec();
             buf.extend(b);
 
             Ok(buf)
@@ -77,8 +89,16 @@ pub fn get_whole_message_in_bytes_from_stream(
     }
 }
 
+/// Lee hasta `len` bytes del `stream`, menos si antes se cierra. Lee del stream directamente (sin `BufReader`),
+/// ya que no debe consumir bytes del mensaje siguiente.
+fn read_up_to(stream: &mut StreamType, len: usize) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::with_capacity(len);
+    Read::by_ref(stream).take(len as u64).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
 /// Envía un mensaje de tipo PubAck por el stream.
-pub fn send_puback(msg: &PublishMessage, stream: &mut TcpStream) -> Result<(), Error> {
+pub fn send_puback(msg: &PublishMessage, stream: &mut StreamType) -> Result<(), Error> {
     if let Some(packet_id) = msg.get_packet_id() {
         let ack = PubAckMessage::new(packet_id, 0);
         let ack_msg_bytes = ack.to_bytes();
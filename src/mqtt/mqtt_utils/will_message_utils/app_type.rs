@@ -1,6 +1,8 @@
 use std::io::Error;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum AppType {
     Cameras,
     Dron,
@@ -1,17 +1,24 @@
 use std::io::{Error, ErrorKind};
 
+use serde::{Deserialize, Serialize};
+
+use crate::apps::payload_codec::{
+    envelope::{Encoding, PayloadSchema},
+    versioned_payload::{narrow_id, unsupported_version_error, VersionedPayload},
+};
+
 use super::app_type::AppType;
 
 /// Representa el contenido del will_message que se enviará desde las apps, y
 /// eventualmente llegará a sistema monitoreo.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct WillContent {
     app_type_identifier: AppType,
-    id: Option<u8>,    
+    id: Option<u32>,
 }
 
 impl WillContent {
-    pub fn new(app_type_identifier: AppType, id: Option<u8>) -> Self {
+    pub fn new(app_type_identifier: AppType, id: Option<u32>) -> Self {
         Self {app_type_identifier, id }
     }
 
@@ -19,10 +26,12 @@ impl WillContent {
         self.app_type_identifier
     }
 
-    pub fn get_id(&self) -> Option<u8> {
+    pub fn get_id(&self) -> Option<u32> {
         self.id
     }
 
+    /// Devuelve el contenido en el formato previo al envelope. Es el que se usa para el will message, que se arma
+    /// al conectarse, antes de poder negociar la versión con las demás apps.
     pub fn to_str(&self) -> String {
         let string_app_type = self.app_type_identifier.to_str();
         let len = self.id.is_some() as u8; // indica si hay que continuar para leer el id o si el mismo es none.
@@ -46,7 +55,7 @@ impl WillContent {
             if let Ok(len) = len_string.parse::<u8>(){
                 // si había, obtiene el id; else lo crea con None
                 if len==1 {
-                    if let Ok(id) = id_string.parse::<u8>(){
+                    if let Ok(id) = id_string.parse::<u32>(){
                         return Ok(Self{ app_type_identifier, id: Some(id)});
                     }
                 } else {
@@ -59,6 +68,48 @@ impl WillContent {
     }
 }
 
+/// `WillContent` en la versión 1 del envelope, con el id de un byte.
+#[derive(Serialize, Deserialize)]
+struct WillContentV1 {
+    app_type_identifier: AppType,
+    id: Option<u8>,
+}
+
+impl VersionedPayload for WillContent {
+    const SCHEMA: PayloadSchema = PayloadSchema::WillContent;
+    // La versión 2 pasó el id de un byte a u32 (el formato previo al envelope es texto, así que ya lo admite).
+    const CURRENT_VERSION: u8 = 2;
+
+    fn to_legacy_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.to_str().into_bytes())
+    }
+
+    fn from_legacy_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let string = std::str::from_utf8(bytes)
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "Error al decodear WillContent."))?;
+        Self::will_content_from_string(string)
+    }
+
+    fn from_older_version(version: u8, body: &[u8], encoding: Encoding) -> Result<Self, Error> {
+        match version {
+            1 => {
+                let v1: WillContentV1 = encoding.deserialize(body)?;
+                Ok(Self::new(v1.app_type_identifier, v1.id.map(u32::from)))
+            }
+            _ => Err(unsupported_version_error(Self::SCHEMA, version)),
+        }
+    }
+
+    fn to_older_version(&self, version: u8, encoding: Encoding) -> Result<Vec<u8>, Error> {
+        match version {
+            1 => encoding.serialize(&WillContentV1 {
+                app_type_identifier: self.app_type_identifier,
+                id: self.id.map(|id| narrow_id(id as u128, Self::SCHEMA)).transpose()?,
+            }),
+            _ => Err(unsupported_version_error(Self::SCHEMA, version)),
+        }
+    }
+}
 
 #[cfg(test)]
 mod test {
@@ -69,7 +120,7 @@ mod test {
     #[test]
     fn test_app_will_content_to_and_from_bytes_works() {
         // pasada a string y reconstruida es igual al original
-        let will_msg = WillContent::new(AppType::Cameras, Some(1));
+        let will_msg = WillContent::new(AppType::Cameras, Some(1000));
         
         assert_eq!(will_msg, WillContent::will_content_from_string(will_msg.to_str().as_str()).unwrap());
     }
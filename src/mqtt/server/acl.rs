@@ -0,0 +1,125 @@
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use super::file_helper::read_lines;
+
+const ANY: &str = "*";
+
+/// Operación sobre un topic que puede ser permitida o no a un cliente.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AclAction {
+    Publish,
+    Subscribe,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct AclRule {
+    client_id: String,
+    action: Option<AclAction>, // None indica que se permiten ambas operaciones.
+    topic: String,
+}
+
+impl AclRule {
+    /// Lee una regla de una línea con formato `<client_id> <pub|sub|all> <topic>`.
+    /// Tanto el client_id como el topic pueden ser `*`, para indicar cualquiera.
+    fn from_line(line: &str) -> Option<Self> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() != 3 {
+            return None;
+        }
+        let action = match parts[1] {
+            "pub" => Some(AclAction::Publish),
+            "sub" => Some(AclAction::Subscribe),
+            "all" => None,
+            _ => return None,
+        };
+        Some(Self {
+            client_id: parts[0].to_string(),
+            action,
+            topic: parts[2].to_string(),
+        })
+    }
+
+    fn allows(&self, client_id: &str, action: AclAction, topic: &str) -> bool {
+        let client_matches = self.client_id == ANY || self.client_id == client_id;
+        let action_matches = self.action.is_none() || self.action == Some(action);
+        let topic_matches = self.topic == ANY || self.topic == topic;
+        client_matches && action_matches && topic_matches
+    }
+}
+
+/// Lista de permisos por cliente y topic. Si un cliente no tiene ninguna regla que lo habilite, la operación se rechaza.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Acl {
+    rules: Vec<AclRule>,
+}
+
+impl Acl {
+    /// Lee las reglas desde el archivo `file_path`. Devuelve error si el archivo no puede leerse, para que un acl
+    /// configurado pero ausente no deje al servidor sin restricciones.
+    /// Se ignoran las líneas vacías, comentadas con `#` o inválidas.
+    pub fn from_file(file_path: &str) -> Result<Self, Error> {
+        let lines = read_lines(Path::new(file_path))
+            .map_err(|e| Error::new(ErrorKind::NotFound, format!("No se pudo leer el acl {:?}: {}", file_path, e)))?;
+        let rules = lines
+            .map_while(Result::ok)
+            .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .filter_map(|line| AclRule::from_line(&line))
+            .collect();
+        Ok(Self { rules })
+    }
+
+    /// Acl sin reglas, que no permite ninguna operación.
+    pub fn deny_all() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Devuelve si el cliente `client_id` tiene permitido realizar `action` sobre el topic `topic`.
+    pub fn allows(&self, client_id: &str, action: AclAction, topic: &str) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| rule.allows(client_id, action, topic))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::{Acl, AclAction};
+
+    #[test]
+    fn test_1_acl_permite_solo_lo_indicado() {
+        let path = "test_acl_1.txt";
+        fs::write(
+            path,
+            "# comentario\nSistema-Camaras pub cam\nSistema-Camaras all inc\n* sub *\n",
+        )
+        .unwrap();
+
+        let acl = Acl::from_file(path);
+        let _ = fs::remove_file(path);
+        let acl = acl.unwrap();
+
+        assert!(acl.allows("Sistema-Camaras", AclAction::Publish, "cam"));
+        assert!(acl.allows("Sistema-Camaras", AclAction::Publish, "inc"));
+        assert!(!acl.allows("Sistema-Camaras", AclAction::Publish, "dron"));
+        assert!(acl.allows("dron-1", AclAction::Subscribe, "inc"));
+        assert!(!acl.allows("dron-1", AclAction::Publish, "dron"));
+    }
+
+    #[test]
+    fn test_2_archivo_inexistente_da_error() {
+        assert!(Acl::from_file("no_existe_acl.txt").is_err());
+    }
+
+    #[test]
+    fn test_3_acl_sin_reglas_no_permite_nada() {
+        let acl = Acl::deny_all();
+
+        assert!(!acl.allows("dron-1", AclAction::Publish, "dron"));
+        assert!(!acl.allows("dron-1", AclAction::Subscribe, "inc"));
+    }
+}
@@ -1,15 +1,15 @@
-use std::{io::Error, path::Path};
+use std::io::Error;
 
 use crate::logging::string_logger::StringLogger;
 use crate::mqtt::messages::{
     connack_message::ConnackMessage, connack_session_present::SessionPresent,
     connect_message::ConnectMessage, connect_return_code::ConnectReturnCode,
+    mqtt_properties::MqttProperties,
 };
 use crate::mqtt::mqtt_utils::utils::write_message_to_stream;
 use crate::mqtt::stream_type::StreamType;
 
-use super::file_helper::read_lines;
-use super::mqtt_server::MQTTServer;
+use super::{mqtt_server::MQTTServer, user::TOPIC_ALIAS_MAXIMUM};
 
 #[derive(Debug)]
 pub struct AuthenticateClient {
@@ -31,7 +31,7 @@ impl AuthenticateClient {
         mqtt_server: &MQTTServer,
     ) -> Result<bool, Error> {
         let (is_authentic, connack_response) =
-            self.was_the_session_created_succesfully(connect_msg)?;
+            self.was_the_session_created_succesfully(connect_msg, mqtt_server)?;
 
         self.send_connection_response(&connack_response, stream)?; // aux: y si mejor le devuelve el connack? []
 
@@ -60,7 +60,7 @@ impl AuthenticateClient {
     ) -> Result<bool, Error> {
         if let Some(username) = connect_msg.get_client_id() {
             let is_reconnection =
-                mqtt_server.manage_possible_reconnecting_or_duplicate_user(username, stream)?;
+                mqtt_server.manage_possible_reconnecting_or_duplicate_user(username, stream, connect_msg)?;
             if !is_reconnection {
                 println!("Agregando nuevo user al server con username {:?}", username);
                 self.logger.log(format!("Agregando nuevo user al server con username {:?}", username));
@@ -72,69 +72,41 @@ impl AuthenticateClient {
         }
     }
 
-    /// Verifica si la sesión fue creada exitosamente: usuario valido o invitado
-    /// y devuelve un mensaje CONNACK acorde.
+    /// Verifica si la sesión fue creada exitosamente: versión de protocolo soportada, y usuario valido o invitado;
+    /// y devuelve un mensaje CONNACK acorde, en la versión de protocolo solicitada por el cliente.
     fn was_the_session_created_succesfully(
         &self,
         connect_msg: &ConnectMessage,
+        mqtt_server: &MQTTServer,
     ) -> Result<(bool, ConnackMessage), Error> {
-        if self.is_guest_mode_active(connect_msg.get_user(), connect_msg.get_passwd())
-            || self.authenticate(connect_msg.get_user(), connect_msg.get_passwd())
-        {
+        let Ok(version) = connect_msg.get_protocol_version() else {
+            self.logger.log("Connect rechazado: versión de protocolo no soportada.".to_string());
             let connack_response = ConnackMessage::new(
                 SessionPresent::NotPresentInLastSession,
-                ConnectReturnCode::ConnectionAccepted,
+                ConnectReturnCode::ProtocolError,
             );
-            Ok((true, connack_response))
+            return Ok((false, connack_response));
+        };
+
+        let (is_authentic, return_code) = if self.is_guest_mode_active(connect_msg.get_user(), connect_msg.get_passwd())
+            || mqtt_server.authenticate(connect_msg.get_user(), connect_msg.get_passwd())
+        {
+            (true, ConnectReturnCode::ConnectionAccepted)
         } else {
-            let connack_response = ConnackMessage::new(
-                SessionPresent::NotPresentInLastSession,
-                ConnectReturnCode::NotAuthorized,
-            );
-            Ok((false, connack_response))
+            (false, ConnectReturnCode::NotAuthorized)
+        };
+
+        let mut connack_response =
+            ConnackMessage::new(SessionPresent::NotPresentInLastSession, return_code);
+        if version.is_v5() {
+            let mut properties = MqttProperties::new();
+            properties.topic_alias_maximum = Some(TOPIC_ALIAS_MAXIMUM);
+            connack_response.set_mqtt5_properties(properties);
         }
+        Ok((is_authentic, connack_response))
     }
 
     fn is_guest_mode_active(&self, user: Option<&String>, passwd: Option<&String>) -> bool {
         user.is_none() && passwd.is_none()
     }
-
-    /// Autentica al usuario con las credenciales almacenadas en el archivo credentials.txt
-    fn authenticate(&self, user: Option<&String>, passwd: Option<&String>) -> bool {
-        let credentials = self.read_credentials_from_file("credentials.txt");
-        self.verify_authentication(user, passwd, &credentials)
-    }
-
-    /// Lee las credenciales del archivo especificado y devuelve un vector de pares (usuario, contraseña)
-    fn read_credentials_from_file(&self, file_path: &str) -> Vec<(String, String)> {
-        let path = Path::new(file_path);
-        let mut credentials = Vec::new();
-
-        if let Ok(lines) = read_lines(path) {
-            for line in lines.map_while(Result::ok) {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() == 2 {
-                    credentials.push((parts[0].to_string(), parts[1].to_string()));
-                }
-            }
-        }
-
-        credentials
-    }
-
-    /// Verifica si el usuario y la contraseña proporcionados coinciden con alguna de las credenciales almacenadas
-    fn verify_authentication(
-        &self,
-        user: Option<&String>,
-        passwd: Option<&String>,
-        credentials: &[(String, String)],
-    ) -> bool {
-        if let (Some(u), Some(p)) = (user, passwd) {
-            credentials
-                .iter()
-                .any(|(username, password)| u == username && p == password)
-        } else {
-            false
-        }
-    }
-}
\ No newline at end of file
+}
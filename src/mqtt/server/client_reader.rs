@@ -151,6 +151,10 @@ impl ClientReader {
 
     /// Desconexión involuntaria (ie se le fue internet).
     fn server_handle_client_disconnection(&mut self, client_id: &str) -> Result<(), Error> {
+        // Si el servidor se está cerrando, el will message ya fue publicado.
+        if self.mqtt_server.is_shutting_down() {
+            return Ok(());
+        }
         self.mqtt_server
             .set_user_as_temporally_disconnected(client_id)?;
         self.mqtt_server.publish_users_will_message(client_id)?;
@@ -196,7 +200,11 @@ impl ClientReader {
                     //aux: self.mqtt_server.publish_users_will_message(client_id)?;
                     //break;
                 }
-                Err(_) => todo!(),
+                Err(e) => {
+                    self.logger.log(format!("Error al leer del stream de {:?}: {:?}.", client_id, e));
+                    self.handle_client_disconnection(client_id)?;
+                    return Ok(DisconnectReason::Involuntaria);
+                }
             }
         }
         //Ok(())
@@ -265,5 +273,5 @@ fn get_connect_message(
 ) -> Result<ConnectMessage, Error> {
     let msg_bytes =
         get_whole_message_in_bytes_from_stream(fixed_header, stream, fixed_header_bytes)?;
-    Ok(ConnectMessage::from_bytes(&msg_bytes))
+    ConnectMessage::from_bytes(&msg_bytes)
 }
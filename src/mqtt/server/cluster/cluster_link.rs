@@ -0,0 +1,221 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::Error,
+    net::{SocketAddr, TcpStream},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::mqtt::messages::publish_message::PublishMessage;
+
+use super::{
+    cluster_message::{ClusterMessage, SessionInfo},
+    cluster_properties::ClusterProperties,
+};
+
+// Tiempo sin recibir latidos luego del cual se considera caído a un nodo.
+const PEER_TIMEOUT: Duration = Duration::from_secs(3);
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+struct PeerInfo {
+    cluster_addr: String,
+    last_seen: Instant,
+    subscriptions: Vec<String>,
+    sessions: Vec<SessionInfo>,
+}
+
+impl PeerInfo {
+    fn is_alive(&self) -> bool {
+        self.last_seen.elapsed() < PEER_TIMEOUT
+    }
+}
+
+#[derive(Debug)]
+struct ClusterState {
+    known_addrs: HashSet<String>,             // Direcciones de cluster de todos los nodos conocidos.
+    peers: HashMap<String, PeerInfo>,         // Por node_id, lo último que se supo de cada nodo.
+    connections: HashMap<String, TcpStream>, // Conexiones salientes, por dirección de cluster.
+}
+
+/// Estado del cluster compartido entre el `MQTTServer` y los hilos del `ClusterNode`.
+/// Conoce a los demás nodos, sus suscripciones y sesiones, y las conexiones hacia ellos.
+#[derive(Debug)]
+pub struct ClusterLink {
+    properties: ClusterProperties,
+    state: Arc<Mutex<ClusterState>>,
+}
+
+impl ClusterLink {
+    pub fn new(properties: ClusterProperties) -> Self {
+        let known_addrs = properties
+            .get_peers()
+            .iter()
+            .filter(|addr| **addr != properties.get_cluster_addr())
+            .cloned()
+            .collect();
+        Self {
+            properties,
+            state: Arc::new(Mutex::new(ClusterState {
+                known_addrs,
+                peers: HashMap::new(),
+                connections: HashMap::new(),
+            })),
+        }
+    }
+
+    pub fn clone_ref(&self) -> Self {
+        Self {
+            properties: self.properties.clone(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// Devuelve el identificador de este nodo.
+    pub fn get_node_id(&self) -> String {
+        self.properties.get_node_id()
+    }
+
+    /// Devuelve la dirección en la que este nodo escucha a los demás.
+    pub fn get_cluster_addr(&self) -> String {
+        self.properties.get_cluster_addr()
+    }
+
+    /// Devuelve las direcciones de cluster de todos los nodos conocidos (salvo éste).
+    pub fn get_known_addrs(&self) -> Vec<String> {
+        if let Ok(state) = self.state.lock() {
+            return state.known_addrs.iter().cloned().collect();
+        }
+        vec![]
+    }
+
+    /// Devuelve los node_id de los nodos que se consideran vivos.
+    pub fn get_alive_members(&self) -> Vec<String> {
+        if let Ok(state) = self.state.lock() {
+            return state
+                .peers
+                .iter()
+                .filter(|(_, peer)| peer.is_alive())
+                .map(|(node_id, _)| node_id.to_string())
+                .collect();
+        }
+        vec![]
+    }
+
+    /// Actualiza la información del nodo `node_id` a partir de un latido recibido de él,
+    /// y agrega a los conocidos los miembros que él conozca.
+    pub fn update_peer(
+        &self,
+        node_id: String,
+        cluster_addr: String,
+        members: Vec<String>,
+        subscriptions: Vec<String>,
+        sessions: Vec<SessionInfo>,
+    ) {
+        let own_addr = self.get_cluster_addr();
+        if let Ok(mut state) = self.state.lock() {
+            for addr in members.into_iter().chain([cluster_addr.to_string()]) {
+                if addr != own_addr {
+                    state.known_addrs.insert(addr);
+                }
+            }
+            state.peers.insert(
+                node_id,
+                PeerInfo {
+                    cluster_addr,
+                    last_seen: Instant::now(),
+                    subscriptions,
+                    sessions,
+                },
+            );
+        }
+    }
+
+    /// Busca la sesión de `client_id` replicada por otro nodo (esté vivo o no), para restaurarla
+    /// si el cliente se reconecta a este nodo. Devuelve los topics a los que estaba suscripto.
+    pub fn get_replicated_session(&self, client_id: &str) -> Option<Vec<String>> {
+        if let Ok(state) = self.state.lock() {
+            return state
+                .peers
+                .values()
+                .filter_map(|peer| {
+                    peer.sessions
+                        .iter()
+                        .find(|session| session.client_id == client_id)
+                        .map(|session| (peer.last_seen, session.topics.to_vec()))
+                })
+                .max_by_key(|(last_seen, _)| *last_seen)
+                .map(|(_, topics)| topics);
+        }
+        None
+    }
+
+    /// Reenvía `msg` a los nodos vivos que tengan suscriptores a su topic.
+    /// Si el mensaje tiene retain, se reenvía a todos los nodos vivos, para que quede replicado.
+    /// Sólo usa conexiones ya establecidas, para no demorar el procesamiento del mensaje.
+    pub fn forward_publish(&self, msg: &PublishMessage) {
+        let topic = msg.get_topic();
+        let cluster_msg = ClusterMessage::Publish {
+            origin: self.get_node_id(),
+            msg_bytes: msg.to_bytes(),
+            mqtt5: msg.get_protocol_version().is_v5(),
+        };
+        if let Ok(mut state) = self.state.lock() {
+            let target_addrs: Vec<String> = state
+                .peers
+                .values()
+                .filter(|peer| peer.is_alive())
+                .filter(|peer| msg.is_retained() || peer.subscriptions.contains(&topic))
+                .map(|peer| peer.cluster_addr.to_string())
+                .collect();
+            for addr in target_addrs {
+                if let Some(stream) = state.connections.get_mut(&addr) {
+                    if cluster_msg.write_to(stream).is_err() {
+                        state.connections.remove(&addr);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Envía `msg` al nodo de dirección `addr`, conectándose a él si todavía no había conexión.
+    pub fn send_to(&self, addr: &str, msg: &ClusterMessage) -> Result<(), Error> {
+        let existing = self.take_connection(addr);
+        let mut stream = match existing {
+            Some(stream) => stream,
+            None => connect(addr)?,
+        };
+        msg.write_to(&mut stream)?;
+        // Si la escritura fue exitosa, se conserva la conexión.
+        if let Ok(mut state) = self.state.lock() {
+            state.connections.insert(addr.to_string(), stream);
+        }
+        Ok(())
+    }
+
+    fn take_connection(&self, addr: &str) -> Option<TcpStream> {
+        if let Ok(mut state) = self.state.lock() {
+            return state.connections.remove(addr);
+        }
+        None
+    }
+
+    /// Cierra las conexiones salientes.
+    pub fn close_connections(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            for (_, stream) in state.connections.drain() {
+                let _ = stream.shutdown(std::net::Shutdown::Both);
+            }
+        }
+    }
+}
+
+fn connect(addr: &str) -> Result<TcpStream, Error> {
+    let socket_addr: SocketAddr = addr.parse().map_err(|_| {
+        Error::new(std::io::ErrorKind::InvalidInput, "Dirección de nodo inválida.")
+    })?;
+    let stream = TcpStream::connect_timeout(&socket_addr, CONNECT_TIMEOUT)?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    Ok(stream)
+}
@@ -0,0 +1,92 @@
+use std::io::{Error, ErrorKind, Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+// Tamaño máximo aceptado para un mensaje entre nodos, para no reservar memoria de más ante datos inválidos.
+const MAX_FRAME_LEN: u32 = 16 * 1024 * 1024;
+
+/// Sesión de un cliente conectado a un nodo, que se replica al resto del cluster
+/// para poder restaurarla si el cliente se reconecta a otro nodo.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub client_id: String,
+    pub topics: Vec<String>,
+}
+
+/// Mensajes intercambiados entre nodos del cluster, por el puerto de cluster.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ClusterMessage {
+    /// Latido periódico: anuncia el nodo, los miembros que conoce, y sus suscripciones y sesiones.
+    Heartbeat {
+        node_id: String,
+        cluster_addr: String,
+        members: Vec<String>,
+        subscriptions: Vec<String>,
+        sessions: Vec<SessionInfo>,
+    },
+    /// Un PublishMessage (en bytes) recibido por el nodo `origin`, reenviado para que se distribuya a sus suscriptores.
+    /// `mqtt5` indica si los bytes están en el formato de MQTT 5 (con propiedades) o en el de 3.1.1.
+    Publish {
+        origin: String,
+        msg_bytes: Vec<u8>,
+        #[serde(default)]
+        mqtt5: bool,
+    },
+}
+
+impl ClusterMessage {
+    /// Escribe el mensaje en el stream: 4 bytes big endian con la longitud, seguidos del mensaje en json.
+    pub fn write_to<W: Write>(&self, stream: &mut W) -> Result<(), Error> {
+        let bytes = serde_json::to_vec(self).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        stream.write_all(&(bytes.len() as u32).to_be_bytes())?;
+        stream.write_all(&bytes)?;
+        stream.flush()
+    }
+
+    /// Lee un mensaje del stream. Devuelve Ok(None) si el otro extremo cerró la conexión.
+    pub fn read_from<R: Read>(stream: &mut R) -> Result<Option<Self>, Error> {
+        let mut len_buf = [0u8; 4];
+        if let Err(e) = stream.read_exact(&mut len_buf) {
+            if e.kind() == ErrorKind::UnexpectedEof {
+                return Ok(None);
+            }
+            return Err(e);
+        }
+        let len = u32::from_be_bytes(len_buf);
+        if len > MAX_FRAME_LEN {
+            return Err(Error::new(ErrorKind::InvalidData, "Mensaje de cluster demasiado largo."));
+        }
+        let mut bytes = vec![0u8; len as usize];
+        stream.read_exact(&mut bytes)?;
+        let msg = serde_json::from_slice(&bytes).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        Ok(Some(msg))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::{ClusterMessage, SessionInfo};
+
+    #[test]
+    fn test_1_heartbeat_se_escribe_y_se_lee_igual() {
+        let msg = ClusterMessage::Heartbeat {
+            node_id: "n1".to_string(),
+            cluster_addr: "127.0.0.1:9190".to_string(),
+            members: vec!["127.0.0.1:9290".to_string()],
+            subscriptions: vec!["inc".to_string()],
+            sessions: vec![SessionInfo {
+                client_id: "dron-1".to_string(),
+                topics: vec!["inc".to_string()],
+            }],
+        };
+        let mut buf = vec![];
+        msg.write_to(&mut buf).unwrap();
+
+        let mut cursor = Cursor::new(buf);
+        assert_eq!(ClusterMessage::read_from(&mut cursor).unwrap(), Some(msg));
+        // No queda nada más por leer.
+        assert_eq!(ClusterMessage::read_from(&mut cursor).unwrap(), None);
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    io::{Error, ErrorKind},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::logging::string_logger::StringLogger;
+use crate::mqtt::messages::{protocol_version::ProtocolVersion, publish_message::PublishMessage};
+use crate::mqtt::server::mqtt_server::MQTTServer;
+
+use super::{cluster_link::ClusterLink, cluster_message::ClusterMessage};
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Hilos del modo cluster de un `MQTTServer`: escucha a los demás nodos por el puerto de cluster,
+/// y les envía latidos periódicos con sus suscripciones y sesiones.
+#[derive(Debug)]
+pub struct ClusterNode {
+    link: ClusterLink,
+    stopped: Arc<AtomicBool>,
+    incoming_streams: Arc<Mutex<Vec<TcpStream>>>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl ClusterNode {
+    /// Comienza a escuchar en el puerto de cluster y lanza los hilos del nodo.
+    pub fn start(link: ClusterLink, mqtt_server: MQTTServer, logger: StringLogger) -> Result<Self, Error> {
+        let listener = TcpListener::bind(link.get_cluster_addr()).map_err(|e| {
+            Error::new(
+                e.kind(),
+                format!("Error al enlazar el puerto de cluster {}: {:?}.", link.get_cluster_addr(), e),
+            )
+        })?;
+        listener.set_nonblocking(true)?;
+
+        let mut node = Self {
+            link,
+            stopped: Arc::new(AtomicBool::new(false)),
+            incoming_streams: Arc::new(Mutex::new(vec![])),
+            handles: vec![],
+        };
+
+        let accept_handle = node.spawn_accept_thread(listener, mqtt_server.clone_ref(), logger.clone_ref());
+        let heartbeat_handle = node.spawn_heartbeat_thread(mqtt_server, logger);
+        node.handles.push(accept_handle);
+        node.handles.push(heartbeat_handle);
+        Ok(node)
+    }
+
+    /// Detiene los hilos del nodo y cierra sus conexiones.
+    pub fn stop(self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.link.close_connections();
+        if let Ok(streams) = self.incoming_streams.lock() {
+            for stream in streams.iter() {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        }
+        for h in self.handles {
+            let _ = h.join();
+        }
+    }
+
+    fn spawn_accept_thread(
+        &self,
+        listener: TcpListener,
+        mqtt_server: MQTTServer,
+        logger: StringLogger,
+    ) -> JoinHandle<()> {
+        let stopped = self.stopped.clone();
+        let incoming_streams = self.incoming_streams.clone();
+        let link = self.link.clone_ref();
+        thread::spawn(move || {
+            let mut handles = vec![];
+            while !stopped.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if let Some(h) = register_and_read(stream, &incoming_streams, &link, &mqtt_server, &logger) {
+                            handles.push(h);
+                        }
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                    Err(e) => logger.log(format!("Cluster: error al aceptar conexión: {:?}.", e)),
+                }
+            }
+            for h in handles {
+                let _ = h.join();
+            }
+        })
+    }
+
+    fn spawn_heartbeat_thread(&self, mqtt_server: MQTTServer, logger: StringLogger) -> JoinHandle<()> {
+        let stopped = self.stopped.clone();
+        let link = self.link.clone_ref();
+        thread::spawn(move || {
+            while !stopped.load(Ordering::SeqCst) {
+                let (subscriptions, sessions) = mqtt_server.get_subscriptions_and_sessions();
+                let heartbeat = ClusterMessage::Heartbeat {
+                    node_id: link.get_node_id(),
+                    cluster_addr: link.get_cluster_addr(),
+                    members: link.get_known_addrs(),
+                    subscriptions,
+                    sessions,
+                };
+                for addr in link.get_known_addrs() {
+                    if let Err(e) = link.send_to(&addr, &heartbeat) {
+                        logger.log(format!("Cluster: no se pudo enviar latido a {}: {:?}.", addr, e));
+                    }
+                }
+                sleep_unless_stopped(&stopped, HEARTBEAT_INTERVAL);
+            }
+        })
+    }
+}
+
+/// Guarda una copia del stream (para poder cerrarlo al detener el nodo) y lanza un hilo que lee de él.
+fn register_and_read(
+    stream: TcpStream,
+    incoming_streams: &Arc<Mutex<Vec<TcpStream>>>,
+    link: &ClusterLink,
+    mqtt_server: &MQTTServer,
+    logger: &StringLogger,
+) -> Option<JoinHandle<()>> {
+    if stream.set_nonblocking(false).is_err() {
+        return None;
+    }
+    let stream_c = stream.try_clone().ok()?;
+    if let Ok(mut streams) = incoming_streams.lock() {
+        streams.push(stream_c);
+    }
+    let link = link.clone_ref();
+    let mqtt_server = mqtt_server.clone_ref();
+    let logger = logger.clone_ref();
+    Some(thread::spawn(move || {
+        if let Err(e) = read_from_peer(stream, &link, &mqtt_server) {
+            logger.log(format!("Cluster: error al leer de un nodo: {:?}.", e));
+        }
+    }))
+}
+
+/// Lee mensajes de un nodo hasta que se cierre la conexión.
+fn read_from_peer(mut stream: TcpStream, link: &ClusterLink, mqtt_server: &MQTTServer) -> Result<(), Error> {
+    while let Some(msg) = ClusterMessage::read_from(&mut stream)? {
+        match msg {
+            ClusterMessage::Heartbeat {
+                node_id,
+                cluster_addr,
+                members,
+                subscriptions,
+                sessions,
+            } => link.update_peer(node_id, cluster_addr, members, subscriptions, sessions),
+            ClusterMessage::Publish {
+                origin,
+                msg_bytes,
+                mqtt5,
+            } => {
+                if origin != link.get_node_id() {
+                    let version = if mqtt5 { ProtocolVersion::V5 } else { ProtocolVersion::V311 };
+                    let publish_msg = PublishMessage::from_bytes_with_version(msg_bytes, version)?;
+                    mqtt_server.handle_cluster_publish_message(&publish_msg)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn sleep_unless_stopped(stopped: &AtomicBool, interval: Duration) {
+    let mut slept = Duration::ZERO;
+    while slept < interval && !stopped.load(Ordering::SeqCst) {
+        thread::sleep(POLL_INTERVAL);
+        slept += POLL_INTERVAL;
+    }
+}
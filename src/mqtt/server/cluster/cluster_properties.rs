@@ -0,0 +1,95 @@
+use std::io::{Error, ErrorKind};
+
+use crate::apps::properties::Properties;
+use crate::mqtt::server::server_properties::trim_quotes;
+
+/// Propiedades del modo cluster del servidor. El modo cluster se activa únicamente
+/// si está presente la propiedad `cluster_port`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterProperties {
+    node_id: String,
+    cluster_ip: String,
+    cluster_port: u16,
+    peers: Vec<String>, // Direcciones `ip:puerto_de_cluster` de los nodos conocidos al iniciar.
+}
+
+impl ClusterProperties {
+    /// Lee las propiedades del cluster. Devuelve Ok(None) si no se configuró un cluster.
+    /// `ip` es la ip del servidor, que se usa también para el puerto de cluster.
+    pub fn from_properties(global_properties: &Properties, ip: &str) -> Result<Option<Self>, Error> {
+        let cluster_port: u16;
+        if let Some(prop) = global_properties.get("cluster_port") {
+            cluster_port = trim_quotes(prop)
+                .parse()
+                .map_err(|_| Error::new(ErrorKind::InvalidInput, "cluster_port"))?;
+        } else {
+            return Ok(None);
+        }
+
+        let node_id: String;
+        if let Some(prop) = global_properties.get("cluster_node_id") {
+            node_id = trim_quotes(prop);
+        } else {
+            node_id = format!("{}:{}", ip, cluster_port);
+        }
+
+        let mut peers = vec![];
+        if let Some(prop) = global_properties.get("cluster_peers") {
+            peers = trim_quotes(prop)
+                .split(',')
+                .map(|peer| peer.trim().to_string())
+                .filter(|peer| !peer.is_empty())
+                .collect();
+        }
+
+        Ok(Some(Self {
+            node_id,
+            cluster_ip: ip.to_string(),
+            cluster_port,
+            peers,
+        }))
+    }
+
+    /// Devuelve el identificador de este nodo.
+    pub fn get_node_id(&self) -> String {
+        self.node_id.to_string()
+    }
+
+    /// Devuelve la dirección `ip:puerto` en la que este nodo escucha a los demás nodos.
+    pub fn get_cluster_addr(&self) -> String {
+        format!("{}:{}", self.cluster_ip, self.cluster_port)
+    }
+
+    /// Devuelve las direcciones de los nodos conocidos al iniciar.
+    pub fn get_peers(&self) -> &Vec<String> {
+        &self.peers
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::apps::properties::Properties;
+
+    use super::ClusterProperties;
+
+    #[test]
+    fn test_1_se_leen_puerto_id_por_defecto_y_peers() {
+        let path = "test_cluster_properties_1.properties";
+        fs::write(
+            path,
+            "cluster_port=\"9190\"\ncluster_peers=\"127.0.0.1:9290, 127.0.0.1:9390\"\n",
+        )
+        .unwrap();
+        let props = Properties::new(path);
+        let _ = fs::remove_file(path);
+
+        let cluster = ClusterProperties::from_properties(&props.unwrap(), "127.0.0.1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(cluster.get_node_id(), "127.0.0.1:9190");
+        assert_eq!(cluster.get_cluster_addr(), "127.0.0.1:9190");
+        assert_eq!(cluster.get_peers(), &vec!["127.0.0.1:9290".to_string(), "127.0.0.1:9390".to_string()]);
+    }
+}
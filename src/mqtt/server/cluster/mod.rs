@@ -0,0 +1,5 @@
+pub mod cluster_link;
+pub mod cluster_message;
+pub mod cluster_node;
+pub mod cluster_properties;
+pub mod shared_subscription;
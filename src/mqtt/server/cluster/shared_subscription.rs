@@ -0,0 +1,28 @@
+const SHARED_PREFIX: &str = "$share/";
+
+/// Si `topic_filter` es una suscripción compartida (`$share/grupo/topic`), devuelve el grupo y el topic.
+/// Los suscriptores de un mismo grupo se reparten los mensajes del topic, en lugar de recibirlos todos.
+pub fn parse_shared_subscription(topic_filter: &str) -> Option<(String, String)> {
+    let rest = topic_filter.strip_prefix(SHARED_PREFIX)?;
+    let (group, topic) = rest.split_once('/')?;
+    if group.is_empty() || topic.is_empty() {
+        return None;
+    }
+    Some((group.to_string(), topic.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_shared_subscription;
+
+    #[test]
+    fn test_1_se_parsean_grupo_y_topic() {
+        assert_eq!(
+            parse_shared_subscription("$share/detectores/cam"),
+            Some(("detectores".to_string(), "cam".to_string()))
+        );
+        assert_eq!(parse_shared_subscription("cam"), None);
+        assert_eq!(parse_shared_subscription("$share/detectores"), None);
+        assert_eq!(parse_shared_subscription("$share//cam"), None);
+    }
+}
@@ -1,9 +1,18 @@
-use std::{io::Error, net::TcpListener, result::Result, thread::JoinHandle};
+use std::{
+    io::{Error, ErrorKind},
+    net::TcpListener,
+    result::Result,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
 
 use crate::{logging::string_logger::StringLogger, mqtt::stream_type::StreamType};
 
 use super::{client_reader::ClientReader, mqtt_server::MQTTServer};
 
+// Cada cuánto se revisa si el servidor se está cerrando, mientras no llegan conexiones.
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 #[derive(Debug)]
 pub struct ClientListener {
     logger: StringLogger,
@@ -22,9 +31,21 @@ impl ClientListener {
         let mut handles = Vec::<JoinHandle<()>>::new();
         println!("Servidor iniciado. Esperando conexiones.\n");
         self.logger.log("Servidor iniciado. Esperando conexiones.".to_string());
-        for stream in listener.incoming() {
-            handles.push(self.handle_stream(stream?, mqtt_server.clone_ref())?);
+        // El listener es no bloqueante para poder dejar de aceptar conexiones al cerrar el servidor.
+        listener.set_nonblocking(true)?;
+        while !mqtt_server.is_shutting_down() {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    stream.set_nonblocking(false)?;
+                    handles.push(self.handle_stream(stream.into(), mqtt_server.clone_ref())?);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL_INTERVAL),
+                Err(e) => {
+                    self.logger.log(format!("Error al aceptar conexión: {:?}.", e));
+                }
+            }
         }
+        self.logger.log("Se dejan de aceptar conexiones.".to_string());
 
         for h in handles {
             if let Err(e) = h.join() {
@@ -35,7 +56,8 @@ impl ClientListener {
         Ok(())
     }
 
-    fn handle_stream(
+    /// Lanza el hilo que atiende al cliente conectado por `stream`.
+    pub fn handle_stream(
         &mut self,
         mut stream: StreamType,
         mqtt_server: MQTTServer,
@@ -1,37 +1,78 @@
 use rustx::logging::string_logger::StringLogger;
-use rustx::mqtt::server::mqtt_server::MQTTServer;
+use rustx::mqtt::bridge::mqtt_bridge::MQTTBridge;
+use rustx::mqtt::server::{
+    mqtt_server::MQTTServer, server_properties::ServerProperties, signal_handler::SignalHandler,
+};
 use std::env::args;
 use std::io::{Error, ErrorKind};
+use std::net::SocketAddr;
 
-/// Lee el puerto por la consola, y devuelve la dirección IP y el puerto.
-pub fn load_port() -> Result<(String, u16), Error> {
+const PROPERTIES_FILE: &str = "message_broker_server_config.properties";
+
+/// Lee las propiedades del servidor. Si se indica un puerto por consola, éste reemplaza al del archivo.
+/// Opcionalmente puede indicarse también otro archivo de propiedades, por ejemplo para levantar varios nodos de un cluster.
+/// Devuelve también la ruta del archivo de propiedades usado, para poder recargarlo.
+pub fn load_properties() -> Result<(ServerProperties, String, String, u16), Error> {
     let argv = args().collect::<Vec<String>>();
-    if argv.len() != 2 {
-        return Err(Error::new(ErrorKind::InvalidInput, "Cantidad de argumentos inválido. Debe ingresar el puerto en el que desea correr el servidor."));
+    if argv.len() > 3 {
+        return Err(Error::new(ErrorKind::InvalidInput, "Cantidad de argumentos inválido. Puede ingresar opcionalmente el puerto en el que desea correr el servidor, y el archivo de propiedades."));
     }
-    let port = match argv[1].parse::<u16>() {
-        Ok(port) => port,
-        Err(_) => {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "El puerto proporcionado no es válido",
-            ))
-        }
-    };
-    let localhost = "127.0.0.1".to_string();
+    let properties_file = argv.get(2).map_or(PROPERTIES_FILE, |f| f.as_str()).to_string();
+    let properties = ServerProperties::new(&properties_file)?;
+    let (ip, mut port) = properties.get_address();
 
-    Ok((localhost, port))
+    if let Some(port_arg) = argv.get(1) {
+        port = match port_arg.parse::<u16>() {
+            Ok(port) => port,
+            Err(_) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "El puerto proporcionado no es válido",
+                ))
+            }
+        };
+    }
+
+    Ok((properties, properties_file, ip, port))
 }
 
 
 fn main() -> Result<(), Error> {
-    let (ip, port) = load_port()?;
+    let (properties, properties_file, ip, port) = load_properties()?;
 
     // Se crean y configuran ambos extremos del string logger
     let (mut logger, handle_logger) = StringLogger::create_logger(get_formatted_app_id());
 
-    let mqtt_server = MQTTServer::new(logger.clone_ref());
-    mqtt_server.run(ip, port)?;
+    let mqtt_server = MQTTServer::new(logger.clone_ref(), &properties);
+    let signal_handler = SignalHandler::spawn(
+        mqtt_server.clone_ref(),
+        properties_file,
+        logger.clone_ref(),
+    )?;
+
+    // Si se configuró un bridge, se lo lanza; se conectará al server local una vez que éste esté corriendo.
+    let bridge = match properties.get_bridge_properties() {
+        Some(bridge_properties) => {
+            let local_addr: SocketAddr = format!("{}:{}", ip, port)
+                .parse()
+                .map_err(|_| Error::new(ErrorKind::InvalidInput, "Dirección no válida"))?;
+            let bridge = MQTTBridge::new(bridge_properties.clone(), local_addr, logger.clone_ref());
+            let handle = bridge.spawn();
+            Some((bridge, handle))
+        }
+        None => None,
+    };
+
+    let run_res = mqtt_server.run(ip, port);
+
+    // Se deja de escuchar señales, y se detiene el bridge
+    signal_handler.stop();
+    if let Some((bridge, handle)) = bridge {
+        bridge.stop();
+        if handle.join().is_err() {
+            println!("Error al esperar al hilo del bridge.");
+        }
+    }
 
     // Se cierra el logger
     logger.stop_logging();
@@ -42,7 +83,7 @@ fn main() -> Result<(), Error> {
         println!("Error al esperar al hijo para string logger writer.")
     }
 
-    Ok(())
+    run_res
 }
 
 fn get_formatted_app_id() -> String {
@@ -6,7 +6,8 @@ use rayon::ThreadPool;
 
 use crate::mqtt::messages::{
         packet_type::PacketType, puback_message::PubAckMessage, publish_message::PublishMessage,
-        subscribe_message::SubscribeMessage, subscribe_return_code::SubscribeReturnCode,
+        reason_code::ReasonCode, subscribe_message::SubscribeMessage,
+        subscribe_return_code::SubscribeReturnCode, unsubscribe_message::UnsubscribeMessage,
 };
 
 use std::io::Error;
@@ -35,12 +36,16 @@ impl MessageProcessor {
         // Con threadpool sería:
         match create_thread_pool_with(20) {
             Ok(thread_pool) => {
-                for packet in rx_1 {
-                    let self_clone = self.clone_ref();
-                    thread_pool.spawn(move || {
-                        self_clone.process_packet(packet);
-                    });
-                }
+                // El scope espera a que terminen de procesarse los paquetes ya recibidos
+                // antes de retornar, para no perder mensajes durante el cierre del servidor.
+                thread_pool.scope(|scope| {
+                    for packet in rx_1 {
+                        let self_clone = self.clone_ref();
+                        scope.spawn(move |_| {
+                            self_clone.process_packet(packet);
+                        });
+                    }
+                });
             }
             Err(e) => {
                 println!("   ERROR: {:?}", e);
@@ -64,19 +69,45 @@ impl MessageProcessor {
         match packet.get_message_type() {
             PacketType::Publish => self.handle_publish(msg_bytes, client_id),
             PacketType::Subscribe => self.handle_subscribe(msg_bytes, client_id),
-            PacketType::Puback => self.handle_puback(msg_bytes),
+            PacketType::Unsubscribe => self.handle_unsubscribe(msg_bytes, client_id),
+            PacketType::Puback => self.handle_puback(msg_bytes, client_id),
+            PacketType::Pingreq => self.handle_pingreq(client_id),
             _ => println!("   ERROR: Tipo de mensaje desconocido\n "),
         };
     }
 
     fn handle_publish(&self, msg_bytes: Vec<u8>, client_id: &str) {
-        let publish_msg_res = PublishMessage::from_bytes(msg_bytes);
+        let version = self.mqtt_server.get_protocol_version_of(client_id);
+        let publish_msg_res = PublishMessage::from_bytes_with_version(msg_bytes, version);
         match publish_msg_res {
-            Ok(publish_msg) => {
-                println!("Publish recibido, topic: {:?}, packet_id: {:?}", publish_msg.get_topic(), publish_msg.get_packet_id());
-                let puback_res = self.send_puback_to(client_id, &publish_msg);
-                if let Err(e) = puback_res {
+            Ok(mut publish_msg) => {
+                if let Err(e) = self.mqtt_server.resolve_topic_alias(client_id, &mut publish_msg) {
                     println!("   Error en handle_publish: {:?}", e);
+                    if let Err(e) = self.send_puback_to(client_id, &publish_msg, ReasonCode::TopicAliasInvalid) {
+                        println!("   Error en handle_publish: {:?}", e);
+                    }
+                    return;
+                }
+                println!(
+                    "Publish recibido, topic: {:?}, packet_id: {:?}, app: {:?}",
+                    publish_msg.get_topic(),
+                    publish_msg.get_packet_id(),
+                    publish_msg.get_app_id().unwrap_or(client_id)
+                );
+                // Se rechaza antes de responder, para no confirmarle al cliente un publish que no se distribuye.
+                if !self.mqtt_server.is_allowed_to_publish(client_id, &publish_msg.get_topic()) {
+                    println!("   Publish de {:?} al topic {:?} rechazado por acl.", client_id, publish_msg.get_topic());
+                    if let Err(e) = self.mqtt_server.reject_unauthorized_publish(client_id, &publish_msg) {
+                        println!("   Error en handle_publish: {:?}", e);
+                    }
+                    return;
+                }
+                // Los publish con qos 0 no llevan packet_id ni se responden con puback.
+                if publish_msg.get_packet_id().is_some() {
+                    let puback_res = self.send_puback_to(client_id, &publish_msg, ReasonCode::Success);
+                    if let Err(e) = puback_res {
+                        println!("   Error en handle_publish: {:?}", e);
+                    }
                 }
                 if let Err(e) = self.mqtt_server.handle_publish_message(&publish_msg){
                     // No quiero retornar si falló alguna operación hacia Un user, solamente logguearlo.
@@ -89,7 +120,8 @@ impl MessageProcessor {
     }
 
     fn handle_subscribe(&self, msg_bytes: Vec<u8>, client_id: &str) {
-        let subscribe_msg_res = SubscribeMessage::from_bytes(msg_bytes);
+        let version = self.mqtt_server.get_protocol_version_of(client_id);
+        let subscribe_msg_res = SubscribeMessage::from_bytes_with_version(msg_bytes, version);
         match subscribe_msg_res {
             Ok(msg) => {
                 let return_codes_res = self.mqtt_server.add_topics_to_subscriber(client_id, &msg);
@@ -109,20 +141,43 @@ impl MessageProcessor {
         }
     }
 
-    fn handle_puback(&self, msg_bytes: Vec<u8>) {
+    fn handle_unsubscribe(&self, msg_bytes: Vec<u8>, client_id: &str) {
+        let version = self.mqtt_server.get_protocol_version_of(client_id);
+        match UnsubscribeMessage::from_bytes_with_version(msg_bytes, version) {
+            Ok(msg) => {
+                let reason_codes = self.mqtt_server.remove_topics_from_subscriber(client_id, &msg);
+                if let Err(e) = self.mqtt_server.send_unsuback_to(client_id, msg.get_packet_id(), reason_codes) {
+                    println!("   ERROR: {:?}", e);
+                }
+            }
+            Err(e) => println!("   ERROR: {:?}", e),
+        }
+    }
+
+    fn handle_puback(&self, msg_bytes: Vec<u8>, client_id: &str) {
         let puback_msg_res = PubAckMessage::msg_from_bytes(msg_bytes);
         match puback_msg_res {
-            Ok(puback_msg) => println!("Pub ack recibido, packet_id: {:?}", puback_msg.get_packet_id()),
+            Ok(puback_msg) => {
+                println!("Pub ack recibido, packet_id: {:?}", puback_msg.get_packet_id());
+                self.mqtt_server.release_packet_id_of(client_id, puback_msg.get_packet_id());
+            }
             Err(e) => println!("   ERROR: {:?}", e),
         }
     }
 
+    fn handle_pingreq(&self, client_id: &str) {
+        if let Err(e) = self.mqtt_server.send_pingresp_to(client_id) {
+            println!("   ERROR: {:?}", e);
+        }
+    }
+
     pub fn send_puback_to(
         &self,
         client_id: &str,
         publish_msg: &PublishMessage,
+        reason_code: ReasonCode,
     ) -> Result<(), Error> {
-        self.mqtt_server.send_puback_to(client_id, publish_msg)?;
+        self.mqtt_server.send_puback_to(client_id, publish_msg, reason_code)?;
 
         Ok(())
     }
@@ -151,6 +206,6 @@ fn create_thread_pool_with(num_threads: usize) -> Result<ThreadPool, Error> {
         .build()
     {
         Ok(thread_pool) => Ok(thread_pool),
-        Err(e) => Err(Error::new(std::io::ErrorKind::Other, e)),
+        Err(e) => Err(Error::other(e)),
     }
 }
@@ -0,0 +1,130 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{self, File},
+    io::{Error, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::mqtt::messages::{protocol_version::ProtocolVersion, publish_message::PublishMessage};
+
+/// Contenido del archivo del store: por topic, los bytes de cada mensaje en MQTT 5 (que incluyen su timestamp
+/// y propiedades).
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct StoredMessages {
+    topics: HashMap<String, Vec<Vec<u8>>>,
+}
+
+/// Archivo en el que el servidor guarda, al cerrarse, los mensajes de cada topic, para volver a tenerlos
+/// al levantarse. Como el registro de cámaras, escribe un archivo temporal y lo renombra sobre el original.
+#[derive(Debug, Clone)]
+pub struct MessageStore {
+    path: PathBuf,
+}
+
+impl MessageStore {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Devuelve los mensajes guardados, por topic, sin los que ya expiraron. Si no hay archivo, no hay mensajes.
+    pub fn load(&self) -> Result<HashMap<String, VecDeque<PublishMessage>>, Error> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(e),
+        };
+        let stored: StoredMessages = serde_json::from_str(&contents).map_err(|e| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Store de mensajes inválido en {:?}: {}", self.path, e),
+            )
+        })?;
+
+        let mut messages_by_topic = HashMap::new();
+        for (topic, messages) in stored.topics {
+            let mut topic_messages = VecDeque::new();
+            for bytes in messages {
+                let msg = PublishMessage::from_bytes_with_version(bytes, ProtocolVersion::V5)?;
+                if !msg.is_expired() {
+                    topic_messages.push_back(msg);
+                }
+            }
+            messages_by_topic.insert(topic, topic_messages);
+        }
+        Ok(messages_by_topic)
+    }
+
+    /// Reemplaza lo guardado por `messages_by_topic`.
+    pub fn save(
+        &self,
+        messages_by_topic: &HashMap<String, VecDeque<PublishMessage>>,
+    ) -> Result<(), Error> {
+        let stored = StoredMessages {
+            topics: messages_by_topic
+                .iter()
+                .map(|(topic, messages)| {
+                    let bytes = messages
+                        .iter()
+                        .map(|msg| msg.to_bytes_for(ProtocolVersion::V5))
+                        .collect();
+                    (topic.clone(), bytes)
+                })
+                .collect(),
+        };
+        let json =
+            serde_json::to_string(&stored).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let mut tmp_file = File::create(&tmp_path)?;
+        tmp_file.write_all(json.as_bytes())?;
+        tmp_file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, VecDeque};
+
+    use super::MessageStore;
+    use crate::mqtt::messages::{
+        mqtt_properties::MqttProperties, publish_flags::PublishFlags,
+        publish_message::PublishMessage,
+    };
+
+    #[test]
+    fn test_1_los_mensajes_guardados_se_recuperan_salvo_los_expirados() {
+        let path =
+            std::env::temp_dir().join(format!("rustx_message_store_{}.json", std::process::id()));
+        let store = MessageStore::new(&path);
+        let _ = std::fs::remove_file(&path);
+        assert!(store.load().unwrap().is_empty());
+
+        let flags = PublishFlags::new(0, 1, 0).unwrap();
+        let kept = PublishMessage::new(flags.clone(), "inc", Some(1), &[1, 2]).unwrap();
+        let mut expired = PublishMessage::new(flags, "inc", Some(2), &[3]).unwrap();
+        let mut properties = MqttProperties::new();
+        properties.message_expiry_interval = Some(0);
+        expired.set_mqtt5_properties(properties);
+        let messages =
+            HashMap::from([("inc".to_string(), VecDeque::from([kept.clone(), expired]))]);
+        store.save(&messages).unwrap();
+
+        let loaded = store.load().unwrap();
+        let loaded_msgs = loaded.get("inc").unwrap();
+        assert_eq!(loaded_msgs.len(), 1);
+        assert_eq!(loaded_msgs[0].get_payload(), kept.get_payload());
+        assert_eq!(loaded_msgs[0].get_timestamp(), kept.get_timestamp());
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
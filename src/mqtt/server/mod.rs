@@ -1,10 +1,17 @@
+pub mod acl;
 pub mod client_authenticator;
 pub mod client_reader;
+pub mod cluster;
 pub mod disconnect_reason;
 pub mod file_helper;
 pub mod incoming_connections;
 pub mod message_processor;
+pub mod message_store;
 pub mod mqtt_server;
 pub mod packet;
+pub mod server_config;
+pub mod server_handle;
+pub mod server_properties;
+pub mod signal_handler;
 pub mod user;
 pub mod user_state;
@@ -1,25 +1,36 @@
 use crate::logging::string_logger::StringLogger;
 use crate::mqtt::messages::connect_message::ConnectMessage;
 use crate::mqtt::messages::{
-    disconnect_message::DisconnectMessage, puback_message::PubAckMessage,
-    publish_message::PublishMessage, suback_message::SubAckMessage,
+    disconnect_message::DisconnectMessage, mqtt_properties::MqttProperties, ping_message::PingMessage,
+    protocol_version::ProtocolVersion, puback_message::PubAckMessage,
+    publish_message::PublishMessage, reason_code::ReasonCode, suback_message::SubAckMessage,
     subscribe_message::SubscribeMessage, subscribe_return_code::SubscribeReturnCode,
+    unsuback_message::Unsuback, unsubscribe_message::UnsubscribeMessage,
 };
 
+use crate::mqtt::server::cluster::{
+    cluster_link::ClusterLink, cluster_message::SessionInfo, cluster_node::ClusterNode,
+    shared_subscription::parse_shared_subscription,
+};
 use crate::mqtt::server::{
-    incoming_connections::ClientListener, user::User, user_state::UserState,
+    acl::AclAction, incoming_connections::ClientListener, message_store::MessageStore,
+    server_config::ServerConfig, server_handle::ServerHandle, server_properties::ServerProperties,
+    user::User, user_state::UserState,
 };
-use crate::mqtt::stream_type::StreamType;
+use crate::mqtt::mqtt_utils::packet_id_allocator::PacketIdAllocator;
+use crate::mqtt::{memory_stream::MemoryStream, stream_type::StreamType};
 use std::{
     collections::{hash_map::ValuesMut, HashMap, VecDeque},
     fs::File,
     io::{Error, ErrorKind, Write},
-    net::TcpListener,
-    sync::{Arc, Mutex},
+    net::{SocketAddr, TcpListener},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
     thread,
 };
 
-const TOPIC_MESSAGES_LEN: usize = 50;
 type ShareableUsers = Arc<Mutex<HashMap<String, User>>>;
 type TopicMessages = VecDeque<PublishMessage>; // Se guardaran todos los mensajes, y se enviaran en caso de reconexión o si un cliente no recibio ciertos mensajes.
 
@@ -32,29 +43,96 @@ fn clean_file(file_path: &str) -> Result<(), Error> {
 #[derive(Debug)]
 pub struct MQTTServer {
     connected_users: ShareableUsers,
-    available_packet_id: u16,                                      //
+    will_packet_ids: Arc<Mutex<PacketIdAllocator>>, // ids de los will messages que publica el propio servidor.
     messages_by_topic: Arc<Mutex<HashMap<String, TopicMessages>>>, // String = topic
+    config: Arc<Mutex<ServerConfig>>, // Credenciales, acl y retención; recargables en caliente.
+    store: Option<MessageStore>, // Dónde se guardan los mensajes al cerrarse, si se configuró.
+    shutting_down: Arc<AtomicBool>,
+    cluster: Option<ClusterLink>, // Presente sólo si el servidor es un nodo de un cluster.
+    shared_counters: Arc<Mutex<HashMap<String, usize>>>, // Por `grupo/topic`, cuántos mensajes se repartieron.
     logger: StringLogger,
 }
 
 impl MQTTServer {
-    pub fn new(logger: StringLogger) -> Self {
+    pub fn new(logger: StringLogger, properties: &ServerProperties) -> Self {
         let file_path = "log.txt";
         if let Err(e) = clean_file(file_path) {
             println!("Error al limpiar el archivo: {:?}", e);
         }
 
+        let config = ServerConfig::load(properties).unwrap_or_else(|e| {
+            logger.log(format!("Error al cargar la configuración, no se permitirá ninguna operación: {:?}.", e));
+            ServerConfig::deny_all(properties)
+        });
+        let store = properties.get_store_file().map(MessageStore::new);
+        let messages_by_topic = match &store {
+            Some(store) => store.load().unwrap_or_else(|e| {
+                logger.log(format!("Error al cargar los mensajes guardados: {:?}.", e));
+                HashMap::new()
+            }),
+            None => HashMap::new(),
+        };
+
         Self {
             connected_users: Arc::new(Mutex::new(HashMap::new())),
-            available_packet_id: 0,
-            messages_by_topic: Arc::new(Mutex::new(HashMap::new())),
+            will_packet_ids: Arc::new(Mutex::new(PacketIdAllocator::new())),
+            messages_by_topic: Arc::new(Mutex::new(messages_by_topic)),
+            config: Arc::new(Mutex::new(config)),
+            store,
+            shutting_down: Arc::new(AtomicBool::new(false)),
+            cluster: properties.get_cluster_properties().cloned().map(ClusterLink::new),
+            shared_counters: Arc::new(Mutex::new(HashMap::new())),
             logger,
         }
     }
 
     pub fn run(&self, ip: String, port: u16) -> Result<(), Error> {
+        let listener = create_server(ip, port)?;
+        self.serve(listener)
+    }
 
+    /// Lanza en otro hilo un servidor en un puerto efímero de `127.0.0.1`, con las propiedades por defecto.
+    /// Pensado para embeber el broker en el mismo proceso, por ejemplo en tests de integración.
+    /// Devuelve el handle con el que detenerlo, y la dirección en la que quedó escuchando.
+    pub fn start_on_ephemeral_port(logger: StringLogger) -> Result<(ServerHandle, SocketAddr), Error> {
+        Self::start_in_background(logger, &ServerProperties::ephemeral())
+    }
+
+    /// Como `start_on_ephemeral_port`, pero con las propiedades `properties` (con puerto 0 para uno efímero).
+    pub fn start_in_background(
+        logger: StringLogger,
+        properties: &ServerProperties,
+    ) -> Result<(ServerHandle, SocketAddr), Error> {
+        let (ip, port) = properties.get_address();
         let listener = create_server(ip, port)?;
+        let addr = listener.local_addr()?;
+
+        let mqtt_server = MQTTServer::new(logger, properties);
+        let self_clone = mqtt_server.clone_ref();
+        let thread_server = thread::spawn(move || self_clone.serve(listener));
+
+        Ok((ServerHandle::new(mqtt_server, thread_server), addr))
+    }
+
+    /// Conecta un cliente al servidor en memoria, sin pasar por la red: atiende un extremo de la conexión como a
+    /// cualquier conexión entrante, y devuelve el otro, para usarlo con `MQTTClient::mqtt_connect_over_stream`.
+    /// No requiere que el servidor esté corriendo con `run`.
+    pub fn connect_in_memory(&self) -> Result<StreamType, Error> {
+        if self.is_shutting_down() {
+            return Err(Error::new(ErrorKind::NotConnected, "El servidor se está cerrando."));
+        }
+        let (client_end, server_end) = MemoryStream::pair();
+        let mut incoming_connections = ClientListener::new(self.logger.clone_ref());
+        incoming_connections.handle_stream(server_end.into(), self.clone_ref())?;
+        Ok(client_end.into())
+    }
+
+    /// Atiende las conexiones que lleguen a `listener`, hasta que se cierre el servidor.
+    fn serve(&self, listener: TcpListener) -> Result<(), Error> {
+        let cluster_node = match &self.cluster {
+            Some(link) => Some(ClusterNode::start(link.clone_ref(), self.clone_ref(), self.logger.clone_ref())?),
+            None => None,
+        };
         let mut incoming_connections = ClientListener::new(self.logger.clone_ref());
         let self_clone = self.clone_ref();
         let logger_c = self.logger.clone_ref();
@@ -69,9 +147,116 @@ impl MQTTServer {
             self.logger.log(format!("Error al esperar al hilo incoming, en run: {:?}.", e));
         }
 
+        if let Some(node) = cluster_node {
+            node.stop();
+        }
+
+        // Ya no quedan clientes que publiquen: se guardan los mensajes para el próximo inicio.
+        if let Err(e) = self.flush_store() {
+            self.logger.log(format!("Error al guardar los mensajes, en run: {:?}.", e));
+        }
+
+        Ok(())
+    }
+
+    /// Guarda los mensajes de cada topic en el store, si se configuró uno.
+    pub fn flush_store(&self) -> Result<(), Error> {
+        let store = match &self.store {
+            Some(store) => store,
+            None => return Ok(()),
+        };
+        if let Ok(messages_by_topic) = self.messages_by_topic.lock() {
+            store.save(&messages_by_topic)?;
+            self.logger.log(format!("Mensajes guardados en {:?}.", store.get_path()));
+            Ok(())
+        } else {
+            Err(Error::other("Error: no se pudo tomar lock a messages_by_topic para guardar los mensajes."))
+        }
+    }
+
+    /// Comienza el cierre ordenado del servidor: deja de aceptar conexiones, publica el will message
+    /// de los usuarios activos, y les cierra la conexión. Los hilos de cada cliente terminan de procesar
+    /// los mensajes ya recibidos antes de que `run` retorne.
+    pub fn shutdown(&self) {
+        // Si ya se estaba cerrando, no hay nada más que hacer.
+        if self.shutting_down.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        println!("Cerrando el servidor.");
+        self.logger.log("Cerrando el servidor.".to_string());
+
+        // Se publican los will messages mientras los suscriptores siguen conectados.
+        for username in self.get_active_usernames() {
+            if let Err(e) = self.publish_users_will_message(&username) {
+                self.logger.log(format!("Error al publicar will message de {:?} durante el cierre: {:?}.", username, e));
+            }
+        }
+
+        // Se desconecta a los usuarios activos.
+        if let Ok(mut users) = self.connected_users.lock() {
+            for user in users.values_mut() {
+                if user.get_state() == &UserState::Active {
+                    let msg = create_disconnect_msg_for(user, ReasonCode::ServerShuttingDown);
+                    let _ = user.write_message(&msg.to_bytes());
+                    user.shutdown();
+                }
+            }
+        }
+    }
+
+    /// Devuelve si el servidor se está cerrando.
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// Recarga credenciales, acl y retención desde los archivos indicados en `properties`,
+    /// sin cortar las conexiones existentes. La ip y el puerto no se modifican.
+    /// Si la nueva configuración no puede cargarse, se mantiene la anterior.
+    pub fn reload_config(&self, properties: &ServerProperties) -> Result<(), Error> {
+        let new_config = ServerConfig::load(properties)?;
+        if let Ok(mut config) = self.config.lock() {
+            *config = new_config;
+        } else {
+            return Err(Error::other(
+                "Error: no se pudo tomar lock a config para recargar la configuración.",
+            ));
+        }
+        println!("Configuración del servidor recargada.");
+        self.logger.log("Configuración del servidor recargada.".to_string());
         Ok(())
     }
 
+    /// Verifica las credenciales recibidas contra las cargadas en la configuración.
+    pub fn authenticate(&self, user: Option<&String>, passwd: Option<&String>) -> bool {
+        if let Ok(config) = self.config.lock() {
+            return config.verify_authentication(user, passwd);
+        }
+        false
+    }
+
+    /// Devuelve si el cliente `client_id` tiene permitido publicar al topic `topic`.
+    pub fn is_allowed_to_publish(&self, client_id: &str, topic: &str) -> bool {
+        self.is_allowed(client_id, AclAction::Publish, topic)
+    }
+
+    fn is_allowed(&self, client_id: &str, action: AclAction, topic: &str) -> bool {
+        if let Ok(config) = self.config.lock() {
+            return config.is_allowed(client_id, action, topic);
+        }
+        false
+    }
+
+    fn get_active_usernames(&self) -> Vec<String> {
+        if let Ok(users) = self.connected_users.lock() {
+            return users
+                .iter()
+                .filter(|(_, user)| user.get_state() == &UserState::Active)
+                .map(|(username, _)| username.to_string())
+                .collect();
+        }
+        vec![]
+    }
+
     /// Agrega un PublishMessage a la estructura de mensajes de su topic.
     fn add_message_to_topic_messages(
         &self,
@@ -91,12 +276,13 @@ impl MQTTServer {
 
     /// Busca al client_id en el hashmap de conectados, si ya existía analiza su estado:
     /// si ya estaba como activo, es un usuario duplicado por lo que le envía disconnect al stream anterior;
-    /// si estaba como desconectado temporalmente (ie ctrl+C), se está reconectando.
+    /// si estaba como desconectado temporalmente (ie ctrl+C), se está reconectando, salvo que su sesión haya expirado.
     /// Devuelve true si era reconexión, false si no era reconexión.
     pub fn manage_possible_reconnecting_or_duplicate_user(
         &self,
         client_id: &str,
         new_stream_of_reconnected_user: &StreamType,
+        connect_msg: &ConnectMessage,
     ) -> Result<bool, Error> {
         if let Ok(mut connected_users_locked) = self.connected_users.lock() {
             if let Some(client) = connected_users_locked.get_mut(client_id) {
@@ -107,8 +293,14 @@ impl MQTTServer {
                         let _ = connected_users_locked.remove(client_id);
                         println!("Se conecta usuario duplicado: {:?}, desconectando el anterior.", client_id);
                     }
+                    UserState::TemporallyDisconnected if client.is_session_expired() => {
+                        // La sesión anterior expiró (MQTT 5) ==> Se conecta como un usuario nuevo.
+                        let _ = connected_users_locked.remove(client_id);
+                        println!("Expiró la sesión de {:?}, se conecta como nuevo.", client_id);
+                    }
                     UserState::TemporallyDisconnected => {
                         // El cliente se encontraba temp desconectado ==> Se está reconectando.
+                        client.set_protocol(connect_msg.get_protocol_version()?, get_session_expiry_interval(connect_msg));
                         self.handle_reconnecting_user(client, new_stream_of_reconnected_user)?;
                         println!("Se reconecta el usuario: {:?}, emviándole mensajes.", client_id);
                         // Único caso en que devuelve true.
@@ -123,7 +315,7 @@ impl MQTTServer {
     /// Desconecta al user previo que ya existía, para permitir la conexión con el nuevo.
     fn handle_duplicate_user(&self, client: &mut User) -> Result<(), Error> {
         // Desconecto al user que ya que existía
-        let msg = DisconnectMessage::new();
+        let msg = create_disconnect_msg_for(client, ReasonCode::SessionTakenOver);
         client.write_message(&msg.to_bytes())?;
         client.shutdown();
         
@@ -149,8 +341,7 @@ impl MQTTServer {
                     self.send_unreceived_messages(client, &topic, topic_messages)?;
                 }
             } else {
-                return Err(Error::new(
-                    ErrorKind::Other,
+                return Err(Error::other(
                     "Error: no se pudo tomar lock a messages_by_topic para enviar Publish durante reconexión."));
             }
         }
@@ -186,7 +377,9 @@ impl MQTTServer {
 
         let username_c = username.to_string();
         //[] Aux: Nos guardamos el stream, volver a ver esto.
-        let user = User::new(stream.try_clone()?, username_c.to_owned(), will_msg_info); //[]
+        let mut user = User::new(stream.try_clone()?, username_c.to_owned(), will_msg_info); //[]
+        user.set_protocol(connect_msg.get_protocol_version()?, get_session_expiry_interval(connect_msg));
+        self.restore_replicated_session(&mut user);
         if let Ok(mut users) = self.connected_users.lock() {
             println!("Username agregado a la lista del server: {:?}", username);
             users.insert(username_c, user); //inserta el usuario en el hashmap
@@ -195,39 +388,155 @@ impl MQTTServer {
         Ok(())
     }
 
+    /// Si el cluster tiene replicada una sesión de `user` (porque antes estaba conectado a otro nodo),
+    /// lo vuelve a suscribir a los mismos topics. Sólo recibirá los mensajes que se publiquen a partir de ahora.
+    fn restore_replicated_session(&self, user: &mut User) {
+        let Some(link) = &self.cluster else {
+            return;
+        };
+        if let Some(topic_filters) = link.get_replicated_session(&user.get_username()) {
+            for topic_filter in topic_filters {
+                let (topic, group) = match parse_shared_subscription(&topic_filter) {
+                    Some((group, topic)) => (topic, Some(group)),
+                    None => (topic_filter, None),
+                };
+                let last_id = self.get_topic_messages_len(&topic);
+                match group {
+                    Some(group) => user.add_shared_topic(topic, group, last_id),
+                    None => {
+                        user.add_topic(topic.to_string());
+                        user.update_last_id_by_topic(&topic, last_id);
+                    }
+                }
+            }
+            println!("Sesión de {:?} restaurada desde el cluster: {:?}", user.get_username(), user.get_topics());
+        }
+    }
+
+    fn get_topic_messages_len(&self, topic: &str) -> u32 {
+        if let Ok(messages_by_topic) = self.messages_by_topic.lock() {
+            if let Some(topic_messages) = messages_by_topic.get(topic) {
+                return topic_messages.len() as u32;
+            }
+        }
+        0
+    }
+
+    /// Devuelve los topics a los que están suscriptos los usuarios de este servidor, y la sesión de cada uno,
+    /// para anunciarlos al resto del cluster.
+    pub fn get_subscriptions_and_sessions(&self) -> (Vec<String>, Vec<SessionInfo>) {
+        let mut subscriptions = vec![];
+        let mut sessions = vec![];
+        if let Ok(users) = self.connected_users.lock() {
+            for user in users.values() {
+                let mut topic_filters = vec![];
+                for topic in user.get_topics() {
+                    if !subscriptions.contains(topic) {
+                        subscriptions.push(topic.to_string());
+                    }
+                    match user.get_shared_group(topic) {
+                        Some(group) => topic_filters.push(format!("$share/{}/{}", group, topic)),
+                        None => topic_filters.push(topic.to_string()),
+                    }
+                }
+                sessions.push(SessionInfo {
+                    client_id: user.get_username(),
+                    topics: topic_filters,
+                });
+            }
+        }
+        (subscriptions, sessions)
+    }
+
     pub fn clone_ref(&self) -> Self {
         Self {
             connected_users: self.connected_users.clone(),
-            available_packet_id: self.available_packet_id,
+            will_packet_ids: self.will_packet_ids.clone(),
             messages_by_topic: self.messages_by_topic.clone(),
+            config: self.config.clone(),
+            store: self.store.clone(),
+            shutting_down: self.shutting_down.clone(),
+            cluster: self.cluster.as_ref().map(|link| link.clone_ref()),
+            shared_counters: self.shared_counters.clone(),
             logger: self.logger.clone_ref(),
         }
     }
 
     /// Envía el will_message del user que se está desconectando, si tenía uno.
+    /// El packet_id del will message sólo se usa mientras se lo procesa: cada suscriptor lo recibe con un id propio.
     pub fn publish_users_will_message(&self, username: &str) -> Result<(), Error> {
-        let packet_id = 1000; // <-- aux: rever esto []: generate_packet_id requiere self mut, pero esto es multihilo, no tiene mucho sentido. Quizás un arc mutex u16, volver.
-        let mut will_message_option = None;
+        let packet_id = self
+            .will_packet_ids
+            .lock()
+            .map_err(|_| Error::other("Error al tomar lock de los packet_ids."))?
+            .allocate()?;
+        let mut will_message_res = Ok(None);
 
         // Obtengo el will_message, si había uno.
         if let Ok(users) = self.connected_users.lock() {
             if let Some(user) = users.get(username) {
-                will_message_option = user.get_publish_message_with(0, packet_id)?;
+                will_message_res = user.get_publish_message_with(0, packet_id);
             }
         }
 
         // Suelto el lock, para que pueda tomarlo la función a la que estoy a punto de llamar.
-        if let Some(will_message) = will_message_option {
-            self.handle_publish_message(&will_message)?;
+        let publish_res = match will_message_res {
+            Ok(Some(will_message)) => self.handle_publish_message(&will_message),
+            Ok(None) => Ok(()),
+            Err(e) => Err(e),
+        };
+        if let Ok(mut will_packet_ids) = self.will_packet_ids.lock() {
+            will_packet_ids.release(packet_id);
+        }
+        publish_res
+    }
+
+    /// Llegó el puback del user `client_id` para el publish que se le envió con `packet_id`.
+    pub fn release_packet_id_of(&self, client_id: &str, packet_id: u16) {
+        if let Ok(mut users) = self.connected_users.lock() {
+            if let Some(user) = users.get_mut(client_id) {
+                user.release_packet_id(packet_id);
+            }
         }
-        Ok(())
     }
 
     /// Procesa el PublishMessage: lo agrega al hashmap de su topic, y luego lo envía a los suscriptores de ese topic
     /// que estén conectados.
+    /// Si el servidor es parte de un cluster, además lo reenvía a los nodos que tengan suscriptores al topic.
     pub fn handle_publish_message(&self, msg: &PublishMessage) -> Result<(), Error> {
         self.store_and_distribute_publish_msg(msg)?;
         self.remove_old_messages_from_server(msg.get_topic())?;
+        if let Some(link) = &self.cluster {
+            link.forward_publish(msg);
+        }
+        Ok(())
+    }
+
+    /// Procesa un PublishMessage reenviado por otro nodo del cluster: igual que `handle_publish_message`,
+    /// pero sin volver a reenviarlo.
+    pub fn handle_cluster_publish_message(&self, msg: &PublishMessage) -> Result<(), Error> {
+        self.store_and_distribute_publish_msg(msg)?;
+        self.remove_old_messages_from_server(msg.get_topic())?;
+        Ok(())
+    }
+
+    /// Devuelve la versión del protocolo con la que se conectó el user `username`.
+    pub fn get_protocol_version_of(&self, username: &str) -> ProtocolVersion {
+        if let Ok(users) = self.connected_users.lock() {
+            if let Some(user) = users.get(username) {
+                return user.get_protocol_version();
+            }
+        }
+        ProtocolVersion::V311
+    }
+
+    /// Resuelve el topic alias (MQTT 5) del `msg` recibido del user `username`, si lo tiene.
+    pub fn resolve_topic_alias(&self, username: &str, msg: &mut PublishMessage) -> Result<(), Error> {
+        if let Ok(mut users) = self.connected_users.lock() {
+            if let Some(user) = users.get_mut(username) {
+                return user.resolve_topic_alias(msg);
+            }
+        }
         Ok(())
     }
 
@@ -243,7 +552,21 @@ impl MQTTServer {
         if let Ok(mut connected_users) = self.connected_users.lock() {
             if let Some(user) = connected_users.get_mut(username) {
                 for (topic, _qos) in msg.get_topic_filters() {
-                    user.add_topic(topic.to_string());
+                    if !self.is_allowed(username, AclAction::Subscribe, topic) {
+                        match user.get_protocol_version() {
+                            ProtocolVersion::V5 => return_codes.push(SubscribeReturnCode::NotAuthorized),
+                            ProtocolVersion::V311 => return_codes.push(SubscribeReturnCode::Failure),
+                        }
+                        self.logger.log(format!("Suscripción de {:?} a {:?} rechazada por acl.", username, topic));
+                        continue;
+                    }
+                    if let Some((group, shared_topic)) = parse_shared_subscription(topic) {
+                        // A una suscripción compartida no se le envían los mensajes anteriores.
+                        let last_id = self.get_topic_messages_len(&shared_topic);
+                        user.add_shared_topic(shared_topic, group, last_id);
+                    } else {
+                        user.add_topic(topic.to_string());
+                    }
                     return_codes.push(SubscribeReturnCode::QoS1);
                     println!(
                         "   Se agregó el topic {:?} al suscriptor {:?}",
@@ -255,6 +578,58 @@ impl MQTTServer {
         Ok(return_codes)
     }
 
+    /// Quita al suscriptor los topics del unsubscribe, y devuelve un reason code por cada uno.
+    pub fn remove_topics_from_subscriber(&self, username: &str, msg: &UnsubscribeMessage) -> Vec<ReasonCode> {
+        let mut reason_codes = vec![];
+        if let Ok(mut connected_users) = self.connected_users.lock() {
+            if let Some(user) = connected_users.get_mut(username) {
+                for topic_filter in msg.get_topics() {
+                    let topic = match parse_shared_subscription(topic_filter) {
+                        Some((_group, shared_topic)) => shared_topic,
+                        None => topic_filter.to_string(),
+                    };
+                    if user.remove_topic(&topic) {
+                        reason_codes.push(ReasonCode::Success);
+                        println!("   Se quitó el topic {:?} al suscriptor {:?}", topic, username);
+                    } else {
+                        reason_codes.push(ReasonCode::NoSubscriptionExisted);
+                    }
+                }
+            }
+        }
+        reason_codes
+    }
+
+    /// Envía un mensaje de tipo Unsuback al cliente.
+    pub fn send_unsuback_to(
+        &self,
+        client_id: &str,
+        packet_id: u16,
+        reason_codes: Vec<ReasonCode>,
+    ) -> Result<(), Error> {
+        let [msb, lsb] = packet_id.to_be_bytes();
+        let mut ack = Unsuback::new(msb, lsb);
+        if let Ok(mut connected_users) = self.connected_users.lock() {
+            if let Some(user) = connected_users.get_mut(client_id) {
+                if user.get_protocol_version().is_v5() {
+                    ack.set_mqtt5_properties(MqttProperties::new(), reason_codes);
+                }
+                user.write_message(&ack.to_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Responde con un pingresp el pingreq del cliente.
+    pub fn send_pingresp_to(&self, client_id: &str) -> Result<(), Error> {
+        if let Ok(mut connected_users) = self.connected_users.lock() {
+            if let Some(user) = connected_users.get_mut(client_id) {
+                user.write_message(&PingMessage::new_pingresp().to_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
     /// Envía un mensaje de tipo SubAck al cliente.
     pub fn send_suback_to(
         &self,
@@ -264,11 +639,13 @@ impl MQTTServer {
     ) -> Result<(), Error> {
         match return_codes_res {
             Ok(return_codes) => {
-                let ack = SubAckMessage::new(packet_id, return_codes.clone());
-                let ack_msg_bytes = ack.to_bytes();
+                let mut ack = SubAckMessage::new(packet_id, return_codes.clone());
                 if let Ok(mut connected_users_locked) = self.get_connected_users().lock() {
                     if let Some(user) = connected_users_locked.get_mut(client_id) {
-                        user.write_message(&ack_msg_bytes)?;
+                        if user.get_protocol_version().is_v5() {
+                            ack.set_mqtt5_properties(MqttProperties::new());
+                        }
+                        user.write_message(&ack.to_bytes())?;
                     }
                 }
                 println!("   tipo subscribe: Enviando el ack: {:?}", ack);
@@ -298,13 +675,11 @@ impl MQTTServer {
 
             // Se devuelve error en los demás casos.
             } else {
-                return Err(Error::new(
-                    ErrorKind::Other,
+                return Err(Error::other(
                     "Error: no se pudo tomar lock a messages_by_topic para almacenar y distribuir un Publish."));
             }
         } else {
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Error: no se pudo tomar lock a users para almacenar y distribuir un Publish.",
             ));
         }
@@ -324,26 +699,69 @@ impl MQTTServer {
 
     /// Devuelve si corresponde ejecutar la eliminación de mensajes anteriores de la estructura `topic_messages`.
     fn check_capacity(&self, topic_messages: &VecDeque<PublishMessage>) -> bool {
-        if topic_messages.len() > TOPIC_MESSAGES_LEN {
+        if topic_messages.len() > self.get_retention() {
             return true;
         }
         false
     }
 
+    fn get_retention(&self) -> usize {
+        if let Ok(config) = self.config.lock() {
+            return config.get_retention();
+        }
+        usize::MAX
+    }
+
     /// Envía a todos los suscriptores del topic `topic`, los mensajes que todavía no hayan recibido.
+    /// De cada grupo de suscripción compartida, sólo uno de sus miembros activos recibe los mensajes (por turnos);
+    /// al resto se le marcan como recibidos.
     fn send_msgs_to_subscribers(
         &self,
         topic: String,
         topic_messages: &VecDeque<PublishMessage>,
         users: &mut ValuesMut<'_, String, User>,
     ) -> Result<(), Error> {
+        let mut members_by_group: HashMap<String, Vec<&mut User>> = HashMap::new();
         // Recorremos todos los usuarios
         for user in users {
-            self.send_unreceived_messages(user, &topic, topic_messages)?;
+            match user.get_shared_group(&topic) {
+                Some(group) => members_by_group.entry(group.to_string()).or_default().push(user),
+                None => self.send_unreceived_messages(user, &topic, topic_messages)?,
+            }
+        }
+
+        for (group, mut members) in members_by_group {
+            members.sort_by_key(|member| member.get_username());
+            let active: Vec<usize> = (0..members.len())
+                .filter(|i| members[*i].get_state() == &UserState::Active)
+                .collect();
+            let chosen = if active.is_empty() {
+                None
+            } else {
+                Some(active[self.next_shared_turn(&group, &topic) % active.len()])
+            };
+            for (i, member) in members.iter_mut().enumerate() {
+                if Some(i) == chosen {
+                    self.send_unreceived_messages(member, &topic, topic_messages)?;
+                } else {
+                    member.update_last_id_by_topic(&topic, topic_messages.len() as u32);
+                }
+            }
         }
         Ok(())
     }
 
+    /// Devuelve el turno a usar para repartir el próximo mensaje de `topic` en el grupo `group`, y lo avanza.
+    fn next_shared_turn(&self, group: &str, topic: &str) -> usize {
+        if let Ok(mut counters) = self.shared_counters.lock() {
+            let counter = counters.entry(format!("{}/{}", group, topic)).or_insert(0);
+            let turn = *counter;
+            *counter = counter.wrapping_add(1);
+            return turn;
+        }
+        0
+    }
+
     // Remueve los mensajes antiguos de la estructuras de mensajes del topic `topic`, si la misma se encuentra cercana a una cierta capacidad fija.
     /// Para ello analiza primero el mínimo mensaje hasta el cual todos los usuarios conectados ya recibieron (el user `last_id``),
     /// borra hasta dicho mínimo, y luego actualiza la información de cada user (el user `last_id`) para que los índices sigan siendo consistentes.
@@ -366,13 +784,11 @@ impl MQTTServer {
                     }
                 }
             } else {
-                return Err(Error::new(
-                    ErrorKind::Other,
+                return Err(Error::other(
                     "Error: no se pudo tomar lock a messages_by_topic para remover elementos de la estructura para un topic."));
             }
         } else {
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Error: no se pudo tomar lock a users para recortar estructura de mensajes.",
             ));
         }
@@ -424,8 +840,7 @@ impl MQTTServer {
     ) -> Result<u32, Error> {
         let mut min_last_id = u32::MAX;
         if users.len() == 0 {
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Error grave: calculate_min_last_id_among_users_for, se está por calcular el mínimo con error, lista de users vacía."));
         }
 
@@ -470,24 +885,54 @@ impl MQTTServer {
 
     // Aux: esta función está comentada solo temporalmente mientras probamos algo, dsp se volverá a usar [].
     /// Envía un mensaje de tipo PubAck al cliente.
-    pub fn send_puback_to(&self, client_id: &str, msg: &PublishMessage) -> Result<(), Error> {
+    /// El `reason_code` sólo se envía a los clientes MQTT 5; los de 3.1.1 reciben siempre un puback exitoso,
+    /// por lo que un rechazo para ellos no debe pasar por acá (ver `reject_unauthorized_publish`).
+    pub fn send_puback_to(
+        &self,
+        client_id: &str,
+        msg: &PublishMessage,
+        reason_code: ReasonCode,
+    ) -> Result<(), Error> {
         let option_packet_id = msg.get_packet_id();
         let packet_id = option_packet_id.unwrap_or(0);
 
-        let ack = PubAckMessage::new(packet_id, 0);
-        let ack_msg_bytes = ack.to_bytes();
         if let Ok(mut connected_users_locked) = self.get_connected_users().lock() {
             if let Some(user) = connected_users_locked.get_mut(client_id) {
-                user.write_message(&ack_msg_bytes)?;
+                let ack = if user.get_protocol_version().is_v5() {
+                    let mut ack = PubAckMessage::new(packet_id, reason_code.to_byte());
+                    ack.set_mqtt5_properties(MqttProperties::new());
+                    ack
+                } else {
+                    PubAckMessage::new(packet_id, 0)
+                };
+                user.write_message(&ack.to_bytes())?;
             }
         }
         println!(
             "   tipo publish: Enviado el ack para packet_id: {:?}",
-            ack.get_packet_id()
+            packet_id
         );
         Ok(())
     }
 
+    /// Rechaza un publish que la acl no permite. A los clientes MQTT 5 se les responde con un puback NotAuthorized
+    /// (si el publish lleva packet_id). Los de 3.1.1 no pueden recibir un rechazo en el puback, por lo que se les
+    /// cierra la conexión, como indica el estándar.
+    pub fn reject_unauthorized_publish(&self, client_id: &str, msg: &PublishMessage) -> Result<(), Error> {
+        if self.get_protocol_version_of(client_id).is_v5() {
+            if msg.get_packet_id().is_some() {
+                self.send_puback_to(client_id, msg, ReasonCode::NotAuthorized)?;
+            }
+            return Ok(());
+        }
+        if let Ok(mut users) = self.connected_users.lock() {
+            if let Some(user) = users.get_mut(client_id) {
+                user.shutdown();
+            }
+        }
+        Ok(())
+    }
+
     /// Recorre la estructura de mensajes para el topic al que el suscriptor `username` se está suscribiendo con el `msg`,
     /// y le envía todos los mensajes que se publicaron a dicho topic previo a la suscripción.
     pub fn send_preexisting_msgs_to_new_subscriber(
@@ -508,14 +953,12 @@ impl MQTTServer {
                             }
                         }
                     } else {
-                        return Err(Error::new(
-                            ErrorKind::Other,
+                        return Err(Error::other(
                             "Error: no se pudo tomar lock a messages_by_topic para enviar Publish durante un Subscribe."));
                     }
                 }
             } else {
-                return Err(Error::new(
-                    ErrorKind::Other,
+                return Err(Error::other(
                     "Error: no se pudo tomar lock a users para enviar Publish durante un Subscribe."));
             }
         }
@@ -529,9 +972,12 @@ impl MQTTServer {
 
 /// Crea un servidor en la dirección ip y puerto especificados.
 fn create_server(ip: String, port: u16) -> Result<TcpListener, Error> {
-    let listener =
-        TcpListener::bind(format!("{}:{}", ip, port)).expect("Error al enlazar el puerto");
-    Ok(listener)
+    TcpListener::bind(format!("{}:{}", ip, port)).map_err(|e| {
+        Error::new(
+            e.kind(),
+            format!("Error al enlazar el puerto {}:{}: {:?}.", ip, port, e),
+        )
+    })
 }
 
 /// Verifica si el `user` está suscripto al `topic`. En caso afirmativo, devuelve en un Option la diferencia
@@ -552,8 +998,7 @@ fn check_subscription_and_calculate_diff(
         let user_last_id = user.get_last_id_by_topic(topic);
 
         if user_last_id > topic_server_last_id {
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Error grave: send_unreceived_messages, la resta estaba por dar negativa.",
             ));
         }
@@ -564,8 +1009,23 @@ fn check_subscription_and_calculate_diff(
     }
 }
 
-/// Envia al usuario `user` los mensajes del topic `topic` no recibidos.
-/// 
+/// Devuelve el session expiry interval (MQTT 5) indicado en el connect, si lo hay.
+fn get_session_expiry_interval(connect_msg: &ConnectMessage) -> Option<u32> {
+    connect_msg
+        .get_properties()
+        .and_then(|props| props.session_expiry_interval)
+}
+
+/// Crea el DisconnectMessage a enviarle a `user`: con el `reason_code` si se conectó con MQTT 5, o el de 3.1.1 si no.
+fn create_disconnect_msg_for(user: &User, reason_code: ReasonCode) -> DisconnectMessage {
+    match user.get_protocol_version() {
+        ProtocolVersion::V5 => DisconnectMessage::new_v5(reason_code, MqttProperties::new()),
+        ProtocolVersion::V311 => DisconnectMessage::new(),
+    }
+}
+
+/// Envia al usuario `user` los mensajes del topic `topic` no recibidos, en la versión de protocolo con la que se conectó.
+/// Los mensajes cuyo message expiry interval (MQTT 5) ya pasó se saltean.
 fn send_unreceived_messages_to_user(
     user: &mut User,
     topic: &String,
@@ -575,7 +1035,9 @@ fn send_unreceived_messages_to_user(
     for _ in 0..diff {
         let next_message_index = user.get_last_id_by_topic(topic);
         if let Some(msg) = topic_messages.get(next_message_index as usize) {
-            user.write_message(&msg.to_bytes())?;
+            if !msg.is_expired() {
+                user.write_publish(msg)?;
+            }
             user.update_last_id_by_topic(topic, next_message_index + 1);
         } else {
             println!("ERROR NO SE ENCUENTRA EL TOPIC_MSGS.GET(TOPIC) A ENVIAR!!!");
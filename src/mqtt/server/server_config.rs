@@ -0,0 +1,82 @@
+use std::{io::Error, path::Path};
+
+use super::{
+    acl::{Acl, AclAction},
+    file_helper::read_lines,
+    server_properties::ServerProperties,
+};
+
+/// Configuración del servidor que puede recargarse en caliente (ie al recibir SIGHUP),
+/// sin cortar las conexiones existentes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    credentials: Vec<(String, String)>,
+    acl: Option<Acl>,
+    retention: usize,
+}
+
+impl ServerConfig {
+    /// Carga credenciales, permisos y retención según lo indicado en `properties`.
+    /// Devuelve error si se configuró un archivo de acl que no puede leerse.
+    pub fn load(properties: &ServerProperties) -> Result<Self, Error> {
+        let acl = match properties.get_acl_file() {
+            Some(acl_file) => Some(Acl::from_file(acl_file)?),
+            None => None,
+        };
+        Ok(Self {
+            credentials: read_credentials_from_file(properties.get_credentials_file()),
+            acl,
+            retention: properties.get_retention(),
+        })
+    }
+
+    /// Configuración que no permite ninguna operación, para cuando la de `properties` no pudo cargarse.
+    pub fn deny_all(properties: &ServerProperties) -> Self {
+        Self {
+            credentials: read_credentials_from_file(properties.get_credentials_file()),
+            acl: Some(Acl::deny_all()),
+            retention: properties.get_retention(),
+        }
+    }
+
+    /// Verifica si el usuario y la contraseña proporcionados coinciden con alguna de las credenciales almacenadas.
+    pub fn verify_authentication(&self, user: Option<&String>, passwd: Option<&String>) -> bool {
+        if let (Some(u), Some(p)) = (user, passwd) {
+            self.credentials
+                .iter()
+                .any(|(username, password)| u == username && p == password)
+        } else {
+            false
+        }
+    }
+
+    /// Devuelve si `client_id` puede realizar `action` sobre `topic`. Si no se configuró un archivo de acl, todo está permitido.
+    pub fn is_allowed(&self, client_id: &str, action: AclAction, topic: &str) -> bool {
+        match &self.acl {
+            Some(acl) => acl.allows(client_id, action, topic),
+            None => true,
+        }
+    }
+
+    /// Devuelve la cantidad de mensajes por topic a partir de la cual se recorta la estructura.
+    pub fn get_retention(&self) -> usize {
+        self.retention
+    }
+}
+
+/// Lee las credenciales del archivo especificado y devuelve un vector de pares (usuario, contraseña)
+fn read_credentials_from_file(file_path: &str) -> Vec<(String, String)> {
+    let path = Path::new(file_path);
+    let mut credentials = Vec::new();
+
+    if let Ok(lines) = read_lines(path) {
+        for line in lines.map_while(Result::ok) {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() == 2 {
+                credentials.push((parts[0].to_string(), parts[1].to_string()));
+            }
+        }
+    }
+
+    credentials
+}
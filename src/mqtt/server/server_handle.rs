@@ -0,0 +1,50 @@
+use std::{
+    io::Error,
+    thread::JoinHandle,
+};
+
+use super::mqtt_server::MQTTServer;
+
+/// Handle de un servidor lanzado en otro hilo (ie con `MQTTServer::start_on_ephemeral_port`).
+/// Permite cerrarlo y esperar a que termine; si no se lo cierra explícitamente, se lo cierra al salir de scope.
+#[derive(Debug)]
+pub struct ServerHandle {
+    mqtt_server: MQTTServer,
+    thread_server: Option<JoinHandle<Result<(), Error>>>,
+}
+
+impl ServerHandle {
+    pub fn new(mqtt_server: MQTTServer, thread_server: JoinHandle<Result<(), Error>>) -> Self {
+        Self {
+            mqtt_server,
+            thread_server: Some(thread_server),
+        }
+    }
+
+    /// Devuelve una referencia al servidor que está corriendo.
+    pub fn server(&self) -> MQTTServer {
+        self.mqtt_server.clone_ref()
+    }
+
+    /// Cierra el servidor de forma ordenada, y espera a que termine su hilo.
+    /// Devuelve el resultado con el que terminó el servidor.
+    pub fn shutdown(mut self) -> Result<(), Error> {
+        self.stop_and_join()
+    }
+
+    fn stop_and_join(&mut self) -> Result<(), Error> {
+        self.mqtt_server.shutdown();
+        match self.thread_server.take() {
+            Some(thread_server) => thread_server.join().map_err(|_| {
+                Error::other("Error al esperar al hilo del servidor.")
+            })?,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        let _ = self.stop_and_join();
+    }
+}
@@ -0,0 +1,168 @@
+use std::io::{Error, ErrorKind};
+
+use crate::apps::properties::Properties;
+use crate::mqtt::bridge::bridge_properties::BridgeProperties;
+use crate::mqtt::server::cluster::cluster_properties::ClusterProperties;
+
+const DEFAULT_RETENTION: usize = 50;
+const DEFAULT_CREDENTIALS_FILE: &str = "credentials.txt";
+
+/// Propiedades del servidor, leídas desde el archivo de configuración (ie `message_broker_server_config.properties`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerProperties {
+    ip: String,
+    port: u16,
+    retention: usize, // Cantidad de mensajes a partir de la cual se intenta recortar la estructura de cada topic.
+    credentials_file: String,
+    acl_file: Option<String>,   // Sin él, no se restringen operaciones.
+    store_file: Option<String>, // Dónde guardar los mensajes al cerrarse; sin él, se pierden.
+    bridge: Option<BridgeProperties>,
+    cluster: Option<ClusterProperties>,
+}
+
+impl ServerProperties {
+    /// Lee las propiedades del servidor desde el archivo `properties_file`.
+    /// Las propiedades `ip` y `port` son obligatorias, las demás toman un valor por defecto si no se encuentran.
+    pub fn new(properties_file: &str) -> Result<Self, Error> {
+        let global_properties = Properties::new(properties_file)?;
+
+        let ip: String;
+        if let Some(prop) = global_properties.get("ip") {
+            ip = trim_quotes(prop);
+        } else {
+            println!("No se encontró la propiedad 'ip");
+            return Err(Error::other("Falta propiedad ip del servidor."));
+        }
+
+        let port: u16;
+        if let Some(prop) = global_properties.get("port") {
+            port = trim_quotes(prop)
+                .parse()
+                .map_err(|_| Error::new(ErrorKind::InvalidInput, "El puerto proporcionado no es válido"))?;
+        } else {
+            println!("No se encontró la propiedad 'port");
+            return Err(Error::other("Falta propiedad port del servidor."));
+        }
+
+        let mut retention = DEFAULT_RETENTION;
+        if let Some(prop) = global_properties.get("retention") {
+            retention = trim_quotes(prop)
+                .parse()
+                .map_err(|_| Error::new(ErrorKind::InvalidInput, "retention"))?;
+        }
+
+        let mut credentials_file = DEFAULT_CREDENTIALS_FILE.to_string();
+        if let Some(prop) = global_properties.get("credentials_file") {
+            credentials_file = trim_quotes(prop);
+        }
+
+        let acl_file = global_properties.get("acl_file").map(|prop| trim_quotes(prop));
+
+        let store_file = global_properties.get("store_file").map(|prop| trim_quotes(prop));
+
+        let bridge = BridgeProperties::from_properties(&global_properties)?;
+        let cluster = ClusterProperties::from_properties(&global_properties, &ip)?;
+
+        Ok(Self {
+            ip,
+            port,
+            retention,
+            credentials_file,
+            acl_file,
+            store_file,
+            bridge,
+            cluster,
+        })
+    }
+
+    /// Propiedades de un servidor local en un puerto efímero (`127.0.0.1:0`), con los valores por defecto
+    /// para lo demás. Es lo que usa `MQTTServer::start_on_ephemeral_port`.
+    pub fn ephemeral() -> Self {
+        Self {
+            ip: "127.0.0.1".to_string(),
+            port: 0,
+            retention: DEFAULT_RETENTION,
+            credentials_file: DEFAULT_CREDENTIALS_FILE.to_string(),
+            acl_file: None,
+            store_file: None,
+            bridge: None,
+            cluster: None,
+        }
+    }
+
+    /// Devuelve la ip y el puerto en los que debe correr el servidor.
+    pub fn get_address(&self) -> (String, u16) {
+        (self.ip.to_string(), self.port)
+    }
+
+    /// Devuelve la cantidad de mensajes por topic a partir de la cual se recorta la estructura.
+    pub fn get_retention(&self) -> usize {
+        self.retention
+    }
+
+    /// Devuelve la ruta al archivo de credenciales.
+    pub fn get_credentials_file(&self) -> &str {
+        &self.credentials_file
+    }
+
+    /// Devuelve la ruta al archivo de permisos (acl), si se configuró uno.
+    pub fn get_acl_file(&self) -> Option<&str> {
+        self.acl_file.as_deref()
+    }
+
+    /// Devuelve la ruta al archivo en el que se guardan los mensajes al cerrar el servidor, si se configuró uno.
+    pub fn get_store_file(&self) -> Option<&str> {
+        self.store_file.as_deref()
+    }
+
+    /// Devuelve las propiedades del bridge, si se configuró uno.
+    pub fn get_bridge_properties(&self) -> Option<&BridgeProperties> {
+        self.bridge.as_ref()
+    }
+
+    /// Devuelve las propiedades del cluster, si se configuró uno.
+    pub fn get_cluster_properties(&self) -> Option<&ClusterProperties> {
+        self.cluster.as_ref()
+    }
+}
+
+/// Quita las comillas que pueda tener el valor en el archivo (ie `ip="127.0.0.1"`).
+pub fn trim_quotes(value: &str) -> String {
+    value.trim().trim_matches('"').to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::ServerProperties;
+
+    #[test]
+    fn test_1_se_leen_ip_y_puerto_sin_comillas_y_valores_por_defecto() {
+        let path = "test_server_properties_1.properties";
+        fs::write(path, "ip=\"127.0.0.1\"\nport=\"9090\"\n").unwrap();
+
+        let props = ServerProperties::new(path);
+        let _ = fs::remove_file(path);
+
+        let props = props.unwrap();
+        assert_eq!(props.get_address(), ("127.0.0.1".to_string(), 9090));
+        assert_eq!(props.get_retention(), 50);
+        assert_eq!(props.get_credentials_file(), "credentials.txt");
+        assert!(props.get_acl_file().is_none());
+        assert!(props.get_store_file().is_none());
+        assert!(props.get_bridge_properties().is_none());
+        assert!(props.get_cluster_properties().is_none());
+    }
+
+    #[test]
+    fn test_2_puerto_invalido_da_error() {
+        let path = "test_server_properties_2.properties";
+        fs::write(path, "ip=127.0.0.1\nport=abc\n").unwrap();
+
+        let props = ServerProperties::new(path);
+        let _ = fs::remove_file(path);
+
+        assert!(props.is_err());
+    }
+}
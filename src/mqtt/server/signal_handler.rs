@@ -0,0 +1,60 @@
+use std::{io::Error, thread::JoinHandle};
+
+use signal_hook::{
+    consts::{SIGHUP, SIGINT, SIGTERM},
+    iterator::{Handle, Signals},
+};
+
+use crate::logging::string_logger::StringLogger;
+
+use super::{mqtt_server::MQTTServer, server_properties::ServerProperties};
+
+/// Atiende las señales del sistema operativo dirigidas al servidor:
+/// SIGINT y SIGTERM inician el cierre ordenado, SIGHUP recarga la configuración.
+#[derive(Debug)]
+pub struct SignalHandler {
+    handle: Handle,
+    thread: JoinHandle<()>,
+}
+
+impl SignalHandler {
+    /// Lanza un hilo que escucha las señales y opera sobre `mqtt_server`.
+    /// Al recibir SIGHUP, se vuelve a leer el archivo `properties_file`.
+    pub fn spawn(
+        mqtt_server: MQTTServer,
+        properties_file: String,
+        logger: StringLogger,
+    ) -> Result<Self, Error> {
+        let mut signals = Signals::new([SIGINT, SIGTERM, SIGHUP])?;
+        let handle = signals.handle();
+
+        let thread = std::thread::spawn(move || {
+            for signal in signals.forever() {
+                match signal {
+                    SIGHUP => {
+                        logger.log("Recibido SIGHUP, recargando configuración.".to_string());
+                        if let Err(e) = ServerProperties::new(&properties_file)
+                            .and_then(|properties| mqtt_server.reload_config(&properties))
+                        {
+                            logger.log(format!("Error al recargar la configuración: {:?}.", e));
+                        }
+                    }
+                    _ => {
+                        logger.log(format!("Recibida señal {}, cerrando el servidor.", signal));
+                        mqtt_server.shutdown();
+                    }
+                }
+            }
+        });
+
+        Ok(Self { handle, thread })
+    }
+
+    /// Deja de escuchar señales y espera al hilo.
+    pub fn stop(self) {
+        self.handle.close();
+        if self.thread.join().is_err() {
+            println!("Error al esperar al hilo de señales.");
+        }
+    }
+}
@@ -1,20 +1,26 @@
 use std::{
     collections::HashMap,
-    io::{Error, Write}, net::Shutdown,
+    io::{Error, ErrorKind, Write}, net::Shutdown,
+    time::{Duration, Instant},
 };
 
 use crate::mqtt::{
-    messages::{publish_flags::PublishFlags, publish_message::PublishMessage},
-    mqtt_utils::will_message_utils::will_message::WillMessageData,
+    messages::{
+        protocol_version::ProtocolVersion, publish_flags::PublishFlags,
+        publish_message::PublishMessage,
+    },
+    mqtt_utils::{packet_id_allocator::PacketIdAllocator, will_message_utils::will_message::WillMessageData},
     stream_type::StreamType,
 };
 
 use super::user_state::UserState;
 
+/// Cantidad máxima de topic aliases (MQTT 5) que el servidor acepta por conexión; se informa en el connack.
+pub const TOPIC_ALIAS_MAXIMUM: u16 = 10;
+
 /// Representa a un usuario (cliente) conectado al MQTTServer, del lado del servidor.
 #[derive(Debug)]
 #[allow(dead_code)]
-
 pub struct User {
     username: String, // se identifica por el username.
     stream: StreamType,
@@ -22,6 +28,12 @@ pub struct User {
     will_message: Option<WillMessageData>,
     topics: Vec<String>,                    // topics a los que esta suscripto
     last_id_by_topic: HashMap<String, u32>, // por cada topic tiene el ultimo id de mensaje enviado.
+    shared_groups: HashMap<String, String>, // por cada topic suscripto con `$share/grupo/topic`, su grupo.
+    protocol_version: ProtocolVersion,      // negociada en el connect.
+    topic_aliases: HashMap<u16, String>,    // MQTT 5: topic aliases de la conexión actual.
+    session_expiry_interval: Option<u32>,   // MQTT 5: segundos que se conserva la sesión tras desconectarse.
+    disconnected_at: Option<Instant>,
+    packet_ids: PacketIdAllocator,          // ids de los publish enviados al user que esperan su puback.
 }
 
 impl User {
@@ -38,9 +50,61 @@ impl User {
             will_message: will_msg_and_topic,
             topics: Vec::new(),
             last_id_by_topic: HashMap::new(),
+            shared_groups: HashMap::new(),
+            protocol_version: ProtocolVersion::V311,
+            topic_aliases: HashMap::new(),
+            session_expiry_interval: None,
+            disconnected_at: None,
+            packet_ids: PacketIdAllocator::new(),
+        }
+    }
+
+    /// Guarda la versión del protocolo y el session expiry interval negociados en el connect.
+    pub fn set_protocol(&mut self, version: ProtocolVersion, session_expiry_interval: Option<u32>) {
+        self.protocol_version = version;
+        self.session_expiry_interval = session_expiry_interval;
+    }
+
+    /// Devuelve la versión del protocolo con la que se conectó el user.
+    pub fn get_protocol_version(&self) -> ProtocolVersion {
+        self.protocol_version
+    }
+
+    /// Devuelve si, estando desconectado, ya pasó el session expiry interval del user.
+    /// Las sesiones de MQTT 3.1.1 no expiran; en MQTT 5, si no se indicó intervalo, la sesión termina al desconectarse.
+    pub fn is_session_expired(&self) -> bool {
+        if !self.protocol_version.is_v5() {
+            return false;
+        }
+        match self.disconnected_at {
+            Some(disconnected_at) => {
+                let interval = Duration::from_secs(self.session_expiry_interval.unwrap_or(0) as u64);
+                disconnected_at.elapsed() >= interval
+            }
+            None => false,
         }
     }
 
+    /// Resuelve el topic alias (MQTT 5) del `msg` recibido de este user: si el mensaje trae topic, lo asocia al alias;
+    /// si no, le asigna el topic previamente asociado. Luego le quita el alias, que sólo vale para esta conexión.
+    pub fn resolve_topic_alias(&mut self, msg: &mut PublishMessage) -> Result<(), Error> {
+        let Some(alias) = msg.get_topic_alias() else {
+            return Ok(());
+        };
+        if alias == 0 || alias > TOPIC_ALIAS_MAXIMUM {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Topic alias inválido: {}.", alias)));
+        }
+        if msg.get_topic().is_empty() {
+            let topic = self.topic_aliases.get(&alias).ok_or_else(|| {
+                Error::new(ErrorKind::InvalidData, format!("Topic alias desconocido: {}.", alias))
+            })?;
+            msg.set_topic(topic);
+        } else {
+            self.topic_aliases.insert(alias, msg.get_topic());
+        }
+        msg.remove_topic_alias();
+        Ok(())
+    }
     /// Devuelve si el user no está desconectado.
     fn is_not_disconnected(&self) -> bool {
         self.state != UserState::TemporallyDisconnected
@@ -90,13 +154,20 @@ impl User {
         &self.topics
     }
 
-    /// Se guarda el nuevo stream, después de una reconexión.
+    /// Se guarda el nuevo stream, después de una reconexión. Los topic aliases de la conexión anterior dejan de valer.
     pub fn update_stream_with(&mut self, new_stream: StreamType) {
-        self.stream = new_stream
+        self.stream = new_stream;
+        self.topic_aliases.clear();
+        // Los pubacks pendientes de la conexión anterior ya no llegarán.
+        self.packet_ids.release_all();
     }
 
-    /// Setea el estado del user.
+    /// Setea el estado del user. Si pasa a estar desconectado, registra el momento, para la expiración de la sesión.
     pub fn set_state(&mut self, state: UserState) {
+        self.disconnected_at = match state {
+            UserState::TemporallyDisconnected => Some(Instant::now()),
+            UserState::Active => None,
+        };
         self.state = state;
     }
 
@@ -107,6 +178,49 @@ impl User {
         self.last_id_by_topic.entry(topic).or_insert(0);
     }
 
+    /// Agrega el topic a los topics a los que user está suscripto, como miembro del grupo compartido `group`.
+    /// Sólo recibirá los mensajes que se publiquen a partir de `last_id`, repartidos con el resto del grupo.
+    pub fn add_shared_topic(&mut self, topic: String, group: String, last_id: u32) {
+        self.topics.push(topic.clone());
+        self.last_id_by_topic.insert(topic.clone(), last_id);
+        self.shared_groups.insert(topic, group);
+    }
+
+    /// Quita el topic de los topics a los que user está suscripto. Devuelve si estaba suscripto.
+    pub fn remove_topic(&mut self, topic: &str) -> bool {
+        let was_subscribed = self.topics.iter().any(|t| t == topic);
+        self.topics.retain(|t| t != topic);
+        self.last_id_by_topic.remove(topic);
+        self.shared_groups.remove(topic);
+        was_subscribed
+    }
+
+    /// Devuelve el grupo compartido con el que user está suscripto al topic `topic`, si lo hay.
+    pub fn get_shared_group(&self, topic: &str) -> Option<&String> {
+        self.shared_groups.get(topic)
+    }
+
+    /// Escribe el publish `msg` por el stream hacia el cliente, en la versión de protocolo con la que se conectó.
+    /// Si tiene qos 1, se envía con un packet_id propio de la sesión del user, que se libera al recibir su puback.
+    pub fn write_publish(&mut self, msg: &PublishMessage) -> Result<(), Error> {
+        if msg.get_qos() == 0 || !self.is_not_disconnected() {
+            return self.write_message(&msg.to_bytes_for(self.protocol_version));
+        }
+        let packet_id = self.packet_ids.allocate()?;
+        let mut msg = msg.clone();
+        msg.set_packet_id(packet_id);
+        let write_res = self.write_message(&msg.to_bytes_for(self.protocol_version));
+        if write_res.is_err() {
+            self.packet_ids.release(packet_id);
+        }
+        write_res
+    }
+
+    /// Llegó el puback del publish enviado con `packet_id`, que ya puede volver a usarse.
+    pub fn release_packet_id(&mut self, packet_id: u16) {
+        self.packet_ids.release(packet_id);
+    }
+
     /// Escribe el mensaje en bytes `msg_bytes` por el stream hacia el cliente.
     /// Puede devolver error si falla la escritura o el flush.
     pub fn write_message(&mut self, msg_bytes: &[u8]) -> Result<(), Error> {
@@ -1,3 +1,75 @@
-use std::net::TcpStream;
+use std::{
+    io::{Error, Read, Write},
+    net::{Shutdown, TcpStream},
+    time::Duration,
+};
 
-pub type StreamType = TcpStream; // Aux: que lo use solo el server por ahora, así es más fácil hacer un refactor dsp-
+use super::memory_stream::MemoryStream;
+
+/// Stream por el que se comunican clientes y servidor: un socket tcp, o un stream en memoria
+/// cuando ambos corren en el mismo proceso (ver `MQTTServer::connect_in_memory`).
+#[derive(Debug)]
+pub enum StreamType {
+    Tcp(TcpStream),
+    Memory(MemoryStream),
+}
+
+impl StreamType {
+    pub fn try_clone(&self) -> Result<Self, Error> {
+        match self {
+            StreamType::Tcp(stream) => Ok(StreamType::Tcp(stream.try_clone()?)),
+            StreamType::Memory(stream) => Ok(StreamType::Memory(stream.try_clone()?)),
+        }
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> Result<(), Error> {
+        match self {
+            StreamType::Tcp(stream) => stream.shutdown(how),
+            StreamType::Memory(stream) => stream.shutdown(how),
+        }
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        match self {
+            StreamType::Tcp(stream) => stream.set_read_timeout(timeout),
+            StreamType::Memory(stream) => stream.set_read_timeout(timeout),
+        }
+    }
+}
+
+impl From<TcpStream> for StreamType {
+    fn from(stream: TcpStream) -> Self {
+        StreamType::Tcp(stream)
+    }
+}
+
+impl From<MemoryStream> for StreamType {
+    fn from(stream: MemoryStream) -> Self {
+        StreamType::Memory(stream)
+    }
+}
+
+impl Read for StreamType {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        match self {
+            StreamType::Tcp(stream) => stream.read(buf),
+            StreamType::Memory(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for StreamType {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        match self {
+            StreamType::Tcp(stream) => stream.write(buf),
+            StreamType::Memory(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        match self {
+            StreamType::Tcp(stream) => stream.flush(),
+            StreamType::Memory(stream) => stream.flush(),
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::{
+    fmt::Debug,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Fuente de tiempo de los componentes que esperan o miden plazos (vuelo y batería de los drones,
+/// timers de retransmisión del cliente MQTT). Permite reemplazar el tiempo real por uno simulado en los tests.
+pub trait Clock: Debug + Send + Sync {
+    /// Devuelve el instante actual según este reloj.
+    fn now(&self) -> Instant;
+
+    /// Bloquea el hilo actual hasta que, según este reloj, haya pasado `duration`.
+    fn sleep(&self, duration: Duration);
+}
+
+/// Reloj compartido entre los componentes y sus hilos.
+pub type ShClock = Arc<dyn Clock>;
+
+/// Reloj del sistema, el que se usa fuera de los tests.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl SystemClock {
+    /// Devuelve el reloj del sistema, listo para compartir.
+    pub fn shared() -> ShClock {
+        Arc::new(SystemClock)
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod clock;
+pub mod simulated_clock;
@@ -0,0 +1,137 @@
+use std::{
+    sync::{Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use super::clock::Clock;
+
+#[derive(Debug, Default)]
+struct SimulatedTime {
+    elapsed: Duration,
+    sleeping: usize, // Cantidad de hilos esperando en `sleep`.
+}
+
+/// Reloj simulado, que sólo avanza cuando se lo pide con `advance`. Los hilos que llaman a `sleep`
+/// quedan bloqueados hasta que el reloj alcance su plazo, sin importar cuánto tiempo real pase.
+/// Sirve para correr escenarios de drones y cámaras en forma determinística y sin esperas reales.
+#[derive(Debug)]
+pub struct SimulatedClock {
+    start: Instant,
+    time: Mutex<SimulatedTime>,
+    time_changed: Condvar,
+}
+
+impl SimulatedClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            time: Mutex::new(SimulatedTime::default()),
+            time_changed: Condvar::new(),
+        }
+    }
+
+    /// Avanza el reloj `duration`, despertando a los hilos cuyo plazo se cumplió.
+    pub fn advance(&self, duration: Duration) {
+        if let Ok(mut time) = self.time.lock() {
+            time.elapsed += duration;
+        }
+        self.time_changed.notify_all();
+    }
+
+    /// Devuelve cuánto avanzó el reloj desde que se creó.
+    pub fn elapsed(&self) -> Duration {
+        self.lock_time().map(|time| time.elapsed).unwrap_or_default()
+    }
+
+    /// Devuelve cuántos hilos están bloqueados en `sleep`.
+    pub fn sleeping_threads(&self) -> usize {
+        self.lock_time().map(|time| time.sleeping).unwrap_or_default()
+    }
+
+    /// Espera, en tiempo real y como mucho `timeout`, a que haya al menos `count` hilos bloqueados en `sleep`.
+    /// Devuelve si se alcanzó esa cantidad. Permite avanzar el reloj recién cuando todos los hilos llegaron a su espera.
+    pub fn wait_for_sleeping_threads(&self, count: usize, timeout: Duration) -> bool {
+        let Some(time) = self.lock_time() else {
+            return false;
+        };
+        match self
+            .time_changed
+            .wait_timeout_while(time, timeout, |time| time.sleeping < count)
+        {
+            Ok((time, _)) => time.sleeping >= count,
+            Err(_) => false,
+        }
+    }
+
+    fn lock_time(&self) -> Option<MutexGuard<'_, SimulatedTime>> {
+        self.time.lock().ok()
+    }
+}
+
+impl Default for SimulatedClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        if duration.is_zero() {
+            return;
+        }
+        let Some(mut time) = self.lock_time() else {
+            return;
+        };
+        let deadline = time.elapsed + duration;
+        time.sleeping += 1;
+        self.time_changed.notify_all();
+
+        if let Ok(mut time) = self
+            .time_changed
+            .wait_while(time, |time| time.elapsed < deadline)
+        {
+            time.sleeping -= 1;
+        }
+        self.time_changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, thread, time::Duration};
+
+    use super::SimulatedClock;
+    use crate::time::clock::Clock;
+
+    #[test]
+    fn test_1_sleep_termina_solo_al_avanzar_el_reloj_hasta_su_plazo() {
+        let clock = Arc::new(SimulatedClock::new());
+        let start = clock.now();
+
+        let clock_c = clock.clone();
+        let sleeper = thread::spawn(move || clock_c.sleep(Duration::from_secs(3600)));
+
+        assert!(clock.wait_for_sleeping_threads(1, Duration::from_secs(5)));
+        clock.advance(Duration::from_secs(1800));
+        thread::sleep(Duration::from_millis(20));
+        assert!(!sleeper.is_finished());
+        assert_eq!(clock.sleeping_threads(), 1);
+
+        clock.advance(Duration::from_secs(1800));
+        sleeper.join().unwrap();
+        assert_eq!(clock.sleeping_threads(), 0);
+        assert_eq!(clock.now() - start, Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn test_2_sleep_de_cero_no_bloquea() {
+        let clock = SimulatedClock::new();
+        clock.sleep(Duration::ZERO);
+        assert_eq!(clock.elapsed(), Duration::ZERO);
+        assert!(!clock.wait_for_sleeping_threads(1, Duration::from_millis(10)));
+    }
+}
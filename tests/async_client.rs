@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+mod common;
+use common::{create_logger, Broker};
+
+use rustx::mqtt::client::{async_mqtt_client::AsyncMqttClient, mqtt_client::MQTTClient};
+use rustx::mqtt::messages::mqtt_properties::MqttProperties;
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn test_1_cliente_async_publica_se_suscribe_y_se_desuscribe() {
+    let broker = Broker::start("async_client");
+    let logger = create_logger();
+
+    let (subscriber, mut incoming) =
+        AsyncMqttClient::connect("async-sub".to_string(), &broker.addr, None, logger.clone_ref())
+            .await
+            .unwrap();
+    subscriber.subscribe(vec![("dron".to_string(), 1)]).await.unwrap();
+
+    let (publisher, _publisher_incoming) =
+        AsyncMqttClient::connect("async-pub".to_string(), &broker.addr, None, logger.clone_ref())
+            .await
+            .unwrap();
+    // Los publish pueden estar en curso a la vez.
+    let (first, second) = tokio::join!(
+        publisher.publish("dron", &[1], 1),
+        publisher.publish("dron", &[2], 1)
+    );
+    assert_ne!(first.unwrap().get_packet_id(), second.unwrap().get_packet_id());
+
+    let mut received = vec![];
+    for _ in 0..2 {
+        let msg = timeout(Duration::from_secs(10), incoming.next()).await.unwrap().unwrap();
+        assert_eq!(msg.get_topic(), "dron");
+        received.push(msg.get_payload()[0]);
+    }
+    received.sort();
+    assert_eq!(received, vec![1, 2]);
+
+    // Tras desuscribirse, ya no recibe lo publicado al topic.
+    subscriber.unsubscribe(vec!["dron".to_string()]).await.unwrap();
+    publisher.publish("dron", &[3], 1).await.unwrap();
+    assert!(timeout(Duration::from_millis(500), incoming.next()).await.is_err());
+
+    // Al desconectarse, termina el stream.
+    subscriber.disconnect().await.unwrap();
+    assert!(timeout(Duration::from_secs(5), incoming.next()).await.unwrap().is_none());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_2_cliente_async_v5_recibe_de_un_cliente_sincronico() {
+    let broker = Broker::start("async_client_sync_interop");
+    let logger = create_logger();
+
+    let (subscriber, mut incoming) = AsyncMqttClient::connect_v5(
+        "async-v5-sub".to_string(),
+        &broker.addr,
+        None,
+        MqttProperties::new(),
+        logger.clone_ref(),
+    )
+    .await
+    .unwrap();
+    assert!(subscriber.get_protocol_version().is_v5());
+    subscriber.subscribe(vec![("inc".to_string(), 1)]).await.unwrap();
+
+    // El cliente sincrónico bloquea, así que se lo usa fuera del runtime.
+    let addr = broker.addr;
+    tokio::task::spawn_blocking(move || {
+        let (mut publisher, _rx, _h) =
+            MQTTClient::mqtt_connect_to_broker("sync-pub".to_string(), &addr, None, logger).unwrap();
+        publisher.mqtt_publish("inc", b"hola", 1).unwrap();
+    })
+    .await
+    .unwrap();
+
+    let msg = timeout(Duration::from_secs(10), incoming.recv()).await.unwrap().unwrap();
+    assert_eq!(msg.get_payload(), b"hola");
+}
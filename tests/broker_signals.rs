@@ -0,0 +1,111 @@
+use std::{
+    fs, thread,
+    time::{Duration, Instant},
+};
+
+mod common;
+use common::{create_logger, Broker};
+
+use rustx::mqtt::client::{credentials::Credentials, mqtt_client::MQTTClient};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Publica `payload` en `topic` con qos 1, como el usuario por defecto de las apps.
+fn publish(broker: &Broker, client_id: &str, topic: &str, payload: &[u8]) {
+    let (mut client, _rx, _handle) = MQTTClient::mqtt_connect_to_broker(
+        client_id.to_string(),
+        &broker.addr,
+        None,
+        create_logger(),
+    )
+    .unwrap();
+    client.mqtt_publish(topic, payload, 1).unwrap();
+    client.mqtt_disconnect().unwrap();
+}
+
+/// Se cierra el broker con `signal`, se verifica que termine bien, y al volver a levantarlo un suscriptor
+/// que llega tarde recibe el mensaje publicado antes del cierre.
+fn assert_store_survives(name: &str, signal: &str) {
+    let mut broker = Broker::start_with(name, common::free_port(), "store_file=\"store.json\"\n");
+    publish(&broker, "publicador", "inc", &[4, 2]);
+
+    broker.signal(signal);
+    assert!(broker.wait_for_exit(TIMEOUT).success());
+    assert!(broker.dir.join("store.json").exists());
+
+    broker.restart();
+    let (mut late, late_rx, _handle) = MQTTClient::mqtt_connect_to_broker(
+        "tardio".to_string(),
+        &broker.addr,
+        None,
+        create_logger(),
+    )
+    .unwrap();
+    late.mqtt_subscribe(vec![("inc".to_string(), 1)]).unwrap();
+    assert_eq!(
+        late_rx.recv_timeout(TIMEOUT).unwrap().get_payload(),
+        vec![4, 2]
+    );
+}
+
+#[test]
+fn test_1_con_sigterm_se_cierra_y_guarda_los_mensajes() {
+    assert_store_survives("signals_sigterm", "TERM");
+}
+
+#[test]
+fn test_2_con_sigint_se_cierra_y_guarda_los_mensajes() {
+    assert_store_survives("signals_sigint", "INT");
+}
+
+#[test]
+fn test_3_con_sighup_se_recargan_las_credenciales_y_el_acl() {
+    // Como el archivo de acl configurado todavía no existe, al principio no se permite ninguna operación.
+    let mut broker = Broker::start_with(
+        "signals_sighup",
+        common::free_port(),
+        "acl_file=\"acl.txt\"\n",
+    );
+    let connect_nuevo = |broker: &Broker| {
+        MQTTClient::mqtt_connect_to_broker_with_credentials(
+            "nuevo".to_string(),
+            &broker.addr,
+            None,
+            Credentials::new("nuevo", "clave"),
+            create_logger(),
+        )
+    };
+    assert!(connect_nuevo(&broker).is_err());
+
+    let credentials = fs::read_to_string(broker.dir.join("credentials.txt")).unwrap();
+    fs::write(
+        broker.dir.join("credentials.txt"),
+        format!("{}\nnuevo clave\n", credentials),
+    )
+    .unwrap();
+    fs::write(broker.dir.join("acl.txt"), "* all inc\n").unwrap();
+    broker.signal("HUP");
+
+    // La recarga se hace en el hilo de señales: se reintenta hasta que tome efecto.
+    let start = Instant::now();
+    let (mut nuevo, nuevo_rx, _handle) = loop {
+        match connect_nuevo(&broker) {
+            Ok(connection) => break connection,
+            Err(_) => assert!(
+                start.elapsed() < TIMEOUT,
+                "No se recargaron las credenciales."
+            ),
+        }
+        thread::sleep(Duration::from_millis(100));
+    };
+    nuevo.mqtt_subscribe(vec![("inc".to_string(), 1)]).unwrap();
+    publish(&broker, "publicador", "inc", &[7]);
+    assert_eq!(
+        nuevo_rx.recv_timeout(TIMEOUT).unwrap().get_payload(),
+        vec![7]
+    );
+
+    // La recarga no cerró el broker, que sigue cerrándose de forma ordenada.
+    broker.signal("TERM");
+    assert!(broker.wait_for_exit(TIMEOUT).success());
+}
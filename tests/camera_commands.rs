@@ -0,0 +1,355 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs, io,
+    path::Path,
+    sync::{
+        mpsc::Receiver,
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+mod common;
+use common::create_logger;
+
+use rustx::apps::{
+    apps_mqtt_topics::AppsMqttTopics,
+    sist_camaras::{
+        ai_detection::{ai_provider::AiProvider, properties::DetectorProperties},
+        camera::{Camera, CameraSystemId, DEFAULT_CAMERA_SYSTEM_ID},
+        camera_command::{CameraCommand, CameraCommandReply, CameraCommandRequest},
+        camera_fov::CameraFov,
+        bordering::{BorderingPolicy, SIST_CAMARAS_PROPERTIES_FILE},
+        camera_registry::{json_file_registry::JsonFileCameraRegistry, registry::CameraRegistry},
+        manage_stored_cameras::{load_cameras, CAMERAS_PROPERTIES_FILE},
+        sistema_camaras::SistemaCamaras,
+    },
+};
+use rustx::mqtt::{
+    client::mqtt_client::MQTTClient,
+    server::{mqtt_server::MQTTServer, server_properties::ServerProperties},
+};
+use rustx::time::clock::SystemClock;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+// Sólo `admin` puede enviar comandos; `intruso` no.
+const ACL: &str = "Sistema-Camaras all cam
+Sistema-Camaras all inc
+Sistema-Camaras sub cam/cmd
+Sistema-Camaras pub cam/cmd/respuestas/admin
+Sistema-Camaras pub cam/cmd/respuestas/intruso
+Sistema-Camaras pub inc/activos
+Sistema-Camaras sub inc/activos/respuestas/Sistema-Camaras
+Sistema-Camaras-2 all cam
+Sistema-Camaras-2 all inc
+Sistema-Camaras-2 sub cam/cmd/2
+Sistema-Camaras-2 pub cam/cmd/respuestas/admin
+Sistema-Camaras-2 pub inc/activos
+Sistema-Camaras-2 sub inc/activos/respuestas/Sistema-Camaras-2
+admin pub cam/cmd
+admin pub cam/cmd/2
+admin sub cam/cmd/respuestas/admin
+intruso sub cam/cmd/respuestas/intruso
+";
+
+/// Proveedor de ia simulado, que nunca encuentra incidentes.
+#[derive(Debug)]
+struct NoIncidentsAiProvider;
+
+impl AiProvider for NoIncidentsAiProvider {
+    fn analyze_image(&self, _image: Vec<u8>) -> Result<String, Box<dyn Error>> {
+        Ok(r#"{"predictions": []}"#.to_string())
+    }
+}
+
+/// Servidor en memoria, con el acl de `ACL` guardado dentro de `dir`.
+fn create_server(dir: &Path) -> MQTTServer {
+    let acl_file = dir.join("acl.txt");
+    fs::write(&acl_file, ACL).unwrap();
+    let properties_file = dir.join("server.properties");
+    fs::write(
+        &properties_file,
+        format!("ip=127.0.0.1\nport=0\nacl_file={}\n", acl_file.display()),
+    )
+    .unwrap();
+    let properties = ServerProperties::new(properties_file.to_str().unwrap()).unwrap();
+    MQTTServer::new(create_logger(), &properties)
+}
+
+fn connect(client_id: &str, server: &MQTTServer) -> MQTTClient {
+    let (mqtt_client, _publish_msg_rx, _handle) = MQTTClient::mqtt_connect_over_stream(
+        client_id.to_string(),
+        server.connect_in_memory().unwrap(),
+        None,
+        SystemClock::shared(),
+        create_logger(),
+    )
+    .unwrap();
+    mqtt_client
+}
+
+fn create_detector_properties(dir: &Path) -> DetectorProperties {
+    create_detector_properties_in(dir, "image_detection")
+}
+
+/// Propiedades del detector, con sus directorios de imágenes en `dir/images_dir`.
+fn create_detector_properties_in(dir: &Path, images_dir: &str) -> DetectorProperties {
+    let properties_file = dir.join("detector.properties");
+    fs::write(
+        &properties_file,
+        format!(
+            "base_dir={}\napi_credentials_file_path=no_usado.json\ninc_tag=incidente\ninc_threshold=0.7\n\
+             img_valid_extension1=jpg\nimg_valid_extension2=jpeg\n",
+            dir.join(images_dir).display()
+        ),
+    )
+    .unwrap();
+    DetectorProperties::new(properties_file.to_str().unwrap()).unwrap()
+}
+
+/// Publica el pedido al sistema de cámaras por defecto, y espera la respuesta con su mismo correlation id.
+fn request(
+    admin: &mut MQTTClient,
+    replies_rx: &Receiver<Result<CameraCommandReply, io::Error>>,
+    correlation_id: &str,
+    command: CameraCommand,
+) -> Result<Vec<Camera>, String> {
+    request_to(admin, DEFAULT_CAMERA_SYSTEM_ID, replies_rx, correlation_id, command)
+}
+
+/// Publica el pedido al sistema de cámaras `system_id`, y espera la respuesta con su mismo correlation id.
+fn request_to(
+    admin: &mut MQTTClient,
+    system_id: CameraSystemId,
+    replies_rx: &Receiver<Result<CameraCommandReply, io::Error>>,
+    correlation_id: &str,
+    command: CameraCommand,
+) -> Result<Vec<Camera>, String> {
+    let reply_topic = AppsMqttTopics::camera_command_replies("admin");
+    let request = CameraCommandRequest::new(correlation_id, reply_topic.get_name(), command);
+    admin
+        .mqtt_publish_typed(&AppsMqttTopics::camera_commands_of(system_id), &request, 1)
+        .unwrap();
+
+    // La respuesta puede llegar en varias páginas, con el mismo correlation id.
+    let mut cameras = vec![];
+    let mut pages_received = 0;
+    let start = Instant::now();
+    while start.elapsed() < TIMEOUT {
+        if let Ok(Ok(reply)) = replies_rx.recv_timeout(Duration::from_millis(200)) {
+            if reply.get_correlation_id() != correlation_id {
+                continue;
+            }
+            assert_eq!(reply.get_page(), pages_received, "Página fuera de orden.");
+            cameras.extend(reply.get_result().clone()?);
+            pages_received += 1;
+            if pages_received == reply.get_pages() {
+                return Ok(cameras);
+            }
+        }
+    }
+    panic!("No se recibió la respuesta al pedido {}.", correlation_id);
+}
+
+fn ids_of(cameras: &[Camera]) -> Vec<u32> {
+    cameras.iter().map(|camera| camera.get_id()).collect()
+}
+
+#[test]
+fn test_comandos_por_mqtt_administran_las_camaras_y_se_responden_al_que_pide() {
+    let dir = std::env::temp_dir().join(format!("rustx_camera_commands_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let server = create_server(&dir);
+
+    // Sistema cámaras, sin abm por consola
+    let mut cameras = HashMap::new();
+    cameras.insert(1, Camera::new(1, -34.6090, -58.3873, 250.0));
+    let registry = JsonFileCameraRegistry::new(dir.join("cameras.json"));
+    let mut sistema_camaras = SistemaCamaras::new(Arc::new(Mutex::new(cameras)), create_logger());
+    let (_children, exit_tx) = sistema_camaras.spawn_threads_without_abm(
+        connect("Sistema-Camaras", &server),
+        Box::new(registry.clone()),
+        create_detector_properties(&dir),
+        Arc::new(NoIncidentsAiProvider),
+    );
+
+    // Un cliente sin permiso envía un alta, que el broker rechaza
+    let mut intruso = connect("intruso", &server);
+    let intruso_replies_rx = intruso
+        .mqtt_subscribe_typed(&AppsMqttTopics::camera_command_replies("intruso"), 1)
+        .unwrap();
+    let add_20 = CameraCommand::Add {
+        id: 20,
+        latitude: -34.6,
+        longitude: -58.4,
+        range: 250.0,
+        fov: CameraFov::default(),
+    };
+    let reply_topic = AppsMqttTopics::camera_command_replies("intruso");
+    // El acl no se lo permite: como se conecta con MQTT 3.1.1, el servidor le cierra la conexión.
+    let _ = intruso.mqtt_publish_typed(
+        &AppsMqttTopics::camera_commands(),
+        &CameraCommandRequest::new("intruso-1", reply_topic.get_name(), add_20),
+        1,
+    );
+
+    // El operador administra las cámaras
+    let mut admin = connect("admin", &server);
+    let replies_rx = admin
+        .mqtt_subscribe_typed(&AppsMqttTopics::camera_command_replies("admin"), 1)
+        .unwrap();
+
+    let add_2 = CameraCommand::Add {
+        id: 2,
+        latitude: -34.6095,
+        longitude: -58.3878,
+        range: 250.0,
+        fov: CameraFov::default(),
+    };
+    let added = request(&mut admin, &replies_rx, "1", add_2.clone()).unwrap();
+    assert_eq!(ids_of(&added), vec![2]);
+    assert!(request(&mut admin, &replies_rx, "2", add_2).is_err());
+
+    let modify_2 = CameraCommand::Modify {
+        id: 2,
+        latitude: -34.7,
+        longitude: -58.5,
+        range: 400.0,
+        fov: CameraFov::new(45.0, 90.0, Some(200.0)).unwrap(),
+    };
+    let modified = request(&mut admin, &replies_rx, "3", modify_2).unwrap();
+    assert_eq!(modified[0].get_position(), (-34.7, -58.5));
+    assert_eq!(modified[0].get_coverage_distance(), 200.0);
+
+    let listed = request(&mut admin, &replies_rx, "4", CameraCommand::List).unwrap();
+    assert_eq!(ids_of(&listed), vec![1, 2]);
+
+    let deleted = request(
+        &mut admin,
+        &replies_rx,
+        "5",
+        CameraCommand::Delete { id: 2 },
+    )
+    .unwrap();
+    assert_eq!(ids_of(&deleted), vec![2]);
+    assert!(request(
+        &mut admin,
+        &replies_rx,
+        "6",
+        CameraCommand::Delete { id: 2 }
+    )
+    .is_err());
+
+    let listed = request(&mut admin, &replies_rx, "7", CameraCommand::List).unwrap();
+    assert_eq!(ids_of(&listed), vec![1]);
+
+    // El pedido del intruso no se ejecutó ni se respondió
+    assert!(intruso_replies_rx.try_recv().is_err());
+
+    // Los cambios quedaron en el registro
+    let records = registry.load().unwrap().unwrap();
+    assert!(records.iter().any(|r| r.get_id() == 2 && r.is_deleted()));
+    assert!(!records.iter().any(|r| r.get_id() == 20));
+
+    let _ = exit_tx.send(true);
+    server.shutdown();
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_cada_sistema_de_camaras_atiende_sus_comandos_y_publica_su_id() {
+    let dir = std::env::temp_dir().join(format!("rustx_camera_systems_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let server = create_server(&dir);
+
+    // Dos sistemas de cámaras, ambos con una cámara 1
+    let mut exit_txs = vec![];
+    for system_id in [DEFAULT_CAMERA_SYSTEM_ID, 2] {
+        let mut cameras = HashMap::new();
+        cameras.insert(1, Camera::new(1, -34.6090, -58.3873, 250.0));
+        let registry = JsonFileCameraRegistry::new(dir.join(format!("cameras_{}.json", system_id)));
+        let mut sistema_camaras = SistemaCamaras::new(Arc::new(Mutex::new(cameras)), create_logger());
+        sistema_camaras.set_system_id(system_id);
+        let client_id = if system_id == DEFAULT_CAMERA_SYSTEM_ID {
+            "Sistema-Camaras".to_string()
+        } else {
+            format!("Sistema-Camaras-{}", system_id)
+        };
+        let (_children, exit_tx) = sistema_camaras.spawn_threads_without_abm(
+            connect(&client_id, &server),
+            Box::new(registry),
+            create_detector_properties_in(&dir, &format!("image_detection_{}", system_id)),
+            Arc::new(NoIncidentsAiProvider),
+        );
+        exit_txs.push(exit_tx);
+    }
+
+    let mut admin = connect("admin", &server);
+    let replies_rx = admin
+        .mqtt_subscribe_typed(&AppsMqttTopics::camera_command_replies("admin"), 1)
+        .unwrap();
+
+    // El alta al sistema 2 sólo la hace ese sistema, y sus cámaras llevan su id
+    let add_5 = CameraCommand::Add {
+        id: 5,
+        latitude: -34.6095,
+        longitude: -58.3878,
+        range: 250.0,
+        fov: CameraFov::default(),
+    };
+    let added = request_to(&mut admin, 2, &replies_rx, "1", add_5).unwrap();
+    assert_eq!(added[0].get_system_id(), 2);
+
+    let listed = request_to(&mut admin, 2, &replies_rx, "2", CameraCommand::List).unwrap();
+    assert_eq!(ids_of(&listed), vec![1, 5]);
+    assert!(listed.iter().all(|camera| camera.get_system_id() == 2));
+
+    let listed = request(&mut admin, &replies_rx, "3", CameraCommand::List).unwrap();
+    assert_eq!(ids_of(&listed), vec![1]);
+    assert_eq!(listed[0].get_system_id(), DEFAULT_CAMERA_SYSTEM_ID);
+
+    for exit_tx in exit_txs {
+        let _ = exit_tx.send(true);
+    }
+    server.shutdown();
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_el_listado_de_las_camaras_por_defecto_llega_completo() {
+    let dir = std::env::temp_dir().join(format!("rustx_camera_list_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let server = create_server(&dir);
+
+    // Sistema cámaras con las cámaras de `cameras.properties`, importadas a un registro vacío como al desplegarlo
+    let mut registry = JsonFileCameraRegistry::new(dir.join("cameras.json"));
+    let policy = BorderingPolicy::from_properties_file(SIST_CAMARAS_PROPERTIES_FILE).unwrap();
+    let (cameras, bordering) = load_cameras(&mut registry, CAMERAS_PROPERTIES_FILE, policy).unwrap();
+    let mut expected_ids: Vec<u32> = cameras.keys().copied().collect();
+    expected_ids.sort();
+    assert!(expected_ids.len() > 1);
+    let mut sistema_camaras =
+        SistemaCamaras::new_with_bordering(Arc::new(Mutex::new(cameras)), bordering, create_logger());
+    let (_children, exit_tx) = sistema_camaras.spawn_threads_without_abm(
+        connect("Sistema-Camaras", &server),
+        Box::new(registry),
+        create_detector_properties(&dir),
+        Arc::new(NoIncidentsAiProvider),
+    );
+
+    let mut admin = connect("admin", &server);
+    let replies_rx = admin
+        .mqtt_subscribe_typed(&AppsMqttTopics::camera_command_replies("admin"), 1)
+        .unwrap();
+
+    let listed = request(&mut admin, &replies_rx, "1", CameraCommand::List).unwrap();
+    let mut listed_ids = ids_of(&listed);
+    listed_ids.sort();
+    assert_eq!(listed_ids, expected_ids);
+
+    let _ = exit_tx.send(true);
+    server.shutdown();
+    let _ = fs::remove_dir_all(&dir);
+}
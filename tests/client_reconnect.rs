@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant};
+
+mod common;
+use common::{create_logger, free_port, Broker};
+
+use rustx::mqtt::client::{
+    connection_event::ConnectionEvent, mqtt_client::MQTTClient, reconnect_policy::ReconnectPolicy,
+};
+
+#[test]
+fn test_1_al_caer_el_broker_se_reconecta_resuscribe_y_envia_los_publish_almacenados() {
+    // El broker se vuelve a levantar en el mismo puerto
+    let port = free_port();
+    let broker = Broker::start_with("reconnect_1", port, "");
+    let policy = ReconnectPolicy {
+        initial_backoff: Duration::from_millis(200),
+        max_backoff: Duration::from_secs(1),
+        ..Default::default()
+    };
+    let (mut client, publish_rx, events_rx, _h) = MQTTClient::mqtt_connect_to_broker_with_reconnect(
+        "reconnecting".to_string(),
+        &broker.addr,
+        None,
+        policy,
+        create_logger(),
+    )
+    .unwrap();
+    client.mqtt_subscribe(vec![("inc".to_string(), 1)]).unwrap();
+
+    drop(broker);
+    assert_eq!(events_rx.recv_timeout(Duration::from_secs(10)).unwrap(), ConnectionEvent::Disconnected);
+
+    // Sin conexión: el qos 1 se almacena, el qos 0 se descarta.
+    client.mqtt_publish("inc", &[1], 1).unwrap();
+    assert!(client.mqtt_publish("inc", &[2], 0).is_err());
+
+    let _broker = Broker::start_with("reconnect_2", port, "");
+    let start = Instant::now();
+    loop {
+        let event = events_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        if event == ConnectionEvent::Reconnected {
+            break;
+        }
+        assert!(matches!(event, ConnectionEvent::Reconnecting { .. }));
+        assert!(start.elapsed() < Duration::from_secs(20), "No se reconectó.");
+    }
+
+    // La suscripción se renovó, así que el cliente recibe su propio publish almacenado.
+    let msg = publish_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!((msg.get_topic(), msg.get_payload()), ("inc".to_string(), vec![1]));
+
+    client.mqtt_publish("inc", &[3], 1).unwrap();
+    let msg = publish_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(msg.get_payload(), vec![3]);
+}
+
+#[test]
+fn test_2_al_agotar_los_intentos_se_informa_que_se_abandona() {
+    let broker = Broker::start("reconnect_3");
+    let policy = ReconnectPolicy {
+        initial_backoff: Duration::from_millis(50),
+        max_attempts: Some(2),
+        ..Default::default()
+    };
+    let (_client, _publish_rx, events_rx, handle) = MQTTClient::mqtt_connect_to_broker_with_reconnect(
+        "giving-up".to_string(),
+        &broker.addr,
+        None,
+        policy,
+        create_logger(),
+    )
+    .unwrap();
+
+    drop(broker);
+    let events: Vec<ConnectionEvent> = events_rx.iter().take(4).collect();
+    assert_eq!(
+        events,
+        vec![
+            ConnectionEvent::Disconnected,
+            ConnectionEvent::Reconnecting { attempt: 1 },
+            ConnectionEvent::Reconnecting { attempt: 2 },
+            ConnectionEvent::GaveUp,
+        ]
+    );
+    handle.join().unwrap();
+}
@@ -0,0 +1,106 @@
+use std::{thread, time::Duration};
+
+mod common;
+use common::{create_logger, free_ports, Broker};
+
+use rustx::mqtt::client::mqtt_client::MQTTClient;
+
+/// Propiedades de cluster del nodo `name`, que se comunica con el nodo par por `peer_cluster_port`.
+fn cluster_properties(name: &str, cluster_port: u16, peer_cluster_port: u16) -> String {
+    format!(
+        "cluster_port=\"{}\"\ncluster_node_id=\"{}\"\ncluster_peers=\"127.0.0.1:{}\"\n",
+        cluster_port, name, peer_cluster_port
+    )
+}
+
+// Tiempo suficiente para que los nodos intercambien latidos.
+fn wait_for_heartbeats() {
+    thread::sleep(Duration::from_millis(2500));
+}
+
+#[test]
+fn test_1_el_cluster_reenvia_publish_y_restaura_la_sesion_al_caer_un_nodo() {
+    let logger = create_logger();
+    let [port_1, port_2, cluster_port_1, cluster_port_2] = free_ports::<4>();
+    let mut node_1 = Broker::start_with(
+        "cluster_failover_nodo1",
+        port_1,
+        &cluster_properties("nodo1", cluster_port_1, cluster_port_2),
+    );
+    let node_2 = Broker::start_with(
+        "cluster_failover_nodo2",
+        port_2,
+        &cluster_properties("nodo2", cluster_port_2, cluster_port_1),
+    );
+
+    // Un suscriptor en cada nodo.
+    let (mut monitor, monitor_rx, _monitor_h) =
+        MQTTClient::mqtt_connect_to_broker("monitor".to_string(), &node_2.addr, None, logger.clone_ref()).unwrap();
+    monitor.mqtt_subscribe(vec![("cam".to_string(), 1)]).unwrap();
+    let (mut detector, _detector_rx, _detector_h) =
+        MQTTClient::mqtt_connect_to_broker("detector".to_string(), &node_1.addr, None, logger.clone_ref()).unwrap();
+    detector.mqtt_subscribe(vec![("inc".to_string(), 1)]).unwrap();
+    wait_for_heartbeats();
+
+    // Lo publicado en el nodo 1 le llega al suscriptor del nodo 2.
+    let (mut publisher_1, _rx_1, _h_1) =
+        MQTTClient::mqtt_connect_to_broker("pub-1".to_string(), &node_1.addr, None, logger.clone_ref()).unwrap();
+    publisher_1.mqtt_publish("cam", &[1, 2, 3], 1).unwrap();
+    let received = monitor_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(received.get_topic(), "cam");
+    assert_eq!(received.get_payload(), vec![1, 2, 3]);
+
+    // Se cae el nodo 1: el detector se reconecta al nodo 2 sin volver a suscribirse, y recibe igual.
+    node_1.kill();
+    drop(detector);
+    let (_detector, detector_rx, _detector_h) =
+        MQTTClient::mqtt_connect_to_broker("detector".to_string(), &node_2.addr, None, logger.clone_ref()).unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    let (mut publisher_2, _rx_2, _h_2) =
+        MQTTClient::mqtt_connect_to_broker("pub-2".to_string(), &node_2.addr, None, logger.clone_ref()).unwrap();
+    publisher_2.mqtt_publish("inc", &[4, 5], 1).unwrap();
+    let received = detector_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(received.get_topic(), "inc");
+    assert_eq!(received.get_payload(), vec![4, 5]);
+
+    drop(node_2);
+}
+
+#[test]
+fn test_2_una_suscripcion_compartida_reparte_los_mensajes_entre_el_grupo() {
+    let logger = create_logger();
+    let [port, cluster_port, peer_cluster_port] = free_ports::<3>();
+    let node = Broker::start_with(
+        "shared_subscription",
+        port,
+        &cluster_properties("nodo", cluster_port, peer_cluster_port),
+    );
+
+    let mut receivers = vec![];
+    let mut detectors = vec![];
+    for i in 0..2 {
+        let (mut detector, rx, _h) =
+            MQTTClient::mqtt_connect_to_broker(format!("detector-{}", i), &node.addr, None, logger.clone_ref()).unwrap();
+        detector.mqtt_subscribe(vec![("$share/detectores/inc".to_string(), 1)]).unwrap();
+        detectors.push(detector);
+        receivers.push(rx);
+    }
+    thread::sleep(Duration::from_millis(300));
+
+    let (mut publisher, _rx, _h) =
+        MQTTClient::mqtt_connect_to_broker("pub".to_string(), &node.addr, None, logger.clone_ref()).unwrap();
+    for i in 0..4 {
+        publisher.mqtt_publish("inc", &[i], 1).unwrap();
+    }
+
+    // Cada miembro del grupo recibe la mitad de los mensajes.
+    for rx in &receivers {
+        for _ in 0..2 {
+            assert_eq!(rx.recv_timeout(Duration::from_secs(10)).unwrap().get_topic(), "inc");
+        }
+        assert!(rx.recv_timeout(Duration::from_millis(500)).is_err());
+    }
+
+    drop(node);
+}
@@ -0,0 +1,160 @@
+//! Utilidades compartidas por los tests de integración.
+
+use std::{
+    fs, io,
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::{Duration, Instant},
+};
+
+use rustx::logging::string_logger::StringLogger;
+
+/// Logger cuyos mensajes se descartan.
+#[allow(dead_code)] // No todos los tests que incluyen este módulo lo usan.
+pub fn create_logger() -> StringLogger {
+    let (tx, rx) = mpsc::channel::<String>();
+    thread::spawn(move || for _ in rx {});
+    StringLogger::new(tx)
+}
+
+/// Espera hasta `timeout` a que `condition` se cumpla para algún valor recibido por `rx`, y lo devuelve.
+/// Si no se cumple, el test falla indicando `what`.
+#[allow(dead_code)] // No todos los tests que incluyen este módulo la usan.
+pub fn wait_for<T>(
+    rx: &Receiver<Result<T, io::Error>>,
+    timeout: Duration,
+    what: &str,
+    mut condition: impl FnMut(&T) -> bool,
+) -> T {
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if let Ok(Ok(value)) = rx.recv_timeout(Duration::from_millis(200)) {
+            if condition(&value) {
+                return value;
+            }
+        }
+    }
+    panic!("No se recibió: {}.", what);
+}
+
+/// Devuelve un puerto de `127.0.0.1` libre en este momento: el que asigna el sistema operativo al pedir el puerto 0.
+#[allow(dead_code)]
+pub fn free_port() -> u16 {
+    let [port] = free_ports::<1>();
+    port
+}
+
+/// Como `free_port`, pero devuelve `N` puertos libres distintos entre sí.
+#[allow(dead_code)]
+pub fn free_ports<const N: usize>() -> [u16; N] {
+    // Se mantienen todos abiertos hasta el final, para que el sistema operativo no repita ninguno
+    let listeners: Vec<TcpListener> = (0..N)
+        .map(|_| TcpListener::bind("127.0.0.1:0").unwrap())
+        .collect();
+    std::array::from_fn(|i| listeners[i].local_addr().unwrap().port())
+}
+
+/// El broker, corriendo como proceso aparte en su propio directorio temporal, con las credenciales del repo.
+/// Se lo mata, y se borra su directorio, al salir de scope.
+#[allow(dead_code)]
+pub struct Broker {
+    child: Child,
+    pub addr: SocketAddr,
+    pub dir: PathBuf,
+}
+
+#[allow(dead_code)]
+impl Broker {
+    /// Levanta el broker en un puerto libre.
+    pub fn start(name: &str) -> Self {
+        Self::start_with(name, free_port(), "")
+    }
+
+    /// Levanta el broker en el puerto `port`, agregando `extra_properties` a su archivo de propiedades
+    /// (ie las del cluster). Espera a que acepte conexiones.
+    pub fn start_with(name: &str, port: u16, extra_properties: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("rustx_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::copy("credentials.txt", dir.join("credentials.txt")).unwrap();
+        let properties_file = dir.join("server.properties");
+        fs::write(
+            &properties_file,
+            format!("ip=\"127.0.0.1\"\nport=\"{}\"\n{}", port, extra_properties),
+        )
+        .unwrap();
+
+        let addr: SocketAddr = format!("127.0.0.1:{}", port).parse().unwrap();
+        let child = spawn_broker(&dir, addr);
+        Self { child, addr, dir }
+    }
+
+    /// Vuelve a levantar el broker, con el mismo puerto, directorio y propiedades. Debe haber terminado antes.
+    pub fn restart(&mut self) {
+        self.child = spawn_broker(&self.dir, self.addr);
+    }
+
+    /// Envía la señal `signal` (ie `TERM`, `INT` o `HUP`) al proceso del broker.
+    pub fn signal(&self, signal: &str) {
+        let status = Command::new("kill")
+            .arg(format!("-{}", signal))
+            .arg(self.child.id().to_string())
+            .status()
+            .unwrap();
+        assert!(
+            status.success(),
+            "No se pudo enviar SIG{} al broker.",
+            signal
+        );
+    }
+
+    /// Espera hasta `timeout` a que el proceso del broker termine, y devuelve cómo terminó.
+    pub fn wait_for_exit(&mut self, timeout: Duration) -> ExitStatus {
+        let start = Instant::now();
+        loop {
+            if let Some(status) = self.child.try_wait().unwrap() {
+                return status;
+            }
+            assert!(start.elapsed() < timeout, "El broker no terminó.");
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    /// Mata el proceso del broker, sin cierre ordenado.
+    pub fn kill(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Drop for Broker {
+    fn drop(&mut self) {
+        self.kill();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Lanza el broker en `dir`, con el `server.properties` de ese directorio, y espera a que acepte conexiones en `addr`.
+fn spawn_broker(dir: &Path, addr: SocketAddr) -> Child {
+    let child = Command::new(env!("CARGO_BIN_EXE_message_broker_server"))
+        .arg(addr.port().to_string())
+        .arg(dir.join("server.properties"))
+        .current_dir(dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let start = Instant::now();
+    while TcpStream::connect(addr).is_err() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "El broker en {:?} no levantó.",
+            dir
+        );
+        thread::sleep(Duration::from_millis(100));
+    }
+    child
+}
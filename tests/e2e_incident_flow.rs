@@ -0,0 +1,320 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fs, io,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::Receiver,
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+mod common;
+use common::{create_logger, wait_for};
+
+use rustx::apps::{
+    apps_mqtt_topics::AppsMqttTopics,
+    incident_data::{
+        active_incidents::{ActiveIncidents, ActiveIncidentsReply},
+        incident::Incident,
+        incident_source::IncidentSource,
+    },
+    sist_camaras::{
+        ai_detection::{ai_provider::AiProvider, properties::DetectorProperties},
+        camera::Camera,
+        camera_registry::json_file_registry::JsonFileCameraRegistry,
+        camera_state::CameraState,
+        sistema_camaras::SistemaCamaras,
+    },
+    sist_dron::{dron::Dron, dron_current_info::DronCurrentInfo, dron_state::DronState},
+};
+use rustx::mqtt::{
+    client::{mqtt_client::MQTTClient, typed_topic::Topic},
+    server::mqtt_server::MQTTServer,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+// La cámara 1 está donde ocurre el incidente; la 2, lejos.
+const CAM_1_POSITION: (f64, f64) = (-34.6090, -58.3873);
+const CAM_2_POSITION: (f64, f64) = (-34.6300, -58.4100);
+// Los drones 1 y 2 están cerca de la cámara 1, el 3 más lejos pero también en rango.
+const DRONES: [(u32, (f64, f64)); 3] = [
+    (1, (-34.6090, -58.3873)),
+    (2, (-34.6085, -58.3868)),
+    (3, (-34.6290, -58.4090)),
+];
+
+/// Proveedor de ia simulado: la primera imagen contiene un incidente, las siguientes no.
+#[derive(Debug, Default)]
+struct MockAiProvider {
+    calls: AtomicUsize,
+}
+
+impl AiProvider for MockAiProvider {
+    fn analyze_image(&self, _image: Vec<u8>) -> Result<String, Box<dyn Error>> {
+        let probability = if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            0.95
+        } else {
+            0.01
+        };
+        Ok(format!(
+            r#"{{"predictions": [{{"probability": {}, "tagName": "incidente"}}]}}"#,
+            probability
+        ))
+    }
+}
+
+/// Properties del detector, con su directorio de imágenes dentro de `dir`.
+fn create_detector_properties(dir: &Path) -> DetectorProperties {
+    let base_dir = dir.join("image_detection");
+    let properties_file = dir.join("detector.properties");
+    fs::write(
+        &properties_file,
+        format!(
+            "base_dir={}\napi_credentials_file_path=no_usado.json\ninc_tag=incidente\ninc_threshold=0.7\n\
+             img_valid_extension1=jpg\nimg_valid_extension2=jpeg\n",
+            base_dir.display()
+        ),
+    )
+    .unwrap();
+    DetectorProperties::new(properties_file.to_str().unwrap()).unwrap()
+}
+
+fn create_cameras() -> Arc<Mutex<HashMap<u32, Camera>>> {
+    let mut cameras = HashMap::new();
+    cameras.insert(1, Camera::new(1, CAM_1_POSITION.0, CAM_1_POSITION.1, 250.0));
+    cameras.insert(2, Camera::new(2, CAM_2_POSITION.0, CAM_2_POSITION.1, 250.0));
+    Arc::new(Mutex::new(cameras))
+}
+
+/// Conecta y lanza un dron en la posición recibida. Su hilo queda atendiendo mensajes hasta que se cierra el broker.
+fn spawn_dron(id: u32, (lat, lon): (f64, f64), addr: &std::net::SocketAddr) {
+    let logger = create_logger();
+    let (mqtt_client, _publish_msg_rx, _handle) =
+        MQTTClient::mqtt_connect_to_broker(format!("dron-{}", id), addr, None, logger.clone_ref())
+            .unwrap();
+    let mut dron = Dron::new(id, lat, lon, logger).unwrap();
+    thread::spawn(move || {
+        let _ = dron.spawn_threads(mqtt_client);
+    });
+}
+
+/// Crea imágenes en el directorio de la cámara, hasta que se publique un incidente.
+fn capture_until_incident(
+    camera_dir: &Path,
+    incidents_rx: &Receiver<Result<Incident, io::Error>>,
+) -> Incident {
+    let start = Instant::now();
+    let mut i = 0;
+    while start.elapsed() < TIMEOUT {
+        if camera_dir.exists() {
+            i += 1;
+            fs::write(camera_dir.join(format!("captura_{}.jpg", i)), b"imagen").unwrap();
+        }
+        if let Ok(Ok(incident)) = incidents_rx.recv_timeout(Duration::from_millis(500)) {
+            return incident;
+        }
+    }
+    panic!("El detector no publicó ningún incidente.");
+}
+
+#[test]
+fn test_incidente_detectado_activa_camaras_despacha_dos_drones_y_se_resuelve() {
+    let dir = std::env::temp_dir().join(format!("rustx_e2e_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    // Broker, embebido en el proceso
+    let (server_handle, addr) = MQTTServer::start_on_ephemeral_port(create_logger()).unwrap();
+
+    // Observador, que hace además de operador de monitoreo
+    let (mut observer, _publish_msg_rx, _handle) =
+        MQTTClient::mqtt_connect_to_broker("observador".to_string(), &addr, None, create_logger())
+            .unwrap();
+    let cameras_rx = observer
+        .mqtt_subscribe_typed(&AppsMqttTopics::cameras(), 1)
+        .unwrap();
+    let drones_rx = observer
+        .mqtt_subscribe_typed(&AppsMqttTopics::drones(), 1)
+        .unwrap();
+    let incidents_rx = observer
+        .mqtt_subscribe_typed(&AppsMqttTopics::incidents(), 1)
+        .unwrap();
+
+    // Drones
+    for (id, position) in DRONES {
+        spawn_dron(id, position, &addr);
+    }
+    let mut started = HashSet::new();
+    wait_for(&drones_rx, TIMEOUT, "la posición inicial de los drones", |ci| {
+        started.insert(ci.get_id());
+        started.len() == DRONES.len()
+    });
+
+    // Sistema cámaras, con el detector usando el proveedor simulado
+    let (mqtt_client, _publish_msg_rx, _handle) = MQTTClient::mqtt_connect_to_broker(
+        "Sistema-Camaras".to_string(),
+        &addr,
+        None,
+        create_logger(),
+    )
+    .unwrap();
+    let ai_provider = Arc::new(MockAiProvider::default());
+    let detector_properties = create_detector_properties(&dir);
+    let camera_dir = Path::new(detector_properties.get_base_dir()).join("camera_1");
+    let mut sistema_camaras = SistemaCamaras::new(create_cameras(), create_logger());
+    let (_children, exit_tx) = sistema_camaras.spawn_threads_without_abm(
+        mqtt_client,
+        Box::new(JsonFileCameraRegistry::new(dir.join("cameras.json"))),
+        detector_properties,
+        ai_provider.clone(),
+    );
+
+    // La cámara 1 capta un incidente, que el detector publica
+    let incident = capture_until_incident(&camera_dir, &incidents_rx);
+    assert!(ai_provider.calls.load(Ordering::SeqCst) >= 1);
+    assert_eq!(incident.get_source(), &IncidentSource::Automated);
+    assert!(!incident.is_resolved());
+
+    // Se activa la cámara que lo capta
+    wait_for(&cameras_rx, TIMEOUT, "la cámara 1 activa", |camera| {
+        camera.get_id() == 1 && camera.get_state() == CameraState::Active
+    });
+
+    // Los dos drones más cercanos llegan al incidente
+    let mut arrived = HashSet::new();
+    wait_for(
+        &drones_rx,
+        TIMEOUT,
+        "dos drones en el incidente",
+        |ci: &DronCurrentInfo| {
+            if ci.get_state() == DronState::ManagingIncident
+                && ci.get_inc_id_to_resolve() == Some(incident.get_info())
+            {
+                arrived.insert(ci.get_id());
+            }
+            arrived.len() == 2
+        },
+    );
+    assert_eq!(arrived, HashSet::from([1, 2]));
+
+    // Monitoreo lo resuelve: la cámara vuelve a ahorro de energía, y los drones a su posición inicial
+    let mut resolved = incident.clone();
+    resolved.set_resolved();
+    observer
+        .mqtt_publish_typed(&AppsMqttTopics::incidents(), &resolved, 1)
+        .unwrap();
+
+    wait_for(&cameras_rx, TIMEOUT, "la cámara 1 en ahorro de energía", |camera| {
+        camera.get_id() == 1 && camera.get_state() == CameraState::SavingMode
+    });
+    let mut returned = HashSet::new();
+    wait_for(
+        &drones_rx,
+        TIMEOUT,
+        "los drones de vuelta en su posición inicial",
+        |ci| {
+            let initial_position = DRONES
+                .iter()
+                .find(|(id, _)| *id == ci.get_id())
+                .map(|(_, pos)| *pos);
+            if ci.get_state() == DronState::ManagingIncident
+                && Some(ci.get_current_position()) == initial_position
+            {
+                returned.insert(ci.get_id());
+            }
+            arrived.is_subset(&returned)
+        },
+    );
+
+    let _ = exit_tx.send(true);
+    observer.mqtt_disconnect().unwrap();
+    server_handle.shutdown().unwrap();
+    let _ = fs::remove_dir_all(&dir);
+}
+
+/// Responde los pedidos de incidentes activos con `active`, como lo hace sistema monitoreo.
+fn spawn_active_incidents_responder(mut monitoreo: MQTTClient, active: ActiveIncidents) {
+    let requests_rx = monitoreo
+        .mqtt_subscribe_typed(&AppsMqttTopics::active_incidents(), 1)
+        .unwrap();
+    thread::spawn(move || {
+        for request in requests_rx.into_iter().flatten() {
+            let reply = ActiveIncidentsReply::new(request.get_correlation_id(), active.to_vec());
+            let reply_topic = Topic::<ActiveIncidentsReply>::new(request.get_reply_topic());
+            let _ = monitoreo.mqtt_publish_typed(&reply_topic, &reply, 1);
+        }
+    });
+}
+
+#[test]
+fn test_al_iniciar_sistema_camaras_recupera_los_incidentes_activos_y_luego_los_resuelve() {
+    let dir = std::env::temp_dir().join(format!("rustx_e2e_reconcile_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (server_handle, addr) = MQTTServer::start_on_ephemeral_port(create_logger()).unwrap();
+
+    // Monitoreo tiene incidentes activos en las cámaras 1 y 2, y otros lejos de toda cámara, publicados antes de
+    // que inicie sistema cámaras. Son suficientes para que la respuesta no entre en un paquete de 255 bytes.
+    let incident = Incident::new(Incident::generate_id(), CAM_1_POSITION, IncidentSource::Manual);
+    let mut active = ActiveIncidents::new();
+    active.update(&incident);
+    active.update(&Incident::new(Incident::generate_id(), CAM_2_POSITION, IncidentSource::Manual));
+    for i in 0..6 {
+        let far_away = (-34.70 - i as f64 * 0.01, -58.50);
+        active.update(&Incident::new(Incident::generate_id(), far_away, IncidentSource::Manual));
+    }
+    assert_eq!(active.to_vec().len(), 8);
+    let (monitoreo, _publish_msg_rx, _handle) =
+        MQTTClient::mqtt_connect_to_broker("monitoreo".to_string(), &addr, None, create_logger())
+            .unwrap();
+    spawn_active_incidents_responder(monitoreo, active);
+
+    let (mut observer, _publish_msg_rx, _handle) =
+        MQTTClient::mqtt_connect_to_broker("observador".to_string(), &addr, None, create_logger())
+            .unwrap();
+    let cameras_rx = observer
+        .mqtt_subscribe_typed(&AppsMqttTopics::cameras(), 1)
+        .unwrap();
+
+    // Sistema cámaras inicia con las cámaras en ahorro de energía, y las corrige con los incidentes activos
+    let (mqtt_client, _publish_msg_rx, _handle) = MQTTClient::mqtt_connect_to_broker(
+        "Sistema-Camaras".to_string(),
+        &addr,
+        None,
+        create_logger(),
+    )
+    .unwrap();
+    let mut sistema_camaras = SistemaCamaras::new(create_cameras(), create_logger());
+    sistema_camaras.set_reconciliation_timeout(Some(TIMEOUT));
+    let (_children, exit_tx) = sistema_camaras.spawn_threads_without_abm(
+        mqtt_client,
+        Box::new(JsonFileCameraRegistry::new(dir.join("cameras.json"))),
+        create_detector_properties(&dir),
+        Arc::new(MockAiProvider::default()),
+    );
+    let mut active_cameras = HashSet::new();
+    wait_for(&cameras_rx, TIMEOUT, "las cámaras 1 y 2 activas", |camera| {
+        if camera.get_state() == CameraState::Active {
+            active_cameras.insert(camera.get_id());
+        }
+        active_cameras.contains(&1) && active_cameras.contains(&2)
+    });
+
+    // El resuelto ya no es de un incidente desconocido: la cámara vuelve a ahorro de energía
+    let mut resolved = incident.clone();
+    resolved.set_resolved();
+    observer
+        .mqtt_publish_typed(&AppsMqttTopics::incidents(), &resolved, 1)
+        .unwrap();
+    wait_for(&cameras_rx, TIMEOUT, "la cámara 1 en ahorro de energía", |camera| {
+        camera.get_id() == 1 && camera.get_state() == CameraState::SavingMode
+    });
+
+    let _ = exit_tx.send(true);
+    observer.mqtt_disconnect().unwrap();
+    server_handle.shutdown().unwrap();
+    let _ = fs::remove_dir_all(&dir);
+}
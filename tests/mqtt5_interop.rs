@@ -0,0 +1,175 @@
+use std::{fs, thread, time::Duration};
+
+mod common;
+use common::{create_logger, Broker};
+
+use rustx::mqtt::client::mqtt_client::MQTTClient;
+use rustx::mqtt::messages::{mqtt_properties::MqttProperties, protocol_version::ProtocolVersion};
+use rustx::mqtt::server::{mqtt_server::MQTTServer, server_properties::ServerProperties};
+
+#[test]
+fn test_1_clientes_mqtt5_y_mqtt311_intercambian_mensajes_en_el_mismo_broker() {
+    let broker = Broker::start("mqtt5_interop");
+    let logger = create_logger();
+
+    let (mut old_sub, old_rx, _h1) =
+        MQTTClient::mqtt_connect_to_broker("sub-311".to_string(), &broker.addr, None, logger.clone_ref()).unwrap();
+    old_sub.mqtt_subscribe(vec![("cam".to_string(), 1)]).unwrap();
+    let (mut new_sub, new_rx, _h2) = MQTTClient::mqtt_connect_to_broker_v5(
+        "sub-5".to_string(),
+        &broker.addr,
+        None,
+        MqttProperties::new(),
+        logger.clone_ref(),
+    )
+    .unwrap();
+    assert_eq!(new_sub.get_protocol_version(), ProtocolVersion::V5);
+    new_sub.mqtt_subscribe(vec![("cam".to_string(), 1)]).unwrap();
+
+    // El segundo publish a "cam" viaja sólo con el topic alias; el broker lo resuelve.
+    let (mut publisher, _rx, _h3) = MQTTClient::mqtt_connect_to_broker_v5(
+        "pub-5".to_string(),
+        &broker.addr,
+        None,
+        MqttProperties::new(),
+        logger.clone_ref(),
+    )
+    .unwrap();
+    for i in 0..2 {
+        let mut properties = MqttProperties::new();
+        properties.add_user_property("origen", "sistema_camaras");
+        let sent = publisher.mqtt_publish_with_properties("cam", &[i], 1, properties).unwrap();
+        assert_eq!(sent.get_topic(), "cam");
+    }
+
+    for i in 0..2 {
+        let old_msg = old_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        let new_msg = new_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!((old_msg.get_topic(), old_msg.get_payload()), ("cam".to_string(), vec![i]));
+        assert_eq!((new_msg.get_topic(), new_msg.get_payload()), ("cam".to_string(), vec![i]));
+        assert_eq!(old_msg.get_timestamp(), new_msg.get_timestamp());
+        // Sólo el cliente MQTT 5 recibe las user properties.
+        assert!(old_msg.get_properties().is_none());
+        let props = new_msg.get_properties().unwrap();
+        assert_eq!(props.get_user_property("origen").unwrap(), "sistema_camaras");
+        // El publish lleva además el client id de quien lo publicó.
+        assert_eq!(new_msg.get_app_id(), Some("pub-5"));
+        assert_eq!(old_msg.get_app_id(), None);
+    }
+}
+
+#[test]
+fn test_2_request_response_con_correlation_data_y_mensajes_expirados() {
+    let broker = Broker::start("mqtt5_request_response");
+    let logger = create_logger();
+    let connect = |client_id: &str| {
+        MQTTClient::mqtt_connect_to_broker_v5(
+            client_id.to_string(),
+            &broker.addr,
+            None,
+            MqttProperties::new(),
+            logger.clone_ref(),
+        )
+        .unwrap()
+    };
+
+    let (mut responder, responder_rx, _h1) = connect("responder");
+    responder.mqtt_subscribe(vec![("req".to_string(), 1)]).unwrap();
+    let (mut requester, requester_rx, _h2) = connect("requester");
+    requester.mqtt_subscribe(vec![("resp/requester".to_string(), 1)]).unwrap();
+
+    let mut properties = MqttProperties::new();
+    properties.response_topic = Some("resp/requester".to_string());
+    properties.correlation_data = Some(vec![4, 2]);
+    requester.mqtt_publish_with_properties("req", &[1], 1, properties).unwrap();
+
+    let request = responder_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    responder.mqtt_respond_to(&request, &[2], 1).unwrap();
+    let response = requester_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!(response.get_topic(), "resp/requester");
+    assert_eq!(response.get_payload(), vec![2]);
+    assert_eq!(response.get_correlation_data(), Some(vec![4, 2]));
+
+    // A un suscriptor que llega tarde no se le envían los mensajes ya expirados.
+    let mut expiring = MqttProperties::new();
+    expiring.message_expiry_interval = Some(1);
+    requester.mqtt_publish_with_properties("hist", &[1], 1, expiring).unwrap();
+    requester.mqtt_publish_with_properties("hist", &[2], 1, MqttProperties::new()).unwrap();
+    thread::sleep(Duration::from_millis(1500));
+
+    let (mut late, late_rx, _h3) = connect("late");
+    late.mqtt_subscribe(vec![("hist".to_string(), 1)]).unwrap();
+    assert_eq!(late_rx.recv_timeout(Duration::from_secs(10)).unwrap().get_payload(), vec![2]);
+    assert!(late_rx.recv_timeout(Duration::from_millis(500)).is_err());
+}
+
+#[test]
+fn test_3_mensajes_de_mas_de_255_bytes_llegan_completos_en_ambas_versiones() {
+    let logger = create_logger();
+    let (_server, addr) = MQTTServer::start_on_ephemeral_port(logger.clone_ref()).unwrap();
+
+    let (mut old_client, old_rx, _h1) =
+        MQTTClient::mqtt_connect_to_broker("largo-311".to_string(), &addr, None, logger.clone_ref()).unwrap();
+    old_client.mqtt_subscribe(vec![("largo".to_string(), 1)]).unwrap();
+    let (mut new_client, new_rx, _h2) = MQTTClient::mqtt_connect_to_broker_v5(
+        "largo-5".to_string(),
+        &addr,
+        None,
+        MqttProperties::new(),
+        logger.clone_ref(),
+    )
+    .unwrap();
+    new_client.mqtt_subscribe(vec![("largo".to_string(), 1)]).unwrap();
+
+    // Luego de cada mensaje largo se envía uno corto: si el largo se truncara, el stream quedaría desfasado.
+    let long_payload: Vec<u8> = (0..20_000).map(|i| (i % 251) as u8).collect();
+    for (client, last) in [(&mut old_client, 1), (&mut new_client, 2)] {
+        client.mqtt_publish("largo", &long_payload, 1).unwrap();
+        client.mqtt_publish("largo", &[last], 1).unwrap();
+
+        for rx in [&old_rx, &new_rx] {
+            assert_eq!(rx.recv_timeout(Duration::from_secs(10)).unwrap().get_payload(), long_payload);
+            assert_eq!(rx.recv_timeout(Duration::from_secs(10)).unwrap().get_payload(), vec![last]);
+        }
+    }
+}
+
+#[test]
+fn test_4_un_publish_que_el_acl_no_permite_no_se_confirma_ni_se_distribuye() {
+    let dir = std::env::temp_dir().join(format!("rustx_mqtt5_acl_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let acl_file = dir.join("acl.txt");
+    fs::write(&acl_file, "* sub *\nintruso-5 pub permitido\n").unwrap();
+    let properties_file = dir.join("server.properties");
+    fs::write(&properties_file, format!("ip=127.0.0.1\nport=0\nacl_file={}\n", acl_file.display())).unwrap();
+    let properties = ServerProperties::new(properties_file.to_str().unwrap()).unwrap();
+
+    let logger = create_logger();
+    let (_server, addr) = MQTTServer::start_in_background(logger.clone_ref(), &properties).unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    let (mut observer, observer_rx, _h1) =
+        MQTTClient::mqtt_connect_to_broker("observador".to_string(), &addr, None, logger.clone_ref()).unwrap();
+    observer.mqtt_subscribe(vec![("denegado".to_string(), 1), ("permitido".to_string(), 1)]).unwrap();
+
+    // Al cliente MQTT 5 se le responde con un puback NotAuthorized, y sigue conectado.
+    let (mut new_client, _rx, _h2) = MQTTClient::mqtt_connect_to_broker_v5(
+        "intruso-5".to_string(),
+        &addr,
+        None,
+        MqttProperties::new(),
+        logger.clone_ref(),
+    )
+    .unwrap();
+    let (_, ack) = new_client.mqtt_publish_pipelined("denegado", &[1], 1).unwrap();
+    assert!(ack.wait().is_ok());
+    new_client.mqtt_publish("permitido", &[2], 1).unwrap();
+    let received = observer_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_eq!((received.get_topic(), received.get_payload()), ("permitido".to_string(), vec![2]));
+
+    // El de 3.1.1 no puede recibir el rechazo en el puback: en vez de confirmarle el publish, se lo desconecta.
+    let (mut old_client, _rx, _h3) =
+        MQTTClient::mqtt_connect_to_broker("intruso-311".to_string(), &addr, None, logger.clone_ref()).unwrap();
+    let (_, ack) = old_client.mqtt_publish_pipelined("denegado", &[3], 1).unwrap();
+    assert!(ack.wait().is_err());
+    assert!(observer_rx.recv_timeout(Duration::from_millis(500)).is_err());
+}
@@ -0,0 +1,141 @@
+use std::{
+    fs,
+    io::BufReader,
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+mod common;
+use common::{create_logger, Broker};
+
+use rustx::apps::mqtt_recorder::{recorder::Recorder, replayer::Replayer};
+use rustx::mqtt::client::mqtt_client::MQTTClient;
+
+/// Corre el binario mqtt_recorder contra el `broker`, con el archivo de grabación `file`.
+fn run_recorder(mode: &str, broker: &Broker, file: &PathBuf, extra_args: &[&str]) -> Child {
+    Command::new(env!("CARGO_BIN_EXE_mqtt_recorder"))
+        .arg(mode)
+        .arg(broker.addr.ip().to_string())
+        .arg(broker.addr.port().to_string())
+        .arg(file)
+        .args(extra_args)
+        .current_dir(&broker.dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap()
+}
+
+fn sorted_payloads(
+    rx: &mpsc::Receiver<rustx::mqtt::messages::publish_message::PublishMessage>,
+    amount: usize,
+) -> Vec<Vec<u8>> {
+    let mut payloads: Vec<Vec<u8>> = (0..amount)
+        .map(|_| {
+            rx.recv_timeout(Duration::from_secs(10))
+                .unwrap()
+                .get_payload()
+        })
+        .collect();
+    payloads.sort();
+    payloads
+}
+
+#[test]
+fn test_1_se_reproduce_una_grabacion_con_sus_tiempos() {
+    let broker = Broker::start("mqtt_replay");
+    let logger = create_logger();
+
+    let (mut recorded_sub, recorded_rx, _h1) = MQTTClient::mqtt_connect_to_broker(
+        "replay-rec".to_string(),
+        &broker.addr,
+        None,
+        logger.clone_ref(),
+    )
+    .unwrap();
+    recorded_sub
+        .mqtt_subscribe(vec![("dron".to_string(), 1), ("inc".to_string(), 1)])
+        .unwrap();
+    let (mut publisher, _rx, _h2) = MQTTClient::mqtt_connect_to_broker(
+        "replay-pub".to_string(),
+        &broker.addr,
+        None,
+        logger.clone_ref(),
+    )
+    .unwrap();
+
+    let mut recorder = Recorder::new(vec![]);
+    for (topic, payload) in [("dron", [1u8]), ("inc", [2]), ("dron", [3])] {
+        publisher.mqtt_publish(topic, &payload, 1).unwrap();
+        recorder
+            .record(&recorded_rx.recv_timeout(Duration::from_secs(10)).unwrap())
+            .unwrap();
+        thread::sleep(Duration::from_millis(200));
+    }
+    let recording = recorder.into_inner();
+
+    // Al doble de velocidad, la reproducción dura la mitad que la grabación.
+    let replayer = Replayer::from_reader(
+        BufReader::new(recording.as_slice()),
+        2.0,
+        logger.clone_ref(),
+    )
+    .unwrap();
+    let expected_duration = replayer.offset_of(2);
+    assert!(expected_duration >= Duration::from_millis(200));
+    let start = Instant::now();
+    assert_eq!(replayer.replay(&mut publisher).unwrap(), 3);
+    assert!(start.elapsed() >= expected_duration);
+
+    assert_eq!(
+        sorted_payloads(&recorded_rx, 3),
+        vec![vec![1], vec![2], vec![3]]
+    );
+}
+
+#[test]
+fn test_2_el_binario_graba_hasta_recibir_sigint_y_luego_reproduce() {
+    let broker = Broker::start("mqtt_recorder");
+    let logger = create_logger();
+    let file = broker.dir.join("grabacion.jsonl");
+
+    let mut recorder = run_recorder("record", &broker, &file, &[]);
+    // Se espera a que el recorder se conecte y se suscriba.
+    thread::sleep(Duration::from_millis(1000));
+    let (mut publisher, _rx, _h1) = MQTTClient::mqtt_connect_to_broker(
+        "recorder-pub".to_string(),
+        &broker.addr,
+        None,
+        logger.clone_ref(),
+    )
+    .unwrap();
+    publisher.mqtt_publish("cam", &[7], 1).unwrap();
+    publisher.mqtt_publish("desc", &[8], 1).unwrap();
+    thread::sleep(Duration::from_millis(500));
+
+    Command::new("kill")
+        .arg("-INT")
+        .arg(recorder.id().to_string())
+        .status()
+        .unwrap();
+    assert!(recorder.wait().unwrap().success());
+    assert_eq!(fs::read_to_string(&file).unwrap().lines().count(), 2);
+
+    let (mut subscriber, sub_rx, _h2) = MQTTClient::mqtt_connect_to_broker(
+        "recorder-sub".to_string(),
+        &broker.addr,
+        None,
+        logger.clone_ref(),
+    )
+    .unwrap();
+    subscriber
+        .mqtt_subscribe(vec![("cam".to_string(), 1), ("desc".to_string(), 1)])
+        .unwrap();
+    let mut replayer = run_recorder("replay", &broker, &file, &["4"]);
+    assert!(replayer.wait().unwrap().success());
+
+    assert_eq!(sorted_payloads(&sub_rx, 2), vec![vec![7], vec![8]]);
+}
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+mod common;
+use common::{create_logger, Broker};
+
+use rustx::mqtt::client::mqtt_client::MQTTClient;
+
+#[test]
+fn test_1_publish_sin_esperar_ack_llegan_todos() {
+    let broker = Broker::start("pipelined_publish");
+    let logger = create_logger();
+
+    let (mut subscriber, sub_rx, _h1) =
+        MQTTClient::mqtt_connect_to_broker("pipelined-sub".to_string(), &broker.addr, None, logger.clone_ref()).unwrap();
+    subscriber.mqtt_subscribe(vec![("dron".to_string(), 1)]).unwrap();
+
+    let (mut publisher, _pub_rx, _h2) =
+        MQTTClient::mqtt_connect_to_broker("pipelined-pub".to_string(), &broker.addr, None, logger.clone_ref()).unwrap();
+    publisher.set_max_in_flight(5).unwrap();
+
+    let handles: Vec<_> = (0..30u8)
+        .map(|i| publisher.mqtt_publish_pipelined("dron", &[i], 1).unwrap().1)
+        .collect();
+    for handle in handles {
+        assert!(handle.wait().is_ok());
+    }
+
+    // El broker procesa los mensajes en paralelo, así que pueden llegar en otro orden.
+    let mut received: Vec<u8> = (0..30)
+        .map(|_| sub_rx.recv_timeout(Duration::from_secs(10)).unwrap().get_payload()[0])
+        .collect();
+    received.sort();
+    assert_eq!(received, (0..30u8).collect::<Vec<u8>>());
+}
+
+#[test]
+fn test_2_el_broker_envia_cada_publish_con_un_packet_id_propio_del_suscriptor() {
+    let broker = Broker::start("subscriber_packet_ids");
+    let logger = create_logger();
+
+    let (mut subscriber, sub_rx, _h1) =
+        MQTTClient::mqtt_connect_to_broker("ids-sub".to_string(), &broker.addr, None, logger.clone_ref()).unwrap();
+    subscriber.mqtt_subscribe(vec![("inc".to_string(), 1)]).unwrap();
+
+    // Cada publisher tiene su propia sesión, así que ambos publican con el mismo packet_id.
+    let mut sent_ids = vec![];
+    for client_id in ["ids-pub-1", "ids-pub-2"] {
+        let (mut publisher, _rx, _h) =
+            MQTTClient::mqtt_connect_to_broker(client_id.to_string(), &broker.addr, None, logger.clone_ref()).unwrap();
+        sent_ids.push(publisher.mqtt_publish("inc", &[1], 1).unwrap().get_packet_id());
+    }
+    assert_eq!(sent_ids[0], sent_ids[1]);
+
+    let first = sub_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    let second = sub_rx.recv_timeout(Duration::from_secs(10)).unwrap();
+    assert_ne!(first.get_packet_id(), second.get_packet_id());
+}
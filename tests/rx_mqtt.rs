@@ -0,0 +1,77 @@
+use std::{
+    process::{Command, Output, Stdio},
+    thread,
+    time::Duration,
+};
+
+mod common;
+use common::Broker;
+
+/// Corre el binario rx_mqtt contra el `broker`, con los argumentos `args`.
+fn rx_mqtt(broker: &Broker, args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_rx_mqtt"));
+    command
+        .args(args)
+        .arg("-h")
+        .arg(broker.addr.ip().to_string())
+        .arg("-p")
+        .arg(broker.addr.port().to_string())
+        .current_dir(&broker.dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    command
+}
+
+fn stdout_of(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_1_el_broker_responde_los_pings() {
+    let broker = Broker::start("rx_mqtt_ping");
+
+    let output = rx_mqtt(&broker, &["ping", "-c", "2"]).output().unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout_of(&output).contains("2 pings enviados, 2 respondidos."));
+}
+
+#[test]
+fn test_2_se_inyecta_un_incidente_y_el_suscriptor_lo_muestra_decodificado() {
+    let broker = Broker::start("rx_mqtt_pubsub");
+    let incident_id = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+    let incident_json = format!(
+        r#"{{"id":"{}","latitude":-34.6,"longitude":-58.4,"state":"ActiveIncident","source":"Manual"}}"#,
+        incident_id
+    );
+
+    let subscriber = rx_mqtt(&broker, &["sub", "-t", "inc", "-t", "dron", "-q", "1", "-d", "-c", "2"])
+        .spawn()
+        .unwrap();
+    // Se espera a que el suscriptor se conecte y se suscriba.
+    thread::sleep(Duration::from_millis(1000));
+    let incident_pub = rx_mqtt(&broker, &["pub", "-t", "inc", "-q", "1", "-r", "-d", "-m", &incident_json])
+        .output()
+        .unwrap();
+    let raw_pub = rx_mqtt(&broker, &["pub", "-t", "dron", "-m", "hola", "-u", "usuario1", "-P", "contraseña1"])
+        .output()
+        .unwrap();
+    assert!(incident_pub.status.success());
+    assert!(raw_pub.status.success());
+
+    let received = stdout_of(&subscriber.wait_with_output().unwrap());
+    assert!(received.contains("[inc] (retain) {"));
+    assert!(received.contains(&format!("\"id\": \"{}\"", incident_id)));
+    assert!(received.contains("[dron] hola"));
+}
+
+#[test]
+fn test_3_con_credenciales_invalidas_no_se_conecta() {
+    let broker = Broker::start("rx_mqtt_credenciales");
+
+    let output = rx_mqtt(&broker, &["pub", "-t", "inc", "-m", "hola", "-u", "usuario1", "-P", "otra"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+}
@@ -0,0 +1,173 @@
+use std::{
+    collections::HashSet,
+    sync::{mpsc::Receiver, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+mod common;
+use common::{create_logger, wait_for};
+
+use rustx::apps::{
+    apps_mqtt_topics::AppsMqttTopics,
+    incident_data::{incident::Incident, incident_source::IncidentSource},
+    sist_dron::{
+        dron::Dron, dron_current_info::DronCurrentInfo, dron_logic::DISTANCES_WAIT,
+        dron_state::DronState, sist_dron_properties::FLIGHT_STEP_SECONDS,
+    },
+};
+use rustx::mqtt::{
+    client::mqtt_client::MQTTClient,
+    server::{mqtt_server::MQTTServer, server_properties::ServerProperties},
+};
+use rustx::time::{
+    clock::{ShClock, SystemClock},
+    simulated_clock::SimulatedClock,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+const DRONES_AMOUNT: u32 = 24;
+const INCIDENT_POSITION: (f64, f64) = (-34.6090, -58.3873);
+
+/// Los drones se ubican alineados, cada uno más lejos del incidente que el anterior: los más cercanos son el 1 y el 2.
+fn initial_position_of(id: u32) -> (f64, f64) {
+    (
+        INCIDENT_POSITION.0 + 0.0005 * id as f64,
+        INCIDENT_POSITION.1,
+    )
+}
+
+/// Espera a que `is_done` se cumpla para alguna de las current infos recibidas por `drones_rx`. Cuando los drones
+/// dejan de publicar (ie esperan en el reloj simulado) avanza el reloj un paso de vuelo, para que sigan volando.
+fn fly_until(
+    drones_rx: &Receiver<Result<DronCurrentInfo, std::io::Error>>,
+    clock: &SimulatedClock,
+    description: &str,
+    mut is_done: impl FnMut(&DronCurrentInfo) -> bool,
+) {
+    let start = Instant::now();
+    while start.elapsed() < TIMEOUT {
+        match drones_rx.recv_timeout(Duration::from_millis(200)) {
+            Ok(Ok(ci)) if is_done(&ci) => return,
+            Ok(_) => {}
+            Err(_) => clock.advance(Duration::from_secs_f64(FLIGHT_STEP_SECONDS)),
+        }
+    }
+    panic!("No se recibió: {}.", description);
+}
+
+/// Conecta en memoria y lanza un dron que usa el reloj simulado. Su hilo queda atendiendo mensajes.
+fn spawn_dron(id: u32, server: &MQTTServer, clock: ShClock) {
+    let logger = create_logger();
+    let stream = server.connect_in_memory().unwrap();
+    let (mqtt_client, _publish_msg_rx, _handle) = MQTTClient::mqtt_connect_over_stream(
+        format!("dron-{}", id),
+        stream,
+        None,
+        clock.clone(),
+        logger.clone_ref(),
+    )
+    .unwrap();
+    let (lat, lon) = initial_position_of(id);
+    let mut dron = Dron::new_with_clock(id, lat, lon, clock, logger).unwrap();
+    thread::spawn(move || {
+        let _ = dron.spawn_threads(mqtt_client);
+    });
+}
+
+#[test]
+fn test_1_con_reloj_simulado_los_dos_drones_mas_cercanos_de_muchos_atienden_el_incidente() {
+    let start = Instant::now();
+    let clock = Arc::new(SimulatedClock::new());
+    let server = MQTTServer::new(create_logger(), &ServerProperties::ephemeral());
+
+    let (mut observer, _publish_msg_rx, _handle) = MQTTClient::mqtt_connect_over_stream(
+        "observador".to_string(),
+        server.connect_in_memory().unwrap(),
+        None,
+        SystemClock::shared(),
+        create_logger(),
+    )
+    .unwrap();
+    let drones_rx = observer
+        .mqtt_subscribe_typed(&AppsMqttTopics::drones(), 1)
+        .unwrap();
+
+    for id in 1..=DRONES_AMOUNT {
+        spawn_dron(id, &server, clock.clone());
+    }
+    let mut started = HashSet::new();
+    wait_for(&drones_rx, TIMEOUT, "la posición inicial de los drones", |ci| {
+        started.insert(ci.get_id());
+        started.len() == DRONES_AMOUNT as usize
+    });
+
+    let incident = Incident::new(
+        Incident::generate_id(),
+        INCIDENT_POSITION,
+        IncidentSource::Manual,
+    );
+    observer
+        .mqtt_publish_typed(&AppsMqttTopics::incidents(), &incident, 1)
+        .unwrap();
+
+    // Todos están en rango: cada uno avisa que responde, y espera a conocer las distancias de los demás
+    let mut responding = HashSet::new();
+    wait_for(&drones_rx, TIMEOUT, "la respuesta de todos los drones", |ci| {
+        if ci.get_state() == DronState::RespondingToIncident {
+            responding.insert(ci.get_id());
+        }
+        responding.len() == DRONES_AMOUNT as usize
+    });
+    // Cada dron tiene dormidos a su BatteryManager y a su decisión de moverse
+    assert!(clock.wait_for_sleeping_threads(2 * DRONES_AMOUNT as usize, TIMEOUT));
+    thread::sleep(Duration::from_millis(100)); // margen para procesar las respuestas ya publicadas
+    clock.advance(DISTANCES_WAIT);
+
+    // Los dos más cercanos vuelan hasta el incidente, de a un paso
+    let mut arrived = HashSet::new();
+    fly_until(
+        &drones_rx,
+        &clock,
+        "dos drones en el incidente",
+        |ci: &DronCurrentInfo| {
+            if ci.get_state() == DronState::ManagingIncident
+                && ci.get_inc_id_to_resolve() == Some(incident.get_info())
+            {
+                arrived.insert(ci.get_id());
+            }
+            arrived.len() == 2
+        },
+    );
+    assert_eq!(arrived, HashSet::from([1, 2]));
+
+    // Se resuelve: vuelven a su posición inicial
+    let mut resolved = incident.clone();
+    resolved.set_resolved();
+    observer
+        .mqtt_publish_typed(&AppsMqttTopics::incidents(), &resolved, 1)
+        .unwrap();
+    let mut returned = HashSet::new();
+    fly_until(
+        &drones_rx,
+        &clock,
+        "los drones de vuelta en su posición inicial",
+        |ci| {
+            if ci.get_state() == DronState::ManagingIncident
+                && ci.get_current_position() == initial_position_of(ci.get_id())
+            {
+                returned.insert(ci.get_id());
+            }
+            arrived.is_subset(&returned)
+        },
+    );
+
+    // Pasó la espera simulada de la decisión, y al menos los dos pasos de vuelo que el dron 2
+    // (a unos 111 metros) tarda en ir y los dos que tarda en volver
+    let flight_step = Duration::from_secs_f64(FLIGHT_STEP_SECONDS);
+    assert!(clock.elapsed() >= DISTANCES_WAIT + 4 * flight_step);
+    assert!(start.elapsed() < TIMEOUT);
+
+    observer.mqtt_disconnect().unwrap();
+    server.shutdown();
+}
@@ -0,0 +1,66 @@
+use std::{sync::mpsc, time::Duration};
+
+mod common;
+use common::{create_logger, Broker};
+
+use rustx::apps::{
+    apps_mqtt_topics::AppsMqttTopics,
+    incident_data::{incident::Incident, incident_source::IncidentSource},
+};
+use rustx::mqtt::client::mqtt_client::MQTTClient;
+
+#[test]
+fn test_1_cada_topic_se_recibe_por_su_ruta() {
+    let broker = Broker::start("topic_routing");
+    let logger = create_logger();
+
+    let (mut subscriber, default_rx, _h1) =
+        MQTTClient::mqtt_connect_to_broker("routing-sub".to_string(), &broker.addr, None, logger.clone_ref()).unwrap();
+    let dron_rx = subscriber.mqtt_subscribe_with_channel("dron", 1).unwrap();
+    let (handled_tx, handled_rx) = mpsc::channel();
+    subscriber
+        .mqtt_subscribe_with_handler("desc", 1, move |msg| handled_tx.send(msg.get_payload()).unwrap())
+        .unwrap();
+    subscriber.mqtt_subscribe(vec![("cam".to_string(), 1)]).unwrap();
+
+    let (mut publisher, _rx, _h2) =
+        MQTTClient::mqtt_connect_to_broker("routing-pub".to_string(), &broker.addr, None, logger.clone_ref()).unwrap();
+    publisher.mqtt_publish("dron", &[1], 1).unwrap();
+    publisher.mqtt_publish("desc", &[2], 1).unwrap();
+    publisher.mqtt_publish("cam", &[3], 1).unwrap();
+
+    let timeout = Duration::from_secs(10);
+    assert_eq!(dron_rx.recv_timeout(timeout).unwrap().get_payload(), vec![1]);
+    assert_eq!(handled_rx.recv_timeout(timeout).unwrap(), vec![2]);
+    // Sólo el topic sin ruta propia llega por el rx general.
+    assert_eq!(default_rx.recv_timeout(timeout).unwrap().get_payload(), vec![3]);
+    assert!(default_rx.recv_timeout(Duration::from_millis(300)).is_err());
+}
+
+#[test]
+fn test_2_topic_tipado_recibe_valores_decodificados_o_el_error() {
+    let broker = Broker::start("typed_topic");
+    let logger = create_logger();
+
+    let (mut subscriber, _rx, _h1) =
+        MQTTClient::mqtt_connect_to_broker("typed-sub".to_string(), &broker.addr, None, logger.clone_ref()).unwrap();
+    let incidents_rx = subscriber.mqtt_subscribe_typed(&AppsMqttTopics::incidents(), 1).unwrap();
+
+    let (mut publisher, _rx, _h2) =
+        MQTTClient::mqtt_connect_to_broker("typed-pub".to_string(), &broker.addr, None, logger.clone_ref()).unwrap();
+    let incident = Incident::new(Incident::generate_id(), (-34.6, -58.4), IncidentSource::Manual);
+    publisher.mqtt_publish_typed(&AppsMqttTopics::incidents(), &incident, 1).unwrap();
+    let first = incidents_rx.recv_timeout(Duration::from_secs(10)).unwrap().unwrap();
+    assert_eq!(first.get_id(), incident.get_id());
+    assert_eq!(first.get_position(), (-34.6, -58.4));
+
+    // Un payload que no es un incidente llega como error, sin cortar la recepción.
+    publisher.mqtt_publish("inc", &[1, 2, 3], 1).unwrap();
+    assert!(incidents_rx.recv_timeout(Duration::from_secs(10)).unwrap().is_err());
+    publisher.mqtt_publish_typed(&AppsMqttTopics::incidents(), &incident, 1).unwrap();
+    assert!(incidents_rx.recv_timeout(Duration::from_secs(10)).unwrap().is_ok());
+
+    // Al desconectarse, termina el rx.
+    subscriber.mqtt_disconnect().unwrap();
+    assert!(incidents_rx.recv_timeout(Duration::from_secs(10)).is_err());
+}